@@ -24,7 +24,7 @@ fn print_catalog_info<P: AsRef<Path>>(path: P) -> Result<(), Box<dyn std::error:
     println!("Maximum magnitude: {:.2}", catalog.max_magnitude());
 
     // Calculate magnitude statistics
-    let mut magnitude_counts = vec![0; 10];
+    let mut magnitude_counts = [0; 10];
     let magnitude_ranges = [
         (-2.0, 0.0, "Very bright (-2 to 0)"),
         (0.0, 2.0, "Bright (0 to 2)"),
@@ -51,7 +51,7 @@ fn print_catalog_info<P: AsRef<Path>>(path: P) -> Result<(), Box<dyn std::error:
     println!("\nMagnitude Distribution:");
     for (i, (_, _, desc)) in magnitude_ranges.iter().enumerate() {
         let count = magnitude_counts[i];
-        let percentage = if catalog.len() > 0 {
+        let percentage = if !catalog.is_empty() {
             (count as f64 / catalog.len() as f64) * 100.0
         } else {
             0.0
@@ -63,7 +63,7 @@ fn print_catalog_info<P: AsRef<Path>>(path: P) -> Result<(), Box<dyn std::error:
     }
 
     // Calculate declination statistics
-    let mut declination_counts = vec![0; 6];
+    let mut declination_counts = [0; 6];
     let declination_bands = [
         (-90.0, -60.0, "South polar region (-90° to -60°)"),
         (-60.0, -30.0, "South temperate (-60° to -30°)"),
@@ -86,7 +86,7 @@ fn print_catalog_info<P: AsRef<Path>>(path: P) -> Result<(), Box<dyn std::error:
     println!("\nSpatial Distribution (by declination):");
     for (i, (_, _, desc)) in declination_bands.iter().enumerate() {
         let count = declination_counts[i];
-        let percentage = if catalog.len() > 0 {
+        let percentage = if !catalog.is_empty() {
             (count as f64 / catalog.len() as f64) * 100.0
         } else {
             0.0
@@ -96,7 +96,7 @@ fn print_catalog_info<P: AsRef<Path>>(path: P) -> Result<(), Box<dyn std::error:
     }
 
     // Calculate RA statistics
-    let mut ra_counts = vec![0; 6];
+    let mut ra_counts = [0; 6];
     let ra_bands = [
         (0.0, 60.0, "RA 0° to 60°"),
         (60.0, 120.0, "RA 60° to 120°"),
@@ -119,7 +119,7 @@ fn print_catalog_info<P: AsRef<Path>>(path: P) -> Result<(), Box<dyn std::error:
     println!("\nSpatial Distribution (by right ascension):");
     for (i, (_, _, desc)) in ra_bands.iter().enumerate() {
         let count = ra_counts[i];
-        let percentage = if catalog.len() > 0 {
+        let percentage = if !catalog.is_empty() {
             (count as f64 / catalog.len() as f64) * 100.0
         } else {
             0.0
@@ -133,7 +133,7 @@ fn print_catalog_info<P: AsRef<Path>>(path: P) -> Result<(), Box<dyn std::error:
     println!("\nFile size: {:.2} KB", file_size as f64 / 1024.0);
     println!(
         "Bytes per star: {:.2}",
-        if catalog.len() > 0 {
+        if !catalog.is_empty() {
             file_size as f64 / catalog.len() as f64
         } else {
             0.0
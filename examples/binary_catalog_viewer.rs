@@ -10,6 +10,7 @@
 //!   --input PATH       Binary catalog file to view
 //!   --convert PATH     Convert binary catalog to CSV at specified path
 //!   --magnitude FLOAT  Only include stars brighter than this magnitude when converting
+//!   --cone RA,DEC,R    Print the stars within R degrees of RA, DEC as JSON
 
 use std::env;
 use std::fs::File;
@@ -17,6 +18,8 @@ use std::io::{BufWriter, Write};
 use std::path::Path;
 
 use starfield::catalogs::{BinaryCatalog, StarPosition};
+use starfield::output::{ConeSearchResult, JsonOutput};
+use starfield::Equatorial;
 
 /// Print information about a binary catalog file
 fn view_catalog<P: AsRef<Path>>(catalog_path: P) -> Result<(), Box<dyn std::error::Error>> {
@@ -49,7 +52,7 @@ fn view_catalog<P: AsRef<Path>>(catalog_path: P) -> Result<(), Box<dyn std::erro
             .filter(|star| star.magnitude >= *min && star.magnitude < *max)
             .len();
 
-        let percentage = if catalog.len() > 0 {
+        let percentage = if !catalog.is_empty() {
             (count as f64 / catalog.len() as f64) * 100.0
         } else {
             0.0
@@ -163,6 +166,35 @@ fn convert_to_csv<P: AsRef<Path>, Q: AsRef<Path>>(
     Ok(())
 }
 
+/// Print a cone search of a binary catalog as a JSON document
+fn cone_search_json<P: AsRef<Path>>(
+    catalog_path: P,
+    cone: &str,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let values = cone
+        .split(',')
+        .map(|value| value.trim().parse::<f64>())
+        .collect::<Result<Vec<_>, _>>()?;
+    let [ra, dec, radius] = values[..] else {
+        return Err("--cone expects RA,DEC,RADIUS in degrees".into());
+    };
+
+    let catalog = BinaryCatalog::load(&catalog_path)?;
+    let stars = catalog.cone_search(ra, dec, radius);
+    let mut result = ConeSearchResult::new(
+        &catalog.metadata().source_label(),
+        &Equatorial::from_degrees(ra, dec),
+        radius,
+        &stars,
+    );
+    if let Some(epoch) = catalog.metadata().epoch {
+        result = result.with_epoch(epoch);
+    }
+    println!("{}", result.to_json()?);
+
+    Ok(())
+}
+
 fn main() -> Result<(), Box<dyn std::error::Error>> {
     let args: Vec<String> = env::args().collect();
 
@@ -170,6 +202,7 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
     let mut input_path = None;
     let mut convert_path = None;
     let mut magnitude_limit = None;
+    let mut cone = None;
 
     // Parse command-line arguments
     let mut i = 1;
@@ -199,6 +232,14 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
                     return Err("Missing value for --magnitude".into());
                 }
             }
+            "--cone" => {
+                if i + 1 < args.len() {
+                    cone = Some(args[i + 1].clone());
+                    i += 2;
+                } else {
+                    return Err("Missing value for --cone".into());
+                }
+            }
             _ => {
                 println!("Unknown argument: {}", args[i]);
                 i += 1;
@@ -206,6 +247,11 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
         }
     }
 
+    // JSON output stands alone so that other tools can read it
+    if let (Some(path), Some(cone)) = (&input_path, &cone) {
+        return cone_search_json(path, cone);
+    }
+
     println!("Binary Star Catalog Viewer");
     println!("==========================");
 
@@ -213,13 +259,14 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
     if input_path.is_none() {
         println!("Usage:");
         println!("  cargo run --example binary_catalog_viewer -- --input <binary_file> [options]");
-        println!("");
+        println!();
         println!("Options:");
         println!("  --input PATH       Binary catalog file to view");
         println!("  --convert PATH     Convert binary catalog to CSV at specified path");
         println!(
             "  --magnitude FLOAT  Only include stars brighter than this magnitude when converting"
         );
+        println!("  --cone RA,DEC,R    Print the stars within R degrees of RA, DEC as JSON");
 
         return Err("Missing required input path".into());
     }
@@ -85,8 +85,8 @@ fn display_features_by_type(catalog: &FeatureCatalog, feature_type: &FeatureType
     println!("{}", "=".repeat(type_name.len() + 4));
 
     println!(
-        "{:<20} {:<10} {:<10} {:<8} {}",
-        "Name", "RA (°)", "Dec (°)", "Size (°)", "Description"
+        "{:<20} {:<10} {:<10} {:<8} Description",
+        "Name", "RA (°)", "Dec (°)", "Size (°)"
     );
     println!("{:-<80}", "");
 
@@ -12,9 +12,41 @@
 //!   --file FILENAME  Download a specific file
 
 use std::env;
-// No need for PathBuf here
+use std::io::Write;
 
-use starfield::data::{download_gaia_catalog, download_gaia_file, list_cached_gaia_files};
+use starfield::data::{
+    download_gaia_catalog_with_progress, download_gaia_file_with_progress, list_cached_gaia_files,
+    ProgressEvent, ProgressReporter,
+};
+
+/// Print a one-line download meter that updates in place
+fn print_progress(event: ProgressEvent) {
+    const MB: f64 = 1024.0 * 1024.0;
+    match event {
+        ProgressEvent::DownloadStarted { url, .. } => println!("Downloading: {}", url),
+        ProgressEvent::Downloaded {
+            bytes,
+            total_bytes: Some(total),
+            ..
+        } => {
+            print!(
+                "\rDownloaded: {:.1}% ({:.1}MB/{:.1}MB)",
+                bytes as f64 / total as f64 * 100.0,
+                bytes as f64 / MB,
+                total as f64 / MB
+            );
+            let _ = std::io::stdout().flush();
+        }
+        ProgressEvent::Downloaded { bytes, .. } => {
+            print!("\rDownloaded: {:.1}MB", bytes as f64 / MB);
+            let _ = std::io::stdout().flush();
+        }
+        ProgressEvent::DownloadFinished { bytes, .. } => {
+            println!("\rDownload complete: {:.1}MB", bytes as f64 / MB)
+        }
+        _ => {}
+    }
+}
 
 fn main() -> Result<(), Box<dyn std::error::Error>> {
     let args: Vec<String> = env::args().collect();
@@ -54,6 +86,8 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
         }
     }
 
+    let progress = ProgressReporter::new(print_progress);
+
     // Handle command options
     if list_files {
         println!("Listing cached Gaia catalog files:");
@@ -69,11 +103,11 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
         }
     } else if let Some(filename) = specific_file {
         println!("Downloading specific file: {}", filename);
-        let path = download_gaia_file(&filename)?;
+        let path = download_gaia_file_with_progress(&filename, &progress)?;
         println!("File downloaded and verified: {}", path.display());
     } else {
         println!("Downloading Gaia catalog files");
-        let downloaded = download_gaia_catalog(download_count)?;
+        let downloaded = download_gaia_catalog_with_progress(download_count, &progress)?;
         println!("Successfully downloaded {} files", downloaded.len());
 
         // Print the list of downloaded files
@@ -0,0 +1,23 @@
+use starfield::planetlib::GreatRedSpot;
+use starfield::Timescale;
+
+fn main() {
+    println!("Jupiter Great Red Spot Transits");
+    println!("===============================\n");
+
+    let ts = Timescale::default();
+    let start = ts.utc((2024, 1, 1, 0, 0, 0.0));
+    let end = ts.utc((2024, 1, 3, 0, 0, 0.0));
+
+    // System II longitude of the spot as measured on the start date;
+    // replace with a current value from JUPOS or ALPO before relying on it
+    let grs = GreatRedSpot::new(55.0, &start).with_drift(0.05);
+    println!(
+        "GRS longitude: {:.1}° (System II), drift {:+.2}°/day\n",
+        grs.longitude, grs.drift_per_day
+    );
+
+    for transit in grs.transits(&start, &end) {
+        println!("  {}", transit.utc_iso(' ', 0).unwrap());
+    }
+}
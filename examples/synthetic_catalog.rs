@@ -67,7 +67,7 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
 
     // Save the cluster catalog to a file
     let output_path = Path::new("test_output/synthetic_cluster.bin");
-    cluster_catalog.save(&output_path)?;
+    cluster_catalog.save(output_path)?;
     println!("\nSaved cluster catalog to: {}", output_path.display());
 
     Ok(())
@@ -95,21 +95,21 @@ fn print_catalog_info(catalog: &BinaryCatalog, title: &str) {
         println!("Magnitude range: {:.2} to {:.2}", min_mag, max_mag);
 
         // Count by magnitude bins
-        let mut mag_bins = vec![0; 10];
+        let mut mag_bins = [0; 10];
         for star in stars {
             let bin = (star.magnitude.floor() as usize).min(9);
             mag_bins[bin] += 1;
         }
 
         println!("\nMagnitude distribution:");
-        for i in 0..10 {
-            if mag_bins[i] > 0 {
-                let percentage = (mag_bins[i] as f64 / catalog.len() as f64) * 100.0;
+        for (i, &count) in mag_bins.iter().enumerate() {
+            if count > 0 {
+                let percentage = (count as f64 / catalog.len() as f64) * 100.0;
                 println!(
                     "  Magnitude {}-{}: {} stars ({:.1}%)",
                     i,
                     i + 1,
-                    mag_bins[i],
+                    count,
                     percentage
                 );
             }
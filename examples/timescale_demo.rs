@@ -1,5 +1,5 @@
 use chrono::{DateTime, TimeZone, Utc};
-use starfield::Timescale;
+use starfield::{TimeDelta, Timescale};
 
 fn main() {
     println!("Starfield Timescale Demonstration");
@@ -49,9 +49,9 @@ fn main() {
     );
 
     // Demonstrate time math
-    let later = millennium.clone() + 1.0; // Add 1 day
+    let later = &millennium + TimeDelta::days(1.0); // Add 1 day
     println!("\nOne day after millennium: {}", later);
-    println!("Difference: {:.6} days", later - millennium);
+    println!("Difference: {:.6} days", (&later - &millennium).as_days());
 
     // Future time
     let future = ts.utc((2050, 1, 1, 0, 0, 0.0));
@@ -65,7 +65,7 @@ fn main() {
 
     // Time span with linspace
     println!("\nCreating a time span with 5 points from J2000 to J2000+10 days:");
-    let span = ts.linspace(&j2000, &(j2000.clone() + 10.0), 5);
+    let span = ts.linspace(&j2000, &(&j2000 + TimeDelta::days(10.0)), 5);
     for (i, t) in span.iter().enumerate() {
         println!("  Point {}: {}", i, t);
     }
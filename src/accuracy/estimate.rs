@@ -0,0 +1,270 @@
+//! Error budgets attached to computed results
+//!
+//! An [`AccuracyEstimate`] lists the expected error contributed by each
+//! model behind a result, so that downstream code can check a position or
+//! an event time against its own tolerance without knowing which ephemeris
+//! or Delta T source produced it. The figures are typical 1-sigma values
+//! from the models' published accuracies, not rigorous bounds.
+
+use crate::framelib::{Atmosphere, Refraction};
+use crate::planetlib::{Body, Ephemeris};
+use crate::time::Time;
+
+/// Typical error of a JPL DE kernel position as seen from the Earth
+const KERNEL_ERROR_ARCSEC: f64 = 0.001;
+
+/// Error of the truncated ELP-2000/82 lunar series (Meeus ch. 47)
+const ELP_TRUNCATED_ERROR_ARCSEC: f64 = 10.0;
+
+/// Error of Delta T interpolated from observed values
+const DELTA_T_TABLE_ERROR_S: f64 = 0.1;
+
+/// Sidereal rotation of the Earth in arcseconds per second of time
+const EARTH_ROTATION_ARCSEC_PER_S: f64 = 15.041;
+
+/// Nutation in longitude reaches 17.2″ and in obliquity 9.2″
+const NUTATION_NEGLECTED_ARCSEC: f64 = 19.5;
+
+/// Deflection by Jupiter at its limb, the largest of the neglected planets
+const PLANETARY_DEFLECTION_ARCSEC: f64 = 0.017;
+
+/// Share of the refraction that departs from the model with the weather
+const REFRACTION_VARIABILITY: f64 = 0.05;
+
+/// A model simplification that contributes to a result's error
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ErrorSource {
+    /// Positions of the Earth and the target body
+    Ephemeris,
+    /// Delta T, which sets the Earth's rotation angle
+    DeltaT,
+    /// Nutation of the Earth's axis
+    Nutation,
+    /// Gravitational light deflection by bodies that are not modelled
+    LightDeflection,
+    /// Aberration that is neglected or approximated
+    Aberration,
+    /// Atmospheric refraction
+    Refraction,
+}
+
+/// One source's contribution to an error budget
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ErrorContribution {
+    /// What the error comes from
+    pub source: ErrorSource,
+    /// Expected angular error in arcseconds
+    pub arcsec: f64,
+}
+
+/// Expected error of a result, broken down by source
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct AccuracyEstimate {
+    /// Contributions, largest first
+    contributions: Vec<ErrorContribution>,
+    /// Rate at which the quantity an event time is found from changes
+    event_rate_arcsec_per_s: Option<f64>,
+}
+
+impl AccuracyEstimate {
+    /// An empty budget
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a contribution, or enlarge an existing one from the same source
+    pub fn with(mut self, source: ErrorSource, arcsec: f64) -> Self {
+        match self.contributions.iter_mut().find(|c| c.source == source) {
+            Some(existing) => existing.arcsec = existing.arcsec.hypot(arcsec),
+            None => self
+                .contributions
+                .push(ErrorContribution { source, arcsec }),
+        }
+        self.contributions
+            .sort_by(|a, b| b.arcsec.total_cmp(&a.arcsec));
+        self
+    }
+
+    /// Mark the result as an event time found where an angle changing at
+    /// `rate_arcsec_per_s` crosses a threshold, so that angular errors
+    /// translate into timing errors
+    pub fn for_event(mut self, rate_arcsec_per_s: f64) -> Self {
+        self.event_rate_arcsec_per_s = Some(rate_arcsec_per_s.abs());
+        self
+    }
+
+    /// The contributions, largest first
+    pub fn contributions(&self) -> &[ErrorContribution] {
+        &self.contributions
+    }
+
+    /// The largest contributor, if any
+    pub fn dominant(&self) -> Option<ErrorSource> {
+        self.contributions.first().map(|c| c.source)
+    }
+
+    /// Total expected angular error in arcseconds, adding the
+    /// contributions in quadrature
+    pub fn total_arcsec(&self) -> f64 {
+        self.contributions
+            .iter()
+            .fold(0.0, |total, c| total.hypot(c.arcsec))
+    }
+
+    /// Expected error of an event time in seconds, for estimates made with
+    /// [`AccuracyEstimate::for_event`]
+    pub fn timing_seconds(&self) -> Option<f64> {
+        self.event_rate_arcsec_per_s
+            .filter(|&rate| rate > 0.0)
+            .map(|rate| self.total_arcsec() / rate)
+    }
+
+    /// Whether the total error is within `tolerance_arcsec`
+    pub fn within_arcsec(&self, tolerance_arcsec: f64) -> bool {
+        self.total_arcsec() <= tolerance_arcsec
+    }
+
+    /// Add the refraction error at apparent altitude `alt_deg`
+    ///
+    /// Without a model the whole refraction is an error, as the result is
+    /// not where the object is seen; with one, the error is the formula's
+    /// stated accuracy plus a few percent of unmodelled atmosphere.
+    pub fn with_refraction(self, model: Option<Refraction>, alt_deg: f64) -> Self {
+        let standard =
+            Refraction::Bennett.from_apparent_altitude(alt_deg, &Atmosphere::default()) * 3600.0;
+        let arcsec = match model {
+            None => standard,
+            Some(model) => {
+                let formula = match model {
+                    Refraction::Bennett => 0.07 * 60.0,
+                    Refraction::Saemundsson => 0.1 * 60.0,
+                };
+                f64::hypot(formula, REFRACTION_VARIABILITY * standard)
+            }
+        };
+        self.with(ErrorSource::Refraction, arcsec)
+    }
+
+    /// Budget for the direction from the Earth to `body`
+    ///
+    /// With the analytic ephemeris this combines the target's error with
+    /// that of the Earth's own orbit, using the 1800-2050 figures of
+    /// Standish's element table; a kernel is good to about a milliarcsecond.
+    pub fn for_position(ephemeris: &Ephemeris, body: Body) -> Self {
+        Self::new().with(
+            ErrorSource::Ephemeris,
+            ephemeris_error_arcsec(ephemeris, body),
+        )
+    }
+
+    /// Budget for [`AccuracyEstimate::for_position`] plus the planetary
+    /// light deflection that apparent places leave out
+    pub fn for_apparent(ephemeris: &Ephemeris, body: Body) -> Self {
+        Self::for_position(ephemeris, body)
+            .with(ErrorSource::LightDeflection, PLANETARY_DEFLECTION_ARCSEC)
+    }
+
+    /// Budget for an airless altitude and azimuth at `t`, adding the Earth
+    /// rotation error from Delta T and the neglected nutation
+    pub fn for_horizontal(ephemeris: &Ephemeris, body: Body, t: &Time) -> Self {
+        Self::for_apparent(ephemeris, body)
+            .with(
+                ErrorSource::DeltaT,
+                delta_t_error_seconds(t) * EARTH_ROTATION_ARCSEC_PER_S,
+            )
+            .with(ErrorSource::Nutation, NUTATION_NEGLECTED_ARCSEC)
+    }
+}
+
+/// Typical error in arcseconds of `body` as seen from the Earth
+pub fn ephemeris_error_arcsec(ephemeris: &Ephemeris, body: Body) -> f64 {
+    if ephemeris.kernel().is_some() {
+        return KERNEL_ERROR_ARCSEC;
+    }
+    // Standish, Table 1, errors in right ascension for 1800-2050
+    let heliocentric = match body {
+        Body::Sun | Body::Earth | Body::EarthMoonBarycenter => 0.0,
+        Body::Moon => return ELP_TRUNCATED_ERROR_ARCSEC,
+        Body::Mercury => 15.0,
+        Body::Venus => 20.0,
+        Body::Mars => 40.0,
+        Body::Jupiter => 400.0,
+        Body::Saturn => 600.0,
+        Body::Uranus => 50.0,
+        Body::Neptune => 10.0,
+        Body::Pluto => 5.0,
+        // Osculating elements drift by arcminutes within a year of epoch
+        Body::Custom(_) => 60.0,
+    };
+    let earth: f64 = 20.0;
+    earth.hypot(heliocentric)
+}
+
+/// Typical error in seconds of the Delta T used at `t`
+///
+/// Interpolated observations are good to a tenth of a second. Outside a
+/// table the error grows quadratically: in the past after Morrison &
+/// Stephenson's uncertainty of historical eclipse timings, and in the future
+/// reaching a couple of minutes a century ahead, as the Earth's rotation is
+/// unpredictable.
+pub fn delta_t_error_seconds(t: &Time) -> f64 {
+    if t.timescale().delta_t_table_covers(t.tt()) {
+        return DELTA_T_TABLE_ERROR_S;
+    }
+    let year = t.j();
+    if year > 2025.0 {
+        1.0 + ((year - 2025.0) / 10.0).powi(2)
+    } else {
+        1.0 + 0.8 * ((year - 1820.0) / 100.0).powi(2)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::time::Timescale;
+    use approx::assert_relative_eq;
+
+    #[test]
+    fn test_budget_combines_in_quadrature() {
+        let estimate = AccuracyEstimate::new()
+            .with(ErrorSource::Nutation, 3.0)
+            .with(ErrorSource::Ephemeris, 4.0);
+        assert_relative_eq!(estimate.total_arcsec(), 5.0);
+        assert_eq!(estimate.dominant(), Some(ErrorSource::Ephemeris));
+        assert!(estimate.within_arcsec(5.0) && !estimate.within_arcsec(4.9));
+        assert_eq!(estimate.timing_seconds(), None);
+        assert_relative_eq!(estimate.for_event(0.5).timing_seconds().unwrap(), 10.0);
+    }
+
+    #[test]
+    fn test_models_set_the_budget() {
+        let ts = Timescale::default().with_polynomial_delta_t();
+        let eph = Ephemeris::new();
+        let t = ts.utc((2024, 6, 1));
+
+        let saturn = AccuracyEstimate::for_position(&eph, Body::Saturn);
+        assert_eq!(saturn.dominant(), Some(ErrorSource::Ephemeris));
+        assert!(saturn.total_arcsec() > 500.0);
+
+        // The Moon's error is dwarfed by polynomial Delta T on the horizon
+        let moon = AccuracyEstimate::for_horizontal(&eph, Body::Moon, &t);
+        assert_eq!(moon.dominant(), Some(ErrorSource::DeltaT));
+        assert!(
+            moon.total_arcsec() > AccuracyEstimate::for_apparent(&eph, Body::Moon).total_arcsec()
+        );
+
+        // Delta T grows away from the present
+        let ancient = ts.utc((-500, 1, 1));
+        assert!(delta_t_error_seconds(&ancient) > 50.0 * delta_t_error_seconds(&t));
+
+        // Refraction at the horizon is half a degree unless modelled
+        let airless = AccuracyEstimate::new().with_refraction(None, 0.0);
+        let modelled = AccuracyEstimate::new().with_refraction(Some(Refraction::Bennett), 0.0);
+        assert!(airless.total_arcsec() > 1_800.0);
+        assert!(modelled.total_arcsec() < 0.1 * airless.total_arcsec());
+
+        let table = ts.with_delta_t_table(vec![t.tt() - 100.0, t.tt() + 100.0], vec![69.0, 69.2]);
+        assert_relative_eq!(delta_t_error_seconds(&table.tt_jd(t.tt(), None)), 0.1);
+    }
+}
@@ -0,0 +1,134 @@
+//! Accuracy profiles: trading precision against runtime with one switch
+//!
+//! An [`AccuracyProfile`] bundles the model choices that dominate the error
+//! budget of a reduction - how Delta T is obtained, how much of the nutation
+//! series is summed and how refraction is modelled. Hand one to
+//! [`crate::Loader::with_accuracy`] and the timescales it builds follow its
+//! Delta T source. [`crate::tracking::Tracker::with_accuracy`] applies its
+//! nutation and refraction choices and
+//! [`crate::framelib::HorizontalFrame::with_accuracy`] its refraction.
+//!
+//! Results of the position and almanac pipelines report what those choices
+//! cost through an [`AccuracyEstimate`].
+
+pub mod estimate;
+
+pub use estimate::{AccuracyEstimate, ErrorContribution, ErrorSource};
+
+use crate::framelib::Refraction;
+
+/// Source of Delta T (TT - UT1)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DeltaTModel {
+    /// Espenak & Meeus polynomial approximations; no data needed, errors of
+    /// around a second for recent decades
+    Polynomial,
+    /// Interpolate the timescale's table of observed values when one is
+    /// loaded, otherwise use the polynomials
+    Table,
+}
+
+/// How much of the nutation series to evaluate
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NutationModel {
+    /// Ignore nutation (errors up to ~20 arcseconds)
+    None,
+    /// The handful of dominant terms (~0.5 arcsecond)
+    Low,
+    /// IAU 2000B, 77 luni-solar terms (~1 milliarcsecond)
+    Iau2000B,
+}
+
+/// Model choices governing the accuracy of computed positions
+#[derive(Debug, Clone, PartialEq)]
+pub struct AccuracyProfile {
+    /// Source of Delta T
+    pub delta_t: DeltaTModel,
+    /// Nutation truncation
+    pub nutation: NutationModel,
+    /// Refraction model used for horizontal coordinates, `None` for
+    /// airless positions
+    pub refraction: Option<Refraction>,
+}
+
+impl AccuracyProfile {
+    /// Fastest settings, good to arcminutes: no nutation or refraction and
+    /// polynomial Delta T
+    pub fn fast() -> Self {
+        Self {
+            delta_t: DeltaTModel::Polynomial,
+            nutation: NutationModel::None,
+            refraction: None,
+        }
+    }
+
+    /// Balanced settings, good to about an arcsecond
+    pub fn standard() -> Self {
+        Self {
+            delta_t: DeltaTModel::Table,
+            nutation: NutationModel::Low,
+            refraction: Some(Refraction::Bennett),
+        }
+    }
+
+    /// Milliarcsecond-level settings matching skyfield's defaults
+    pub fn precise() -> Self {
+        Self {
+            delta_t: DeltaTModel::Table,
+            nutation: NutationModel::Iau2000B,
+            refraction: Some(Refraction::Saemundsson),
+        }
+    }
+
+    /// Set the Delta T source
+    pub fn with_delta_t(mut self, model: DeltaTModel) -> Self {
+        self.delta_t = model;
+        self
+    }
+
+    /// Set the nutation truncation
+    pub fn with_nutation(mut self, model: NutationModel) -> Self {
+        self.nutation = model;
+        self
+    }
+
+    /// Set the refraction model, `None` for airless positions
+    pub fn with_refraction(mut self, model: Option<Refraction>) -> Self {
+        self.refraction = model;
+        self
+    }
+}
+
+impl Default for AccuracyProfile {
+    fn default() -> Self {
+        Self::standard()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_presets_are_ordered() {
+        let fast = AccuracyProfile::fast();
+        let standard = AccuracyProfile::default();
+        let precise = AccuracyProfile::precise();
+
+        assert_eq!(standard, AccuracyProfile::standard());
+        assert_eq!(fast.refraction, None);
+        assert_eq!(standard.refraction, Some(Refraction::Bennett));
+        assert_eq!(fast.delta_t, DeltaTModel::Polynomial);
+        assert_eq!(precise.nutation, NutationModel::Iau2000B);
+    }
+
+    #[test]
+    fn test_builders_override_presets() {
+        let profile = AccuracyProfile::precise()
+            .with_refraction(None)
+            .with_delta_t(DeltaTModel::Polynomial);
+        assert_eq!(profile.refraction, None);
+        assert_eq!(profile.delta_t, DeltaTModel::Polynomial);
+        assert_eq!(profile.nutation, NutationModel::Iau2000B);
+    }
+}
@@ -0,0 +1,174 @@
+//! Dark-sky windows for deep-sky imaging
+//!
+//! A moment is dark when the Sun is below the twilight limit and the Moon is
+//! either below its altitude limit or too thin to matter.
+
+use super::{altitude, moon_phase, windows_where, TimeWindow};
+use crate::observers::GeographicLocation;
+use crate::planetlib::{Body, Ephemeris};
+use crate::time::Time;
+
+/// Sampling interval for the search in days (10 minutes)
+const SEARCH_STEP_DAYS: f64 = 10.0 / 1440.0;
+
+/// Conditions a moment must meet to count as dark
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DarkSkyCriteria {
+    /// Sun altitude in degrees below which the sky is dark (default -18,
+    /// astronomical twilight)
+    pub sun_altitude_deg: f64,
+    /// Moon altitude in degrees below which the Moon is ignored (default 0)
+    pub moon_altitude_deg: f64,
+    /// Illuminated fraction at or below which the Moon is ignored wherever it
+    /// is (default 0, so any risen Moon spoils the sky)
+    pub max_moon_illumination: f64,
+}
+
+impl Default for DarkSkyCriteria {
+    fn default() -> Self {
+        Self {
+            sun_altitude_deg: -18.0,
+            moon_altitude_deg: 0.0,
+            max_moon_illumination: 0.0,
+        }
+    }
+}
+
+impl DarkSkyCriteria {
+    /// Set the Sun altitude limit in degrees
+    pub fn with_sun_altitude(mut self, altitude_deg: f64) -> Self {
+        self.sun_altitude_deg = altitude_deg;
+        self
+    }
+
+    /// Set the Moon altitude limit in degrees
+    pub fn with_moon_altitude(mut self, altitude_deg: f64) -> Self {
+        self.moon_altitude_deg = altitude_deg;
+        self
+    }
+
+    /// Set the Moon illuminated fraction (0 to 1) that is tolerated above the
+    /// altitude limit
+    pub fn with_max_moon_illumination(mut self, fraction: f64) -> Self {
+        self.max_moon_illumination = fraction;
+        self
+    }
+
+    /// Whether the sky is dark at `t`
+    pub fn is_dark(&self, ephemeris: &Ephemeris, location: &GeographicLocation, t: &Time) -> bool {
+        if altitude(ephemeris, location, Body::Sun, t) >= self.sun_altitude_deg {
+            return false;
+        }
+        if altitude(ephemeris, location, Body::Moon, t) < self.moon_altitude_deg {
+            return true;
+        }
+        moon_phase(ephemeris, t).illuminated_fraction <= self.max_moon_illumination
+    }
+}
+
+/// Find the dark, moonless windows between `start` and `end`
+///
+/// Windows open and close at the transitions of
+/// [`DarkSkyCriteria::is_dark`], located to about a millisecond; windows
+/// already open at `start` or still open at `end` are clipped to the range.
+/// The sky is sampled every ten minutes, so briefer dark spells can be
+/// missed.
+///
+/// # Examples
+///
+/// ```
+/// use starfield::almanac::{dark_sky_windows, DarkSkyCriteria};
+/// use starfield::observers::GeographicLocation;
+/// use starfield::planetlib::Ephemeris;
+/// use starfield::time::Timescale;
+///
+/// let ts = Timescale::default();
+/// let kitt_peak = GeographicLocation::new(31.96, -111.60, 2_096.0);
+/// let criteria = DarkSkyCriteria::default().with_max_moon_illumination(0.1);
+///
+/// let windows = dark_sky_windows(
+///     &Ephemeris::new(),
+///     &kitt_peak,
+///     &ts.utc((2024, 1, 10)),
+///     &ts.utc((2024, 1, 12)),
+///     &criteria,
+/// );
+/// for w in &windows {
+///     println!("{:.1} h of dark sky", w.duration_hours());
+/// }
+/// ```
+pub fn dark_sky_windows(
+    ephemeris: &Ephemeris,
+    location: &GeographicLocation,
+    start: &Time,
+    end: &Time,
+    criteria: &DarkSkyCriteria,
+) -> Vec<TimeWindow> {
+    windows_where(start, end, SEARCH_STEP_DAYS, |t| {
+        criteria.is_dark(ephemeris, location, t)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::time::Timescale;
+
+    fn kitt_peak() -> GeographicLocation {
+        GeographicLocation::new(31.96, -111.60, 2_096.0)
+    }
+
+    #[test]
+    fn test_new_moon_nights() {
+        // New Moon on 2024 January 11; each night has ~10.5 h of astronomical darkness
+        let ts = Timescale::default();
+        let eph = Ephemeris::new();
+        let criteria = DarkSkyCriteria::default();
+        // Local noon to local noon (Arizona is UTC-7)
+        let start = ts.utc((2024, 1, 10, 19, 0, 0.0));
+        let end = ts.utc((2024, 1, 13, 19, 0, 0.0));
+
+        let windows = dark_sky_windows(&eph, &kitt_peak(), &start, &end, &criteria);
+        assert_eq!(windows.len(), 3);
+
+        for w in &windows {
+            assert!(
+                (9.5..11.5).contains(&w.duration_hours()),
+                "{}",
+                w.duration_hours()
+            );
+
+            // Dark at the middle of the window, not just outside it
+            let ts = w.start.timescale();
+            let mid = ts.tt_jd(0.5 * (w.start.tt() + w.end.tt()), None);
+            assert!(criteria.is_dark(&eph, &kitt_peak(), &mid));
+            let before = ts.tt_jd(w.start.tt() - 1e-4, None);
+            assert!(!criteria.is_dark(&eph, &kitt_peak(), &before));
+        }
+    }
+
+    #[test]
+    fn test_full_moon_spoils_the_night() {
+        // Full Moon on 2024 January 25 is up all night
+        let ts = Timescale::default();
+        let eph = Ephemeris::new();
+        let start = ts.utc((2024, 1, 25, 19, 0, 0.0));
+        let end = ts.utc((2024, 1, 26, 19, 0, 0.0));
+
+        let strict = dark_sky_windows(
+            &eph,
+            &kitt_peak(),
+            &start,
+            &end,
+            &DarkSkyCriteria::default(),
+        );
+        let total: f64 = strict.iter().map(TimeWindow::duration_hours).sum();
+        assert!(total < 0.5, "{}", total);
+
+        // Ignoring the Moon recovers the full astronomical night
+        let any_moon = DarkSkyCriteria::default().with_max_moon_illumination(1.0);
+        let windows = dark_sky_windows(&eph, &kitt_peak(), &start, &end, &any_moon);
+        assert_eq!(windows.len(), 1);
+        assert!(windows[0].duration_hours() > 9.5);
+    }
+}
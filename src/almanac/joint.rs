@@ -0,0 +1,179 @@
+//! Windows when a target is visible from several sites at once
+//!
+//! Occultation campaigns and very long baseline observations need the
+//! stretches of time in which every station can point at the same target.
+//! Each [`ObservingSite`] sets its own altitude limit and, optionally, its
+//! own darkness requirement; the joint windows are the times at which all of
+//! them are satisfied together.
+
+use super::{target_altitude, windows_where, DarkSkyCriteria, TimeWindow};
+use crate::observers::GeographicLocation;
+use crate::planetlib::Ephemeris;
+use crate::time::Time;
+use crate::tracking::TrackingTarget;
+
+/// Sampling interval for the search in days (5 minutes)
+const SEARCH_STEP_DAYS: f64 = 5.0 / 1440.0;
+
+/// A station taking part in a joint observation
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ObservingSite {
+    /// Where the station is
+    pub location: GeographicLocation,
+    /// Lowest usable altitude of the target in degrees (default 0)
+    pub min_altitude_deg: f64,
+    /// Darkness the station needs, or `None` if it can observe by day, as
+    /// radio telescopes can
+    pub darkness: Option<DarkSkyCriteria>,
+}
+
+impl ObservingSite {
+    /// A station that can observe whenever the target is above the horizon
+    pub fn new(location: GeographicLocation) -> Self {
+        Self {
+            location,
+            min_altitude_deg: 0.0,
+            darkness: None,
+        }
+    }
+
+    /// Set the lowest usable altitude in degrees
+    pub fn with_min_altitude(mut self, altitude_deg: f64) -> Self {
+        self.min_altitude_deg = altitude_deg;
+        self
+    }
+
+    /// Require the sky to be dark by `criteria`
+    pub fn with_darkness(mut self, criteria: DarkSkyCriteria) -> Self {
+        self.darkness = Some(criteria);
+        self
+    }
+
+    /// Whether the station can observe `target` at `t`
+    pub fn can_observe(&self, ephemeris: &Ephemeris, target: &TrackingTarget, t: &Time) -> bool {
+        if target_altitude(ephemeris, &self.location, target, t) < self.min_altitude_deg {
+            return false;
+        }
+        self.darkness
+            .is_none_or(|criteria| criteria.is_dark(ephemeris, &self.location, t))
+    }
+}
+
+/// Find the windows between `start` and `end` in which every site can
+/// observe `target`
+///
+/// Windows open and close at the first site to gain or lose the target,
+/// located to about a millisecond, and are clipped to the range. The sites
+/// are sampled every five minutes, so briefer overlaps can be missed. With
+/// no sites the whole range is one window.
+///
+/// # Examples
+///
+/// ```
+/// use starfield::almanac::{joint_visibility_windows, DarkSkyCriteria, ObservingSite};
+/// use starfield::coordinates::Equatorial;
+/// use starfield::observers::GeographicLocation;
+/// use starfield::planetlib::Ephemeris;
+/// use starfield::time::Timescale;
+/// use starfield::tracking::TrackingTarget;
+///
+/// let ts = Timescale::default();
+/// let sites = [
+///     ObservingSite::new(GeographicLocation::new(31.96, -111.60, 2_096.0))
+///         .with_min_altitude(20.0)
+///         .with_darkness(DarkSkyCriteria::default().with_max_moon_illumination(1.0)),
+///     ObservingSite::new(GeographicLocation::new(19.82, -155.47, 4_205.0)).with_min_altitude(20.0),
+/// ];
+/// let vega = TrackingTarget::Star(Equatorial::from_degrees(279.23, 38.78));
+///
+/// let windows = joint_visibility_windows(
+///     &Ephemeris::new(),
+///     &sites,
+///     &vega,
+///     &ts.utc((2024, 8, 1)),
+///     &ts.utc((2024, 8, 2)),
+/// );
+/// for w in &windows {
+///     println!("{:.1} h of joint coverage", w.duration_hours());
+/// }
+/// ```
+pub fn joint_visibility_windows(
+    ephemeris: &Ephemeris,
+    sites: &[ObservingSite],
+    target: &TrackingTarget,
+    start: &Time,
+    end: &Time,
+) -> Vec<TimeWindow> {
+    windows_where(start, end, SEARCH_STEP_DAYS, |t| {
+        sites
+            .iter()
+            .all(|site| site.can_observe(ephemeris, target, t))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::coordinates::Equatorial;
+    use crate::planetlib::Body;
+    use crate::time::Timescale;
+
+    fn kitt_peak() -> GeographicLocation {
+        GeographicLocation::new(31.96, -111.60, 2_096.0)
+    }
+
+    fn la_palma() -> GeographicLocation {
+        GeographicLocation::new(28.76, -17.89, 2_396.0)
+    }
+
+    #[test]
+    fn test_joint_windows_need_every_site() {
+        let ts = Timescale::default();
+        let eph = Ephemeris::new();
+        let vega = TrackingTarget::Star(Equatorial::from_degrees(279.23, 38.78));
+        let start = ts.utc((2024, 8, 1));
+        let end = ts.utc((2024, 8, 3));
+        let sites = [
+            ObservingSite::new(kitt_peak()).with_min_altitude(30.0),
+            ObservingSite::new(la_palma()).with_min_altitude(30.0),
+        ];
+
+        let joint = joint_visibility_windows(&eph, &sites, &vega, &start, &end);
+        let alone = joint_visibility_windows(&eph, &sites[..1], &vega, &start, &end);
+        let joint_hours: f64 = joint.iter().map(TimeWindow::duration_hours).sum();
+        let alone_hours: f64 = alone.iter().map(TimeWindow::duration_hours).sum();
+        // Sites 94° apart in longitude share only part of Vega's day
+        assert_eq!(joint.len(), 2);
+        assert!(joint_hours > 1.0 && joint_hours < alone_hours - 4.0);
+
+        for w in &joint {
+            let mid = ts.tt_jd(0.5 * (w.start.tt() + w.end.tt()), None);
+            assert!(sites.iter().all(|s| s.can_observe(&eph, &vega, &mid)));
+            let before = ts.tt_jd(w.start.tt() - 1e-4, None);
+            assert!(sites.iter().any(|s| !s.can_observe(&eph, &vega, &before)));
+        }
+
+        assert_eq!(
+            joint_visibility_windows(&eph, &[], &vega, &start, &end).len(),
+            1
+        );
+    }
+
+    #[test]
+    fn test_darkness_requirement() {
+        let ts = Timescale::default();
+        let eph = Ephemeris::new();
+        let start = ts.utc((2024, 6, 21));
+        let end = ts.utc((2024, 6, 22));
+        let sun = TrackingTarget::Body(Body::Sun);
+
+        // The Sun is never up in a dark sky
+        let optical = [ObservingSite::new(kitt_peak()).with_darkness(DarkSkyCriteria::default())];
+        assert!(joint_visibility_windows(&eph, &optical, &sun, &start, &end).is_empty());
+
+        let radio = [ObservingSite::new(kitt_peak())];
+        let day = joint_visibility_windows(&eph, &radio, &sun, &start, &end);
+        let hours: f64 = day.iter().map(TimeWindow::duration_hours).sum();
+        assert!((13.5..15.0).contains(&hours), "{}", hours);
+    }
+}
@@ -0,0 +1,281 @@
+//! Moon phases and lunar eclipses
+//!
+//! Phases follow skyfield's convention: the phase is the Moon's geocentric
+//! ecliptic longitude minus the Sun's, 0° at new moon and 180° at full
+//! moon. Eclipses are found by checking each full moon for the Moon's
+//! passage through the Earth's shadow, whose radii are enlarged by 2% for
+//! the atmosphere as in the Astronomical Almanac.
+
+use super::{geocentric_position, minimize, sun_moon_event_accuracy};
+use crate::accuracy::{AccuracyEstimate, ErrorSource};
+use crate::constants::{AU_KM, RAD2DEG};
+use crate::framelib::INERTIAL_FRAMES;
+use crate::planetlib::{Body, Ephemeris};
+use crate::searchlib::find_discrete;
+use crate::time::Time;
+use nalgebra::Vector3;
+
+/// Sampling step for phase searches; a quarter lasts at least 6.5 days
+const PHASE_STEP_DAYS: f64 = 5.0;
+
+/// Half-width of the window around full moon searched for greatest eclipse
+const ECLIPSE_WINDOW_DAYS: f64 = 0.25;
+
+/// Enlargement of the Earth's shadow by its atmosphere
+const SHADOW_ENLARGEMENT: f64 = 1.02;
+
+/// The Sun's annual aberration, by which geometric positions misplace the shadow
+const SOLAR_ABERRATION_ARCSEC: f64 = 20.5;
+
+/// Principal phases of the Moon
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MoonPhaseKind {
+    /// Phase 0°
+    New,
+    /// Phase 90°
+    FirstQuarter,
+    /// Phase 180°
+    Full,
+    /// Phase 270°
+    LastQuarter,
+}
+
+impl MoonPhaseKind {
+    /// The phase that begins in a quarter of the lunation, 0 to 3
+    fn from_quarter(quarter: u8) -> Self {
+        match quarter % 4 {
+            0 => MoonPhaseKind::New,
+            1 => MoonPhaseKind::FirstQuarter,
+            2 => MoonPhaseKind::Full,
+            _ => MoonPhaseKind::LastQuarter,
+        }
+    }
+}
+
+/// Appearance of the Moon at a moment
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MoonPhase {
+    /// Ecliptic longitude of the Moon minus the Sun's, in [0, 360) degrees
+    pub phase_deg: f64,
+    /// Sun-Moon-Earth angle in degrees: 180 at new moon, 0 at full
+    pub phase_angle_deg: f64,
+    /// Fraction of the disk that is lit, 0 to 1
+    pub illuminated_fraction: f64,
+}
+
+impl MoonPhase {
+    /// Whether the lit part is growing
+    pub fn is_waxing(&self) -> bool {
+        self.phase_deg < 180.0
+    }
+}
+
+/// Phase of the Moon at `t`
+pub fn moon_phase(ephemeris: &Ephemeris, t: &Time) -> MoonPhase {
+    let sun = geocentric_position(ephemeris, Body::Sun, t);
+    let moon = geocentric_position(ephemeris, Body::Moon, t);
+
+    let ecliptic = &INERTIAL_FRAMES["ECLIPJ2000"];
+    let (sun_ecl, moon_ecl) = (ecliptic * sun, ecliptic * moon);
+    let longitude = |v: &Vector3<f64>| v.y.atan2(v.x) * RAD2DEG;
+    let phase_deg = (longitude(&moon_ecl) - longitude(&sun_ecl)).rem_euclid(360.0);
+
+    let phase_angle = (sun - moon).angle(&-moon);
+    MoonPhase {
+        phase_deg,
+        phase_angle_deg: phase_angle * RAD2DEG,
+        illuminated_fraction: (1.0 + phase_angle.cos()) / 2.0,
+    }
+}
+
+/// Times of the principal phases between `start` and `end`
+pub fn find_moon_phases(
+    ephemeris: &Ephemeris,
+    start: &Time,
+    end: &Time,
+) -> Vec<(Time, MoonPhaseKind)> {
+    let quarter = |t: &Time| (moon_phase(ephemeris, t).phase_deg / 90.0) as u8;
+    find_discrete(start, end, PHASE_STEP_DAYS, quarter)
+        .into_iter()
+        .map(|(t, q)| (t, MoonPhaseKind::from_quarter(q)))
+        .collect()
+}
+
+/// Times of new and full moon between `start` and `end`
+pub fn find_new_and_full_moons(
+    ephemeris: &Ephemeris,
+    start: &Time,
+    end: &Time,
+) -> Vec<(Time, MoonPhaseKind)> {
+    find_moon_phases(ephemeris, start, end)
+        .into_iter()
+        .filter(|(_, kind)| matches!(kind, MoonPhaseKind::New | MoonPhaseKind::Full))
+        .collect()
+}
+
+/// How deeply the Moon enters the Earth's shadow
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LunarEclipseKind {
+    /// Only the penumbra touches the Moon
+    Penumbral,
+    /// Part of the Moon enters the umbra
+    Partial,
+    /// The whole Moon is inside the umbra
+    Total,
+}
+
+/// A lunar eclipse at its greatest
+#[derive(Debug, Clone)]
+pub struct LunarEclipse {
+    /// Time of greatest eclipse
+    pub time: Time,
+    /// Classification at greatest eclipse
+    pub kind: LunarEclipseKind,
+    /// Fraction of the Moon's diameter inside the umbra (negative if none)
+    pub umbral_magnitude: f64,
+    /// Fraction of the Moon's diameter inside the penumbra
+    pub penumbral_magnitude: f64,
+    /// Expected error of the position of the Moon against the shadow, and
+    /// of the time of greatest eclipse
+    pub accuracy: AccuracyEstimate,
+}
+
+/// Umbral and penumbral magnitudes of the Moon at `t`
+fn shadow_magnitudes(ephemeris: &Ephemeris, t: &Time) -> (f64, f64) {
+    let sun = geocentric_position(ephemeris, Body::Sun, t);
+    let moon = geocentric_position(ephemeris, Body::Moon, t);
+    let (sun_km, moon_km) = (sun.norm() * AU_KM, moon.norm() * AU_KM);
+
+    let earth_radius = Body::Earth.radii_km().0;
+    let moon_parallax = (earth_radius / moon_km).asin();
+    let sun_parallax = (earth_radius / sun_km).asin();
+    let sun_radius = (Body::Sun.radii_km().0 / sun_km).asin();
+    let moon_radius = (Body::Moon.radii_km().0 / moon_km).asin();
+
+    let umbra = SHADOW_ENLARGEMENT * (moon_parallax + sun_parallax - sun_radius);
+    let penumbra = SHADOW_ENLARGEMENT * (moon_parallax + sun_parallax + sun_radius);
+    // Angle between the Moon and the shadow axis, which points away from the Sun
+    let offset = moon.angle(&-sun);
+
+    let magnitude = |shadow: f64| (shadow + moon_radius - offset) / (2.0 * moon_radius);
+    (magnitude(umbra), magnitude(penumbra))
+}
+
+/// Time in a window at which the Moon is closest to the shadow axis
+fn greatest_eclipse(ephemeris: &Ephemeris, full_moon: &Time) -> Time {
+    let ts = full_moon.timescale();
+    let jd = minimize(
+        full_moon.tt() - ECLIPSE_WINDOW_DAYS,
+        full_moon.tt() + ECLIPSE_WINDOW_DAYS,
+        |jd| {
+            let t = ts.tt_jd(jd, None);
+            let sun = geocentric_position(ephemeris, Body::Sun, &t);
+            geocentric_position(ephemeris, Body::Moon, &t).angle(&-sun)
+        },
+    );
+    ts.tt_jd(jd, None)
+}
+
+/// Lunar eclipses with greatest eclipse between `start` and `end`
+///
+/// Positions are geometric, which shifts the shadow by up to the Sun's 20"
+/// aberration; magnitudes are good to about 0.02.
+pub fn find_lunar_eclipses(ephemeris: &Ephemeris, start: &Time, end: &Time) -> Vec<LunarEclipse> {
+    let ts = start.timescale();
+    // Widen the phase search so eclipses just inside the range are found
+    let search_start = ts.tt_jd(start.tt() - ECLIPSE_WINDOW_DAYS, None);
+    let search_end = ts.tt_jd(end.tt() + ECLIPSE_WINDOW_DAYS, None);
+
+    find_new_and_full_moons(ephemeris, &search_start, &search_end)
+        .into_iter()
+        .filter(|(_, kind)| *kind == MoonPhaseKind::Full)
+        .filter_map(|(full_moon, _)| {
+            let time = greatest_eclipse(ephemeris, &full_moon);
+            if !(start.tt()..=end.tt()).contains(&time.tt()) {
+                return None;
+            }
+            let (umbral_magnitude, penumbral_magnitude) = shadow_magnitudes(ephemeris, &time);
+            let kind = if umbral_magnitude >= 1.0 {
+                LunarEclipseKind::Total
+            } else if umbral_magnitude > 0.0 {
+                LunarEclipseKind::Partial
+            } else if penumbral_magnitude > 0.0 {
+                LunarEclipseKind::Penumbral
+            } else {
+                return None;
+            };
+            Some(LunarEclipse {
+                time,
+                kind,
+                umbral_magnitude,
+                penumbral_magnitude,
+                accuracy: sun_moon_event_accuracy(ephemeris)
+                    .with(ErrorSource::Aberration, SOLAR_ABERRATION_ARCSEC),
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::time::Timescale;
+    use approx::assert_relative_eq;
+
+    fn minutes_between(a: &Time, b: &Time) -> f64 {
+        (a.tt() - b.tt()).abs() * 1_440.0
+    }
+
+    #[test]
+    fn test_phases_of_january_2024() {
+        let ts = Timescale::default();
+        let eph = Ephemeris::new();
+
+        let phases = find_new_and_full_moons(&eph, &ts.utc((2024, 1, 1)), &ts.utc((2024, 2, 1)));
+        assert_eq!(phases.len(), 2);
+        // New moon 2024-01-11 11:57 UT, full moon 2024-01-25 17:54 UT
+        assert_eq!(phases[0].1, MoonPhaseKind::New);
+        assert!(minutes_between(&phases[0].0, &ts.utc((2024, 1, 11, 11, 57, 0.0))) < 10.0);
+        assert_eq!(phases[1].1, MoonPhaseKind::Full);
+        assert!(minutes_between(&phases[1].0, &ts.utc((2024, 1, 25, 17, 54, 0.0))) < 10.0);
+
+        let all = find_moon_phases(&eph, &ts.utc((2024, 1, 1)), &ts.utc((2024, 2, 1)));
+        assert_eq!(all.len(), 4);
+        assert_eq!(all[0].1, MoonPhaseKind::LastQuarter);
+
+        let full = moon_phase(&eph, &phases[1].0);
+        assert!(full.illuminated_fraction > 0.99);
+        let waxing = moon_phase(&eph, &ts.utc((2024, 1, 18, 4, 0, 0.0)));
+        assert!(waxing.is_waxing());
+        assert_relative_eq!(waxing.phase_deg, 90.0, epsilon = 2.0);
+        assert_relative_eq!(waxing.illuminated_fraction, 0.5, epsilon = 0.03);
+    }
+
+    #[test]
+    fn test_eclipses_of_2022_and_2023() {
+        let ts = Timescale::default();
+        let eph = Ephemeris::new();
+
+        let eclipses = find_lunar_eclipses(&eph, &ts.utc((2022, 9, 1)), &ts.utc((2023, 12, 31)));
+        let kinds: Vec<LunarEclipseKind> = eclipses.iter().map(|e| e.kind).collect();
+        assert_eq!(
+            kinds,
+            [
+                LunarEclipseKind::Total,
+                LunarEclipseKind::Penumbral,
+                LunarEclipseKind::Partial
+            ]
+        );
+
+        // 2022-11-08: greatest at 10:59 UT, umbral magnitude 1.359
+        assert!(minutes_between(&eclipses[0].time, &ts.utc((2022, 11, 8, 10, 59, 0.0))) < 10.0);
+        assert_relative_eq!(eclipses[0].umbral_magnitude, 1.359, epsilon = 0.03);
+        // The analytic Moon and neglected aberration leave about a minute
+        let timing = eclipses[0].accuracy.timing_seconds().unwrap();
+        assert!((10.0..600.0).contains(&timing), "{}", timing);
+        // 2023-05-05: penumbral magnitude 0.964
+        assert_relative_eq!(eclipses[1].penumbral_magnitude, 0.964, epsilon = 0.03);
+        // 2023-10-28: umbral magnitude 0.122
+        assert_relative_eq!(eclipses[2].umbral_magnitude, 0.122, epsilon = 0.03);
+    }
+}
@@ -1 +1,221 @@
-//! almanac module\n\n// Placeholder for almanac implementations
+//! Almanac routines: finding when the sky meets one or more observers' conditions,
+//! Moon phases, eclipses, planetary conjunctions and elongations, satellite
+//! passes and the extremes of variable stars, the Sun's position, solar noon,
+//! twilight and the seasons, and a [`whats_up_tonight`] summary of an
+//! evening's sky
+//!
+//! Searches sample the sky on a regular grid and refine each transition with
+//! [`crate::searchlib::find_discrete`].
+
+pub mod dark_sky;
+pub mod joint;
+pub mod lunar;
+pub mod occultation;
+pub mod phenomena;
+pub mod rise_set;
+pub mod satellite_passes;
+pub mod seasons;
+pub mod solar;
+pub mod solar_eclipse;
+pub mod tonight;
+pub mod twilight;
+pub mod variable;
+pub mod visibility;
+
+pub use dark_sky::{dark_sky_windows, DarkSkyCriteria};
+pub use joint::{joint_visibility_windows, ObservingSite};
+pub use lunar::{
+    find_lunar_eclipses, find_moon_phases, find_new_and_full_moons, moon_phase, LunarEclipse,
+    LunarEclipseKind, MoonPhase, MoonPhaseKind,
+};
+pub use occultation::{find_occultation_path, OccultationPath, ShadowPoint};
+pub use phenomena::{
+    find_closest_approaches, find_conjunctions_and_oppositions, find_max_elongations, Alignment,
+    AlignmentKind, Approach, Elongation, Viewpoint,
+};
+pub use rise_set::{
+    distance_to_horizon_km, find_risings_and_settings, horizon_dip_deg, RiseSetEvent,
+    RiseSetHorizon,
+};
+pub use satellite_passes::{
+    find_satellite_passes, SatelliteEvent, SatelliteEventKind, SatellitePass,
+};
+pub use seasons::{find_seasons, solar_longitude, SeasonKind};
+pub use solar::{equation_of_time, solar_noon, sun_position, SunPosition};
+pub use solar_eclipse::{local_solar_eclipses, LocalSolarEclipse, SolarEclipseKind};
+pub use tonight::{
+    whats_up_tonight, FeatureSighting, PlanetSighting, SatelliteSighting, TonightCriteria,
+    TonightSummary,
+};
+pub use twilight::{
+    dark_twilight_day, find_twilight_transitions, Twilight, TwilightEvent, SUNRISE_ALTITUDE_DEG,
+};
+pub use variable::find_variable_star_extrema;
+pub use visibility::visibility_windows;
+
+use crate::accuracy::estimate::ephemeris_error_arcsec;
+use crate::accuracy::{AccuracyEstimate, ErrorSource};
+use crate::constants::DAY_S;
+use crate::observers::GeographicLocation;
+use crate::planetlib::{Body, Ephemeris};
+use crate::searchlib::find_discrete;
+use crate::time::Time;
+use crate::tracking::TrackingTarget;
+
+/// Mean rate of the Moon's elongation from the Sun, one turn per synodic month
+pub(crate) const MOON_ELONGATION_RATE_ARCSEC_PER_S: f64 = 1_296_000.0 / (29.530_589 * DAY_S);
+
+/// Error budget of an event timed by the Moon's motion relative to the Sun
+pub(crate) fn sun_moon_event_accuracy(ephemeris: &Ephemeris) -> AccuracyEstimate {
+    AccuracyEstimate::for_position(ephemeris, Body::Moon)
+        .with(
+            ErrorSource::Ephemeris,
+            ephemeris_error_arcsec(ephemeris, Body::Sun),
+        )
+        .for_event(MOON_ELONGATION_RATE_ARCSEC_PER_S)
+}
+
+/// A span of time between two instants
+#[derive(Debug, Clone)]
+pub struct TimeWindow {
+    /// Start of the window
+    pub start: Time,
+    /// End of the window
+    pub end: Time,
+}
+
+impl TimeWindow {
+    /// Length of the window in days
+    pub fn duration_days(&self) -> f64 {
+        self.end.tt() - self.start.tt()
+    }
+
+    /// Length of the window in hours
+    pub fn duration_hours(&self) -> f64 {
+        self.duration_days() * 24.0
+    }
+
+    /// Whether `t` falls inside the window
+    pub fn contains(&self, t: &Time) -> bool {
+        (self.start.tt()..=self.end.tt()).contains(&t.tt())
+    }
+}
+
+/// The spans between `start` and `end` in which `f` holds
+///
+/// Transitions are sampled every `step_days` and refined with
+/// [`find_discrete`]; spans already open at `start` or still open at `end`
+/// are clipped to the range.
+pub(crate) fn windows_where(
+    start: &Time,
+    end: &Time,
+    step_days: f64,
+    f: impl Fn(&Time) -> bool,
+) -> Vec<TimeWindow> {
+    let transitions = find_discrete(start, end, step_days, &f);
+
+    let mut windows = Vec::new();
+    let mut open = f(start).then(|| start.clone());
+
+    for (t, holds) in transitions {
+        match (holds, open.take()) {
+            (true, None) => open = Some(t),
+            (false, Some(window_start)) => windows.push(TimeWindow {
+                start: window_start,
+                end: t,
+            }),
+            (_, still_open) => open = still_open,
+        }
+    }
+
+    if let Some(window_start) = open {
+        windows.push(TimeWindow {
+            start: window_start,
+            end: end.clone(),
+        });
+    }
+
+    windows
+}
+
+/// Geocentric position of a body in AU, J2000 equatorial axes
+///
+/// Light time and aberration are neglected, which is well within the
+/// accuracy needed for altitude thresholds. Dates outside a kernel-backed
+/// ephemeris' coverage give NaN, so every altitude test there fails.
+pub(crate) fn geocentric_position(
+    ephemeris: &Ephemeris,
+    body: Body,
+    t: &Time,
+) -> nalgebra::Vector3<f64> {
+    let jd = t.tdb();
+    match (
+        ephemeris.position(body, jd),
+        ephemeris.position(Body::Earth, jd),
+    ) {
+        (Ok(target), Ok(earth)) => target - earth,
+        _ => nalgebra::Vector3::repeat(f64::NAN),
+    }
+}
+
+/// Refraction-free altitude of a body in degrees as seen from a site
+pub(crate) fn altitude(
+    ephemeris: &Ephemeris,
+    location: &GeographicLocation,
+    body: Body,
+    t: &Time,
+) -> f64 {
+    location
+        .altaz(&geocentric_position(ephemeris, body, t), t)
+        .0
+}
+
+/// Distance in AU at which stars are placed, far enough that the site's
+/// offset from the geocenter causes no parallax
+const STAR_DISTANCE_AU: f64 = 1e9;
+
+/// Refraction-free altitude of a target in degrees as seen from a site
+pub(crate) fn target_altitude(
+    ephemeris: &Ephemeris,
+    location: &GeographicLocation,
+    target: &TrackingTarget,
+    t: &Time,
+) -> f64 {
+    match target {
+        TrackingTarget::Star(position) => {
+            let (sin_dec, cos_dec) = position.dec.sin_cos();
+            let (sin_ra, cos_ra) = position.ra.sin_cos();
+            let direction = nalgebra::Vector3::new(cos_dec * cos_ra, cos_dec * sin_ra, sin_dec);
+            location.altaz(&(direction * STAR_DISTANCE_AU), t).0
+        }
+        TrackingTarget::Body(body) => altitude(ephemeris, location, *body, t),
+    }
+}
+
+/// TT Julian date in `[start_jd, end_jd]` at which `f` is smallest, to a
+/// second
+///
+/// A golden-section search, so `f` must have a single minimum in the range.
+pub(crate) fn minimize(start_jd: f64, end_jd: f64, mut f: impl FnMut(f64) -> f64) -> f64 {
+    let ratio = (5f64.sqrt() - 1.0) / 2.0;
+    let (mut a, mut b) = (start_jd, end_jd);
+    let mut c = b - ratio * (b - a);
+    let mut d = a + ratio * (b - a);
+    let (mut fc, mut fd) = (f(c), f(d));
+    while b - a > 1.0 / DAY_S {
+        if fc < fd {
+            b = d;
+            d = c;
+            fd = fc;
+            c = b - ratio * (b - a);
+            fc = f(c);
+        } else {
+            a = c;
+            c = d;
+            fc = fd;
+            d = a + ratio * (b - a);
+            fd = f(d);
+        }
+    }
+    0.5 * (a + b)
+}
@@ -0,0 +1,421 @@
+//! Ground tracks of stellar occultations by asteroids
+//!
+//! A star is so distant that an asteroid's shadow is a cylinder of the
+//! asteroid's own diameter, parallel to the star's direction. Positions are
+//! worked in the fundamental plane through the geocenter perpendicular to
+//! that direction: the shadow's centre crosses it in a nearly straight line,
+//! and each point of the track on the ground is where a line parallel to the
+//! star's direction through the shadow meets the ellipsoid on the side
+//! facing the star.
+//!
+//! ```
+//! use starfield::almanac::find_occultation_path;
+//! use starfield::coordinates::Equatorial;
+//! use starfield::planetlib::{Ephemeris, OrbitalElements};
+//! use starfield::time::{TimeDelta, Timescale};
+//!
+//! let ts = Timescale::default();
+//! let eph = Ephemeris::new();
+//! let ceres = OrbitalElements {
+//!     epoch_jd: 2_460_600.5,
+//!     semi_major_axis_au: 2.7656,
+//!     eccentricity: 0.0796,
+//!     inclination_deg: 10.588,
+//!     ascending_node_deg: 80.25,
+//!     argument_of_perihelion_deg: 73.3,
+//!     mean_anomaly_deg: 187.1,
+//! };
+//! let star = Equatorial::new(4.8, -0.45);
+//! let start = ts.utc((2024, 11, 1));
+//! let end = &start + TimeDelta::days(1.0);
+//! if let Some(path) = find_occultation_path(&eph, &ceres, 939.4, &star, &start, &end)? {
+//!     println!("{}", path.to_geojson());
+//! }
+//! # Ok::<(), starfield::planetlib::PlanetError>(())
+//! ```
+//!
+//! The asteroid's place is light-time corrected; aberration shifts star
+//! and asteroid alike and cancels. Paths are only as good as the asteroid's
+//! orbit: an error of 0.01″ moves the track by tens of kilometres.
+
+use super::minimize;
+use crate::constants::{AU_KM, AU_M, C, DAY_S, EARTH_RADIUS, IERS_2010_INVERSE_EARTH_FLATTENING};
+use crate::coordinates::Equatorial;
+use crate::earthlib::itrs_to_gcrs;
+use crate::observers::GeographicLocation;
+use crate::planetlib::{Body, Ephemeris, OrbitalElements, PlanetError};
+use crate::satellites::frames::geodetic;
+use crate::time::{Time, TimeDelta};
+use nalgebra::Vector3;
+use serde_json::json;
+
+/// Spacing of the points along the track, in seconds
+const TRACK_STEP_S: f64 = 5.0;
+
+/// Light-time iterations for the asteroid's position
+const LIGHT_TIME_ITERATIONS: usize = 3;
+
+/// One moment of the shadow's passage
+#[derive(Debug, Clone)]
+pub struct ShadowPoint {
+    /// Time of the point
+    pub time: Time,
+    /// Where the centre of the shadow touches the ground, if it does
+    pub center: Option<GeographicLocation>,
+    /// Northern edge of the shadow on the ground, if it touches
+    pub north_limit: Option<GeographicLocation>,
+    /// Southern edge of the shadow on the ground, if it touches
+    pub south_limit: Option<GeographicLocation>,
+    /// Altitude of the Sun at the centre point, degrees; the event is only
+    /// observable below about -12°
+    pub sun_altitude_deg: Option<f64>,
+}
+
+/// Path of an asteroid's shadow across the Earth
+#[derive(Debug, Clone)]
+pub struct OccultationPath {
+    /// Moment the shadow axis passes closest to the geocenter
+    pub closest_approach: Time,
+    /// Distance of the shadow axis from the geocenter at closest approach,
+    /// in Earth equatorial radii
+    pub impact_parameter: f64,
+    /// Width of the path perpendicular to the track, km
+    pub width_km: f64,
+    /// Speed of the shadow across the fundamental plane, km/s
+    pub shadow_speed_km_s: f64,
+    /// Points along the track, every few seconds while the shadow touches
+    /// the Earth
+    pub points: Vec<ShadowPoint>,
+}
+
+impl OccultationPath {
+    /// Longest occultation a stationary observer on the centre line can
+    /// see, seconds
+    pub fn max_duration_s(&self) -> f64 {
+        self.width_km / self.shadow_speed_km_s
+    }
+
+    /// Centre line as (latitude, longitude) pairs in degrees
+    pub fn center_line(&self) -> Vec<(f64, f64)> {
+        polyline(self.points.iter().map(|p| p.center.as_ref()))
+    }
+
+    /// Northern limit as (latitude, longitude) pairs in degrees
+    pub fn north_limit(&self) -> Vec<(f64, f64)> {
+        polyline(self.points.iter().map(|p| p.north_limit.as_ref()))
+    }
+
+    /// Southern limit as (latitude, longitude) pairs in degrees
+    pub fn south_limit(&self) -> Vec<(f64, f64)> {
+        polyline(self.points.iter().map(|p| p.south_limit.as_ref()))
+    }
+
+    /// The centre line and limits as a GeoJSON `FeatureCollection` of
+    /// `LineString`s, with each point's time on the centre line
+    pub fn to_geojson(&self) -> String {
+        let line = |name: &str, points: Vec<(f64, f64)>| {
+            let coordinates: Vec<[f64; 2]> = points.iter().map(|&(lat, lon)| [lon, lat]).collect();
+            json!({
+                "type": "Feature",
+                "properties": { "name": name },
+                "geometry": { "type": "LineString", "coordinates": coordinates },
+            })
+        };
+        let times: Vec<String> = self
+            .points
+            .iter()
+            .filter(|p| p.center.is_some())
+            .filter_map(|p| p.time.utc_iso('T', 1).ok())
+            .collect();
+
+        let mut center = line("center", self.center_line());
+        center["properties"]["times"] = json!(times);
+        json!({
+            "type": "FeatureCollection",
+            "properties": {
+                "closest_approach": self.closest_approach.utc_iso('T', 1).ok(),
+                "width_km": self.width_km,
+                "shadow_speed_km_s": self.shadow_speed_km_s,
+            },
+            "features": [
+                center,
+                line("north_limit", self.north_limit()),
+                line("south_limit", self.south_limit()),
+            ],
+        })
+        .to_string()
+    }
+}
+
+/// Latitude and longitude of the points that touch the ground
+fn polyline<'a>(points: impl Iterator<Item = Option<&'a GeographicLocation>>) -> Vec<(f64, f64)> {
+    points
+        .flatten()
+        .map(|p| (p.latitude_deg, p.longitude_deg))
+        .collect()
+}
+
+/// Axes of the fundamental plane: east and north on the sky at the star
+struct FundamentalPlane {
+    star: Vector3<f64>,
+    east: Vector3<f64>,
+    north: Vector3<f64>,
+}
+
+impl FundamentalPlane {
+    fn new(star: &Equatorial) -> Self {
+        let (sin_dec, cos_dec) = star.dec.sin_cos();
+        let (sin_ra, cos_ra) = star.ra.sin_cos();
+        let star = Vector3::new(cos_dec * cos_ra, cos_dec * sin_ra, sin_dec);
+        let east = Vector3::new(-sin_ra, cos_ra, 0.0);
+        let north = star.cross(&east);
+        Self { star, east, north }
+    }
+
+    /// Shadow axis in the plane, km east and north of the geocenter
+    fn project(&self, geocentric_km: &Vector3<f64>) -> (f64, f64) {
+        (
+            geocentric_km.dot(&self.east),
+            geocentric_km.dot(&self.north),
+        )
+    }
+
+    /// Ground point below the plane point `(x, y)` km at `t`, on the side of
+    /// the Earth facing the star
+    fn ground_point(&self, x: f64, y: f64, t: &Time) -> Option<GeographicLocation> {
+        let to_itrs = itrs_to_gcrs(t).transpose();
+        let origin = to_itrs * (self.east * x + self.north * y) * 1000.0;
+        let direction = to_itrs * self.star;
+
+        // Stretch z so that the ellipsoid becomes a sphere
+        let stretch = 1.0 / (1.0 - 1.0 / IERS_2010_INVERSE_EARTH_FLATTENING);
+        let scale = Vector3::new(1.0, 1.0, stretch);
+        let (o, d) = (
+            origin.component_mul(&scale),
+            direction.component_mul(&scale),
+        );
+
+        let a = d.norm_squared();
+        let b = o.dot(&d);
+        let c = o.norm_squared() - EARTH_RADIUS * EARTH_RADIUS;
+        let discriminant = b * b - a * c;
+        if discriminant < 0.0 {
+            return None;
+        }
+        let lambda = (-b + discriminant.sqrt()) / a;
+        let mut point = geodetic(&(origin + direction * lambda));
+        point.elevation_m = 0.0;
+        Some(point)
+    }
+}
+
+/// Geocentric astrometric position of an asteroid in km, J2000 axes
+fn asteroid_geocentric_km(
+    ephemeris: &Ephemeris,
+    elements: &OrbitalElements,
+    t: &Time,
+) -> Result<Vector3<f64>, PlanetError> {
+    let jd = t.tdb();
+    let earth = ephemeris.position(Body::Earth, jd)?;
+    let mut light_time = 0.0;
+    let mut geocentric = Vector3::zeros();
+    for _ in 0..LIGHT_TIME_ITERATIONS {
+        let emitted = jd - light_time;
+        let asteroid =
+            ephemeris.position(Body::Sun, emitted)? + elements.heliocentric_position(emitted);
+        geocentric = asteroid - earth;
+        light_time = geocentric.norm() * AU_M / C / DAY_S;
+    }
+    Ok(geocentric * AU_KM)
+}
+
+/// Find the shadow path of an occultation of `star` by an asteroid between
+/// `start` and `end`
+///
+/// `star` is the star's astrometric J2000 place at the date, proper motion
+/// applied. The search range should be short, a day or two around the
+/// predicted conjunction, so that the shadow's distance from the geocenter
+/// has a single minimum. Returns `None` when the shadow misses the Earth.
+pub fn find_occultation_path(
+    ephemeris: &Ephemeris,
+    asteroid: &OrbitalElements,
+    diameter_km: f64,
+    star: &Equatorial,
+    start: &Time,
+    end: &Time,
+) -> Result<Option<OccultationPath>, PlanetError> {
+    let plane = FundamentalPlane::new(star);
+    let at = |jd: f64| start + TimeDelta::days(jd - start.tt());
+    let shadow = |t: &Time| -> Result<(f64, f64), PlanetError> {
+        Ok(plane.project(&asteroid_geocentric_km(ephemeris, asteroid, t)?))
+    };
+
+    let closest_jd = minimize(start.tt(), end.tt(), |jd| {
+        shadow(&at(jd)).map_or(f64::INFINITY, |(x, y)| x.hypot(y))
+    });
+
+    // Refine on the straight-line motion around the minimum, and take the
+    // shadow's velocity from the same two samples
+    let step = 1.0 / DAY_S;
+    let (x0, y0) = shadow(&at(closest_jd - step))?;
+    let (x1, y1) = shadow(&at(closest_jd + step))?;
+    let (vx, vy) = ((x1 - x0) / 2.0, (y1 - y0) / 2.0);
+    let speed = vx.hypot(vy);
+    let (xm, ym) = ((x0 + x1) / 2.0, (y0 + y1) / 2.0);
+    let offset_s = -(xm * vx + ym * vy) / (speed * speed);
+    let closest_approach = at(closest_jd + offset_s / DAY_S);
+
+    let (x, y) = shadow(&closest_approach)?;
+    let earth_radius_km = EARTH_RADIUS / 1000.0;
+    let half_width = diameter_km / 2.0;
+    if x.hypot(y) > earth_radius_km + half_width {
+        return Ok(None);
+    }
+
+    // The limits lie half a diameter either side of the track, to the left
+    // and right of the motion; "north" is the one with the larger y
+    let (ux, uy) = (vx / speed, vy / speed);
+    let mut left = (-uy * half_width, ux * half_width);
+    if left.1 < 0.0 {
+        left = (-left.0, -left.1);
+    }
+
+    let half_span_s = (earth_radius_km + half_width) / speed;
+    let steps = (2.0 * half_span_s / TRACK_STEP_S).ceil() as usize;
+    let mut points = Vec::with_capacity(steps + 1);
+    for i in 0..=steps {
+        let t = &closest_approach + TimeDelta::seconds(i as f64 * TRACK_STEP_S - half_span_s);
+        let (x, y) = shadow(&t)?;
+        let center = plane.ground_point(x, y, &t);
+        let sun_altitude_deg = match &center {
+            Some(site) => {
+                let sun = ephemeris.position(Body::Sun, t.tdb())?
+                    - ephemeris.position(Body::Earth, t.tdb())?;
+                Some(site.altaz(&sun, &t).0)
+            }
+            None => None,
+        };
+        points.push(ShadowPoint {
+            north_limit: plane.ground_point(x + left.0, y + left.1, &t),
+            south_limit: plane.ground_point(x - left.0, y - left.1, &t),
+            center,
+            sun_altitude_deg,
+            time: t,
+        });
+    }
+    points.retain(|p| p.north_limit.is_some() || p.south_limit.is_some() || p.center.is_some());
+
+    Ok(Some(OccultationPath {
+        closest_approach,
+        impact_parameter: x.hypot(y) / earth_radius_km,
+        width_km: diameter_km,
+        shadow_speed_km_s: speed,
+        points,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::time::Timescale;
+    use approx::assert_relative_eq;
+
+    fn ceres() -> OrbitalElements {
+        OrbitalElements {
+            epoch_jd: 2_460_600.5,
+            semi_major_axis_au: 2.7656,
+            eccentricity: 0.0796,
+            inclination_deg: 10.588,
+            ascending_node_deg: 80.25,
+            argument_of_perihelion_deg: 73.3,
+            mean_anomaly_deg: 187.1,
+        }
+    }
+
+    /// A star exactly behind the asteroid, as seen from the geocenter, at `t`
+    fn star_behind(eph: &Ephemeris, t: &Time) -> Equatorial {
+        let v = asteroid_geocentric_km(eph, &ceres(), t).unwrap();
+        Equatorial::new(v.y.atan2(v.x), (v.z / v.norm()).asin())
+    }
+
+    #[test]
+    fn test_central_occultation() {
+        let ts = Timescale::default();
+        let eph = Ephemeris::new();
+        let t = ts.utc((2024, 11, 20, 6, 0, 0.0));
+        let star = star_behind(&eph, &t);
+
+        let path = find_occultation_path(
+            &eph,
+            &ceres(),
+            939.4,
+            &star,
+            &(&t - TimeDelta::days(0.5)),
+            &(&t + TimeDelta::days(0.5)),
+        )
+        .unwrap()
+        .unwrap();
+        assert!((path.closest_approach.tt() - t.tt()).abs() * DAY_S < 0.5);
+        assert!(path.impact_parameter < 1e-3);
+        // A main-belt asteroid's shadow moves at 5-30 km/s
+        assert!((1.0..40.0).contains(&path.shadow_speed_km_s));
+        assert_relative_eq!(path.max_duration_s(), 939.4 / path.shadow_speed_km_s);
+
+        // At closest approach the star is overhead on the centre line, and
+        // the limits lie half a diameter either side of it
+        let middle = path
+            .points
+            .iter()
+            .min_by(|a, b| {
+                let gap = |p: &ShadowPoint| (p.time.tt() - t.tt()).abs();
+                gap(a).total_cmp(&gap(b))
+            })
+            .unwrap();
+        let center = middle.center.unwrap();
+        let direction = Vector3::new(
+            star.dec.cos() * star.ra.cos(),
+            star.dec.cos() * star.ra.sin(),
+            star.dec.sin(),
+        );
+        assert!(center.altaz(&(direction * 1e9), &middle.time).0 > 89.5);
+
+        let itrs = |p: &GeographicLocation| p.itrs_position() * AU_KM;
+        let north = middle.north_limit.unwrap();
+        let south = middle.south_limit.unwrap();
+        assert!(north.latitude_deg > south.latitude_deg);
+        assert_relative_eq!(
+            (itrs(&north) - itrs(&south)).norm(),
+            939.4,
+            max_relative = 0.02
+        );
+
+        let center_line = path.center_line();
+        assert!(center_line.len() > 10);
+        let geojson: serde_json::Value = serde_json::from_str(&path.to_geojson()).unwrap();
+        assert_eq!(geojson["features"].as_array().unwrap().len(), 3);
+        assert_eq!(
+            geojson["features"][0]["geometry"]["coordinates"][0][0],
+            center_line[0].1
+        );
+    }
+
+    #[test]
+    fn test_miss() {
+        let ts = Timescale::default();
+        let eph = Ephemeris::new();
+        let t = ts.utc((2024, 11, 20, 6, 0, 0.0));
+        let behind = star_behind(&eph, &t);
+        // Ten arcseconds off puts the shadow some 12,000 km from the geocenter
+        let star = Equatorial::new(behind.ra, behind.dec + 10.0 * crate::constants::ASEC2RAD);
+        let path = find_occultation_path(
+            &eph,
+            &ceres(),
+            939.4,
+            &star,
+            &(&t - TimeDelta::days(0.5)),
+            &(&t + TimeDelta::days(0.5)),
+        )
+        .unwrap();
+        assert!(path.is_none());
+    }
+}
@@ -0,0 +1,316 @@
+//! Conjunctions, oppositions, greatest elongations and close approaches
+//!
+//! Two bodies are in conjunction when their ecliptic longitudes agree and
+//! in opposition when they differ by 180°, seen either from the Earth or
+//! from the Sun. A planet's opposition is its geocentric opposition with the
+//! Sun, and Mercury and Venus are best seen at their greatest elongations
+//! from it:
+//!
+//! ```
+//! use starfield::almanac::{find_conjunctions_and_oppositions, AlignmentKind, Viewpoint};
+//! use starfield::planetlib::{Body, Ephemeris};
+//! use starfield::Timescale;
+//!
+//! let ts = Timescale::default();
+//! let events = find_conjunctions_and_oppositions(
+//!     &Ephemeris::new(),
+//!     Body::Jupiter,
+//!     Body::Sun,
+//!     Viewpoint::Geocentric,
+//!     &ts.utc((2023, 1, 1)),
+//!     &ts.utc((2024, 1, 1)),
+//! );
+//! let kinds: Vec<_> = events.iter().map(|e| e.kind).collect();
+//! assert_eq!(kinds, [AlignmentKind::Conjunction, AlignmentKind::Opposition]);
+//! ```
+
+use super::geocentric_position;
+use crate::constants::RAD2DEG;
+use crate::framelib::INERTIAL_FRAMES;
+use crate::planetlib::{Body, Ephemeris};
+use crate::searchlib::find_discrete;
+use crate::time::{Time, TimeDelta};
+use nalgebra::Vector3;
+
+/// Sampling step for the searches in days; the Moon takes two weeks to go
+/// from conjunction to opposition
+const SEARCH_STEP_DAYS: f64 = 1.0;
+
+/// Half-width in days of the difference used to tell whether a quantity is
+/// growing
+const SLOPE_HALF_WIDTH_DAYS: f64 = 1e-4;
+
+/// Where longitudes are measured from
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Viewpoint {
+    /// From the centre of the Earth
+    Geocentric,
+    /// From the centre of the Sun
+    Heliocentric,
+}
+
+impl Viewpoint {
+    fn center(&self) -> Body {
+        match self {
+            Viewpoint::Geocentric => Body::Earth,
+            Viewpoint::Heliocentric => Body::Sun,
+        }
+    }
+}
+
+/// Whether two bodies line up on the same side or opposite sides
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AlignmentKind {
+    /// Equal ecliptic longitudes
+    Conjunction,
+    /// Ecliptic longitudes 180° apart
+    Opposition,
+}
+
+/// A conjunction or opposition in ecliptic longitude
+#[derive(Debug, Clone)]
+pub struct Alignment {
+    /// When the longitudes line up
+    pub time: Time,
+    /// Conjunction or opposition
+    pub kind: AlignmentKind,
+    /// Angle between the bodies in degrees at that moment, which their
+    /// differing latitudes keep from being 0 or 180
+    pub separation_deg: f64,
+}
+
+/// A greatest elongation from the Sun
+#[derive(Debug, Clone)]
+pub struct Elongation {
+    /// When the elongation peaks
+    pub time: Time,
+    /// Angle from the Sun in degrees
+    pub elongation_deg: f64,
+    /// Whether the body is east of the Sun, in the evening sky
+    pub eastern: bool,
+}
+
+/// A moment at which two bodies pass closest in the sky
+#[derive(Debug, Clone)]
+pub struct Approach {
+    /// When the separation is smallest
+    pub time: Time,
+    /// Geocentric angle between the bodies in degrees
+    pub separation_deg: f64,
+}
+
+/// Position of `body` relative to the viewpoint in AU, J2000 equatorial axes
+fn position_from(
+    ephemeris: &Ephemeris,
+    body: Body,
+    viewpoint: Viewpoint,
+    t: &Time,
+) -> Vector3<f64> {
+    let jd = t.tdb();
+    match (
+        ephemeris.position(body, jd),
+        ephemeris.position(viewpoint.center(), jd),
+    ) {
+        (Ok(target), Ok(center)) => target - center,
+        _ => Vector3::repeat(f64::NAN),
+    }
+}
+
+/// Ecliptic longitude of `a` minus that of `b` in [0, 360) degrees
+fn longitude_difference(
+    ephemeris: &Ephemeris,
+    a: Body,
+    b: Body,
+    viewpoint: Viewpoint,
+    t: &Time,
+) -> f64 {
+    let ecliptic = &INERTIAL_FRAMES["ECLIPJ2000"];
+    let longitude = |body| {
+        let v = ecliptic * position_from(ephemeris, body, viewpoint, t);
+        v.y.atan2(v.x) * RAD2DEG
+    };
+    (longitude(a) - longitude(b)).rem_euclid(360.0)
+}
+
+/// Geocentric angle between two bodies in degrees
+fn separation_deg(ephemeris: &Ephemeris, a: Body, b: Body, t: &Time) -> f64 {
+    let a = geocentric_position(ephemeris, a, t);
+    let b = geocentric_position(ephemeris, b, t);
+    a.angle(&b) * RAD2DEG
+}
+
+/// Local maxima (`true`) and minima (`false`) of `f` between `start` and
+/// `end`, found where its slope changes sign
+fn turning_points(start: &Time, end: &Time, f: impl Fn(&Time) -> f64) -> Vec<(Time, bool)> {
+    let h = SLOPE_HALF_WIDTH_DAYS;
+    let growing = |t: &Time| f(&(t + TimeDelta::days(h))) > f(&(t - TimeDelta::days(h)));
+    find_discrete(start, end, SEARCH_STEP_DAYS, growing)
+        .into_iter()
+        .map(|(t, now_growing)| (t, !now_growing))
+        .collect()
+}
+
+/// Conjunctions and oppositions in ecliptic longitude of `a` and `b`
+/// between `start` and `end`
+///
+/// With [`Viewpoint::Geocentric`] and `b` the Sun these are the planets'
+/// conjunctions and oppositions; the Earth may only be given
+/// heliocentrically. Positions are geometric.
+pub fn find_conjunctions_and_oppositions(
+    ephemeris: &Ephemeris,
+    a: Body,
+    b: Body,
+    viewpoint: Viewpoint,
+    start: &Time,
+    end: &Time,
+) -> Vec<Alignment> {
+    let half = |t: &Time| longitude_difference(ephemeris, a, b, viewpoint, t) < 180.0;
+    find_discrete(start, end, SEARCH_STEP_DAYS, half)
+        .into_iter()
+        .map(|(time, _)| {
+            // Whichever way the difference moves, the boundary it crossed
+            // tells the two apart
+            let difference = longitude_difference(ephemeris, a, b, viewpoint, &time);
+            let pa = position_from(ephemeris, a, viewpoint, &time);
+            let pb = position_from(ephemeris, b, viewpoint, &time);
+            Alignment {
+                kind: if (difference - 180.0).abs() < 90.0 {
+                    AlignmentKind::Opposition
+                } else {
+                    AlignmentKind::Conjunction
+                },
+                separation_deg: pa.angle(&pb) * RAD2DEG,
+                time,
+            }
+        })
+        .collect()
+}
+
+/// Greatest elongations of `body` from the Sun between `start` and `end`
+///
+/// Meant for Mercury and Venus; an outer planet's elongation peaks near
+/// 180° at opposition.
+pub fn find_max_elongations(
+    ephemeris: &Ephemeris,
+    body: Body,
+    start: &Time,
+    end: &Time,
+) -> Vec<Elongation> {
+    turning_points(start, end, |t| {
+        separation_deg(ephemeris, body, Body::Sun, t)
+    })
+    .into_iter()
+    .filter(|(_, is_maximum)| *is_maximum)
+    .map(|(time, _)| Elongation {
+        elongation_deg: separation_deg(ephemeris, body, Body::Sun, &time),
+        eastern: longitude_difference(ephemeris, body, Body::Sun, Viewpoint::Geocentric, &time)
+            < 180.0,
+        time,
+    })
+    .collect()
+}
+
+/// Moments between `start` and `end` at which `a` and `b` come closest
+/// together in the Earth's sky
+pub fn find_closest_approaches(
+    ephemeris: &Ephemeris,
+    a: Body,
+    b: Body,
+    start: &Time,
+    end: &Time,
+) -> Vec<Approach> {
+    turning_points(start, end, |t| separation_deg(ephemeris, a, b, t))
+        .into_iter()
+        .filter(|(_, is_maximum)| !*is_maximum)
+        .map(|(time, _)| Approach {
+            separation_deg: separation_deg(ephemeris, a, b, &time),
+            time,
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::time::Timescale;
+
+    /// Days between an event and a published UTC date
+    fn days_from(t: &Time, published: (i32, u32, u32, u32)) -> f64 {
+        let ts = t.timescale();
+        let (year, month, day, hour) = published;
+        (t.tt() - ts.utc((year, month, day, hour, 0, 0.0)).tt()).abs()
+    }
+
+    #[test]
+    fn test_jupiter_opposition() {
+        // Jupiter was at opposition on 2023 November 3, 5h UTC
+        let ts = Timescale::default();
+        let eph = Ephemeris::new();
+        let (start, end) = (ts.utc((2023, 7, 1)), ts.utc((2024, 1, 1)));
+        let geocentric = find_conjunctions_and_oppositions(
+            &eph,
+            Body::Jupiter,
+            Body::Sun,
+            Viewpoint::Geocentric,
+            &start,
+            &end,
+        );
+        assert_eq!(geocentric.len(), 1);
+        let opposition = &geocentric[0];
+        assert_eq!(opposition.kind, AlignmentKind::Opposition);
+        assert!(days_from(&opposition.time, (2023, 11, 3, 5)) < 1.0);
+        assert!(opposition.separation_deg > 178.0);
+
+        // Seen from the Sun, the Earth passes Jupiter at the same moment
+        let heliocentric = find_conjunctions_and_oppositions(
+            &eph,
+            Body::Earth,
+            Body::Jupiter,
+            Viewpoint::Heliocentric,
+            &start,
+            &end,
+        );
+        assert_eq!(heliocentric.len(), 1);
+        assert_eq!(heliocentric[0].kind, AlignmentKind::Conjunction);
+        assert!((heliocentric[0].time.tt() - opposition.time.tt()).abs() < 0.1);
+    }
+
+    #[test]
+    fn test_venus_elongations_2023() {
+        // Greatest eastern elongation 45.4° on June 4, western 46.4° on
+        // October 23
+        let ts = Timescale::default();
+        let eph = Ephemeris::new();
+        let elongations = find_max_elongations(
+            &eph,
+            Body::Venus,
+            &ts.utc((2023, 1, 1)),
+            &ts.utc((2024, 1, 1)),
+        );
+        assert_eq!(elongations.len(), 2);
+        let (east, west) = (&elongations[0], &elongations[1]);
+        assert!(east.eastern && !west.eastern);
+        assert!(days_from(&east.time, (2023, 6, 4, 12)) < 1.5);
+        assert!((east.elongation_deg - 45.4).abs() < 0.1);
+        assert!(days_from(&west.time, (2023, 10, 23, 12)) < 1.5);
+        assert!((west.elongation_deg - 46.4).abs() < 0.1);
+    }
+
+    #[test]
+    fn test_venus_jupiter_close_approach() {
+        // Venus passed half a degree from Jupiter on 2023 March 1-2
+        let ts = Timescale::default();
+        let eph = Ephemeris::new();
+        let approaches = find_closest_approaches(
+            &eph,
+            Body::Venus,
+            Body::Jupiter,
+            &ts.utc((2023, 2, 15)),
+            &ts.utc((2023, 3, 15)),
+        );
+        assert_eq!(approaches.len(), 1);
+        let approach = &approaches[0];
+        assert!(days_from(&approach.time, (2023, 3, 2, 0)) < 1.0);
+        assert!((0.4..0.6).contains(&approach.separation_deg));
+    }
+}
@@ -0,0 +1,231 @@
+//! Risings and settings, with the horizon dip of elevated observers
+//!
+//! A body rises or sets when its center crosses a fixed altitude a little
+//! below the geometric horizon: refraction lifts it by about 34′ there, and
+//! for the Sun and Moon the upper limb shows a semidiameter before the
+//! center. An observer above sea level or the surrounding plain sees
+//! further still, since their horizon is depressed by the dip
+//!
+//! ```text
+//! cos(dip) = R / (R + h),    R = R⊕ / (1 - k)
+//! ```
+//!
+//! where `h` is the observer's elevation and `k` the
+//! terrestrial refraction coefficient, which bends near-horizontal lines of
+//! sight and is taken into account by enlarging the Earth's radius. From a
+//! 4000 m summit the dip is nearly two degrees and the Sun rises some ten
+//! minutes early.
+
+use super::target_altitude;
+use crate::constants::RAD2DEG;
+use crate::observers::GeographicLocation;
+use crate::planetlib::{Body, Ephemeris};
+use crate::searchlib::find_zero_crossings;
+use crate::time::Time;
+use crate::tracking::TrackingTarget;
+
+/// Mean radius of the Earth in metres
+const MEAN_EARTH_RADIUS_M: f64 = 6_371_008.8;
+
+/// Sampling interval for the search in days (10 minutes)
+const SEARCH_STEP_DAYS: f64 = 10.0 / 1440.0;
+
+/// Typical terrestrial refraction coefficient near the ground
+pub const TERRESTRIAL_REFRACTION_COEFFICIENT: f64 = 0.13;
+
+/// Refraction at the horizon assumed by almanacs, in degrees (34′)
+pub const HORIZON_REFRACTION_DEG: f64 = 34.0 / 60.0;
+
+/// Dip of the horizon in degrees for an observer at `elevation_m`, with
+/// refraction coefficient `k` (0 for the geometric dip)
+pub fn horizon_dip_deg(elevation_m: f64, k: f64) -> f64 {
+    let radius = effective_radius_m(k);
+    (radius / (radius + elevation_m.max(0.0))).acos() * RAD2DEG
+}
+
+/// Distance in km to the visible horizon for an observer at `elevation_m`,
+/// with refraction coefficient `k`
+pub fn distance_to_horizon_km(elevation_m: f64, k: f64) -> f64 {
+    let radius = effective_radius_m(k);
+    let h = elevation_m.max(0.0);
+    (h * (2.0 * radius + h)).sqrt() / 1000.0
+}
+
+/// Earth radius enlarged so that refracted rays can be drawn straight
+fn effective_radius_m(k: f64) -> f64 {
+    MEAN_EARTH_RADIUS_M / (1.0 - k)
+}
+
+/// Where a body is taken to rise and set
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RiseSetHorizon {
+    /// Altitude of the body's center at rising and setting, for an
+    /// observer at sea level, in degrees
+    pub altitude_deg: f64,
+    /// Whether to lower the horizon by the dip for the observer's elevation
+    pub apply_dip: bool,
+    /// Terrestrial refraction coefficient for the dip
+    pub refraction_coefficient: f64,
+}
+
+impl RiseSetHorizon {
+    /// Horizon at a given altitude of the body's center, without dip
+    pub fn new(altitude_deg: f64) -> Self {
+        Self {
+            altitude_deg,
+            apply_dip: false,
+            refraction_coefficient: TERRESTRIAL_REFRACTION_COEFFICIENT,
+        }
+    }
+
+    /// The conventional horizon for a target: the upper limb on a
+    /// refracted sea-level horizon for the Sun and Moon, the center for
+    /// everything else
+    pub fn for_target(target: &TrackingTarget) -> Self {
+        let semidiameter_deg = match target {
+            TrackingTarget::Body(Body::Sun) => 16.0 / 60.0,
+            TrackingTarget::Body(Body::Moon) => 15.5 / 60.0,
+            _ => 0.0,
+        };
+        Self::new(-HORIZON_REFRACTION_DEG - semidiameter_deg)
+    }
+
+    /// Lower the horizon by the dip for the observer's elevation, as seen
+    /// from a mountain over the sea or an aircraft in flight
+    pub fn with_dip(mut self) -> Self {
+        self.apply_dip = true;
+        self
+    }
+
+    /// Set the terrestrial refraction coefficient used for the dip
+    pub fn with_refraction_coefficient(mut self, k: f64) -> Self {
+        self.refraction_coefficient = k;
+        self
+    }
+
+    /// Altitude of the body's center at rising and setting for an observer
+    /// at `location`, dip included
+    pub fn threshold_deg(&self, location: &GeographicLocation) -> f64 {
+        if self.apply_dip {
+            self.altitude_deg - horizon_dip_deg(location.elevation_m, self.refraction_coefficient)
+        } else {
+            self.altitude_deg
+        }
+    }
+}
+
+/// A rising or setting
+#[derive(Debug, Clone)]
+pub struct RiseSetEvent {
+    /// When the body crosses the horizon
+    pub time: Time,
+    /// Whether it is rising rather than setting
+    pub rising: bool,
+}
+
+/// Find every rising and setting of `target` between `start` and `end`
+///
+/// Altitudes are topocentric, so the Moon's parallax is accounted for.
+/// Crossings are located to about a millisecond; a body that grazes the
+/// horizon for less than the 10-minute sampling step can be missed.
+///
+/// # Examples
+///
+/// ```
+/// use starfield::almanac::{find_risings_and_settings, RiseSetHorizon};
+/// use starfield::observers::GeographicLocation;
+/// use starfield::planetlib::{Body, Ephemeris};
+/// use starfield::time::Timescale;
+/// use starfield::tracking::TrackingTarget;
+///
+/// let ts = Timescale::default();
+/// let sun = TrackingTarget::Body(Body::Sun);
+/// // Mauna Kea, whose horizon is the sea 4200 m below
+/// let summit = GeographicLocation::new(19.82, -155.47, 4_205.0);
+/// let horizon = RiseSetHorizon::for_target(&sun).with_dip();
+///
+/// let events = find_risings_and_settings(
+///     &Ephemeris::new(),
+///     &summit,
+///     &sun,
+///     &ts.utc((2024, 3, 20)),
+///     &ts.utc((2024, 3, 21)),
+///     &horizon,
+/// );
+/// assert_eq!(events.len(), 2);
+/// ```
+pub fn find_risings_and_settings(
+    ephemeris: &Ephemeris,
+    location: &GeographicLocation,
+    target: &TrackingTarget,
+    start: &Time,
+    end: &Time,
+    horizon: &RiseSetHorizon,
+) -> Vec<RiseSetEvent> {
+    let threshold = horizon.threshold_deg(location);
+    find_zero_crossings(start, end, SEARCH_STEP_DAYS, 0.0, |t| {
+        target_altitude(ephemeris, location, target, t) - threshold
+    })
+    .into_iter()
+    .map(|event| RiseSetEvent {
+        time: event.time,
+        rising: event.value,
+    })
+    .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::time::Timescale;
+    use approx::assert_relative_eq;
+
+    #[test]
+    fn test_dip_and_distance() {
+        // Geometric dip is about 1.93'√h, refracted about 1.8'√h
+        assert_relative_eq!(horizon_dip_deg(100.0, 0.0) * 60.0, 19.26, epsilon = 0.05);
+        assert_relative_eq!(
+            horizon_dip_deg(100.0, TERRESTRIAL_REFRACTION_COEFFICIENT) * 60.0,
+            17.97,
+            epsilon = 0.05
+        );
+        assert_eq!(horizon_dip_deg(-5.0, 0.13), 0.0);
+
+        // An eye 2 m above the sea sees about 5.4 km
+        assert_relative_eq!(distance_to_horizon_km(2.0, 0.13), 5.41, epsilon = 0.01);
+        assert_relative_eq!(distance_to_horizon_km(10_000.0, 0.0), 357.1, epsilon = 0.5);
+    }
+
+    #[test]
+    fn test_sunrise_and_sunset() {
+        let ts = Timescale::default();
+        let eph = Ephemeris::new();
+        let sun = TrackingTarget::Body(Body::Sun);
+        let greenwich = GeographicLocation::new(51.4769, 0.0, 46.0);
+        let start = ts.utc((2024, 6, 21));
+        let end = ts.utc((2024, 6, 22));
+
+        let sea_level = RiseSetHorizon::for_target(&sun);
+        let events = find_risings_and_settings(&eph, &greenwich, &sun, &start, &end, &sea_level);
+        assert_eq!(events.len(), 2);
+        assert!(events[0].rising && !events[1].rising);
+        // Almanac times are 03:43 and 20:21 UTC
+        let hours = |e: &RiseSetEvent| (e.time.tt() - start.tt()) * 24.0;
+        assert_relative_eq!(hours(&events[0]), 3.0 + 43.0 / 60.0, epsilon = 2.0 / 60.0);
+        assert_relative_eq!(hours(&events[1]), 20.0 + 21.0 / 60.0, epsilon = 2.0 / 60.0);
+
+        // The dip matters little at 46 m
+        let dipped = sea_level.with_dip();
+        let shift = sea_level.threshold_deg(&greenwich) - dipped.threshold_deg(&greenwich);
+        assert_relative_eq!(shift, 0.2, epsilon = 0.02);
+
+        // Seen from 1000 m above, the Sun rises earlier and sets later
+        let aloft = GeographicLocation::new(51.4769, 0.0, 1_000.0);
+        assert!(dipped.threshold_deg(&aloft) < sea_level.threshold_deg(&aloft) - 0.9);
+        let raised = find_risings_and_settings(&eph, &aloft, &sun, &start, &end, &dipped);
+        let early_min = (events[0].time.tt() - raised[0].time.tt()) * 1440.0;
+        let late_min = (raised[1].time.tt() - events[1].time.tt()) * 1440.0;
+        assert!((5.0..15.0).contains(&early_min), "{}", early_min);
+        assert_relative_eq!(early_min, late_min, epsilon = 1.0);
+    }
+}
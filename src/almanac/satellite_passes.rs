@@ -0,0 +1,366 @@
+//! Passes of Earth satellites over an observer
+//!
+//! Mirrors skyfield's `EarthSatellite.find_events`: the satellite's
+//! altitude is sampled a hundred times an orbit (at most a minute apart),
+//! each local maximum above the altitude limit is refined into a
+//! culmination, and the rise and set around it are bisected to a
+//! millisecond. Each event also says whether the satellite is sunlit,
+//! which is what decides whether a pass can be seen with the naked eye.
+//!
+//! A satellite is in shadow inside the cone of the Earth's umbra. Sunlight
+//! grazing the atmosphere is neglected, so satellites at the edge of the
+//! shadow count as lit a few seconds longer than they appear.
+
+use super::geocentric_position;
+use crate::constants::{AU_KM, DAY_S};
+use crate::observers::GeographicLocation;
+use crate::output::{PassEvent, PassPrediction};
+use crate::planetlib::{Body, Ephemeris};
+use crate::satellites::{EarthSatellite, SatelliteError};
+use crate::searchlib::DEFAULT_EPSILON_DAYS;
+use crate::time::Time;
+use nalgebra::Vector3;
+
+/// Samples of the altitude per orbit
+const SAMPLES_PER_ORBIT: f64 = 100.0;
+
+/// Longest sampling interval in days (one minute)
+const MAX_STEP_DAYS: f64 = 60.0 / DAY_S;
+
+/// Precision of culmination times in days (a tenth of a second)
+const CULMINATION_TOLERANCE_DAYS: f64 = 0.1 / DAY_S;
+
+/// Which part of a pass an event marks
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SatelliteEventKind {
+    /// The satellite climbs through the altitude limit
+    Rise,
+    /// The satellite reaches its highest altitude
+    Culmination,
+    /// The satellite sinks through the altitude limit
+    Set,
+}
+
+/// The satellite's place at one event of a pass
+#[derive(Debug, Clone)]
+pub struct SatelliteEvent {
+    /// When the event happens
+    pub time: Time,
+    /// Which event this is
+    pub kind: SatelliteEventKind,
+    /// Altitude in degrees, without refraction
+    pub altitude_deg: f64,
+    /// Azimuth in degrees, from north through east
+    pub azimuth_deg: f64,
+    /// Distance from the observer in km
+    pub range_km: f64,
+    /// Whether the satellite is outside the Earth's umbra
+    pub sunlit: bool,
+}
+
+impl SatelliteEvent {
+    /// The event in the interchange form of [`crate::output`]
+    pub fn to_output(&self) -> crate::Result<PassEvent> {
+        PassEvent::new(
+            &self.time,
+            self.altitude_deg,
+            self.azimuth_deg,
+            self.range_km,
+        )
+    }
+}
+
+/// One pass of a satellite above the altitude limit
+#[derive(Debug, Clone)]
+pub struct SatellitePass {
+    /// Rising through the limit
+    pub rise: SatelliteEvent,
+    /// Highest point
+    pub culmination: SatelliteEvent,
+    /// Setting through the limit
+    pub set: SatelliteEvent,
+}
+
+impl SatellitePass {
+    /// Highest altitude of the pass in degrees
+    pub fn max_altitude_deg(&self) -> f64 {
+        self.culmination.altitude_deg
+    }
+
+    /// Time from rise to set in minutes
+    pub fn duration_minutes(&self) -> f64 {
+        (self.set.time.tt() - self.rise.time.tt()) * 1440.0
+    }
+
+    /// The three events in time order
+    pub fn events(&self) -> [&SatelliteEvent; 3] {
+        [&self.rise, &self.culmination, &self.set]
+    }
+
+    /// The pass in the interchange form of [`crate::output`]
+    pub fn to_prediction(&self, satellite: &EarthSatellite) -> crate::Result<PassPrediction> {
+        Ok(PassPrediction {
+            satellite: satellite.name().map(str::to_string),
+            catalog_number: satellite.tle().catalog_number,
+            rise: self.rise.to_output()?,
+            culmination: self.culmination.to_output()?,
+            set: self.set.to_output()?,
+        })
+    }
+}
+
+impl EarthSatellite {
+    /// Whether the satellite is outside the Earth's umbra at `t`
+    pub fn is_sunlit(&self, ephemeris: &Ephemeris, t: &Time) -> Result<bool, SatelliteError> {
+        let position = self.geocentric_position(t)? * AU_KM;
+        let sun = geocentric_position(ephemeris, Body::Sun, t) * AU_KM;
+        Ok(!in_umbra(&sun, &position))
+    }
+}
+
+/// Whether a geocentric position in km lies inside the umbra cast by the
+/// Earth away from the Sun at `sun`, also geocentric in km
+fn in_umbra(sun: &Vector3<f64>, position: &Vector3<f64>) -> bool {
+    let earth_radius = Body::Earth.radii_km().0;
+    let sun_radius = Body::Sun.radii_km().0;
+
+    // Distance behind the Earth along the shadow axis, and from the axis
+    let axis = -sun.normalize();
+    let behind = position.dot(&axis);
+    if behind <= 0.0 {
+        return false;
+    }
+    let off_axis = (position - behind * axis).norm();
+
+    // The umbra narrows to a point where the Sun's and Earth's discs match
+    let length = earth_radius * sun.norm() / (sun_radius - earth_radius);
+    off_axis < earth_radius * (1.0 - behind / length)
+}
+
+/// Find every complete pass of `satellite` above `min_altitude_deg` as seen
+/// from `observer` between `start` and `end`
+///
+/// Altitudes are topocentric and unrefracted. Passes already under way at
+/// `start` or still under way at `end` are left out, as are passes that
+/// stay above the limit throughout, like a geostationary satellite's.
+///
+/// # Examples
+///
+/// ```
+/// use starfield::almanac::find_satellite_passes;
+/// use starfield::observers::GeographicLocation;
+/// use starfield::planetlib::Ephemeris;
+/// use starfield::satellites::EarthSatellite;
+/// use starfield::time::Timescale;
+///
+/// let ts = Timescale::default();
+/// let iss = EarthSatellite::parse(
+///     "1 25544U 98067A   08264.51782528 -.00002182  00000-0 -11606-4 0  2927",
+///     "2 25544  51.6416 247.4627 0006703 130.5360 325.0288 15.72125391563537",
+///     &ts,
+/// )?;
+/// let greenwich = GeographicLocation::new(51.4769, 0.0, 46.0);
+///
+/// let passes = find_satellite_passes(
+///     &Ephemeris::new(),
+///     &iss,
+///     &greenwich,
+///     &ts.utc((2008, 9, 21, 0, 0, 0.0)),
+///     &ts.utc((2008, 9, 22, 0, 0, 0.0)),
+///     10.0,
+/// )?;
+/// for pass in &passes {
+///     println!(
+///         "{:.0}° high for {:.1} minutes{}",
+///         pass.max_altitude_deg(),
+///         pass.duration_minutes(),
+///         if pass.culmination.sunlit { ", sunlit" } else { "" }
+///     );
+/// }
+/// # Ok::<(), starfield::satellites::SatelliteError>(())
+/// ```
+pub fn find_satellite_passes(
+    ephemeris: &Ephemeris,
+    satellite: &EarthSatellite,
+    observer: &GeographicLocation,
+    start: &Time,
+    end: &Time,
+    min_altitude_deg: f64,
+) -> Result<Vec<SatellitePass>, SatelliteError> {
+    let ts = start.timescale();
+    let (jd_start, jd_end) = (start.tt(), end.tt());
+    if jd_end <= jd_start {
+        return Ok(Vec::new());
+    }
+
+    let period_days = 1.0 / satellite.tle().mean_motion;
+    let step = (period_days / SAMPLES_PER_ORBIT).min(MAX_STEP_DAYS);
+    let steps = ((jd_end - jd_start) / step).ceil() as usize;
+    let altitude = |jd: f64| -> Result<f64, SatelliteError> {
+        Ok(satellite.altaz(observer, &ts.tt_jd(jd, None))?.0)
+    };
+
+    let samples = (0..=steps)
+        .map(|i| {
+            let jd = (jd_start + i as f64 * step).min(jd_end);
+            altitude(jd).map(|alt| (jd, alt))
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let event = |jd: f64, kind| -> Result<SatelliteEvent, SatelliteError> {
+        let time = ts.tt_jd(jd, None);
+        let (altitude_deg, azimuth_deg, range_km) = satellite.altaz(observer, &time)?;
+        Ok(SatelliteEvent {
+            sunlit: satellite.is_sunlit(ephemeris, &time)?,
+            time,
+            kind,
+            altitude_deg,
+            azimuth_deg,
+            range_km,
+        })
+    };
+
+    let mut passes: Vec<SatellitePass> = Vec::new();
+    for i in 1..samples.len().saturating_sub(1) {
+        let (before, (jd, alt), after) = (samples[i - 1].1, samples[i], samples[i + 1].1);
+        if alt <= before || alt < after || alt < min_altitude_deg {
+            continue;
+        }
+        // A pass with several maxima is reported once
+        if passes.last().is_some_and(|p| p.set.time.tt() >= jd) {
+            continue;
+        }
+
+        // Walk out to the samples below the limit on either side
+        let Some(below_before) = samples[..i].iter().rposition(|s| s.1 < min_altitude_deg) else {
+            continue;
+        };
+        let Some(below_after) = samples[i..].iter().position(|s| s.1 < min_altitude_deg) else {
+            continue;
+        };
+        let below_after = i + below_after;
+
+        let peak = golden_section_max(samples[i - 1].0, samples[i + 1].0, altitude)?;
+        let rise = bisect(samples[below_before].0, samples[below_before + 1].0, |jd| {
+            Ok(altitude(jd)? >= min_altitude_deg)
+        })?;
+        let set = bisect(samples[below_after - 1].0, samples[below_after].0, |jd| {
+            Ok(altitude(jd)? < min_altitude_deg)
+        })?;
+
+        passes.push(SatellitePass {
+            rise: event(rise, SatelliteEventKind::Rise)?,
+            culmination: event(peak, SatelliteEventKind::Culmination)?,
+            set: event(set, SatelliteEventKind::Set)?,
+        });
+    }
+
+    Ok(passes)
+}
+
+/// First time in `[lo, hi]`, to [`DEFAULT_EPSILON_DAYS`], at which `f`
+/// turns true, given that it is false at `lo` and true at `hi`
+fn bisect(
+    mut lo: f64,
+    mut hi: f64,
+    f: impl Fn(f64) -> Result<bool, SatelliteError>,
+) -> Result<f64, SatelliteError> {
+    while hi - lo > DEFAULT_EPSILON_DAYS {
+        let mid = 0.5 * (lo + hi);
+        if f(mid)? {
+            hi = mid;
+        } else {
+            lo = mid;
+        }
+    }
+    Ok(0.5 * (lo + hi))
+}
+
+/// Time of the maximum of a unimodal `f` in `[lo, hi]`
+fn golden_section_max(
+    mut lo: f64,
+    mut hi: f64,
+    f: impl Fn(f64) -> Result<f64, SatelliteError>,
+) -> Result<f64, SatelliteError> {
+    let ratio = (5f64.sqrt() - 1.0) / 2.0;
+    let mut a = hi - ratio * (hi - lo);
+    let mut b = lo + ratio * (hi - lo);
+    let (mut fa, mut fb) = (f(a)?, f(b)?);
+    while hi - lo > CULMINATION_TOLERANCE_DAYS {
+        if fa < fb {
+            lo = a;
+            a = b;
+            fa = fb;
+            b = lo + ratio * (hi - lo);
+            fb = f(b)?;
+        } else {
+            hi = b;
+            b = a;
+            fb = fa;
+            a = hi - ratio * (hi - lo);
+            fa = f(a)?;
+        }
+    }
+    Ok(0.5 * (lo + hi))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::output::JsonOutput;
+    use crate::satellites::iss;
+    use crate::time::{TimeDelta, Timescale};
+
+    #[test]
+    fn test_umbra_cone() {
+        let earth = Body::Earth.radii_km().0;
+        let sun = Vector3::new(AU_KM, 0.0, 0.0);
+
+        // Lit on the day side and beside the Earth; dark just behind it
+        assert!(!in_umbra(&sun, &Vector3::new(earth + 400.0, 0.0, 0.0)));
+        assert!(!in_umbra(&sun, &Vector3::new(0.0, earth + 400.0, 0.0)));
+        assert!(in_umbra(&sun, &Vector3::new(-earth - 400.0, 0.0, 0.0)));
+        // The umbra narrows: at the Moon's distance it is about 4600 km wide
+        assert!(in_umbra(&sun, &Vector3::new(-384_400.0, 2_000.0, 0.0)));
+        assert!(!in_umbra(&sun, &Vector3::new(-384_400.0, 5_000.0, 0.0)));
+    }
+
+    #[test]
+    fn test_iss_passes_over_greenwich() {
+        let ts = Timescale::default();
+        let eph = Ephemeris::new();
+        let iss = iss(&ts);
+        let site = GeographicLocation::new(51.4769, 0.0, 46.0);
+        let start = ts.utc((2008, 9, 21, 0, 0, 0.0));
+        let end = ts.utc((2008, 9, 22, 0, 0, 0.0));
+
+        let passes = find_satellite_passes(&eph, &iss, &site, &start, &end, 10.0).unwrap();
+        assert!((2..=8).contains(&passes.len()));
+
+        for pass in &passes {
+            let [rise, culmination, set] = pass.events();
+            assert!(rise.time.tt() < culmination.time.tt());
+            assert!(culmination.time.tt() < set.time.tt());
+            assert!((rise.altitude_deg - 10.0).abs() < 1e-3);
+            assert!((set.altitude_deg - 10.0).abs() < 1e-3);
+            assert!(pass.max_altitude_deg() > 10.0);
+            assert!((0.5..12.0).contains(&pass.duration_minutes()));
+
+            // Nothing a second either side of the culmination is higher
+            for offset in [-1.0, 1.0] {
+                let t = &culmination.time + TimeDelta::seconds(offset);
+                assert!(iss.altaz(&site, &t).unwrap().0 < culmination.altitude_deg);
+            }
+        }
+
+        // Around the equinox the ISS is eclipsed for part of every orbit,
+        // but in a UK autumn twilight some passes are in sunlight
+        let events: Vec<_> = passes.iter().flat_map(|p| p.events()).collect();
+        assert!(events.iter().any(|e| e.sunlit));
+
+        let prediction = passes[0].to_prediction(&iss).unwrap();
+        assert_eq!(prediction.catalog_number, 25544);
+        assert!((prediction.duration_minutes() - passes[0].duration_minutes()).abs() < 1e-6);
+        assert!(PassPrediction::from_json(&prediction.to_json().unwrap()).is_ok());
+    }
+}
@@ -0,0 +1,130 @@
+//! Equinoxes and solstices
+//!
+//! A season begins when the Sun's apparent longitude, measured along the
+//! true ecliptic of date, reaches a multiple of 90°: 0° at the March
+//! equinox, 90° at the June solstice and so on. The crossings are bisected
+//! to a millisecond, so the times are as good as the ephemeris: within a
+//! few seconds with a JPL kernel, a few minutes with the analytic model.
+//!
+//! ```
+//! use starfield::almanac::{find_seasons, SeasonKind};
+//! use starfield::planetlib::Ephemeris;
+//!
+//! let seasons = find_seasons(&Ephemeris::new(), 2024..=2024);
+//! assert_eq!(seasons.len(), 4);
+//! let (march, kind) = &seasons[0];
+//! assert_eq!(*kind, SeasonKind::MarchEquinox);
+//! let utc = march.utc_calendar().unwrap();
+//! assert_eq!((utc.month, utc.day, utc.hour), (3, 20, 3));
+//! ```
+
+use std::ops::RangeInclusive;
+
+use super::solar::apparent_sun;
+use crate::constants::RAD2DEG;
+use crate::framelib::{CelestialFrame, Equinox};
+use crate::planetlib::Ephemeris;
+use crate::searchlib::find_discrete;
+use crate::time::{Time, Timescale};
+
+/// Sampling step for the search; the shortest season lasts 89 days
+const SEASON_STEP_DAYS: f64 = 30.0;
+
+/// The four turning points of the Sun's year
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SeasonKind {
+    /// Apparent solar longitude 0°, the northern vernal equinox
+    MarchEquinox,
+    /// Apparent solar longitude 90°
+    JuneSolstice,
+    /// Apparent solar longitude 180°
+    SeptemberEquinox,
+    /// Apparent solar longitude 270°
+    DecemberSolstice,
+}
+
+impl SeasonKind {
+    /// The event that begins a quarter of the Sun's year, 0 to 3
+    fn from_quarter(quarter: u8) -> Self {
+        match quarter % 4 {
+            0 => SeasonKind::MarchEquinox,
+            1 => SeasonKind::JuneSolstice,
+            2 => SeasonKind::SeptemberEquinox,
+            _ => SeasonKind::DecemberSolstice,
+        }
+    }
+
+    /// Apparent solar longitude of the event in degrees
+    pub fn longitude_deg(&self) -> f64 {
+        match self {
+            SeasonKind::MarchEquinox => 0.0,
+            SeasonKind::JuneSolstice => 90.0,
+            SeasonKind::SeptemberEquinox => 180.0,
+            SeasonKind::DecemberSolstice => 270.0,
+        }
+    }
+}
+
+/// Apparent geocentric longitude of the Sun at `t` in [0, 360) degrees,
+/// on the true ecliptic and equinox of date
+pub fn solar_longitude(ephemeris: &Ephemeris, t: &Time) -> f64 {
+    let to_ecliptic = CelestialFrame::Equatorial(Equinox::J2000)
+        .transform_to(&CelestialFrame::Ecliptic(Equinox::TrueOfDate), t);
+    let sun = to_ecliptic * apparent_sun(ephemeris, t);
+    (sun.y.atan2(sun.x) * RAD2DEG).rem_euclid(360.0)
+}
+
+/// Equinoxes and solstices of the calendar years in `years`, in order
+pub fn find_seasons(ephemeris: &Ephemeris, years: RangeInclusive<i32>) -> Vec<(Time, SeasonKind)> {
+    let ts = Timescale::builtin();
+    let start = ts.utc((*years.start(), 1, 1));
+    let end = ts.utc((years.end() + 1, 1, 1));
+    let quarter = |t: &Time| (solar_longitude(ephemeris, t) / 90.0) as u8;
+    find_discrete(&start, &end, SEASON_STEP_DAYS, quarter)
+        .into_iter()
+        .map(|(t, q)| (t, SeasonKind::from_quarter(q)))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_seasons_2024() {
+        // From the US Naval Observatory, to the minute, in UTC. The
+        // analytic ephemeris places the Earth to about 20", which the Sun
+        // covers in eight minutes
+        let expected = [
+            (SeasonKind::MarchEquinox, (3, 20, 3, 6)),
+            (SeasonKind::JuneSolstice, (6, 20, 20, 51)),
+            (SeasonKind::SeptemberEquinox, (9, 22, 12, 44)),
+            (SeasonKind::DecemberSolstice, (12, 21, 9, 20)),
+        ];
+        let ts = Timescale::builtin();
+        let eph = Ephemeris::new();
+        let seasons = find_seasons(&eph, 2024..=2024);
+        assert_eq!(seasons.len(), 4);
+        for ((t, kind), (expected_kind, (month, day, hour, minute))) in seasons.iter().zip(expected)
+        {
+            assert_eq!(*kind, expected_kind);
+            let published = ts.utc((2024, month, day, hour, minute, 30.0));
+            let error_minutes = (t.tt() - published.tt()) * 1440.0;
+            assert!(error_minutes.abs() < 5.0, "{:?}: {}", kind, error_minutes);
+
+            // The longitude sits on the boundary at the instant found
+            let longitude = solar_longitude(&eph, t);
+            let offset = (longitude - kind.longitude_deg() + 180.0).rem_euclid(360.0) - 180.0;
+            assert!(offset.abs() < 1e-5, "{}", offset);
+        }
+    }
+
+    #[test]
+    fn test_several_years() {
+        let seasons = find_seasons(&Ephemeris::new(), 2020..=2022);
+        assert_eq!(seasons.len(), 12);
+        assert!(seasons.windows(2).all(|w| w[0].0.tt() < w[1].0.tt()));
+        let kinds: Vec<_> = seasons.iter().map(|(_, kind)| *kind).collect();
+        assert_eq!(kinds[..4], kinds[8..]);
+    }
+}
@@ -0,0 +1,160 @@
+//! The Sun's place in the sky for solar energy and daylight models
+//!
+//! These use the built-in analytic ephemeris, so nothing needs loading
+//! first:
+//!
+//! ```
+//! use starfield::almanac::{equation_of_time, solar_noon, sun_position};
+//! use starfield::observers::GeographicLocation;
+//! use starfield::Timescale;
+//!
+//! let ts = Timescale::default();
+//! let greenwich = GeographicLocation::new(51.4769, 0.0, 46.0);
+//! let t = ts.utc((2024, 6, 21, 12, 0, 0.0));
+//!
+//! let sun = sun_position(&t, &greenwich);
+//! assert!((sun.altitude_deg - 61.96).abs() < 0.2);
+//! println!("Equation of time: {:+.1} min", equation_of_time(&t));
+//! let noon = solar_noon(&t, &greenwich);
+//! println!("Solar noon: {}", noon.utc_iso('T', 0).unwrap());
+//! ```
+
+use super::geocentric_position;
+use crate::accuracy::NutationModel;
+use crate::constants::C_AUDAY;
+use crate::earthlib::gast;
+use crate::nutationlib::precession_nutation_matrix;
+use crate::observers::GeographicLocation;
+use crate::planetlib::{Body, Ephemeris};
+use crate::time::{Time, TimeDelta};
+use nalgebra::Vector3;
+
+/// Where the Sun appears from a site, without atmospheric refraction
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SunPosition {
+    /// Altitude above the horizon in degrees
+    pub altitude_deg: f64,
+    /// Azimuth in degrees, clockwise from north
+    pub azimuth_deg: f64,
+    /// Distance from the Earth's centre in AU
+    pub distance_au: f64,
+}
+
+impl SunPosition {
+    /// Angle from the zenith in degrees
+    pub fn zenith_deg(&self) -> f64 {
+        90.0 - self.altitude_deg
+    }
+
+    /// Whether the Sun's centre is above the horizon
+    pub fn is_up(&self) -> bool {
+        self.altitude_deg > 0.0
+    }
+}
+
+/// Geocentric direction of the Sun in AU, J2000 equatorial axes, as it
+/// appears at `t`
+///
+/// Seen from the moving Earth, light time and annual aberration together
+/// amount to viewing the Sun from where the Earth was when the light left.
+pub(super) fn apparent_sun(ephemeris: &Ephemeris, t: &Time) -> Vector3<f64> {
+    let geometric = geocentric_position(ephemeris, Body::Sun, t);
+    let light_time = t - TimeDelta::days(geometric.norm() / C_AUDAY);
+    geocentric_position(ephemeris, Body::Sun, &light_time)
+}
+
+/// Altitude and azimuth of the Sun at `t` seen from `location`
+pub fn sun_position(t: &Time, location: &GeographicLocation) -> SunPosition {
+    let sun = apparent_sun(&Ephemeris::new(), t);
+    let (altitude_deg, azimuth_deg) = location.altaz(&sun, t);
+    SunPosition {
+        altitude_deg,
+        azimuth_deg,
+        distance_au: sun.norm(),
+    }
+}
+
+/// Equation of time in minutes: apparent solar time minus mean solar time
+///
+/// Positive when a sundial runs ahead of the clock, as in early November.
+pub fn equation_of_time(t: &Time) -> f64 {
+    let sun = precession_nutation_matrix(t.tt(), NutationModel::Iau2000B)
+        * apparent_sun(&Ephemeris::new(), t);
+    let right_ascension = sun.y.atan2(sun.x).to_degrees();
+    let apparent_hour_angle = gast(t, NutationModel::Iau2000B) * 15.0 - right_ascension;
+    // Julian dates start at noon, when the mean Sun crosses Greenwich
+    let mean_hour_angle = t.ut1().rem_euclid(1.0) * 360.0;
+    let difference = (apparent_hour_angle - mean_hour_angle + 180.0).rem_euclid(360.0) - 180.0;
+    difference * 4.0
+}
+
+/// When the Sun crosses the meridian of `location` on the local mean solar
+/// day containing `t`
+pub fn solar_noon(t: &Time, location: &GeographicLocation) -> Time {
+    // Days since local mean midnight
+    let local_day = (t.ut1() + 0.5 + location.longitude_deg / 360.0).rem_euclid(1.0);
+    let mean_noon = t + TimeDelta::days(0.5 - local_day);
+    // The equation of time drifts by under 30 seconds a day, so evaluating
+    // it near noon twice converges to well under a second
+    let mut noon = mean_noon.clone();
+    for _ in 0..2 {
+        noon = &mean_noon - TimeDelta::minutes(equation_of_time(&noon));
+    }
+    noon
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::time::Timescale;
+
+    #[test]
+    fn test_equation_of_time_extremes() {
+        // The yearly extremes: about -14.2 min in February, +16.4 in November
+        let ts = Timescale::default();
+        let february = equation_of_time(&ts.utc((2024, 2, 11, 12, 0, 0.0)));
+        assert!((february + 14.2).abs() < 0.1, "{}", february);
+        let november = equation_of_time(&ts.utc((2024, 11, 3, 12, 0, 0.0)));
+        assert!((november - 16.4).abs() < 0.1, "{}", november);
+        // Near zero in mid-April
+        let april = equation_of_time(&ts.utc((2024, 4, 15, 12, 0, 0.0)));
+        assert!(april.abs() < 0.5, "{}", april);
+    }
+
+    #[test]
+    fn test_solar_noon() {
+        let ts = Timescale::default();
+        let boulder = GeographicLocation::new(40.015, -105.27, 1_655.0);
+        let morning = ts.utc((2024, 11, 3, 15, 0, 0.0));
+        let noon = solar_noon(&morning, &boulder);
+
+        // 105.27 deg west is 7h 01m 05s behind Greenwich, less 16.4 min
+        let expected = ts.utc((2024, 11, 3, 18, 44, 41.0));
+        assert!(((noon.tt() - expected.tt()) * 86_400.0).abs() < 15.0);
+
+        // The Sun is due south and at its highest then
+        let sun = sun_position(&noon, &boulder);
+        assert!(
+            (sun.azimuth_deg - 180.0).abs() < 0.05,
+            "{}",
+            sun.azimuth_deg
+        );
+        let earlier = sun_position(&(&noon - TimeDelta::days(0.01)), &boulder);
+        let later = sun_position(&(&noon + TimeDelta::days(0.01)), &boulder);
+        assert!(sun.altitude_deg > earlier.altitude_deg && sun.altitude_deg > later.altitude_deg);
+
+        // Any time in the same local day finds the same noon
+        let evening = solar_noon(&ts.utc((2024, 11, 4, 4, 0, 0.0)), &boulder);
+        assert!(((evening.tt() - noon.tt()) * 86_400.0).abs() < 1.0);
+    }
+
+    #[test]
+    fn test_sun_position_at_night() {
+        let ts = Timescale::default();
+        let greenwich = GeographicLocation::new(51.4769, 0.0, 46.0);
+        let midnight = sun_position(&ts.utc((2024, 12, 21, 0, 0, 0.0)), &greenwich);
+        assert!(!midnight.is_up());
+        assert!((midnight.zenith_deg() - (90.0 + 90.0 - 51.4769 + 23.44)).abs() < 0.3);
+        assert!((0.98..0.99).contains(&midnight.distance_au));
+    }
+}
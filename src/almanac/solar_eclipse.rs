@@ -0,0 +1,352 @@
+//! Local circumstances of solar eclipses
+//!
+//! For a site, each new moon in the search range is checked for overlap of
+//! the topocentric apparent disks of the Sun and Moon. Contacts are the
+//! moments the limbs touch: first and fourth contact bound the partial
+//! phase, second and third the total or annular phase.
+
+use super::{
+    find_new_and_full_moons, minimize, sun_moon_event_accuracy, MoonPhaseKind, TimeWindow,
+};
+use crate::accuracy::estimate::delta_t_error_seconds;
+use crate::accuracy::{AccuracyEstimate, ErrorSource};
+use crate::constants::{AU_KM, DEG2RAD};
+use crate::observers::GeographicLocation;
+use crate::planetlib::{Body, Ephemeris, PlanetError};
+use crate::searchlib::find_discrete;
+use crate::time::Time;
+use std::f64::consts::PI;
+
+/// Half-width of the window around new moon searched for an eclipse;
+/// parallax can shift local maximum a few hours from geocentric new moon
+const ECLIPSE_WINDOW_DAYS: f64 = 0.3;
+
+/// Sampling step for contacts (5 minutes); grazing eclipses briefer than
+/// this can be missed
+const CONTACT_STEP_DAYS: f64 = 5.0 / 1440.0;
+
+/// Drift of the Moon's topocentric place per second of error in the Earth's
+/// rotation angle, for a site on the equator: 0.465 km/s at 384,400 km
+const PARALLAX_DRIFT_ARCSEC_PER_S: f64 = 0.2496;
+
+/// Type of a solar eclipse as seen from a site
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SolarEclipseKind {
+    /// The Moon covers part of the Sun
+    Partial,
+    /// The Moon passes inside the Sun's disk, leaving a ring
+    Annular,
+    /// The Moon covers the whole Sun
+    Total,
+}
+
+/// Circumstances of a solar eclipse at one site
+#[derive(Debug, Clone)]
+pub struct LocalSolarEclipse {
+    /// Type of eclipse at this site
+    pub kind: SolarEclipseKind,
+    /// Start of the partial phase
+    pub first_contact: Time,
+    /// Start of the total or annular phase, if any
+    pub second_contact: Option<Time>,
+    /// Moment of greatest eclipse
+    pub maximum: Time,
+    /// End of the total or annular phase, if any
+    pub third_contact: Option<Time>,
+    /// End of the partial phase
+    pub fourth_contact: Time,
+    /// Fraction of the Sun's diameter covered at maximum
+    pub magnitude: f64,
+    /// Fraction of the Sun's disk area covered at maximum
+    pub obscuration: f64,
+    /// Altitude of the Sun at maximum in degrees, without refraction
+    pub sun_altitude_deg: f64,
+    /// Expected error of the Moon's place against the Sun, and of the
+    /// contact times
+    pub accuracy: AccuracyEstimate,
+}
+
+impl LocalSolarEclipse {
+    /// Whether the Sun is above the horizon at maximum
+    pub fn is_visible(&self) -> bool {
+        self.sun_altitude_deg > 0.0
+    }
+
+    /// Span of the partial phase
+    pub fn partial_phase(&self) -> TimeWindow {
+        TimeWindow {
+            start: self.first_contact.clone(),
+            end: self.fourth_contact.clone(),
+        }
+    }
+
+    /// Span of the total or annular phase, if any
+    pub fn central_phase(&self) -> Option<TimeWindow> {
+        match (&self.second_contact, &self.third_contact) {
+            (Some(start), Some(end)) => Some(TimeWindow {
+                start: start.clone(),
+                end: end.clone(),
+            }),
+            _ => None,
+        }
+    }
+}
+
+/// Topocentric disks of the Sun and Moon at one moment
+struct Disks {
+    /// Angular radius of the Sun in radians
+    sun_radius: f64,
+    /// Angular radius of the Moon in radians
+    moon_radius: f64,
+    /// Angle between the disk centers in radians
+    separation: f64,
+    /// Altitude of the Sun in degrees
+    sun_altitude_deg: f64,
+}
+
+impl Disks {
+    fn at(
+        ephemeris: &Ephemeris,
+        location: &GeographicLocation,
+        t: &Time,
+    ) -> Result<Self, PlanetError> {
+        let observer = location.at(ephemeris, t)?;
+        let sun = observer.observe(Body::Sun)?.apparent(ephemeris)?;
+        let moon = observer.observe(Body::Moon)?.apparent(ephemeris)?;
+
+        let angular_radius =
+            |body: Body, distance_au: f64| (body.radii_km().0 / (distance_au * AU_KM)).asin();
+        Ok(Self {
+            sun_radius: angular_radius(Body::Sun, sun.position.distance()),
+            moon_radius: angular_radius(Body::Moon, moon.position.distance()),
+            separation: sun.position.vector().angle(moon.position.vector()),
+            sun_altitude_deg: sun.altaz()?.0,
+        })
+    }
+
+    /// 0 outside eclipse, 1 in the partial phase, 2 in the central phase
+    fn stage(&self) -> u8 {
+        if self.separation >= self.sun_radius + self.moon_radius {
+            0
+        } else if self.separation > (self.sun_radius - self.moon_radius).abs() {
+            1
+        } else {
+            2
+        }
+    }
+
+    /// Fraction of the Sun's diameter covered
+    fn magnitude(&self) -> f64 {
+        (self.sun_radius + self.moon_radius - self.separation) / (2.0 * self.sun_radius)
+    }
+
+    /// Fraction of the Sun's disk area covered
+    fn obscuration(&self) -> f64 {
+        let (r_sun, r_moon, d) = (self.sun_radius, self.moon_radius, self.separation);
+        if d >= r_sun + r_moon {
+            return 0.0;
+        }
+        if d <= (r_sun - r_moon).abs() {
+            return (r_moon.min(r_sun) / r_sun).powi(2);
+        }
+        // Area of the lens where the disks overlap
+        let moon_part = r_moon
+            * r_moon
+            * ((d * d + r_moon * r_moon - r_sun * r_sun) / (2.0 * d * r_moon)).acos();
+        let sun_part =
+            r_sun * r_sun * ((d * d + r_sun * r_sun - r_moon * r_moon) / (2.0 * d * r_sun)).acos();
+        let kite = 0.5
+            * ((-d + r_moon + r_sun)
+                * (d + r_moon - r_sun)
+                * (d - r_moon + r_sun)
+                * (d + r_moon + r_sun))
+                .sqrt();
+        (moon_part + sun_part - kite) / (PI * r_sun * r_sun)
+    }
+}
+
+/// Solar eclipses seen from `location` with maximum between `start` and
+/// `end`
+///
+/// Eclipses are reported whether or not the Sun is up; check
+/// [`LocalSolarEclipse::is_visible`]. Dates the ephemeris cannot serve are
+/// skipped.
+///
+/// # Examples
+///
+/// ```
+/// use starfield::almanac::local_solar_eclipses;
+/// use starfield::observers::GeographicLocation;
+/// use starfield::planetlib::Ephemeris;
+/// use starfield::time::Timescale;
+///
+/// let ts = Timescale::default();
+/// let dallas = GeographicLocation::new(32.78, -96.80, 140.0);
+/// let eclipses = local_solar_eclipses(
+///     &Ephemeris::new(),
+///     &dallas,
+///     &ts.utc((2024, 4, 1)),
+///     &ts.utc((2024, 4, 30)),
+/// );
+/// for e in &eclipses {
+///     println!("{:?} eclipse, magnitude {:.3}", e.kind, e.magnitude);
+/// }
+/// ```
+pub fn local_solar_eclipses(
+    ephemeris: &Ephemeris,
+    location: &GeographicLocation,
+    start: &Time,
+    end: &Time,
+) -> Vec<LocalSolarEclipse> {
+    let ts = start.timescale();
+    let search_start = ts.tt_jd(start.tt() - ECLIPSE_WINDOW_DAYS, None);
+    let search_end = ts.tt_jd(end.tt() + ECLIPSE_WINDOW_DAYS, None);
+
+    find_new_and_full_moons(ephemeris, &search_start, &search_end)
+        .into_iter()
+        .filter(|(_, kind)| *kind == MoonPhaseKind::New)
+        .filter_map(|(new_moon, _)| circumstances(ephemeris, location, &new_moon))
+        .filter(|eclipse| (start.tt()..=end.tt()).contains(&eclipse.maximum.tt()))
+        .collect()
+}
+
+/// Circumstances of the eclipse near `new_moon`, if there is one
+fn circumstances(
+    ephemeris: &Ephemeris,
+    location: &GeographicLocation,
+    new_moon: &Time,
+) -> Option<LocalSolarEclipse> {
+    let ts = new_moon.timescale();
+    let window_start = ts.tt_jd(new_moon.tt() - ECLIPSE_WINDOW_DAYS, None);
+    let window_end = ts.tt_jd(new_moon.tt() + ECLIPSE_WINDOW_DAYS, None);
+    let stage = |t: &Time| Disks::at(ephemeris, location, t).map_or(0, |d| d.stage());
+
+    let mut contacts = [None, None, None, None];
+    let mut previous = stage(&window_start);
+    for (t, current) in find_discrete(&window_start, &window_end, CONTACT_STEP_DAYS, stage) {
+        let slot = match (previous, current) {
+            (0, _) => 0,
+            (1, 2) => 1,
+            (2, _) => 2,
+            _ => 3,
+        };
+        contacts[slot].get_or_insert(t);
+        previous = current;
+    }
+    let [Some(first), second, third, Some(fourth)] = contacts else {
+        return None;
+    };
+
+    let separation = |jd: f64| {
+        Disks::at(ephemeris, location, &ts.tt_jd(jd, None)).map_or(f64::MAX, |d| d.separation)
+    };
+    let maximum = ts.tt_jd(minimize(first.tt(), fourth.tt(), separation), None);
+    let disks = Disks::at(ephemeris, location, &maximum).ok()?;
+
+    let kind = match disks.stage() {
+        2 if disks.moon_radius >= disks.sun_radius => SolarEclipseKind::Total,
+        2 => SolarEclipseKind::Annular,
+        _ => SolarEclipseKind::Partial,
+    };
+
+    // An error in the Earth's rotation angle moves the site under the Moon
+    let accuracy = sun_moon_event_accuracy(ephemeris).with(
+        ErrorSource::DeltaT,
+        delta_t_error_seconds(&maximum)
+            * PARALLAX_DRIFT_ARCSEC_PER_S
+            * (location.latitude_deg * DEG2RAD).cos(),
+    );
+
+    Some(LocalSolarEclipse {
+        kind,
+        first_contact: first,
+        second_contact: second,
+        maximum,
+        third_contact: third,
+        fourth_contact: fourth,
+        magnitude: disks.magnitude(),
+        obscuration: disks.obscuration(),
+        sun_altitude_deg: disks.sun_altitude_deg,
+        accuracy,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::time::Timescale;
+    use approx::assert_relative_eq;
+
+    fn minutes_between(a: &Time, b: &Time) -> f64 {
+        (a.tt() - b.tt()).abs() * 1_440.0
+    }
+
+    #[test]
+    fn test_total_eclipse_2024_from_dallas() {
+        let ts = Timescale::default();
+        let eph = Ephemeris::new();
+        let dallas = GeographicLocation::new(32.78, -96.80, 140.0);
+
+        let eclipses =
+            local_solar_eclipses(&eph, &dallas, &ts.utc((2024, 3, 1)), &ts.utc((2024, 5, 1)));
+        assert_eq!(eclipses.len(), 1);
+        let e = &eclipses[0];
+        assert_eq!(e.kind, SolarEclipseKind::Total);
+        assert!(e.is_visible());
+        assert_relative_eq!(e.obscuration, 1.0);
+
+        // Contacts from NASA's local circumstances for Dallas
+        assert!(minutes_between(&e.first_contact, &ts.utc((2024, 4, 8, 17, 23, 0.0))) < 5.0);
+        assert!(minutes_between(&e.maximum, &ts.utc((2024, 4, 8, 18, 42, 30.0))) < 5.0);
+        assert!(minutes_between(&e.fourth_contact, &ts.utc((2024, 4, 8, 20, 2, 40.0))) < 5.0);
+        let totality = e.central_phase().unwrap();
+        assert!(totality.duration_hours() * 60.0 < 5.0);
+        assert_relative_eq!(e.partial_phase().duration_hours(), 2.66, epsilon = 0.1);
+        assert_eq!(
+            e.accuracy.dominant(),
+            Some(crate::accuracy::ErrorSource::Ephemeris)
+        );
+        assert!(e.accuracy.timing_seconds().unwrap() < 300.0);
+    }
+
+    #[test]
+    fn test_partial_and_annular_eclipses() {
+        let ts = Timescale::default();
+        let eph = Ephemeris::new();
+
+        // New York saw about 90% of the Sun covered on 2024-04-08
+        let new_york = GeographicLocation::new(40.71, -74.01, 10.0);
+        let eclipses = local_solar_eclipses(
+            &eph,
+            &new_york,
+            &ts.utc((2024, 4, 1)),
+            &ts.utc((2024, 4, 30)),
+        );
+        assert_eq!(eclipses.len(), 1);
+        assert_eq!(eclipses[0].kind, SolarEclipseKind::Partial);
+        assert!(eclipses[0].central_phase().is_none());
+        assert_relative_eq!(eclipses[0].magnitude, 0.90, epsilon = 0.02);
+        assert!((0.85..0.95).contains(&eclipses[0].obscuration));
+
+        // Albuquerque was on the annular path on 2023-10-14
+        let albuquerque = GeographicLocation::new(35.08, -106.65, 1_619.0);
+        let eclipses = local_solar_eclipses(
+            &eph,
+            &albuquerque,
+            &ts.utc((2023, 10, 1)),
+            &ts.utc((2023, 10, 31)),
+        );
+        assert_eq!(eclipses.len(), 1);
+        assert_eq!(eclipses[0].kind, SolarEclipseKind::Annular);
+        assert!(eclipses[0].obscuration < 1.0);
+
+        // No eclipse in a month without one
+        let none = local_solar_eclipses(
+            &eph,
+            &new_york,
+            &ts.utc((2024, 6, 1)),
+            &ts.utc((2024, 7, 1)),
+        );
+        assert!(none.is_empty());
+    }
+}
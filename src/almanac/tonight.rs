@@ -0,0 +1,394 @@
+//! "What's up tonight": one evening's sky summarised for an observer
+//!
+//! [`whats_up_tonight`] ties the almanac searches together. It finds
+//! astronomical dusk and dawn and lists the planets above the horizon at
+//! dusk, the Moon's phase, rising and setting, the visible passes of the
+//! given satellites, and the deep-sky objects of a [`FeatureCatalog`] that
+//! stand high enough to observe well:
+//!
+//! ```
+//! use starfield::almanac::{whats_up_tonight, TonightCriteria};
+//! use starfield::catalogs::features::FeatureCatalog;
+//! use starfield::observers::GeographicLocation;
+//! use starfield::planetlib::Ephemeris;
+//!
+//! let site = GeographicLocation::new(34.2, -118.2, 1700.0);
+//! let tonight = whats_up_tonight(
+//!     &Ephemeris::new(),
+//!     &site,
+//!     (2024, 10, 5),
+//!     &[],
+//!     &FeatureCatalog::default(),
+//!     &TonightCriteria::default(),
+//! );
+//! for planet in &tonight.planets {
+//!     println!("{} at {:.0}° altitude", planet.body.name(), planet.altitude_deg);
+//! }
+//! assert!(tonight.dusk.is_some());
+//! ```
+//!
+//! The evening is taken to run from local mean noon on the given date to
+//! local mean noon on the next, so the date is the observer's calendar date
+//! whatever their longitude.
+
+use super::rise_set::{find_risings_and_settings, RiseSetHorizon};
+use super::satellite_passes::{find_satellite_passes, SatellitePass};
+use super::{altitude, geocentric_position, moon_phase, MoonPhase};
+use crate::catalogs::features::{FeatureCatalog, FeatureType};
+use crate::constants::DEG2RAD;
+use crate::observers::GeographicLocation;
+use crate::planetlib::{Body, Ephemeris};
+use crate::satellites::EarthSatellite;
+use crate::time::{Time, TimeDelta, Timescale};
+use crate::tracking::TrackingTarget;
+use nalgebra::Vector3;
+
+/// Planets looked for, in order from the Sun
+const PLANETS: [Body; 7] = [
+    Body::Mercury,
+    Body::Venus,
+    Body::Mars,
+    Body::Jupiter,
+    Body::Saturn,
+    Body::Uranus,
+    Body::Neptune,
+];
+
+/// Distance in AU at which fixed objects are placed, as for catalog stars
+const FIXED_DISTANCE_AU: f64 = 1e9;
+
+/// Limits deciding what makes the summary
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TonightCriteria {
+    /// Sun altitude in degrees that defines dusk and dawn (default -18,
+    /// astronomical twilight)
+    pub twilight_altitude_deg: f64,
+    /// Lowest altitude in degrees at which a planet is listed (default 0)
+    pub min_planet_altitude_deg: f64,
+    /// Lowest altitude in degrees at which a deep-sky object is listed
+    /// (default 30)
+    pub min_feature_altitude_deg: f64,
+    /// Lowest culmination altitude in degrees of a satellite pass
+    /// (default 10)
+    pub min_satellite_altitude_deg: f64,
+    /// Sun altitude in degrees below which a sunlit satellite can be seen
+    /// (default -6, civil twilight)
+    pub satellite_sun_altitude_deg: f64,
+}
+
+impl Default for TonightCriteria {
+    fn default() -> Self {
+        Self {
+            twilight_altitude_deg: -18.0,
+            min_planet_altitude_deg: 0.0,
+            min_feature_altitude_deg: 30.0,
+            min_satellite_altitude_deg: 10.0,
+            satellite_sun_altitude_deg: -6.0,
+        }
+    }
+}
+
+impl TonightCriteria {
+    /// Set the Sun altitude defining dusk and dawn in degrees
+    pub fn with_twilight_altitude(mut self, altitude_deg: f64) -> Self {
+        self.twilight_altitude_deg = altitude_deg;
+        self
+    }
+
+    /// Set the lowest altitude of a listed planet in degrees
+    pub fn with_min_planet_altitude(mut self, altitude_deg: f64) -> Self {
+        self.min_planet_altitude_deg = altitude_deg;
+        self
+    }
+
+    /// Set the lowest altitude of a listed deep-sky object in degrees
+    pub fn with_min_feature_altitude(mut self, altitude_deg: f64) -> Self {
+        self.min_feature_altitude_deg = altitude_deg;
+        self
+    }
+
+    /// Set the lowest culmination altitude of a listed satellite pass in
+    /// degrees
+    pub fn with_min_satellite_altitude(mut self, altitude_deg: f64) -> Self {
+        self.min_satellite_altitude_deg = altitude_deg;
+        self
+    }
+
+    /// Set the Sun altitude below which sunlit satellites count as visible
+    /// in degrees
+    pub fn with_satellite_sun_altitude(mut self, altitude_deg: f64) -> Self {
+        self.satellite_sun_altitude_deg = altitude_deg;
+        self
+    }
+}
+
+/// A planet above the horizon
+#[derive(Debug, Clone)]
+pub struct PlanetSighting {
+    /// Which planet
+    pub body: Body,
+    /// Refraction-free altitude in degrees
+    pub altitude_deg: f64,
+    /// Azimuth in degrees east of north
+    pub azimuth_deg: f64,
+    /// Visual magnitude, when the physical model has one
+    pub magnitude: Option<f64>,
+}
+
+/// A deep-sky object high in the sky
+#[derive(Debug, Clone)]
+pub struct FeatureSighting {
+    /// Name from the feature catalog
+    pub name: String,
+    /// Kind of object
+    pub feature_type: FeatureType,
+    /// Refraction-free altitude in degrees
+    pub altitude_deg: f64,
+    /// Azimuth in degrees east of north
+    pub azimuth_deg: f64,
+}
+
+/// A visible pass of a satellite
+#[derive(Debug, Clone)]
+pub struct SatelliteSighting {
+    /// Satellite name from its element set, if it has one
+    pub name: Option<String>,
+    /// NORAD catalog number
+    pub catalog_number: u32,
+    /// The pass itself
+    pub pass: SatellitePass,
+}
+
+/// One evening's sky
+#[derive(Debug, Clone)]
+pub struct TonightSummary {
+    /// Start of the night: the Sun sinking through the twilight altitude,
+    /// or `None` when it never gets dark
+    pub dusk: Option<Time>,
+    /// End of the night: the Sun climbing through the twilight altitude
+    pub dawn: Option<Time>,
+    /// When the planets and deep-sky objects were placed: dusk, or local
+    /// midnight when there is none
+    pub observed_at: Time,
+    /// Planets above the altitude limit, in order from the Sun
+    pub planets: Vec<PlanetSighting>,
+    /// The Moon's phase at `observed_at`
+    pub moon_phase: MoonPhase,
+    /// First moonrise of the evening, if any
+    pub moonrise: Option<Time>,
+    /// First moonset of the evening, if any
+    pub moonset: Option<Time>,
+    /// Sunlit passes against a darkened sky, in time order
+    pub satellite_passes: Vec<SatelliteSighting>,
+    /// Clusters, nebulae and galaxies above the altitude limit, highest
+    /// first
+    pub features: Vec<FeatureSighting>,
+}
+
+/// Summarise the night beginning on the evening of `date` at `location`
+///
+/// Satellites whose elements cannot be propagated over the night (decayed
+/// or badly stale element sets) are left out rather than failing the whole
+/// summary. Brightness is not modelled; a pass is listed when the satellite
+/// is sunlit at culmination while the Sun is below
+/// [`TonightCriteria::satellite_sun_altitude_deg`].
+pub fn whats_up_tonight(
+    ephemeris: &Ephemeris,
+    location: &GeographicLocation,
+    date: (i32, u32, u32),
+    satellites: &[EarthSatellite],
+    features: &FeatureCatalog,
+    criteria: &TonightCriteria,
+) -> TonightSummary {
+    let ts = Timescale::builtin();
+    let (year, month, day) = date;
+    let start =
+        ts.utc((year, month, day, 12, 0, 0.0)) - TimeDelta::days(location.longitude_deg / 360.0);
+    let end = &start + TimeDelta::days(1.0);
+
+    let sun = TrackingTarget::Body(Body::Sun);
+    let twilight = RiseSetHorizon::new(criteria.twilight_altitude_deg);
+    let sun_events = find_risings_and_settings(ephemeris, location, &sun, &start, &end, &twilight);
+    let dusk = sun_events
+        .iter()
+        .find(|e| !e.rising)
+        .map(|e| e.time.clone());
+    let dawn = sun_events
+        .iter()
+        .find(|e| e.rising && dusk.as_ref().is_none_or(|d| e.time.tt() > d.tt()))
+        .map(|e| e.time.clone());
+    let observed_at = dusk
+        .clone()
+        .unwrap_or_else(|| &start + TimeDelta::days(0.5));
+
+    let planets = PLANETS
+        .into_iter()
+        .filter_map(|body| {
+            let geocentric = geocentric_position(ephemeris, body, &observed_at);
+            let (altitude_deg, azimuth_deg) = location.altaz(&geocentric, &observed_at);
+            (altitude_deg >= criteria.min_planet_altitude_deg).then(|| PlanetSighting {
+                body,
+                altitude_deg,
+                azimuth_deg,
+                magnitude: ephemeris
+                    .physical_ephemeris(body, &observed_at, Body::Earth)
+                    .ok()
+                    .map(|p| p.magnitude),
+            })
+        })
+        .collect();
+
+    let moon = TrackingTarget::Body(Body::Moon);
+    let moon_horizon = RiseSetHorizon::for_target(&moon);
+    let moon_events =
+        find_risings_and_settings(ephemeris, location, &moon, &start, &end, &moon_horizon);
+    let moonrise = moon_events
+        .iter()
+        .find(|e| e.rising)
+        .map(|e| e.time.clone());
+    let moonset = moon_events
+        .iter()
+        .find(|e| !e.rising)
+        .map(|e| e.time.clone());
+
+    let mut satellite_passes: Vec<SatelliteSighting> = satellites
+        .iter()
+        .filter_map(|satellite| {
+            let passes = find_satellite_passes(
+                ephemeris,
+                satellite,
+                location,
+                &start,
+                &end,
+                criteria.min_satellite_altitude_deg,
+            )
+            .ok()?;
+            Some(
+                passes
+                    .into_iter()
+                    .filter(|pass| {
+                        pass.culmination.sunlit
+                            && altitude(ephemeris, location, Body::Sun, &pass.culmination.time)
+                                < criteria.satellite_sun_altitude_deg
+                    })
+                    .map(|pass| SatelliteSighting {
+                        name: satellite.name().map(str::to_string),
+                        catalog_number: satellite.tle().catalog_number,
+                        pass,
+                    })
+                    .collect::<Vec<_>>(),
+            )
+        })
+        .flatten()
+        .collect();
+    satellite_passes.sort_by(|a, b| {
+        a.pass
+            .culmination
+            .time
+            .tt()
+            .total_cmp(&b.pass.culmination.time.tt())
+    });
+
+    let mut features: Vec<FeatureSighting> = features
+        .all_features()
+        .into_iter()
+        .filter(|f| is_deep_sky(&f.feature_type))
+        .filter_map(|f| {
+            let (sin_dec, cos_dec) = (f.dec_deg * DEG2RAD).sin_cos();
+            let (sin_ra, cos_ra) = (f.ra_deg * DEG2RAD).sin_cos();
+            let direction = Vector3::new(cos_dec * cos_ra, cos_dec * sin_ra, sin_dec);
+            let (altitude_deg, azimuth_deg) =
+                location.altaz(&(direction * FIXED_DISTANCE_AU), &observed_at);
+            (altitude_deg >= criteria.min_feature_altitude_deg).then(|| FeatureSighting {
+                name: f.name.clone(),
+                feature_type: f.feature_type.clone(),
+                altitude_deg,
+                azimuth_deg,
+            })
+        })
+        .collect();
+    features.sort_by(|a, b| b.altitude_deg.total_cmp(&a.altitude_deg));
+
+    TonightSummary {
+        moon_phase: moon_phase(ephemeris, &observed_at),
+        dusk,
+        dawn,
+        observed_at,
+        planets,
+        moonrise,
+        moonset,
+        satellite_passes,
+        features,
+    }
+}
+
+/// Whether a feature is a deep-sky object rather than a star or a
+/// constellation
+fn is_deep_sky(feature_type: &FeatureType) -> bool {
+    matches!(
+        feature_type,
+        FeatureType::OpenCluster
+            | FeatureType::GlobularCluster
+            | FeatureType::Nebula
+            | FeatureType::Galaxy
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_summer_evening_at_mid_latitude() {
+        let ephemeris = Ephemeris::new();
+        // Mount Wilson
+        let site = GeographicLocation::new(34.2239, -118.0572, 1742.0);
+        let tonight = whats_up_tonight(
+            &ephemeris,
+            &site,
+            (2024, 7, 4),
+            &[],
+            &FeatureCatalog::default(),
+            &TonightCriteria::default(),
+        );
+
+        // Astronomical dusk falls around 04:30 UTC, dawn around 11:10 UTC
+        let dusk = tonight.dusk.as_ref().unwrap();
+        let dawn = tonight.dawn.as_ref().unwrap();
+        assert!(dawn.tt() > dusk.tt());
+        let night_hours = (dawn.tt() - dusk.tt()) * 24.0;
+        assert!((6.0..8.0).contains(&night_hours), "{}", night_hours);
+        assert!(altitude(&ephemeris, &site, Body::Sun, dusk) < -17.9);
+
+        // The Moon was two days before new and rose before dawn
+        assert!(tonight.moon_phase.illuminated_fraction < 0.1);
+        assert!(tonight.moonrise.is_some());
+
+        for planet in &tonight.planets {
+            assert!(planet.altitude_deg >= 0.0);
+        }
+        for pair in tonight.features.windows(2) {
+            assert!(pair[0].altitude_deg >= pair[1].altitude_deg);
+        }
+        assert!(tonight
+            .features
+            .iter()
+            .all(|f| f.altitude_deg >= 30.0 && is_deep_sky(&f.feature_type)));
+        assert!(tonight.satellite_passes.is_empty());
+    }
+
+    #[test]
+    fn test_midnight_sun_has_no_dusk() {
+        let site = GeographicLocation::new(78.2, 15.6, 0.0);
+        let tonight = whats_up_tonight(
+            &Ephemeris::new(),
+            &site,
+            (2024, 6, 21),
+            &[],
+            &FeatureCatalog::default(),
+            &TonightCriteria::default(),
+        );
+        assert!(tonight.dusk.is_none());
+        assert!(tonight.dawn.is_none());
+    }
+}
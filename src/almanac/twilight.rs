@@ -0,0 +1,211 @@
+//! Day, night and the three twilights
+//!
+//! As in skyfield's `dark_twilight_day`, a moment is classified by the
+//! Sun's refraction-free altitude: day while its upper limb is above the
+//! horizon (-0.8333°), then civil, nautical and astronomical twilight down
+//! to -6°, -12° and -18°, and night below that.
+//!
+//! ```
+//! use starfield::almanac::{dark_twilight_day, find_twilight_transitions, Twilight};
+//! use starfield::observers::GeographicLocation;
+//! use starfield::planetlib::Ephemeris;
+//! use starfield::Timescale;
+//!
+//! let ts = Timescale::default();
+//! let eph = Ephemeris::new();
+//! let greenwich = GeographicLocation::new(51.4769, 0.0, 46.0);
+//!
+//! let noon = ts.utc((2024, 3, 20, 12, 0, 0.0));
+//! assert_eq!(dark_twilight_day(&eph, &greenwich, &noon), Twilight::Day);
+//!
+//! // Dusk and dawn each pass through all four boundaries
+//! let events = find_twilight_transitions(
+//!     &eph,
+//!     &greenwich,
+//!     &noon,
+//!     &ts.utc((2024, 3, 21, 12, 0, 0.0)),
+//! );
+//! assert_eq!(events.len(), 8);
+//! ```
+
+use super::altitude;
+use crate::observers::GeographicLocation;
+use crate::planetlib::{Body, Ephemeris};
+use crate::searchlib::find_discrete;
+use crate::time::Time;
+use std::fmt;
+
+/// Sampling interval for the search in days (5 minutes)
+///
+/// Near the poles a twilight can last only minutes, so the step is shorter
+/// than that of the rise and set search.
+const SEARCH_STEP_DAYS: f64 = 5.0 / 1440.0;
+
+/// Sun altitude in degrees at which its upper limb touches the refracted
+/// horizon
+pub const SUNRISE_ALTITUDE_DEG: f64 = -0.8333;
+
+/// How light the sky is, from darkest to brightest
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Twilight {
+    /// Sun more than 18° below the horizon
+    Night,
+    /// Sun between 12° and 18° below the horizon
+    Astronomical,
+    /// Sun between 6° and 12° below the horizon
+    Nautical,
+    /// Sun between 6° below the horizon and sunrise or sunset
+    Civil,
+    /// Sun above the horizon
+    Day,
+}
+
+impl Twilight {
+    /// Classify a Sun altitude in degrees
+    pub fn from_sun_altitude(altitude_deg: f64) -> Self {
+        if altitude_deg > SUNRISE_ALTITUDE_DEG {
+            Twilight::Day
+        } else if altitude_deg > -6.0 {
+            Twilight::Civil
+        } else if altitude_deg > -12.0 {
+            Twilight::Nautical
+        } else if altitude_deg > -18.0 {
+            Twilight::Astronomical
+        } else {
+            Twilight::Night
+        }
+    }
+
+    /// Name as skyfield's `TWILIGHTS` gives it
+    pub fn name(&self) -> &'static str {
+        match self {
+            Twilight::Night => "Night",
+            Twilight::Astronomical => "Astronomical twilight",
+            Twilight::Nautical => "Nautical twilight",
+            Twilight::Civil => "Civil twilight",
+            Twilight::Day => "Day",
+        }
+    }
+}
+
+impl fmt::Display for Twilight {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+/// A change from one kind of sky to another
+#[derive(Debug, Clone)]
+pub struct TwilightEvent {
+    /// When the Sun crosses the boundary
+    pub time: Time,
+    /// Sky from then on
+    pub twilight: Twilight,
+    /// Whether the sky is growing lighter, as at dawn
+    pub brightening: bool,
+}
+
+/// Day, night or which twilight it is at `t` for an observer at `location`
+pub fn dark_twilight_day(
+    ephemeris: &Ephemeris,
+    location: &GeographicLocation,
+    t: &Time,
+) -> Twilight {
+    Twilight::from_sun_altitude(altitude(ephemeris, location, Body::Sun, t))
+}
+
+/// Find every change between day, twilight and night from `start` to `end`
+pub fn find_twilight_transitions(
+    ephemeris: &Ephemeris,
+    location: &GeographicLocation,
+    start: &Time,
+    end: &Time,
+) -> Vec<TwilightEvent> {
+    let mut previous = dark_twilight_day(ephemeris, location, start);
+    find_discrete(start, end, SEARCH_STEP_DAYS, |t| {
+        dark_twilight_day(ephemeris, location, t)
+    })
+    .into_iter()
+    .map(|(time, twilight)| {
+        let brightening = twilight > previous;
+        previous = twilight;
+        TwilightEvent {
+            time,
+            twilight,
+            brightening,
+        }
+    })
+    .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::time::Timescale;
+
+    #[test]
+    fn test_classification() {
+        assert_eq!(Twilight::from_sun_altitude(10.0), Twilight::Day);
+        assert_eq!(Twilight::from_sun_altitude(-0.5), Twilight::Day);
+        assert_eq!(Twilight::from_sun_altitude(-3.0), Twilight::Civil);
+        assert_eq!(Twilight::from_sun_altitude(-9.0), Twilight::Nautical);
+        assert_eq!(Twilight::from_sun_altitude(-15.0), Twilight::Astronomical);
+        assert_eq!(Twilight::from_sun_altitude(-40.0), Twilight::Night);
+        assert!(Twilight::Night < Twilight::Civil && Twilight::Civil < Twilight::Day);
+        assert_eq!(Twilight::Nautical.to_string(), "Nautical twilight");
+    }
+
+    #[test]
+    fn test_equinox_evening_at_greenwich() {
+        let ts = Timescale::default();
+        let eph = Ephemeris::new();
+        let greenwich = GeographicLocation::new(51.4769, 0.0, 46.0);
+        let events = find_twilight_transitions(
+            &eph,
+            &greenwich,
+            &ts.utc((2024, 3, 20, 12, 0, 0.0)),
+            &ts.utc((2024, 3, 21, 12, 0, 0.0)),
+        );
+
+        let kinds: Vec<_> = events.iter().map(|e| (e.twilight, e.brightening)).collect();
+        assert_eq!(
+            kinds,
+            [
+                (Twilight::Civil, false),
+                (Twilight::Nautical, false),
+                (Twilight::Astronomical, false),
+                (Twilight::Night, false),
+                (Twilight::Astronomical, true),
+                (Twilight::Nautical, true),
+                (Twilight::Civil, true),
+                (Twilight::Day, true),
+            ]
+        );
+
+        // Sunset about 18:13 UTC, civil dusk half an hour later
+        let sunset = events[0].time.utc_calendar().unwrap();
+        assert_eq!((sunset.hour, sunset.minute / 10), (18, 1));
+        let civil_minutes = (events[1].time.tt() - events[0].time.tt()) * 1440.0;
+        assert!((30.0..40.0).contains(&civil_minutes), "{}", civil_minutes);
+    }
+
+    #[test]
+    fn test_midsummer_has_no_night_in_london() {
+        // The Sun gets no lower than -15° at midnight in late June
+        let ts = Timescale::default();
+        let eph = Ephemeris::new();
+        let greenwich = GeographicLocation::new(51.4769, 0.0, 46.0);
+        let events = find_twilight_transitions(
+            &eph,
+            &greenwich,
+            &ts.utc((2024, 6, 21, 12, 0, 0.0)),
+            &ts.utc((2024, 6, 22, 12, 0, 0.0)),
+        );
+        assert!(events.iter().all(|e| e.twilight >= Twilight::Astronomical));
+        let midnight = ts.utc((2024, 6, 22, 0, 0, 0.0));
+        assert_eq!(
+            dark_twilight_day(&eph, &greenwich, &midnight),
+            Twilight::Astronomical
+        );
+    }
+}
@@ -0,0 +1,59 @@
+//! Maxima and minima of periodic variable stars
+//!
+//! Extremes follow from a star's GCVS light elements, so unlike the other
+//! almanac searches no sampling is needed: each cycle's maximum and minimum
+//! fall at fixed phases.
+
+use crate::catalogs::gcvs::{Extremum, GcvsEntry};
+use crate::time::Time;
+
+/// Maxima and minima of `star` between `start` and `end`, in time order
+///
+/// Stars without a period or epoch have none.
+pub fn find_variable_star_extrema(
+    star: &GcvsEntry,
+    start: &Time,
+    end: &Time,
+) -> Vec<(Time, Extremum)> {
+    let ts = start.timescale();
+    star.extrema_between(start.tt(), end.tt())
+        .into_iter()
+        .map(|(jd, kind)| (ts.tt_jd(jd, None), kind))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::catalogs::gcvs::tests::SAMPLE;
+    use crate::catalogs::GcvsCatalog;
+    use crate::time::Timescale;
+
+    #[test]
+    fn test_algol_minima_in_a_month() {
+        let ts = Timescale::default();
+        let catalog = GcvsCatalog::parse(SAMPLE).unwrap();
+        let algol = catalog.get("bet Per").unwrap();
+        let start = ts.utc((2024, 11, 1));
+        let end = ts.utc((2024, 12, 1));
+
+        let events = find_variable_star_extrema(algol, &start, &end);
+        let primary: Vec<&Time> = events
+            .iter()
+            .filter(|(t, kind)| {
+                *kind == Extremum::Minimum
+                    && algol.phase(t).unwrap().min(1.0 - algol.phase(t).unwrap()) < 1e-6
+            })
+            .map(|(t, _)| t)
+            .collect();
+        // 30 days at 2.867 days per cycle
+        assert!((10..=11).contains(&primary.len()), "{}", primary.len());
+        assert!(events.windows(2).all(|w| w[0].0.tt() < w[1].0.tt()));
+        assert!(events
+            .iter()
+            .all(|(t, _)| start.tt() <= t.tt() && t.tt() < end.tt()));
+
+        let t_crb = catalog.get("T CrB").unwrap();
+        assert!(find_variable_star_extrema(t_crb, &start, &end).is_empty());
+    }
+}
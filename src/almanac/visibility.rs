@@ -0,0 +1,91 @@
+//! Windows when a target is above an observer's horizon
+//!
+//! The observer is a [`Trajectory`], so the windows can be found for an
+//! aircraft or ship as readily as for a fixed site: the altitude is
+//! evaluated from wherever the observer is at each moment.
+
+use super::{target_altitude, windows_where, TimeWindow};
+use crate::observers::Trajectory;
+use crate::planetlib::Ephemeris;
+use crate::time::Time;
+use crate::tracking::TrackingTarget;
+
+/// Sampling interval for the search in days (5 minutes)
+const SEARCH_STEP_DAYS: f64 = 5.0 / 1440.0;
+
+/// Find the windows between `start` and `end` in which `target` stands at
+/// least `min_altitude_deg` above the horizon of `observer`
+///
+/// Altitudes are topocentric and unrefracted. Windows are located to about
+/// a millisecond and clipped to the range; the observer is sampled every
+/// five minutes, so briefer windows can be missed.
+///
+/// # Examples
+///
+/// ```
+/// use starfield::almanac::visibility_windows;
+/// use starfield::observers::{GeographicLocation, Trajectory};
+/// use starfield::planetlib::{Body, Ephemeris};
+/// use starfield::time::Timescale;
+/// use starfield::tracking::TrackingTarget;
+///
+/// let ts = Timescale::default();
+/// // A flight chasing the night westward over the Pacific
+/// let flight = Trajectory::sampled(vec![
+///     (ts.utc((2024, 5, 1, 10, 0, 0.0)), GeographicLocation::new(35.0, 140.0, 11_000.0)),
+///     (ts.utc((2024, 5, 1, 20, 0, 0.0)), GeographicLocation::new(37.0, -122.0, 11_000.0)),
+/// ])?;
+///
+/// let windows = visibility_windows(
+///     &Ephemeris::new(),
+///     &flight,
+///     &TrackingTarget::Body(Body::Moon),
+///     10.0,
+///     &ts.utc((2024, 5, 1, 10, 0, 0.0)),
+///     &ts.utc((2024, 5, 1, 20, 0, 0.0)),
+/// );
+/// # Ok::<(), starfield::StarfieldError>(())
+/// ```
+pub fn visibility_windows(
+    ephemeris: &Ephemeris,
+    observer: &Trajectory,
+    target: &TrackingTarget,
+    min_altitude_deg: f64,
+    start: &Time,
+    end: &Time,
+) -> Vec<TimeWindow> {
+    windows_where(start, end, SEARCH_STEP_DAYS, |t| {
+        target_altitude(ephemeris, &observer.location_at(t), target, t) >= min_altitude_deg
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::observers::GeographicLocation;
+    use crate::planetlib::Body;
+    use crate::time::Timescale;
+
+    #[test]
+    fn test_flying_west_lengthens_the_day() {
+        let ts = Timescale::default();
+        let eph = Ephemeris::new();
+        let sun = TrackingTarget::Body(Body::Sun);
+        let start = ts.utc((2024, 3, 20, 0, 0, 0.0));
+        let end = ts.utc((2024, 3, 21, 12, 0, 0.0));
+
+        let home = GeographicLocation::new(0.0, 0.0, 0.0);
+        let ground = visibility_windows(&eph, &home.into(), &sun, 0.0, &start, &end);
+        assert!((11.8..12.4).contains(&ground[0].duration_hours()));
+
+        // Flying west at 10° an hour from dawn, the Sun crosses the sky at a
+        // third of its usual pace
+        let dawn = ground[0].start.tt();
+        let flight = Trajectory::from_fn(move |t| {
+            let hours = ((t.tt() - dawn) * 24.0).max(0.0);
+            GeographicLocation::new(0.0, -10.0 * hours, 10_000.0)
+        });
+        let aloft = visibility_windows(&eph, &flight, &sun, 0.0, &start, &end);
+        assert!(aloft[0].duration_hours() > 24.0, "{:?}", aloft);
+    }
+}
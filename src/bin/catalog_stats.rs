@@ -7,7 +7,7 @@ use std::io::{self, Write};
 use std::path::Path;
 
 use starfield::catalogs::hipparcos::HipparcosEntry;
-use starfield::catalogs::{BinaryCatalog, MinimalStar, StarCatalog};
+use starfield::catalogs::{BinaryCatalog, CatalogMetadata, MinimalStar, StarCatalog};
 use starfield::Loader;
 
 /// Print a simple progress bar
@@ -38,12 +38,16 @@ fn filter_and_save<P: AsRef<Path>>(
         output_path.as_ref().display()
     );
 
-    // Create description for the binary catalog
-    let desc = format!(
-        "Hipparcos filtered catalog: magnitude <= {}, created on {}",
-        magnitude_limit,
-        chrono::Local::now().format("%Y-%m-%d")
-    );
+    // Record where the binary catalog came from
+    let metadata = CatalogMetadata::new("Hipparcos", "ESA 1997")
+        .with_epoch(1991.25)
+        .with_band("V")
+        .with_magnitude_limit(magnitude_limit)
+        .with_description(&format!(
+            "Hipparcos filtered catalog: magnitude <= {}, created on {}",
+            magnitude_limit,
+            chrono::Local::now().format("%Y-%m-%d")
+        ));
 
     // Filter stars and add to binary catalog
     let mut count = 0;
@@ -60,7 +64,7 @@ fn filter_and_save<P: AsRef<Path>>(
     }
 
     // Create the catalog from collected stars
-    let binary_catalog = BinaryCatalog::from_stars(filtered_stars, &desc);
+    let binary_catalog = BinaryCatalog::from_stars_with_metadata(filtered_stars, metadata);
 
     // Save the catalog
     binary_catalog.save(output_path)?;
@@ -0,0 +1,93 @@
+//! Tool to precompute a compact Chebyshev ephemeris table
+//!
+//! Samples the ephemeris (a JPL kernel if given, otherwise whatever the
+//! loader finds) between two TDB Julian dates and writes an
+//! `EphemerisTable` that can be embedded with `include_bytes!`.
+
+use std::env;
+
+use starfield::planetlib::{Body, EphemerisTable, TableConfig};
+use starfield::Loader;
+
+fn print_usage(program: &str) {
+    println!(
+        "Usage: {} --start JD --end JD --output PATH [--kernel PATH] [--tolerance KM] [--degree N]",
+        program
+    );
+}
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let args: Vec<String> = env::args().collect();
+
+    let mut start = None;
+    let mut end = None;
+    let mut output = None;
+    let mut kernel = None;
+    let mut config = TableConfig::default();
+
+    let mut i = 1;
+    while i < args.len() {
+        let value = args.get(i + 1);
+        match (args[i].as_str(), value) {
+            ("--start", Some(v)) => start = Some(v.parse::<f64>()?),
+            ("--end", Some(v)) => end = Some(v.parse::<f64>()?),
+            ("--output", Some(v)) => output = Some(v.clone()),
+            ("--kernel", Some(v)) => kernel = Some(v.clone()),
+            ("--tolerance", Some(v)) => config = config.with_tolerance_km(v.parse()?),
+            ("--degree", Some(v)) => config = config.with_degree(v.parse()?),
+            _ => {
+                print_usage(&args[0]);
+                return Ok(());
+            }
+        }
+        i += 2;
+    }
+
+    let (Some(start), Some(end), Some(output)) = (start, end, output) else {
+        print_usage(&args[0]);
+        return Ok(());
+    };
+
+    let loader = Loader::new();
+    let ephemeris = match kernel {
+        Some(path) => loader.load_ephemeris_from(path)?,
+        None => loader.load_ephemeris()?,
+    };
+
+    let bodies = [
+        Body::Sun,
+        Body::Mercury,
+        Body::Venus,
+        Body::Earth,
+        Body::Moon,
+        Body::EarthMoonBarycenter,
+        Body::Mars,
+        Body::Jupiter,
+        Body::Saturn,
+        Body::Uranus,
+        Body::Neptune,
+        Body::Pluto,
+    ];
+    let bodies: Vec<Body> = bodies
+        .into_iter()
+        .filter(|&body| ephemeris.get_state(body, start).is_ok())
+        .collect();
+
+    println!(
+        "Fitting {} bodies from JD {} to {} at {} km...",
+        bodies.len(),
+        start,
+        end,
+        config.tolerance_km
+    );
+    let table = EphemerisTable::fit(&ephemeris, &bodies, start, end, &config)?;
+    table.save(&output)?;
+
+    println!(
+        "Wrote {} granules ({} bytes) to {}",
+        table.granule_count(),
+        table.to_bytes().len(),
+        output
+    );
+    Ok(())
+}
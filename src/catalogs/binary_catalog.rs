@@ -2,27 +2,305 @@
 //!
 //! This module provides a compact binary format for storing star catalogs with
 //! minimal fields (ID, position, magnitude), optimized for size and loading speed.
+//!
+//! The header carries a [`CatalogMetadata`] block recording where the stars
+//! came from (survey, data release, epoch, band, filters), so catalogs built
+//! from different sources are not merged by accident.
+//!
+//! Since version 5 each star may also carry a proper motion, parallax and
+//! color index. A flag byte after the metadata says which of these fields
+//! the file stores, and stars lacking one store NaN in its place, so
+//! catalogs without them cost nothing extra. Older files still load, with
+//! the fields unset, and [`BinaryCatalog::save_version`] writes version 4
+//! for readers that predate them.
 
 use crate::coordinates::Equatorial;
 use byteorder::{LittleEndian, ReadBytesExt, WriteBytesExt};
+use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 use std::fs::File;
 use std::io::{self, BufReader, BufWriter, Read, Seek, SeekFrom, Write};
 use std::path::Path;
 
-use super::{StarCatalog, StarData, StarPosition};
+use super::spatial_index::IndexCache;
+use super::{Band, Photometry, ProperMotion, SkyIndex, StarCatalog, StarData, StarPosition};
 use crate::StarfieldError;
 
 /// Magic bytes for identification of binary catalog format files
 pub const MAGIC_BYTES: &[u8; 6] = b"BINCAT";
 
 /// Current version of the binary format
-pub const FORMAT_VERSION: u8 = 3;
+pub const FORMAT_VERSION: u8 = 5;
+
+/// Last version storing only the core star fields
+pub const CORE_FIELDS_VERSION: u8 = 4;
+
+/// Oldest version that can still be read (free-text description header)
+pub const MIN_READABLE_VERSION: u8 = 3;
 
-/// Fixed length of the catalog description
+/// Fixed length of the catalog description in version 3 files
 pub const DESCRIPTION_LENGTH: usize = 128;
 
+/// Largest metadata block accepted when reading, so a corrupt length cannot
+/// trigger a huge allocation
+pub const MAX_METADATA_LENGTH: usize = 1 << 20;
+
+/// Provenance of a binary catalog, stored in the file header
+///
+/// Version 4 files store this block as length-prefixed JSON after the star
+/// count; version 3 files only had a free-text description, which loads into
+/// [`CatalogMetadata::description`].
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CatalogMetadata {
+    /// Survey the stars were taken from (e.g. "Gaia", "Hipparcos")
+    pub survey: String,
+    /// Data release of the survey (e.g. "DR3")
+    pub data_release: String,
+    /// Reference frame of the positions
+    pub frame: String,
+    /// Epoch of the positions as a Julian year (e.g. 2016.0 for Gaia DR3)
+    pub epoch: Option<f64>,
+    /// Photometric band of the magnitudes (e.g. "G", "V", "Hp")
+    pub band: String,
+    /// Faintest magnitude kept when the catalog was built
+    pub magnitude_limit: Option<f64>,
+    /// Any other selection applied, as name/value pairs
+    pub filters: BTreeMap<String, String>,
+    /// When the catalog file was created, as Unix seconds
+    pub created_unix: i64,
+    /// Version of the software that wrote the catalog
+    pub tool_version: String,
+    /// Free-text description
+    pub description: String,
+    /// Survey/release of every catalog merged into this one
+    pub merged_from: Vec<String>,
+}
+
+impl Default for CatalogMetadata {
+    fn default() -> Self {
+        Self {
+            survey: String::new(),
+            data_release: String::new(),
+            frame: "ICRS".to_string(),
+            epoch: None,
+            band: String::new(),
+            magnitude_limit: None,
+            filters: BTreeMap::new(),
+            created_unix: Utc::now().timestamp(),
+            tool_version: concat!("starfield ", env!("CARGO_PKG_VERSION")).to_string(),
+            description: String::new(),
+            merged_from: Vec::new(),
+        }
+    }
+}
+
+impl CatalogMetadata {
+    /// Metadata for stars from a given survey and data release
+    pub fn new(survey: &str, data_release: &str) -> Self {
+        Self {
+            survey: survey.to_string(),
+            data_release: data_release.to_string(),
+            ..Self::default()
+        }
+    }
+
+    /// Set the epoch of the positions (Julian year)
+    pub fn with_epoch(mut self, epoch: f64) -> Self {
+        self.epoch = Some(epoch);
+        self
+    }
+
+    /// Set the reference frame of the positions
+    pub fn with_frame(mut self, frame: &str) -> Self {
+        self.frame = frame.to_string();
+        self
+    }
+
+    /// Set the photometric band of the magnitudes
+    pub fn with_band(mut self, band: &str) -> Self {
+        self.band = band.to_string();
+        self
+    }
+
+    /// Set the magnitude limit used to build the catalog
+    pub fn with_magnitude_limit(mut self, limit: f64) -> Self {
+        self.magnitude_limit = Some(limit);
+        self
+    }
+
+    /// Record an additional selection filter
+    pub fn with_filter(mut self, name: &str, value: impl ToString) -> Self {
+        self.filters.insert(name.to_string(), value.to_string());
+        self
+    }
+
+    /// Set the free-text description
+    pub fn with_description(mut self, description: &str) -> Self {
+        self.description = description.to_string();
+        self
+    }
+
+    /// Creation time of the catalog
+    pub fn created(&self) -> Option<DateTime<Utc>> {
+        DateTime::from_timestamp(self.created_unix, 0)
+    }
+
+    /// Short "survey release" label identifying the source
+    pub fn source_label(&self) -> String {
+        format!("{} {}", self.survey, self.data_release)
+            .trim()
+            .to_string()
+    }
+
+    /// Check that stars described by `other` can be mixed with these
+    ///
+    /// Positions must share a frame and epoch and magnitudes a band; fields
+    /// left unset on either side are not compared.
+    pub fn check_compatible(&self, other: &CatalogMetadata) -> Result<(), StarfieldError> {
+        let mismatch = |what: &str, a: &dyn std::fmt::Display, b: &dyn std::fmt::Display| {
+            StarfieldError::DataError(format!(
+                "Cannot merge catalogs with different {}: {} vs {}",
+                what, a, b
+            ))
+        };
+
+        if !self.frame.is_empty() && !other.frame.is_empty() && self.frame != other.frame {
+            return Err(mismatch("frames", &self.frame, &other.frame));
+        }
+        if let (Some(a), Some(b)) = (self.epoch, other.epoch) {
+            if a != b {
+                return Err(mismatch("epochs", &a, &b));
+            }
+        }
+        if !self.band.is_empty() && !other.band.is_empty() && self.band != other.band {
+            return Err(mismatch("bands", &self.band, &other.band));
+        }
+        Ok(())
+    }
+
+    fn write<W: Write>(&self, writer: &mut W) -> Result<(), StarfieldError> {
+        let json = serde_json::to_vec(self).map_err(|e| {
+            StarfieldError::DataError(format!("Failed to encode catalog metadata: {}", e))
+        })?;
+        if json.len() > MAX_METADATA_LENGTH {
+            return Err(StarfieldError::DataError(format!(
+                "Catalog metadata of {} bytes exceeds the {} byte limit",
+                json.len(),
+                MAX_METADATA_LENGTH
+            )));
+        }
+        writer.write_u32::<LittleEndian>(json.len() as u32)?;
+        writer.write_all(&json)?;
+        Ok(())
+    }
+
+    fn read<R: Read>(reader: &mut R, version: u8) -> Result<Self, StarfieldError> {
+        if version == 3 {
+            let mut description_bytes = [0u8; DESCRIPTION_LENGTH];
+            reader.read_exact(&mut description_bytes)?;
+
+            // Convert to string, trimming null bytes
+            let null_pos = description_bytes
+                .iter()
+                .position(|&b| b == 0)
+                .unwrap_or(DESCRIPTION_LENGTH);
+
+            return Ok(Self {
+                description: String::from_utf8_lossy(&description_bytes[..null_pos]).to_string(),
+                created_unix: 0,
+                tool_version: String::new(),
+                ..Self::default()
+            });
+        }
+
+        let length = reader.read_u32::<LittleEndian>()? as usize;
+        if length > MAX_METADATA_LENGTH {
+            return Err(StarfieldError::DataError(format!(
+                "Catalog metadata of {} bytes exceeds the {} byte limit",
+                length, MAX_METADATA_LENGTH
+            )));
+        }
+        let mut json = vec![0u8; length];
+        reader.read_exact(&mut json)?;
+        serde_json::from_slice(&json)
+            .map_err(|e| StarfieldError::DataError(format!("Invalid catalog metadata: {}", e)))
+    }
+}
+
+/// Optional per-star fields stored by a catalog file
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct StarFields {
+    /// Proper motion in right ascension and declination
+    pub proper_motion: bool,
+    /// Parallax
+    pub parallax: bool,
+    /// Color index
+    pub color_index: bool,
+}
+
+impl StarFields {
+    const PROPER_MOTION: u8 = 1;
+    const PARALLAX: u8 = 2;
+    const COLOR_INDEX: u8 = 4;
+
+    /// Every optional field
+    pub fn all() -> Self {
+        Self {
+            proper_motion: true,
+            parallax: true,
+            color_index: true,
+        }
+    }
+
+    /// Whether no optional field is stored
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+
+    /// Fields stored by either set
+    pub fn union(self, other: StarFields) -> Self {
+        Self {
+            proper_motion: self.proper_motion || other.proper_motion,
+            parallax: self.parallax || other.parallax,
+            color_index: self.color_index || other.color_index,
+        }
+    }
+
+    /// Bytes each star takes in a file storing these fields
+    pub fn record_bytes(&self) -> usize {
+        MinimalStar::size_bytes()
+            + 8 * (2 * self.proper_motion as usize
+                + self.parallax as usize
+                + self.color_index as usize)
+    }
+
+    fn to_bits(self) -> u8 {
+        (self.proper_motion as u8 * Self::PROPER_MOTION)
+            | (self.parallax as u8 * Self::PARALLAX)
+            | (self.color_index as u8 * Self::COLOR_INDEX)
+    }
+
+    fn from_bits(bits: u8) -> Result<Self, StarfieldError> {
+        let known = Self::PROPER_MOTION | Self::PARALLAX | Self::COLOR_INDEX;
+        if bits & !known != 0 {
+            return Err(StarfieldError::DataError(format!(
+                "Unknown star fields in binary catalog: {:#04x}",
+                bits & !known
+            )));
+        }
+        Ok(Self {
+            proper_motion: bits & Self::PROPER_MOTION != 0,
+            parallax: bits & Self::PARALLAX != 0,
+            color_index: bits & Self::COLOR_INDEX != 0,
+        })
+    }
+}
+
 /// Minimal star entry with only essential fields
+///
+/// The optional fields are kept only by version 5 and later files.
 #[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
 pub struct MinimalStar {
     /// Star identifier (usually from source catalog)
@@ -31,17 +309,23 @@ pub struct MinimalStar {
     pub position: Equatorial,
     /// Apparent magnitude
     pub magnitude: f64,
+    /// Proper motion (μα*, μδ) in mas/yr, the first including the cos δ
+    /// factor
+    #[serde(default)]
+    pub proper_motion_mas_yr: Option<(f64, f64)>,
+    /// Parallax in mas
+    #[serde(default)]
+    pub parallax_mas: Option<f64>,
+    /// Color index in the catalog's system (e.g. B-V or BP-RP)
+    #[serde(default)]
+    pub color_index: Option<f64>,
 }
 
 impl MinimalStar {
     /// Create a new minimal star entry with RA/Dec in degrees
     #[inline]
     pub fn new(id: u64, ra_deg: f64, dec_deg: f64, magnitude: f64) -> Self {
-        Self {
-            id,
-            position: Equatorial::from_degrees(ra_deg, dec_deg),
-            magnitude,
-        }
+        Self::with_position(id, Equatorial::from_degrees(ra_deg, dec_deg), magnitude)
     }
 
     /// Create from an existing Equatorial position
@@ -50,16 +334,46 @@ impl MinimalStar {
             id,
             position,
             magnitude,
+            proper_motion_mas_yr: None,
+            parallax_mas: None,
+            color_index: None,
         }
     }
 
-    /// Size of a single star entry in bytes
+    /// Set the proper motion in mas/yr, `pm_ra` including the cos δ factor
+    pub fn with_proper_motion(mut self, pm_ra_mas_yr: f64, pm_dec_mas_yr: f64) -> Self {
+        self.proper_motion_mas_yr = Some((pm_ra_mas_yr, pm_dec_mas_yr));
+        self
+    }
+
+    /// Set the parallax in mas
+    pub fn with_parallax(mut self, parallax_mas: f64) -> Self {
+        self.parallax_mas = Some(parallax_mas);
+        self
+    }
+
+    /// Set the color index
+    pub fn with_color_index(mut self, color_index: f64) -> Self {
+        self.color_index = Some(color_index);
+        self
+    }
+
+    /// Optional fields this star has
+    pub fn fields(&self) -> StarFields {
+        StarFields {
+            proper_motion: self.proper_motion_mas_yr.is_some(),
+            parallax: self.parallax_mas.is_some(),
+            color_index: self.color_index.is_some(),
+        }
+    }
+
+    /// Size of the core star entry in bytes, without optional fields
     pub const fn size_bytes() -> usize {
         // u64 + f64 + f64 + f64 = 8 + 8 + 8 + 8 = 32 bytes
         32
     }
 
-    /// Write star data in binary format
+    /// Write the core star fields in binary format
     #[inline]
     pub fn write_binary<W: Write>(&self, writer: &mut W) -> io::Result<()> {
         writer.write_u64::<LittleEndian>(self.id)?;
@@ -69,7 +383,7 @@ impl MinimalStar {
         Ok(())
     }
 
-    /// Read star data from binary format
+    /// Read the core star fields from binary format
     #[inline]
     pub fn read_binary<R: Read>(reader: &mut R) -> io::Result<Self> {
         let id = reader.read_u64::<LittleEndian>()?;
@@ -77,11 +391,45 @@ impl MinimalStar {
         let dec_deg = reader.read_f64::<LittleEndian>()?;
         let magnitude = reader.read_f64::<LittleEndian>()?;
 
-        Ok(MinimalStar {
-            id,
-            position: Equatorial::from_degrees(ra_deg, dec_deg),
-            magnitude,
-        })
+        Ok(MinimalStar::new(id, ra_deg, dec_deg, magnitude))
+    }
+
+    /// Write the star with the optional `fields`, NaN marking absent values
+    pub fn write_record<W: Write>(&self, writer: &mut W, fields: StarFields) -> io::Result<()> {
+        self.write_binary(writer)?;
+        let mut write =
+            |value: Option<f64>| writer.write_f64::<LittleEndian>(value.unwrap_or(f64::NAN));
+        if fields.proper_motion {
+            write(self.proper_motion_mas_yr.map(|pm| pm.0))?;
+            write(self.proper_motion_mas_yr.map(|pm| pm.1))?;
+        }
+        if fields.parallax {
+            write(self.parallax_mas)?;
+        }
+        if fields.color_index {
+            write(self.color_index)?;
+        }
+        Ok(())
+    }
+
+    /// Read a star written by [`MinimalStar::write_record`]
+    pub fn read_record<R: Read>(reader: &mut R, fields: StarFields) -> io::Result<Self> {
+        let mut star = Self::read_binary(reader)?;
+        let mut read = || {
+            let value = reader.read_f64::<LittleEndian>()?;
+            Ok::<_, io::Error>(Some(value).filter(|v| !v.is_nan()))
+        };
+        if fields.proper_motion {
+            let (pm_ra, pm_dec) = (read()?, read()?);
+            star.proper_motion_mas_yr = pm_ra.zip(pm_dec);
+        }
+        if fields.parallax {
+            star.parallax_mas = read()?;
+        }
+        if fields.color_index {
+            star.color_index = read()?;
+        }
+        Ok(star)
     }
 }
 
@@ -95,13 +443,21 @@ impl StarPosition for MinimalStar {
     }
 }
 
+impl ProperMotion for MinimalStar {
+    fn proper_motion_mas_yr(&self) -> Option<(f64, f64)> {
+        self.proper_motion_mas_yr
+    }
+}
+
 /// Binary star catalog container
 #[derive(Debug, Clone)]
 pub struct BinaryCatalog {
     /// Vector of minimal star entries
     stars: Vec<MinimalStar>,
-    /// Catalog description
-    description: String,
+    /// Catalog provenance
+    metadata: CatalogMetadata,
+    /// Spatial index, built on the first cone search
+    index: IndexCache,
 }
 
 impl BinaryCatalog {
@@ -109,7 +465,8 @@ impl BinaryCatalog {
     pub fn new() -> Self {
         Self {
             stars: Vec::new(),
-            description: String::new(),
+            metadata: CatalogMetadata::default(),
+            index: IndexCache::default(),
         }
     }
 
@@ -117,7 +474,8 @@ impl BinaryCatalog {
     pub fn with_description(description: &str) -> Self {
         Self {
             stars: Vec::new(),
-            description: description.to_string(),
+            metadata: CatalogMetadata::default().with_description(description),
+            index: IndexCache::default(),
         }
     }
 
@@ -125,13 +483,78 @@ impl BinaryCatalog {
     pub fn from_stars(stars: Vec<MinimalStar>, description: &str) -> Self {
         Self {
             stars,
-            description: description.to_string(),
+            metadata: CatalogMetadata::default().with_description(description),
+            index: IndexCache::default(),
+        }
+    }
+
+    /// Create a catalog from a vector of stars and their provenance
+    pub fn from_stars_with_metadata(stars: Vec<MinimalStar>, metadata: CatalogMetadata) -> Self {
+        Self {
+            stars,
+            metadata,
+            index: IndexCache::default(),
         }
     }
 
+    /// Replace the catalog's metadata
+    pub fn with_metadata(mut self, metadata: CatalogMetadata) -> Self {
+        // Star data depends on the band, so the index is rebuilt
+        self.metadata = metadata;
+        self.index = IndexCache::default();
+        self
+    }
+
     /// Get the catalog description
     pub fn description(&self) -> &str {
-        &self.description
+        &self.metadata.description
+    }
+
+    /// Get the catalog's provenance metadata
+    pub fn metadata(&self) -> &CatalogMetadata {
+        &self.metadata
+    }
+
+    /// Get a mutable reference to the catalog's metadata
+    pub fn metadata_mut(&mut self) -> &mut CatalogMetadata {
+        &mut self.metadata
+    }
+
+    /// Merge another catalog's stars into this one
+    ///
+    /// Fails if the two catalogs' positions or magnitudes are not comparable
+    /// (see [`CatalogMetadata::check_compatible`]). Stars whose ID is already
+    /// present are skipped, and the other catalog's source is appended to
+    /// [`CatalogMetadata::merged_from`].
+    pub fn merge(&mut self, other: BinaryCatalog) -> Result<(), StarfieldError> {
+        self.metadata.check_compatible(&other.metadata)?;
+
+        if self.metadata.merged_from.is_empty() {
+            self.metadata.merged_from.push(self.metadata.source_label());
+        }
+        let mut sources = other.metadata.merged_from.clone();
+        if sources.is_empty() {
+            sources.push(other.metadata.source_label());
+        }
+        self.metadata.merged_from.extend(sources);
+
+        self.metadata.epoch = self.metadata.epoch.or(other.metadata.epoch);
+        if self.metadata.band.is_empty() {
+            self.metadata.band = other.metadata.band;
+        }
+        self.metadata.magnitude_limit = match (
+            self.metadata.magnitude_limit,
+            other.metadata.magnitude_limit,
+        ) {
+            (Some(a), Some(b)) => Some(a.min(b)),
+            _ => None,
+        };
+
+        let ids: std::collections::HashSet<u64> = self.stars.iter().map(|s| s.id).collect();
+        self.stars
+            .extend(other.stars.into_iter().filter(|s| !ids.contains(&s.id)));
+        self.index.invalidate();
+        Ok(())
     }
 
     /// Get the number of stars in the catalog
@@ -159,18 +582,35 @@ impl BinaryCatalog {
 
     /// Get a mutable reference to all stars
     pub fn stars_mut(&mut self) -> &mut Vec<MinimalStar> {
+        self.index.invalidate();
         &mut self.stars
     }
 
+    /// Find all stars within `radius_deg` of a position
+    ///
+    /// Uses a kd-tree built on the first call and reused until the stars
+    /// change, so repeated queries against a large catalog avoid a full scan.
+    pub fn cone_search(&self, ra_deg: f64, dec_deg: f64, radius_deg: f64) -> Vec<StarData> {
+        self.spatial_index()
+            .cone_search(ra_deg, dec_deg, radius_deg)
+    }
+
+    /// Indexed equivalent of [`StarCatalog::stars_in_field`]
+    pub fn stars_in_field_indexed(&self, ra_deg: f64, dec_deg: f64, fov_deg: f64) -> Vec<StarData> {
+        self.cone_search(ra_deg, dec_deg, fov_deg / 2.0)
+    }
+
+    /// Get the catalog's spatial index, building it if needed
+    pub fn spatial_index(&self) -> &SkyIndex {
+        self.index.get_or_build(|| self.star_data())
+    }
+
     /// Builder method to add a star and return a new catalog
     pub fn add_star(self, star: MinimalStar) -> Self {
         let mut new_stars = self.stars;
         new_stars.push(star);
 
-        Self {
-            stars: new_stars,
-            description: self.description,
-        }
+        Self::from_stars_with_metadata(new_stars, self.metadata)
     }
 
     /// Get stars brighter than a given magnitude
@@ -189,8 +629,32 @@ impl BinaryCatalog {
         self.stars.iter().filter(|star| predicate(star)).collect()
     }
 
+    /// Optional fields held by any star in the catalog
+    pub fn fields(&self) -> StarFields {
+        self.stars
+            .iter()
+            .fold(StarFields::default(), |fields, star| {
+                fields.union(star.fields())
+            })
+    }
+
     /// Save catalog to a binary file
     pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<(), StarfieldError> {
+        self.save_version(path, FORMAT_VERSION)
+    }
+
+    /// Save catalog in an older format version, for older readers
+    ///
+    /// Version 4 drops the optional star fields. Versions before 4 cannot be
+    /// written.
+    pub fn save_version<P: AsRef<Path>>(&self, path: P, version: u8) -> Result<(), StarfieldError> {
+        if !(CORE_FIELDS_VERSION..=FORMAT_VERSION).contains(&version) {
+            return Err(StarfieldError::DataError(format!(
+                "Cannot write binary catalog version {}. Expected version {} to {}",
+                version, CORE_FIELDS_VERSION, FORMAT_VERSION
+            )));
+        }
+
         // Open file for writing
         let file = File::create(path)?;
         let mut writer = BufWriter::new(file);
@@ -199,21 +663,24 @@ impl BinaryCatalog {
         writer.write_all(MAGIC_BYTES)?;
 
         // Write format version
-        writer.write_u8(FORMAT_VERSION)?;
+        writer.write_u8(version)?;
 
         // Write number of stars as u64
         writer.write_u64::<LittleEndian>(self.stars.len() as u64)?;
 
-        // Write description with fixed length
-        let mut description_bytes = [0u8; DESCRIPTION_LENGTH];
-        let desc_bytes = self.description.as_bytes();
-        let copy_len = desc_bytes.len().min(DESCRIPTION_LENGTH);
-        description_bytes[..copy_len].copy_from_slice(&desc_bytes[..copy_len]);
-        writer.write_all(&description_bytes)?;
+        // Write the metadata block
+        self.metadata.write(&mut writer)?;
 
-        // Write all stars
+        // Write the optional fields present, then all stars
+        let fields = if version > CORE_FIELDS_VERSION {
+            let fields = self.fields();
+            writer.write_u8(fields.to_bits())?;
+            fields
+        } else {
+            StarFields::default()
+        };
         for star in &self.stars {
-            star.write_binary(&mut writer)?;
+            star.write_record(&mut writer, fields)?;
         }
 
         // Ensure all data is flushed to disk
@@ -240,34 +707,32 @@ impl BinaryCatalog {
 
         // Read and verify version
         let version = reader.read_u8()?;
-        if version != FORMAT_VERSION {
+        if !(MIN_READABLE_VERSION..=FORMAT_VERSION).contains(&version) {
             return Err(StarfieldError::DataError(format!(
-                "Unsupported binary catalog version: {}. Expected version {}",
-                version, FORMAT_VERSION
+                "Unsupported binary catalog version: {}. Expected version {} to {}",
+                version, MIN_READABLE_VERSION, FORMAT_VERSION
             )));
         }
 
         // Read number of stars
         let star_count = reader.read_u64::<LittleEndian>()?;
 
-        // Read description
-        let mut description_bytes = [0u8; DESCRIPTION_LENGTH];
-        reader.read_exact(&mut description_bytes)?;
+        // Read the metadata block (or the description of older files)
+        let metadata = CatalogMetadata::read(&mut reader, version)?;
 
-        // Convert to string, trimming null bytes
-        let null_pos = description_bytes
-            .iter()
-            .position(|&b| b == 0)
-            .unwrap_or(DESCRIPTION_LENGTH);
-
-        let description = String::from_utf8_lossy(&description_bytes[..null_pos]).to_string();
+        // Optional star fields, absent before version 5
+        let fields = if version > CORE_FIELDS_VERSION {
+            StarFields::from_bits(reader.read_u8()?)?
+        } else {
+            StarFields::default()
+        };
 
         // Pre-allocate stars vector
         let mut stars = Vec::with_capacity(star_count as usize);
 
         // Read all stars
         for _ in 0..star_count {
-            match MinimalStar::read_binary(&mut reader) {
+            match MinimalStar::read_record(&mut reader, fields) {
                 Ok(star) => stars.push(star),
                 Err(e) => {
                     if e.kind() == io::ErrorKind::UnexpectedEof {
@@ -290,7 +755,22 @@ impl BinaryCatalog {
             )));
         }
 
-        Ok(Self { stars, description })
+        Ok(Self::from_stars_with_metadata(stars, metadata))
+    }
+
+    /// Rewrite a catalog file in another format version, returning the
+    /// number of stars
+    ///
+    /// Any readable file can be converted to version 4 or 5; converting to
+    /// version 4 drops the optional star fields.
+    pub fn convert<P, Q>(input: P, output: Q, version: u8) -> Result<usize, StarfieldError>
+    where
+        P: AsRef<Path>,
+        Q: AsRef<Path>,
+    {
+        let catalog = Self::load(input)?;
+        catalog.save_version(output, version)?;
+        Ok(catalog.len())
     }
 }
 
@@ -315,6 +795,24 @@ impl BinaryCatalog {
         description: &str,
         star_count: Option<u64>,
     ) -> Result<u64, StarfieldError>
+    where
+        P: AsRef<Path>,
+        I: Iterator<Item = StarData>,
+    {
+        let metadata = CatalogMetadata::default().with_description(description);
+        Self::write_from_star_data_with_metadata(path, stars, &metadata, star_count)
+    }
+
+    /// Stream star data to a catalog file with full provenance metadata
+    ///
+    /// Behaves like [`BinaryCatalog::write_from_star_data`], writing
+    /// `metadata` into the header instead of a bare description.
+    pub fn write_from_star_data_with_metadata<P, I>(
+        path: P,
+        stars: I,
+        metadata: &CatalogMetadata,
+        star_count: Option<u64>,
+    ) -> Result<u64, StarfieldError>
     where
         P: AsRef<Path>,
         I: Iterator<Item = StarData>,
@@ -333,20 +831,23 @@ impl BinaryCatalog {
         // Write placeholder count (we'll update this at the end if not provided)
         writer.write_u64::<LittleEndian>(star_count.unwrap_or(0))?;
 
-        // Write description with fixed length
-        let mut description_bytes = [0u8; DESCRIPTION_LENGTH];
-        let desc_bytes = description.as_bytes();
-        let copy_len = desc_bytes.len().min(DESCRIPTION_LENGTH);
-        description_bytes[..copy_len].copy_from_slice(&desc_bytes[..copy_len]);
-        writer.write_all(&description_bytes)?;
+        // Write the metadata block; star data only carries a color index
+        metadata.write(&mut writer)?;
+        let fields = StarFields {
+            color_index: true,
+            ..StarFields::default()
+        };
+        writer.write_u8(fields.to_bits())?;
 
         // Process stars and write them
         let mut actual_count: u64 = 0;
         for star in stars {
             // Convert StarData to MinimalStar and write directly
-            let minimal_star = MinimalStar::with_position(star.id, star.position, star.magnitude);
+            let mut minimal_star =
+                MinimalStar::with_position(star.id, star.position, star.magnitude);
+            minimal_star.color_index = star.b_v;
 
-            minimal_star.write_binary(&mut writer)?;
+            minimal_star.write_record(&mut writer, fields)?;
             actual_count += 1;
         }
 
@@ -386,6 +887,10 @@ impl StarCatalog for BinaryCatalog {
         self.stars.len()
     }
 
+    fn epoch(&self) -> Option<f64> {
+        self.metadata.epoch
+    }
+
     fn filter<F>(&self, predicate: F) -> Vec<&Self::Star>
     where
         F: Fn(&Self::Star) -> bool,
@@ -393,14 +898,17 @@ impl StarCatalog for BinaryCatalog {
         self.stars.iter().filter(|star| predicate(star)).collect()
     }
 
+    /// Stars of a V-band catalog with a color index also carry magnitudes in
+    /// the other bands, from the color relations of [`Photometry`]
     fn star_data(&self) -> impl Iterator<Item = StarData> + '_ {
-        self.stars.iter().map(|star| {
-            StarData::with_position(
-                star.id,
-                star.position,
-                star.magnitude,
-                None, // Binary catalog doesn't store B-V color
-            )
+        let v_band = Band::from_name(&self.metadata.band) == Some(Band::V);
+        self.stars.iter().map(move |star| {
+            let data =
+                StarData::with_position(star.id, star.position, star.magnitude, star.color_index);
+            match star.color_index.filter(|_| v_band) {
+                Some(b_v) => data.with_bands(Photometry::from_v_and_b_v(star.magnitude, b_v)),
+                None => data,
+            }
         })
     }
 
@@ -595,9 +1103,8 @@ mod tests {
         writer.write_u8(FORMAT_VERSION).unwrap();
         writer.write_u64::<LittleEndian>(5).unwrap();
 
-        // Write empty description (version 3 format)
-        let empty_desc = [0u8; DESCRIPTION_LENGTH];
-        writer.write_all(&empty_desc).unwrap();
+        // Write the metadata block
+        CatalogMetadata::default().write(&mut writer).unwrap();
 
         // But only write 2 stars
         MinimalStar::new(1, 100.0, 10.0, 1.5)
@@ -626,7 +1133,7 @@ mod tests {
         let file_path = temp_dir.path().join("streamed_catalog.bin");
 
         // Create some test star data
-        let star_data = vec![
+        let star_data = [
             StarData::new(1, 100.0, 10.0, -1.5, Some(-0.4)),
             StarData::new(2, 50.0, -20.0, 0.5, Some(0.1)),
             StarData::new(3, 150.0, 30.0, 1.2, Some(0.5)),
@@ -675,6 +1182,186 @@ mod tests {
             assert_eq!(star.ra(), original.ra());
             assert_eq!(star.dec(), original.dec());
             assert_eq!(star.magnitude, original.magnitude);
+            assert_eq!(star.color_index, original.b_v);
         }
     }
+
+    #[test]
+    fn test_metadata_roundtrip() {
+        let temp_dir = tempdir().unwrap();
+        let file_path = temp_dir.path().join("gaia_bright.bin");
+
+        let metadata = CatalogMetadata::new("Gaia", "DR3")
+            .with_epoch(2016.0)
+            .with_band("G")
+            .with_magnitude_limit(6.5)
+            .with_filter("ruwe_max", 1.4)
+            .with_description("Bright Gaia stars");
+        let catalog = BinaryCatalog::from_stars_with_metadata(
+            create_test_catalog().stars().to_vec(),
+            metadata.clone(),
+        );
+        catalog.save(&file_path).unwrap();
+
+        let loaded = BinaryCatalog::load(&file_path).unwrap();
+        assert_eq!(loaded.metadata(), &metadata);
+        assert_eq!(loaded.description(), "Bright Gaia stars");
+        assert_eq!(loaded.metadata().filters["ruwe_max"], "1.4");
+        assert!(loaded.metadata().created().is_some());
+        assert_eq!(loaded.len(), 5);
+    }
+
+    #[test]
+    fn test_oversized_metadata_is_rejected() {
+        let temp_dir = tempdir().unwrap();
+        let file_path = temp_dir.path().join("huge_metadata.bin");
+
+        let file = File::create(&file_path).unwrap();
+        let mut writer = BufWriter::new(file);
+        writer.write_all(MAGIC_BYTES).unwrap();
+        writer.write_u8(FORMAT_VERSION).unwrap();
+        writer.write_u64::<LittleEndian>(0).unwrap();
+        writer.write_u32::<LittleEndian>(u32::MAX).unwrap();
+        writer.flush().unwrap();
+        drop(writer);
+
+        match BinaryCatalog::load(&file_path) {
+            Err(StarfieldError::DataError(msg)) => assert!(msg.contains("exceeds")),
+            other => panic!("Expected DataError, got {:?}", other.map(|c| c.len())),
+        }
+    }
+
+    #[test]
+    fn test_reads_version_3_files() {
+        let temp_dir = tempdir().unwrap();
+        let file_path = temp_dir.path().join("v3.bin");
+
+        let file = File::create(&file_path).unwrap();
+        let mut writer = BufWriter::new(file);
+        writer.write_all(MAGIC_BYTES).unwrap();
+        writer.write_u8(3).unwrap();
+        writer.write_u64::<LittleEndian>(1).unwrap();
+        let mut description = [0u8; DESCRIPTION_LENGTH];
+        description[..6].copy_from_slice(b"Legacy");
+        writer.write_all(&description).unwrap();
+        MinimalStar::new(7, 10.0, 20.0, 4.0)
+            .write_binary(&mut writer)
+            .unwrap();
+        writer.flush().unwrap();
+        drop(writer);
+
+        let loaded = BinaryCatalog::load(&file_path).unwrap();
+        assert_eq!(loaded.description(), "Legacy");
+        assert_eq!(loaded.metadata().survey, "");
+        assert_eq!(loaded.stars()[0].id, 7);
+    }
+
+    #[test]
+    fn test_optional_star_fields() {
+        let temp_dir = tempdir().unwrap();
+        let v5_path = temp_dir.path().join("v5.bin");
+        let v4_path = temp_dir.path().join("v4.bin");
+
+        let stars = vec![
+            MinimalStar::new(1, 101.29, -16.72, -1.46)
+                .with_proper_motion(-546.0, -1223.1)
+                .with_parallax(379.2),
+            MinimalStar::new(2, 279.23, 38.78, 0.03).with_parallax(130.2),
+            MinimalStar::new(3, 50.0, -20.0, 5.0),
+        ];
+        let catalog = BinaryCatalog::from_stars_with_metadata(
+            stars.clone(),
+            CatalogMetadata::new("Hipparcos", "2007").with_epoch(1991.25),
+        );
+        assert_eq!(
+            catalog.fields(),
+            StarFields {
+                proper_motion: true,
+                parallax: true,
+                color_index: false,
+            }
+        );
+        catalog.save(&v5_path).unwrap();
+
+        let loaded = BinaryCatalog::load(&v5_path).unwrap();
+        for (star, original) in loaded.stars().iter().zip(&stars) {
+            assert_eq!(star.id, original.id);
+            assert_eq!(star.fields(), original.fields());
+            assert_eq!(star.proper_motion_mas_yr, original.proper_motion_mas_yr);
+            assert_eq!(star.parallax_mas, original.parallax_mas);
+        }
+        assert_eq!(loaded.metadata().epoch, Some(1991.25));
+        let size = std::fs::metadata(&v5_path).unwrap().len() as usize;
+        assert!(size > 3 * catalog.fields().record_bytes());
+
+        // Converting to version 4 keeps the stars but not their extra fields
+        assert_eq!(BinaryCatalog::convert(&v5_path, &v4_path, 4).unwrap(), 3);
+        let mut bytes = [0u8; 7];
+        File::open(&v4_path)
+            .unwrap()
+            .read_exact(&mut bytes)
+            .unwrap();
+        assert_eq!(bytes[6], CORE_FIELDS_VERSION);
+        let v4 = BinaryCatalog::load(&v4_path).unwrap();
+        assert!(v4.fields().is_empty());
+        assert_eq!(v4.stars()[0].ra(), loaded.stars()[0].ra());
+        assert_eq!(v4.metadata(), loaded.metadata());
+
+        assert!(catalog.save_version(&v4_path, 3).is_err());
+        assert!(catalog.save_version(&v4_path, FORMAT_VERSION + 1).is_err());
+        assert!(StarFields::from_bits(0x80).is_err());
+    }
+
+    #[test]
+    fn test_provenance_aware_merge() {
+        let dr3 = CatalogMetadata::new("Gaia", "DR3")
+            .with_epoch(2016.0)
+            .with_band("G");
+        let mut a = BinaryCatalog::from_stars_with_metadata(
+            vec![MinimalStar::new(1, 0.0, 0.0, 5.0)],
+            dr3.clone().with_magnitude_limit(6.0),
+        );
+        let b = BinaryCatalog::from_stars_with_metadata(
+            vec![
+                MinimalStar::new(1, 0.0, 0.0, 5.0),
+                MinimalStar::new(2, 1.0, 1.0, 5.5),
+            ],
+            dr3.clone().with_magnitude_limit(7.0),
+        );
+        a.merge(b).unwrap();
+        assert_eq!(a.len(), 2);
+        assert_eq!(a.metadata().merged_from, vec!["Gaia DR3", "Gaia DR3"]);
+        assert_eq!(a.metadata().magnitude_limit, Some(6.0));
+
+        // Different epochs or bands cannot be combined
+        let dr2 = BinaryCatalog::from_stars_with_metadata(
+            vec![],
+            CatalogMetadata::new("Gaia", "DR2").with_epoch(2015.5),
+        );
+        assert!(a.merge(dr2).is_err());
+        let hip = BinaryCatalog::from_stars_with_metadata(
+            vec![],
+            CatalogMetadata::new("Hipparcos", "").with_band("Hp"),
+        );
+        assert!(a.merge(hip).is_err());
+    }
+
+    #[test]
+    fn test_cone_search_tracks_star_changes() {
+        let mut catalog = create_test_catalog();
+        let ids = |stars: Vec<StarData>| stars.iter().map(|s| s.id).collect::<Vec<_>>();
+
+        assert_eq!(ids(catalog.cone_search(100.0, 10.0, 1.0)), vec![1]);
+        assert_eq!(
+            catalog.stars_in_field_indexed(100.0, 10.0, 2.0).len(),
+            catalog.stars_in_field(100.0, 10.0, 2.0).len()
+        );
+
+        catalog
+            .stars_mut()
+            .push(MinimalStar::new(6, 100.5, 10.0, 4.0));
+        let mut found = ids(catalog.cone_search(100.0, 10.0, 1.0));
+        found.sort_unstable();
+        assert_eq!(found, vec![1, 6]);
+    }
 }
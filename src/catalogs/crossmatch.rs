@@ -0,0 +1,259 @@
+//! Positional cross-matching between two catalogs
+//!
+//! [`crossmatch`] pairs the stars of two catalogs that lie within a radius of
+//! each other, for instance to check a custom catalog against Gaia. Both
+//! catalogs are first moved by their proper motions to a common epoch (by
+//! default that of the second catalog), so that fast movers observed decades
+//! apart still match. Each star takes part in at most one pair, the closest
+//! pairs being made first.
+//!
+//! ```
+//! use starfield::catalogs::{crossmatch, BinaryCatalog, CatalogMetadata, MinimalStar};
+//!
+//! let hipparcos = BinaryCatalog::from_stars_with_metadata(
+//!     vec![MinimalStar::new(1, 10.0, 20.0, 8.0).with_proper_motion(0.0, 500.0)],
+//!     CatalogMetadata::new("Hipparcos", "2007").with_epoch(1991.25),
+//! );
+//! let gaia = BinaryCatalog::from_stars_with_metadata(
+//!     vec![MinimalStar::new(7, 10.0, 20.0 + 12.375 / 3600.0, 8.1)],
+//!     CatalogMetadata::new("Gaia", "DR3").with_epoch(2016.0),
+//! );
+//!
+//! let result = crossmatch(&hipparcos, &gaia, 1.0);
+//! assert_eq!(result.matches.len(), 1);
+//! assert_eq!(result.matches[0].right.id, 7);
+//! ```
+
+use super::window::move_star;
+use super::{ProperMotion, SkyIndex, StarCatalog, StarData};
+use crate::coordinates::Equatorial;
+
+/// Settings for matching two catalogs by position
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CrossMatch {
+    /// Largest separation of a match, arcseconds
+    pub radius_arcsec: f64,
+    /// Julian year to compare positions at; defaults to the epoch of the
+    /// right catalog, or of the left one if the right has none
+    pub epoch: Option<f64>,
+}
+
+/// Two stars matched by position
+#[derive(Debug, Clone, Copy)]
+pub struct MatchedPair {
+    /// Star from the left catalog, at the match epoch
+    pub left: StarData,
+    /// Star from the right catalog, at the match epoch
+    pub right: StarData,
+    /// Separation at the match epoch, arcseconds
+    pub separation_arcsec: f64,
+}
+
+/// Outcome of [`CrossMatch::run`]
+#[derive(Debug, Clone, Default)]
+pub struct CrossMatchResult {
+    /// Matched pairs, in left catalog order
+    pub matches: Vec<MatchedPair>,
+    /// Left catalog stars without a match
+    pub unmatched_left: Vec<StarData>,
+    /// Right catalog stars without a match
+    pub unmatched_right: Vec<StarData>,
+    /// Epoch the positions were compared at, if either catalog had one
+    pub epoch: Option<f64>,
+}
+
+impl CrossMatchResult {
+    /// Fraction of the left catalog that found a match
+    pub fn match_fraction(&self) -> f64 {
+        match self.matches.len() + self.unmatched_left.len() {
+            0 => 0.0,
+            total => self.matches.len() as f64 / total as f64,
+        }
+    }
+}
+
+impl CrossMatch {
+    /// Match stars within `radius_arcsec` of each other
+    pub fn new(radius_arcsec: f64) -> Self {
+        Self {
+            radius_arcsec,
+            epoch: None,
+        }
+    }
+
+    /// Compare positions at the Julian year `epoch`
+    pub fn with_epoch(mut self, epoch: f64) -> Self {
+        self.epoch = Some(epoch);
+        self
+    }
+
+    /// Match the stars of `left` against those of `right`
+    ///
+    /// Stars of a catalog without an epoch, or without a proper motion, are
+    /// compared where the catalog puts them.
+    pub fn run<L, R>(&self, left: &L, right: &R) -> CrossMatchResult
+    where
+        L: StarCatalog,
+        L::Star: ProperMotion,
+        R: StarCatalog,
+        R::Star: ProperMotion,
+    {
+        let epoch = self.epoch.or(right.epoch()).or(left.epoch());
+        let left_stars = positions_at(left, epoch);
+        let right_stars = positions_at(right, epoch);
+
+        // Index the right catalog by position in `right_stars`
+        let index = SkyIndex::build(right_stars.iter().enumerate().map(|(i, star)| StarData {
+            id: i as u64,
+            ..*star
+        }));
+
+        let radius_deg = self.radius_arcsec / 3600.0;
+        let mut candidates = Vec::new();
+        for (i, star) in left_stars.iter().enumerate() {
+            for found in index.cone_search(star.ra_deg(), star.dec_deg(), radius_deg) {
+                let separation = separation_arcsec(&star.position, &found.position);
+                candidates.push((separation, i, found.id as usize));
+            }
+        }
+        candidates.sort_by(|a, b| a.0.total_cmp(&b.0));
+
+        // Pair the closest candidates first, each star at most once
+        let mut left_match = vec![None; left_stars.len()];
+        let mut right_taken = vec![false; right_stars.len()];
+        for (separation, i, j) in candidates {
+            if left_match[i].is_none() && !right_taken[j] {
+                left_match[i] = Some((j, separation));
+                right_taken[j] = true;
+            }
+        }
+
+        let mut result = CrossMatchResult {
+            epoch,
+            ..Default::default()
+        };
+        for (star, matched) in left_stars.iter().zip(left_match) {
+            match matched {
+                Some((j, separation_arcsec)) => result.matches.push(MatchedPair {
+                    left: *star,
+                    right: right_stars[j],
+                    separation_arcsec,
+                }),
+                None => result.unmatched_left.push(*star),
+            }
+        }
+        result.unmatched_right = right_stars
+            .into_iter()
+            .zip(right_taken)
+            .filter(|(_, taken)| !taken)
+            .map(|(star, _)| star)
+            .collect();
+        result
+    }
+}
+
+/// Match the stars of two catalogs within `radius_arcsec`, at the epoch of
+/// `right`
+pub fn crossmatch<L, R>(left: &L, right: &R, radius_arcsec: f64) -> CrossMatchResult
+where
+    L: StarCatalog,
+    L::Star: ProperMotion,
+    R: StarCatalog,
+    R::Star: ProperMotion,
+{
+    CrossMatch::new(radius_arcsec).run(left, right)
+}
+
+/// Stars of `catalog` moved by their proper motions to `epoch`
+fn positions_at<C>(catalog: &C, epoch: Option<f64>) -> Vec<StarData>
+where
+    C: StarCatalog,
+    C::Star: ProperMotion,
+{
+    let years = epoch.zip(catalog.epoch()).map(|(to, from)| to - from);
+    catalog
+        .stars()
+        .zip(catalog.star_data())
+        .map(
+            |(entry, star)| match (years, entry.proper_motion_mas_yr()) {
+                (Some(years), Some(motion)) => move_star(star, motion, years),
+                _ => star,
+            },
+        )
+        .collect()
+}
+
+/// Angle between two positions in arcseconds, accurate at small separations
+fn separation_arcsec(a: &Equatorial, b: &Equatorial) -> f64 {
+    let half_dec = ((b.dec - a.dec) / 2.0).sin();
+    let half_ra = ((b.ra - a.ra) / 2.0).sin();
+    let h = half_dec * half_dec + a.dec.cos() * b.dec.cos() * half_ra * half_ra;
+    (2.0 * h.sqrt().min(1.0).asin()).to_degrees() * 3600.0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::catalogs::{BinaryCatalog, CatalogMetadata, MinimalStar};
+    use approx::assert_relative_eq;
+
+    fn catalog(stars: Vec<MinimalStar>, epoch: Option<f64>) -> BinaryCatalog {
+        let mut metadata = CatalogMetadata::new("Test", "1");
+        metadata.epoch = epoch;
+        BinaryCatalog::from_stars_with_metadata(stars, metadata)
+    }
+
+    #[test]
+    fn test_matches_across_epochs() {
+        let arcsec = 1.0 / 3600.0;
+        // A fast mover covering 25" between the epochs, a slow star, a
+        // close pair of which only one can match, and a lone star
+        let left = catalog(
+            vec![
+                MinimalStar::new(1, 120.0, -30.0, 6.0).with_proper_motion(0.0, 1000.0),
+                MinimalStar::new(2, 200.0, 45.0, 9.0).with_proper_motion(10.0, -5.0),
+                MinimalStar::new(3, 300.0, 0.0, 10.0),
+                MinimalStar::new(4, 300.0, 0.3 * arcsec, 10.5),
+                MinimalStar::new(5, 10.0, 10.0, 11.0),
+            ],
+            Some(1991.0),
+        );
+        let right = catalog(
+            vec![
+                MinimalStar::new(11, 120.0, -30.0 + 25.0 * arcsec, 6.1),
+                MinimalStar::new(12, 200.0, 45.0 + 0.2 * arcsec, 9.1),
+                MinimalStar::new(13, 300.0, 0.4 * arcsec, 10.2),
+                MinimalStar::new(14, 50.0, -60.0, 12.0),
+            ],
+            Some(2016.0),
+        );
+
+        let result = crossmatch(&left, &right, 1.0);
+        assert_eq!(result.epoch, Some(2016.0));
+        let pairs: Vec<(u64, u64)> = result
+            .matches
+            .iter()
+            .map(|pair| (pair.left.id, pair.right.id))
+            .collect();
+        assert_eq!(pairs, vec![(1, 11), (2, 12), (4, 13)]);
+        assert_relative_eq!(result.matches[0].separation_arcsec, 0.0, epsilon = 1e-6);
+        assert_relative_eq!(result.matches[2].separation_arcsec, 0.1, epsilon = 1e-6);
+        let unmatched: Vec<u64> = result.unmatched_left.iter().map(|s| s.id).collect();
+        assert_eq!(unmatched, vec![3, 5]);
+        assert_eq!(result.unmatched_right.len(), 1);
+        assert_eq!(result.unmatched_right[0].id, 14);
+        assert_relative_eq!(result.match_fraction(), 0.6);
+
+        // Compared at the left epoch, the right catalog stays put: it has
+        // no proper motions, so the fast mover is lost
+        let at_left = CrossMatch::new(1.0).with_epoch(1991.0).run(&left, &right);
+        assert_eq!(at_left.matches.len(), 2);
+
+        // Without epochs nothing is moved either
+        let left = catalog(left.stars().to_vec(), None);
+        let right = catalog(right.stars().to_vec(), None);
+        let result = crossmatch(&left, &right, 1.0);
+        assert_eq!(result.epoch, None);
+        assert_eq!(result.matches.len(), 2);
+    }
+}
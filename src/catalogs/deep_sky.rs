@@ -0,0 +1,487 @@
+//! Messier, NGC and IC deep-sky objects
+//!
+//! The 110 Messier objects are bundled with the crate; the roughly 13,000
+//! NGC and IC objects are read from the semicolon-separated `NGC.csv` of
+//! the OpenNGC project, which [`crate::data::download_openngc`] fetches
+//! into the cache. Objects are found by designation, ignoring case, spacing
+//! and leading zeros, or by common name:
+//!
+//! ```
+//! use starfield::catalogs::{DeepSkyCatalog, DeepSkyType};
+//!
+//! let messier = DeepSkyCatalog::messier();
+//! let andromeda = messier.get("M31").unwrap();
+//! assert_eq!(andromeda.designation, "NGC 224");
+//! assert_eq!(andromeda.object_type, DeepSkyType::Galaxy);
+//! assert!(messier.get("ngc0224").is_some());
+//! assert!(messier.get("Ring Nebula").is_some());
+//! ```
+//!
+//! Positions are J2000, sizes the major and minor axes in arcminutes, and
+//! magnitudes visual where known and blue otherwise. Surface brightness is
+//! taken from the catalog or, failing that, spread the magnitude over the
+//! ellipse of the object's axes.
+
+use super::features::{FeatureType, SkyFeature};
+use crate::coordinates::Equatorial;
+use crate::units::Angle;
+use crate::{Result, StarfieldError};
+use std::collections::HashMap;
+use std::path::Path;
+
+/// The Messier catalog, in the column layout of OpenNGC
+const MESSIER_CSV: &str = include_str!("messier.csv");
+
+/// Kind of deep-sky object, following OpenNGC's type codes
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum DeepSkyType {
+    /// A single star (`*`)
+    Star,
+    /// A double star (`**`)
+    DoubleStar,
+    /// An asterism or stellar association (`*Ass`)
+    Asterism,
+    /// A dense patch of the Milky Way (`*Cld`)
+    StarCloud,
+    /// An open cluster (`OCl`)
+    OpenCluster,
+    /// A globular cluster (`GCl`)
+    GlobularCluster,
+    /// A star cluster with nebulosity (`Cl+N`)
+    ClusterWithNebula,
+    /// A galaxy (`G`)
+    Galaxy,
+    /// A pair, triplet or group of galaxies (`GPair`, `GTrpl`, `GGroup`)
+    GalaxyGroup,
+    /// A planetary nebula (`PN`)
+    PlanetaryNebula,
+    /// An HII region (`HII`)
+    HiiRegion,
+    /// An emission nebula (`EmN`)
+    EmissionNebula,
+    /// A reflection nebula (`RfN`)
+    ReflectionNebula,
+    /// A dark nebula (`DrkN`)
+    DarkNebula,
+    /// A nebula of unspecified kind (`Neb`)
+    Nebula,
+    /// A supernova remnant (`SNR`)
+    SupernovaRemnant,
+    /// A nova (`Nova`)
+    Nova,
+    /// Anything else
+    Other,
+}
+
+impl DeepSkyType {
+    /// Type for an OpenNGC code; `None` for duplicate and nonexistent
+    /// entries
+    pub fn from_code(code: &str) -> Option<Self> {
+        Some(match code.trim() {
+            "*" => Self::Star,
+            "**" => Self::DoubleStar,
+            "*Ass" => Self::Asterism,
+            "*Cld" => Self::StarCloud,
+            "OCl" => Self::OpenCluster,
+            "GCl" => Self::GlobularCluster,
+            "Cl+N" => Self::ClusterWithNebula,
+            "G" => Self::Galaxy,
+            "GPair" | "GTrpl" | "GGroup" => Self::GalaxyGroup,
+            "PN" => Self::PlanetaryNebula,
+            "HII" => Self::HiiRegion,
+            "EmN" => Self::EmissionNebula,
+            "RfN" => Self::ReflectionNebula,
+            "DrkN" => Self::DarkNebula,
+            "Neb" => Self::Nebula,
+            "SNR" => Self::SupernovaRemnant,
+            "Nova" => Self::Nova,
+            "Dup" | "NonEx" => return None,
+            _ => Self::Other,
+        })
+    }
+
+    /// The broader [`FeatureType`] the object belongs to
+    pub fn feature_type(&self) -> FeatureType {
+        match self {
+            Self::Star | Self::DoubleStar => FeatureType::Star,
+            Self::OpenCluster | Self::Asterism | Self::StarCloud => FeatureType::OpenCluster,
+            Self::GlobularCluster => FeatureType::GlobularCluster,
+            Self::Galaxy | Self::GalaxyGroup => FeatureType::Galaxy,
+            Self::ClusterWithNebula
+            | Self::PlanetaryNebula
+            | Self::HiiRegion
+            | Self::EmissionNebula
+            | Self::ReflectionNebula
+            | Self::DarkNebula
+            | Self::Nebula
+            | Self::SupernovaRemnant => FeatureType::Nebula,
+            Self::Nova | Self::Other => FeatureType::Other,
+        }
+    }
+}
+
+/// One Messier, NGC or IC object
+#[derive(Debug, Clone, PartialEq)]
+pub struct DeepSkyObject {
+    /// Catalog designation, such as `NGC 7000` or `IC 434`
+    pub designation: String,
+    /// Number in Messier's catalog
+    pub messier: Option<u16>,
+    /// Common names, such as `Andromeda Galaxy`
+    pub names: Vec<String>,
+    /// Kind of object
+    pub object_type: DeepSkyType,
+    /// J2000 position
+    pub position: Equatorial,
+    /// Major axis in arcminutes
+    pub major_axis_arcmin: Option<f64>,
+    /// Minor axis in arcminutes
+    pub minor_axis_arcmin: Option<f64>,
+    /// Position angle of the major axis, degrees east of north
+    pub position_angle_deg: Option<f64>,
+    /// Integrated magnitude, visual where known and blue otherwise
+    pub magnitude: Option<f64>,
+    /// Mean surface brightness, magnitudes per square arcsecond
+    pub surface_brightness: Option<f64>,
+}
+
+impl DeepSkyObject {
+    /// Messier designation (`M31`), or else the catalog designation
+    pub fn label(&self) -> String {
+        match self.messier {
+            Some(number) => format!("M{}", number),
+            None => self.designation.clone(),
+        }
+    }
+
+    /// Largest extent in arcminutes, if known
+    pub fn size_arcmin(&self) -> Option<f64> {
+        self.major_axis_arcmin.or(self.minor_axis_arcmin)
+    }
+
+    /// The object as a [`SkyFeature`] for a [`super::FeatureCatalog`]
+    pub fn to_feature(&self) -> SkyFeature {
+        let description = match self.names.first() {
+            Some(name) => format!("{} ({})", name, self.designation),
+            None => self.designation.clone(),
+        };
+        SkyFeature::new(
+            &self.label(),
+            self.object_type.feature_type(),
+            self.position.ra_degrees(),
+            self.position.dec_degrees(),
+            self.size_arcmin().unwrap_or(0.0) / 60.0,
+            &description,
+        )
+    }
+}
+
+/// Magnitude spread over an ellipse with the given axes in arcminutes
+fn mean_surface_brightness(magnitude: f64, major_arcmin: f64, minor_arcmin: f64) -> f64 {
+    let area_arcsec2 = std::f64::consts::FRAC_PI_4 * major_arcmin * minor_arcmin * 3600.0;
+    magnitude + 2.5 * area_arcsec2.log10()
+}
+
+/// A catalog number without its leading zeros, keeping a lone zero
+fn trim_zeros(number: &str) -> &str {
+    let trimmed = number.trim_start_matches('0');
+    match trimmed.starts_with(|c: char| c.is_ascii_digit()) {
+        true => trimmed,
+        false => &number[number.len() - trimmed.len() - 1..],
+    }
+}
+
+/// Key a designation or name is looked up by: upper case, without spaces
+/// or the leading zeros of its number
+fn lookup_key(designation: &str) -> String {
+    let compact: String = designation
+        .split_whitespace()
+        .collect::<String>()
+        .to_ascii_uppercase();
+    match compact.find(|c: char| c.is_ascii_digit()) {
+        Some(start) => format!("{}{}", &compact[..start], trim_zeros(&compact[start..])),
+        None => compact,
+    }
+}
+
+/// Designation in its usual form: `NGC0224` becomes `NGC 224`, `M024`
+/// becomes `M24`, anything else is kept
+fn display_designation(name: &str) -> String {
+    let name = name.trim();
+    for (prefix, separator) in [("NGC", " "), ("IC", " "), ("M", "")] {
+        if let Some(number) = name.strip_prefix(prefix) {
+            if number.starts_with(|c: char| c.is_ascii_digit()) {
+                return format!("{}{}{}", prefix, separator, trim_zeros(number));
+            }
+        }
+    }
+    name.to_string()
+}
+
+/// A catalog of deep-sky objects, searchable by designation and name
+#[derive(Debug, Clone, Default)]
+pub struct DeepSkyCatalog {
+    objects: Vec<DeepSkyObject>,
+    index: HashMap<String, usize>,
+}
+
+impl DeepSkyCatalog {
+    /// The 110 Messier objects, from the data bundled with the crate
+    pub fn messier() -> Self {
+        Self::parse(MESSIER_CSV).expect("bundled Messier catalog is valid")
+    }
+
+    /// Parse an OpenNGC `NGC.csv`, locating columns by the header names
+    ///
+    /// Duplicate and nonexistent entries are skipped, as are rows without
+    /// a position.
+    pub fn parse(text: &str) -> Result<Self> {
+        let mut lines = text.lines();
+        let header: Vec<&str> = lines
+            .next()
+            .ok_or_else(|| StarfieldError::DataError("empty deep-sky catalog".to_string()))?
+            .split(';')
+            .map(str::trim)
+            .collect();
+        let column = |name: &str| header.iter().position(|h| *h == name);
+        let required = |name: &str| {
+            column(name).ok_or_else(|| {
+                StarfieldError::DataError(format!("deep-sky catalog has no {} column", name))
+            })
+        };
+        let (name, kind, ra, dec) = (
+            required("Name")?,
+            required("Type")?,
+            required("RA")?,
+            required("Dec")?,
+        );
+        let (major, minor, angle) = (column("MajAx"), column("MinAx"), column("PosAng"));
+        let (v_mag, b_mag, surface) = (column("V-Mag"), column("B-Mag"), column("SurfBr"));
+        let (messier, names) = (column("M"), column("Common names"));
+
+        let mut catalog = Self::default();
+        for line in lines.filter(|line| !line.trim().is_empty()) {
+            let fields: Vec<&str> = line.split(';').map(str::trim).collect();
+            let field = |index: Option<usize>| {
+                index
+                    .and_then(|i| fields.get(i))
+                    .copied()
+                    .filter(|f| !f.is_empty())
+            };
+            let number = |index: Option<usize>| field(index).and_then(|f| f.parse::<f64>().ok());
+
+            let Some(object_type) = field(Some(kind)).and_then(DeepSkyType::from_code) else {
+                continue;
+            };
+            let position = match (field(Some(ra)), field(Some(dec))) {
+                (Some(ra), Some(dec)) => Equatorial::from_degrees(
+                    Angle::parse_hours(ra)?.degrees(),
+                    Angle::parse_degrees(dec)?.degrees(),
+                ),
+                _ => continue,
+            };
+
+            let major_axis_arcmin = number(major);
+            let minor_axis_arcmin = number(minor).or(major_axis_arcmin);
+            let magnitude = number(v_mag).or(number(b_mag));
+            let surface_brightness = number(surface).or_else(|| {
+                Some(mean_surface_brightness(
+                    magnitude?,
+                    major_axis_arcmin?,
+                    minor_axis_arcmin?,
+                ))
+            });
+
+            catalog.push(DeepSkyObject {
+                designation: display_designation(field(Some(name)).unwrap_or_default()),
+                messier: field(messier).and_then(|m| m.parse().ok()),
+                names: field(names)
+                    .map(|n| n.split(',').map(|s| s.trim().to_string()).collect())
+                    .unwrap_or_default(),
+                object_type,
+                position,
+                major_axis_arcmin,
+                minor_axis_arcmin,
+                position_angle_deg: number(angle),
+                magnitude,
+                surface_brightness,
+            });
+        }
+        if catalog.is_empty() && text.lines().skip(1).any(|line| !line.trim().is_empty()) {
+            return Err(StarfieldError::DataError(
+                "no deep-sky objects found".to_string(),
+            ));
+        }
+        Ok(catalog)
+    }
+
+    /// Load an OpenNGC `NGC.csv` from disk
+    pub fn from_file<P: AsRef<Path>>(path: P) -> Result<Self> {
+        Self::parse(&std::fs::read_to_string(path)?)
+    }
+
+    /// Add an object, indexing its designation, Messier number and names
+    ///
+    /// Keys already taken keep pointing at the earlier object.
+    pub fn push(&mut self, object: DeepSkyObject) {
+        let position = self.objects.len();
+        let keys = std::iter::once(object.designation.clone())
+            .chain(object.messier.map(|m| format!("M{}", m)))
+            .chain(object.names.iter().cloned());
+        for key in keys {
+            self.index.entry(lookup_key(&key)).or_insert(position);
+        }
+        self.objects.push(object);
+    }
+
+    /// Add the objects of `other` that are not already here
+    ///
+    /// An object already present takes its Messier number and names from
+    /// `other` when it has none, so merging the Messier catalog into an
+    /// NGC/IC catalog makes every Messier object findable by number.
+    pub fn merge(&mut self, other: DeepSkyCatalog) {
+        for object in other.objects {
+            let Some(&existing) = self.index.get(&lookup_key(&object.designation)) else {
+                self.push(object);
+                continue;
+            };
+            let target = &mut self.objects[existing];
+            if target.messier.is_none() {
+                target.messier = object.messier;
+            }
+            if target.names.is_empty() {
+                target.names = object.names.clone();
+            }
+            let keys = object.messier.map(|m| format!("M{}", m)).into_iter();
+            for key in keys.chain(object.names) {
+                self.index.entry(lookup_key(&key)).or_insert(existing);
+            }
+        }
+    }
+
+    /// Look up an object by designation (`M31`, `NGC 7000`, `ic434`) or
+    /// common name
+    pub fn get(&self, designation: &str) -> Option<&DeepSkyObject> {
+        self.index
+            .get(&lookup_key(designation))
+            .map(|&i| &self.objects[i])
+    }
+
+    /// All objects, in catalog order
+    pub fn objects(&self) -> &[DeepSkyObject] {
+        &self.objects
+    }
+
+    /// Objects of one type
+    pub fn of_type(&self, object_type: DeepSkyType) -> impl Iterator<Item = &DeepSkyObject> {
+        self.objects
+            .iter()
+            .filter(move |o| o.object_type == object_type)
+    }
+
+    /// Objects whose centers lie within `radius_deg` of `center`
+    pub fn within(&self, center: &Equatorial, radius_deg: f64) -> Vec<&DeepSkyObject> {
+        let radius = radius_deg.to_radians();
+        self.objects
+            .iter()
+            .filter(|o| o.position.angular_distance(center) <= radius)
+            .collect()
+    }
+
+    /// Number of objects
+    pub fn len(&self) -> usize {
+        self.objects.len()
+    }
+
+    /// Whether the catalog is empty
+    pub fn is_empty(&self) -> bool {
+        self.objects.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use approx::assert_relative_eq;
+
+    /// Rows in the layout of OpenNGC's `NGC.csv`, trimmed to a few columns
+    const SAMPLE: &str = "\
+Name;Type;RA;Dec;Const;MajAx;MinAx;PosAng;B-Mag;V-Mag;SurfBr;M;NGC;IC;Common names
+IC0434;DrkN;05:41:00.88;-02:27:13.6;Ori;60.00;10.00;;;;;;;;Horsehead Nebula
+NGC0224;G;00:42:44.35;+41:16:08.6;And;177.83;69.66;35;4.29;3.44;23.63;031;;;Andromeda Galaxy
+NGC0225;Dup;00:43:29.8;+61:47:08;Cas;;;;;;;;;;
+NGC6543;PN;17:58:33.42;+66:37:59.5;Dra;0.33;0.33;;8.85;8.10;;;;;Cat's Eye Nebula
+NGC7000;HII;20:59:17.14;+44:31:43.6;Cyg;120.00;100.00;;;4.00;;;;;North America Nebula
+";
+
+    #[test]
+    fn test_parse_openngc() {
+        let catalog = DeepSkyCatalog::parse(SAMPLE).unwrap();
+        assert_eq!(catalog.len(), 4);
+
+        let m31 = catalog.get("M 31").unwrap();
+        assert_eq!(m31.designation, "NGC 224");
+        assert_eq!(m31.label(), "M31");
+        assert_eq!(m31.magnitude, Some(3.44));
+        assert_eq!(m31.surface_brightness, Some(23.63));
+        assert_eq!(m31.position_angle_deg, Some(35.0));
+        assert_relative_eq!(m31.position.ra_degrees(), 10.684_792, epsilon = 1e-6);
+
+        let horsehead = catalog.get("IC 434").unwrap();
+        assert_eq!(horsehead.object_type, DeepSkyType::DarkNebula);
+        assert_eq!(horsehead.magnitude, None);
+        assert_eq!(horsehead.surface_brightness, None);
+        assert!(catalog.get("NGC 225").is_none());
+
+        // Surface brightness from the magnitude and size: 4.0 mag spread
+        // over a 120' x 100' ellipse
+        let nan = catalog.get("north america nebula").unwrap();
+        assert_eq!(nan.designation, "NGC 7000");
+        assert_relative_eq!(
+            nan.surface_brightness.unwrap(),
+            4.0 + 2.5 * (std::f64::consts::FRAC_PI_4 * 120.0 * 100.0 * 3600.0).log10(),
+            epsilon = 1e-9
+        );
+        assert_eq!(catalog.get("ngc7000"), Some(nan));
+
+        let cygnus = Equatorial::from_degrees(315.0, 44.0);
+        let nearby: Vec<&str> = catalog
+            .within(&cygnus, 5.0)
+            .iter()
+            .map(|o| o.designation.as_str())
+            .collect();
+        assert_eq!(nearby, vec!["NGC 7000"]);
+
+        assert!(DeepSkyCatalog::parse("Name;Type\nNGC0001;G\n").is_err());
+    }
+
+    #[test]
+    fn test_messier_catalog() {
+        let messier = DeepSkyCatalog::messier();
+        assert_eq!(messier.len(), 110);
+        for number in 1..=110 {
+            let object = messier
+                .get(&format!("M{}", number))
+                .unwrap_or_else(|| panic!("M{} missing", number));
+            assert_eq!(object.messier, Some(number));
+            assert!(object.magnitude.is_some() && object.surface_brightness.is_some());
+        }
+        assert_eq!(messier.get("M45").unwrap().names[0], "Pleiades");
+        assert_eq!(messier.get("m024").unwrap().designation, "M24");
+        assert_eq!(messier.get("IC 4725").unwrap().label(), "M25");
+        assert_eq!(messier.of_type(DeepSkyType::GlobularCluster).count(), 29);
+
+        let feature = messier.get("Orion Nebula").unwrap().to_feature();
+        assert_eq!(feature.name, "M42");
+        assert_eq!(feature.feature_type, FeatureType::Nebula);
+        assert_relative_eq!(feature.diameter_deg, 85.0 / 60.0);
+
+        // Merged into an NGC/IC catalog, Messier numbers reach the NGC rows
+        // and the objects outside NGC/IC are added
+        let mut catalog = DeepSkyCatalog::parse(SAMPLE).unwrap();
+        catalog.merge(messier);
+        assert_eq!(catalog.len(), 4 + 109);
+        assert_eq!(catalog.get("M31").unwrap().surface_brightness, Some(23.63));
+        assert_eq!(catalog.get("M57").unwrap().designation, "NGC 6720");
+    }
+}
@@ -0,0 +1,310 @@
+//! Tabular export of binary catalogs
+//!
+//! [`BinaryCatalog::export_csv`] writes a catalog as comma-separated text,
+//! one star per row, for pandas and other table tools. With the `parquet`
+//! feature, [`BinaryCatalog::export_parquet`] writes the same table as an
+//! Apache Parquet file. [`ExportOptions`] chooses the columns and drops
+//! stars fainter than a magnitude limit:
+//!
+//! ```no_run
+//! use starfield::catalogs::{BinaryCatalog, ExportColumn, ExportOptions};
+//!
+//! let catalog = BinaryCatalog::load("gaia_g12.bin")?;
+//! let options = ExportOptions::default()
+//!     .with_columns(&[ExportColumn::Id, ExportColumn::Ra, ExportColumn::Dec])
+//!     .with_magnitude_limit(9.0);
+//! let rows = catalog.export_csv_with("bright.csv", &options)?;
+//! println!("wrote {} stars", rows);
+//! # Ok::<(), starfield::StarfieldError>(())
+//! ```
+//!
+//! Positions are in degrees, proper motions (μα*, μδ) in mas/yr and
+//! parallaxes in mas. Fields a star lacks are left empty in CSV and null in
+//! Parquet.
+
+use super::{BinaryCatalog, MinimalStar};
+use crate::StarfieldError;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::Path;
+
+/// A column of an exported catalog
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ExportColumn {
+    /// Star identifier
+    Id,
+    /// Right ascension in degrees
+    Ra,
+    /// Declination in degrees
+    Dec,
+    /// Apparent magnitude
+    Magnitude,
+    /// Proper motion in right ascension, including the cos δ factor, mas/yr
+    PmRa,
+    /// Proper motion in declination, mas/yr
+    PmDec,
+    /// Parallax in mas
+    Parallax,
+    /// Color index in the catalog's system
+    ColorIndex,
+}
+
+impl ExportColumn {
+    /// Every column, in export order
+    pub const ALL: [ExportColumn; 8] = [
+        ExportColumn::Id,
+        ExportColumn::Ra,
+        ExportColumn::Dec,
+        ExportColumn::Magnitude,
+        ExportColumn::PmRa,
+        ExportColumn::PmDec,
+        ExportColumn::Parallax,
+        ExportColumn::ColorIndex,
+    ];
+
+    /// Header of the column
+    pub fn name(&self) -> &'static str {
+        match self {
+            ExportColumn::Id => "id",
+            ExportColumn::Ra => "ra_deg",
+            ExportColumn::Dec => "dec_deg",
+            ExportColumn::Magnitude => "magnitude",
+            ExportColumn::PmRa => "pm_ra_mas_yr",
+            ExportColumn::PmDec => "pm_dec_mas_yr",
+            ExportColumn::Parallax => "parallax_mas",
+            ExportColumn::ColorIndex => "color_index",
+        }
+    }
+
+    /// Value of a floating-point column for `star`; `None` for the id
+    fn value(&self, star: &MinimalStar) -> Option<f64> {
+        match self {
+            ExportColumn::Id => None,
+            ExportColumn::Ra => Some(star.position.ra_degrees()),
+            ExportColumn::Dec => Some(star.position.dec_degrees()),
+            ExportColumn::Magnitude => Some(star.magnitude),
+            ExportColumn::PmRa => star.proper_motion_mas_yr.map(|pm| pm.0),
+            ExportColumn::PmDec => star.proper_motion_mas_yr.map(|pm| pm.1),
+            ExportColumn::Parallax => star.parallax_mas,
+            ExportColumn::ColorIndex => star.color_index,
+        }
+    }
+}
+
+/// Columns and stars to export
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ExportOptions {
+    /// Columns in order; `None` exports the core columns and the optional
+    /// fields the catalog stores
+    pub columns: Option<Vec<ExportColumn>>,
+    /// Faintest magnitude exported
+    pub magnitude_limit: Option<f64>,
+}
+
+impl ExportOptions {
+    /// Export these columns, in this order
+    pub fn with_columns(mut self, columns: &[ExportColumn]) -> Self {
+        self.columns = Some(columns.to_vec());
+        self
+    }
+
+    /// Export only stars at or brighter than `magnitude`
+    pub fn with_magnitude_limit(mut self, magnitude: f64) -> Self {
+        self.magnitude_limit = Some(magnitude);
+        self
+    }
+
+    /// Columns to export from `catalog`
+    fn columns_for(&self, catalog: &BinaryCatalog) -> Vec<ExportColumn> {
+        match &self.columns {
+            Some(columns) => columns.clone(),
+            None => {
+                let fields = catalog.fields();
+                ExportColumn::ALL
+                    .into_iter()
+                    .filter(|column| match column {
+                        ExportColumn::PmRa | ExportColumn::PmDec => fields.proper_motion,
+                        ExportColumn::Parallax => fields.parallax,
+                        ExportColumn::ColorIndex => fields.color_index,
+                        _ => true,
+                    })
+                    .collect()
+            }
+        }
+    }
+
+    /// Stars of `catalog` within the magnitude limit
+    fn stars<'a>(&self, catalog: &'a BinaryCatalog) -> impl Iterator<Item = &'a MinimalStar> {
+        let limit = self.magnitude_limit.unwrap_or(f64::INFINITY);
+        catalog
+            .stars()
+            .iter()
+            .filter(move |star| star.magnitude <= limit)
+    }
+}
+
+impl BinaryCatalog {
+    /// Write every star as CSV, with the core columns and any optional
+    /// fields the catalog stores; returns the number of stars written
+    pub fn export_csv<P: AsRef<Path>>(&self, path: P) -> Result<usize, StarfieldError> {
+        self.export_csv_with(path, &ExportOptions::default())
+    }
+
+    /// Write the stars and columns chosen by `options` as CSV; returns the
+    /// number of stars written
+    pub fn export_csv_with<P: AsRef<Path>>(
+        &self,
+        path: P,
+        options: &ExportOptions,
+    ) -> Result<usize, StarfieldError> {
+        let columns = options.columns_for(self);
+        let mut writer = BufWriter::new(File::create(path)?);
+        let header: Vec<&str> = columns.iter().map(|column| column.name()).collect();
+        writeln!(writer, "{}", header.join(","))?;
+
+        let mut rows = 0;
+        for star in options.stars(self) {
+            let fields: Vec<String> = columns
+                .iter()
+                .map(|column| match column {
+                    ExportColumn::Id => star.id.to_string(),
+                    _ => column
+                        .value(star)
+                        .map(|v| v.to_string())
+                        .unwrap_or_default(),
+                })
+                .collect();
+            writeln!(writer, "{}", fields.join(","))?;
+            rows += 1;
+        }
+        writer.flush()?;
+        Ok(rows)
+    }
+
+    /// Write the stars and columns chosen by `options` as a Parquet file;
+    /// returns the number of stars written
+    ///
+    /// The id is an unsigned 64-bit column and the others nullable doubles.
+    #[cfg(feature = "parquet")]
+    pub fn export_parquet<P: AsRef<Path>>(
+        &self,
+        path: P,
+        options: &ExportOptions,
+    ) -> Result<usize, StarfieldError> {
+        use arrow_array::{ArrayRef, Float64Array, RecordBatch, UInt64Array};
+        use arrow_schema::{DataType, Field, Schema};
+        use parquet::arrow::ArrowWriter;
+        use std::sync::Arc;
+
+        let parquet_error = |e: &dyn std::fmt::Display| {
+            StarfieldError::DataError(format!("Parquet export failed: {}", e))
+        };
+
+        let columns = options.columns_for(self);
+        let stars: Vec<&MinimalStar> = options.stars(self).collect();
+        let (fields, arrays): (Vec<Field>, Vec<ArrayRef>) = columns
+            .iter()
+            .map(|column| match column {
+                ExportColumn::Id => (
+                    Field::new(column.name(), DataType::UInt64, false),
+                    Arc::new(UInt64Array::from_iter_values(stars.iter().map(|s| s.id))) as ArrayRef,
+                ),
+                _ => (
+                    Field::new(column.name(), DataType::Float64, true),
+                    Arc::new(Float64Array::from_iter(
+                        stars.iter().map(|s| column.value(s)),
+                    )) as ArrayRef,
+                ),
+            })
+            .unzip();
+
+        let schema = Arc::new(Schema::new(fields));
+        let batch = RecordBatch::try_new(schema.clone(), arrays).map_err(|e| parquet_error(&e))?;
+        let mut writer = ArrowWriter::try_new(File::create(path)?, schema, None)
+            .map_err(|e| parquet_error(&e))?;
+        writer.write(&batch).map_err(|e| parquet_error(&e))?;
+        writer.close().map_err(|e| parquet_error(&e))?;
+        Ok(stars.len())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    fn catalog() -> BinaryCatalog {
+        BinaryCatalog::from_stars(
+            vec![
+                MinimalStar::new(1, 10.5, -20.25, 4.0).with_proper_motion(12.5, -3.0),
+                MinimalStar::new(2, 200.0, 45.0, 11.0).with_parallax(2.5),
+                MinimalStar::new(3, 300.125, 0.0, 8.5),
+            ],
+            "Export test",
+        )
+    }
+
+    #[test]
+    fn test_export_csv() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("stars.csv");
+
+        assert_eq!(catalog().export_csv(&path).unwrap(), 3);
+        assert_eq!(
+            std::fs::read_to_string(&path).unwrap(),
+            "id,ra_deg,dec_deg,magnitude,pm_ra_mas_yr,pm_dec_mas_yr,parallax_mas\n\
+             1,10.5,-20.25,4,12.5,-3,\n\
+             2,200,45,11,,,2.5\n\
+             3,300.125,0,8.5,,,\n"
+        );
+
+        let options = ExportOptions::default()
+            .with_columns(&[
+                ExportColumn::Id,
+                ExportColumn::Magnitude,
+                ExportColumn::ColorIndex,
+            ])
+            .with_magnitude_limit(9.0);
+        assert_eq!(catalog().export_csv_with(&path, &options).unwrap(), 2);
+        assert_eq!(
+            std::fs::read_to_string(&path).unwrap(),
+            "id,magnitude,color_index\n1,4,\n3,8.5,\n"
+        );
+    }
+
+    #[cfg(feature = "parquet")]
+    #[test]
+    fn test_export_parquet() {
+        use arrow_array::{Array, Float64Array, UInt64Array};
+        use parquet::arrow::arrow_reader::ParquetRecordBatchReaderBuilder;
+
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("stars.parquet");
+        let options = ExportOptions::default().with_magnitude_limit(11.0);
+        assert_eq!(catalog().export_parquet(&path, &options).unwrap(), 3);
+
+        let reader = ParquetRecordBatchReaderBuilder::try_new(File::open(&path).unwrap())
+            .unwrap()
+            .build()
+            .unwrap();
+        let batches: Vec<_> = reader.map(|batch| batch.unwrap()).collect();
+        assert_eq!(batches.len(), 1);
+        let batch = &batches[0];
+        assert_eq!(batch.num_columns(), 7);
+        let ids = batch
+            .column_by_name("id")
+            .unwrap()
+            .as_any()
+            .downcast_ref::<UInt64Array>()
+            .unwrap();
+        assert_eq!(ids.values().to_vec(), vec![1, 2, 3]);
+        let parallax = batch
+            .column_by_name("parallax_mas")
+            .unwrap()
+            .as_any()
+            .downcast_ref::<Float64Array>()
+            .unwrap();
+        assert!(parallax.is_null(0));
+        assert_eq!(parallax.value(1), 2.5);
+    }
+}
@@ -11,6 +11,7 @@ use std::collections::HashMap;
 
 /// Represents a region of interest in the sky
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct SkyFeature {
     /// Name of the feature
     pub name: String,
@@ -28,6 +29,7 @@ pub struct SkyFeature {
 
 /// Types of astronomical features
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum FeatureType {
     /// A constellation (formal IAU division of the sky)
     Constellation,
@@ -1,18 +1,166 @@
 //! Gaia star catalog implementation
 //!
 //! This module provides functionality for loading and using the Gaia star catalog.
+//!
+//! Column sets differ between data releases, so the release of a file is
+//! detected from its header (or given explicitly as a [`DataRelease`]) and
+//! checked against the columns that release is known to provide.
+//!
+//! Only positions, astrometry and G photometry are kept by default. BP/RP
+//! photometry, radial velocities and RUWE are read as well when asked for
+//! with [`GaiaColumns::Extended`]:
+//!
+//! ```no_run
+//! use starfield::catalogs::{GaiaCatalog, GaiaCatalogReader, GaiaColumns};
+//!
+//! let reader = GaiaCatalogReader::open("GaiaSource_000000-003111.csv.gz", 12.0)?
+//!     .with_columns(GaiaColumns::Extended);
+//! let catalog = GaiaCatalog::from_reader(reader)?;
+//! # Ok::<(), starfield::StarfieldError>(())
+//! ```
 
 use nalgebra as na;
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{HashMap, VecDeque};
+use std::fmt;
 use std::fs::File;
-use std::io::{BufRead, BufReader};
-use std::path::Path;
+use std::io::{BufRead, BufReader, Lines};
+use std::path::{Path, PathBuf};
 
-use super::{StarCatalog, StarData};
+use super::photometry::johnson_v_from_gaia;
+use super::spatial_index::IndexCache;
+use super::{ProperMotion, SkyIndex, StarCatalog, StarData};
 use crate::Result;
 use crate::StarfieldError;
 
+/// Gaia data release a catalog file was exported from
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum DataRelease {
+    /// Gaia DR1 (2016)
+    Dr1,
+    /// Gaia DR2 (2018)
+    Dr2,
+    /// Gaia Early DR3 (2020)
+    Edr3,
+    /// Gaia DR3 (2022)
+    Dr3,
+    /// Gaia DR4, assumed to follow the DR3 schema until it is published
+    Dr4,
+}
+
+/// Columns every release provides and the loader needs
+const CORE_COLUMNS: &[&str] = &[
+    "source_id",
+    "solution_id",
+    "ra",
+    "dec",
+    "ra_error",
+    "dec_error",
+    "parallax",
+    "parallax_error",
+    "pmra",
+    "pmdec",
+    "phot_g_mean_mag",
+    "phot_g_mean_flux",
+    "l",
+    "b",
+    "ecl_lon",
+    "ecl_lat",
+];
+
+impl DataRelease {
+    /// Reference epoch of the positions, as a Julian year
+    pub fn reference_epoch(&self) -> f64 {
+        match self {
+            DataRelease::Dr1 => 2015.0,
+            DataRelease::Dr2 => 2015.5,
+            DataRelease::Edr3 | DataRelease::Dr3 => 2016.0,
+            DataRelease::Dr4 => 2017.5,
+        }
+    }
+
+    /// Guess the release from a file's (lower-case) column names
+    ///
+    /// Relies on columns introduced or renamed by each release: XP spectra
+    /// flags in DR3, `dr2_radial_velocity` in EDR3, the chromaticity fields of
+    /// the EDR3 astrometric solution, and BP/RP photometry from DR2 onwards.
+    /// DR4 cannot be told from DR3 and must be requested explicitly.
+    pub fn detect<S: AsRef<str>>(headers: &[S]) -> Self {
+        let has = |name: &str| headers.iter().any(|h| h.as_ref() == name);
+
+        if has("has_xp_continuous") || has("has_xp_sampled") {
+            DataRelease::Dr3
+        } else if has("dr2_radial_velocity") {
+            DataRelease::Edr3
+        } else if has("nu_eff_used_in_astrometry") {
+            if has("radial_velocity") {
+                DataRelease::Dr3
+            } else {
+                DataRelease::Edr3
+            }
+        } else if has("phot_bp_mean_mag") || has("astrometric_pseudo_colour") {
+            DataRelease::Dr2
+        } else {
+            DataRelease::Dr1
+        }
+    }
+
+    /// Columns a file from this release must contain
+    pub fn required_columns(&self) -> Vec<&'static str> {
+        let mut columns = CORE_COLUMNS.to_vec();
+        // EDR3 dropped the variability flag; DR3 restored it
+        if matches!(self, DataRelease::Dr1 | DataRelease::Dr2 | DataRelease::Dr3) {
+            columns.push("phot_variable_flag");
+        }
+        columns
+    }
+}
+
+impl fmt::Display for DataRelease {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            DataRelease::Dr1 => "DR1",
+            DataRelease::Dr2 => "DR2",
+            DataRelease::Edr3 => "EDR3",
+            DataRelease::Dr3 => "DR3",
+            DataRelease::Dr4 => "DR4",
+        };
+        write!(f, "Gaia {}", name)
+    }
+}
+
+/// Which columns of a Gaia file to keep
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum GaiaColumns {
+    /// Positions, astrometry and G photometry
+    #[default]
+    Core,
+    /// Also BP/RP photometry, radial velocity and RUWE, where the file has
+    /// them
+    Extended,
+}
+
+/// Columns read only with [`GaiaColumns::Extended`]
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct GaiaExtras {
+    /// BP-band mean magnitude
+    pub phot_bp_mean_mag: Option<f64>,
+    /// RP-band mean magnitude
+    pub phot_rp_mean_mag: Option<f64>,
+    /// Radial velocity (km/s)
+    pub radial_velocity: Option<f64>,
+    /// Renormalised unit weight error of the astrometric solution (EDR3
+    /// onwards); above about 1.4 the single-star solution is poor
+    pub ruwe: Option<f64>,
+}
+
+impl GaiaExtras {
+    /// BP - RP colour index
+    pub fn bp_rp(&self) -> Option<f64> {
+        Some(self.phot_bp_mean_mag? - self.phot_rp_mean_mag?)
+    }
+}
+
 /// Struct representing an entry in the Gaia catalog
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct GaiaEntry {
@@ -20,9 +168,9 @@ pub struct GaiaEntry {
     pub source_id: u64,
     /// Solution ID
     pub solution_id: u64,
-    /// Right ascension in degrees (epoch J2000)
+    /// Right ascension in degrees (ICRS, at the release's reference epoch)
     pub ra: f64,
-    /// Declination in degrees (epoch J2000)
+    /// Declination in degrees (ICRS, at the release's reference epoch)
     pub dec: f64,
     /// Error in RA (mas)
     pub ra_error: f64,
@@ -50,6 +198,10 @@ pub struct GaiaEntry {
     pub ecl_lon: f64,
     /// Ecliptic latitude (degrees)
     pub ecl_lat: f64,
+    /// Extended columns, if they were read; boxed so that a catalog of
+    /// positions pays only a pointer per star
+    #[serde(default)]
+    pub extras: Option<Box<GaiaExtras>>,
 }
 
 impl GaiaEntry {
@@ -76,35 +228,171 @@ impl GaiaEntry {
         })
     }
 
+    /// BP - RP colour index, if the extended columns were read
+    pub fn bp_rp(&self) -> Option<f64> {
+        self.extras.as_ref()?.bp_rp()
+    }
+
     /// Convert G magnitude to approximate V magnitude
-    /// This is a rough approximation - for precise values, color information is needed
+    ///
+    /// Uses [`johnson_v_from_gaia`] when the colour is known, and G alone
+    /// otherwise (within ~0.3 mag for most stars).
     pub fn approx_v_magnitude(&self) -> f64 {
-        // A simple approximation (within ~0.3 mag for most stars)
-        self.phot_g_mean_mag
+        match self.bp_rp() {
+            Some(bp_rp) => johnson_v_from_gaia(self.phot_g_mean_mag, bp_rp),
+            None => self.phot_g_mean_mag,
+        }
     }
 }
 
-/// Gaia catalog
+impl ProperMotion for GaiaEntry {
+    fn proper_motion_mas_yr(&self) -> Option<(f64, f64)> {
+        self.pmra.zip(self.pmdec)
+    }
+}
+
+/// Positions of the columns a [`GaiaEntry`] is read from
 #[derive(Debug, Clone)]
-pub struct GaiaCatalog {
-    /// Stars by source_id
-    stars: HashMap<u64, GaiaEntry>,
-    /// Magnitude limit used when loading
-    mag_limit: f64,
+struct Columns {
+    count: usize,
+    source_id: usize,
+    solution_id: usize,
+    ra: usize,
+    dec: usize,
+    ra_error: usize,
+    dec_error: usize,
+    parallax: usize,
+    parallax_error: usize,
+    pmra: usize,
+    pmdec: usize,
+    g_mag: usize,
+    g_flux: usize,
+    var_flag: Option<usize>,
+    l: usize,
+    b: usize,
+    ecl_lon: usize,
+    ecl_lat: usize,
+    bp_mag: Option<usize>,
+    rp_mag: Option<usize>,
+    radial_velocity: Option<usize>,
+    ruwe: Option<usize>,
 }
 
-impl GaiaCatalog {
-    /// Create a new empty Gaia catalog
-    pub fn new() -> Self {
-        Self {
-            stars: HashMap::new(),
-            mag_limit: f64::MAX,
+impl Columns {
+    /// Find the columns of a (lower-case) header for a data release
+    fn new(headers: &[String], release: DataRelease) -> Result<Self> {
+        let missing: Vec<&str> = release
+            .required_columns()
+            .into_iter()
+            .filter(|name| !headers.iter().any(|h| h == name))
+            .collect();
+        if !missing.is_empty() {
+            return Err(StarfieldError::DataError(format!(
+                "{} file is missing columns: {}",
+                release,
+                missing.join(", ")
+            )));
         }
+
+        let column = |name: &str| headers.iter().position(|h| h == name);
+        let find_column = |name: &str| -> Result<usize> {
+            column(name)
+                .ok_or_else(|| StarfieldError::DataError(format!("Missing column: {}", name)))
+        };
+        Ok(Self {
+            count: headers.len(),
+            source_id: find_column("source_id")?,
+            solution_id: find_column("solution_id")?,
+            ra: find_column("ra")?,
+            dec: find_column("dec")?,
+            ra_error: find_column("ra_error")?,
+            dec_error: find_column("dec_error")?,
+            parallax: find_column("parallax")?,
+            parallax_error: find_column("parallax_error")?,
+            pmra: find_column("pmra")?,
+            pmdec: find_column("pmdec")?,
+            g_mag: find_column("phot_g_mean_mag")?,
+            g_flux: find_column("phot_g_mean_flux")?,
+            var_flag: column("phot_variable_flag"),
+            l: find_column("l")?,
+            b: find_column("b")?,
+            ecl_lon: find_column("ecl_lon")?,
+            ecl_lat: find_column("ecl_lat")?,
+            bp_mag: column("phot_bp_mean_mag"),
+            rp_mag: column("phot_rp_mean_mag"),
+            // EDR3 carried the DR2 values over under a new name
+            radial_velocity: column("radial_velocity").or(column("dr2_radial_velocity")),
+            ruwe: column("ruwe"),
+        })
     }
 
-    /// Load from a file (either CSV or gzipped CSV)
-    pub fn from_file<P: AsRef<Path>>(path: P, mag_limit: f64) -> Result<Self> {
-        let file = File::open(&path).map_err(StarfieldError::IoError)?;
+    /// Parse a data line, or `None` if it is malformed or fainter than
+    /// `mag_limit`
+    fn parse(&self, line: &str, mag_limit: f64, selection: GaiaColumns) -> Option<GaiaEntry> {
+        let fields: Vec<&str> = line.split(',').collect();
+        if fields.len() < self.count {
+            return None;
+        }
+        let number = |i: usize| fields[i].parse::<f64>().ok();
+        let optional = |i: usize| {
+            if fields[i].is_empty() {
+                None
+            } else {
+                number(i)
+            }
+        };
+        let extra = |i: Option<usize>| i.and_then(optional);
+
+        let phot_g_mean_mag = number(self.g_mag)?;
+        // Skip stars fainter than magnitude limit
+        if phot_g_mean_mag > mag_limit {
+            return None;
+        }
+
+        Some(GaiaEntry {
+            source_id: fields[self.source_id].parse().ok()?,
+            solution_id: fields[self.solution_id].parse().ok()?,
+            ra: number(self.ra)?,
+            dec: number(self.dec)?,
+            ra_error: number(self.ra_error)?,
+            dec_error: number(self.dec_error)?,
+            parallax: optional(self.parallax),
+            parallax_error: optional(self.parallax_error),
+            pmra: optional(self.pmra),
+            pmdec: optional(self.pmdec),
+            phot_g_mean_mag,
+            phot_g_mean_flux: number(self.g_flux)?,
+            phot_variable_flag: match self.var_flag {
+                Some(i) => fields[i].to_string(),
+                None => "NOT_AVAILABLE".to_string(),
+            },
+            l: number(self.l)?,
+            b: number(self.b)?,
+            ecl_lon: number(self.ecl_lon)?,
+            ecl_lat: number(self.ecl_lat)?,
+            extras: match selection {
+                GaiaColumns::Core => None,
+                GaiaColumns::Extended => Some(Box::new(GaiaExtras {
+                    phot_bp_mean_mag: extra(self.bp_mag),
+                    phot_rp_mean_mag: extra(self.rp_mag),
+                    radial_velocity: extra(self.radial_velocity),
+                    ruwe: extra(self.ruwe),
+                })),
+            },
+        })
+    }
+}
+
+/// An open catalog file, positioned after its header
+struct OpenFile {
+    lines: Lines<Box<dyn BufRead>>,
+    columns: Columns,
+    release: DataRelease,
+}
+
+impl OpenFile {
+    fn open(path: &Path, release: Option<DataRelease>) -> Result<Self> {
+        let file = File::open(path).map_err(StarfieldError::IoError)?;
 
         // Check if the file is empty
         let metadata = file.metadata().map_err(StarfieldError::IoError)?;
@@ -115,31 +403,15 @@ impl GaiaCatalog {
         }
 
         // Determine if the file is gzipped or not
-        let path_str = path.as_ref().to_string_lossy().to_string();
-        let is_gzipped = path_str.ends_with(".gz");
-
-        let reader: Box<dyn BufRead> = if is_gzipped {
-            println!("Loading gzipped file: {}", path.as_ref().display());
-            // Create a gzip decoder
-            let gz_reader = BufReader::new(file);
-            let decoder = flate2::read::GzDecoder::new(gz_reader);
+        let reader: Box<dyn BufRead> = if path.to_string_lossy().ends_with(".gz") {
+            let decoder = flate2::read::GzDecoder::new(BufReader::new(file));
             Box::new(BufReader::new(decoder))
         } else {
-            println!("Loading CSV file: {}", path.as_ref().display());
             Box::new(BufReader::new(file))
         };
 
-        let mut catalog = Self {
-            stars: HashMap::new(),
-            mag_limit,
-        };
-
-        let mut line_count = 0;
-        let mut valid_stars = 0;
-
-        // Read lines from the reader, which might be wrapped with a gzip decoder
-        let mut lines_iter = reader.lines();
-        let header = match lines_iter.next() {
+        let mut lines = reader.lines();
+        let header = match lines.next() {
             Some(Ok(line)) => line,
             _ => {
                 return Err(StarfieldError::DataError(
@@ -149,170 +421,256 @@ impl GaiaCatalog {
         };
 
         // Parse header to find column indices
-        let headers: Vec<&str> = header.split(',').collect();
-        let find_column = |name: &str| -> Result<usize> {
-            headers
-                .iter()
-                .position(|&h| h == name)
-                .ok_or_else(|| StarfieldError::DataError(format!("Missing column: {}", name)))
-        };
+        let headers: Vec<String> = header
+            .split(',')
+            .map(|h| h.trim().trim_matches('"').to_ascii_lowercase())
+            .collect();
+        let release = release.unwrap_or_else(|| DataRelease::detect(&headers));
+        let columns = Columns::new(&headers, release)?;
+
+        Ok(Self {
+            lines,
+            columns,
+            release,
+        })
+    }
+}
 
-        // Find required column indices
-        let source_id_idx = find_column("source_id")?;
-        let solution_id_idx = find_column("solution_id")?;
-        let ra_idx = find_column("ra")?;
-        let dec_idx = find_column("dec")?;
-        let ra_error_idx = find_column("ra_error")?;
-        let dec_error_idx = find_column("dec_error")?;
-        let parallax_idx = find_column("parallax")?;
-        let parallax_error_idx = find_column("parallax_error")?;
-        let pmra_idx = find_column("pmra")?;
-        let pmdec_idx = find_column("pmdec")?;
-        let g_mag_idx = find_column("phot_g_mean_mag")?;
-        let g_flux_idx = find_column("phot_g_mean_flux")?;
-        let var_flag_idx = find_column("phot_variable_flag")?;
-        let l_idx = find_column("l")?;
-        let b_idx = find_column("b")?;
-        let ecl_lon_idx = find_column("ecl_lon")?;
-        let ecl_lat_idx = find_column("ecl_lat")?;
-
-        // Process data lines
-        for line_result in lines_iter {
-            let line = match line_result {
-                Ok(l) => l,
-                Err(e) => {
-                    eprintln!("Error reading line: {}", e);
-                    continue;
-                }
-            };
+/// Streams [`GaiaEntry`] values from one or more (gzipped) CSV files
+///
+/// Unlike [`GaiaCatalog`], nothing is kept once it has been yielded, so a
+/// whole multi-gigabyte export can be filtered or aggregated in constant
+/// memory. Files are opened one at a time as the previous one runs out;
+/// rows that are malformed or fainter than the magnitude limit are
+/// skipped, while read errors and files from a different data release are
+/// reported as `Err` items.
+///
+/// ```no_run
+/// use starfield::catalogs::GaiaCatalogReader;
+///
+/// let reader = GaiaCatalogReader::open("GaiaSource_000000-003111.csv.gz", 12.0)?;
+/// let near_pole = reader
+///     .filter_map(|entry| entry.ok())
+///     .filter(|entry| entry.dec > 80.0)
+///     .count();
+/// # Ok::<(), starfield::StarfieldError>(())
+/// ```
+pub struct GaiaCatalogReader {
+    /// Files not yet opened
+    pending: VecDeque<PathBuf>,
+    /// File being read
+    current: Option<OpenFile>,
+    /// Magnitude limit applied to every row
+    mag_limit: f64,
+    /// Columns kept from each row
+    selection: GaiaColumns,
+    /// Release requested by the caller, if any
+    requested: Option<DataRelease>,
+    /// Release of the first file
+    release: Option<DataRelease>,
+    /// Data lines read so far, across all files
+    lines_read: usize,
+}
 
-            line_count += 1;
+impl GaiaCatalogReader {
+    /// Stream a single file, detecting its data release from the header
+    pub fn open<P: AsRef<Path>>(path: P, mag_limit: f64) -> Result<Self> {
+        Self::open_with_release(path, mag_limit, None)
+    }
 
-            if line.trim().is_empty() {
-                continue;
-            }
+    /// Stream a single file with the schema of a given data release
+    pub fn open_with_release<P: AsRef<Path>>(
+        path: P,
+        mag_limit: f64,
+        release: Option<DataRelease>,
+    ) -> Result<Self> {
+        Self::from_files_with_release([path], mag_limit, release)
+    }
 
-            let fields: Vec<&str> = line.split(',').collect();
-            if fields.len() < headers.len() {
-                continue; // Skip lines with insufficient columns
-            }
+    /// Stream several files in turn
+    ///
+    /// The first file is opened, and its header checked, straight away.
+    pub fn from_files<I, P>(paths: I, mag_limit: f64) -> Result<Self>
+    where
+        I: IntoIterator<Item = P>,
+        P: AsRef<Path>,
+    {
+        Self::from_files_with_release(paths, mag_limit, None)
+    }
 
-            // Parse required fields
-            let source_id = match fields[source_id_idx].parse::<u64>() {
-                Ok(id) => id,
-                Err(_) => continue,
-            };
+    /// Stream several files with the schema of a given data release
+    pub fn from_files_with_release<I, P>(
+        paths: I,
+        mag_limit: f64,
+        release: Option<DataRelease>,
+    ) -> Result<Self>
+    where
+        I: IntoIterator<Item = P>,
+        P: AsRef<Path>,
+    {
+        let mut pending: VecDeque<PathBuf> = paths
+            .into_iter()
+            .map(|p| p.as_ref().to_path_buf())
+            .collect();
+        let first = pending.pop_front().ok_or_else(|| {
+            StarfieldError::DataError("No Gaia catalog files to read".to_string())
+        })?;
+        let current = OpenFile::open(&first, release)?;
+        Ok(Self {
+            pending,
+            release: Some(current.release),
+            current: Some(current),
+            mag_limit,
+            selection: GaiaColumns::default(),
+            requested: release,
+            lines_read: 0,
+        })
+    }
 
-            let solution_id = match fields[solution_id_idx].parse::<u64>() {
-                Ok(id) => id,
-                Err(_) => continue,
-            };
+    /// Keep the given columns of each row, [`GaiaColumns::Core`] by default
+    pub fn with_columns(mut self, selection: GaiaColumns) -> Self {
+        self.selection = selection;
+        self
+    }
 
-            let ra = match fields[ra_idx].parse::<f64>() {
-                Ok(ra) => ra,
-                Err(_) => continue,
-            };
+    /// Data release of the stream, taken from the first file
+    pub fn release(&self) -> Option<DataRelease> {
+        self.release
+    }
 
-            let dec = match fields[dec_idx].parse::<f64>() {
-                Ok(dec) => dec,
-                Err(_) => continue,
-            };
+    /// Number of data lines read so far, including skipped ones
+    pub fn lines_read(&self) -> usize {
+        self.lines_read
+    }
 
-            let ra_error = match fields[ra_error_idx].parse::<f64>() {
-                Ok(err) => err,
-                Err(_) => continue,
-            };
+    /// Stream the common [`StarData`] of each entry instead
+    pub fn star_data(self) -> impl Iterator<Item = Result<StarData>> {
+        self.map(|entry| {
+            entry.map(|star| {
+                StarData::new(
+                    star.source_id,
+                    star.ra,
+                    star.dec,
+                    star.phot_g_mean_mag,
+                    None,
+                )
+            })
+        })
+    }
 
-            let dec_error = match fields[dec_error_idx].parse::<f64>() {
-                Ok(err) => err,
-                Err(_) => continue,
-            };
+    /// Open the next pending file, checking that its release matches
+    fn open_next(&mut self) -> Option<Result<()>> {
+        let path = self.pending.pop_front()?;
+        let opened = OpenFile::open(&path, self.requested).and_then(|file| match self.release {
+            Some(ours) if ours != file.release => Err(StarfieldError::DataError(format!(
+                "{} holds {} stars, but the stream is {}",
+                path.display(),
+                file.release,
+                ours
+            ))),
+            _ => Ok(file),
+        });
+        Some(opened.map(|file| self.current = Some(file)))
+    }
+}
 
-            let g_mag = match fields[g_mag_idx].parse::<f64>() {
-                Ok(mag) => mag,
-                Err(_) => continue,
-            };
+impl Iterator for GaiaCatalogReader {
+    type Item = Result<GaiaEntry>;
 
-            // Skip stars fainter than magnitude limit
-            if g_mag > mag_limit {
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let Some(file) = &mut self.current else {
+                if let Err(e) = self.open_next()? {
+                    return Some(Err(e));
+                }
                 continue;
-            }
-
-            let g_flux = match fields[g_flux_idx].parse::<f64>() {
-                Ok(flux) => flux,
-                Err(_) => continue,
-            };
-
-            // Parse optional fields
-            let parallax = if !fields[parallax_idx].is_empty() {
-                fields[parallax_idx].parse::<f64>().ok()
-            } else {
-                None
-            };
-
-            let parallax_error = if !fields[parallax_error_idx].is_empty() {
-                fields[parallax_error_idx].parse::<f64>().ok()
-            } else {
-                None
-            };
-
-            let pmra = if !fields[pmra_idx].is_empty() {
-                fields[pmra_idx].parse::<f64>().ok()
-            } else {
-                None
-            };
-
-            let pmdec = if !fields[pmdec_idx].is_empty() {
-                fields[pmdec_idx].parse::<f64>().ok()
-            } else {
-                None
-            };
-
-            let var_flag = fields[var_flag_idx].to_string();
-
-            let l = match fields[l_idx].parse::<f64>() {
-                Ok(l) => l,
-                Err(_) => continue,
             };
+            match file.lines.next() {
+                Some(Ok(line)) => {
+                    self.lines_read += 1;
+                    if let Some(entry) = file.columns.parse(&line, self.mag_limit, self.selection) {
+                        return Some(Ok(entry));
+                    }
+                }
+                Some(Err(e)) => {
+                    // A broken file rarely recovers; move on to the next one
+                    self.current = None;
+                    return Some(Err(StarfieldError::IoError(e)));
+                }
+                None => self.current = None,
+            }
+        }
+    }
+}
 
-            let b = match fields[b_idx].parse::<f64>() {
-                Ok(b) => b,
-                Err(_) => continue,
-            };
+/// Gaia catalog
+#[derive(Debug, Clone)]
+pub struct GaiaCatalog {
+    /// Stars by source_id
+    stars: HashMap<u64, GaiaEntry>,
+    /// Magnitude limit used when loading
+    mag_limit: f64,
+    /// Data release the stars came from, if known
+    release: Option<DataRelease>,
+    /// Spatial index, built on the first cone search
+    index: IndexCache,
+}
 
-            let ecl_lon = match fields[ecl_lon_idx].parse::<f64>() {
-                Ok(lon) => lon,
-                Err(_) => continue,
-            };
+impl GaiaCatalog {
+    /// Create a new empty Gaia catalog
+    pub fn new() -> Self {
+        Self {
+            stars: HashMap::new(),
+            mag_limit: f64::MAX,
+            release: None,
+            index: IndexCache::default(),
+        }
+    }
 
-            let ecl_lat = match fields[ecl_lat_idx].parse::<f64>() {
-                Ok(lat) => lat,
-                Err(_) => continue,
-            };
+    /// Load from a file (either CSV or gzipped CSV)
+    ///
+    /// The data release is detected from the header; see
+    /// [`DataRelease::detect`].
+    pub fn from_file<P: AsRef<Path>>(path: P, mag_limit: f64) -> Result<Self> {
+        Self::from_file_with_release(path, mag_limit, None)
+    }
 
-            let entry = GaiaEntry {
-                source_id,
-                solution_id,
-                ra,
-                dec,
-                ra_error,
-                dec_error,
-                parallax,
-                parallax_error,
-                pmra,
-                pmdec,
-                phot_g_mean_mag: g_mag,
-                phot_g_mean_flux: g_flux,
-                phot_variable_flag: var_flag,
-                l,
-                b,
-                ecl_lon,
-                ecl_lat,
-            };
+    /// Load from a file, parsing it with the schema of a given data release
+    ///
+    /// With `release` set to `None` the release is detected from the header.
+    /// Fails with a list of every missing column if the file lacks columns
+    /// the release should have.
+    pub fn from_file_with_release<P: AsRef<Path>>(
+        path: P,
+        mag_limit: f64,
+        release: Option<DataRelease>,
+    ) -> Result<Self> {
+        log::debug!("Loading Gaia file: {}", path.as_ref().display());
+        Self::from_reader(GaiaCatalogReader::open_with_release(
+            path, mag_limit, release,
+        )?)
+    }
 
-            catalog.stars.insert(source_id, entry);
-            valid_stars += 1;
+    /// Load every star a reader yields, logging and skipping rows it
+    /// reports as errors
+    pub fn from_reader(mut reader: GaiaCatalogReader) -> Result<Self> {
+        let mut catalog = Self {
+            stars: HashMap::new(),
+            mag_limit: reader.mag_limit,
+            release: reader.release(),
+            index: IndexCache::default(),
+        };
+        let mut valid_stars = 0;
+        for entry in reader.by_ref() {
+            match entry {
+                Ok(entry) => {
+                    catalog.stars.insert(entry.source_id, entry);
+                    valid_stars += 1;
+                }
+                Err(e) => log::warn!("Error reading line: {}", e),
+            }
         }
+        let line_count = reader.lines_read();
 
         if catalog.stars.is_empty() {
             return Err(StarfieldError::DataError(format!(
@@ -321,9 +679,10 @@ impl GaiaCatalog {
             )));
         }
 
-        println!(
+        log::info!(
             "Loaded {} stars from Gaia catalog (processed {} lines).",
-            valid_stars, line_count
+            valid_stars,
+            line_count
         );
         Ok(catalog)
     }
@@ -346,8 +705,44 @@ impl GaiaCatalog {
         self.mag_limit
     }
 
+    /// Data release the stars came from, if known
+    pub fn release(&self) -> Option<DataRelease> {
+        self.release
+    }
+
+    /// Find all stars within `radius_deg` of a position
+    ///
+    /// Uses a kd-tree built on the first call and reused afterwards.
+    pub fn cone_search(&self, ra_deg: f64, dec_deg: f64, radius_deg: f64) -> Vec<StarData> {
+        self.spatial_index()
+            .cone_search(ra_deg, dec_deg, radius_deg)
+    }
+
+    /// Indexed equivalent of [`StarCatalog::stars_in_field`]
+    pub fn stars_in_field_indexed(&self, ra_deg: f64, dec_deg: f64, fov_deg: f64) -> Vec<StarData> {
+        self.cone_search(ra_deg, dec_deg, fov_deg / 2.0)
+    }
+
+    /// Get the catalog's spatial index, building it if needed
+    pub fn spatial_index(&self) -> &SkyIndex {
+        self.index.get_or_build(|| self.star_data())
+    }
+
     /// Merge another catalog into this one
+    ///
+    /// Catalogs from different data releases have positions at different
+    /// epochs and cannot be merged.
     pub fn merge(&mut self, other: GaiaCatalog) -> Result<()> {
+        if let (Some(ours), Some(theirs)) = (self.release, other.release) {
+            if ours != theirs {
+                return Err(StarfieldError::DataError(format!(
+                    "Cannot merge {} stars into a {} catalog",
+                    theirs, ours
+                )));
+            }
+        }
+        self.release = self.release.or(other.release);
+
         // Merge stars, using our catalog's entries if there are duplicates
         for (id, star) in other.stars {
             self.stars.entry(id).or_insert(star);
@@ -355,6 +750,7 @@ impl GaiaCatalog {
 
         // Keep the lower magnitude limit of the two catalogs
         self.mag_limit = self.mag_limit.min(other.mag_limit);
+        self.index.invalidate();
 
         Ok(())
     }
@@ -367,6 +763,8 @@ impl GaiaCatalog {
         let mut catalog = Self {
             stars: HashMap::new(),
             mag_limit: 20.0,
+            release: None,
+            index: IndexCache::default(),
         };
 
         // Use a fixed seed for reproducibility
@@ -496,6 +894,7 @@ impl GaiaCatalog {
                 b: star.14,
                 ecl_lon: star.15,
                 ecl_lat: star.16,
+                extras: None,
             };
 
             catalog.stars.insert(star.0, entry);
@@ -561,12 +960,13 @@ impl GaiaCatalog {
                 b,
                 ecl_lon,
                 ecl_lat,
+                extras: None,
             };
 
             catalog.stars.insert(source_id, entry);
         }
 
-        println!(
+        log::debug!(
             "Created synthetic Gaia catalog with {} stars",
             catalog.stars.len()
         );
@@ -595,6 +995,10 @@ impl StarCatalog for GaiaCatalog {
         self.stars.len()
     }
 
+    fn epoch(&self) -> Option<f64> {
+        self.release.map(|release| release.reference_epoch())
+    }
+
     fn filter<F>(&self, predicate: F) -> Vec<&Self::Star>
     where
         F: Fn(&Self::Star) -> bool,
@@ -628,6 +1032,182 @@ impl StarCatalog for GaiaCatalog {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use std::io::Write;
+
+    const DR2_HEADER: &str = "solution_id,source_id,ref_epoch,ra,ra_error,dec,dec_error,parallax,parallax_error,pmra,pmdec,astrometric_pseudo_colour,phot_g_mean_flux,phot_g_mean_mag,phot_bp_mean_mag,phot_variable_flag,l,b,ecl_lon,ecl_lat";
+    const EDR3_HEADER: &str = "solution_id,source_id,ref_epoch,ra,ra_error,dec,dec_error,parallax,parallax_error,pmra,pmdec,nu_eff_used_in_astrometry,phot_g_mean_flux,phot_g_mean_mag,dr2_radial_velocity,l,b,ecl_lon,ecl_lat";
+
+    fn write_csv(header: &str, rows: &[&str]) -> tempfile::NamedTempFile {
+        let mut file = tempfile::Builder::new().suffix(".csv").tempfile().unwrap();
+        writeln!(file, "{}", header).unwrap();
+        for row in rows {
+            writeln!(file, "{}", row).unwrap();
+        }
+        file
+    }
+
+    #[test]
+    fn test_detects_release_from_header() {
+        let dr2 = write_csv(
+            DR2_HEADER,
+            &["1,42,2015.5,10.0,0.1,20.0,0.1,5.0,0.2,1.0,2.0,1.5,1e6,8.5,9.0,VARIABLE,100.0,10.0,15.0,5.0"],
+        );
+        let catalog = GaiaCatalog::from_file(dr2.path(), 12.0).unwrap();
+        assert_eq!(catalog.release(), Some(DataRelease::Dr2));
+        assert_eq!(catalog.get_star(42).unwrap().phot_variable_flag, "VARIABLE");
+
+        // EDR3 has no variability flag at all
+        let edr3 = write_csv(
+            EDR3_HEADER,
+            &["1,43,2016.0,10.0,0.1,20.0,0.1,5.0,0.2,1.0,2.0,1.5,1e6,8.5,,100.0,10.0,15.0,5.0"],
+        );
+        let catalog = GaiaCatalog::from_file(edr3.path(), 12.0).unwrap();
+        assert_eq!(catalog.release(), Some(DataRelease::Edr3));
+        assert_eq!(catalog.release().unwrap().reference_epoch(), 2016.0);
+        assert_eq!(
+            catalog.get_star(43).unwrap().phot_variable_flag,
+            "NOT_AVAILABLE"
+        );
+    }
+
+    #[test]
+    fn test_extended_columns() {
+        let dr3 = write_csv(
+            "solution_id,source_id,ra,ra_error,dec,dec_error,parallax,parallax_error,pmra,pmdec,ruwe,phot_g_mean_flux,phot_g_mean_mag,phot_bp_mean_mag,phot_rp_mean_mag,phot_variable_flag,radial_velocity,has_xp_continuous,l,b,ecl_lon,ecl_lat",
+            &[
+                "1,42,10.0,0.1,20.0,0.1,5.0,0.2,1.0,2.0,1.02,1e6,8.5,9.1,7.8,NOT_AVAILABLE,-12.5,True,100.0,10.0,15.0,5.0",
+                "1,43,11.0,0.1,21.0,0.1,,,,,,1e3,11.0,,,NOT_AVAILABLE,,False,100.0,10.0,15.0,5.0",
+            ],
+        );
+
+        // Positions only unless asked
+        let core = GaiaCatalog::from_file(dr3.path(), 12.0).unwrap();
+        assert!(core.get_star(42).unwrap().extras.is_none());
+        assert_eq!(core.get_star(42).unwrap().approx_v_magnitude(), 8.5);
+
+        let reader = GaiaCatalogReader::open(dr3.path(), 12.0)
+            .unwrap()
+            .with_columns(GaiaColumns::Extended);
+        let catalog = GaiaCatalog::from_reader(reader).unwrap();
+        assert_eq!(catalog.release(), Some(DataRelease::Dr3));
+        let star = catalog.get_star(42).unwrap();
+        let extras = star.extras.as_deref().unwrap();
+        assert_eq!(extras.phot_bp_mean_mag, Some(9.1));
+        assert_eq!(extras.radial_velocity, Some(-12.5));
+        assert_eq!(extras.ruwe, Some(1.02));
+        assert!((star.bp_rp().unwrap() - 1.3).abs() < 1e-12);
+        // For an early K star V is about a third of a magnitude fainter than G
+        assert!((star.approx_v_magnitude() - 8.84).abs() < 0.01);
+
+        let faint = catalog.get_star(43).unwrap();
+        assert_eq!(faint.extras.as_deref(), Some(&GaiaExtras::default()));
+        assert_eq!(faint.bp_rp(), None);
+    }
+
+    #[test]
+    fn test_missing_columns_are_listed() {
+        let file = write_csv("source_id,ra,dec,phot_g_mean_mag", &["1,10.0,20.0,5.0"]);
+        let err = GaiaCatalog::from_file(file.path(), 12.0).unwrap_err();
+        let message = err.to_string();
+        assert!(
+            message.contains("Gaia DR1 file is missing columns"),
+            "{}",
+            message
+        );
+        assert!(message.contains("solution_id"));
+        assert!(message.contains("ecl_lat"));
+
+        // Asking for DR3 explicitly requires the variability flag
+        let edr3 = write_csv(EDR3_HEADER, &[]);
+        let err = GaiaCatalog::from_file_with_release(edr3.path(), 12.0, Some(DataRelease::Dr3))
+            .unwrap_err();
+        assert!(err
+            .to_string()
+            .ends_with("missing columns: phot_variable_flag"));
+    }
+
+    #[test]
+    fn test_merge_rejects_mixed_releases() {
+        let dr2 = write_csv(
+            DR2_HEADER,
+            &["1,42,2015.5,10.0,0.1,20.0,0.1,5.0,0.2,1.0,2.0,1.5,1e6,8.5,9.0,VARIABLE,100.0,10.0,15.0,5.0"],
+        );
+        let edr3 = write_csv(
+            EDR3_HEADER,
+            &["1,43,2016.0,10.0,0.1,20.0,0.1,5.0,0.2,1.0,2.0,1.5,1e6,8.5,,100.0,10.0,15.0,5.0"],
+        );
+        let mut catalog = GaiaCatalog::from_file(dr2.path(), 12.0).unwrap();
+        let other = GaiaCatalog::from_file(edr3.path(), 12.0).unwrap();
+        assert!(catalog.merge(other).is_err());
+        assert!(catalog.merge(GaiaCatalog::new()).is_ok());
+    }
+
+    #[test]
+    fn test_reader_streams_several_files() {
+        let first = write_csv(
+            DR2_HEADER,
+            &[
+                "1,42,2015.5,10.0,0.1,20.0,0.1,5.0,0.2,1.0,2.0,1.5,1e6,8.5,9.0,VARIABLE,100.0,10.0,15.0,5.0",
+                "1,44,2015.5,11.0,0.1,21.0,0.1,,,,,1.5,1e3,15.5,16.0,NOT_AVAILABLE,100.0,10.0,15.0,5.0",
+                "not,a,row",
+            ],
+        );
+
+        // The second file is gzipped
+        let second = tempfile::Builder::new()
+            .suffix(".csv.gz")
+            .tempfile()
+            .unwrap();
+        let mut gz = flate2::write::GzEncoder::new(
+            File::create(second.path()).unwrap(),
+            flate2::Compression::default(),
+        );
+        writeln!(gz, "{}", DR2_HEADER).unwrap();
+        writeln!(
+            gz,
+            "1,45,2015.5,12.0,0.1,22.0,0.1,2.0,0.2,1.0,2.0,1.5,1e5,9.5,10.0,NOT_AVAILABLE,100.0,10.0,15.0,5.0"
+        )
+        .unwrap();
+        gz.finish().unwrap();
+
+        let mut reader =
+            GaiaCatalogReader::from_files([first.path(), second.path()], 12.0).unwrap();
+        assert_eq!(reader.release(), Some(DataRelease::Dr2));
+        let ids: Vec<u64> = reader
+            .by_ref()
+            .map(|entry| entry.unwrap().source_id)
+            .collect();
+        assert_eq!(ids, vec![42, 45]);
+        assert_eq!(reader.lines_read(), 4);
+
+        let stars: Vec<StarData> = GaiaCatalogReader::open(first.path(), 20.0)
+            .unwrap()
+            .star_data()
+            .collect::<Result<_>>()
+            .unwrap();
+        assert_eq!(stars.len(), 2);
+        assert!(stars[1].position.dec_degrees() > 20.9);
+    }
+
+    #[test]
+    fn test_reader_rejects_mixed_releases() {
+        let dr2 = write_csv(
+            DR2_HEADER,
+            &["1,42,2015.5,10.0,0.1,20.0,0.1,5.0,0.2,1.0,2.0,1.5,1e6,8.5,9.0,VARIABLE,100.0,10.0,15.0,5.0"],
+        );
+        let edr3 = write_csv(
+            EDR3_HEADER,
+            &["1,43,2016.0,10.0,0.1,20.0,0.1,5.0,0.2,1.0,2.0,1.5,1e6,8.5,,100.0,10.0,15.0,5.0"],
+        );
+        let results: Vec<Result<GaiaEntry>> =
+            GaiaCatalogReader::from_files([dr2.path(), edr3.path()], 12.0)
+                .unwrap()
+                .collect();
+        assert_eq!(results.len(), 2);
+        assert!(results[0].is_ok());
+        assert!(results[1].is_err());
+        assert!(GaiaCatalogReader::from_files(Vec::<PathBuf>::new(), 12.0).is_err());
+    }
 
     #[test]
     fn test_synthetic_catalog() {
@@ -0,0 +1,433 @@
+//! General Catalogue of Variable Stars (GCVS)
+//!
+//! Reads the pipe-separated `gcvs5.txt` distributed by the Sternberg
+//! Astronomical Institute and predicts the brightness of periodic variables
+//! from their light elements. The catalog gives, for each star, an epoch
+//! (of maximum light for pulsating stars, of primary minimum for eclipsing
+//! binaries) and a period, so the phase at any time follows directly.
+//!
+//! The light curve is a smooth template between the catalogued extreme
+//! magnitudes: a cosine decline and rise split by the rise duration for
+//! pulsators, and cosine-shaped eclipses of the catalogued duration for
+//! eclipsing binaries. It is good for planning observations, not for
+//! photometry. Epochs are heliocentric Julian dates and are used as TT
+//! without the light-time correction of up to 8 minutes.
+
+use super::StarPosition;
+use crate::coordinates::Equatorial;
+use crate::time::Time;
+use crate::{Result, StarfieldError};
+use std::f64::consts::PI;
+use std::path::Path;
+
+/// GCVS epochs are given as JD - 2400000
+const EPOCH_OFFSET_JD: f64 = 2_400_000.0;
+
+/// Eclipse duration, as a fraction of the period, assumed when none is given
+const DEFAULT_ECLIPSE_FRACTION: f64 = 0.1;
+
+/// A brightness extreme of a variable star
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Extremum {
+    /// Greatest brightness (smallest magnitude)
+    Maximum,
+    /// Least brightness (largest magnitude)
+    Minimum,
+}
+
+/// One star from the GCVS
+#[derive(Debug, Clone, PartialEq)]
+pub struct GcvsEntry {
+    /// GCVS number: constellation code followed by the star's number
+    pub number: String,
+    /// Designation, such as `omi Cet` or `RR Lyr`
+    pub name: String,
+    /// J2000 position, when the catalog gives one
+    pub position: Option<Equatorial>,
+    /// Variability type, such as `M`, `DCEP` or `EA/SD`
+    pub variable_type: String,
+    /// Magnitude at maximum
+    pub max_magnitude: Option<f64>,
+    /// Magnitude at (primary) minimum
+    pub min_magnitude: Option<f64>,
+    /// Magnitude at secondary minimum, for eclipsing binaries
+    pub secondary_min_magnitude: Option<f64>,
+    /// Photometric system of the magnitudes, such as `V` or `p`
+    pub photometric_system: String,
+    /// Epoch of maximum, or of primary minimum for eclipsing binaries, as a
+    /// Julian date
+    pub epoch_jd: Option<f64>,
+    /// Period in days
+    pub period_days: Option<f64>,
+    /// Rise time (pulsators) or eclipse duration (eclipsing binaries) as a
+    /// percentage of the period
+    pub duration_percent: Option<f64>,
+    /// Spectral type
+    pub spectral_type: String,
+}
+
+/// Number in a GCVS field, ignoring uncertainty and limit flags
+fn parse_number(field: &str) -> Option<f64> {
+    let cleaned: String = field
+        .chars()
+        .filter(|c| c.is_ascii_digit() || matches!(c, '.' | '-' | '+'))
+        .collect();
+    cleaned.parse().ok()
+}
+
+/// A minimum-magnitude field, which gives an amplitude when in parentheses
+fn parse_minimum(field: &str, max: Option<f64>) -> Option<f64> {
+    let value = parse_number(field)?;
+    if field.trim_start().starts_with('(') {
+        max.map(|m| m + value)
+    } else {
+        Some(value)
+    }
+}
+
+/// J2000 position from `hhmmss.s+ddmmss`
+pub(super) fn parse_position(field: &str) -> Option<Equatorial> {
+    let field = field.trim();
+    let sign_at = field.find(['+', '-'])?;
+    let (ra, dec) = field.split_at(sign_at);
+    if ra.len() < 6 || dec.len() < 7 {
+        return None;
+    }
+    let hours: f64 = ra[0..2].parse().ok()?;
+    let minutes: f64 = ra[2..4].parse().ok()?;
+    let seconds: f64 = ra[4..].parse().ok()?;
+    let degrees: f64 = dec[1..3].parse().ok()?;
+    let arcmin: f64 = dec[3..5].parse().ok()?;
+    let arcsec: f64 = dec[5..].parse().ok()?;
+    let sign = if dec.starts_with('-') { -1.0 } else { 1.0 };
+    Some(Equatorial::from_degrees(
+        15.0 * (hours + minutes / 60.0 + seconds / 3600.0),
+        sign * (degrees + arcmin / 60.0 + arcsec / 3600.0),
+    ))
+}
+
+impl GcvsEntry {
+    /// Parse one catalog line, or `None` for headers and malformed lines
+    pub fn parse_line(line: &str) -> Option<Self> {
+        let fields: Vec<&str> = line.split('|').collect();
+        if fields.len() < 11 {
+            return None;
+        }
+        let number = fields[0].trim();
+        if number.is_empty() || !number.chars().all(|c| c.is_ascii_digit()) {
+            return None;
+        }
+        let field = |i: usize| fields.get(i).map_or("", |f| f.trim());
+
+        let max_magnitude = parse_number(field(4));
+        Some(Self {
+            number: number.to_string(),
+            name: normalize_name(field(1)),
+            position: parse_position(field(2)),
+            variable_type: field(3).to_string(),
+            max_magnitude,
+            min_magnitude: parse_minimum(field(5), max_magnitude),
+            secondary_min_magnitude: parse_minimum(field(6), max_magnitude),
+            photometric_system: field(7).to_string(),
+            epoch_jd: parse_number(field(8)).map(|e| e + EPOCH_OFFSET_JD),
+            period_days: parse_number(field(10)).filter(|&p| p > 0.0),
+            duration_percent: parse_number(field(11)),
+            spectral_type: field(12).to_string(),
+        })
+    }
+
+    /// Whether the star is an eclipsing binary (types `E`, `EA`, `EB`, `EW`)
+    pub fn is_eclipsing(&self) -> bool {
+        self.variable_type.starts_with('E')
+    }
+
+    /// Epoch and period, if the star has light elements
+    fn elements(&self) -> Option<(f64, f64)> {
+        Some((self.epoch_jd?, self.period_days?))
+    }
+
+    /// Phase at `t` as a fraction of the period in [0, 1), zero at the
+    /// catalog epoch
+    pub fn phase(&self, t: &Time) -> Option<f64> {
+        let (epoch, period) = self.elements()?;
+        Some(((t.tt() - epoch) / period).rem_euclid(1.0))
+    }
+
+    /// Predicted magnitude at `t` from the light-curve template
+    pub fn magnitude(&self, t: &Time) -> Option<f64> {
+        let phase = self.phase(t)?;
+        let max = self.max_magnitude?;
+        let min = self.min_magnitude?;
+
+        if !self.is_eclipsing() {
+            // Decline from maximum at phase 0 to minimum, then rise
+            let rise = self.rise_fraction();
+            let s = if phase < 1.0 - rise {
+                phase / (1.0 - rise)
+            } else {
+                (1.0 - phase) / rise
+            };
+            return Some(max + (min - max) * (1.0 - (PI * s).cos()) / 2.0);
+        }
+
+        let secondary = self.secondary_min_magnitude.unwrap_or(max);
+        let (offset, depth) = if (0.25..0.75).contains(&phase) {
+            (phase - 0.5, secondary - max)
+        } else {
+            (phase - phase.round(), min - max)
+        };
+        let dimming =
+            if self.variable_type.starts_with("EW") || self.variable_type.starts_with("EB") {
+                // Continuous variation between quadratures
+                (2.0 * PI * offset).cos().powi(2)
+            } else {
+                let half_width = self.eclipse_fraction() / 2.0;
+                if offset.abs() < half_width {
+                    (PI * offset / (2.0 * half_width)).cos().powi(2)
+                } else {
+                    0.0
+                }
+            };
+        Some(max + depth * dimming)
+    }
+
+    /// Rise from minimum to maximum as a fraction of the period
+    fn rise_fraction(&self) -> f64 {
+        self.duration_percent
+            .map_or(0.5, |p| p / 100.0)
+            .clamp(0.01, 0.99)
+    }
+
+    /// Eclipse duration as a fraction of the period
+    fn eclipse_fraction(&self) -> f64 {
+        self.duration_percent
+            .map_or(DEFAULT_ECLIPSE_FRACTION, |p| p / 100.0)
+            .clamp(0.01, 0.5)
+    }
+
+    /// Phases of the extremes within one cycle
+    pub(crate) fn extremum_phases(&self) -> Vec<(f64, Extremum)> {
+        if self.is_eclipsing() {
+            let mut phases = vec![
+                (0.0, Extremum::Minimum),
+                (0.25, Extremum::Maximum),
+                (0.75, Extremum::Maximum),
+            ];
+            if self.secondary_min_magnitude.is_some() {
+                phases.insert(2, (0.5, Extremum::Minimum));
+            }
+            phases
+        } else {
+            vec![
+                (0.0, Extremum::Maximum),
+                (1.0 - self.rise_fraction(), Extremum::Minimum),
+            ]
+        }
+    }
+
+    /// Julian dates of every extreme between `start_jd` and `end_jd`
+    pub(crate) fn extrema_between(&self, start_jd: f64, end_jd: f64) -> Vec<(f64, Extremum)> {
+        let Some((epoch, period)) = self.elements() else {
+            return Vec::new();
+        };
+        let phases = self.extremum_phases();
+        let first_cycle = ((start_jd - epoch) / period).floor() as i64;
+        let last_cycle = ((end_jd - epoch) / period).floor() as i64;
+        (first_cycle..=last_cycle)
+            .flat_map(|cycle| {
+                phases
+                    .iter()
+                    .map(move |&(phase, kind)| (epoch + (cycle as f64 + phase) * period, kind))
+            })
+            .filter(|(jd, _)| (start_jd..end_jd).contains(jd))
+            .collect()
+    }
+
+    /// First extreme of the given kind after `t`
+    fn next(&self, t: &Time, kind: Extremum) -> Option<Time> {
+        let (_, period) = self.elements()?;
+        let jd = self
+            .extrema_between(t.tt(), t.tt() + period * 1.000_001)
+            .into_iter()
+            .find(|(_, k)| *k == kind)?
+            .0;
+        Some(t.timescale().tt_jd(jd, None))
+    }
+
+    /// Next maximum after `t`
+    pub fn next_maximum(&self, t: &Time) -> Option<Time> {
+        self.next(t, Extremum::Maximum)
+    }
+
+    /// Next (primary or secondary) minimum after `t`
+    pub fn next_minimum(&self, t: &Time) -> Option<Time> {
+        self.next(t, Extremum::Minimum)
+    }
+}
+
+impl StarPosition for GcvsEntry {
+    fn ra(&self) -> f64 {
+        self.position.map_or(f64::NAN, |p| p.ra_degrees())
+    }
+
+    fn dec(&self) -> f64 {
+        self.position.map_or(f64::NAN, |p| p.dec_degrees())
+    }
+}
+
+/// Collapse runs of spaces in a designation, so `R     And` matches `R And`
+fn normalize_name(name: &str) -> String {
+    name.trim_end_matches('*')
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// The variable stars of the GCVS
+#[derive(Debug, Clone, Default)]
+pub struct GcvsCatalog {
+    entries: Vec<GcvsEntry>,
+}
+
+impl GcvsCatalog {
+    /// Parse the text of `gcvs5.txt`, skipping header and malformed lines
+    pub fn parse(text: &str) -> Result<Self> {
+        let entries: Vec<GcvsEntry> = text.lines().filter_map(GcvsEntry::parse_line).collect();
+        if entries.is_empty() && !text.trim().is_empty() {
+            return Err(StarfieldError::DataError(
+                "no GCVS entries found".to_string(),
+            ));
+        }
+        Ok(Self { entries })
+    }
+
+    /// Load `gcvs5.txt` from disk
+    pub fn from_file<P: AsRef<Path>>(path: P) -> Result<Self> {
+        Self::parse(&std::fs::read_to_string(path)?)
+    }
+
+    /// Look up a star by designation, ignoring case and spacing
+    pub fn get(&self, name: &str) -> Option<&GcvsEntry> {
+        let name = normalize_name(name);
+        self.entries
+            .iter()
+            .find(|e| e.name.eq_ignore_ascii_case(&name))
+    }
+
+    /// All entries
+    pub fn entries(&self) -> &[GcvsEntry] {
+        &self.entries
+    }
+
+    /// Stars with a period and epoch, whose light can be predicted
+    pub fn periodic(&self) -> impl Iterator<Item = &GcvsEntry> {
+        self.entries.iter().filter(|e| e.elements().is_some())
+    }
+
+    /// Number of entries
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Whether the catalog is empty
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use crate::time::{TimeDelta, Timescale};
+    use approx::assert_relative_eq;
+
+    pub(crate) const SAMPLE: &str = "\
+     NNo   GCVS name   Coordinates J2000  Type       Max          Min I        Min II       Sys Epoch        Year   Period       M-m  Spectrum
+190001 |omi Cet    |021920.7-025840 |M          |  2.0       | 10.1       |            |V |44839.      |        | 331.96     |38   |M5e-M9e          |
+100001 |bet Per    |030810.1+405720 |EA/SD      |  2.09      |  3.30      |  2.12      |V |45641.5135  |        |   2.8673043|14   |B8V+G8III        |
+010001 |R     And *|002401.9+383437 |M          |  5.8       | 15.2       |            |V |53820.      |        | 409.2      |38   |S3,5e-S8,8e(M7e) |
+150002 |T     CrB  |155930.1+255512 |NR         |  2.0       | 10.8       |            |V |            |1946    |            |     |M3III+p          |
+";
+
+    #[test]
+    fn test_parse_catalog() {
+        let catalog = GcvsCatalog::parse(SAMPLE).unwrap();
+        assert_eq!(catalog.len(), 4);
+        assert_eq!(catalog.periodic().count(), 3);
+
+        let mira = catalog.get("OMI  cet").unwrap();
+        assert_eq!(mira.variable_type, "M");
+        assert_relative_eq!(mira.period_days.unwrap(), 331.96);
+        assert_relative_eq!(mira.epoch_jd.unwrap(), 2_444_839.0);
+        let position = mira.position.unwrap();
+        assert_relative_eq!(position.ra_degrees(), 34.836_25, epsilon = 1e-4);
+        assert_relative_eq!(position.dec_degrees(), -2.977_78, epsilon = 1e-4);
+
+        assert_eq!(catalog.get("R And").unwrap().number, "010001");
+        assert!(catalog
+            .get("T CrB")
+            .unwrap()
+            .phase(&Timescale::default().utc((2024, 1, 1)))
+            .is_none());
+        assert!(GcvsCatalog::parse("not a catalog").is_err());
+    }
+
+    #[test]
+    fn test_pulsator_light_curve() {
+        let ts = Timescale::default();
+        let catalog = GcvsCatalog::parse(SAMPLE).unwrap();
+        let mira = catalog.get("omi Cet").unwrap();
+        let epoch = ts.tt_jd(mira.epoch_jd.unwrap(), None);
+        let period = mira.period_days.unwrap();
+
+        assert_relative_eq!(mira.magnitude(&epoch).unwrap(), 2.0, epsilon = 1e-9);
+        // Minimum comes 38% of a period before the next maximum
+        let minimum = &epoch + TimeDelta::days(0.62 * period);
+        assert_relative_eq!(mira.magnitude(&minimum).unwrap(), 10.1, epsilon = 1e-9);
+        assert_relative_eq!(
+            mira.phase(&(&epoch + TimeDelta::days(10.0 * period + 1.0)))
+                .unwrap(),
+            1.0 / period,
+            epsilon = 1e-9
+        );
+
+        let t = &epoch + TimeDelta::days(100.5 * period);
+        let next = mira.next_maximum(&t).unwrap();
+        assert_relative_eq!(next.tt(), epoch.tt() + 101.0 * period, epsilon = 1e-6);
+        let next_min = mira.next_minimum(&t).unwrap();
+        assert_relative_eq!(next_min.tt(), epoch.tt() + 100.62 * period, epsilon = 1e-6);
+    }
+
+    #[test]
+    fn test_eclipsing_light_curve() {
+        let ts = Timescale::default();
+        let catalog = GcvsCatalog::parse(SAMPLE).unwrap();
+        let algol = catalog.get("bet Per").unwrap();
+        assert!(algol.is_eclipsing());
+        let epoch = ts.tt_jd(algol.epoch_jd.unwrap(), None);
+        let period = algol.period_days.unwrap();
+
+        assert_relative_eq!(algol.magnitude(&epoch).unwrap(), 3.30, epsilon = 1e-9);
+        assert_relative_eq!(
+            algol
+                .magnitude(&(&epoch + TimeDelta::days(0.25 * period)))
+                .unwrap(),
+            2.09
+        );
+        assert_relative_eq!(
+            algol
+                .magnitude(&(&epoch + TimeDelta::days(0.5 * period)))
+                .unwrap(),
+            2.12,
+            epsilon = 1e-9
+        );
+        // Halfway into the eclipse the star has lost half the depth
+        let ingress = &epoch - TimeDelta::days(0.035 * period);
+        assert_relative_eq!(algol.magnitude(&ingress).unwrap(), 2.695, epsilon = 1e-6);
+
+        let next = algol
+            .next_minimum(&(&epoch + TimeDelta::days(0.1)))
+            .unwrap();
+        assert_relative_eq!(next.tt(), epoch.tt() + 0.5 * period, epsilon = 1e-6);
+    }
+}
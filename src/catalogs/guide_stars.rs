@@ -0,0 +1,410 @@
+//! Guide star selection for guiding and adaptive optics
+//!
+//! [`select_guide_stars`] looks for stars near a field center that fall in
+//! a magnitude range, have no brighter-than-allowed neighbour close enough
+//! to confuse a centroid or wavefront sensor, and, when a
+//! [`GuideDetector`] is given, land on the guide detector away from its
+//! edges. Candidates come back ranked, best first.
+//!
+//! ```
+//! use starfield::catalogs::{
+//!     select_guide_stars, BinaryCatalog, GuideDetector, GuideStarConstraints, MinimalStar,
+//! };
+//! use starfield::coordinates::Equatorial;
+//!
+//! let catalog = BinaryCatalog::from_stars(
+//!     vec![
+//!         MinimalStar::new(1, 83.82, -5.39, 11.2),
+//!         MinimalStar::new(2, 83.83, -5.40, 12.5),
+//!     ],
+//!     "field",
+//! );
+//! let constraints = GuideStarConstraints::default()
+//!     .with_magnitude_range(9.0, 14.0)
+//!     .with_isolation(10.0, 3.0)
+//!     .with_detector(GuideDetector::new(1024, 1024, 0.5));
+//! let center = Equatorial::from_degrees(83.82, -5.39);
+//! let candidates = select_guide_stars(&catalog, &center, &constraints);
+//! assert_eq!(candidates[0].star.id, 1);
+//! ```
+
+use super::{Band, SkyIndex, StarCatalog, StarData};
+use crate::charting::GnomonicProjection;
+use crate::constants::{DEG2RAD, RAD2DEG};
+use crate::coordinates::Equatorial;
+
+/// A guide camera's pixel grid, placed relative to the field center
+///
+/// With zero rotation, pixel x runs east and y north; the rotation is the
+/// position angle of the detector's +y axis, east of north.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct GuideDetector {
+    /// Width in pixels
+    pub width_px: usize,
+    /// Height in pixels
+    pub height_px: usize,
+    /// Plate scale in arcseconds per pixel
+    pub pixel_scale_arcsec: f64,
+    /// Position angle of the +y axis, degrees east of north
+    pub rotation_deg: f64,
+    /// Offset of the detector center from the field center, arcseconds
+    /// east and north, for off-axis guiders
+    pub offset_arcsec: (f64, f64),
+    /// Border in pixels within which stars are rejected
+    pub edge_margin_px: f64,
+}
+
+impl GuideDetector {
+    /// An on-axis, unrotated detector of `width_px` by `height_px` pixels
+    pub fn new(width_px: usize, height_px: usize, pixel_scale_arcsec: f64) -> Self {
+        Self {
+            width_px,
+            height_px,
+            pixel_scale_arcsec,
+            rotation_deg: 0.0,
+            offset_arcsec: (0.0, 0.0),
+            edge_margin_px: 0.0,
+        }
+    }
+
+    /// Set the position angle of the detector's +y axis
+    pub fn with_rotation(mut self, rotation_deg: f64) -> Self {
+        self.rotation_deg = rotation_deg;
+        self
+    }
+
+    /// Set the offset of the detector center from the field center
+    pub fn with_offset(mut self, east_arcsec: f64, north_arcsec: f64) -> Self {
+        self.offset_arcsec = (east_arcsec, north_arcsec);
+        self
+    }
+
+    /// Set the border within which stars are rejected
+    pub fn with_edge_margin(mut self, margin_px: f64) -> Self {
+        self.edge_margin_px = margin_px;
+        self
+    }
+
+    /// Distance from the field center to the farthest corner, arcseconds
+    fn reach_arcsec(&self) -> f64 {
+        let half_diagonal = (self.width_px as f64).hypot(self.height_px as f64) / 2.0;
+        self.offset_arcsec.0.hypot(self.offset_arcsec.1) + half_diagonal * self.pixel_scale_arcsec
+    }
+
+    /// Pixel coordinates of a position, from the detector's corner, when
+    /// the field is centered on `projection`'s tangent point
+    fn pixel(&self, projection: &GnomonicProjection, position: &Equatorial) -> Option<(f64, f64)> {
+        let (xi, eta) = projection.project(position)?;
+        let east = xi.atan() * RAD2DEG * 3600.0 - self.offset_arcsec.0;
+        let north = eta.atan() * RAD2DEG * 3600.0 - self.offset_arcsec.1;
+        let (sin_pa, cos_pa) = (self.rotation_deg * DEG2RAD).sin_cos();
+        let x = east * cos_pa - north * sin_pa;
+        let y = east * sin_pa + north * cos_pa;
+        Some((
+            x / self.pixel_scale_arcsec + self.width_px as f64 / 2.0,
+            y / self.pixel_scale_arcsec + self.height_px as f64 / 2.0,
+        ))
+    }
+
+    /// Whether pixel coordinates are on the detector, clear of the margin
+    fn accepts(&self, (x, y): (f64, f64)) -> bool {
+        let m = self.edge_margin_px;
+        (m..=self.width_px as f64 - m).contains(&x) && (m..=self.height_px as f64 - m).contains(&y)
+    }
+}
+
+/// What makes a star usable for guiding
+#[derive(Debug, Clone, PartialEq)]
+pub struct GuideStarConstraints {
+    /// Brightest acceptable magnitude, to avoid saturating the sensor
+    pub brightest_mag: f64,
+    /// Faintest acceptable magnitude
+    pub faintest_mag: f64,
+    /// Band the limits apply in, or `None` for the catalog magnitude
+    pub band: Option<Band>,
+    /// Radius around a candidate searched for contaminating neighbours,
+    /// arcseconds
+    pub isolation_radius_arcsec: f64,
+    /// A neighbour within the isolation radius disqualifies a candidate if
+    /// it is fainter by less than this many magnitudes (or brighter)
+    pub isolation_delta_mag: f64,
+    /// Radius around the field center searched for candidates, arcseconds;
+    /// ignored when a detector is given
+    pub patrol_radius_arcsec: f64,
+    /// Guide detector the star must fall on, if any
+    pub detector: Option<GuideDetector>,
+    /// Most candidates to return
+    pub max_candidates: usize,
+}
+
+impl Default for GuideStarConstraints {
+    /// Magnitudes 8 to 15, no neighbour within 10″ less than 3 magnitudes
+    /// fainter, anywhere within 5′ of the field center
+    fn default() -> Self {
+        Self {
+            brightest_mag: 8.0,
+            faintest_mag: 15.0,
+            band: None,
+            isolation_radius_arcsec: 10.0,
+            isolation_delta_mag: 3.0,
+            patrol_radius_arcsec: 300.0,
+            detector: None,
+            max_candidates: 10,
+        }
+    }
+}
+
+impl GuideStarConstraints {
+    /// Set the acceptable magnitude range
+    pub fn with_magnitude_range(mut self, brightest: f64, faintest: f64) -> Self {
+        self.brightest_mag = brightest;
+        self.faintest_mag = faintest;
+        self
+    }
+
+    /// Apply the magnitude limits in `band`
+    pub fn with_band(mut self, band: Band) -> Self {
+        self.band = Some(band);
+        self
+    }
+
+    /// Reject candidates with a neighbour within `radius_arcsec` that is
+    /// fainter by less than `delta_mag`
+    pub fn with_isolation(mut self, radius_arcsec: f64, delta_mag: f64) -> Self {
+        self.isolation_radius_arcsec = radius_arcsec;
+        self.isolation_delta_mag = delta_mag;
+        self
+    }
+
+    /// Set the radius searched when no detector is given
+    pub fn with_patrol_radius(mut self, radius_arcsec: f64) -> Self {
+        self.patrol_radius_arcsec = radius_arcsec;
+        self
+    }
+
+    /// Require candidates to fall on `detector`
+    pub fn with_detector(mut self, detector: GuideDetector) -> Self {
+        self.detector = Some(detector);
+        self
+    }
+
+    /// Return at most `count` candidates
+    pub fn with_max_candidates(mut self, count: usize) -> Self {
+        self.max_candidates = count;
+        self
+    }
+
+    /// Magnitude the limits are tested against
+    fn magnitude(&self, star: &StarData) -> Option<f64> {
+        match self.band {
+            Some(band) => star.magnitude_in(band),
+            None => Some(star.magnitude),
+        }
+    }
+}
+
+/// A star that meets the constraints
+#[derive(Debug, Clone, Copy)]
+pub struct GuideStarCandidate {
+    /// The catalog entry
+    pub star: StarData,
+    /// Magnitude in the constrained band
+    pub magnitude: f64,
+    /// Distance from the field center, arcseconds
+    pub separation_arcsec: f64,
+    /// Position on the guide detector in pixels, if one was given
+    pub pixel: Option<(f64, f64)>,
+    /// Distance to the nearest catalog neighbour of any brightness within
+    /// three isolation radii, arcseconds
+    pub nearest_neighbor_arcsec: Option<f64>,
+    /// Ranking score, higher is better
+    pub score: f64,
+}
+
+/// Angle between two positions in arcseconds
+fn separation_arcsec(a: &Equatorial, b: &Equatorial) -> f64 {
+    let cos = a.dec.sin() * b.dec.sin() + a.dec.cos() * b.dec.cos() * (a.ra - b.ra).cos();
+    cos.clamp(-1.0, 1.0).acos() * RAD2DEG * 3600.0
+}
+
+/// Choose guide stars around `field_center` from `catalog`
+///
+/// Candidates are ranked by a score adding three terms between 0 and 1:
+/// brightness within the magnitude range, isolation (1 when no neighbour of
+/// any brightness lies within three isolation radii) and, with half the
+/// weight, closeness to the detector center or the field center.
+pub fn select_guide_stars<C: StarCatalog>(
+    catalog: &C,
+    field_center: &Equatorial,
+    constraints: &GuideStarConstraints,
+) -> Vec<GuideStarCandidate> {
+    let reach_arcsec = match &constraints.detector {
+        Some(detector) => detector.reach_arcsec(),
+        None => constraints.patrol_radius_arcsec,
+    };
+    let neighbour_arcsec = 3.0 * constraints.isolation_radius_arcsec;
+    let field_deg = 2.0 * (reach_arcsec + neighbour_arcsec) / 3600.0;
+    let field = SkyIndex::build(catalog.stars_in_field(
+        field_center.ra_degrees(),
+        field_center.dec_degrees(),
+        field_deg,
+    ));
+    let projection = GnomonicProjection::new(*field_center);
+
+    let mut candidates: Vec<GuideStarCandidate> = Vec::new();
+    for star in field.cone_search(
+        field_center.ra_degrees(),
+        field_center.dec_degrees(),
+        reach_arcsec / 3600.0,
+    ) {
+        let Some(magnitude) = constraints.magnitude(&star) else {
+            continue;
+        };
+        if !(constraints.brightest_mag..=constraints.faintest_mag).contains(&magnitude) {
+            continue;
+        }
+
+        let (pixel, centrality) = match &constraints.detector {
+            Some(detector) => {
+                let Some(pixel) = detector.pixel(&projection, &star.position) else {
+                    continue;
+                };
+                if !detector.accepts(pixel) {
+                    continue;
+                }
+                let half = (
+                    detector.width_px as f64 / 2.0,
+                    detector.height_px as f64 / 2.0,
+                );
+                let from_center = (pixel.0 - half.0).hypot(pixel.1 - half.1) / half.0.hypot(half.1);
+                (Some(pixel), from_center)
+            }
+            None => {
+                let from_center = separation_arcsec(field_center, &star.position) / reach_arcsec;
+                (None, from_center)
+            }
+        };
+
+        let mut nearest_neighbor_arcsec: Option<f64> = None;
+        let mut contaminated = false;
+        for neighbour in field.cone_search(star.ra_deg(), star.dec_deg(), neighbour_arcsec / 3600.0)
+        {
+            if neighbour.id == star.id {
+                continue;
+            }
+            let distance = separation_arcsec(&star.position, &neighbour.position);
+            nearest_neighbor_arcsec =
+                Some(nearest_neighbor_arcsec.map_or(distance, |d| d.min(distance)));
+            let neighbour_mag = constraints
+                .magnitude(&neighbour)
+                .unwrap_or(neighbour.magnitude);
+            if distance <= constraints.isolation_radius_arcsec
+                && neighbour_mag < magnitude + constraints.isolation_delta_mag
+            {
+                contaminated = true;
+                break;
+            }
+        }
+        if contaminated {
+            continue;
+        }
+
+        let range = (constraints.faintest_mag - constraints.brightest_mag).max(f64::EPSILON);
+        let brightness = (constraints.faintest_mag - magnitude) / range;
+        let isolation = nearest_neighbor_arcsec.map_or(1.0, |d| d / neighbour_arcsec);
+        candidates.push(GuideStarCandidate {
+            star,
+            magnitude,
+            separation_arcsec: separation_arcsec(field_center, &star.position),
+            pixel,
+            nearest_neighbor_arcsec,
+            score: brightness + isolation - 0.5 * centrality.min(1.0),
+        });
+    }
+
+    candidates.sort_by(|a, b| b.score.total_cmp(&a.score));
+    candidates.truncate(constraints.max_candidates);
+    candidates
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::catalogs::{BinaryCatalog, MinimalStar};
+
+    const RA: f64 = 150.0;
+    const DEC: f64 = 20.0;
+
+    /// A star `east` and `north` arcseconds from the field center
+    fn star(id: u64, east: f64, north: f64, magnitude: f64) -> MinimalStar {
+        let ra = RA + east / 3600.0 / (DEC * DEG2RAD).cos();
+        MinimalStar::new(id, ra, DEC + north / 3600.0, magnitude)
+    }
+
+    #[test]
+    fn test_constraints_and_ranking() {
+        let catalog = BinaryCatalog::from_stars(
+            vec![
+                star(1, 60.0, 0.0, 10.0),   // good
+                star(2, -90.0, 30.0, 12.0), // good, fainter
+                star(3, 0.0, 120.0, 6.0),   // too bright
+                star(4, 30.0, -30.0, 16.5), // too faint
+                star(5, -60.0, -60.0, 11.0),
+                star(6, -55.0, -60.0, 12.0), // crowds star 5 at 5"
+                star(7, 600.0, 0.0, 9.0),    // outside the patrol radius
+            ],
+            "guide test",
+        );
+        let center = Equatorial::from_degrees(RA, DEC);
+        let candidates = select_guide_stars(&catalog, &center, &GuideStarConstraints::default());
+
+        let ids: Vec<u64> = candidates.iter().map(|c| c.star.id).collect();
+        assert_eq!(ids, vec![1, 2]);
+        assert!((candidates[0].separation_arcsec - 60.0).abs() < 0.1);
+        assert!(candidates[0].pixel.is_none());
+
+        // Loosening the isolation test admits star 5, which crowds 6 less
+        let loose = GuideStarConstraints::default().with_isolation(10.0, 0.5);
+        let ids: Vec<u64> = select_guide_stars(&catalog, &center, &loose)
+            .iter()
+            .map(|c| c.star.id)
+            .collect();
+        assert!(ids.contains(&5) && !ids.contains(&6));
+    }
+
+    #[test]
+    fn test_detector_placement() {
+        let catalog = BinaryCatalog::from_stars(
+            vec![star(1, 100.0, 20.0, 10.0), star(2, -100.0, 0.0, 10.0)],
+            "guide test",
+        );
+        let center = Equatorial::from_degrees(RA, DEC);
+
+        // An off-axis guider 100" east: star 1 lands 20" north of its center
+        let detector = GuideDetector::new(200, 200, 0.5)
+            .with_offset(100.0, 0.0)
+            .with_edge_margin(10.0);
+        let constraints = GuideStarConstraints::default().with_detector(detector);
+        let candidates = select_guide_stars(&catalog, &center, &constraints);
+        assert_eq!(candidates.len(), 1);
+        let (x, y) = candidates[0].pixel.unwrap();
+        assert!(
+            (x - 100.0).abs() < 0.1 && (y - 140.0).abs() < 0.1,
+            "{} {}",
+            x,
+            y
+        );
+
+        // Turned a quarter turn, north runs along -x
+        let rotated = constraints.with_detector(detector.with_rotation(90.0));
+        let (x, y) = select_guide_stars(&catalog, &center, &rotated)[0]
+            .pixel
+            .unwrap();
+        assert!(
+            (x - 60.0).abs() < 0.1 && (y - 100.0).abs() < 0.1,
+            "{} {}",
+            x,
+            y
+        );
+    }
+}
@@ -1,6 +1,9 @@
 //! Hipparcos star catalog implementation
 //!
 //! This module provides functionality for loading and using the Hipparcos star catalog.
+//! Entries read from `hip_main.dat` keep the astrometry with its standard
+//! errors, the B-V and V-I colours, and the variability and multiplicity
+//! flags, so the catalog can be used for astrometric work on its own.
 
 use nalgebra as na;
 use serde::{Deserialize, Serialize};
@@ -8,13 +11,16 @@ use std::collections::HashMap;
 use std::fs::File;
 use std::io::{BufRead, BufReader};
 use std::path::Path;
+use std::str::FromStr;
 
-use super::{StarCatalog, StarData, StarPosition};
+use super::spatial_index::IndexCache;
+use super::window::HIPPARCOS_EPOCH;
+use super::{ProperMotion, SkyIndex, StarCatalog, StarData, StarPosition};
 use crate::Result;
 use crate::StarfieldError;
 
 /// Struct representing an entry in the Hipparcos catalog
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct HipparcosEntry {
     /// Hipparcos identifier
     pub hip: usize,
@@ -32,6 +38,30 @@ pub struct HipparcosEntry {
     pub pm_dec: Option<f64>,
     /// Parallax (mas)
     pub parallax: Option<f64>,
+    /// Standard error of the parallax (mas)
+    #[serde(default)]
+    pub e_parallax: Option<f64>,
+    /// Standard error of the proper motion in RA (mas/year)
+    #[serde(default)]
+    pub e_pm_ra: Option<f64>,
+    /// Standard error of the proper motion in declination (mas/year)
+    #[serde(default)]
+    pub e_pm_dec: Option<f64>,
+    /// V-I color index
+    #[serde(default)]
+    pub v_i: Option<f64>,
+    /// Coarse variability: 1 for under 0.06 mag, 2 for 0.06 to 0.6 mag,
+    /// 3 for more
+    #[serde(default)]
+    pub var_flag: Option<u8>,
+    /// Variability type: `C` constant, `D` duplicity-induced, `M` possibly
+    /// micro-variable, `P` periodic, `R` revised colour index, `U` unsolved
+    #[serde(default)]
+    pub var_type: Option<char>,
+    /// Double and multiple systems solution: `C` component, `G` acceleration
+    /// terms, `O` orbital, `V` variability-induced mover, `X` stochastic
+    #[serde(default)]
+    pub mult_flag: Option<char>,
 }
 
 impl HipparcosEntry {
@@ -49,6 +79,16 @@ impl HipparcosEntry {
         )
     }
 
+    /// Whether the star was found to vary in brightness
+    pub fn is_variable(&self) -> bool {
+        self.var_flag.is_some() || matches!(self.var_type, Some('M' | 'P' | 'U'))
+    }
+
+    /// Whether the astrometry needed a double or multiple star solution
+    pub fn is_multiple(&self) -> bool {
+        self.mult_flag.is_some()
+    }
+
     /// Calculate cartesian position in parsecs
     pub fn cartesian_position(&self) -> Option<na::Vector3<f64>> {
         self.parallax.filter(|&p| p > 0.0).map(|parallax| {
@@ -70,6 +110,17 @@ impl StarPosition for HipparcosEntry {
     }
 }
 
+impl ProperMotion for HipparcosEntry {
+    fn proper_motion_mas_yr(&self) -> Option<(f64, f64)> {
+        self.pm_ra.zip(self.pm_dec)
+    }
+}
+
+/// Value of field `index` in a `hip_main.dat` record, if it is not blank
+fn parse_field<T: FromStr>(fields: &[&str], index: usize) -> Option<T> {
+    fields.get(index).and_then(|s| s.trim().parse().ok())
+}
+
 /// Hipparcos catalog
 #[derive(Debug, Clone)]
 pub struct HipparcosCatalog {
@@ -77,6 +128,8 @@ pub struct HipparcosCatalog {
     stars: HashMap<usize, HipparcosEntry>,
     /// Magnitude limit used when loading
     mag_limit: f64,
+    /// Spatial index, built on the first cone search
+    index: IndexCache,
 }
 
 impl HipparcosCatalog {
@@ -85,6 +138,7 @@ impl HipparcosCatalog {
         Self {
             stars: HashMap::new(),
             mag_limit: f64::MAX,
+            index: IndexCache::default(),
         }
     }
 
@@ -104,6 +158,7 @@ impl HipparcosCatalog {
         let mut catalog = Self {
             stars: HashMap::new(),
             mag_limit,
+            index: IndexCache::default(),
         };
 
         let mut line_count = 0;
@@ -115,7 +170,7 @@ impl HipparcosCatalog {
             let line = match line {
                 Ok(l) => l,
                 Err(e) => {
-                    println!("Error reading line: {}", e);
+                    log::warn!("Error reading line: {}", e);
                     skipped_lines += 1;
                     continue;
                 }
@@ -185,25 +240,25 @@ impl HipparcosCatalog {
                 }
             };
 
-            // Extract parallax from field 11
-            let parallax = fields.get(11).and_then(|s| s.trim().parse::<f64>().ok());
-
-            // Extract proper motion in RA and Dec from fields 12 and 13
-            let pm_ra = fields.get(12).and_then(|s| s.trim().parse::<f64>().ok());
-            let pm_dec = fields.get(13).and_then(|s| s.trim().parse::<f64>().ok());
-
-            // B-V color index from field 37
-            let b_v = fields.get(37).and_then(|s| s.trim().parse::<f64>().ok());
-
             let entry = HipparcosEntry {
                 hip,
                 ra,
                 dec,
                 mag,
-                b_v,
-                pm_ra,
-                pm_dec,
-                parallax,
+                // Astrometry and its standard errors, fields 11-13 and 16-18
+                parallax: parse_field(&fields, 11),
+                pm_ra: parse_field(&fields, 12),
+                pm_dec: parse_field(&fields, 13),
+                e_parallax: parse_field(&fields, 16),
+                e_pm_ra: parse_field(&fields, 17),
+                e_pm_dec: parse_field(&fields, 18),
+                // Colour indices, fields 37 and 40
+                b_v: parse_field(&fields, 37),
+                v_i: parse_field(&fields, 40),
+                // Flags, fields 6, 52 and 59
+                var_flag: parse_field(&fields, 6),
+                var_type: parse_field(&fields, 52),
+                mult_flag: parse_field(&fields, 59),
             };
 
             catalog.stars.insert(hip, entry);
@@ -223,7 +278,7 @@ impl HipparcosCatalog {
             }
         }
 
-        println!("Loaded {} stars from Hipparcos catalog (read {} lines, skipped {} lines, accepted {} stars within magnitude limit)",
+        log::info!("Loaded {} stars from Hipparcos catalog (read {} lines, skipped {} lines, accepted {} stars within magnitude limit)",
                  catalog.stars.len(), line_count, skipped_lines, accepted_stars);
         Ok(catalog)
     }
@@ -240,6 +295,24 @@ impl HipparcosCatalog {
     pub fn mag_limit(&self) -> f64 {
         self.mag_limit
     }
+
+    /// Find all stars within `radius_deg` of a position
+    ///
+    /// Uses a kd-tree built on the first call and reused afterwards.
+    pub fn cone_search(&self, ra_deg: f64, dec_deg: f64, radius_deg: f64) -> Vec<StarData> {
+        self.spatial_index()
+            .cone_search(ra_deg, dec_deg, radius_deg)
+    }
+
+    /// Indexed equivalent of [`StarCatalog::stars_in_field`]
+    pub fn stars_in_field_indexed(&self, ra_deg: f64, dec_deg: f64, fov_deg: f64) -> Vec<StarData> {
+        self.cone_search(ra_deg, dec_deg, fov_deg / 2.0)
+    }
+
+    /// Get the catalog's spatial index, building it if needed
+    pub fn spatial_index(&self) -> &SkyIndex {
+        self.index.get_or_build(|| self.star_data())
+    }
 }
 
 impl Default for HipparcosCatalog {
@@ -254,6 +327,7 @@ impl HipparcosCatalog {
         let mut catalog = Self {
             stars: HashMap::new(),
             mag_limit: 10.0,
+            index: IndexCache::default(),
         };
 
         // Add some well-known stars
@@ -270,6 +344,7 @@ impl HipparcosCatalog {
                 pm_ra: Some(-546.05),
                 pm_dec: Some(-1223.14),
                 parallax: Some(379.21),
+                ..Default::default()
             },
         );
 
@@ -285,6 +360,7 @@ impl HipparcosCatalog {
                 pm_ra: Some(200.94),
                 pm_dec: Some(286.23),
                 parallax: Some(130.23),
+                ..Default::default()
             },
         );
 
@@ -300,6 +376,7 @@ impl HipparcosCatalog {
                 pm_ra: Some(26.40),
                 pm_dec: Some(9.56),
                 parallax: Some(5.95),
+                ..Default::default()
             },
         );
 
@@ -316,6 +393,7 @@ impl HipparcosCatalog {
                 pm_ra: Some(3.19),
                 pm_dec: Some(2.03),
                 parallax: Some(3.99),
+                ..Default::default()
             },
         );
 
@@ -331,6 +409,7 @@ impl HipparcosCatalog {
                 pm_ra: Some(1.49),
                 pm_dec: Some(-1.06),
                 parallax: Some(2.43),
+                ..Default::default()
             },
         );
 
@@ -346,6 +425,7 @@ impl HipparcosCatalog {
                 pm_ra: Some(0.92),
                 pm_dec: Some(-1.20),
                 parallax: Some(3.56),
+                ..Default::default()
             },
         );
 
@@ -370,6 +450,7 @@ impl HipparcosCatalog {
                     pm_ra: Some(rng.gen_range(-100.0..100.0)),
                     pm_dec: Some(rng.gen_range(-100.0..100.0)),
                     parallax: Some(rng.gen_range(1.0..1000.0)),
+                    ..Default::default()
                 },
             );
         }
@@ -393,6 +474,10 @@ impl StarCatalog for HipparcosCatalog {
         self.stars.len()
     }
 
+    fn epoch(&self) -> Option<f64> {
+        Some(HIPPARCOS_EPOCH)
+    }
+
     fn filter<F>(&self, predicate: F) -> Vec<&Self::Star>
     where
         F: Fn(&Self::Star) -> bool,
@@ -427,10 +512,7 @@ mod tests {
             ra: 0.0,  // RA = 0 degrees
             dec: 0.0, // Dec = 0 degrees
             mag: 0.0,
-            b_v: None,
-            pm_ra: None,
-            pm_dec: None,
-            parallax: None,
+            ..Default::default()
         };
 
         let vec = star.unit_vector();
@@ -438,4 +520,92 @@ mod tests {
         assert!(vec.y.abs() < 1e-10);
         assert!(vec.z.abs() < 1e-10);
     }
+
+    /// A `hip_main.dat` record with the given fields set and the rest blank
+    fn record(values: &[(usize, &str)]) -> String {
+        let mut fields = vec![" "; 78];
+        fields[0] = "H";
+        for &(index, value) in values {
+            fields[index] = value;
+        }
+        fields.join("|")
+    }
+
+    #[test]
+    fn test_reads_all_fields() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("hip_main.dat");
+        let lines = [
+            record(&[
+                (1, "       32349"),
+                (5, "-1.44"),
+                (6, "1"),
+                (8, "101.28854105"),
+                (9, "-16.71314306"),
+                (11, " 379.21"),
+                (12, " -546.01"),
+                (13, "-1223.08"),
+                (16, "  1.58"),
+                (17, "  1.33"),
+                (18, "  1.24"),
+                (37, " 0.009"),
+                (40, "-0.03"),
+                (52, "U"),
+            ]),
+            record(&[
+                (1, "       11767"),
+                (5, " 1.97"),
+                (8, " 37.94614689"),
+                (9, "+89.26413805"),
+                (11, "   7.56"),
+                (37, " 0.636"),
+                (59, "G"),
+            ]),
+            // Too faint for the limit
+            record(&[(1, "           1"), (5, " 9.10"), (8, "0.0"), (9, "1.0")]),
+        ];
+        std::fs::write(&path, lines.join("\n")).unwrap();
+
+        let catalog = HipparcosCatalog::from_dat_file(&path, 6.0).unwrap();
+        assert_eq!(catalog.len(), 2);
+
+        let sirius = catalog.get_star(32349).unwrap();
+        assert_eq!(sirius.parallax, Some(379.21));
+        assert_eq!(sirius.proper_motion_mas_yr(), Some((-546.01, -1223.08)));
+        assert_eq!(
+            (sirius.e_parallax, sirius.e_pm_ra, sirius.e_pm_dec),
+            (Some(1.58), Some(1.33), Some(1.24))
+        );
+        assert_eq!((sirius.b_v, sirius.v_i), (Some(0.009), Some(-0.03)));
+        assert_eq!((sirius.var_flag, sirius.var_type), (Some(1), Some('U')));
+        assert!(sirius.is_variable() && !sirius.is_multiple());
+
+        // Blank fields stay empty
+        let polaris = catalog.get_star(11767).unwrap();
+        assert_eq!(
+            (polaris.pm_ra, polaris.e_parallax, polaris.v_i),
+            (None, None, None)
+        );
+        assert_eq!(polaris.mult_flag, Some('G'));
+        assert!(!polaris.is_variable() && polaris.is_multiple());
+    }
+
+    #[test]
+    fn test_cone_search_matches_linear_scan() {
+        let catalog = HipparcosCatalog::create_synthetic();
+        let mut expected: Vec<u64> = catalog
+            .stars_in_field(100.0, -15.0, 30.0)
+            .iter()
+            .map(|s| s.id)
+            .collect();
+        let mut actual: Vec<u64> = catalog
+            .stars_in_field_indexed(100.0, -15.0, 30.0)
+            .iter()
+            .map(|s| s.id)
+            .collect();
+        expected.sort_unstable();
+        actual.sort_unstable();
+        assert!(actual.contains(&32349));
+        assert_eq!(actual, expected);
+    }
 }
@@ -5,26 +5,44 @@
 //! It also provides an index of interesting astronomical features for targeting simulations.
 
 use crate::coordinates::Equatorial;
+use crate::framelib::inertial::{Ecliptic, Galactic, InertialFrame};
 
 pub mod binary_catalog;
+pub mod crossmatch;
+pub mod deep_sky;
+pub mod export;
 pub mod features;
 mod gaia;
+pub mod gcvs;
+pub mod guide_stars;
 pub mod hipparcos;
+pub mod orb6;
+pub mod photometry;
+pub mod sharded;
+pub mod spatial_index;
 pub mod synthetic;
+pub mod window;
 
-pub use binary_catalog::{BinaryCatalog, MinimalStar};
+pub use binary_catalog::{BinaryCatalog, CatalogMetadata, MinimalStar, StarFields};
+pub use crossmatch::{crossmatch, CrossMatch, CrossMatchResult, MatchedPair};
+pub use deep_sky::{DeepSkyCatalog, DeepSkyObject, DeepSkyType};
+pub use export::{ExportColumn, ExportOptions};
 pub use features::{FeatureCatalog, FeatureType, SkyFeature};
-pub use gaia::{GaiaCatalog, GaiaEntry};
+pub use gaia::{DataRelease, GaiaCatalog, GaiaCatalogReader, GaiaColumns, GaiaEntry, GaiaExtras};
+pub use gcvs::{Extremum, GcvsCatalog, GcvsEntry};
+pub use guide_stars::{
+    select_guide_stars, GuideDetector, GuideStarCandidate, GuideStarConstraints,
+};
 pub use hipparcos::{HipparcosCatalog, HipparcosEntry};
+pub use orb6::{BinaryOrbit, BinaryPosition, Orb6Catalog, Orb6Entry};
+pub use photometry::{Band, Photometry, PopulationComponent, StellarPopulation};
+pub use sharded::{ShardIndex, ShardedCatalog, ShardedCatalogWriter};
+pub use spatial_index::SkyIndex;
 pub use synthetic::{
-    create_fov_catalog, create_synthetic_catalog, MagnitudeDistribution, SpatialDistribution,
-    SyntheticCatalogConfig,
+    create_fov_catalog, create_synthetic_catalog, GalaxyModel, MagnitudeDistribution,
+    SpatialDistribution, SyntheticCatalogConfig,
 };
-
-use rand::distributions::{Distribution, Uniform};
-use rand::rngs::StdRng;
-use rand::SeedableRng;
-use std::path::PathBuf;
+pub use window::{CatalogSource, WindowQuery, WindowQueryError, WindowResult};
 
 /// Trait for accessing star position data
 pub trait StarPosition {
@@ -35,9 +53,17 @@ pub trait StarPosition {
     fn dec(&self) -> f64;
 }
 
+/// Trait for catalog entries that may carry a proper motion
+pub trait ProperMotion {
+    /// Proper motion (μα*, μδ) in mas/yr, the first including the cos δ
+    /// factor
+    fn proper_motion_mas_yr(&self) -> Option<(f64, f64)>;
+}
+
 /// Common star properties that all catalog entries must provide
 /// This represents the minimal set of properties required for rendering and calculations
 #[derive(Debug, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct StarData {
     /// Star identifier
     pub id: u64,
@@ -47,6 +73,9 @@ pub struct StarData {
     pub magnitude: f64,
     /// Optional B-V color index for rendering
     pub b_v: Option<f64>,
+    /// Magnitudes in other bands, where measured or modelled
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub bands: Photometry,
 }
 
 impl StarData {
@@ -57,6 +86,7 @@ impl StarData {
             position: Equatorial::from_degrees(ra_deg, dec_deg),
             magnitude,
             b_v,
+            bands: Photometry::default(),
         }
     }
 
@@ -67,9 +97,21 @@ impl StarData {
             position,
             magnitude,
             b_v,
+            bands: Photometry::default(),
         }
     }
 
+    /// Set the magnitudes in other bands
+    pub fn with_bands(mut self, bands: Photometry) -> Self {
+        self.bands = bands;
+        self
+    }
+
+    /// Magnitude in `band`, if known
+    pub fn magnitude_in(&self, band: Band) -> Option<f64> {
+        self.bands.get(band)
+    }
+
     /// Get right ascension in degrees
     pub fn ra_deg(&self) -> f64 {
         self.position.ra_degrees()
@@ -79,6 +121,16 @@ impl StarData {
     pub fn dec_deg(&self) -> f64 {
         self.position.dec_degrees()
     }
+
+    /// Position in galactic longitude and latitude
+    pub fn galactic(&self) -> Galactic {
+        self.position.into()
+    }
+
+    /// Position in J2000 ecliptic longitude and latitude
+    pub fn ecliptic(&self) -> Ecliptic {
+        self.position.into()
+    }
 }
 
 impl StarPosition for StarData {
@@ -110,6 +162,11 @@ pub trait StarCatalog {
         self.len() == 0
     }
 
+    /// Epoch of the star positions as a Julian year, if known
+    fn epoch(&self) -> Option<f64> {
+        None
+    }
+
     /// Filter stars based on a predicate
     fn filter<F>(&self, predicate: F) -> Vec<&Self::Star>
     where
@@ -147,128 +204,102 @@ pub trait StarCatalog {
             cos_dist > cos_radius
         })
     }
-}
 
-/// Options for star catalog sources
-#[derive(Debug, Clone)]
-pub enum CatalogSource {
-    /// Hipparcos catalog (default path in cache)
-    Hipparcos,
-    /// Binary catalog with specified path (checks relative path and cache)
-    Binary(PathBuf),
-    /// Random synthetic stars with specified seed and count
-    Random { seed: u64, count: usize },
+    /// Get stars within a circular field of view centered on a position in
+    /// any inertial frame, such as [`Galactic`] or [`Ecliptic`]
+    fn stars_in_field_at<C>(&self, center: C, fov_deg: f64) -> Vec<StarData>
+    where
+        C: InertialFrame + Into<Equatorial>,
+    {
+        let center: Equatorial = center.into();
+        self.stars_in_field(center.ra_degrees(), center.dec_degrees(), fov_deg)
+    }
 }
 
 /// Get stars from the specified catalog source
+///
+/// Hipparcos stars are cut at magnitude 8.0 and read from `hip_main.dat` in
+/// the working directory as they always have been, or else from the default
+/// cache. Nothing is downloaded, and random stars fill the square RA/Dec box
+/// around `position` as they always have.
+#[deprecated(note = "use `WindowQuery`, which honors the `Loader`'s data directory and cache")]
 pub fn get_stars_in_window(
     source: CatalogSource,
     position: Equatorial,
     fov_deg: f64,
 ) -> crate::Result<Vec<StarData>> {
-    let ra_deg = position.ra_degrees();
-    let dec_deg = position.dec_degrees();
-
-    match source {
-        CatalogSource::Random { seed, count } => {
-            println!("Using synthetic stars ({})", count);
-            println!("Seed: {}", seed);
-            Ok(generate_synthetic_stars(
-                count, ra_deg, dec_deg, fov_deg, seed,
-            ))
-        }
-
-        CatalogSource::Binary(path) => {
-            println!("Loading binary catalog from: {}", path.display());
-            let catalog = BinaryCatalog::load(&path)?;
-            println!("Loaded catalog: {}", catalog.description());
-            println!("Total stars in catalog: {}", catalog.len());
-
-            // Get stars in the specified field
-            let stars = catalog.stars_in_field(ra_deg, dec_deg, fov_deg);
-            println!("Found {} stars in field of view", stars.len());
-
-            Ok(stars)
-        }
+    if let CatalogSource::Random { seed, count } = source {
+        return Ok(generate_square_synthetic_stars(
+            count,
+            position.ra_degrees(),
+            position.dec_degrees(),
+            fov_deg,
+            seed,
+        ));
+    }
 
-        CatalogSource::Hipparcos => {
-            // Default path for Hipparcos catalog
-            let path = PathBuf::from("hip_main.dat");
-            if !path.exists() {
-                return Err(crate::StarfieldError::DataError(format!(
-                    "Hipparcos catalog not found at: {}",
-                    path.display()
-                )));
-            }
-
-            println!("Loading Hipparcos catalog from: {}", path.display());
-            // Use the Hipparcos parser
-            let catalog = HipparcosCatalog::from_dat_file(&path, 8.0)?;
-            println!("Total stars in catalog: {}", catalog.len());
-
-            // Get stars in the specified field
-            let stars = catalog.stars_in_field(ra_deg, dec_deg, fov_deg);
-            println!("Found {} stars in field of view", stars.len());
-
-            Ok(stars)
+    let mut query = WindowQuery::new(source.clone(), position, fov_deg);
+    if source == CatalogSource::Hipparcos {
+        let path = std::path::Path::new("hip_main.dat");
+        if path.is_file() {
+            log::info!("Loading Hipparcos catalog from: {}", path.display());
+            let catalog = HipparcosCatalog::from_dat_file(path, 8.0)?;
+            return Ok(catalog.stars_in_field(
+                position.ra_degrees(),
+                position.dec_degrees(),
+                fov_deg,
+            ));
         }
+        query = query.with_mag_limit(8.0);
+    }
+    match query.execute(&crate::Loader::new().offline(true)) {
+        Ok(result) => Ok(result.stars),
+        Err(WindowQueryError::Load(e)) => Err(e),
+        Err(e) => Err(crate::StarfieldError::DataError(e.to_string())),
     }
 }
 
-/// Generate synthetic stars for testing without a catalog
-fn generate_synthetic_stars(
+/// Synthetic stars uniform in RA and Dec over a square box, as
+/// [`get_stars_in_window`] has always produced them
+fn generate_square_synthetic_stars(
     count: usize,
     center_ra: f64,
     center_dec: f64,
     fov_deg: f64,
     seed: u64,
 ) -> Vec<StarData> {
-    // Create a seeded RNG for reproducible results
-    let mut rng = StdRng::seed_from_u64(seed);
-    let mut stars = Vec::with_capacity(count);
+    use rand::distributions::{Distribution, Uniform};
+    use rand::SeedableRng;
 
-    // Half FOV in degrees
+    let mut rng = rand::rngs::StdRng::seed_from_u64(seed);
     let half_fov = fov_deg / 2.0;
-
-    // Distributions for random star positions and magnitudes
     let ra_dist = Uniform::from(center_ra - half_fov..center_ra + half_fov);
     let dec_dist = Uniform::from(center_dec - half_fov..center_dec + half_fov);
 
-    // For realistic magnitude distribution, use exponential distribution
-    // For every step in magnitude, there are ~2.5x more stars
-    let min_mag = 3.0; // Brightest stars (lower magnitude = brighter)
-    let max_mag = 8.0; // Dimmest stars
-
-    // We'll generate random values and transform them to follow stellar magnitude distribution
+    // Magnitudes from 3 to 8, with ~2.5x more stars per magnitude step
+    let min_mag = 3.0;
+    let max_mag = 8.0;
     let uniform = Uniform::from(0.0..1.0);
 
-    for id in 1..=count {
-        let ra = ra_dist.sample(&mut rng);
-        let dec = dec_dist.sample(&mut rng);
+    (1..=count)
+        .map(|id| {
+            let ra = ra_dist.sample(&mut rng);
+            let dec = dec_dist.sample(&mut rng);
 
-        // Generate magnitude using the exponential distribution
-        let u = uniform.sample(&mut rng);
+            let log_base: f64 = 2.5; // Pogson ratio
+            let exp_range = log_base.powf(max_mag - min_mag) - 1.0;
+            let t: f64 = uniform.sample(&mut rng) * exp_range + 1.0;
+            let magnitude = min_mag + t.log(log_base).clamp(0.0, max_mag - min_mag);
 
-        // Transform uniform distribution to exponential distribution
-        // Using the fact that for every magnitude step, we have 2.5× more stars
-        let log_base: f64 = 2.5; // Pogson ratio
-        let exp_range = log_base.powf(max_mag - min_mag) - 1.0;
-        let t: f64 = u * exp_range + 1.0; // Transform to [1, 2.5^range]
-
-        // Convert back to magnitude scale
-        let magnitude = min_mag + t.log(log_base).clamp(0.0, max_mag - min_mag);
-
-        // Generate random B-V color index (simplified)
-        let b_v = if uniform.sample(&mut rng) > 0.3 {
-            Some(uniform.sample(&mut rng) * 2.0 - 0.3) // Range from -0.3 to 1.7
-        } else {
-            None
-        };
+            let b_v = if uniform.sample(&mut rng) > 0.3 {
+                Some(uniform.sample(&mut rng) * 2.0 - 0.3)
+            } else {
+                None
+            };
 
-        stars.push(StarData::new(id as u64, ra, dec, magnitude, b_v));
-    }
-
-    stars
+            StarData::new(id as u64, ra, dec, magnitude, b_v)
+        })
+        .collect()
 }
 
 #[cfg(test)]
@@ -305,4 +336,103 @@ mod tests {
             .unwrap();
         assert_eq!(brightest.magnitude, -1.5);
     }
+
+    #[test]
+    fn test_field_in_other_frames() {
+        // Stars along the galactic equator and one at the north galactic pole
+        let mut stars: Vec<MinimalStar> = (0..36)
+            .map(|i| {
+                let p: Equatorial = Galactic::from_degrees(i as f64 * 10.0, 0.0).into();
+                MinimalStar::new(i, p.ra_degrees(), p.dec_degrees(), 5.0)
+            })
+            .collect();
+        let ngp: Equatorial = Galactic::from_degrees(0.0, 90.0).into();
+        stars.push(MinimalStar::new(
+            99,
+            ngp.ra_degrees(),
+            ngp.dec_degrees(),
+            5.0,
+        ));
+        let catalog = BinaryCatalog::from_stars(stars, "Galactic plane");
+
+        let around_center = catalog.stars_in_field_at(Galactic::from_degrees(0.0, 0.0), 25.0);
+        let mut ids: Vec<u64> = around_center.iter().map(|s| s.id).collect();
+        ids.sort();
+        assert_eq!(ids, vec![0, 1, 35]);
+        assert!(around_center[0].galactic().lat_degrees().abs() < 1e-6);
+
+        let pole = catalog.stars_in_field_at(Galactic::from_degrees(0.0, 90.0), 2.0);
+        assert_eq!(pole.len(), 1);
+        assert!((pole[0].galactic().lat_degrees() - 90.0).abs() < 1e-6);
+
+        // The same query in equatorial coordinates
+        let center: Equatorial = Galactic::from_degrees(0.0, 0.0).into();
+        assert_eq!(
+            catalog.stars_in_field_at(center, 25.0).len(),
+            catalog
+                .stars_in_field(center.ra_degrees(), center.dec_degrees(), 25.0)
+                .len()
+        );
+
+        let plane: Vec<StarData> =
+            catalog.filter_star_data(|s| s.galactic().lat_degrees().abs() < 5.0);
+        assert_eq!(plane.len(), 36);
+        let ecliptic_pole = StarData::new(0, 270.0, 66.560_708_3, 5.0, None).ecliptic();
+        assert!((ecliptic_pole.lat_degrees() - 90.0).abs() < 1e-4);
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_serde_roundtrip() {
+        let star = StarData::new(32349, 101.287, -16.716, -1.46, Some(0.0))
+            .with_bands(Photometry::from_v_and_b_v(-1.46, 0.0));
+        let json = serde_json::to_string(&star).unwrap();
+        let back: StarData = serde_json::from_str(&json).unwrap();
+        assert_eq!(back.id, star.id);
+        assert_eq!(back.position, star.position);
+        assert_eq!(back.bands, star.bands);
+
+        let sirius = HipparcosCatalog::create_synthetic()
+            .stars()
+            .next()
+            .unwrap()
+            .clone();
+        let json = serde_json::to_string(&sirius).unwrap();
+        let back: HipparcosEntry = serde_json::from_str(&json).unwrap();
+        assert_eq!(
+            (back.hip, back.ra, back.pm_ra),
+            (sirius.hip, sirius.ra, sirius.pm_ra)
+        );
+
+        let feature = FeatureCatalog::default()
+            .get_feature("Orion")
+            .unwrap()
+            .clone();
+        let back: SkyFeature =
+            serde_json::from_str(&serde_json::to_string(&feature).unwrap()).unwrap();
+        assert_eq!(back.name, "Orion");
+        assert_eq!(back.feature_type, FeatureType::Constellation);
+    }
+
+    #[test]
+    #[allow(deprecated)]
+    fn test_window_shim_keeps_square_random_fields() {
+        let source = CatalogSource::Random {
+            seed: 7,
+            count: 500,
+        };
+        let center = Equatorial::from_degrees(100.0, 0.0);
+        let stars = get_stars_in_window(source.clone(), center, 4.0).unwrap();
+        assert_eq!(stars.len(), 500);
+        assert!(stars
+            .iter()
+            .all(|s| (s.ra_deg() - 100.0).abs() <= 2.0 && s.dec_deg().abs() <= 2.0));
+        // The corners of the box lie outside the inscribed circle
+        assert!(stars
+            .iter()
+            .any(|s| center.angular_distance(&s.position) > 2f64.to_radians()));
+
+        let again = get_stars_in_window(source, center, 4.0).unwrap();
+        assert_eq!(again[0].position, stars[0].position);
+    }
 }
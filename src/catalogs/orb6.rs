@@ -0,0 +1,380 @@
+//! Sixth Catalog of Orbits of Visual Binary Stars (ORB6)
+//!
+//! Reads the fixed-column `orb6orbits.txt` maintained by the US Naval
+//! Observatory alongside the Washington Double Star Catalog, and predicts
+//! where the companion of a visual binary stands relative to its primary.
+//!
+//! Each orbit is given by the Campbell elements: period, semi-major axis
+//! in arcseconds, inclination, position angle of the node, time of
+//! periastron, eccentricity and argument of periastron. The apparent
+//! orbit follows from the Thiele-Innes construction (Meeus, *Astronomical
+//! Algorithms*, ch. 57). Position angles are measured from north through
+//! east and are referred to the equinox of the elements; precession turns
+//! them by less than 0.01° per year away from the poles.
+
+use super::gcvs::parse_position;
+use super::StarPosition;
+use crate::constants::{B1950, DEG2RAD, RAD2DEG, TAU};
+use crate::coordinates::Equatorial;
+use crate::planetlib::elements::solve_kepler;
+use crate::time::{Time, TimeArray};
+use crate::{Result, StarfieldError};
+use std::path::Path;
+
+/// Length of the Besselian year in days, the unit of ORB6 periods and epochs
+const BESSELIAN_YEAR_DAYS: f64 = 365.242_198_781;
+
+/// ORB6 times of periastron coded `d` are given as JD - 2400000
+const EPOCH_OFFSET_JD: f64 = 2_400_000.0;
+
+/// Modified Julian Date zero point, for times of periastron coded `m`
+const MJD_ZERO: f64 = 2_400_000.5;
+
+/// Julian date of a Besselian epoch such as 1934.008
+fn besselian_epoch_jd(year: f64) -> f64 {
+    B1950 + (year - 1950.0) * BESSELIAN_YEAR_DAYS
+}
+
+/// Apparent place of a companion relative to its primary
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BinaryPosition {
+    /// Angular separation in arcseconds
+    pub separation_arcsec: f64,
+    /// Position angle of the companion in degrees, north through east
+    pub position_angle_deg: f64,
+}
+
+/// Campbell elements of a visual binary's relative orbit
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BinaryOrbit {
+    /// Period in days
+    pub period_days: f64,
+    /// Semi-major axis in arcseconds
+    pub semi_major_axis_arcsec: f64,
+    /// Inclination in degrees; above 90° the motion is clockwise on the sky
+    pub inclination_deg: f64,
+    /// Position angle of the ascending node in degrees
+    pub node_deg: f64,
+    /// Time of periastron passage as a Julian date
+    pub periastron_jd: f64,
+    /// Eccentricity
+    pub eccentricity: f64,
+    /// Argument of periastron in degrees, measured in the orbit from the node
+    pub periastron_arg_deg: f64,
+}
+
+impl BinaryOrbit {
+    /// Separation and position angle at `t`
+    pub fn position_at(&self, t: &Time) -> BinaryPosition {
+        let mean_anomaly = (TAU * (t.tt() - self.periastron_jd) / self.period_days).rem_euclid(TAU);
+        let e = self.eccentricity;
+        let ecc_anomaly = solve_kepler(mean_anomaly, e);
+        let true_anomaly = 2.0
+            * f64::atan2(
+                (1.0 + e).sqrt() * (ecc_anomaly / 2.0).sin(),
+                (1.0 - e).sqrt() * (ecc_anomaly / 2.0).cos(),
+            );
+        let radius = self.semi_major_axis_arcsec * (1.0 - e * ecc_anomaly.cos());
+
+        // Offsets towards north and east of the primary
+        let u = true_anomaly + self.periastron_arg_deg * DEG2RAD;
+        let (sin_node, cos_node) = (self.node_deg * DEG2RAD).sin_cos();
+        let cos_i = (self.inclination_deg * DEG2RAD).cos();
+        let north = radius * (u.cos() * cos_node - u.sin() * sin_node * cos_i);
+        let east = radius * (u.cos() * sin_node + u.sin() * cos_node * cos_i);
+
+        BinaryPosition {
+            separation_arcsec: north.hypot(east),
+            position_angle_deg: (east.atan2(north) * RAD2DEG).rem_euclid(360.0),
+        }
+    }
+
+    /// Separation and position angle at each of `times`
+    pub fn positions(&self, times: &TimeArray) -> Vec<BinaryPosition> {
+        times.iter().map(|t| self.position_at(&t)).collect()
+    }
+}
+
+/// One orbit from ORB6
+#[derive(Debug, Clone, PartialEq)]
+pub struct Orb6Entry {
+    /// J2000 position of the primary
+    pub position: Option<Equatorial>,
+    /// WDS designation, such as `00057+4549`
+    pub wds: String,
+    /// Discoverer code and number, such as `STT 547AB`
+    pub discoverer: String,
+    /// Henry Draper catalog number
+    pub hd: Option<u32>,
+    /// Hipparcos catalog number
+    pub hip: Option<u32>,
+    /// V magnitude of the primary
+    pub primary_magnitude: Option<f64>,
+    /// V magnitude of the secondary
+    pub secondary_magnitude: Option<f64>,
+    /// The relative orbit
+    pub orbit: BinaryOrbit,
+    /// Equinox the node is referred to, as a year
+    pub equinox: Option<i32>,
+    /// Year of the last observation used in the solution
+    pub last_observation: Option<i32>,
+    /// Orbit grade, from 1 (definitive) to 5 (indeterminate); 8 and 9 mark
+    /// astrometric and interferometric orbits
+    pub grade: Option<u8>,
+    /// Bibliographic code of the orbit's source
+    pub reference: String,
+}
+
+/// Text between 1-based columns `start` and `end` inclusive, trimmed
+fn columns(line: &str, start: usize, end: usize) -> &str {
+    line.get(start - 1..end.min(line.len()))
+        .unwrap_or("")
+        .trim()
+}
+
+/// Number in a fixed-column field, or `None` if blank
+fn number<T: std::str::FromStr>(line: &str, start: usize, end: usize) -> Option<T> {
+    columns(line, start, end).parse().ok()
+}
+
+impl Orb6Entry {
+    /// Parse one catalog line, or `None` for headers and lines without a
+    /// complete set of elements
+    pub fn parse_line(line: &str) -> Option<Self> {
+        let period: f64 = number(line, 81, 91)?;
+        let period_days = period
+            * match columns(line, 92, 92) {
+                "m" => 1.0 / 1440.0,
+                "h" => 1.0 / 24.0,
+                "d" => 1.0,
+                "c" => 100.0 * BESSELIAN_YEAR_DAYS,
+                _ => BESSELIAN_YEAR_DAYS,
+            };
+        let axis: f64 = number(line, 106, 114)?;
+        let semi_major_axis_arcsec = axis
+            * match columns(line, 115, 115) {
+                "m" => 1e-3,
+                "u" => 1e-6,
+                _ => 1.0,
+            };
+        let periastron: f64 = number(line, 164, 175)?;
+        let periastron_jd = match columns(line, 176, 176) {
+            "d" => periastron + EPOCH_OFFSET_JD,
+            "m" => periastron + MJD_ZERO,
+            _ => besselian_epoch_jd(periastron),
+        };
+
+        Some(Self {
+            position: parse_position(columns(line, 1, 18)),
+            wds: columns(line, 20, 29).to_string(),
+            discoverer: normalize_designation(columns(line, 31, 44)),
+            hd: number(line, 52, 57),
+            hip: number(line, 59, 64),
+            primary_magnitude: number(line, 66, 70),
+            secondary_magnitude: number(line, 73, 77),
+            orbit: BinaryOrbit {
+                period_days,
+                semi_major_axis_arcsec,
+                inclination_deg: number(line, 127, 134)?,
+                node_deg: number(line, 145, 152)?,
+                periastron_jd,
+                eccentricity: number::<f64>(line, 189, 196).filter(|e| (0.0..1.0).contains(e))?,
+                periastron_arg_deg: number(line, 207, 214)?,
+            },
+            equinox: number(line, 226, 229),
+            last_observation: number(line, 231, 234),
+            grade: number(line, 236, 236),
+            reference: columns(line, 240, 247).to_string(),
+        })
+        .filter(|entry| entry.orbit.period_days > 0.0)
+    }
+
+    /// Separation and position angle at `t`
+    pub fn position_at(&self, t: &Time) -> BinaryPosition {
+        self.orbit.position_at(t)
+    }
+}
+
+impl StarPosition for Orb6Entry {
+    fn ra(&self) -> f64 {
+        self.position.map_or(f64::NAN, |p| p.ra_degrees())
+    }
+
+    fn dec(&self) -> f64 {
+        self.position.map_or(f64::NAN, |p| p.dec_degrees())
+    }
+}
+
+/// Collapse runs of spaces in a designation, so `STT  547AB` matches
+/// `STT 547AB`
+fn normalize_designation(name: &str) -> String {
+    name.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// The orbits of ORB6
+#[derive(Debug, Clone, Default)]
+pub struct Orb6Catalog {
+    entries: Vec<Orb6Entry>,
+}
+
+impl Orb6Catalog {
+    /// Parse the text of `orb6orbits.txt`, skipping header lines and
+    /// orbits with missing elements
+    pub fn parse(text: &str) -> Result<Self> {
+        let entries: Vec<Orb6Entry> = text.lines().filter_map(Orb6Entry::parse_line).collect();
+        if entries.is_empty() && !text.trim().is_empty() {
+            return Err(StarfieldError::DataError(
+                "no ORB6 orbits found".to_string(),
+            ));
+        }
+        Ok(Self { entries })
+    }
+
+    /// Load `orb6orbits.txt` from disk
+    pub fn from_file<P: AsRef<Path>>(path: P) -> Result<Self> {
+        Self::parse(&std::fs::read_to_string(path)?)
+    }
+
+    /// Orbits of a pair, by WDS or discoverer designation, ignoring case
+    /// and spacing; a WDS system can have orbits for several pairs
+    pub fn get(&self, designation: &str) -> Vec<&Orb6Entry> {
+        let designation = normalize_designation(designation);
+        self.entries
+            .iter()
+            .filter(|e| {
+                e.wds.eq_ignore_ascii_case(&designation)
+                    || e.discoverer.eq_ignore_ascii_case(&designation)
+            })
+            .collect()
+    }
+
+    /// All entries
+    pub fn entries(&self) -> &[Orb6Entry] {
+        &self.entries
+    }
+
+    /// Number of entries
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Whether the catalog is empty
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::time::{TimeDelta, Timescale};
+    use approx::assert_relative_eq;
+
+    /// A catalog line with each field starting at its 1-based column
+    fn line(fields: &[(usize, &str)]) -> String {
+        let mut line = vec![b' '; 256];
+        for &(start, text) in fields {
+            line[start - 1..start - 1 + text.len()].copy_from_slice(text.as_bytes());
+        }
+        String::from_utf8(line).unwrap().trim_end().to_string()
+    }
+
+    /// Eta Coronae Borealis, with Meeus's elements (Example 57.a)
+    fn eta_crb() -> String {
+        line(&[
+            (1, "152314.37+301716.4"),
+            (20, "15232+3017"),
+            (31, "STF1937AB"),
+            (52, "137107"),
+            (59, " 75312"),
+            (66, " 5.64"),
+            (73, " 5.95"),
+            (81, "     41.623"),
+            (92, "y"),
+            (106, "  0.90700"),
+            (115, "a"),
+            (127, " 59.0250"),
+            (145, " 23.7170"),
+            (164, "    1934.008"),
+            (176, "y"),
+            (189, "0.276300"),
+            (207, "219.9070"),
+            (226, "2000"),
+            (231, "2019"),
+            (236, "1"),
+            (240, "Mee1991"),
+        ])
+    }
+
+    #[test]
+    fn test_parse_catalog() {
+        let text = [
+            "RA,Dec (J2000)     WDS        Discoverer",
+            &eta_crb(),
+            &line(&[
+                (20, "00057+4549"),
+                (31, "STT 547AB"),
+                (81, "   1040.5"),
+                (92, "d"),
+                (106, "  123.000"),
+                (115, "m"),
+                (127, " 90.0000"),
+                (145, "  0.0000"),
+                (164, "   58000.000"),
+                (176, "m"),
+                (189, "0.500000"),
+                (207, "  0.0000"),
+            ]),
+            // No eccentricity: not a usable orbit
+            &line(&[(20, "01234+5678"), (81, "12.0"), (92, "y"), (106, "1.0")]),
+        ]
+        .join("\n");
+        let catalog = Orb6Catalog::parse(&text).unwrap();
+        assert_eq!(catalog.len(), 2);
+
+        let eta = &catalog.get("stf1937ab")[0];
+        assert_eq!(eta.wds, "15232+3017");
+        assert_eq!(
+            (eta.hd, eta.hip, eta.grade),
+            (Some(137107), Some(75312), Some(1))
+        );
+        assert_relative_eq!(eta.orbit.period_days, 41.623 * BESSELIAN_YEAR_DAYS);
+        assert_relative_eq!(
+            eta.position.unwrap().dec_degrees(),
+            30.287_89,
+            epsilon = 1e-4
+        );
+
+        let stt = &catalog.get("00057+4549")[0];
+        assert_eq!(stt.discoverer, "STT 547AB");
+        assert_relative_eq!(stt.orbit.semi_major_axis_arcsec, 0.123);
+        assert_relative_eq!(stt.orbit.periastron_jd, 2_458_000.5);
+        assert!(Orb6Catalog::parse("not a catalog").is_err());
+    }
+
+    #[test]
+    fn test_eta_crb_meeus() {
+        let ts = Timescale::default();
+        let entry = Orb6Entry::parse_line(&eta_crb()).unwrap();
+
+        // Meeus finds 318.4° at 0.411″ for 1980.0
+        let t = ts.tt_jd(besselian_epoch_jd(1980.0), None);
+        let p = entry.position_at(&t);
+        assert_relative_eq!(p.position_angle_deg, 318.4, epsilon = 0.05);
+        assert_relative_eq!(p.separation_arcsec, 0.411, epsilon = 0.0005);
+
+        // The apparent orbit repeats every period
+        let later = &t + TimeDelta::days(entry.orbit.period_days);
+        assert_relative_eq!(
+            entry.position_at(&later).position_angle_deg,
+            p.position_angle_deg,
+            epsilon = 1e-6
+        );
+
+        let track = entry.orbit.positions(&ts.linspace(&t, &later, 9));
+        assert_eq!(track.len(), 9);
+        assert!(track
+            .iter()
+            .all(|p| p.separation_arcsec <= 0.907 * (1.0 + 0.2763)));
+    }
+}
@@ -0,0 +1,381 @@
+//! Magnitudes in several photometric bands
+//!
+//! Catalogs usually give a star's brightness in one band and a color index.
+//! Camera simulations with other filters need magnitudes in their own band,
+//! so [`Photometry::from_v_and_b_v`] extends a V magnitude and B-V color to
+//! Cousins R and I and Gaia G with main-sequence color relations, and
+//! [`StellarPopulation`] draws B-V colors for synthetic stars from a mix of
+//! spectral types.
+//!
+//! For comparing catalogs and rendering images there are also empirical
+//! conversions from Gaia photometry to Johnson V, from B-V to effective
+//! temperature and display color, and from V to a photon rate:
+//!
+//! ```
+//! use starfield::catalogs::photometry::{b_v_to_rgb, effective_temperature, photon_rate};
+//! use starfield::catalogs::{Band, Photometry};
+//!
+//! // A solar-type star
+//! let sun_like = Photometry::from_v_and_b_v(5.0, 0.65);
+//! let v_minus_i = sun_like.get(Band::V).unwrap() - sun_like.get(Band::I).unwrap();
+//! assert!((v_minus_i - 0.71).abs() < 0.02);
+//! assert!((effective_temperature(0.65) - 5780.0).abs() < 50.0);
+//! let [r, g, b] = b_v_to_rgb(0.65);
+//! assert!(r >= g && g > b);
+//!
+//! // Photons per second from a 5th magnitude star through a 20 cm aperture
+//! // and a 90 nm wide V filter
+//! let area_m2 = std::f64::consts::PI * 0.1 * 0.1;
+//! println!("{:.3e} photons/s", photon_rate(5.0, area_m2, 90.0));
+//! ```
+
+use rand::Rng;
+use std::f64::consts::PI;
+use std::fmt;
+
+/// Range of B-V over which the color relations hold
+const B_V_RANGE: (f64, f64) = (-0.3, 1.6);
+
+/// Range of BP-RP over which the Gaia DR3 G-V relation holds
+const BP_RP_RANGE: (f64, f64) = (-0.5, 2.75);
+
+/// Photon flux of a V = 0 star at 550 nm, photons/s/m²/nm
+const V_ZERO_PHOTON_FLUX: f64 = 1.005e8;
+
+/// A photometric band
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Band {
+    /// Johnson V
+    V,
+    /// Gaia G
+    G,
+    /// Cousins R
+    R,
+    /// Cousins I
+    I,
+}
+
+impl Band {
+    /// Every band, in order of increasing wavelength
+    pub const ALL: [Band; 4] = [Band::V, Band::G, Band::R, Band::I];
+
+    /// Short name of the band, as used in catalog metadata
+    pub fn name(&self) -> &'static str {
+        match self {
+            Band::V => "V",
+            Band::G => "G",
+            Band::R => "R",
+            Band::I => "I",
+        }
+    }
+
+    /// Band with the given short name
+    pub fn from_name(name: &str) -> Option<Band> {
+        Self::ALL.into_iter().find(|band| band.name() == name)
+    }
+}
+
+impl fmt::Display for Band {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+/// Magnitudes of one star in each band, where known
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Photometry {
+    /// Johnson V magnitude
+    pub v: Option<f64>,
+    /// Gaia G magnitude
+    pub g: Option<f64>,
+    /// Cousins R magnitude
+    pub r: Option<f64>,
+    /// Cousins I magnitude
+    pub i: Option<f64>,
+}
+
+impl Photometry {
+    /// Magnitudes of a star with visual magnitude `v` and color `b_v`
+    ///
+    /// V-R and V-I are quadratic fits to Bessell's (1990) main-sequence
+    /// colors and G-V is the relation of Evans et al. (2018). Giants are
+    /// redder in V-I than dwarfs of the same B-V by up to a tenth of a
+    /// magnitude; colors outside -0.3 < B-V < 1.6 are clamped to it.
+    pub fn from_v_and_b_v(v: f64, b_v: f64) -> Self {
+        let b_v = b_v.clamp(B_V_RANGE.0, B_V_RANGE.1);
+        let v_r = 0.524 * b_v + 0.046 * b_v * b_v;
+        let v_i = 0.96 * b_v + 0.2 * b_v * b_v;
+        let g_v = -0.01746 + 0.008092 * v_i - 0.2810 * v_i.powi(2) + 0.03655 * v_i.powi(3);
+
+        Self {
+            v: Some(v),
+            g: Some(v + g_v),
+            r: Some(v - v_r),
+            i: Some(v - v_i),
+        }
+    }
+
+    /// Magnitudes of a star with Gaia G magnitude `g` and color `bp_rp`
+    ///
+    /// Fills in G and V; see [`johnson_v_from_gaia`].
+    pub fn from_gaia(g: f64, bp_rp: f64) -> Self {
+        Self {
+            v: Some(johnson_v_from_gaia(g, bp_rp)),
+            g: Some(g),
+            ..Self::default()
+        }
+    }
+
+    /// Magnitude in `band`, if known
+    pub fn get(&self, band: Band) -> Option<f64> {
+        match band {
+            Band::V => self.v,
+            Band::G => self.g,
+            Band::R => self.r,
+            Band::I => self.i,
+        }
+    }
+
+    /// Set the magnitude in `band`
+    pub fn with(mut self, band: Band, magnitude: f64) -> Self {
+        let slot = match band {
+            Band::V => &mut self.v,
+            Band::G => &mut self.g,
+            Band::R => &mut self.r,
+            Band::I => &mut self.i,
+        };
+        *slot = Some(magnitude);
+        self
+    }
+
+    /// Whether no band is known
+    pub fn is_empty(&self) -> bool {
+        Band::ALL.iter().all(|&band| self.get(band).is_none())
+    }
+}
+
+/// Johnson V magnitude of a star with Gaia G magnitude `g` and color
+/// `bp_rp`
+///
+/// Uses the cubic G-V relation of Riello et al. (2021) for Gaia DR3, good
+/// to about 0.05 mag; colors outside -0.5 < BP-RP < 2.75 are clamped to it.
+pub fn johnson_v_from_gaia(g: f64, bp_rp: f64) -> f64 {
+    let c = bp_rp.clamp(BP_RP_RANGE.0, BP_RP_RANGE.1);
+    let g_v = -0.02704 + c * (0.01424 + c * (-0.2156 + c * 0.01426));
+    g - g_v
+}
+
+/// Effective temperature in kelvin of a star with color `b_v`
+///
+/// The blackbody fit of Ballesteros (2012), within a few percent for main
+/// sequence stars from late B to early M.
+pub fn effective_temperature(b_v: f64) -> f64 {
+    4600.0 * (1.0 / (0.92 * b_v + 1.7) + 1.0 / (0.92 * b_v + 0.62))
+}
+
+/// Display color of a blackbody at `temperature_k`, as sRGB components in
+/// [0, 1] with the brightest at 1
+///
+/// Tanner Helland's fit to the blackbody locus, for 1000 K to 40000 K.
+pub fn blackbody_rgb(temperature_k: f64) -> [f64; 3] {
+    let t = temperature_k.clamp(1000.0, 40_000.0) / 100.0;
+    let red = if t <= 66.0 {
+        255.0
+    } else {
+        329.698_727_446 * (t - 60.0).powf(-0.133_204_759_2)
+    };
+    let green = if t <= 66.0 {
+        99.470_802_586_1 * t.ln() - 161.119_568_166_1
+    } else {
+        288.122_169_528_3 * (t - 60.0).powf(-0.075_514_849_2)
+    };
+    let blue = if t >= 66.0 {
+        255.0
+    } else if t <= 19.0 {
+        0.0
+    } else {
+        138.517_731_223_1 * (t - 10.0).ln() - 305.044_792_730_7
+    };
+    [red, green, blue].map(|c| (c / 255.0).clamp(0.0, 1.0))
+}
+
+/// Display color of a star with color `b_v`; see [`blackbody_rgb`]
+pub fn b_v_to_rgb(b_v: f64) -> [f64; 3] {
+    blackbody_rgb(effective_temperature(b_v))
+}
+
+/// Photons per second from a star of magnitude `v` collected by an aperture
+/// of `aperture_area_m2` through a band `bandwidth_nm` wide
+///
+/// Treats the band as flat at the V-band photon flux, so for other filters
+/// it is only as good as the star's color is close to that of Vega.
+pub fn photon_rate(v: f64, aperture_area_m2: f64, bandwidth_nm: f64) -> f64 {
+    V_ZERO_PHOTON_FLUX * 10f64.powf(-0.4 * v) * aperture_area_m2 * bandwidth_nm
+}
+
+/// One group of stars in a [`StellarPopulation`]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PopulationComponent {
+    /// Fraction of the stars in this group (normalized over all groups)
+    pub weight: f64,
+    /// Mean B-V color
+    pub mean_b_v: f64,
+    /// Spread of B-V about the mean
+    pub sigma_b_v: f64,
+}
+
+/// Distribution of stellar colors, as a mixture of Gaussians in B-V
+#[derive(Debug, Clone, PartialEq)]
+pub struct StellarPopulation {
+    components: Vec<PopulationComponent>,
+}
+
+impl Default for StellarPopulation {
+    /// Colors of a magnitude-limited sample of field stars: hot main-sequence
+    /// stars, solar-type dwarfs and the red clump giants that dominate
+    /// catalogs to a few magnitudes fainter than the naked eye
+    fn default() -> Self {
+        Self::new(vec![
+            PopulationComponent {
+                weight: 0.3,
+                mean_b_v: 0.05,
+                sigma_b_v: 0.15,
+            },
+            PopulationComponent {
+                weight: 0.35,
+                mean_b_v: 0.6,
+                sigma_b_v: 0.15,
+            },
+            PopulationComponent {
+                weight: 0.35,
+                mean_b_v: 1.1,
+                sigma_b_v: 0.2,
+            },
+        ])
+    }
+}
+
+impl StellarPopulation {
+    /// Population made of the given groups
+    pub fn new(components: Vec<PopulationComponent>) -> Self {
+        Self { components }
+    }
+
+    /// Population of stars all of one color
+    pub fn single(b_v: f64) -> Self {
+        Self::new(vec![PopulationComponent {
+            weight: 1.0,
+            mean_b_v: b_v,
+            sigma_b_v: 0.0,
+        }])
+    }
+
+    /// Groups making up the population
+    pub fn components(&self) -> &[PopulationComponent] {
+        &self.components
+    }
+
+    /// Draw a B-V color, within the range the color relations hold
+    pub fn sample_b_v<R: Rng + ?Sized>(&self, rng: &mut R) -> f64 {
+        let total: f64 = self.components.iter().map(|c| c.weight).sum();
+        let mut pick = rng.gen::<f64>() * total;
+        let component = self
+            .components
+            .iter()
+            .find(|c| {
+                pick -= c.weight;
+                pick < 0.0
+            })
+            .or(self.components.last());
+        let Some(component) = component else {
+            return 0.0;
+        };
+
+        // Box-Muller
+        let u1 = 1.0 - rng.gen::<f64>();
+        let u2 = rng.gen::<f64>();
+        let normal = (-2.0 * u1.ln()).sqrt() * (2.0 * PI * u2).cos();
+        (component.mean_b_v + component.sigma_b_v * normal).clamp(B_V_RANGE.0, B_V_RANGE.1)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    #[test]
+    fn test_color_relations() {
+        // An A0 star has zero colors in every system but G
+        let vega = Photometry::from_v_and_b_v(0.03, 0.0);
+        assert_eq!(vega.get(Band::R), Some(0.03));
+        assert_eq!(vega.get(Band::I), Some(0.03));
+        assert!((vega.g.unwrap() - 0.03 + 0.017).abs() < 1e-3);
+
+        // Redder stars are brighter at longer wavelengths, and a K5 dwarf
+        // has V-I near 1.35 and G-V near -0.45
+        let k5 = Photometry::from_v_and_b_v(8.0, 1.15);
+        let (g, r, i) = (k5.g.unwrap(), k5.r.unwrap(), k5.i.unwrap());
+        assert!(i < r && r < 8.0);
+        assert!((8.0 - i - 1.37).abs() < 0.05);
+        assert!((g - 8.0 + 0.44).abs() < 0.05);
+
+        let partial = Photometry::default().with(Band::G, 12.0);
+        assert_eq!(partial.get(Band::G), Some(12.0));
+        assert_eq!(partial.get(Band::V), None);
+        assert!(Photometry::default().is_empty());
+        assert_eq!(Band::from_name("R"), Some(Band::R));
+    }
+
+    #[test]
+    fn test_gaia_to_v() {
+        // G - V is small for white stars and about -0.34 at BP-RP = 1.3
+        assert!((johnson_v_from_gaia(10.0, 0.0) - 10.027).abs() < 1e-3);
+        assert!((johnson_v_from_gaia(10.0, 1.3) - 10.342).abs() < 1e-3);
+        // Consistent with the B-V relations for a solar-type star, whose
+        // BP-RP is about 0.82
+        let sun_like = Photometry::from_v_and_b_v(5.0, 0.65);
+        let from_gaia = Photometry::from_gaia(sun_like.g.unwrap(), 0.82);
+        assert!((from_gaia.v.unwrap() - 5.0).abs() < 0.05);
+        assert_eq!(from_gaia.i, None);
+    }
+
+    #[test]
+    fn test_temperature_and_color() {
+        assert!((effective_temperature(0.0) - 10_125.0).abs() < 10.0);
+        assert!((effective_temperature(1.5) - 3_700.0).abs() < 100.0);
+
+        // Near 6600 K the color is white; hot stars are blue, cool ones red
+        assert_eq!(blackbody_rgb(6_600.0), [1.0, 1.0, 1.0]);
+        let [r, _, b] = b_v_to_rgb(-0.2);
+        assert!(b > r);
+        let [r, g, b] = b_v_to_rgb(1.5);
+        assert!(r == 1.0 && g < 1.0 && b < g);
+    }
+
+    #[test]
+    fn test_photon_rate() {
+        // A V = 0 star gives about 1e8 photons/s per square metre per nm
+        assert!((photon_rate(0.0, 1.0, 1.0) - 1.005e8).abs() < 1.0);
+        // Five magnitudes is a factor of 100
+        assert!((photon_rate(5.0, 2.0, 90.0) / photon_rate(0.0, 2.0, 90.0) - 0.01).abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_population_sampling() {
+        let mut rng = StdRng::seed_from_u64(7);
+        let population = StellarPopulation::default();
+        let colors: Vec<f64> = (0..5000).map(|_| population.sample_b_v(&mut rng)).collect();
+
+        assert!(colors.iter().all(|c| (-0.3..=1.6).contains(c)));
+        let mean = colors.iter().sum::<f64>() / colors.len() as f64;
+        assert!((mean - 0.6).abs() < 0.05, "mean B-V {}", mean);
+        // The blue and red groups are both well represented
+        assert!(colors.iter().filter(|&&c| c < 0.2).count() > 1000);
+        assert!(colors.iter().filter(|&&c| c > 1.0).count() > 1000);
+
+        assert_eq!(StellarPopulation::single(0.4).sample_b_v(&mut rng), 0.4);
+    }
+}
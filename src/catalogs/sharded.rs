@@ -0,0 +1,391 @@
+//! Catalogs split into HEALPix shards on disk
+//!
+//! A sharded catalog is a directory holding one [`BinaryCatalog`] file per
+//! NESTED HEALPix pixel plus an `index.json` listing the shards, so a field
+//! of view can be served by loading only the few shards it touches. This
+//! keeps catalogs far larger than memory, such as all of Gaia, usable on a
+//! laptop:
+//!
+//! ```no_run
+//! use starfield::catalogs::ShardedCatalog;
+//!
+//! let gaia = ShardedCatalog::open("gaia_dr3_shards")?;
+//! // Stars in a 2° field on the Pleiades, from a handful of shards
+//! let field = gaia.load_region(56.75, 24.12, 2.0)?;
+//! println!("{} stars", field.len());
+//! # Ok::<(), starfield::StarfieldError>(())
+//! ```
+//!
+//! Catalogs are sharded with [`ShardedCatalog::write`] or, when they do not
+//! fit in memory, streamed through a [`ShardedCatalogWriter`], which spills
+//! stars to temporary files as it goes.
+
+use super::{BinaryCatalog, CatalogMetadata, MinimalStar, StarFields};
+use crate::coordinates::Equatorial;
+use crate::healpix::{Healpix, PixelOrdering};
+use crate::StarfieldError;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, BufReader, BufWriter, Write};
+use std::path::{Path, PathBuf};
+
+/// Name of the index file in a shard directory
+pub const INDEX_FILE: &str = "index.json";
+
+/// Version of the index layout
+const INDEX_VERSION: u32 = 1;
+
+/// Stars held in memory by a writer before spilling to temporary files
+const SPILL_THRESHOLD: usize = 1_000_000;
+
+/// What a shard directory holds
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ShardIndex {
+    /// Layout version of the index
+    pub version: u32,
+    /// HEALPix resolution of the shards, NESTED numbering
+    pub nside: u32,
+    /// Provenance shared by every shard
+    pub metadata: CatalogMetadata,
+    /// Number of stars in each non-empty shard, by pixel
+    pub shards: BTreeMap<usize, u64>,
+}
+
+/// A catalog stored as one binary file per HEALPix pixel
+#[derive(Debug, Clone)]
+pub struct ShardedCatalog {
+    dir: PathBuf,
+    index: ShardIndex,
+    grid: Healpix,
+}
+
+impl ShardedCatalog {
+    /// Open the sharded catalog in `dir`
+    pub fn open<P: AsRef<Path>>(dir: P) -> Result<Self, StarfieldError> {
+        let dir = dir.as_ref().to_path_buf();
+        let file = File::open(dir.join(INDEX_FILE))?;
+        let index: ShardIndex = serde_json::from_reader(BufReader::new(file))
+            .map_err(|e| StarfieldError::DataError(format!("Invalid shard index: {}", e)))?;
+        if index.version != INDEX_VERSION {
+            return Err(StarfieldError::DataError(format!(
+                "Unsupported shard index version: {}. Expected version {}",
+                index.version, INDEX_VERSION
+            )));
+        }
+        let grid = Healpix::new(index.nside, PixelOrdering::Nested)?;
+        Ok(Self { dir, index, grid })
+    }
+
+    /// Shard `catalog` into `dir` at resolution `nside`, which must be a
+    /// power of two
+    ///
+    /// Around 100 000 stars per shard suits most uses; for the 1.8 billion
+    /// stars of Gaia DR3 that is `nside` 32.
+    pub fn write<P: AsRef<Path>>(
+        dir: P,
+        catalog: &BinaryCatalog,
+        nside: u32,
+    ) -> Result<Self, StarfieldError> {
+        let mut writer = ShardedCatalogWriter::new(dir, nside, catalog.metadata().clone())?;
+        for star in catalog.stars() {
+            writer.add(*star)?;
+        }
+        writer.finish()
+    }
+
+    /// Directory holding the shards
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// The shard index
+    pub fn index(&self) -> &ShardIndex {
+        &self.index
+    }
+
+    /// Provenance of the catalog
+    pub fn metadata(&self) -> &CatalogMetadata {
+        &self.index.metadata
+    }
+
+    /// HEALPix resolution of the shards
+    pub fn nside(&self) -> u32 {
+        self.index.nside
+    }
+
+    /// Number of stars across all shards
+    pub fn len(&self) -> u64 {
+        self.index.shards.values().sum()
+    }
+
+    /// Whether the catalog holds no stars
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Path of the file for `pixel`
+    pub fn shard_path(&self, pixel: usize) -> PathBuf {
+        shard_path(&self.dir, pixel)
+    }
+
+    /// Non-empty shards overlapping a field of diameter `fov_deg` centered
+    /// on `ra_deg`, `dec_deg`
+    pub fn shards_for_region(&self, ra_deg: f64, dec_deg: f64, fov_deg: f64) -> Vec<usize> {
+        self.grid
+            .query_disc(ra_deg, dec_deg, fov_deg / 2.0)
+            .into_iter()
+            .filter(|pixel| self.index.shards.contains_key(pixel))
+            .collect()
+    }
+
+    /// Load one shard
+    pub fn load_shard(&self, pixel: usize) -> Result<BinaryCatalog, StarfieldError> {
+        if !self.index.shards.contains_key(&pixel) {
+            return Ok(BinaryCatalog::new().with_metadata(self.index.metadata.clone()));
+        }
+        BinaryCatalog::load(self.shard_path(pixel))
+    }
+
+    /// Stars within a field of diameter `fov_deg` centered on `ra_deg`,
+    /// `dec_deg`, loading only the shards it overlaps
+    pub fn load_region(
+        &self,
+        ra_deg: f64,
+        dec_deg: f64,
+        fov_deg: f64,
+    ) -> Result<BinaryCatalog, StarfieldError> {
+        let center = Equatorial::from_degrees(ra_deg, dec_deg);
+        let radius = (fov_deg / 2.0).to_radians();
+
+        let mut stars = Vec::new();
+        for pixel in self.shards_for_region(ra_deg, dec_deg, fov_deg) {
+            let shard = self.load_shard(pixel)?;
+            stars.extend(
+                shard
+                    .stars()
+                    .iter()
+                    .filter(|star| center.angular_distance(&star.position) <= radius),
+            );
+        }
+        Ok(BinaryCatalog::from_stars_with_metadata(
+            stars,
+            self.index.metadata.clone(),
+        ))
+    }
+}
+
+/// Streams stars into a sharded catalog without holding them all in memory
+///
+/// Stars are buffered per shard and spilled to temporary files in the
+/// target directory once a million are waiting; [`finish`] turns each
+/// shard's stars into its catalog file and writes the index.
+///
+/// [`finish`]: ShardedCatalogWriter::finish
+#[derive(Debug)]
+pub struct ShardedCatalogWriter {
+    dir: PathBuf,
+    grid: Healpix,
+    metadata: CatalogMetadata,
+    buffers: HashMap<usize, Vec<MinimalStar>>,
+    buffered: usize,
+    counts: BTreeMap<usize, u64>,
+}
+
+impl ShardedCatalogWriter {
+    /// Start writing shards of resolution `nside` into `dir`, creating it
+    /// if needed
+    pub fn new<P: AsRef<Path>>(
+        dir: P,
+        nside: u32,
+        metadata: CatalogMetadata,
+    ) -> Result<Self, StarfieldError> {
+        let grid = Healpix::new(nside, PixelOrdering::Nested)?;
+        let dir = dir.as_ref().to_path_buf();
+        fs::create_dir_all(&dir)?;
+        Ok(Self {
+            dir,
+            grid,
+            metadata,
+            buffers: HashMap::new(),
+            buffered: 0,
+            counts: BTreeMap::new(),
+        })
+    }
+
+    /// Add a star to its shard
+    pub fn add(&mut self, star: MinimalStar) -> Result<(), StarfieldError> {
+        let pixel = self.grid.pixel_of(&star.position);
+        self.buffers.entry(pixel).or_default().push(star);
+        *self.counts.entry(pixel).or_insert(0) += 1;
+        self.buffered += 1;
+        if self.buffered >= SPILL_THRESHOLD {
+            self.spill()?;
+        }
+        Ok(())
+    }
+
+    /// Number of stars added so far
+    pub fn len(&self) -> u64 {
+        self.counts.values().sum()
+    }
+
+    /// Whether no stars have been added
+    pub fn is_empty(&self) -> bool {
+        self.counts.is_empty()
+    }
+
+    /// Write every shard and the index, returning the finished catalog
+    pub fn finish(mut self) -> Result<ShardedCatalog, StarfieldError> {
+        for &pixel in self.counts.keys() {
+            let spilled = spill_path(&self.dir, pixel);
+            let mut stars = Vec::new();
+            if spilled.exists() {
+                let mut reader = BufReader::new(File::open(&spilled)?);
+                loop {
+                    match MinimalStar::read_record(&mut reader, StarFields::all()) {
+                        Ok(star) => stars.push(star),
+                        Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => break,
+                        Err(e) => return Err(StarfieldError::IoError(e)),
+                    }
+                }
+                fs::remove_file(&spilled)?;
+            }
+            stars.extend(self.buffers.remove(&pixel).unwrap_or_default());
+
+            BinaryCatalog::from_stars_with_metadata(stars, self.metadata.clone())
+                .save(shard_path(&self.dir, pixel))?;
+        }
+
+        let index = ShardIndex {
+            version: INDEX_VERSION,
+            nside: self.grid.nside(),
+            metadata: self.metadata,
+            shards: self.counts,
+        };
+        let mut writer = BufWriter::new(File::create(self.dir.join(INDEX_FILE))?);
+        serde_json::to_writer_pretty(&mut writer, &index)
+            .map_err(|e| StarfieldError::DataError(format!("Cannot write shard index: {}", e)))?;
+        writer.flush()?;
+
+        Ok(ShardedCatalog {
+            dir: self.dir,
+            index,
+            grid: self.grid,
+        })
+    }
+
+    /// Append every buffered star to its shard's temporary file
+    fn spill(&mut self) -> Result<(), StarfieldError> {
+        for (pixel, stars) in self.buffers.drain() {
+            let file = OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(spill_path(&self.dir, pixel))?;
+            let mut writer = BufWriter::new(file);
+            for star in &stars {
+                star.write_record(&mut writer, StarFields::all())?;
+            }
+            writer.flush()?;
+        }
+        self.buffered = 0;
+        Ok(())
+    }
+}
+
+/// Catalog file of a shard
+fn shard_path(dir: &Path, pixel: usize) -> PathBuf {
+    dir.join(format!("shard_{:08}.bin", pixel))
+}
+
+/// Temporary file of a shard's spilled stars
+fn spill_path(dir: &Path, pixel: usize) -> PathBuf {
+    dir.join(format!("shard_{:08}.tmp", pixel))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::{Rng, SeedableRng};
+    use tempfile::tempdir;
+
+    fn random_catalog(n: usize) -> BinaryCatalog {
+        let mut rng = rand::rngs::StdRng::seed_from_u64(11);
+        let stars = (0..n as u64)
+            .map(|id| {
+                let ra = rng.gen_range(0.0..360.0);
+                let dec = rng.gen_range(-1.0f64..1.0).asin().to_degrees();
+                let star = MinimalStar::new(id, ra, dec, rng.gen_range(2.0..16.0));
+                if id % 2 == 0 {
+                    star.with_parallax(rng.gen_range(0.1..20.0))
+                } else {
+                    star
+                }
+            })
+            .collect();
+        BinaryCatalog::from_stars_with_metadata(stars, CatalogMetadata::new("Test", "1"))
+    }
+
+    #[test]
+    fn test_region_matches_cone_search() {
+        let dir = tempdir().unwrap();
+        let catalog = random_catalog(20_000);
+        let sharded = ShardedCatalog::write(dir.path(), &catalog, 8).unwrap();
+        assert_eq!(sharded.len(), 20_000);
+        assert_eq!(sharded.index().shards.len(), 768);
+
+        let reopened = ShardedCatalog::open(dir.path()).unwrap();
+        assert_eq!(reopened.index(), sharded.index());
+        assert_eq!(reopened.metadata().survey, "Test");
+
+        for (ra, dec, fov) in [(10.0, 20.0, 8.0), (359.0, -89.0, 6.0), (180.0, 0.0, 0.5)] {
+            let region = reopened.load_region(ra, dec, fov).unwrap();
+            let mut got: Vec<u64> = region.stars().iter().map(|s| s.id).collect();
+            let mut want: Vec<u64> = catalog
+                .cone_search(ra, dec, fov / 2.0)
+                .iter()
+                .map(|s| s.id)
+                .collect();
+            got.sort_unstable();
+            want.sort_unstable();
+            assert_eq!(got, want);
+
+            // Only a few shards of the 768 are read
+            assert!(reopened.shards_for_region(ra, dec, fov).len() < 40);
+        }
+    }
+
+    #[test]
+    fn test_writer_spills_and_keeps_fields() {
+        let dir = tempdir().unwrap();
+        let catalog = random_catalog(3_000);
+        let mut writer =
+            ShardedCatalogWriter::new(dir.path(), 2, CatalogMetadata::default()).unwrap();
+        for (i, star) in catalog.stars().iter().enumerate() {
+            writer.add(*star).unwrap();
+            if i % 1_000 == 999 {
+                writer.spill().unwrap();
+            }
+        }
+        assert_eq!(writer.len(), 3_000);
+        let sharded = writer.finish().unwrap();
+
+        let mut total = 0;
+        for &pixel in sharded.index().shards.keys() {
+            let shard = sharded.load_shard(pixel).unwrap();
+            total += shard.len();
+            assert!(shard.fields().parallax);
+            for star in shard.stars() {
+                let original = &catalog.stars()[star.id as usize];
+                assert_eq!(star.parallax_mas, original.parallax_mas);
+            }
+        }
+        assert_eq!(total, 3_000);
+        assert!(fs::read_dir(dir.path()).unwrap().all(|entry| entry
+            .unwrap()
+            .path()
+            .extension()
+            .unwrap()
+            != "tmp"));
+    }
+}
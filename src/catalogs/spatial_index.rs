@@ -0,0 +1,202 @@
+//! Kd-tree spatial index for cone searches over star catalogs
+//!
+//! Stars are stored as unit vectors on the celestial sphere, so angular
+//! separations become chord lengths and the usual Euclidean kd-tree pruning
+//! applies without any special handling of the RA wrap-around or the poles.
+
+use std::sync::OnceLock;
+
+use super::StarData;
+
+/// Kd-tree over star positions, built once and queried many times
+///
+/// The tree is stored implicitly: the stars are reordered so that every
+/// range `[lo, hi)` has its splitting star at the midpoint, with smaller
+/// coordinates on the left and larger on the right.
+#[derive(Debug, Clone, Default)]
+pub struct SkyIndex {
+    stars: Vec<StarData>,
+    points: Vec<[f64; 3]>,
+}
+
+/// Unit vector pointing at a position given in radians
+fn unit_vector(ra: f64, dec: f64) -> [f64; 3] {
+    let (sin_dec, cos_dec) = dec.sin_cos();
+    let (sin_ra, cos_ra) = ra.sin_cos();
+    [cos_dec * cos_ra, cos_dec * sin_ra, sin_dec]
+}
+
+impl SkyIndex {
+    /// Build an index over the given stars
+    pub fn build<I: IntoIterator<Item = StarData>>(stars: I) -> Self {
+        let mut entries: Vec<([f64; 3], StarData)> = stars
+            .into_iter()
+            .map(|star| (unit_vector(star.position.ra, star.position.dec), star))
+            .collect();
+
+        Self::partition(&mut entries, 0);
+
+        let (points, stars) = entries.into_iter().unzip();
+        Self { stars, points }
+    }
+
+    /// Recursively arrange a range so its median along `axis` sits in the middle
+    fn partition(entries: &mut [([f64; 3], StarData)], axis: usize) {
+        if entries.len() <= 1 {
+            return;
+        }
+        let mid = entries.len() / 2;
+        entries.select_nth_unstable_by(mid, |a, b| a.0[axis].total_cmp(&b.0[axis]));
+
+        let (left, right) = entries.split_at_mut(mid);
+        let next = (axis + 1) % 3;
+        Self::partition(left, next);
+        Self::partition(&mut right[1..], next);
+    }
+
+    /// Number of indexed stars
+    pub fn len(&self) -> usize {
+        self.stars.len()
+    }
+
+    /// Check if the index is empty
+    pub fn is_empty(&self) -> bool {
+        self.stars.is_empty()
+    }
+
+    /// Find all stars within `radius_deg` of a position
+    ///
+    /// Stars exactly on the boundary are excluded, matching
+    /// [`StarCatalog::stars_in_field`](super::StarCatalog::stars_in_field).
+    pub fn cone_search(&self, ra_deg: f64, dec_deg: f64, radius_deg: f64) -> Vec<StarData> {
+        let mut found = Vec::new();
+        if self.is_empty() || radius_deg < 0.0 {
+            return found;
+        }
+
+        let center = unit_vector(ra_deg.to_radians(), dec_deg.to_radians());
+
+        // |a - b|² = 2 - 2 cos(θ) for unit vectors
+        let cos_radius = radius_deg.to_radians().cos();
+        let chord = (2.0 - 2.0 * cos_radius).sqrt();
+
+        self.search(
+            0,
+            self.stars.len(),
+            0,
+            &center,
+            chord,
+            cos_radius,
+            &mut found,
+        );
+        found
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn search(
+        &self,
+        lo: usize,
+        hi: usize,
+        axis: usize,
+        center: &[f64; 3],
+        chord: f64,
+        cos_radius: f64,
+        found: &mut Vec<StarData>,
+    ) {
+        if lo >= hi {
+            return;
+        }
+        let mid = lo + (hi - lo) / 2;
+        let point = &self.points[mid];
+
+        let cos_dist = point[0] * center[0] + point[1] * center[1] + point[2] * center[2];
+        if cos_dist > cos_radius {
+            found.push(self.stars[mid]);
+        }
+
+        let next = (axis + 1) % 3;
+        let delta = center[axis] - point[axis];
+        if delta <= chord {
+            self.search(lo, mid, next, center, chord, cos_radius, found);
+        }
+        if delta >= -chord {
+            self.search(mid + 1, hi, next, center, chord, cos_radius, found);
+        }
+    }
+
+    /// Find all stars within a circular field of view of diameter `fov_deg`
+    pub fn stars_in_field(&self, ra_deg: f64, dec_deg: f64, fov_deg: f64) -> Vec<StarData> {
+        self.cone_search(ra_deg, dec_deg, fov_deg / 2.0)
+    }
+}
+
+/// Lazily built [`SkyIndex`] owned by a catalog
+///
+/// Catalogs build the index on their first cone search and must call
+/// [`IndexCache::invalidate`] whenever their stars change.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct IndexCache(OnceLock<SkyIndex>);
+
+impl IndexCache {
+    /// Get the index, building it from `stars` if needed
+    pub(crate) fn get_or_build<I, F>(&self, stars: F) -> &SkyIndex
+    where
+        I: IntoIterator<Item = StarData>,
+        F: FnOnce() -> I,
+    {
+        self.0.get_or_init(|| SkyIndex::build(stars()))
+    }
+
+    /// Drop the index so it is rebuilt on next use
+    pub(crate) fn invalidate(&mut self) {
+        self.0 = OnceLock::new();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::catalogs::{create_synthetic_catalog, StarCatalog};
+
+    fn sorted_ids(stars: &[StarData]) -> Vec<u64> {
+        let mut ids: Vec<u64> = stars.iter().map(|s| s.id).collect();
+        ids.sort_unstable();
+        ids
+    }
+
+    #[test]
+    fn test_matches_linear_scan() {
+        let catalog = create_synthetic_catalog(5000, 0.0, 12.0, 7).unwrap();
+        let index = SkyIndex::build(catalog.star_data());
+        assert_eq!(index.len(), catalog.len());
+
+        for &(ra, dec, fov) in &[
+            (0.0, 0.0, 10.0),
+            (359.5, 12.0, 8.0),
+            (120.0, 89.5, 6.0),
+            (45.0, -88.0, 20.0),
+            (200.0, -30.0, 90.0),
+            (10.0, 10.0, 400.0),
+        ] {
+            let expected = catalog.stars_in_field(ra, dec, fov);
+            let actual = index.stars_in_field(ra, dec, fov);
+            assert_eq!(
+                sorted_ids(&actual),
+                sorted_ids(&expected),
+                "field {ra},{dec}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_empty_and_degenerate() {
+        let index = SkyIndex::build(Vec::new());
+        assert!(index.is_empty());
+        assert!(index.cone_search(0.0, 0.0, 10.0).is_empty());
+
+        let index = SkyIndex::build(vec![StarData::new(1, 10.0, 20.0, 5.0, None)]);
+        assert_eq!(index.cone_search(10.0, 20.0, 0.01).len(), 1);
+        assert!(index.cone_search(10.0, 20.0, -1.0).is_empty());
+        assert!(index.cone_search(190.0, -20.0, 1.0).is_empty());
+    }
+}
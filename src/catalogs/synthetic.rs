@@ -4,15 +4,48 @@
 //! for testing and development purposes. It uses statistical models of actual
 //! stellar magnitude and spatial distributions to create catalogs that
 //! approximate real-world astronomical data.
+//!
+//! Magnitudes are Johnson V. Each star also gets a B-V color drawn from a
+//! [`StellarPopulation`], so that its magnitudes in the other bands of
+//! [`super::Band`] follow and cameras with different filters can be
+//! simulated from the same catalog.
+//!
+//! With [`SpatialDistribution::Galaxy`] the stars are drawn from a model of
+//! the Milky Way, so that fields near the galactic plane and centre are
+//! crowded and those near the galactic poles sparse. Giving the catalog an
+//! all-sky total then makes the number of stars in a field follow from
+//! where it points:
+//!
+//! ```
+//! use starfield::catalogs::{GalaxyModel, SpatialDistribution, StarCatalog, SyntheticCatalogConfig};
+//!
+//! let field = |ra, dec| {
+//!     SyntheticCatalogConfig::new()
+//!         .with_spatial_distribution(SpatialDistribution::Galaxy(GalaxyModel::default()))
+//!         .with_all_sky_count(20_000)
+//!         .with_field_of_view(ra, dec, 20.0)
+//!         .generate()
+//!         .unwrap()
+//!         .len()
+//! };
+//! // Towards Sagittarius and towards the north galactic pole
+//! assert!(field(270.0, -25.0) > 3 * field(192.86, 27.13));
+//! ```
 
 use rand::distributions::{Distribution, Uniform};
 use rand::rngs::StdRng;
-use rand::SeedableRng;
+use rand::{Rng, SeedableRng};
 use std::f64::consts::PI;
 
-use super::{BinaryCatalog, MinimalStar};
+use super::{BinaryCatalog, CatalogMetadata, MinimalStar, StellarPopulation};
+use crate::coordinates::Equatorial;
+use crate::framelib::inertial::Galactic;
 use crate::StarfieldError;
 
+/// Mixed into the seed for the stream of colors, which is kept apart from
+/// positions and magnitudes so that those stay the same for a given seed
+const COLOR_SEED_SALT: u64 = 0x9e37_79b9_7f4a_7c15;
+
 /// Statistical star magnitude distribution parameters
 pub struct MagnitudeDistribution {
     /// Minimum star magnitude (brightest stars)
@@ -34,13 +67,99 @@ impl Default for MagnitudeDistribution {
     }
 }
 
+/// Star density of the Milky Way as a double-exponential disk and an
+/// exponential bulge
+///
+/// Densities are relative to that of the disk at the Sun, and lengths are
+/// in parsecs. The defaults follow the thin disk of Jurić et al. (2008).
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct GalaxyModel {
+    /// Radial scale length of the disk
+    pub disk_scale_length_pc: f64,
+    /// Vertical scale height of the disk
+    pub disk_scale_height_pc: f64,
+    /// Scale length of the bulge
+    pub bulge_scale_pc: f64,
+    /// Density at the centre of the bulge
+    pub bulge_density: f64,
+    /// Distance of the Sun from the galactic centre
+    pub sun_radius_pc: f64,
+    /// Height of the Sun above the plane
+    pub sun_height_pc: f64,
+    /// Distance out to which stars are drawn; a deeper, fainter catalog
+    /// reaches further and sees more of the bulge
+    pub depth_pc: f64,
+}
+
+impl Default for GalaxyModel {
+    fn default() -> Self {
+        Self {
+            disk_scale_length_pc: 2600.0,
+            disk_scale_height_pc: 300.0,
+            bulge_scale_pc: 600.0,
+            bulge_density: 50.0,
+            sun_radius_pc: 8200.0,
+            sun_height_pc: 20.0,
+            depth_pc: 3000.0,
+        }
+    }
+}
+
+impl GalaxyModel {
+    /// Set the distance out to which stars are drawn
+    pub fn with_depth(mut self, depth_pc: f64) -> Self {
+        self.depth_pc = depth_pc;
+        self
+    }
+
+    /// Star density at `radius_pc` from the galactic axis and `height_pc`
+    /// above the plane
+    pub fn density(&self, radius_pc: f64, height_pc: f64) -> f64 {
+        let disk = (-(radius_pc - self.sun_radius_pc) / self.disk_scale_length_pc
+            - height_pc.abs() / self.disk_scale_height_pc)
+            .exp();
+        let r = radius_pc.hypot(height_pc);
+        disk + self.bulge_density * (-r / self.bulge_scale_pc).exp()
+    }
+
+    /// Draw the direction of a star within `depth_pc` of the Sun, as RA and
+    /// Dec in degrees
+    ///
+    /// Points are drawn uniformly within the sphere and kept in proportion
+    /// to the density there.
+    fn sample_direction<R: Rng>(&self, rng: &mut R) -> (f64, f64) {
+        // Both components fall off away from the centre and the plane, so
+        // the point of the sphere nearest the centre bounds the density
+        let nearest = (self.sun_radius_pc - self.depth_pc).max(0.0);
+        let max_density = self.density(nearest, 0.0);
+        loop {
+            let distance = self.depth_pc * rng.gen::<f64>().cbrt();
+            let sin_b: f64 = rng.gen_range(-1.0..1.0);
+            let l = rng.gen_range(0.0..2.0 * PI);
+            let cos_b = (1.0 - sin_b * sin_b).sqrt();
+
+            // Heliocentric x towards the centre, galactocentric from there
+            let x = self.sun_radius_pc - distance * cos_b * l.cos();
+            let y = distance * cos_b * l.sin();
+            let z = self.sun_height_pc + distance * sin_b;
+            if rng.gen::<f64>() * max_density < self.density(x.hypot(y), z) {
+                let position: Equatorial =
+                    Galactic::from_degrees(l.to_degrees(), sin_b.asin().to_degrees()).into();
+                return (position.ra_degrees(), position.dec_degrees());
+            }
+        }
+    }
+}
+
 /// Spatial distribution models for stars
 #[derive(Debug, Clone, Copy, PartialEq)]
 pub enum SpatialDistribution {
     /// Uniform distribution across the entire sphere
     Uniform,
-    /// Stars clustered toward the galactic plane
+    /// Stars clustered toward the celestial equator
     GalacticPlane { concentration: f64 },
+    /// Stars drawn from a model of the Milky Way's disk and bulge
+    Galaxy(GalaxyModel),
     /// Stars clustered around a specific point (simulates a star cluster)
     Cluster {
         center_ra: f64,
@@ -67,6 +186,13 @@ pub struct SyntheticCatalogConfig {
     pub fov_deg: Option<f64>,
     /// Optional catalog description
     pub description: String,
+    /// Population the B-V colors are drawn from
+    pub population: StellarPopulation,
+    /// Julian year of the positions, recorded in the catalog metadata
+    pub epoch: Option<f64>,
+    /// Stars on the whole sky, of which a field of view keeps those inside
+    /// it; takes the place of `count`
+    pub all_sky_count: Option<usize>,
 }
 
 impl Default for SyntheticCatalogConfig {
@@ -80,6 +206,9 @@ impl Default for SyntheticCatalogConfig {
             center_dec: None,
             fov_deg: None,
             description: "Synthetic star catalog".to_string(),
+            population: StellarPopulation::default(),
+            epoch: None,
+            all_sky_count: None,
         }
     }
 }
@@ -135,18 +264,41 @@ impl SyntheticCatalogConfig {
         self
     }
 
+    /// Set the population the B-V colors are drawn from
+    pub fn with_population(mut self, population: StellarPopulation) -> Self {
+        self.population = population;
+        self
+    }
+
+    /// Set the epoch of the positions (Julian year)
+    pub fn with_epoch(mut self, epoch: f64) -> Self {
+        self.epoch = Some(epoch);
+        self
+    }
+
+    /// Draw `count` stars over the whole sky and keep those in the field of
+    /// view, so that a field's count follows the spatial distribution
+    pub fn with_all_sky_count(mut self, count: usize) -> Self {
+        self.all_sky_count = Some(count);
+        self
+    }
+
     /// Generate a synthetic star catalog with the configured parameters
     pub fn generate(&self) -> Result<BinaryCatalog, StarfieldError> {
         // Create seeded RNG
         let mut rng = StdRng::seed_from_u64(self.seed);
+        let mut color_rng = StdRng::seed_from_u64(self.seed ^ COLOR_SEED_SALT);
 
         // Generate stars
         let mut stars = Vec::with_capacity(self.count);
 
         // Determine actual number of stars to generate
         // We might need to generate more if using a field of view filter
-        let generation_count = match (self.center_ra, self.center_dec, self.fov_deg) {
-            (Some(_), Some(_), Some(fov)) => {
+        let target = self.all_sky_count.map_or(self.count, |_| usize::MAX);
+        let field = (self.center_ra, self.center_dec, self.fov_deg);
+        let generation_count = match (self.all_sky_count, field) {
+            (Some(total), _) => total,
+            (None, (Some(_), Some(_), Some(fov))) => {
                 // When using a field of view filter, we need to generate more stars
                 // to ensure we have enough within the desired field
                 // This is a rough estimate; 4*pi/(fov area) * count
@@ -172,28 +324,34 @@ impl SyntheticCatalogConfig {
             let magnitude = self.generate_star_magnitude(&mut rng);
 
             // Create star
-            let star = MinimalStar::new(id as u64, ra, dec, magnitude);
+            let b_v = self.population.sample_b_v(&mut color_rng);
+            let star = MinimalStar::new(id as u64, ra, dec, magnitude).with_color_index(b_v);
             stars.push(star);
 
             // Break if we've generated enough stars
-            if stars.len() >= self.count {
+            if stars.len() >= target {
                 break;
             }
         }
 
         // If we couldn't generate enough stars, adjust the catalog size
         let final_count = stars.len();
-        if final_count < self.count {
-            // Print a warning if we couldn't generate the requested number
-            println!(
-                "Warning: Could only generate {} of {} requested stars within the specified field of view",
+        if final_count < self.count && self.all_sky_count.is_none() {
+            log::warn!(
+                "Could only generate {} of {} requested stars within the specified field of view",
                 final_count,
                 self.count
             );
         }
 
         // Create and return the binary catalog
-        Ok(BinaryCatalog::from_stars(stars, &self.description))
+        let mut metadata = CatalogMetadata::default()
+            .with_description(&self.description)
+            .with_band("V");
+        if let Some(epoch) = self.epoch {
+            metadata = metadata.with_epoch(epoch);
+        }
+        Ok(BinaryCatalog::from_stars_with_metadata(stars, metadata))
     }
 
     /// Generate a star's position based on the spatial distribution model
@@ -234,6 +392,7 @@ impl SyntheticCatalogConfig {
 
                 (ra, dec)
             }
+            SpatialDistribution::Galaxy(model) => model.sample_direction(rng),
             SpatialDistribution::Cluster {
                 center_ra,
                 center_dec,
@@ -349,7 +508,7 @@ pub fn create_fov_catalog(
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::catalogs::StarPosition;
+    use crate::catalogs::{Band, StarCatalog, StarPosition};
 
     #[test]
     fn test_synthetic_catalog_generation() {
@@ -399,13 +558,92 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_colors_and_bands() {
+        let catalog = SyntheticCatalogConfig::new()
+            .with_count(500)
+            .with_epoch(2016.0)
+            .generate()
+            .unwrap();
+        assert_eq!(catalog.metadata().band, "V");
+        assert_eq!(catalog.metadata().epoch, Some(2016.0));
+
+        // Colors don't disturb the positions and magnitudes of a seed
+        let plain = create_synthetic_catalog(500, 1.0, 12.0, 42).unwrap();
+        for (a, b) in catalog.stars().iter().zip(plain.stars()) {
+            assert_eq!((a.id, a.magnitude), (b.id, b.magnitude));
+        }
+
+        for star in catalog.star_data() {
+            let b_v = star.b_v.unwrap();
+            assert_eq!(star.magnitude_in(Band::V), Some(star.magnitude));
+            let (r, i) = (star.magnitude_in(Band::R), star.magnitude_in(Band::I));
+            assert_eq!(b_v > 0.0, i.unwrap() < star.magnitude);
+            assert!(i <= r || b_v < 0.0);
+        }
+
+        // Bands are modelled again after a round trip through a file
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("synthetic.bin");
+        catalog.save(&path).unwrap();
+        let loaded = BinaryCatalog::load(&path).unwrap();
+        let first = loaded.star_data().next().unwrap();
+        assert_eq!(first.bands, catalog.star_data().next().unwrap().bands);
+
+        let blue = SyntheticCatalogConfig::new()
+            .with_count(10)
+            .with_population(StellarPopulation::single(-0.2))
+            .generate()
+            .unwrap();
+        assert!(blue.stars().iter().all(|s| s.color_index == Some(-0.2)));
+    }
+
+    #[test]
+    fn test_galaxy_model() {
+        let model = GalaxyModel::default();
+        assert!((model.density(8200.0, 0.0) - 1.0).abs() < 1e-3);
+        assert!(model.density(8200.0, 600.0) < 0.14);
+        assert!(model.density(0.0, 0.0) > model.density(4000.0, 0.0));
+
+        let catalog = SyntheticCatalogConfig::new()
+            .with_count(5000)
+            .with_spatial_distribution(SpatialDistribution::Galaxy(model))
+            .generate()
+            .unwrap();
+        assert_eq!(catalog.len(), 5000);
+        let latitudes: Vec<f64> = catalog
+            .stars()
+            .iter()
+            .map(|s| Galactic::from(Equatorial::from_degrees(s.ra(), s.dec())).lat_degrees())
+            .collect();
+
+        // A uniform sky has 17% of its stars within 10 degrees of the plane
+        let near_plane = latitudes.iter().filter(|b| b.abs() < 10.0).count();
+        assert!(near_plane > 2000, "{}", near_plane);
+        let near_poles = latitudes.iter().filter(|b| b.abs() > 60.0).count();
+        assert!(near_poles < 500, "{}", near_poles);
+    }
+
+    #[test]
+    fn test_all_sky_count() {
+        let config = SyntheticCatalogConfig::new().with_all_sky_count(2000);
+        assert_eq!(config.generate().unwrap().len(), 2000);
+
+        // A field covering a quarter of the sky keeps about a quarter
+        let field = config
+            .with_field_of_view(0.0, 90.0, 120.0)
+            .generate()
+            .unwrap();
+        assert!((400..600).contains(&field.len()), "{}", field.len());
+    }
+
     #[test]
     fn test_magnitude_distribution() {
         // Generate a large catalog to test magnitude distribution
         let catalog = create_synthetic_catalog(10000, 0.0, 10.0, 42).unwrap();
 
         // Count stars in magnitude bins
-        let mut bins = vec![0; 10];
+        let mut bins = [0; 10];
 
         for star in catalog.stars() {
             let bin = star.magnitude.floor() as usize;
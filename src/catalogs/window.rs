@@ -0,0 +1,384 @@
+//! Star fields from any catalog source
+//!
+//! A [`WindowQuery`] names a catalog, a field center and diameter, and
+//! optionally a magnitude limit and an epoch. [`WindowQuery::execute`] finds
+//! the catalog through a [`Loader`], so pinned bundles, the data directory
+//! and the download cache are honored, and returns the stars in the field:
+//!
+//! ```no_run
+//! use starfield::catalogs::{CatalogSource, WindowQuery};
+//! use starfield::coordinates::Equatorial;
+//! use starfield::Loader;
+//!
+//! let pleiades = Equatorial::from_degrees(56.75, 24.12);
+//! let field = WindowQuery::new(CatalogSource::Hipparcos, pleiades, 2.0)
+//!     .with_mag_limit(9.0)
+//!     .with_epoch(2025.0)
+//!     .execute(&Loader::new())?;
+//! println!("{} stars from {}", field.stars.len(), field.catalog);
+//! # Ok::<(), starfield::catalogs::WindowQueryError>(())
+//! ```
+//!
+//! With an epoch, stars with a proper motion are moved linearly from the
+//! catalog epoch before the field is cut, so fast movers enter and leave it
+//! correctly.
+
+use super::{BinaryCatalog, Photometry, ShardedCatalog, StarCatalog, StarData, StellarPopulation};
+use crate::coordinates::Equatorial;
+use crate::{Loader, StarfieldError};
+use rand::distributions::{Distribution, Uniform};
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+use std::f64::consts::PI;
+use std::path::PathBuf;
+use thiserror::Error;
+
+/// Epoch of the Hipparcos catalog positions, as a Julian year
+pub const HIPPARCOS_EPOCH: f64 = 1991.25;
+
+/// Epoch assumed for binary catalogs that do not record one (J2000)
+const DEFAULT_EPOCH: f64 = 2000.0;
+
+/// Fastest proper motion of any star (Barnard's star), in arcseconds a year
+const MAX_PROPER_MOTION_ARCSEC_YR: f64 = 10.4;
+
+/// Where a [`WindowQuery`] takes its stars from
+#[derive(Debug, Clone, PartialEq)]
+pub enum CatalogSource {
+    /// The Hipparcos catalog, downloaded to the cache if needed
+    Hipparcos,
+    /// A binary catalog file, looked up as given and then in the data
+    /// directory and the download cache
+    Binary(PathBuf),
+    /// A directory of HEALPix shards written by [`ShardedCatalog`], looked
+    /// up like [`CatalogSource::Binary`]
+    Sharded(PathBuf),
+    /// Random synthetic stars with specified seed and count
+    Random { seed: u64, count: usize },
+}
+
+/// Error type for window queries
+#[derive(Debug, Error)]
+pub enum WindowQueryError {
+    #[error("Invalid field of view: {0} degrees")]
+    InvalidFieldOfView(f64),
+
+    #[error("Catalog not found: {}", .0.display())]
+    CatalogNotFound(PathBuf),
+
+    #[error("Failed to load catalog: {0}")]
+    Load(#[from] StarfieldError),
+}
+
+/// A request for the stars in a circular field
+#[derive(Debug, Clone, PartialEq)]
+pub struct WindowQuery {
+    /// Catalog to draw stars from
+    pub source: CatalogSource,
+    /// Center of the field
+    pub center: Equatorial,
+    /// Diameter of the field in degrees
+    pub fov_deg: f64,
+    /// Faintest magnitude to keep, or `None` for every star
+    pub mag_limit: Option<f64>,
+    /// Julian year to move stars to, or `None` for the catalog epoch
+    pub epoch: Option<f64>,
+}
+
+/// Stars found by a [`WindowQuery`]
+#[derive(Debug, Clone)]
+pub struct WindowResult {
+    /// Stars in the field, in catalog order
+    pub stars: Vec<StarData>,
+    /// Description of the catalog the stars came from
+    pub catalog: String,
+    /// Julian year of the star positions, if known
+    pub epoch: Option<f64>,
+}
+
+/// A catalog star before it is moved to the query epoch
+struct Candidate {
+    star: StarData,
+    proper_motion_mas_yr: Option<(f64, f64)>,
+}
+
+impl WindowQuery {
+    /// Query a field of diameter `fov_deg` centered on `center`, which may
+    /// be given in galactic or ecliptic coordinates
+    pub fn new<C: Into<Equatorial>>(source: CatalogSource, center: C, fov_deg: f64) -> Self {
+        Self {
+            source,
+            center: center.into(),
+            fov_deg,
+            mag_limit: None,
+            epoch: None,
+        }
+    }
+
+    /// Keep only stars at least as bright as `magnitude`
+    pub fn with_mag_limit(mut self, magnitude: f64) -> Self {
+        self.mag_limit = Some(magnitude);
+        self
+    }
+
+    /// Move stars to the Julian year `epoch` by their proper motions
+    pub fn with_epoch(mut self, epoch: f64) -> Self {
+        self.epoch = Some(epoch);
+        self
+    }
+
+    /// Find the catalog through `loader` and collect the stars in the field
+    pub fn execute(&self, loader: &Loader) -> Result<WindowResult, WindowQueryError> {
+        if !(self.fov_deg > 0.0 && self.fov_deg <= 360.0) {
+            return Err(WindowQueryError::InvalidFieldOfView(self.fov_deg));
+        }
+        let ra_deg = self.center.ra_degrees();
+        let dec_deg = self.center.dec_degrees();
+        let mag_limit = self.mag_limit.unwrap_or(f64::INFINITY);
+
+        let (candidates, catalog, catalog_epoch) = match &self.source {
+            CatalogSource::Random { seed, count } => {
+                let stars = generate_synthetic_stars(*count, &self.center, self.fov_deg, *seed);
+                let label = format!("Synthetic stars (seed {})", seed);
+                (without_motion(stars), label, None)
+            }
+
+            CatalogSource::Hipparcos => {
+                let catalog = loader.load_hipparcos_catalog(mag_limit)?;
+                let candidates = catalog
+                    .stars()
+                    .map(|entry| Candidate {
+                        star: StarData::new(
+                            entry.hip as u64,
+                            entry.ra,
+                            entry.dec,
+                            entry.mag,
+                            entry.b_v,
+                        ),
+                        proper_motion_mas_yr: entry.pm_ra.zip(entry.pm_dec),
+                    })
+                    .collect();
+                (candidates, "Hipparcos".to_string(), Some(HIPPARCOS_EPOCH))
+            }
+
+            CatalogSource::Binary(path) => {
+                let found = loader
+                    .resolve_catalog_path("binary", path)
+                    .ok_or_else(|| WindowQueryError::CatalogNotFound(path.clone()))?;
+                let catalog = BinaryCatalog::load(found)?;
+                let epoch = catalog.metadata().epoch.unwrap_or(DEFAULT_EPOCH);
+                (
+                    binary_candidates(&catalog),
+                    catalog.metadata().source_label(),
+                    Some(epoch),
+                )
+            }
+
+            CatalogSource::Sharded(dir) => {
+                let found = loader
+                    .resolve_catalog_path("sharded", dir)
+                    .ok_or_else(|| WindowQueryError::CatalogNotFound(dir.clone()))?;
+                let sharded = ShardedCatalog::open(found)?;
+                let epoch = sharded.metadata().epoch.unwrap_or(DEFAULT_EPOCH);
+                // Widen the cut so stars moving into the field are loaded
+                let drift = self.epoch.map_or(0.0, |to| {
+                    2.0 * MAX_PROPER_MOTION_ARCSEC_YR * (to - epoch).abs() / 3600.0
+                });
+                let region = sharded.load_region(ra_deg, dec_deg, self.fov_deg + drift)?;
+                (
+                    binary_candidates(&region),
+                    sharded.metadata().source_label(),
+                    Some(epoch),
+                )
+            }
+        };
+
+        let years = self.epoch.zip(catalog_epoch).map(|(to, from)| to - from);
+        let radius = (self.fov_deg / 2.0).to_radians();
+
+        let stars = candidates
+            .into_iter()
+            .filter(|candidate| candidate.star.magnitude <= mag_limit)
+            .map(|candidate| match (years, candidate.proper_motion_mas_yr) {
+                (Some(years), Some(pm)) => move_star(candidate.star, pm, years),
+                _ => candidate.star,
+            })
+            .filter(|star| self.center.angular_distance(&star.position) <= radius)
+            .collect();
+
+        Ok(WindowResult {
+            stars,
+            catalog,
+            epoch: self.epoch.filter(|_| years.is_some()).or(catalog_epoch),
+        })
+    }
+}
+
+/// Candidates from a binary catalog, keeping proper motions
+fn binary_candidates(catalog: &BinaryCatalog) -> Vec<Candidate> {
+    catalog
+        .stars()
+        .iter()
+        .map(|star| Candidate {
+            star: StarData::with_position(star.id, star.position, star.magnitude, star.color_index),
+            proper_motion_mas_yr: star.proper_motion_mas_yr,
+        })
+        .collect()
+}
+
+/// Candidates with no known motion
+fn without_motion(stars: Vec<StarData>) -> Vec<Candidate> {
+    stars
+        .into_iter()
+        .map(|star| Candidate {
+            star,
+            proper_motion_mas_yr: None,
+        })
+        .collect()
+}
+
+/// Move a star linearly by its proper motion (μα*, μδ in mas/yr) over
+/// `years`
+pub(super) fn move_star(star: StarData, (pm_ra, pm_dec): (f64, f64), years: f64) -> StarData {
+    let mas = (1.0f64 / 3_600_000.0).to_radians();
+    let dec = star.position.dec + pm_dec * years * mas;
+    let ra = star.position.ra + pm_ra * years * mas / star.position.dec.cos();
+    StarData {
+        position: Equatorial::new(ra, dec.clamp(-PI / 2.0, PI / 2.0)),
+        ..star
+    }
+}
+
+/// Generate synthetic stars spread evenly over a circular field
+pub(super) fn generate_synthetic_stars(
+    count: usize,
+    center: &Equatorial,
+    fov_deg: f64,
+    seed: u64,
+) -> Vec<StarData> {
+    // Create a seeded RNG for reproducible results
+    let mut rng = StdRng::seed_from_u64(seed);
+    let mut stars = Vec::with_capacity(count);
+
+    // Offsets from the center: uniform in the cosine of the distance and in
+    // position angle gives an even spread over the cap
+    let cos_radius = (fov_deg / 2.0).to_radians().cos();
+    let cos_dist = Uniform::from(cos_radius..1.0);
+    let angle = Uniform::from(0.0..2.0 * PI);
+    let population = StellarPopulation::default();
+
+    // For realistic magnitude distribution, use exponential distribution
+    // For every step in magnitude, there are ~2.5x more stars
+    let min_mag = 3.0; // Brightest stars (lower magnitude = brighter)
+    let max_mag = 8.0; // Dimmest stars
+
+    // We'll generate random values and transform them to follow stellar magnitude distribution
+    let uniform = Uniform::from(0.0..1.0);
+
+    let (sin_dec0, cos_dec0) = center.dec.sin_cos();
+    for id in 1..=count {
+        let cos_rho: f64 = cos_dist.sample(&mut rng);
+        let sin_rho = (1.0 - cos_rho * cos_rho).sqrt();
+        let (sin_theta, cos_theta) = angle.sample(&mut rng).sin_cos();
+        let sin_dec = sin_dec0 * cos_rho + cos_dec0 * sin_rho * cos_theta;
+        let dec = sin_dec.clamp(-1.0, 1.0).asin();
+        let ra = center.ra + (sin_theta * sin_rho * cos_dec0).atan2(cos_rho - sin_dec0 * sin_dec);
+
+        // Generate magnitude using the exponential distribution
+        let u = uniform.sample(&mut rng);
+
+        // Transform uniform distribution to exponential distribution
+        // Using the fact that for every magnitude step, we have 2.5× more stars
+        let log_base: f64 = 2.5; // Pogson ratio
+        let exp_range = log_base.powf(max_mag - min_mag) - 1.0;
+        let t: f64 = u * exp_range + 1.0; // Transform to [1, 2.5^range]
+
+        // Convert back to magnitude scale
+        let magnitude = min_mag + t.log(log_base).clamp(0.0, max_mag - min_mag);
+
+        // Color from a field-star population, which fixes the other bands
+        let b_v = population.sample_b_v(&mut rng);
+
+        stars.push(
+            StarData::with_position(id as u64, Equatorial::new(ra, dec), magnitude, Some(b_v))
+                .with_bands(Photometry::from_v_and_b_v(magnitude, b_v)),
+        );
+    }
+
+    stars
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::catalogs::{CatalogMetadata, MinimalStar};
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_random_source_fills_the_field() {
+        let center = Equatorial::from_degrees(359.5, 40.0);
+        let query = WindowQuery::new(
+            CatalogSource::Random {
+                seed: 3,
+                count: 500,
+            },
+            center,
+            4.0,
+        );
+        let result = query.execute(&Loader::new()).unwrap();
+        assert_eq!(result.stars.len(), 500);
+        assert!(result.epoch.is_none());
+
+        let faint = query
+            .clone()
+            .with_mag_limit(6.0)
+            .execute(&Loader::new())
+            .unwrap();
+        assert!(faint.stars.len() < 500);
+        assert!(faint.stars.iter().all(|s| s.magnitude <= 6.0));
+
+        assert!(matches!(
+            WindowQuery::new(query.source, center, 0.0).execute(&Loader::new()),
+            Err(WindowQueryError::InvalidFieldOfView(_))
+        ));
+    }
+
+    #[test]
+    fn test_binary_sources_through_the_data_dir() {
+        let dir = tempdir().unwrap();
+        // A star moving 7.2" a year northward, an arcsecond south of the field
+        let edge = -1.0 - 1.0 / 3600.0;
+        let stars = vec![
+            MinimalStar::new(1, 100.0, 0.0, 5.0),
+            MinimalStar::new(2, 100.0, edge, 5.0).with_proper_motion(0.0, 7_200.0),
+            MinimalStar::new(3, 120.0, 0.0, 5.0),
+        ];
+        let metadata = CatalogMetadata::new("Test", "1").with_epoch(2000.0);
+        let catalog = BinaryCatalog::from_stars_with_metadata(stars, metadata);
+        catalog.save(dir.path().join("test.bin")).unwrap();
+        ShardedCatalog::write(dir.path().join("shards"), &catalog, 4).unwrap();
+
+        let loader = Loader::new().with_data_dir(dir.path());
+        let center = Equatorial::from_degrees(100.0, 0.0);
+        for source in [
+            CatalogSource::Binary("test.bin".into()),
+            CatalogSource::Sharded("shards".into()),
+        ] {
+            let query = WindowQuery::new(source, center, 2.0);
+            let now = query.execute(&loader).unwrap();
+            assert_eq!(now.stars.iter().map(|s| s.id).collect::<Vec<_>>(), vec![1]);
+            assert_eq!(now.epoch, Some(2000.0));
+
+            let later = query.with_epoch(2001.0).execute(&loader).unwrap();
+            assert_eq!(later.stars.len(), 2);
+            assert!((later.stars[1].dec_deg() - edge - 7.2 / 3600.0).abs() < 1e-9);
+            assert_eq!(later.epoch, Some(2001.0));
+        }
+
+        let missing = WindowQuery::new(CatalogSource::Binary("nope.bin".into()), center, 1.0);
+        assert!(matches!(
+            missing.execute(&loader),
+            Err(WindowQueryError::CatalogNotFound(_))
+        ));
+    }
+}
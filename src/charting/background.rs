@@ -0,0 +1,301 @@
+//! Diffuse sky backgrounds from the Milky Way and zodiacal light
+//!
+//! Each background is a [`BackgroundLayer`]: a HEALPix map of surface
+//! brightness in S10 units (the brightness of one 10th-magnitude star per
+//! square degree, V band) in the frame the light is fixed in. The Milky Way
+//! is mapped in galactic coordinates. Zodiacal light is mapped in
+//! sun-centered ecliptic coordinates, longitude measured from the Sun, so
+//! one map serves every date.
+//!
+//! The built-in layers are coarse analytic models, good to a few tenths of
+//! a magnitude away from the Sun and the galactic center: enough to shade a
+//! rendered sky or adjust a limiting magnitude, not for photometry. Finer
+//! maps can be saved and loaded in the same layout.
+//!
+//! ```
+//! use starfield::charting::SkyBackground;
+//! use starfield::coordinates::Equatorial;
+//!
+//! let background = SkyBackground::coarse();
+//! let sun = Equatorial::from_degrees(0.0, 0.0);
+//! // The galactic center in Sagittarius, far from the Sun
+//! let sgr = Equatorial::from_degrees(266.4, -28.9);
+//! let pole = Equatorial::from_degrees(192.9, 27.1);
+//! assert!(background.mag_per_arcsec2(&sgr, &sun) < background.mag_per_arcsec2(&pole, &sun));
+//! ```
+
+use crate::coordinates::Equatorial;
+use crate::framelib::inertial::{Ecliptic, Galactic};
+use crate::healpix::{npix, HealpixMap};
+use crate::StarfieldError;
+use byteorder::{LittleEndian, ReadBytesExt, WriteBytesExt};
+use std::fs::File;
+use std::io::{BufReader, BufWriter, Read, Write};
+use std::path::Path;
+
+/// Magic bytes identifying a background layer file
+pub const LAYER_MAGIC_BYTES: &[u8; 6] = b"HPXLYR";
+
+/// Version of the background layer file format
+pub const LAYER_FORMAT_VERSION: u8 = 1;
+
+/// Resolution of the built-in layers, about 3.7° per pixel
+pub const COARSE_NSIDE: u32 = 16;
+
+/// Surface brightness in mag/arcsec² of 1 S10
+const S10_MAG_PER_ARCSEC2: f64 = 27.78;
+
+/// Convert a surface brightness in S10 units to V mag/arcsec²
+pub fn s10_to_mag_per_arcsec2(s10: f64) -> f64 {
+    S10_MAG_PER_ARCSEC2 - 2.5 * s10.max(1e-6).log10()
+}
+
+/// Convert a surface brightness in V mag/arcsec² to S10 units
+pub fn mag_per_arcsec2_to_s10(mag: f64) -> f64 {
+    10f64.powf((S10_MAG_PER_ARCSEC2 - mag) / 2.5)
+}
+
+/// Source of a background layer, which fixes the frame it is mapped in
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum BackgroundKind {
+    /// Integrated light of the galaxy, in galactic coordinates
+    MilkyWay,
+    /// Sunlight scattered by interplanetary dust, in ecliptic coordinates
+    /// with longitude measured from the Sun
+    ZodiacalLight,
+}
+
+impl BackgroundKind {
+    fn code(self) -> u8 {
+        match self {
+            BackgroundKind::MilkyWay => 1,
+            BackgroundKind::ZodiacalLight => 2,
+        }
+    }
+
+    fn from_code(code: u8) -> Result<Self, StarfieldError> {
+        match code {
+            1 => Ok(BackgroundKind::MilkyWay),
+            2 => Ok(BackgroundKind::ZodiacalLight),
+            _ => Err(StarfieldError::DataError(format!(
+                "Unknown background layer kind: {}",
+                code
+            ))),
+        }
+    }
+}
+
+/// A background brightness map
+#[derive(Debug, Clone, PartialEq)]
+pub struct BackgroundLayer {
+    /// What the layer holds
+    pub kind: BackgroundKind,
+    /// Brightness in S10 units over the layer's frame
+    pub map: HealpixMap,
+}
+
+impl BackgroundLayer {
+    /// Coarse Milky Way model at resolution `nside`
+    ///
+    /// The disk falls off exponentially with galactic latitude, with a
+    /// bulge of extra light toward the center, over a floor of faint
+    /// unresolved stars.
+    pub fn milky_way(nside: u32) -> Self {
+        let map = HealpixMap::from_fn(nside, |l, b| {
+            let l = (l + 180.0).rem_euclid(360.0) - 180.0;
+            let disk = 300.0 * (-b.abs() / 7.0).exp();
+            let bulge = 1.0 + 1.8 * (-(l / 35.0).powi(2) - (b / 12.0).powi(2)).exp();
+            25.0 + disk * bulge
+        });
+        Self {
+            kind: BackgroundKind::MilkyWay,
+            map,
+        }
+    }
+
+    /// Coarse zodiacal light model at resolution `nside`
+    ///
+    /// Brightness rises steeply toward the Sun along the ecliptic, fades to
+    /// about 60 S10 at the ecliptic poles, and has a faint gegenschein
+    /// opposite the Sun. Within 20° of the Sun, where the model is least
+    /// reliable and the sky is never dark anyway, it is held constant.
+    pub fn zodiacal_light(nside: u32) -> Self {
+        let map = HealpixMap::from_fn(nside, |dlon, beta| {
+            let (sin_b, cos_b) = beta.to_radians().sin_cos();
+            let elongation = (cos_b * dlon.to_radians().cos()).clamp(-1.0, 1.0).acos();
+            let elongation = elongation.to_degrees().max(20.0);
+            let along_ecliptic = 140.0
+                + 3500.0 * (-elongation / 20.0).exp()
+                + 40.0 * (-((180.0 - elongation) / 10.0).powi(2)).exp();
+            60.0 + (along_ecliptic - 60.0) * (-sin_b.abs() / 0.35).exp()
+        });
+        Self {
+            kind: BackgroundKind::ZodiacalLight,
+            map,
+        }
+    }
+
+    /// Brightness in S10 units toward `position`, with the Sun at `sun`
+    ///
+    /// The Sun's position is only used by the zodiacal light.
+    pub fn s10(&self, position: &Equatorial, sun: &Equatorial) -> f64 {
+        match self.kind {
+            BackgroundKind::MilkyWay => {
+                let g = Galactic::from(*position);
+                self.map.value(g.lon.to_degrees(), g.lat.to_degrees())
+            }
+            BackgroundKind::ZodiacalLight => {
+                let e = Ecliptic::from(*position);
+                let s = Ecliptic::from(*sun);
+                self.map
+                    .value((e.lon - s.lon).to_degrees(), e.lat.to_degrees())
+            }
+        }
+    }
+
+    /// Save the layer to a file
+    ///
+    /// The file holds the magic bytes, format version, kind, `nside` and
+    /// then one little-endian `f32` per pixel in RING order.
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<(), StarfieldError> {
+        let mut writer = BufWriter::new(File::create(path)?);
+        writer.write_all(LAYER_MAGIC_BYTES)?;
+        writer.write_u8(LAYER_FORMAT_VERSION)?;
+        writer.write_u8(self.kind.code())?;
+        writer.write_u32::<LittleEndian>(self.map.nside())?;
+        for &value in self.map.values() {
+            writer.write_f32::<LittleEndian>(value as f32)?;
+        }
+        writer.flush()?;
+        Ok(())
+    }
+
+    /// Load a layer written by [`BackgroundLayer::save`]
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self, StarfieldError> {
+        let mut reader = BufReader::new(File::open(path)?);
+        let mut magic = [0u8; 6];
+        reader.read_exact(&mut magic)?;
+        if &magic != LAYER_MAGIC_BYTES {
+            return Err(StarfieldError::DataError(
+                "Invalid background layer: incorrect magic bytes".to_string(),
+            ));
+        }
+        let version = reader.read_u8()?;
+        if version != LAYER_FORMAT_VERSION {
+            return Err(StarfieldError::DataError(format!(
+                "Unsupported background layer version: {}",
+                version
+            )));
+        }
+        let kind = BackgroundKind::from_code(reader.read_u8()?)?;
+        let nside = reader.read_u32::<LittleEndian>()?;
+        let values = (0..npix(nside))
+            .map(|_| reader.read_f32::<LittleEndian>().map(f64::from))
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(Self {
+            kind,
+            map: HealpixMap::new(nside, values)?,
+        })
+    }
+}
+
+/// Diffuse light summed over several layers
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SkyBackground {
+    layers: Vec<BackgroundLayer>,
+}
+
+impl SkyBackground {
+    /// A background with no layers
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The built-in Milky Way and zodiacal light models
+    pub fn coarse() -> Self {
+        Self::new()
+            .with_layer(BackgroundLayer::milky_way(COARSE_NSIDE))
+            .with_layer(BackgroundLayer::zodiacal_light(COARSE_NSIDE))
+    }
+
+    /// Add a layer
+    pub fn with_layer(mut self, layer: BackgroundLayer) -> Self {
+        self.layers.push(layer);
+        self
+    }
+
+    /// Layers in the order they were added
+    pub fn layers(&self) -> &[BackgroundLayer] {
+        &self.layers
+    }
+
+    /// Total brightness in S10 units toward `position`, with the Sun at
+    /// `sun`
+    pub fn s10(&self, position: &Equatorial, sun: &Equatorial) -> f64 {
+        self.layers.iter().map(|l| l.s10(position, sun)).sum()
+    }
+
+    /// Total brightness in V mag/arcsec² toward `position`, with the Sun at
+    /// `sun`
+    pub fn mag_per_arcsec2(&self, position: &Equatorial, sun: &Equatorial) -> f64 {
+        s10_to_mag_per_arcsec2(self.s10(position, sun))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::charting::ReferenceCircle;
+    use approx::assert_relative_eq;
+
+    #[test]
+    fn test_coarse_models() {
+        let sun = ReferenceCircle::Ecliptic.point(80.0);
+        let milky_way = BackgroundLayer::milky_way(COARSE_NSIDE);
+        let zodiacal = BackgroundLayer::zodiacal_light(COARSE_NSIDE);
+
+        // Brightest at the galactic center, faintest at the poles
+        let center = ReferenceCircle::GalacticEquator.point(0.0);
+        let anticenter = ReferenceCircle::GalacticEquator.point(180.0);
+        let ngp = ReferenceCircle::GalacticEquator.north_pole();
+        let mw = |p: &Equatorial| milky_way.s10(p, &sun);
+        assert!(mw(&center) > 2.0 * mw(&anticenter));
+        assert!(mw(&anticenter) > 5.0 * mw(&ngp));
+        assert!((20.0..21.0).contains(&s10_to_mag_per_arcsec2(mw(&center))));
+
+        // Zodiacal light follows the Sun along the ecliptic
+        let zl = |p: &Equatorial| zodiacal.s10(p, &sun);
+        let near = ReferenceCircle::Ecliptic.point(110.0);
+        let quadrature = ReferenceCircle::Ecliptic.point(170.0);
+        let gegenschein = ReferenceCircle::Ecliptic.point(260.0);
+        let nep = ReferenceCircle::Ecliptic.north_pole();
+        assert!(zl(&near) > 3.0 * zl(&quadrature));
+        assert!(zl(&gegenschein) > zl(&ReferenceCircle::Ecliptic.point(230.0)));
+        assert!((55.0..70.0).contains(&zl(&nep)));
+        let later_sun = ReferenceCircle::Ecliptic.point(200.0);
+        assert!(zodiacal.s10(&near, &later_sun) < zl(&near));
+
+        let sky = SkyBackground::coarse();
+        assert_eq!(sky.layers().len(), 2);
+        assert_relative_eq!(sky.s10(&nep, &sun), mw(&nep) + zl(&nep));
+        assert_relative_eq!(mag_per_arcsec2_to_s10(s10_to_mag_per_arcsec2(150.0)), 150.0);
+    }
+
+    #[test]
+    fn test_layer_file_roundtrip() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("zodiacal.hpx");
+        let layer = BackgroundLayer::zodiacal_light(4);
+        layer.save(&path).unwrap();
+
+        let loaded = BackgroundLayer::load(&path).unwrap();
+        assert_eq!(loaded.kind, BackgroundKind::ZodiacalLight);
+        assert_eq!(loaded.map.nside(), 4);
+        for (a, b) in loaded.map.values().iter().zip(layer.map.values()) {
+            assert_relative_eq!(*a, *b, max_relative = 1e-6);
+        }
+
+        std::fs::write(&path, b"HPXLYR\x01\x09").unwrap();
+        assert!(BackgroundLayer::load(&path).is_err());
+    }
+}
@@ -0,0 +1,645 @@
+//! Star charts and printable atlases
+//!
+//! The sky is tiled into overlapping pages, each drawn on a gnomonic
+//! (tangent-plane) projection so great circles are straight lines and
+//! positions are exact to well under an arcminute at any field size a page
+//! can hold. [`StarAtlas`] renders the pages as multi-page PostScript,
+//! which prints at full resolution and converts to PDF with `ps2pdf`.
+//!
+//! Alongside the atlas are reference circles to overlay on any chart
+//! ([`overlays`]), diffuse sky backgrounds on HEALPix grids
+//! ([`background`]) and pointing grids for survey planning ([`survey`]).
+
+pub mod background;
+pub mod overlays;
+pub mod survey;
+
+pub use background::{
+    mag_per_arcsec2_to_s10, s10_to_mag_per_arcsec2, BackgroundKind, BackgroundLayer, SkyBackground,
+};
+pub use overlays::{pole_markers, seasonal_sun_markers, ReferenceCircle, SkyMarker};
+pub use survey::{tessellate, CameraFootprint, SkyRegion, SurveyPointing};
+
+use crate::catalogs::{SkyIndex, StarCatalog, StarData};
+use crate::constants::DEG2RAD;
+use crate::coordinates::Equatorial;
+use std::io::{self, Write};
+
+/// Gnomonic projection about a tangent point
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct GnomonicProjection {
+    center: Equatorial,
+}
+
+impl GnomonicProjection {
+    /// Project about `center`
+    pub fn new(center: Equatorial) -> Self {
+        Self { center }
+    }
+
+    /// Tangent point
+    pub fn center(&self) -> Equatorial {
+        self.center
+    }
+
+    /// Standard coordinates (ξ east, η north) of a position, in tangent-plane
+    /// units, or `None` for points 90° or more from the center
+    pub fn project(&self, position: &Equatorial) -> Option<(f64, f64)> {
+        let (sin_d0, cos_d0) = self.center.dec.sin_cos();
+        let (sin_d, cos_d) = position.dec.sin_cos();
+        let (sin_da, cos_da) = (position.ra - self.center.ra).sin_cos();
+
+        let cos_c = sin_d0 * sin_d + cos_d0 * cos_d * cos_da;
+        if cos_c <= 1e-6 {
+            return None;
+        }
+        let xi = cos_d * sin_da / cos_c;
+        let eta = (cos_d0 * sin_d - sin_d0 * cos_d * cos_da) / cos_c;
+        Some((xi, eta))
+    }
+
+    /// Position at standard coordinates (ξ, η)
+    pub fn unproject(&self, xi: f64, eta: f64) -> Equatorial {
+        let (sin_d0, cos_d0) = self.center.dec.sin_cos();
+        let denominator = cos_d0 - eta * sin_d0;
+        let ra = self.center.ra + xi.atan2(denominator);
+        let dec = (sin_d0 + eta * cos_d0).atan2((xi * xi + denominator * denominator).sqrt());
+        Equatorial::new(ra, dec)
+    }
+}
+
+/// Layout and content settings for an atlas
+#[derive(Debug, Clone, PartialEq)]
+pub struct AtlasConfig {
+    /// Width of the field shown on each page, in degrees
+    pub field_deg: f64,
+    /// Sky shared by neighbouring pages, in degrees
+    pub overlap_deg: f64,
+    /// Faintest magnitude plotted
+    pub magnitude_limit: f64,
+    /// Label stars brighter than this magnitude with their IDs, if set
+    pub label_magnitude_limit: Option<f64>,
+    /// Page width and height in PostScript points (1/72 inch)
+    pub page_size_pt: (f64, f64),
+    /// Blank margin around the chart in points
+    pub margin_pt: f64,
+    /// Title printed in each page header
+    pub title: String,
+    /// Reference circles drawn over the grid; the ecliptic carries the
+    /// Sun's seasonal positions
+    pub reference_circles: Vec<ReferenceCircle>,
+}
+
+impl Default for AtlasConfig {
+    fn default() -> Self {
+        Self {
+            field_deg: 30.0,
+            overlap_deg: 3.0,
+            magnitude_limit: 6.5,
+            label_magnitude_limit: None,
+            // US Letter
+            page_size_pt: (612.0, 792.0),
+            margin_pt: 36.0,
+            title: "Star Atlas".to_string(),
+            reference_circles: Vec::new(),
+        }
+    }
+}
+
+impl AtlasConfig {
+    /// Set the field width of each page in degrees
+    pub fn with_field(mut self, field_deg: f64) -> Self {
+        self.field_deg = field_deg;
+        self
+    }
+
+    /// Set the overlap between neighbouring pages in degrees
+    pub fn with_overlap(mut self, overlap_deg: f64) -> Self {
+        self.overlap_deg = overlap_deg;
+        self
+    }
+
+    /// Set the faintest magnitude plotted
+    pub fn with_magnitude_limit(mut self, magnitude: f64) -> Self {
+        self.magnitude_limit = magnitude;
+        self
+    }
+
+    /// Label stars brighter than `magnitude`
+    pub fn with_labels(mut self, magnitude: f64) -> Self {
+        self.label_magnitude_limit = Some(magnitude);
+        self
+    }
+
+    /// Set the page size in points
+    pub fn with_page_size(mut self, width_pt: f64, height_pt: f64) -> Self {
+        self.page_size_pt = (width_pt, height_pt);
+        self
+    }
+
+    /// Set the page margin in points
+    pub fn with_margin(mut self, margin_pt: f64) -> Self {
+        self.margin_pt = margin_pt;
+        self
+    }
+
+    /// Set the title printed on each page
+    pub fn with_title(mut self, title: &str) -> Self {
+        self.title = title.to_string();
+        self
+    }
+
+    /// Draw a reference circle on every page
+    pub fn with_reference_circle(mut self, circle: ReferenceCircle) -> Self {
+        if !self.reference_circles.contains(&circle) {
+            self.reference_circles.push(circle);
+        }
+        self
+    }
+}
+
+/// One page of an atlas
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct AtlasPage {
+    /// Page number, starting at 1
+    pub number: usize,
+    /// Center of the page's field
+    pub center: Equatorial,
+}
+
+/// A multi-page star atlas
+#[derive(Debug, Clone)]
+pub struct StarAtlas {
+    config: AtlasConfig,
+    pages: Vec<AtlasPage>,
+}
+
+impl StarAtlas {
+    /// Lay out an atlas covering the whole sky
+    ///
+    /// Pages are arranged in declination bands from the north pole down,
+    /// with a page centered on each pole. Bands are split in right
+    /// ascension so that neighbouring pages share at least `overlap_deg` of
+    /// sky.
+    pub fn new(config: AtlasConfig) -> Self {
+        let step = (config.field_deg - config.overlap_deg).max(config.field_deg * 0.1);
+        let (band_height, counts) = layout_bands(-90.0, 90.0, step, step);
+
+        let mut centers = vec![Equatorial::from_degrees(0.0, 90.0)];
+        for (band, count) in counts.into_iter().enumerate() {
+            let dec = 90.0 - band_height * (band as f64 + 0.5);
+            centers.extend(
+                (0..count).map(|i| Equatorial::from_degrees(360.0 * i as f64 / count as f64, dec)),
+            );
+        }
+        centers.push(Equatorial::from_degrees(0.0, -90.0));
+
+        let pages = centers
+            .into_iter()
+            .enumerate()
+            .map(|(i, center)| AtlasPage {
+                number: i + 1,
+                center,
+            })
+            .collect();
+
+        Self { config, pages }
+    }
+
+    /// Atlas settings
+    pub fn config(&self) -> &AtlasConfig {
+        &self.config
+    }
+
+    /// Pages in print order
+    pub fn pages(&self) -> &[AtlasPage] {
+        &self.pages
+    }
+
+    /// The page whose center is nearest a position
+    pub fn page_for(&self, position: &Equatorial) -> Option<&AtlasPage> {
+        self.pages.iter().min_by(|a, b| {
+            a.center
+                .angular_distance(position)
+                .total_cmp(&b.center.angular_distance(position))
+        })
+    }
+
+    /// Points per tangent-plane unit, fitting the field across the narrower
+    /// page dimension
+    fn scale(&self) -> f64 {
+        let (width, height) = self.config.page_size_pt;
+        let plot = width.min(height) - 2.0 * self.config.margin_pt;
+        plot / (2.0 * (self.config.field_deg * DEG2RAD / 2.0).tan())
+    }
+
+    /// Write the atlas as PostScript, returning the number of pages
+    pub fn write_postscript<C, W>(&self, catalog: &C, writer: &mut W) -> io::Result<usize>
+    where
+        C: StarCatalog,
+        W: Write,
+    {
+        let limit = self.config.magnitude_limit;
+        let index = SkyIndex::build(catalog.star_data().filter(|s| s.magnitude <= limit));
+        let (width, height) = self.config.page_size_pt;
+
+        writeln!(writer, "%!PS-Adobe-3.0")?;
+        writeln!(writer, "%%Title: {}", escape(&self.config.title))?;
+        writeln!(writer, "%%Creator: starfield {}", env!("CARGO_PKG_VERSION"))?;
+        writeln!(writer, "%%Pages: {}", self.pages.len())?;
+        writeln!(writer, "%%BoundingBox: 0 0 {:.0} {:.0}", width, height)?;
+        writeln!(writer, "%%EndComments")?;
+        writeln!(writer, "%%BeginProlog")?;
+        writeln!(writer, "/star {{ newpath 0 360 arc fill }} bind def")?;
+        writeln!(writer, "/label {{ moveto show }} bind def")?;
+        writeln!(writer, "%%EndProlog")?;
+
+        for page in &self.pages {
+            self.write_page(page, &index, writer)?;
+        }
+
+        writeln!(writer, "%%EOF")?;
+        Ok(self.pages.len())
+    }
+
+    fn write_page<W: Write>(
+        &self,
+        page: &AtlasPage,
+        index: &SkyIndex,
+        w: &mut W,
+    ) -> io::Result<()> {
+        let config = &self.config;
+        let (width, height) = config.page_size_pt;
+        let scale = self.scale();
+        let (cx, cy) = (width / 2.0, height / 2.0);
+        let half_x = (width - 2.0 * config.margin_pt) / 2.0;
+        let half_y = (height - 2.0 * config.margin_pt) / 2.0;
+        let projection = GnomonicProjection::new(page.center);
+        let to_page = |position: &Equatorial| {
+            projection
+                .project(position)
+                // East is to the left on a sky chart
+                .map(|(xi, eta)| (cx - xi * scale, cy + eta * scale))
+                .filter(|&(x, y)| (x - cx).abs() <= half_x && (y - cy).abs() <= half_y)
+        };
+
+        writeln!(w, "%%Page: {} {}", page.number, page.number)?;
+        writeln!(w, "gsave")?;
+
+        // Header
+        writeln!(w, "/Helvetica findfont 10 scalefont setfont")?;
+        writeln!(
+            w,
+            "({} - chart {}: RA {} Dec {:+.0}) {:.1} {:.1} label",
+            escape(&config.title),
+            page.number,
+            format_ra(page.center.ra_degrees()),
+            page.center.dec_degrees(),
+            config.margin_pt,
+            height - config.margin_pt + 10.0
+        )?;
+
+        // Frame and clip
+        writeln!(
+            w,
+            "0.5 setlinewidth newpath {:.2} {:.2} {:.2} {:.2} rectstroke",
+            cx - half_x,
+            cy - half_y,
+            2.0 * half_x,
+            2.0 * half_y
+        )?;
+        writeln!(
+            w,
+            "newpath {:.2} {:.2} {:.2} {:.2} rectclip",
+            cx - half_x,
+            cy - half_y,
+            2.0 * half_x,
+            2.0 * half_y
+        )?;
+
+        self.write_grid(&projection, scale, (cx, cy), w)?;
+        self.write_reference_circles(&projection, scale, (cx, cy), &to_page, w)?;
+
+        // Stars, brightest last so they sit on top
+        let radius = config.field_deg * std::f64::consts::SQRT_2 / 2.0 + 1.0;
+        let mut stars: Vec<StarData> =
+            index.cone_search(page.center.ra_degrees(), page.center.dec_degrees(), radius);
+        stars.sort_by(|a, b| b.magnitude.total_cmp(&a.magnitude));
+
+        writeln!(w, "0 setgray /Helvetica findfont 6 scalefont setfont")?;
+        for star in &stars {
+            let Some((x, y)) = to_page(&star.position) else {
+                continue;
+            };
+            let r = star_radius(star.magnitude, config.magnitude_limit);
+            // White halo so overlapping stars stay distinct
+            writeln!(w, "1 setgray {:.2} {:.2} {:.2} star", x, y, r + 0.4)?;
+            writeln!(w, "0 setgray {:.2} {:.2} {:.2} star", x, y, r)?;
+            if config
+                .label_magnitude_limit
+                .is_some_and(|limit| star.magnitude <= limit)
+            {
+                writeln!(w, "({}) {:.2} {:.2} label", star.id, x + r + 1.0, y - 2.0)?;
+            }
+        }
+
+        writeln!(w, "grestore")?;
+        writeln!(w, "showpage")?;
+        Ok(())
+    }
+
+    /// Draw the configured reference circles, dashed, with the seasonal
+    /// points marked on the ecliptic
+    fn write_reference_circles<W: Write>(
+        &self,
+        projection: &GnomonicProjection,
+        scale: f64,
+        (cx, cy): (f64, f64),
+        to_page: &impl Fn(&Equatorial) -> Option<(f64, f64)>,
+        w: &mut W,
+    ) -> io::Result<()> {
+        if self.config.reference_circles.is_empty() {
+            return Ok(());
+        }
+        writeln!(w, "0.3 setgray 0.5 setlinewidth [4 2] 0 setdash newpath")?;
+        for circle in &self.config.reference_circles {
+            let line = circle.polyline(720).into_iter();
+            write_path(projection, scale, (cx, cy), line, w)?;
+        }
+        writeln!(w, "stroke [] 0 setdash")?;
+
+        if self
+            .config
+            .reference_circles
+            .contains(&ReferenceCircle::Ecliptic)
+        {
+            writeln!(w, "/Helvetica findfont 6 scalefont setfont")?;
+            for marker in seasonal_sun_markers() {
+                if let Some((x, y)) = to_page(&marker.position) {
+                    writeln!(w, "newpath {:.2} {:.2} 2 0 360 arc stroke", x, y)?;
+                    writeln!(
+                        w,
+                        "({}) {:.2} {:.2} label",
+                        escape(&marker.label),
+                        x + 3.0,
+                        y + 3.0
+                    )?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Draw lines of constant RA and Dec across the page
+    fn write_grid<W: Write>(
+        &self,
+        projection: &GnomonicProjection,
+        scale: f64,
+        (cx, cy): (f64, f64),
+        w: &mut W,
+    ) -> io::Result<()> {
+        let field = self.config.field_deg;
+        let dec_step = nice_step(field / 4.0);
+        let center_dec = projection.center().dec_degrees();
+        let ra_span = field / (center_dec.abs().min(85.0) * DEG2RAD).cos();
+        let ra_step = nice_step(ra_span / 4.0).max(dec_step);
+
+        writeln!(w, "0.6 setgray 0.25 setlinewidth newpath")?;
+        let reach = field * 0.75 + dec_step;
+        let mut dec = ((center_dec - reach) / dec_step).floor() * dec_step;
+        while dec <= center_dec + reach {
+            if dec.abs() < 90.0 {
+                let circle = (0..=360).map(|i| Equatorial::from_degrees(i as f64, dec));
+                write_path(projection, scale, (cx, cy), circle, w)?;
+            }
+            dec += dec_step;
+        }
+        let mut ra = 0.0;
+        while ra < 360.0 {
+            let meridian = (-90..=90).map(|i| Equatorial::from_degrees(ra, i as f64));
+            write_path(projection, scale, (cx, cy), meridian, w)?;
+            ra += ra_step;
+        }
+        writeln!(w, "stroke")?;
+        Ok(())
+    }
+}
+
+/// Split the declinations from `dec_max` down to `dec_min` into equal
+/// bands, returning the band height and the number of fields needed to
+/// close each band
+///
+/// Fields span `ra_step` by `dec_step` degrees, the part of each field not
+/// shared with its neighbours.
+fn layout_bands(dec_min: f64, dec_max: f64, ra_step: f64, dec_step: f64) -> (f64, Vec<usize>) {
+    let span = dec_max - dec_min;
+    let mut bands = ((span / dec_step).ceil() as usize).max(1);
+    loop {
+        let band_height = span / bands as f64;
+        let counts: Option<Vec<usize>> = (0..bands)
+            .map(|band| {
+                let dec = dec_max - band_height * (band as f64 + 0.5);
+                fields_in_band(dec, band_height, ra_step, dec_step)
+            })
+            .collect();
+        match counts {
+            Some(counts) => return (band_height, counts),
+            None => bands += 1,
+        }
+    }
+}
+
+/// Number of fields needed around a declination band so that every point
+/// of the band lies within half a step of some field center, in projected
+/// coordinates, or `None` if the band is too tall for that
+///
+/// The projection stretches away from the tangent point, so the worst case
+/// is a band corner halfway between two fields.
+fn fields_in_band(dec: f64, band_height: f64, ra_step: f64, dec_step: f64) -> Option<usize> {
+    let projection = GnomonicProjection::new(Equatorial::from_degrees(0.0, dec));
+    let half_xi = (ra_step / 2.0 * DEG2RAD).tan();
+    let half_eta = (dec_step / 2.0 * DEG2RAD).tan();
+    let widest = (dec.abs() - band_height / 2.0).max(0.0);
+    let first = ((360.0 * (widest * DEG2RAD).cos()) / ra_step)
+        .ceil()
+        .max(1.0) as usize;
+
+    (first..=(4 * first).max(360)).find(|&count| {
+        let half_gap = 180.0 / count as f64;
+        [dec - band_height / 2.0, dec + band_height / 2.0]
+            .into_iter()
+            .all(|edge| {
+                let corner = Equatorial::from_degrees(half_gap, edge.clamp(-90.0, 90.0));
+                projection
+                    .project(&corner)
+                    .is_some_and(|(xi, eta)| xi.abs() <= half_xi && eta.abs() <= half_eta)
+            })
+    })
+}
+
+/// Append a polyline through `points`, lifting the pen wherever the
+/// projection is undefined
+fn write_path<W: Write>(
+    projection: &GnomonicProjection,
+    scale: f64,
+    (cx, cy): (f64, f64),
+    points: impl Iterator<Item = Equatorial>,
+    w: &mut W,
+) -> io::Result<()> {
+    let mut pen_down = false;
+    for p in points {
+        match projection.project(&p) {
+            Some((xi, eta)) => {
+                let op = if pen_down { "lineto" } else { "moveto" };
+                writeln!(w, "{:.2} {:.2} {}", cx - xi * scale, cy + eta * scale, op)?;
+                pen_down = true;
+            }
+            None => pen_down = false,
+        }
+    }
+    Ok(())
+}
+
+/// Dot radius in points for a magnitude: 0.5 pt at the limit, growing by
+/// 0.45 pt per magnitude brighter
+fn star_radius(magnitude: f64, limit: f64) -> f64 {
+    0.5 + 0.45 * (limit - magnitude).max(0.0)
+}
+
+/// Round a grid spacing in degrees up to a value from a readable sequence
+fn nice_step(degrees: f64) -> f64 {
+    const STEPS: [f64; 12] = [
+        0.25, 0.5, 1.0, 2.0, 2.5, 5.0, 10.0, 15.0, 20.0, 30.0, 45.0, 90.0,
+    ];
+    STEPS.into_iter().find(|&s| s >= degrees).unwrap_or(90.0)
+}
+
+/// Right ascension in degrees as `HHhMMm`
+fn format_ra(ra_deg: f64) -> String {
+    let minutes = (ra_deg / 15.0 * 60.0).round() as i64 % (24 * 60);
+    format!("{:02}h{:02}m", minutes / 60, minutes % 60)
+}
+
+/// Escape text for a PostScript string literal
+fn escape(text: &str) -> String {
+    text.replace('\\', "\\\\")
+        .replace('(', "\\(")
+        .replace(')', "\\)")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::catalogs::HipparcosCatalog;
+    use crate::constants::RAD2DEG;
+    use approx::assert_relative_eq;
+    use rand::{Rng, SeedableRng};
+
+    fn separation_deg(a: &Equatorial, b: &Equatorial) -> f64 {
+        a.angular_distance(b) * RAD2DEG
+    }
+
+    #[test]
+    fn test_gnomonic_roundtrip() {
+        let projection = GnomonicProjection::new(Equatorial::from_degrees(83.8, -5.4));
+        assert_eq!(projection.project(&projection.center()), Some((0.0, 0.0)));
+
+        let p = Equatorial::from_degrees(88.8, 7.4);
+        let (xi, eta) = projection.project(&p).unwrap();
+        let back = projection.unproject(xi, eta);
+        assert!(separation_deg(&p, &back) < 1e-10);
+        assert!(xi > 0.0 && eta > 0.0);
+
+        assert!(projection
+            .project(&Equatorial::from_degrees(263.8, 5.4))
+            .is_none());
+    }
+
+    #[test]
+    fn test_pages_cover_the_sky_with_overlap() {
+        let config = AtlasConfig::default().with_field(20.0).with_overlap(2.0);
+        let atlas = StarAtlas::new(config);
+        assert!(atlas.pages().len() > 100);
+
+        // Every point lies inside some page, at least a degree from its edge
+        let mut rng = rand::rngs::StdRng::seed_from_u64(3);
+        for _ in 0..2000 {
+            let ra = rng.gen_range(0.0..360.0);
+            let dec = rng.gen_range(-1.0f64..1.0).asin() * RAD2DEG;
+            let p = Equatorial::from_degrees(ra, dec);
+            let inside = atlas.pages().iter().any(|page| {
+                GnomonicProjection::new(page.center)
+                    .project(&p)
+                    .is_some_and(|(xi, eta)| {
+                        let half = (9.0 * DEG2RAD).tan();
+                        xi.abs() < half && eta.abs() < half
+                    })
+            });
+            assert!(inside, "{} {} not covered", ra, dec);
+        }
+    }
+
+    #[test]
+    fn test_postscript_output() {
+        let catalog = HipparcosCatalog::create_synthetic();
+        let config = AtlasConfig::default()
+            .with_field(60.0)
+            .with_overlap(10.0)
+            .with_labels(1.0)
+            .with_title("Test (atlas)");
+        let atlas = StarAtlas::new(config);
+
+        let mut out = Vec::new();
+        let pages = atlas.write_postscript(&catalog, &mut out).unwrap();
+        let text = String::from_utf8(out).unwrap();
+
+        assert_eq!(pages, atlas.pages().len());
+        assert!(text.starts_with("%!PS-Adobe-3.0"));
+        assert!(text.contains(&format!("%%Pages: {}", pages)));
+        assert_eq!(text.matches("showpage").count(), pages);
+        assert!(text.contains("Test \\(atlas\\)"));
+        // Sirius is labelled on the page that holds it
+        assert!(text.contains("(32349)"));
+        assert!(text.trim_end().ends_with("%%EOF"));
+
+        let sirius = Equatorial::from_degrees(101.2874, -16.7161);
+        let page = atlas.page_for(&sirius).unwrap();
+        assert!(separation_deg(&page.center, &sirius) < 30.0);
+    }
+
+    #[test]
+    fn test_reference_circle_overlay() {
+        let catalog = HipparcosCatalog::create_synthetic();
+        let config = AtlasConfig::default()
+            .with_field(60.0)
+            .with_reference_circle(ReferenceCircle::Ecliptic)
+            .with_reference_circle(ReferenceCircle::Ecliptic);
+        assert_eq!(config.reference_circles.len(), 1);
+
+        let mut out = Vec::new();
+        StarAtlas::new(config)
+            .write_postscript(&catalog, &mut out)
+            .unwrap();
+        let text = String::from_utf8(out).unwrap();
+        assert!(text.contains("[4 2] 0 setdash"));
+        for label in ["March equinox", "June solstice", "December solstice"] {
+            assert!(text.contains(label), "{} missing", label);
+        }
+
+        // Plain atlases are unchanged
+        let mut plain = Vec::new();
+        StarAtlas::new(AtlasConfig::default().with_field(60.0))
+            .write_postscript(&catalog, &mut plain)
+            .unwrap();
+        assert!(!String::from_utf8(plain).unwrap().contains("setdash"));
+    }
+
+    #[test]
+    fn test_helpers() {
+        assert_eq!(format_ra(0.0), "00h00m");
+        assert_eq!(format_ra(101.2874), "06h45m");
+        assert_eq!(nice_step(3.0), 5.0);
+        assert_relative_eq!(star_radius(6.5, 6.5), 0.5);
+        assert!(star_radius(-1.5, 6.5) > star_radius(2.0, 6.5));
+    }
+}
@@ -0,0 +1,174 @@
+//! Reference circles and poles for chart overlays
+//!
+//! The celestial equator, ecliptic and galactic equator are generated as
+//! closed polylines of equatorial (ICRS) positions, ready to be projected
+//! onto any chart. The ecliptic is that of J2000, which stays within an
+//! arcminute of the ecliptic of date for several decades either side; the
+//! Sun's positions at the equinoxes and solstices are marked along it.
+//!
+//! ```
+//! use starfield::charting::{seasonal_sun_markers, ReferenceCircle};
+//!
+//! let ecliptic = ReferenceCircle::Ecliptic.polyline(360);
+//! assert_eq!(ecliptic.len(), 361);
+//! let june = &seasonal_sun_markers()[1];
+//! assert!((june.position.dec_degrees() - 23.44).abs() < 0.01);
+//! ```
+
+use crate::constants::DEG2RAD;
+use crate::coordinates::Equatorial;
+use crate::framelib::inertial::{Ecliptic, Galactic};
+
+/// A great circle used as a reference on the sky
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ReferenceCircle {
+    /// Declination zero
+    CelestialEquator,
+    /// The Sun's annual path (J2000)
+    Ecliptic,
+    /// Galactic latitude zero
+    GalacticEquator,
+}
+
+impl ReferenceCircle {
+    /// Every reference circle
+    pub const ALL: [ReferenceCircle; 3] = [
+        ReferenceCircle::CelestialEquator,
+        ReferenceCircle::Ecliptic,
+        ReferenceCircle::GalacticEquator,
+    ];
+
+    /// Display name of the circle
+    pub fn name(&self) -> &'static str {
+        match self {
+            ReferenceCircle::CelestialEquator => "Celestial equator",
+            ReferenceCircle::Ecliptic => "Ecliptic",
+            ReferenceCircle::GalacticEquator => "Galactic equator",
+        }
+    }
+
+    /// Position on the circle at a longitude in its own system, in degrees
+    /// (right ascension, ecliptic or galactic longitude)
+    pub fn point(&self, longitude_deg: f64) -> Equatorial {
+        let lon = longitude_deg * DEG2RAD;
+        match self {
+            ReferenceCircle::CelestialEquator => Equatorial::new(lon, 0.0),
+            ReferenceCircle::Ecliptic => Ecliptic { lon, lat: 0.0 }.into(),
+            ReferenceCircle::GalacticEquator => Galactic { lon, lat: 0.0 }.into(),
+        }
+    }
+
+    /// North pole of the circle
+    pub fn north_pole(&self) -> Equatorial {
+        let lat = 90.0 * DEG2RAD;
+        match self {
+            ReferenceCircle::CelestialEquator => Equatorial::new(0.0, lat),
+            ReferenceCircle::Ecliptic => Ecliptic { lon: 0.0, lat }.into(),
+            ReferenceCircle::GalacticEquator => Galactic { lon: 0.0, lat }.into(),
+        }
+    }
+
+    /// South pole of the circle
+    pub fn south_pole(&self) -> Equatorial {
+        let north = self.north_pole();
+        Equatorial::new(north.ra + std::f64::consts::PI, -north.dec)
+    }
+
+    /// The circle as a closed polyline of `segments` equal steps in
+    /// longitude, starting and ending at longitude 0
+    pub fn polyline(&self, segments: usize) -> Vec<Equatorial> {
+        let segments = segments.max(3);
+        (0..=segments)
+            .map(|i| self.point(360.0 * i as f64 / segments as f64))
+            .collect()
+    }
+}
+
+/// A labelled point to mark on a chart
+#[derive(Debug, Clone, PartialEq)]
+pub struct SkyMarker {
+    /// Text to show beside the point
+    pub label: String,
+    /// Where the point is
+    pub position: Equatorial,
+}
+
+impl SkyMarker {
+    fn new(label: &str, position: Equatorial) -> Self {
+        Self {
+            label: label.to_string(),
+            position,
+        }
+    }
+}
+
+/// The Sun's positions at the equinoxes and solstices, in calendar order
+/// from the March equinox
+pub fn seasonal_sun_markers() -> Vec<SkyMarker> {
+    let ecliptic = ReferenceCircle::Ecliptic;
+    vec![
+        SkyMarker::new("March equinox", ecliptic.point(0.0)),
+        SkyMarker::new("June solstice", ecliptic.point(90.0)),
+        SkyMarker::new("September equinox", ecliptic.point(180.0)),
+        SkyMarker::new("December solstice", ecliptic.point(270.0)),
+    ]
+}
+
+/// North and south poles of every reference circle
+pub fn pole_markers() -> Vec<SkyMarker> {
+    vec![
+        SkyMarker::new("NCP", ReferenceCircle::CelestialEquator.north_pole()),
+        SkyMarker::new("SCP", ReferenceCircle::CelestialEquator.south_pole()),
+        SkyMarker::new("NEP", ReferenceCircle::Ecliptic.north_pole()),
+        SkyMarker::new("SEP", ReferenceCircle::Ecliptic.south_pole()),
+        SkyMarker::new("NGP", ReferenceCircle::GalacticEquator.north_pole()),
+        SkyMarker::new("SGP", ReferenceCircle::GalacticEquator.south_pole()),
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::constants::RAD2DEG;
+    use approx::assert_relative_eq;
+
+    #[test]
+    fn test_circles_lie_90_degrees_from_their_poles() {
+        for circle in ReferenceCircle::ALL {
+            let pole = circle.north_pole();
+            let line = circle.polyline(72);
+            assert_eq!(line.len(), 73);
+            assert!(line[0].angular_distance(&line[72]) < 1e-12);
+            for p in &line {
+                assert_relative_eq!(pole.angular_distance(p) * RAD2DEG, 90.0, epsilon = 1e-9);
+            }
+            assert_relative_eq!(
+                pole.angular_distance(&circle.south_pole()) * RAD2DEG,
+                180.0,
+                epsilon = 1e-9
+            );
+        }
+    }
+
+    #[test]
+    fn test_known_poles_and_points() {
+        let ngp = ReferenceCircle::GalacticEquator.north_pole();
+        assert_relative_eq!(ngp.ra_degrees(), 192.859, epsilon = 1e-3);
+        assert_relative_eq!(ngp.dec_degrees(), 27.128, epsilon = 1e-3);
+
+        let nep = ReferenceCircle::Ecliptic.north_pole();
+        assert_relative_eq!(nep.ra_degrees(), 270.0, epsilon = 1e-6);
+        assert_relative_eq!(nep.dec_degrees(), 66.56, epsilon = 0.01);
+
+        // The galactic center lies in Sagittarius
+        let center = ReferenceCircle::GalacticEquator.point(0.0);
+        assert_relative_eq!(center.ra_degrees(), 266.405, epsilon = 1e-3);
+        assert_relative_eq!(center.dec_degrees(), -28.936, epsilon = 1e-3);
+
+        let seasons = seasonal_sun_markers();
+        assert_eq!(seasons.len(), 4);
+        assert_relative_eq!(seasons[1].position.ra_degrees(), 90.0, epsilon = 1e-6);
+        assert_relative_eq!(seasons[3].position.dec_degrees(), -23.44, epsilon = 0.01);
+        assert_eq!(pole_markers().len(), 6);
+    }
+}
@@ -0,0 +1,330 @@
+//! Pointing grids for surveys and mosaics
+//!
+//! A region of sky is tiled with camera footprints in declination bands,
+//! using the same band layout as the atlas pages. Footprints are assumed to
+//! be aligned with north up, and neighbouring footprints share at least the
+//! requested fraction of their width and height. Pointings are ordered in a
+//! serpentine, from the northernmost band down with right ascension running
+//! alternately up and down, which keeps the slew between exposures short.
+//!
+//! ```
+//! use starfield::charting::{tessellate, CameraFootprint, SkyRegion};
+//! use starfield::coordinates::Equatorial;
+//!
+//! // A 3.2° × 2.1° field with 10% overlap over the Orion complex
+//! let camera = CameraFootprint::new(3.2, 2.1);
+//! let region = SkyRegion::Cap {
+//!     center: Equatorial::from_degrees(83.8, -5.4),
+//!     radius_deg: 8.0,
+//! };
+//! let pointings = tessellate(&camera, 0.1, &region);
+//! println!("{} exposures", pointings.len());
+//! ```
+
+use super::{layout_bands, GnomonicProjection};
+use crate::constants::{DEG2RAD, RAD2DEG};
+use crate::coordinates::Equatorial;
+
+/// Angular size of a camera's field of view
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CameraFootprint {
+    /// Field width along right ascension, in degrees
+    pub width_deg: f64,
+    /// Field height along declination, in degrees
+    pub height_deg: f64,
+}
+
+impl CameraFootprint {
+    /// A field of `width_deg` by `height_deg`
+    pub fn new(width_deg: f64, height_deg: f64) -> Self {
+        Self {
+            width_deg,
+            height_deg,
+        }
+    }
+
+    /// Field of a sensor of `width_px` by `height_px` pixels at a plate scale
+    /// of `pixel_scale_arcsec` per pixel
+    pub fn from_sensor(width_px: usize, height_px: usize, pixel_scale_arcsec: f64) -> Self {
+        Self::new(
+            width_px as f64 * pixel_scale_arcsec / 3600.0,
+            height_px as f64 * pixel_scale_arcsec / 3600.0,
+        )
+    }
+
+    /// Field of a sensor measuring `width_mm` by `height_mm` behind optics of
+    /// focal length `focal_length_mm`
+    pub fn from_optics(width_mm: f64, height_mm: f64, focal_length_mm: f64) -> Self {
+        let field = |size: f64| 2.0 * (size / (2.0 * focal_length_mm)).atan() * RAD2DEG;
+        Self::new(field(width_mm), field(height_mm))
+    }
+
+    /// Whether a position falls inside the footprint when it is centered on
+    /// `center`
+    pub fn contains(&self, center: &Equatorial, position: &Equatorial) -> bool {
+        let half_xi = (self.width_deg / 2.0 * DEG2RAD).tan();
+        let half_eta = (self.height_deg / 2.0 * DEG2RAD).tan();
+        GnomonicProjection::new(*center)
+            .project(position)
+            .is_some_and(|(xi, eta)| xi.abs() <= half_xi && eta.abs() <= half_eta)
+    }
+}
+
+/// Part of the sky to cover
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SkyRegion {
+    /// The whole sky
+    AllSky,
+    /// Everything within `radius_deg` of `center`
+    Cap {
+        /// Center of the cap
+        center: Equatorial,
+        /// Angular radius in degrees
+        radius_deg: f64,
+    },
+    /// A range of right ascension and declination, in degrees. The right
+    /// ascension range runs east from `ra_min_deg` and may wrap through 0h;
+    /// equal limits mean a full circle.
+    RaDecBox {
+        /// Western edge
+        ra_min_deg: f64,
+        /// Eastern edge
+        ra_max_deg: f64,
+        /// Southern edge
+        dec_min_deg: f64,
+        /// Northern edge
+        dec_max_deg: f64,
+    },
+}
+
+impl SkyRegion {
+    /// Whether a position lies inside the region
+    pub fn contains(&self, position: &Equatorial) -> bool {
+        match *self {
+            SkyRegion::AllSky => true,
+            SkyRegion::Cap { center, radius_deg } => {
+                center.angular_distance(position) * RAD2DEG <= radius_deg
+            }
+            SkyRegion::RaDecBox {
+                ra_min_deg,
+                dec_min_deg,
+                dec_max_deg,
+                ..
+            } => {
+                let offset = (position.ra_degrees() - ra_min_deg).rem_euclid(360.0);
+                offset <= self.ra_span_deg()
+                    && (dec_min_deg..=dec_max_deg).contains(&position.dec_degrees())
+            }
+        }
+    }
+
+    /// Southern and northern limits in degrees
+    fn dec_range_deg(&self) -> (f64, f64) {
+        match *self {
+            SkyRegion::AllSky => (-90.0, 90.0),
+            SkyRegion::Cap { center, radius_deg } => {
+                let dec = center.dec_degrees();
+                ((dec - radius_deg).max(-90.0), (dec + radius_deg).min(90.0))
+            }
+            SkyRegion::RaDecBox {
+                dec_min_deg,
+                dec_max_deg,
+                ..
+            } => (dec_min_deg.max(-90.0), dec_max_deg.min(90.0)),
+        }
+    }
+
+    /// Width of a box's right ascension range in degrees
+    fn ra_span_deg(&self) -> f64 {
+        match *self {
+            SkyRegion::RaDecBox {
+                ra_min_deg,
+                ra_max_deg,
+                ..
+            } => match (ra_max_deg - ra_min_deg).rem_euclid(360.0) {
+                0.0 => 360.0,
+                span => span,
+            },
+            _ => 360.0,
+        }
+    }
+}
+
+/// One exposure of a survey
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SurveyPointing {
+    /// Position in the observing sequence, starting at 0
+    pub index: usize,
+    /// Center of the field
+    pub center: Equatorial,
+}
+
+/// Cover `region` with footprints of `camera`, neighbours overlapping by
+/// `overlap` of the field (0 to just under 1), in observing order
+///
+/// Every point of the region falls inside at least one footprint. Fields
+/// are laid out in declination bands across the region, so a cap is
+/// covered by its bounding band and fields wholly outside the cap are
+/// dropped.
+pub fn tessellate(
+    camera: &CameraFootprint,
+    overlap: f64,
+    region: &SkyRegion,
+) -> Vec<SurveyPointing> {
+    let keep = 1.0 - overlap.clamp(0.0, 0.95);
+    let (ra_step, dec_step) = (camera.width_deg * keep, camera.height_deg * keep);
+    let (dec_min, dec_max) = region.dec_range_deg();
+    let (band_height, counts) = layout_bands(dec_min, dec_max, ra_step, dec_step);
+
+    let mut centers = Vec::new();
+    for (band, count) in counts.into_iter().enumerate() {
+        let dec = dec_max - band_height * (band as f64 + 0.5);
+        let mut row: Vec<Equatorial> = match *region {
+            SkyRegion::AllSky => (0..count)
+                .map(|i| Equatorial::from_degrees(360.0 * i as f64 / count as f64, dec))
+                .collect(),
+            SkyRegion::Cap { center, radius_deg } => {
+                // Run the ring from the far side of the cap so its fields
+                // come out in one unbroken stretch of right ascension
+                let half_gap = 180.0 / count as f64;
+                let first_ra = center.ra_degrees() - 180.0;
+                (0..count)
+                    .map(|i| {
+                        let ra = first_ra + 360.0 * i as f64 / count as f64;
+                        Equatorial::from_degrees(ra, dec)
+                    })
+                    .filter(|field| {
+                        let reach = cell_radius_deg(field, half_gap, band_height);
+                        center.angular_distance(field) * RAD2DEG <= radius_deg + reach
+                    })
+                    .collect()
+            }
+            SkyRegion::RaDecBox { ra_min_deg, .. } => {
+                // Narrow the gaps evenly so the fields end at the box edges
+                let span = region.ra_span_deg();
+                let fields = (span * count as f64 / 360.0).ceil().max(1.0) as usize;
+                (0..fields)
+                    .map(|i| {
+                        let ra = ra_min_deg + span * (i as f64 + 0.5) / fields as f64;
+                        Equatorial::from_degrees(ra, dec)
+                    })
+                    .collect()
+            }
+        };
+        if band % 2 == 1 {
+            row.reverse();
+        }
+        centers.extend(row);
+    }
+
+    centers
+        .into_iter()
+        .enumerate()
+        .map(|(index, center)| SurveyPointing { index, center })
+        .collect()
+}
+
+/// Largest distance in degrees from a field center to the corners of the
+/// band cell it is responsible for
+fn cell_radius_deg(center: &Equatorial, half_gap_deg: f64, band_height_deg: f64) -> f64 {
+    let (ra, dec) = (center.ra_degrees(), center.dec_degrees());
+    [dec - band_height_deg / 2.0, dec + band_height_deg / 2.0]
+        .into_iter()
+        .map(|edge| {
+            let corner = Equatorial::from_degrees(ra + half_gap_deg, edge.clamp(-90.0, 90.0));
+            center.angular_distance(&corner) * RAD2DEG
+        })
+        .fold(0.0, f64::max)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::{Rng, SeedableRng};
+
+    /// Random positions spread evenly over the sky
+    fn random_positions(seed: u64, n: usize) -> Vec<Equatorial> {
+        let mut rng = rand::rngs::StdRng::seed_from_u64(seed);
+        (0..n)
+            .map(|_| {
+                let ra = rng.gen_range(0.0..360.0);
+                let dec = rng.gen_range(-1.0f64..1.0).asin() * RAD2DEG;
+                Equatorial::from_degrees(ra, dec)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_footprint_constructors() {
+        let sensor = CameraFootprint::from_sensor(6000, 4000, 1.8);
+        assert!((sensor.width_deg - 3.0).abs() < 1e-12);
+        assert!((sensor.height_deg - 2.0).abs() < 1e-12);
+
+        // A full-frame sensor behind a 50 mm lens
+        let optics = CameraFootprint::from_optics(36.0, 24.0, 50.0);
+        assert!((optics.width_deg - 39.6).abs() < 0.1);
+        assert!((optics.height_deg - 27.0).abs() < 0.1);
+    }
+
+    #[test]
+    fn test_whole_sky_is_covered() {
+        let camera = CameraFootprint::new(12.0, 8.0);
+        let pointings = tessellate(&camera, 0.1, &SkyRegion::AllSky);
+        // 41253 square degrees over 96 square degrees per field, plus overlap
+        assert!((430..800).contains(&pointings.len()), "{}", pointings.len());
+        assert!(pointings.iter().enumerate().all(|(i, p)| p.index == i));
+
+        // Covered with the overlap to spare: a footprint shrunk by the
+        // overlap still reaches every point
+        let inner = CameraFootprint::new(12.0 * 0.9, 8.0 * 0.9);
+        for p in random_positions(5, 2000) {
+            assert!(
+                pointings.iter().any(|f| inner.contains(&f.center, &p)),
+                "{:.2} {:.2} not covered",
+                p.ra_degrees(),
+                p.dec_degrees()
+            );
+        }
+    }
+
+    #[test]
+    fn test_regions_are_covered_in_serpentine_order() {
+        let camera = CameraFootprint::from_sensor(4096, 4096, 1.5);
+        let cap = SkyRegion::Cap {
+            center: Equatorial::from_degrees(2.7, 41.3),
+            radius_deg: 5.0,
+        };
+        let boxed = SkyRegion::RaDecBox {
+            ra_min_deg: 350.0,
+            ra_max_deg: 15.0,
+            dec_min_deg: -10.0,
+            dec_max_deg: 5.0,
+        };
+
+        for region in [cap, boxed] {
+            let pointings = tessellate(&camera, 0.2, &region);
+            let mut inside = 0;
+            for p in random_positions(8, 50_000) {
+                if region.contains(&p) {
+                    inside += 1;
+                    assert!(pointings.iter().any(|f| camera.contains(&f.center, &p)));
+                }
+            }
+            assert!(inside > 50);
+
+            // Consecutive fields are never more than a couple of fields apart
+            for pair in pointings.windows(2) {
+                let slew = pair[0].center.angular_distance(&pair[1].center) * RAD2DEG;
+                assert!(slew < 2.5 * camera.width_deg, "{}", slew);
+            }
+        }
+
+        // The box wraps through 0h and spans 25° by 15°
+        assert!(boxed.contains(&Equatorial::from_degrees(5.0, 0.0)));
+        assert!(!boxed.contains(&Equatorial::from_degrees(20.0, 0.0)));
+        let fields = tessellate(&camera, 0.2, &boxed);
+        assert!(fields
+            .iter()
+            .all(|f| f.center.ra_degrees() > 350.0 || f.center.ra_degrees() < 15.0));
+    }
+}
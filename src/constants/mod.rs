@@ -19,6 +19,16 @@ pub const B1950: f64 = 2_433_282.423_5;
 pub const TT_MINUS_TAI_S: f64 = 32.184;
 /// TT minus TAI in days
 pub const TT_MINUS_TAI: f64 = TT_MINUS_TAI_S / DAY_S;
+/// TAI minus GPS time in seconds
+pub const TAI_MINUS_GPS_S: f64 = 19.0;
+/// TAI minus GPS time in days
+pub const TAI_MINUS_GPS: f64 = TAI_MINUS_GPS_S / DAY_S;
+/// Rate of TCB relative to TDB (IAU 2006 Resolution B3)
+pub const L_B: f64 = 1.550_519_768e-8;
+/// TDB minus TCB in seconds at [`TCB_EPOCH`]
+pub const TDB0_S: f64 = -6.55e-5;
+/// 1977 January 1.0 TAI as a TCB Julian date, where TCB, TCG and TT agree
+pub const TCB_EPOCH: f64 = 2_443_144.500_372_5;
 /// Microseconds in a day
 pub const DAY_US: f64 = 86_400_000_000.0;
 
@@ -50,6 +60,12 @@ pub const EARTH_RADIUS: f64 = 6_378_136.6;
 /// IERS 2010 inverse Earth flattening
 pub const IERS_2010_INVERSE_EARTH_FLATTENING: f64 = 298.25642;
 
+// Solar system constants
+/// Moon/Earth mass ratio (IAU 2009 System of Astronomical Constants)
+pub const MOON_EARTH_MASS_RATIO: f64 = 0.012_300_037_1;
+/// Earth/Moon mass ratio, the `EMRAT` constant of the JPL ephemerides
+pub const EARTH_MOON_MASS_RATIO: f64 = 1.0 / MOON_EARTH_MASS_RATIO;
+
 // Derived constants
 /// Speed of light in AU/day
 pub const C_AUDAY: f64 = C * DAY_S / AU_M;
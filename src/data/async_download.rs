@@ -0,0 +1,368 @@
+//! Non-blocking downloads for tokio services
+//!
+//! With the `async` feature these mirror the blocking `download_*`
+//! functions, streaming each response to disk through `tokio::fs` on the
+//! caller's runtime instead of holding a thread for the whole transfer.
+//! They share the blocking functions' cache, so a file fetched one way is
+//! found by the other:
+//!
+//! ```no_run
+//! # async fn run() -> starfield::Result<()> {
+//! use starfield::data::{download_gaia_file_async, download_hipparcos_async};
+//!
+//! let hipparcos = download_hipparcos_async().await?;
+//! let gaia = download_gaia_file_async("GaiaSource_000-000-000.csv.gz").await?;
+//! println!("{} and {}", hipparcos.display(), gaia.display());
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! [`Loader`](crate::Loader) has `_async` versions of its downloading
+//! methods as well.
+
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use tokio::io::AsyncWriteExt;
+
+use super::cache::{downloaded, CachePolicy, Fetch};
+use super::downloader::{
+    continues_partial, file_exists_and_not_empty, partial_path, project_hipparcos,
+    response_validator, resumable_partial, sha256_file, store_validator, verify_checksum,
+    DownloadError, DownloadErrorKind, Transfer, HIPPARCOS, HIPPARCOS_FILE, HIPPARCOS_URL,
+    OPENNGC_URL,
+};
+use super::gaia_downloader::{
+    calculate_md5, get_gaia_cache_dir, parse_md5sums, GAIA_DR1_BASE_URL, GAIA_MD5SUMS_URL,
+};
+use super::iers::{FINALS_URL, LEAP_SECOND_URL};
+use super::progress::{DownloadProgress, ProgressReporter};
+use crate::{Result, StarfieldError};
+
+/// Download a file from URL to a local path without blocking, reporting
+/// progress
+pub(crate) async fn download_file_async(
+    url: &str,
+    path: &Path,
+    progress: Option<&ProgressReporter>,
+) -> Result<()> {
+    download_with_retry_async(url, path, &Transfer::default(), progress).await
+}
+
+/// [`download_with_retry`](super::downloader::download_with_retry) without
+/// blocking, waiting out backoffs on the runtime's timer
+pub(crate) async fn download_with_retry_async(
+    url: &str,
+    path: &Path,
+    transfer: &Transfer<'_>,
+    progress: Option<&ProgressReporter>,
+) -> Result<()> {
+    if let Some(parent) = path.parent() {
+        tokio::fs::create_dir_all(parent).await?;
+    }
+    let partial = partial_path(path);
+
+    let client = reqwest::Client::builder()
+        .timeout(transfer.timeout)
+        .build()
+        .map_err(|e| StarfieldError::DataError(format!("Failed to create HTTP client: {}", e)))?;
+
+    let mut total_bytes = None;
+    let mut attempt = 0;
+    loop {
+        attempt += 1;
+        match download_attempt(&client, url, &partial, &mut total_bytes, progress).await {
+            Ok(()) => break,
+            Err(kind) if kind.is_retriable() && attempt < transfer.retry.max_attempts() => {
+                let wait = transfer.retry.backoff(attempt);
+                log::warn!(
+                    "Download of {} failed ({}), retrying in {:?}",
+                    url,
+                    kind,
+                    wait
+                );
+                tokio::time::sleep(wait).await;
+            }
+            Err(kind) => {
+                return Err(DownloadError::new(url, &partial, attempt, total_bytes, kind).into())
+            }
+        }
+    }
+
+    if let Some(expected) = transfer.sha256 {
+        let hashed = partial.clone();
+        let actual = unblock(move || sha256_file(hashed)).await?;
+        verify_checksum(url, &partial, "SHA-256", actual, expected)?;
+    }
+    tokio::fs::rename(&partial, path).await?;
+    unblock(move || store_validator(&partial, None)).await?;
+    Ok(())
+}
+
+/// Run blocking file work on the runtime's blocking threads rather than
+/// stalling the executor
+async fn unblock<T, E, F>(work: F) -> std::result::Result<T, E>
+where
+    F: FnOnce() -> std::result::Result<T, E> + Send + 'static,
+    T: Send + 'static,
+    E: From<std::io::Error> + Send + 'static,
+{
+    tokio::task::spawn_blocking(work)
+        .await
+        .map_err(|e| E::from(std::io::Error::other(e)))?
+}
+
+/// Fetch the rest of `url` into the partial file without blocking
+async fn download_attempt(
+    client: &reqwest::Client,
+    url: &str,
+    partial: &Path,
+    total_bytes: &mut Option<u64>,
+    progress: Option<&ProgressReporter>,
+) -> std::result::Result<(), DownloadErrorKind> {
+    let io_error = |e: std::io::Error| DownloadErrorKind::Io(e.to_string());
+    let network_error = |e: reqwest::Error| DownloadErrorKind::Network(e.to_string());
+
+    let owned = partial.to_path_buf();
+    let resume = unblock(move || Ok::<_, std::io::Error>(resumable_partial(&owned)))
+        .await
+        .map_err(io_error)?;
+    let offset = resume.as_ref().map_or(0, |(offset, _)| *offset);
+    let mut request = client.get(url);
+    if let Some((offset, validator)) = &resume {
+        log::info!("Resuming {} from byte {}", url, offset);
+        request = request
+            .header(reqwest::header::RANGE, format!("bytes={}-", offset))
+            .header(reqwest::header::IF_RANGE, validator);
+    }
+    let mut response = request.send().await.map_err(network_error)?;
+
+    let resumed = match continues_partial(response.status().as_u16(), offset) {
+        Ok(resumed) => resumed,
+        Err(kind) => {
+            // The partial file no longer matches what the server has
+            if kind == DownloadErrorKind::Status(416) {
+                tokio::fs::remove_file(partial).await.map_err(io_error)?;
+            }
+            return Err(kind);
+        }
+    };
+    if !resumed {
+        let (owned, validator) = (
+            partial.to_path_buf(),
+            response_validator(response.headers()),
+        );
+        unblock(move || store_validator(&owned, validator.as_deref()))
+            .await
+            .map_err(io_error)?;
+    }
+    let skipped = if resumed { offset } else { 0 };
+    *total_bytes = response.content_length().map(|n| n + skipped);
+    let file = if resumed {
+        tokio::fs::OpenOptions::new()
+            .append(true)
+            .open(partial)
+            .await
+    } else {
+        tokio::fs::File::create(partial).await
+    };
+    let mut file = tokio::io::BufWriter::new(file.map_err(io_error)?);
+
+    let mut download = DownloadProgress::start(progress, url, *total_bytes);
+    download.advance(skipped as usize);
+    loop {
+        let chunk = match response.chunk().await {
+            Ok(Some(chunk)) => chunk,
+            Ok(None) => break,
+            Err(e) => {
+                file.flush().await.map_err(io_error)?;
+                return Err(network_error(e));
+            }
+        };
+        file.write_all(&chunk).await.map_err(io_error)?;
+        download.advance(chunk.len());
+    }
+    download.finish();
+    file.flush().await.map_err(io_error)?;
+    Ok(())
+}
+
+impl CachePolicy {
+    /// [`CachePolicy::fetch`] without blocking on the download
+    pub(crate) async fn fetch_async(
+        &self,
+        dataset: &str,
+        default_url: &str,
+        file_name: &str,
+        progress: Option<&ProgressReporter>,
+    ) -> Result<PathBuf> {
+        match self.plan(dataset, default_url, file_name)? {
+            Fetch::Ready(path) => Ok(path),
+            Fetch::Download {
+                url,
+                path,
+                stale,
+                sha256,
+            } => {
+                log::info!("Downloading {} from {}", dataset, url);
+                let transfer = Transfer {
+                    retry: self.retry(),
+                    sha256: sha256.as_deref(),
+                    ..Transfer::default()
+                };
+                let result = download_with_retry_async(&url, &path, &transfer, progress).await;
+                downloaded(dataset, path, stale, result)
+            }
+        }
+    }
+}
+
+/// Download the Hipparcos catalog without blocking
+pub async fn download_hipparcos_async() -> Result<PathBuf> {
+    fetch_hipparcos_async(&CachePolicy::default(), None).await
+}
+
+/// The Hipparcos catalog as `policy` provides it, downloading it without
+/// blocking if needed
+pub(crate) async fn fetch_hipparcos_async(
+    policy: &CachePolicy,
+    progress: Option<&ProgressReporter>,
+) -> Result<PathBuf> {
+    if let Some((source, dat_path)) = project_hipparcos(policy) {
+        tokio::fs::create_dir_all(policy.cache_dir()).await?;
+        tokio::fs::copy(&source, &dat_path).await?;
+        log::info!(
+            "Copied Hipparcos catalog from {} to cache: {}",
+            source.display(),
+            dat_path.display()
+        );
+        return Ok(dat_path);
+    }
+    policy
+        .fetch_async(HIPPARCOS, HIPPARCOS_URL, HIPPARCOS_FILE, progress)
+        .await
+}
+
+/// Download OpenNGC's `NGC.csv` without blocking unless it is cached
+pub async fn download_openngc_async() -> Result<PathBuf> {
+    fetch_openngc_async(&CachePolicy::default()).await
+}
+
+/// OpenNGC's `NGC.csv` as `policy` provides it
+pub(crate) async fn fetch_openngc_async(policy: &CachePolicy) -> Result<PathBuf> {
+    policy
+        .fetch_async("openngc", OPENNGC_URL, "NGC.csv", None)
+        .await
+}
+
+/// Download `Leap_Second.dat` and `finals2000A.all` without blocking
+/// unless they are cached
+pub async fn download_iers_async() -> Result<(PathBuf, PathBuf)> {
+    fetch_iers_async(&CachePolicy::default()).await
+}
+
+/// `Leap_Second.dat` and `finals2000A.all` as `policy` provides them
+pub(crate) async fn fetch_iers_async(policy: &CachePolicy) -> Result<(PathBuf, PathBuf)> {
+    Ok((
+        policy
+            .fetch_async("leap_seconds", LEAP_SECOND_URL, "Leap_Second.dat", None)
+            .await?,
+        policy
+            .fetch_async("iers_finals", FINALS_URL, "finals2000A.all", None)
+            .await?,
+    ))
+}
+
+/// Download and verify a specific Gaia file without blocking
+pub async fn download_gaia_file_async(filename: &str) -> Result<PathBuf> {
+    fetch_gaia_file_async(filename, None).await
+}
+
+/// Download and verify a specific Gaia file without blocking, reporting
+/// the download's progress
+pub async fn download_gaia_file_with_progress_async(
+    filename: &str,
+    progress: &ProgressReporter,
+) -> Result<PathBuf> {
+    fetch_gaia_file_async(filename, Some(progress)).await
+}
+
+async fn fetch_gaia_file_async(
+    filename: &str,
+    progress: Option<&ProgressReporter>,
+) -> Result<PathBuf> {
+    let cache_dir = get_gaia_cache_dir();
+    tokio::fs::create_dir_all(&cache_dir).await?;
+
+    // A decompressed copy means the file was already processed
+    let csv_path = cache_dir.join(filename.strip_suffix(".gz").unwrap_or(filename));
+    if file_exists_and_not_empty(&csv_path) {
+        return Ok(csv_path);
+    }
+
+    let md5sums_path = cache_dir.join("MD5SUM.txt");
+    if !file_exists_and_not_empty(&md5sums_path) {
+        download_file_async(GAIA_MD5SUMS_URL, &md5sums_path, progress).await?;
+    }
+    let checksums = parse_md5sums(&tokio::fs::read_to_string(&md5sums_path).await?);
+
+    let gz_path = cache_dir.join(filename);
+    let file_url = format!("{}{}", GAIA_DR1_BASE_URL, filename);
+    if !file_exists_and_not_empty(&gz_path) {
+        let transfer = Transfer {
+            timeout: Duration::from_secs(600),
+            ..Transfer::default()
+        };
+        download_with_retry_async(&file_url, &gz_path, &transfer, progress).await?;
+    }
+
+    match checksums.get(filename) {
+        Some(expected_md5) => {
+            let hashed = gz_path.clone();
+            let actual_md5 = unblock(move || calculate_md5(hashed)).await?;
+            verify_checksum(&file_url, &gz_path, "MD5", actual_md5, expected_md5)?;
+        }
+        None => log::warn!("No MD5 checksum found for {}", filename),
+    }
+    Ok(gz_path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data::RetryPolicy;
+    use tempfile::tempdir;
+
+    #[tokio::test]
+    async fn test_fetch_async_follows_policy() {
+        let dir = tempdir().unwrap();
+        let policy = CachePolicy::new().with_cache_dir(dir.path()).offline(true);
+        assert!(fetch_openngc_async(&policy).await.is_err());
+
+        let ngc = dir.path().join("NGC.csv");
+        std::fs::write(&ngc, "Name;Type\n").unwrap();
+        assert_eq!(fetch_openngc_async(&policy).await.unwrap(), ngc);
+
+        let local = dir.path().join("hip.dat");
+        std::fs::write(&local, "hip").unwrap();
+        let policy = policy.with_dataset_path(HIPPARCOS, &local);
+        assert_eq!(fetch_hipparcos_async(&policy, None).await.unwrap(), local);
+    }
+
+    #[tokio::test]
+    async fn test_download_failure_leaves_no_file() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("file.dat");
+        let transfer = Transfer {
+            retry: RetryPolicy::default().with_backoff(Duration::ZERO, Duration::ZERO),
+            ..Transfer::default()
+        };
+        // Nothing listens on the discard port
+        assert!(
+            download_with_retry_async("http://127.0.0.1:9/file.dat", &path, &transfer, None)
+                .await
+                .is_err()
+        );
+        assert!(!path.exists());
+    }
+}
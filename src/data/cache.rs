@@ -0,0 +1,408 @@
+//! Where downloaded data files come from and how long they are kept
+//!
+//! A [`CachePolicy`] decides which directory downloads are cached in, when
+//! a cached file is too old to use, and which URL or local file each
+//! dataset is taken from. Datasets are named `"hipparcos"`, `"openngc"`,
+//! `"leap_seconds"` and `"iers_finals"`. The policy is usually configured
+//! through the [`Loader`](crate::Loader) builder:
+//!
+//! ```no_run
+//! use starfield::Loader;
+//! use std::time::Duration;
+//!
+//! let loader = Loader::new()
+//!     .with_cache_dir("/data/starfield")
+//!     .with_cache_ttl(Duration::from_secs(7 * 86_400))
+//!     .with_dataset_path("hipparcos", "/mnt/catalogs/hip_main.dat")
+//!     .offline(true);
+//! let catalog = loader.load_hipparcos_catalog(6.0)?;
+//! println!("cache holds {} bytes", loader.cache_size()?);
+//! # Ok::<(), starfield::StarfieldError>(())
+//! ```
+//!
+//! Offline, a cached file is used however old it is, and a missing one is
+//! an error rather than a download. Online, a file older than the TTL is
+//! downloaded again; if that fails the stale copy is used with a warning.
+//! Downloads are retried and resumed as the [`RetryPolicy`] allows, and
+//! checked against any SHA-256 digest pinned for the dataset.
+
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+
+use super::downloader::{
+    download_with_retry, file_exists_and_not_empty, get_cache_dir, RetryPolicy, Transfer,
+};
+use super::progress::ProgressReporter;
+use crate::{Result, StarfieldError};
+
+/// Where a dataset is taken from instead of its default URL
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DatasetSource {
+    /// Download from this URL into the cache
+    Url(String),
+    /// Use this local file as is, without caching it
+    Path(PathBuf),
+}
+
+/// Cache location, expiry, offline mode and download sources
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct CachePolicy {
+    offline: bool,
+    ttl: Option<Duration>,
+    cache_dir: Option<PathBuf>,
+    mirror: Option<String>,
+    overrides: HashMap<String, DatasetSource>,
+    retry: RetryPolicy,
+    checksums: HashMap<String, String>,
+}
+
+impl CachePolicy {
+    /// Cache in `~/.cache/starfield`, forever, downloading from the
+    /// default URLs
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Never touch the network; fail instead when a file is not cached
+    pub fn offline(mut self, offline: bool) -> Self {
+        self.offline = offline;
+        self
+    }
+
+    /// Download cached files again once they are older than `ttl`
+    pub fn with_ttl(mut self, ttl: Duration) -> Self {
+        self.ttl = Some(ttl);
+        self
+    }
+
+    /// Cache downloads in `dir`
+    pub fn with_cache_dir<P: AsRef<Path>>(mut self, dir: P) -> Self {
+        self.cache_dir = Some(dir.as_ref().to_path_buf());
+        self
+    }
+
+    /// Download every dataset by file name from `base_url` instead of its
+    /// default host
+    pub fn with_mirror(mut self, base_url: &str) -> Self {
+        self.mirror = Some(base_url.trim_end_matches('/').to_string());
+        self
+    }
+
+    /// Download `dataset` from `url`, ahead of any mirror
+    pub fn with_dataset_url(mut self, dataset: &str, url: &str) -> Self {
+        self.overrides
+            .insert(dataset.to_string(), DatasetSource::Url(url.to_string()));
+        self
+    }
+
+    /// Read `dataset` from a local file instead of downloading it
+    pub fn with_dataset_path<P: AsRef<Path>>(mut self, dataset: &str, path: P) -> Self {
+        self.overrides.insert(
+            dataset.to_string(),
+            DatasetSource::Path(path.as_ref().to_path_buf()),
+        );
+        self
+    }
+
+    /// Retry failed downloads as `retry` says
+    pub fn with_retry(mut self, retry: RetryPolicy) -> Self {
+        self.retry = retry;
+        self
+    }
+
+    /// Reject a download of `dataset` whose SHA-256 digest is not `sha256`
+    pub fn with_dataset_sha256(mut self, dataset: &str, sha256: &str) -> Self {
+        self.checksums
+            .insert(dataset.to_string(), sha256.to_lowercase());
+        self
+    }
+
+    /// Whether downloads are disabled
+    pub fn is_offline(&self) -> bool {
+        self.offline
+    }
+
+    /// Age after which cached files are downloaded again
+    pub fn ttl(&self) -> Option<Duration> {
+        self.ttl
+    }
+
+    /// Retries for failed downloads
+    pub fn retry(&self) -> RetryPolicy {
+        self.retry
+    }
+
+    /// Directory downloads are cached in
+    pub fn cache_dir(&self) -> PathBuf {
+        self.cache_dir.clone().unwrap_or_else(get_cache_dir)
+    }
+
+    /// Override for `dataset`, if any
+    pub fn source(&self, dataset: &str) -> Option<&DatasetSource> {
+        self.overrides.get(dataset)
+    }
+
+    /// Total size in bytes of the files in the cache directory
+    pub fn cache_size(&self) -> Result<u64> {
+        let dir = self.cache_dir();
+        if !dir.is_dir() {
+            return Ok(0);
+        }
+        dir_size(&dir)
+    }
+
+    /// Delete everything in the cache directory and return the number of
+    /// bytes freed
+    pub fn clear_cache(&self) -> Result<u64> {
+        let dir = self.cache_dir();
+        if !dir.is_dir() {
+            return Ok(0);
+        }
+        let mut freed = 0;
+        for entry in fs::read_dir(&dir)? {
+            let path = entry?.path();
+            if path.is_dir() {
+                freed += dir_size(&path)?;
+                fs::remove_dir_all(&path)?;
+            } else {
+                freed += fs::metadata(&path)?.len();
+                fs::remove_file(&path)?;
+            }
+        }
+        log::info!("Cleared {} bytes from {}", freed, dir.display());
+        Ok(freed)
+    }
+
+    /// URL `dataset` is downloaded from
+    fn url_for(&self, dataset: &str, default_url: &str, file_name: &str) -> String {
+        match (self.overrides.get(dataset), &self.mirror) {
+            (Some(DatasetSource::Url(url)), _) => url.clone(),
+            (_, Some(mirror)) => format!("{}/{}", mirror, file_name),
+            _ => default_url.to_string(),
+        }
+    }
+
+    /// Whether the cached file at `path` is younger than the TTL
+    fn is_fresh(&self, path: &Path) -> bool {
+        let Some(ttl) = self.ttl else {
+            return true;
+        };
+        fs::metadata(path)
+            .and_then(|m| m.modified())
+            .ok()
+            .and_then(|modified| SystemTime::now().duration_since(modified).ok())
+            .is_some_and(|age| age < ttl)
+    }
+
+    /// Path of `dataset`, cached as `file_name` and downloaded from
+    /// `default_url` when the policy allows and requires it
+    pub(crate) fn fetch(
+        &self,
+        dataset: &str,
+        default_url: &str,
+        file_name: &str,
+        progress: Option<&ProgressReporter>,
+    ) -> Result<PathBuf> {
+        match self.plan(dataset, default_url, file_name)? {
+            Fetch::Ready(path) => Ok(path),
+            Fetch::Download {
+                url,
+                path,
+                stale,
+                sha256,
+            } => {
+                log::info!("Downloading {} from {}", dataset, url);
+                let transfer = Transfer {
+                    retry: self.retry,
+                    sha256: sha256.as_deref(),
+                    ..Transfer::default()
+                };
+                let result = download_with_retry(&url, &path, &transfer, progress);
+                downloaded(dataset, path, stale, result)
+            }
+        }
+    }
+
+    /// Decide whether `dataset` can be used as is or must be downloaded
+    pub(super) fn plan(&self, dataset: &str, default_url: &str, file_name: &str) -> Result<Fetch> {
+        if let Some(DatasetSource::Path(path)) = self.overrides.get(dataset) {
+            if !path.is_file() {
+                return Err(StarfieldError::DataError(format!(
+                    "File for {} not found: {}",
+                    dataset,
+                    path.display()
+                )));
+            }
+            return Ok(Fetch::Ready(path.clone()));
+        }
+
+        let path = self.cache_dir().join(file_name);
+        let cached = file_exists_and_not_empty(&path);
+        if cached && self.is_fresh(&path) {
+            log::debug!("Using cached {} from {}", dataset, path.display());
+            return Ok(Fetch::Ready(path));
+        }
+        if self.offline {
+            if cached {
+                log::warn!("Using stale {} from {} offline", dataset, path.display());
+                return Ok(Fetch::Ready(path));
+            }
+            return Err(StarfieldError::DataError(format!(
+                "{} is not cached at {} and the loader is offline",
+                dataset,
+                path.display()
+            )));
+        }
+
+        Ok(Fetch::Download {
+            url: self.url_for(dataset, default_url, file_name),
+            path,
+            stale: cached,
+            sha256: self.checksums.get(dataset).cloned(),
+        })
+    }
+}
+
+/// What a [`CachePolicy`] makes of a request for a dataset
+pub(super) enum Fetch {
+    /// The file at this path is ready to use
+    Ready(PathBuf),
+    /// Download `url` to `path`, which holds a stale copy if `stale`,
+    /// checking the SHA-256 digest if one is pinned
+    Download {
+        url: String,
+        path: PathBuf,
+        stale: bool,
+        sha256: Option<String>,
+    },
+}
+
+/// The path a download `result` leaves `dataset` at, falling back to a
+/// stale copy when the download failed
+pub(super) fn downloaded(
+    dataset: &str,
+    path: PathBuf,
+    stale: bool,
+    result: Result<()>,
+) -> Result<PathBuf> {
+    match result {
+        Ok(()) => Ok(path),
+        Err(e) if stale => {
+            log::warn!(
+                "Failed to refresh {} ({}), using stale {}",
+                dataset,
+                e,
+                path.display()
+            );
+            Ok(path)
+        }
+        Err(e) => Err(e),
+    }
+}
+
+/// Total size of the files under `dir`
+fn dir_size(dir: &Path) -> Result<u64> {
+    let mut size = 0;
+    for entry in fs::read_dir(dir)? {
+        let entry = entry?;
+        let metadata = entry.metadata()?;
+        size += if metadata.is_dir() {
+            dir_size(&entry.path())?
+        } else {
+            metadata.len()
+        };
+    }
+    Ok(size)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    const URL: &str = "https://example.com/data/hip_main.dat";
+
+    #[test]
+    fn test_offline_fetch() {
+        let dir = tempdir().unwrap();
+        let policy = CachePolicy::new().with_cache_dir(dir.path()).offline(true);
+        assert!(policy
+            .fetch("hipparcos", URL, "hip_main.dat", None)
+            .is_err());
+
+        // A cached file is used offline however old it is
+        let cached = dir.path().join("hip_main.dat");
+        fs::write(&cached, "cached").unwrap();
+        let policy = policy.with_ttl(Duration::ZERO);
+        assert!(!policy.is_fresh(&cached));
+        assert_eq!(
+            policy
+                .fetch("hipparcos", URL, "hip_main.dat", None)
+                .unwrap(),
+            cached
+        );
+        assert!(CachePolicy::new()
+            .with_ttl(Duration::from_secs(3600))
+            .is_fresh(&cached));
+    }
+
+    #[test]
+    fn test_dataset_sources() {
+        let dir = tempdir().unwrap();
+        let local = dir.path().join("local.dat");
+        fs::write(&local, "local").unwrap();
+        let policy = CachePolicy::new()
+            .with_cache_dir(dir.path().join("cache"))
+            .with_mirror("https://mirror.example.org/starfield/")
+            .with_dataset_url("openngc", "https://example.net/NGC.csv")
+            .with_dataset_path("hipparcos", &local)
+            .offline(true);
+
+        assert_eq!(
+            policy
+                .fetch("hipparcos", URL, "hip_main.dat", None)
+                .unwrap(),
+            local
+        );
+        assert_eq!(
+            policy.url_for("openngc", "https://default/NGC.csv", "NGC.csv"),
+            "https://example.net/NGC.csv"
+        );
+        assert_eq!(
+            policy.url_for(
+                "leap_seconds",
+                "https://default/Leap_Second.dat",
+                "Leap_Second.dat"
+            ),
+            "https://mirror.example.org/starfield/Leap_Second.dat"
+        );
+        assert_eq!(
+            CachePolicy::new().url_for("hipparcos", URL, "hip_main.dat"),
+            URL
+        );
+
+        let missing = policy.with_dataset_path("hipparcos", dir.path().join("missing.dat"));
+        assert!(missing
+            .fetch("hipparcos", URL, "hip_main.dat", None)
+            .is_err());
+    }
+
+    #[test]
+    fn test_cache_size_and_clear() {
+        let dir = tempdir().unwrap();
+        let policy = CachePolicy::new().with_cache_dir(dir.path().join("cache"));
+        assert_eq!(policy.cache_size().unwrap(), 0);
+
+        let cache = policy.cache_dir();
+        fs::create_dir_all(cache.join("gaia")).unwrap();
+        fs::write(cache.join("NGC.csv"), [0u8; 100]).unwrap();
+        fs::write(cache.join("gaia").join("part.csv.gz"), [0u8; 50]).unwrap();
+        assert_eq!(policy.cache_size().unwrap(), 150);
+
+        assert_eq!(policy.clear_cache().unwrap(), 150);
+        assert_eq!(policy.cache_size().unwrap(), 0);
+        assert!(cache.is_dir());
+    }
+}
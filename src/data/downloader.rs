@@ -8,11 +8,21 @@ use std::io::{self, BufReader, BufWriter, Read, Write};
 use std::path::{Path, PathBuf};
 use std::time::Duration;
 
+use thiserror::Error;
+
+use super::cache::CachePolicy;
+use super::progress::{DownloadProgress, ProgressReporter};
 use crate::Result;
 use crate::StarfieldError;
 
-// Hipparcos catalog URL
-const HIPPARCOS_URL: &str = "https://cdsarc.cds.unistra.fr/ftp/cats/I/239/hip_main.dat";
+// Hipparcos catalog URL, dataset name and cached file
+pub(super) const HIPPARCOS_URL: &str = "https://cdsarc.cds.unistra.fr/ftp/cats/I/239/hip_main.dat";
+pub(super) const HIPPARCOS: &str = "hipparcos";
+pub(super) const HIPPARCOS_FILE: &str = "hip_main.dat";
+
+/// The NGC and IC objects of the OpenNGC project
+pub const OPENNGC_URL: &str =
+    "https://raw.githubusercontent.com/mattiaverga/OpenNGC/master/database_files/NGC.csv";
 
 /// Get the cache directory path
 pub fn get_cache_dir() -> PathBuf {
@@ -28,73 +38,421 @@ pub fn ensure_cache_dir() -> io::Result<PathBuf> {
 }
 
 /// Check if a file exists and is not empty
-fn file_exists_and_not_empty<P: AsRef<Path>>(path: P) -> bool {
+pub(crate) fn file_exists_and_not_empty<P: AsRef<Path>>(path: P) -> bool {
     match fs::metadata(path) {
         Ok(metadata) => metadata.is_file() && metadata.len() > 0,
         Err(_) => false,
     }
 }
 
+/// How many times, and how patiently, a failed download is retried
+///
+/// Each retry resumes from the bytes already on disk when the server
+/// supports range requests. Waits double from the initial backoff up to
+/// the maximum.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RetryPolicy {
+    max_attempts: u32,
+    initial_backoff: Duration,
+    max_backoff: Duration,
+}
+
+impl Default for RetryPolicy {
+    /// Four attempts, waiting 1 s, 2 s and 4 s between them
+    fn default() -> Self {
+        Self {
+            max_attempts: 4,
+            initial_backoff: Duration::from_secs(1),
+            max_backoff: Duration::from_secs(60),
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// Try each download once
+    pub fn none() -> Self {
+        Self::default().with_max_attempts(1)
+    }
+
+    /// Give up after `attempts` tries in all
+    pub fn with_max_attempts(mut self, attempts: u32) -> Self {
+        self.max_attempts = attempts.max(1);
+        self
+    }
+
+    /// Wait `initial` before the first retry, doubling up to `max`
+    pub fn with_backoff(mut self, initial: Duration, max: Duration) -> Self {
+        self.initial_backoff = initial;
+        self.max_backoff = max;
+        self
+    }
+
+    /// Tries made before giving up
+    pub fn max_attempts(&self) -> u32 {
+        self.max_attempts
+    }
+
+    /// Wait after the `attempt`th failed try, counting from 1
+    pub fn backoff(&self, attempt: u32) -> Duration {
+        let doublings = attempt.saturating_sub(1).min(31);
+        self.initial_backoff
+            .saturating_mul(1 << doublings)
+            .min(self.max_backoff)
+    }
+}
+
+/// Why a download failed
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum DownloadErrorKind {
+    /// The server answered with an error status
+    #[error("HTTP status {0}")]
+    Status(u16),
+    /// The connection failed or dropped
+    #[error("network error: {0}")]
+    Network(String),
+    /// The partial file could not be written
+    #[error("I/O error: {0}")]
+    Io(String),
+    /// The complete file does not have the expected digest
+    #[error("{algorithm} mismatch: expected {expected}, got {actual}")]
+    ChecksumMismatch {
+        algorithm: &'static str,
+        expected: String,
+        actual: String,
+    },
+}
+
+impl DownloadErrorKind {
+    /// Whether trying again might succeed
+    pub fn is_retriable(&self) -> bool {
+        match self {
+            // Timeouts, rate limits, an unsatisfiable resume and server errors
+            DownloadErrorKind::Status(status) => {
+                matches!(status, 408 | 416 | 429) || *status >= 500
+            }
+            DownloadErrorKind::Network(_) => true,
+            DownloadErrorKind::Io(_) | DownloadErrorKind::ChecksumMismatch { .. } => false,
+        }
+    }
+}
+
+/// A download that did not complete, with what was left on disk
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+#[error("Failed to download {url} after {attempts} attempt(s): {kind}")]
+pub struct DownloadError {
+    /// URL being downloaded
+    pub url: String,
+    /// Partial file the next attempt resumes from, if any bytes arrived
+    pub partial_path: Option<PathBuf>,
+    /// Bytes received across all attempts
+    pub bytes_downloaded: u64,
+    /// Size of the complete file, when the server reported it
+    pub total_bytes: Option<u64>,
+    /// Tries made
+    pub attempts: u32,
+    /// Why the last try failed
+    pub kind: DownloadErrorKind,
+}
+
+impl DownloadError {
+    /// The failure of `url` after `attempts` tries, with whatever is left
+    /// at `partial`
+    pub(super) fn new(
+        url: &str,
+        partial: &Path,
+        attempts: u32,
+        total_bytes: Option<u64>,
+        kind: DownloadErrorKind,
+    ) -> Self {
+        let bytes_downloaded = fs::metadata(partial).map(|m| m.len()).unwrap_or(0);
+        Self {
+            url: url.to_string(),
+            partial_path: (bytes_downloaded > 0).then(|| partial.to_path_buf()),
+            bytes_downloaded,
+            total_bytes,
+            attempts,
+            kind,
+        }
+    }
+}
+
+/// Where the download of `path` is kept until it completes
+pub(super) fn partial_path(path: &Path) -> PathBuf {
+    let mut name = path.file_name().unwrap_or_default().to_os_string();
+    name.push(".part");
+    path.with_file_name(name)
+}
+
+/// Where the validator of the partial file at `partial` is kept
+///
+/// The validator is the strong ETag or Last-Modified date of the response
+/// that started the partial file. A resume sends it as `If-Range`, so a
+/// server whose file has changed since answers with the whole new file
+/// rather than appending its tail to stale bytes.
+pub(super) fn validator_path(partial: &Path) -> PathBuf {
+    let mut name = partial.file_name().unwrap_or_default().to_os_string();
+    name.push(".validator");
+    partial.with_file_name(name)
+}
+
+/// The `If-Range` validator of a response: a strong ETag, or else its
+/// Last-Modified date
+pub(super) fn response_validator(headers: &reqwest::header::HeaderMap) -> Option<String> {
+    use reqwest::header::{ETAG, LAST_MODIFIED};
+
+    let header = |name| headers.get(name).and_then(|v| v.to_str().ok());
+    header(ETAG)
+        .filter(|etag| !etag.starts_with("W/"))
+        .or_else(|| header(LAST_MODIFIED))
+        .map(str::to_string)
+}
+
+/// Bytes of the partial file at `partial` that can be resumed, with the
+/// validator to send as `If-Range`
+///
+/// A partial file without a validator cannot be checked against the
+/// server's copy, so it is started over.
+pub(super) fn resumable_partial(partial: &Path) -> Option<(u64, String)> {
+    let offset = fs::metadata(partial).map(|m| m.len()).unwrap_or(0);
+    let validator = fs::read_to_string(validator_path(partial)).ok()?;
+    (offset > 0 && !validator.is_empty()).then_some((offset, validator))
+}
+
+/// Record the validator of the response that starts the partial file at
+/// `partial`, or forget any old one if the response has none
+pub(super) fn store_validator(partial: &Path, validator: Option<&str>) -> io::Result<()> {
+    let path = validator_path(partial);
+    match validator {
+        Some(validator) => fs::write(path, validator),
+        None => match fs::remove_file(path) {
+            Err(e) if e.kind() != io::ErrorKind::NotFound => Err(e),
+            _ => Ok(()),
+        },
+    }
+}
+
+/// Whether a response with `status`, to a request for the bytes from
+/// `offset` on, continues the partial file rather than replacing it
+pub(super) fn continues_partial(
+    status: u16,
+    offset: u64,
+) -> std::result::Result<bool, DownloadErrorKind> {
+    match status {
+        206 if offset > 0 => Ok(true),
+        200..=299 => Ok(false),
+        status => Err(DownloadErrorKind::Status(status)),
+    }
+}
+
+/// Hex SHA-256 digest of a file
+pub fn sha256_file<P: AsRef<Path>>(path: P) -> Result<String> {
+    use sha2::{Digest, Sha256};
+
+    let mut file = File::open(path)?;
+    let mut hasher = Sha256::new();
+    io::copy(&mut file, &mut hasher)?;
+    Ok(format!("{:x}", hasher.finalize()))
+}
+
+/// Check the file at `path` against a hex digest, deleting it on a mismatch
+pub(super) fn verify_checksum(
+    url: &str,
+    path: &Path,
+    algorithm: &'static str,
+    actual: String,
+    expected: &str,
+) -> Result<()> {
+    if actual.eq_ignore_ascii_case(expected) {
+        log::debug!("{} verified for {}", algorithm, path.display());
+        return Ok(());
+    }
+    log::warn!(
+        "{} mismatch for {}: expected {}, got {}",
+        algorithm,
+        path.display(),
+        expected,
+        actual
+    );
+    let bytes = fs::metadata(path).map(|m| m.len()).unwrap_or(0);
+    fs::remove_file(path)?;
+    Err(DownloadError {
+        url: url.to_string(),
+        partial_path: None,
+        bytes_downloaded: bytes,
+        total_bytes: Some(bytes),
+        attempts: 1,
+        kind: DownloadErrorKind::ChecksumMismatch {
+            algorithm,
+            expected: expected.to_lowercase(),
+            actual,
+        },
+    }
+    .into())
+}
+
 /// Download a file from URL to a local path
-fn download_file<P: AsRef<Path>>(url: &str, path: P) -> Result<()> {
+pub(crate) fn download_file<P: AsRef<Path>>(url: &str, path: P) -> Result<()> {
+    download_file_with_progress(url, path, None)
+}
+
+/// Download a file from URL to a local path, reporting progress
+pub(crate) fn download_file_with_progress<P: AsRef<Path>>(
+    url: &str,
+    path: P,
+    progress: Option<&ProgressReporter>,
+) -> Result<()> {
+    download_with_retry(url, path.as_ref(), &Transfer::default(), progress)
+}
+
+/// Settings for one download
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct Transfer<'a> {
+    /// Retries and backoff
+    pub retry: RetryPolicy,
+    /// Longest a single attempt may take
+    pub timeout: Duration,
+    /// Hex SHA-256 digest the complete file must have
+    pub sha256: Option<&'a str>,
+}
+
+impl Default for Transfer<'_> {
+    fn default() -> Self {
+        Self {
+            retry: RetryPolicy::default(),
+            timeout: Duration::from_secs(30),
+            sha256: None,
+        }
+    }
+}
+
+/// Download `url` to `path`, resuming and retrying as `transfer` allows
+///
+/// Bytes arrive in a `.part` file next to `path`, which is renamed once
+/// the download completes and verifies. A failed download leaves the
+/// partial file for the next call to resume.
+pub(crate) fn download_with_retry(
+    url: &str,
+    path: &Path,
+    transfer: &Transfer,
+    progress: Option<&ProgressReporter>,
+) -> Result<()> {
     // Create parent directories if they don't exist
-    if let Some(parent) = path.as_ref().parent() {
+    if let Some(parent) = path.parent() {
         fs::create_dir_all(parent).map_err(StarfieldError::IoError)?;
     }
-
-    // Create a temporary file first to avoid partial downloads
-    let temp_path = path.as_ref().with_extension("tmp");
-    let mut file = BufWriter::new(File::create(&temp_path).map_err(StarfieldError::IoError)?);
+    let partial = partial_path(path);
 
     // Create HTTP client with timeout
     let client = reqwest::blocking::Client::builder()
-        .timeout(Duration::from_secs(30))
+        .timeout(transfer.timeout)
         .build()
         .map_err(|e| StarfieldError::DataError(format!("Failed to create HTTP client: {}", e)))?;
 
-    // Make the request
-    let mut response = client
-        .get(url)
-        .send()
-        .map_err(|e| StarfieldError::DataError(format!("Failed to download file: {}", e)))?;
+    let mut total_bytes = None;
+    let mut attempt = 0;
+    loop {
+        attempt += 1;
+        match download_attempt(&client, url, &partial, &mut total_bytes, progress) {
+            Ok(()) => break,
+            Err(kind) if kind.is_retriable() && attempt < transfer.retry.max_attempts() => {
+                let wait = transfer.retry.backoff(attempt);
+                log::warn!(
+                    "Download of {} failed ({}), retrying in {:?}",
+                    url,
+                    kind,
+                    wait
+                );
+                std::thread::sleep(wait);
+            }
+            Err(kind) => {
+                return Err(DownloadError::new(url, &partial, attempt, total_bytes, kind).into())
+            }
+        }
+    }
+
+    if let Some(expected) = transfer.sha256 {
+        verify_checksum(url, &partial, "SHA-256", sha256_file(&partial)?, expected)?;
+    }
+
+    // Rename the partial file to the final path
+    fs::rename(&partial, path).map_err(StarfieldError::IoError)?;
+    store_validator(&partial, None).map_err(StarfieldError::IoError)?;
+    Ok(())
+}
 
-    // Check if the request was successful
-    if !response.status().is_success() {
-        return Err(StarfieldError::DataError(format!(
-            "Failed to download file, status: {}",
-            response.status()
-        )));
+/// Fetch the rest of `url` into the partial file
+fn download_attempt(
+    client: &reqwest::blocking::Client,
+    url: &str,
+    partial: &Path,
+    total_bytes: &mut Option<u64>,
+    progress: Option<&ProgressReporter>,
+) -> std::result::Result<(), DownloadErrorKind> {
+    let io_error = |e: io::Error| DownloadErrorKind::Io(e.to_string());
+    let network_error = |e: &dyn std::fmt::Display| DownloadErrorKind::Network(e.to_string());
+
+    let resume = resumable_partial(partial);
+    let offset = resume.as_ref().map_or(0, |(offset, _)| *offset);
+    let mut request = client.get(url);
+    if let Some((offset, validator)) = &resume {
+        log::info!("Resuming {} from byte {}", url, offset);
+        request = request
+            .header(reqwest::header::RANGE, format!("bytes={}-", offset))
+            .header(reqwest::header::IF_RANGE, validator);
+    }
+    let mut response = request.send().map_err(|e| network_error(&e))?;
+
+    let resumed = match continues_partial(response.status().as_u16(), offset) {
+        Ok(resumed) => resumed,
+        Err(kind) => {
+            // The partial file no longer matches what the server has
+            if kind == DownloadErrorKind::Status(416) {
+                fs::remove_file(partial).map_err(io_error)?;
+            }
+            return Err(kind);
+        }
+    };
+    if !resumed {
+        store_validator(partial, response_validator(response.headers()).as_deref())
+            .map_err(io_error)?;
     }
+    let skipped = if resumed { offset } else { 0 };
+    *total_bytes = response.content_length().map(|n| n + skipped);
+    let file = if resumed {
+        fs::OpenOptions::new().append(true).open(partial)
+    } else {
+        File::create(partial)
+    };
+    let mut file = BufWriter::new(file.map_err(io_error)?);
 
     // Copy the response body to the file
+    let mut download = DownloadProgress::start(progress, url, *total_bytes);
+    download.advance(skipped as usize);
     let mut buffer = [0; 8192];
     loop {
-        let bytes_read = response
-            .read(&mut buffer)
-            .map_err(|e| StarfieldError::DataError(format!("Failed to read response: {}", e)))?;
-
+        let bytes_read = match response.read(&mut buffer) {
+            Ok(n) => n,
+            Err(e) => {
+                file.flush().map_err(io_error)?;
+                return Err(network_error(&e));
+            }
+        };
         if bytes_read == 0 {
             break;
         }
-
-        file.write_all(&buffer[..bytes_read])
-            .map_err(StarfieldError::IoError)?;
+        file.write_all(&buffer[..bytes_read]).map_err(io_error)?;
+        download.advance(bytes_read);
     }
-
-    // Flush and sync the file
-    file.flush().map_err(StarfieldError::IoError)?;
-    drop(file);
-
-    // Rename the temporary file to the final path
-    fs::rename(temp_path, path).map_err(StarfieldError::IoError)?;
-
+    download.finish();
+    file.flush().map_err(io_error)?;
     Ok(())
 }
 
 /// Decompress a gzipped file
-/// Currently unused as we're using synthetic data, but kept for future reference
-#[allow(dead_code)]
-fn decompress_gzip<P: AsRef<Path>, Q: AsRef<Path>>(gz_path: P, output_path: Q) -> Result<()> {
+pub(crate) fn decompress_gzip<P: AsRef<Path>, Q: AsRef<Path>>(
+    gz_path: P,
+    output_path: Q,
+) -> Result<()> {
     let file = File::open(&gz_path).map_err(StarfieldError::IoError)?;
 
     // Check if file is a valid gzip file (gzip header starts with magic numbers 0x1F 0x8B)
@@ -147,63 +505,244 @@ fn decompress_gzip<P: AsRef<Path>, Q: AsRef<Path>>(gz_path: P, output_path: Q) -
     }
 }
 
+/// Download OpenNGC's `NGC.csv` into the cache directory unless it is
+/// already there, and return its path
+pub fn download_openngc() -> Result<PathBuf> {
+    fetch_openngc(&CachePolicy::default())
+}
+
+/// OpenNGC's `NGC.csv` as `policy` provides it
+pub(crate) fn fetch_openngc(policy: &CachePolicy) -> Result<PathBuf> {
+    policy.fetch("openngc", OPENNGC_URL, "NGC.csv", None)
+}
+
 /// Download the Hipparcos catalog
 pub fn download_hipparcos() -> Result<PathBuf> {
-    let cache_dir = ensure_cache_dir().map_err(StarfieldError::IoError)?;
-
-    // File paths
-    let dat_path = cache_dir.join("hip_main.dat");
+    fetch_hipparcos(&CachePolicy::default(), None)
+}
 
-    // If the file already exists and is not empty, return its path
-    if file_exists_and_not_empty(&dat_path) {
-        println!("Using cached Hipparcos catalog from {}", dat_path.display());
-        return Ok(dat_path);
-    }
+/// Download the Hipparcos catalog, reporting the download's progress
+pub fn download_hipparcos_with_progress(progress: &ProgressReporter) -> Result<PathBuf> {
+    fetch_hipparcos(&CachePolicy::default(), Some(progress))
+}
 
-    // Check if hip_main.dat exists in the project root (for CI environments)
-    let project_root_dat = PathBuf::from("hip_main.dat");
-    if file_exists_and_not_empty(&project_root_dat) {
-        println!(
-            "Using Hipparcos catalog from project root: {}",
-            project_root_dat.display()
+/// The Hipparcos catalog as `policy` provides it, downloading it if needed
+pub(crate) fn fetch_hipparcos(
+    policy: &CachePolicy,
+    progress: Option<&ProgressReporter>,
+) -> Result<PathBuf> {
+    if let Some((source, dat_path)) = project_hipparcos(policy) {
+        fs::create_dir_all(policy.cache_dir()).map_err(StarfieldError::IoError)?;
+        fs::copy(&source, &dat_path).map_err(StarfieldError::IoError)?;
+        log::info!(
+            "Copied Hipparcos catalog from {} to cache: {}",
+            source.display(),
+            dat_path.display()
         );
-
-        // Copy the file to the cache directory
-        fs::copy(&project_root_dat, &dat_path).map_err(StarfieldError::IoError)?;
-        println!("Copied Hipparcos catalog to cache: {}", dat_path.display());
         return Ok(dat_path);
     }
 
-    // Download the real Hipparcos catalog
-    println!("Downloading Hipparcos catalog from {}...", HIPPARCOS_URL);
-    println!("This may take a moment as the catalog is approximately 36MB");
+    // The real Hipparcos catalog is about 36MB
+    policy
+        .fetch(HIPPARCOS, HIPPARCOS_URL, HIPPARCOS_FILE, progress)
+        .inspect_err(|e| log::warn!("Failed to fetch Hipparcos catalog: {}", e))
+}
 
-    // Attempt to download the file
-    match download_file(HIPPARCOS_URL, &dat_path) {
-        Ok(_) => {
-            println!(
-                "Hipparcos catalog downloaded successfully to {}",
-                dat_path.display()
-            );
-            Ok(dat_path)
-        }
-        Err(e) => {
-            // If download fails, we could provide a fallback to synthetic data, but
-            // for now we'll just return the error
-            println!("Failed to download Hipparcos catalog: {}", e);
-            println!("Check your internet connection or try again later.");
-            Err(e)
-        }
-    }
+/// A `hip_main.dat` in the project root (for CI environments) to copy into
+/// the cache, and where to copy it, when the cache has none
+pub(super) fn project_hipparcos(policy: &CachePolicy) -> Option<(PathBuf, PathBuf)> {
+    let dat_path = policy.cache_dir().join(HIPPARCOS_FILE);
+    let project_root_dat = PathBuf::from(HIPPARCOS_FILE);
+    let usable = policy.source(HIPPARCOS).is_none()
+        && !file_exists_and_not_empty(&dat_path)
+        && file_exists_and_not_empty(&project_root_dat);
+    usable.then_some((project_root_dat, dat_path))
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    use std::io::BufRead;
+    use std::net::TcpListener;
+    use std::sync::{Arc, Mutex};
+    use tempfile::tempdir;
+
+    const BODY: &[u8] = b"0123456789abcdefghij";
+
+    /// The `Range` and `If-Range` headers of one request
+    type Requested = (Option<String>, Option<String>);
+
+    /// Serve `BODY` over HTTP with the ETag `"v1"`, honouring `Range` when
+    /// `If-Range` matches, but drop the connection halfway through the
+    /// first response; returns the URL and the headers of each request
+    fn flaky_server() -> (String, Arc<Mutex<Vec<Requested>>>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/file.dat", listener.local_addr().unwrap());
+        let requests = Arc::new(Mutex::new(Vec::new()));
+        let seen = requests.clone();
+        std::thread::spawn(move || {
+            for (request, stream) in listener.incoming().enumerate() {
+                let mut stream = stream.unwrap();
+                let mut reader = BufReader::new(stream.try_clone().unwrap());
+                let (mut range, mut if_range) = (None, None);
+                loop {
+                    let mut line = String::new();
+                    reader.read_line(&mut line).unwrap();
+                    if line.trim().is_empty() {
+                        break;
+                    }
+                    let lower = line.to_lowercase();
+                    if let Some(value) = lower.strip_prefix("range: bytes=") {
+                        range = Some(value.trim().trim_end_matches('-').to_string());
+                    }
+                    if let Some(value) = lower.strip_prefix("if-range: ") {
+                        if_range = Some(value.trim().to_string());
+                    }
+                }
+                seen.lock().unwrap().push((range.clone(), if_range.clone()));
+
+                let current = if_range.as_deref().is_none_or(|v| v == "\"v1\"");
+                let start: usize = range.filter(|_| current).map_or(0, |r| r.parse().unwrap());
+                let rest = &BODY[start..];
+                let status = if start > 0 {
+                    "206 Partial Content"
+                } else {
+                    "200 OK"
+                };
+                let head = format!(
+                    "HTTP/1.1 {}\r\nETag: \"v1\"\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+                    status,
+                    rest.len()
+                );
+                stream.write_all(head.as_bytes()).unwrap();
+                let sent = if request == 0 {
+                    rest.len() / 2
+                } else {
+                    rest.len()
+                };
+                stream.write_all(&rest[..sent]).unwrap();
+            }
+        });
+        (url, requests)
+    }
+
+    fn quick_retries(attempts: u32) -> Transfer<'static> {
+        Transfer {
+            retry: RetryPolicy::default()
+                .with_max_attempts(attempts)
+                .with_backoff(Duration::ZERO, Duration::ZERO),
+            ..Transfer::default()
+        }
+    }
+
     #[test]
     fn test_cache_dir() {
         let cache_dir = get_cache_dir();
         assert!(cache_dir.to_str().unwrap().contains(".cache/starfield"));
     }
+
+    #[test]
+    fn test_retry_policy() {
+        let retry =
+            RetryPolicy::default().with_backoff(Duration::from_secs(2), Duration::from_secs(10));
+        let waits: Vec<u64> = (1..=5).map(|n| retry.backoff(n).as_secs()).collect();
+        assert_eq!(waits, vec![2, 4, 8, 10, 10]);
+        assert_eq!(RetryPolicy::none().max_attempts(), 1);
+
+        assert_eq!(continues_partial(206, 100), Ok(true));
+        assert_eq!(continues_partial(200, 100), Ok(false));
+        assert_eq!(continues_partial(200, 0), Ok(false));
+        assert!(continues_partial(503, 0).unwrap_err().is_retriable());
+        assert!(!continues_partial(404, 0).unwrap_err().is_retriable());
+        assert_eq!(
+            partial_path(Path::new("/cache/part.csv.gz")),
+            Path::new("/cache/part.csv.gz.part")
+        );
+    }
+
+    #[test]
+    fn test_download_resumes_after_dropped_connection() {
+        let (url, ranges) = flaky_server();
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("file.dat");
+
+        download_with_retry(&url, &path, &quick_retries(3), None).unwrap();
+        assert_eq!(fs::read(&path).unwrap(), BODY);
+        assert!(!partial_path(&path).exists());
+        assert!(!validator_path(&partial_path(&path)).exists());
+        assert_eq!(
+            *ranges.lock().unwrap(),
+            vec![
+                (None, None),
+                (Some("10".to_string()), Some("\"v1\"".to_string()))
+            ]
+        );
+    }
+
+    #[test]
+    fn test_stale_partial_files_are_not_resumed() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("file.dat");
+        let partial = partial_path(&path);
+
+        // Left by an older copy of the file, which the server no longer has
+        fs::write(&partial, "XXXXX").unwrap();
+        fs::write(validator_path(&partial), "\"v0\"").unwrap();
+        let (url, requests) = flaky_server();
+        download_with_retry(&url, &path, &quick_retries(3), None).unwrap();
+        assert_eq!(fs::read(&path).unwrap(), BODY);
+        assert_eq!(
+            requests.lock().unwrap()[0],
+            (Some("5".to_string()), Some("\"v0\"".to_string()))
+        );
+
+        // Without a validator a partial file cannot be checked, so it is
+        // downloaded again from the start
+        fs::write(&partial, "XXXXX").unwrap();
+        let (url, requests) = flaky_server();
+        download_with_retry(&url, &path, &quick_retries(3), None).unwrap();
+        assert_eq!(fs::read(&path).unwrap(), BODY);
+        assert_eq!(requests.lock().unwrap()[0], (None, None));
+    }
+
+    #[test]
+    fn test_failed_download_keeps_partial_file() {
+        let (url, _) = flaky_server();
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("file.dat");
+
+        let err = download_with_retry(&url, &path, &quick_retries(1), None).unwrap_err();
+        let StarfieldError::DownloadError(err) = err else {
+            panic!("expected a download error, got {}", err);
+        };
+        assert_eq!(err.attempts, 1);
+        assert_eq!(err.bytes_downloaded, 10);
+        assert_eq!(err.total_bytes, Some(20));
+        assert_eq!(err.partial_path, Some(partial_path(&path)));
+        assert!(matches!(err.kind, DownloadErrorKind::Network(_)));
+        assert!(!path.exists());
+    }
+
+    #[test]
+    fn test_sha256_verification() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("abc.txt");
+        fs::write(&path, "abc").unwrap();
+        let digest = sha256_file(&path).unwrap();
+        assert_eq!(
+            digest,
+            "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+        );
+        assert!(verify_checksum("url", &path, "SHA-256", digest.clone(), &digest).is_ok());
+
+        let err = verify_checksum("url", &path, "SHA-256", digest, "00ff").unwrap_err();
+        assert!(matches!(
+            err,
+            StarfieldError::DownloadError(ref e)
+                if matches!(e.kind, DownloadErrorKind::ChecksumMismatch { .. })
+        ));
+        // A corrupt file is not left in the cache
+        assert!(!path.exists());
+    }
 }
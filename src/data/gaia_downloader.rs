@@ -5,19 +5,23 @@
 use std::collections::HashMap;
 use std::env;
 use std::fs::{self, File};
-use std::io::{self, BufRead, BufReader, BufWriter, Read, Write};
+use std::io::{self, Read};
 use std::path::{Path, PathBuf};
 use std::time::Duration;
 // No need for sync primitives yet
 
+use super::downloader::{download_with_retry, sha256_file, verify_checksum, Transfer};
+use super::progress::ProgressReporter;
 use crate::Result;
 use crate::StarfieldError;
 use regex::Regex;
 
 // Base URL for Gaia DR1 catalog
-const GAIA_DR1_BASE_URL: &str = "https://cdn.gea.esac.esa.int/Gaia/gdr1/gaia_source/csv/";
+pub(super) const GAIA_DR1_BASE_URL: &str =
+    "https://cdn.gea.esac.esa.int/Gaia/gdr1/gaia_source/csv/";
 // URL to the MD5SUMS file
-const GAIA_MD5SUMS_URL: &str = "https://cdn.gea.esac.esa.int/Gaia/gdr1/gaia_source/csv/MD5SUM.txt";
+pub(super) const GAIA_MD5SUMS_URL: &str =
+    "https://cdn.gea.esac.esa.int/Gaia/gdr1/gaia_source/csv/MD5SUM.txt";
 
 /// Get the Gaia cache directory path
 pub fn get_gaia_cache_dir() -> PathBuf {
@@ -43,118 +47,18 @@ fn file_exists_and_not_empty<P: AsRef<Path>>(path: P) -> bool {
     }
 }
 
-/// Download a file from URL to a local path
-fn download_file<P: AsRef<Path>>(url: &str, path: P) -> Result<()> {
-    // Create parent directories if they don't exist
-    if let Some(parent) = path.as_ref().parent() {
-        fs::create_dir_all(parent).map_err(StarfieldError::IoError)?;
-    }
-
-    // Create a temporary file first to avoid partial downloads
-    let temp_path = path.as_ref().with_extension("tmp");
-    let mut file = BufWriter::new(File::create(&temp_path).map_err(StarfieldError::IoError)?);
-
-    // Create HTTP client with timeout
-    let client = reqwest::blocking::Client::builder()
-        .timeout(Duration::from_secs(600)) // 10 minute timeout for large files
-        .build()
-        .map_err(|e| StarfieldError::DataError(format!("Failed to create HTTP client: {}", e)))?;
-
-    println!("Downloading: {}", url);
-
-    // Make the request
-    let mut response = client
-        .get(url)
-        .send()
-        .map_err(|e| StarfieldError::DataError(format!("Failed to download file: {}", e)))?;
-
-    // Check if the request was successful
-    if !response.status().is_success() {
-        return Err(StarfieldError::DataError(format!(
-            "Failed to download file, status: {}",
-            response.status()
-        )));
-    }
-
-    // Get file size for progress tracking
-    let total_size = response.content_length().unwrap_or(0);
-    let mut downloaded: u64 = 0;
-    let start_time = std::time::Instant::now();
-
-    // Copy the response body to the file with progress reporting
-    let mut buffer = [0; 8192];
-
-    loop {
-        match response.read(&mut buffer) {
-            Ok(0) => break, // EOF
-            Ok(n) => {
-                file.write_all(&buffer[..n])
-                    .map_err(StarfieldError::IoError)?;
-                downloaded += n as u64;
-
-                // Print progress every 5MB
-                if downloaded % (5 * 1024 * 1024) == 0 {
-                    let elapsed = start_time.elapsed().as_secs_f64();
-                    let speed = if elapsed > 0.0 {
-                        downloaded as f64 / elapsed / 1024.0 / 1024.0
-                    } else {
-                        0.0
-                    };
-
-                    if total_size > 0 {
-                        let percentage = (downloaded as f64 / total_size as f64) * 100.0;
-                        print!(
-                            "\rDownloaded: {:.1}% ({:.1}MB/{:.1}MB) at {:.1} MB/s",
-                            percentage,
-                            downloaded as f64 / 1024.0 / 1024.0,
-                            total_size as f64 / 1024.0 / 1024.0,
-                            speed
-                        );
-                    } else {
-                        print!(
-                            "\rDownloaded: {:.1}MB at {:.1} MB/s",
-                            downloaded as f64 / 1024.0 / 1024.0,
-                            speed
-                        );
-                    }
-                    io::stdout().flush().unwrap();
-                }
-            }
-            Err(e) => {
-                return Err(StarfieldError::DataError(format!(
-                    "Error downloading file: {}",
-                    e
-                )))
-            }
-        }
-    }
-
-    // Final progress update
-    let elapsed = start_time.elapsed().as_secs_f64();
-    let speed = if elapsed > 0.0 {
-        downloaded as f64 / elapsed / 1024.0 / 1024.0
-    } else {
-        0.0
+/// Download a file from URL to a local path, allowing ten minutes per
+/// attempt for the large catalog files
+fn download_file(url: &str, path: &Path, progress: Option<&ProgressReporter>) -> Result<()> {
+    let transfer = Transfer {
+        timeout: Duration::from_secs(600),
+        ..Transfer::default()
     };
-    println!(
-        "\rDownload complete: {:.1}MB at {:.1} MB/s in {:.1}s",
-        downloaded as f64 / 1024.0 / 1024.0,
-        speed,
-        elapsed
-    );
-
-    // Flush and sync the file
-    file.flush().map_err(StarfieldError::IoError)?;
-    drop(file);
-
-    // Rename the temporary file to the final path
-    fs::rename(temp_path, path).map_err(StarfieldError::IoError)?;
-
-    Ok(())
+    download_with_retry(url, path, &transfer, progress)
 }
 
 /// Calculate MD5 checksum of a file
-fn calculate_md5<P: AsRef<Path>>(path: P) -> Result<String> {
+pub(crate) fn calculate_md5<P: AsRef<Path>>(path: P) -> Result<String> {
     let mut file = File::open(path).map_err(StarfieldError::IoError)?;
     let mut buffer = [0; 1024 * 1024]; // 1MB buffer
     let mut context = md5::Context::new();
@@ -172,32 +76,28 @@ fn calculate_md5<P: AsRef<Path>>(path: P) -> Result<String> {
 }
 
 /// Download the MD5SUMS file and parse it
-fn download_md5sums() -> Result<HashMap<String, String>> {
+fn download_md5sums(progress: Option<&ProgressReporter>) -> Result<HashMap<String, String>> {
     let cache_dir = ensure_gaia_cache_dir().map_err(StarfieldError::IoError)?;
     let md5sums_path = cache_dir.join("MD5SUM.txt");
 
     // Download MD5SUMS file if it doesn't exist or is empty
     if !file_exists_and_not_empty(&md5sums_path) {
-        download_file(GAIA_MD5SUMS_URL, &md5sums_path)?;
+        download_file(GAIA_MD5SUMS_URL, &md5sums_path, progress)?;
     }
 
-    // Parse MD5SUMS file
-    let file = File::open(md5sums_path).map_err(StarfieldError::IoError)?;
-    let reader = BufReader::new(file);
-    let mut checksums = HashMap::new();
-
-    for line in reader.lines() {
-        let line = line.map_err(StarfieldError::IoError)?;
-        let parts: Vec<&str> = line.split_whitespace().collect();
-
-        if parts.len() >= 2 {
-            let checksum = parts[0].to_string();
-            let filename = parts[1].trim_start_matches("*").to_string();
-            checksums.insert(filename, checksum);
-        }
-    }
+    Ok(parse_md5sums(&fs::read_to_string(md5sums_path)?))
+}
 
-    Ok(checksums)
+/// Checksums by file name from the text of an MD5SUMS file
+pub(super) fn parse_md5sums(text: &str) -> HashMap<String, String> {
+    text.lines()
+        .filter_map(|line| {
+            let mut parts = line.split_whitespace();
+            let checksum = parts.next()?;
+            let filename = parts.next()?.trim_start_matches('*');
+            Some((filename.to_string(), checksum.to_string()))
+        })
+        .collect()
 }
 
 /// List all files in the Gaia DR1 catalog index
@@ -208,7 +108,7 @@ fn list_gaia_files() -> Result<Vec<String>> {
         .build()
         .map_err(|e| StarfieldError::DataError(format!("Failed to create HTTP client: {}", e)))?;
 
-    println!("Fetching Gaia catalog index...");
+    log::info!("Fetching Gaia catalog index");
     let response = client
         .get(GAIA_DR1_BASE_URL)
         .send()
@@ -236,7 +136,7 @@ fn list_gaia_files() -> Result<Vec<String>> {
         .collect::<Vec<_>>();
 
     if files.is_empty() {
-        println!("Warning: No Gaia files found in index. Using fallback enumeration.");
+        log::warn!("No Gaia files found in index. Using fallback enumeration.");
         // Fallback to the previous implementation if no files are found
         let fallback_files = (0..=999)
             .map(|i| format!("GaiaSource_000-000-{:03}.csv.gz", i))
@@ -244,7 +144,7 @@ fn list_gaia_files() -> Result<Vec<String>> {
         return Ok(fallback_files);
     }
 
-    println!("Found {} Gaia catalog files", files.len());
+    log::info!("Found {} Gaia catalog files", files.len());
     Ok(files)
 }
 
@@ -274,25 +174,31 @@ pub fn list_cached_gaia_files() -> Result<Vec<PathBuf>> {
     Ok(files)
 }
 
-/// Verify a file against its MD5 checksum
-fn verify_file<P: AsRef<Path>>(path: P, expected_md5: &str) -> Result<bool> {
-    println!("Verifying {}...", path.as_ref().display());
-    let actual_md5 = calculate_md5(&path)?;
+/// Download and verify a specific Gaia file
+pub fn download_gaia_file(filename: &str) -> Result<PathBuf> {
+    fetch_gaia_file(filename, None, None)
+}
 
-    let valid = actual_md5 == expected_md5;
-    if !valid {
-        println!("Checksum mismatch for {}", path.as_ref().display());
-        println!("  Expected: {}", expected_md5);
-        println!("  Actual:   {}", actual_md5);
-    } else {
-        println!("Checksum verified for {}", path.as_ref().display());
-    }
+/// Download a specific Gaia file and verify it against the published MD5
+/// and a known SHA-256 digest
+pub fn download_gaia_file_with_sha256(filename: &str, sha256: &str) -> Result<PathBuf> {
+    fetch_gaia_file(filename, Some(sha256), None)
+}
 
-    Ok(valid)
+/// Download and verify a specific Gaia file, reporting the download's
+/// progress
+pub fn download_gaia_file_with_progress(
+    filename: &str,
+    progress: &ProgressReporter,
+) -> Result<PathBuf> {
+    fetch_gaia_file(filename, None, Some(progress))
 }
 
-/// Download and verify a specific Gaia file
-pub fn download_gaia_file(filename: &str) -> Result<PathBuf> {
+fn fetch_gaia_file(
+    filename: &str,
+    sha256: Option<&str>,
+    progress: Option<&ProgressReporter>,
+) -> Result<PathBuf> {
     let cache_dir = ensure_gaia_cache_dir().map_err(StarfieldError::IoError)?;
 
     // Check if the file is a *.csv.gz and extract base name
@@ -311,62 +217,86 @@ pub fn download_gaia_file(filename: &str) -> Result<PathBuf> {
     }
 
     // Download checksums
-    let checksums = download_md5sums()?;
+    let checksums = download_md5sums(progress)?;
 
     // Download the gzipped file if it doesn't exist
+    let file_url = format!("{}{}", GAIA_DR1_BASE_URL, filename);
     if !file_exists_and_not_empty(&gz_path) {
-        let file_url = format!("{}{}", GAIA_DR1_BASE_URL, filename);
-        download_file(&file_url, &gz_path)?;
+        download_file(&file_url, &gz_path, progress)?;
     }
 
-    // Verify the file
-    if let Some(expected_md5) = checksums.get(filename) {
-        if !verify_file(&gz_path, expected_md5)? {
-            return Err(StarfieldError::DataError(format!(
-                "MD5 checksum verification failed for {}",
-                filename
-            )));
-        }
-    } else {
-        println!("Warning: No MD5 checksum found for {}", filename);
+    // Verify the file, deleting it if corrupt so the next call downloads it
+    // again
+    match checksums.get(filename) {
+        Some(expected_md5) => verify_checksum(
+            &file_url,
+            &gz_path,
+            "MD5",
+            calculate_md5(&gz_path)?,
+            expected_md5,
+        )?,
+        None => log::warn!("No MD5 checksum found for {}", filename),
+    }
+    if let Some(expected) = sha256 {
+        verify_checksum(
+            &file_url,
+            &gz_path,
+            "SHA-256",
+            sha256_file(&gz_path)?,
+            expected,
+        )?;
     }
 
     // Return the gz file path directly, without decompressing
-    println!("File verified and ready for streaming decompression.");
-
     Ok(gz_path)
 }
 
 /// Download the entire Gaia catalog (all files)
 pub fn download_gaia_catalog(max_files: Option<usize>) -> Result<Vec<PathBuf>> {
+    fetch_gaia_catalog(max_files, None)
+}
+
+/// Download the entire Gaia catalog (all files), reporting each file's
+/// progress
+pub fn download_gaia_catalog_with_progress(
+    max_files: Option<usize>,
+    progress: &ProgressReporter,
+) -> Result<Vec<PathBuf>> {
+    fetch_gaia_catalog(max_files, Some(progress))
+}
+
+fn fetch_gaia_catalog(
+    max_files: Option<usize>,
+    progress: Option<&ProgressReporter>,
+) -> Result<Vec<PathBuf>> {
     let files = list_gaia_files()?;
     let max_files = max_files.unwrap_or(files.len());
     let files_to_download = files.into_iter().take(max_files).collect::<Vec<_>>();
 
-    println!("Downloading {} Gaia catalog files", files_to_download.len());
+    log::info!("Downloading {} Gaia catalog files", files_to_download.len());
 
     // Process files
     let mut downloaded_files = Vec::new();
 
     for (i, filename) in files_to_download.iter().enumerate() {
-        println!(
+        log::info!(
             "[{}/{}] Processing {}",
             i + 1,
             files_to_download.len(),
             filename
         );
-        match download_gaia_file(filename) {
+        match fetch_gaia_file(filename, None, progress) {
             Ok(path) => {
                 downloaded_files.push(path);
             }
             Err(e) => {
-                println!("Error downloading {}: {}", filename, e);
+                log::warn!("Error downloading {}: {}", filename, e);
                 // Continue with other files
             }
         }
     }
 
-    println!(
+    log::info!(
         "Downloaded and verified {} Gaia catalog files",
         downloaded_files.len()
     );
@@ -0,0 +1,253 @@
+//! On-demand Gaia cone searches through the archive's TAP service
+//!
+//! Rather than downloading the bulk CSV exports, [`GaiaTapClient`] sends an
+//! ADQL query for one region of sky to the synchronous TAP endpoint of the
+//! ESA Gaia archive and loads the CSV it returns. Each result is cached
+//! under a name derived from the query text, so repeating a search, or
+//! replaying an analysis, does not touch the network.
+//!
+//! ```no_run
+//! use starfield::catalogs::StarCatalog;
+//! use starfield::data::GaiaTapClient;
+//!
+//! let client = GaiaTapClient::new();
+//! // Stars to G = 12 within half a degree of the Pleiades
+//! let catalog = client.cone_search(56.75, 24.12, 0.5, 12.0)?;
+//! println!("{} stars", catalog.len());
+//! # Ok::<(), starfield::StarfieldError>(())
+//! ```
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use super::gaia_downloader::get_gaia_cache_dir;
+use crate::catalogs::{DataRelease, GaiaCatalog, GaiaCatalogReader};
+use crate::{Result, StarfieldError};
+
+/// Synchronous TAP endpoint of the ESA Gaia archive
+pub const GAIA_TAP_URL: &str = "https://gea.esac.esa.int/tap-server/tap/sync";
+
+/// Queries can take a while on a busy archive
+const QUERY_TIMEOUT_S: u64 = 300;
+
+/// Runs cone searches against a Gaia TAP service and caches the results
+#[derive(Debug, Clone)]
+pub struct GaiaTapClient {
+    endpoint: String,
+    release: DataRelease,
+    cache_dir: PathBuf,
+    offline: bool,
+}
+
+impl Default for GaiaTapClient {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl GaiaTapClient {
+    /// Query DR3 at the ESA archive, caching results in the Gaia cache
+    /// directory
+    pub fn new() -> Self {
+        Self {
+            endpoint: GAIA_TAP_URL.to_string(),
+            release: DataRelease::Dr3,
+            cache_dir: get_gaia_cache_dir().join("tap"),
+            offline: false,
+        }
+    }
+
+    /// Send queries to another TAP service, such as a mirror
+    pub fn with_endpoint(mut self, url: &str) -> Self {
+        self.endpoint = url.to_string();
+        self
+    }
+
+    /// Query a different data release
+    pub fn with_release(mut self, release: DataRelease) -> Self {
+        self.release = release;
+        self
+    }
+
+    /// Cache query results in `dir`
+    pub fn with_cache_dir<P: AsRef<Path>>(mut self, dir: P) -> Self {
+        self.cache_dir = dir.as_ref().to_path_buf();
+        self
+    }
+
+    /// Answer only queries whose results are cached, without the network
+    pub fn offline(mut self, offline: bool) -> Self {
+        self.offline = offline;
+        self
+    }
+
+    /// Data release being queried
+    pub fn release(&self) -> DataRelease {
+        self.release
+    }
+
+    /// ADQL for the stars brighter than `mag_limit` within `radius_deg` of
+    /// a position
+    pub fn cone_search_query(
+        &self,
+        ra_deg: f64,
+        dec_deg: f64,
+        radius_deg: f64,
+        mag_limit: f64,
+    ) -> Result<String> {
+        let table = match self.release {
+            DataRelease::Dr1 => "gaiadr1.gaia_source",
+            DataRelease::Dr2 => "gaiadr2.gaia_source",
+            DataRelease::Edr3 => "gaiaedr3.gaia_source",
+            DataRelease::Dr3 => "gaiadr3.gaia_source",
+            DataRelease::Dr4 => {
+                return Err(StarfieldError::DataError(
+                    "Gaia DR4 is not yet available from the archive".to_string(),
+                ))
+            }
+        };
+        Ok(format!(
+            "SELECT {} FROM {} WHERE 1 = CONTAINS(POINT('ICRS', ra, dec), \
+             CIRCLE('ICRS', {:.8}, {:.8}, {:.8})) AND phot_g_mean_mag <= {:.3}",
+            self.release.required_columns().join(", "),
+            table,
+            ra_deg,
+            dec_deg,
+            radius_deg,
+            mag_limit
+        ))
+    }
+
+    /// Cone search, loaded into a catalog
+    pub fn cone_search(
+        &self,
+        ra_deg: f64,
+        dec_deg: f64,
+        radius_deg: f64,
+        mag_limit: f64,
+    ) -> Result<GaiaCatalog> {
+        let path = self.fetch(&self.cone_search_query(ra_deg, dec_deg, radius_deg, mag_limit)?)?;
+        GaiaCatalog::from_file_with_release(path, mag_limit, Some(self.release))
+    }
+
+    /// Cone search, streamed a star at a time
+    pub fn cone_search_stream(
+        &self,
+        ra_deg: f64,
+        dec_deg: f64,
+        radius_deg: f64,
+        mag_limit: f64,
+    ) -> Result<GaiaCatalogReader> {
+        let path = self.fetch(&self.cone_search_query(ra_deg, dec_deg, radius_deg, mag_limit)?)?;
+        GaiaCatalogReader::open_with_release(path, mag_limit, Some(self.release))
+    }
+
+    /// Where the result of `query` is cached
+    pub fn cache_path(&self, query: &str) -> PathBuf {
+        let digest = md5::compute(format!("{}\n{}", self.endpoint, query));
+        self.cache_dir.join(format!("{:x}.csv", digest))
+    }
+
+    /// Run `query`, or reuse its cached result, and return the CSV's path
+    fn fetch(&self, query: &str) -> Result<PathBuf> {
+        let path = self.cache_path(query);
+        if fs::metadata(&path).is_ok_and(|m| m.len() > 0) {
+            return Ok(path);
+        }
+        if self.offline {
+            return Err(StarfieldError::DataError(format!(
+                "TAP query result is not cached at {} and the client is offline",
+                path.display()
+            )));
+        }
+        fs::create_dir_all(&self.cache_dir)?;
+
+        let client = reqwest::blocking::Client::builder()
+            .timeout(Duration::from_secs(QUERY_TIMEOUT_S))
+            .build()
+            .map_err(|e| {
+                StarfieldError::DataError(format!("Failed to create HTTP client: {}", e))
+            })?;
+        let response = client
+            .post(&self.endpoint)
+            .form(&[
+                ("REQUEST", "doQuery"),
+                ("LANG", "ADQL"),
+                ("FORMAT", "csv"),
+                ("QUERY", query),
+            ])
+            .send()
+            .map_err(|e| StarfieldError::DataError(format!("TAP query failed: {}", e)))?;
+        if !response.status().is_success() {
+            return Err(StarfieldError::DataError(format!(
+                "TAP query failed, status: {}",
+                response.status()
+            )));
+        }
+        let body = response
+            .bytes()
+            .map_err(|e| StarfieldError::DataError(format!("Failed to read response: {}", e)))?;
+
+        // Write under a temporary name so an interrupted query is not cached
+        let temp_path = path.with_extension("tmp");
+        fs::write(&temp_path, &body)?;
+        fs::rename(&temp_path, &path)?;
+        Ok(path)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::catalogs::StarCatalog;
+
+    #[test]
+    fn test_query_text() {
+        let client = GaiaTapClient::new();
+        let query = client.cone_search_query(56.75, 24.12, 0.5, 12.0).unwrap();
+        assert!(query.starts_with("SELECT source_id, solution_id, ra, dec,"));
+        assert!(query.contains("FROM gaiadr3.gaia_source"));
+        assert!(query.contains("CIRCLE('ICRS', 56.75000000, 24.12000000, 0.50000000)"));
+        assert!(query.ends_with("phot_g_mean_mag <= 12.000"));
+
+        // EDR3 has no variability flag to ask for
+        let edr3 = client.clone().with_release(DataRelease::Edr3);
+        let query = edr3.cone_search_query(0.0, 0.0, 1.0, 10.0).unwrap();
+        assert!(query.contains("gaiaedr3") && !query.contains("phot_variable_flag"));
+        assert!(client
+            .with_release(DataRelease::Dr4)
+            .cone_search_query(0.0, 0.0, 1.0, 10.0)
+            .is_err());
+    }
+
+    #[test]
+    fn test_cached_result_is_reused() {
+        let dir = tempfile::tempdir().unwrap();
+        // An unreachable endpoint: only the cache can answer
+        let client = GaiaTapClient::new()
+            .with_endpoint("http://127.0.0.1:9/tap/sync")
+            .with_cache_dir(dir.path());
+        let query = client.cone_search_query(56.75, 24.12, 0.5, 12.0).unwrap();
+        fs::write(
+            client.cache_path(&query),
+            "source_id,solution_id,ra,dec,ra_error,dec_error,parallax,parallax_error,pmra,pmdec,phot_g_mean_mag,phot_g_mean_flux,l,b,ecl_lon,ecl_lat,phot_variable_flag\n\
+             66714384141781760,1,56.75,24.12,0.02,0.01,7.4,0.03,19.9,-45.4,2.8,1e8,166.6,-23.5,60.0,4.1,VARIABLE\n",
+        )
+        .unwrap();
+
+        let catalog = client.cone_search(56.75, 24.12, 0.5, 12.0).unwrap();
+        assert_eq!(catalog.len(), 1);
+        assert_eq!(catalog.release(), Some(DataRelease::Dr3));
+        let streamed = client.cone_search_stream(56.75, 24.12, 0.5, 12.0).unwrap();
+        assert_eq!(streamed.count(), 1);
+
+        // A different region is not cached, and the endpoint refuses
+        assert!(client.cone_search(10.0, 10.0, 0.5, 12.0).is_err());
+
+        // Offline, the cache still answers but nothing else is tried
+        let client = client.offline(true);
+        assert!(client.cone_search(56.75, 24.12, 0.5, 12.0).is_ok());
+        assert!(client.cone_search(10.0, 10.0, 0.5, 12.0).is_err());
+    }
+}
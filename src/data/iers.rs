@@ -0,0 +1,251 @@
+//! IERS Earth-orientation data: leap seconds and UT1 - UTC
+//!
+//! `Leap_Second.dat` lists every change of TAI - UTC, and
+//! `finals2000A.all` gives daily UT1 - UTC and polar motion from 1973 to a
+//! year of predictions ahead. Together they give Delta T exactly:
+//!
+//! ```text
+//! Delta T = TT - UT1 = 32.184 s + (TAI - UTC) - (UT1 - UTC)
+//! ```
+//!
+//! [`iers_timescale`] builds a [`Timescale`] from the two files, with the
+//! leap seconds replacing the built-in list and a Delta T table holding one
+//! row per day of the finals file.
+
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+
+use super::cache::CachePolicy;
+use crate::constants::{DAY_S, GREGORIAN_START, TT_MINUS_TAI_S};
+use crate::time::Timescale;
+use crate::{Result, StarfieldError};
+
+/// Leap-second history from the IERS Earth Orientation Centre
+pub const LEAP_SECOND_URL: &str = "https://hpiers.obspm.fr/iers/bul/bulc/Leap_Second.dat";
+
+/// Daily Earth orientation parameters (IAU 2000) from the IERS Rapid Service
+pub const FINALS_URL: &str = "https://datacenter.iers.org/data/9/finals2000A.all";
+
+/// Modified Julian Date zero point
+const MJD_ZERO: f64 = 2_400_000.5;
+
+/// A change of TAI - UTC
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct LeapSecond {
+    /// UTC Julian date from which the offset applies
+    pub jd_utc: f64,
+    /// TAI - UTC in seconds from that date
+    pub tai_minus_utc: i32,
+}
+
+/// One day of `finals2000A.all`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct EopRecord {
+    /// Modified Julian Date, at 0h UTC
+    pub mjd: f64,
+    /// UT1 - UTC in seconds
+    pub ut1_minus_utc: f64,
+    /// Polar motion x in arcseconds, towards the Greenwich meridian
+    pub pm_x_arcsec: f64,
+    /// Polar motion y in arcseconds, towards 90 degrees west
+    pub pm_y_arcsec: f64,
+    /// Whether the value is a prediction rather than a measurement
+    pub predicted: bool,
+}
+
+/// Parse `Leap_Second.dat`: comment lines start with `#`, and each data
+/// line holds the MJD, day, month, year and TAI - UTC
+pub fn parse_leap_seconds(text: &str) -> Result<Vec<LeapSecond>> {
+    let mut leaps = Vec::new();
+    for line in text.lines() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let fields: Vec<&str> = line.split_whitespace().collect();
+        let parsed = match fields.as_slice() {
+            [mjd, _, _, _, offset] => mjd.parse::<f64>().ok().zip(offset.parse::<i32>().ok()),
+            _ => None,
+        };
+        let (mjd, offset) = parsed.ok_or_else(|| {
+            StarfieldError::DataError(format!("bad leap-second line: {:?}", line))
+        })?;
+        leaps.push(LeapSecond {
+            jd_utc: mjd + MJD_ZERO,
+            tai_minus_utc: offset,
+        });
+    }
+    if leaps.is_empty() {
+        return Err(StarfieldError::DataError(
+            "no leap seconds found".to_string(),
+        ));
+    }
+    Ok(leaps)
+}
+
+/// Parse the fixed-column `finals2000A.all`, keeping the days that have a
+/// UT1 - UTC value (the tail of the file has polar motion predictions only)
+pub fn parse_finals(text: &str) -> Result<Vec<EopRecord>> {
+    let column = |line: &str, start: usize, end: usize| {
+        line.get(start..end.min(line.len()))
+            .unwrap_or("")
+            .trim()
+            .to_string()
+    };
+    let mut records = Vec::new();
+    for line in text.lines() {
+        let ut1 = column(line, 58, 68);
+        if ut1.is_empty() {
+            continue;
+        }
+        let (Ok(mjd), Ok(ut1_minus_utc), Ok(pm_x_arcsec), Ok(pm_y_arcsec)) = (
+            column(line, 7, 15).parse(),
+            ut1.parse(),
+            column(line, 18, 27).parse(),
+            column(line, 37, 46).parse(),
+        ) else {
+            return Err(StarfieldError::DataError(format!(
+                "bad finals2000A line: {:?}",
+                line
+            )));
+        };
+        records.push(EopRecord {
+            mjd,
+            ut1_minus_utc,
+            pm_x_arcsec,
+            pm_y_arcsec,
+            predicted: column(line, 57, 58) == "P",
+        });
+    }
+    if records.is_empty() {
+        return Err(StarfieldError::DataError(
+            "no UT1 - UTC values found".to_string(),
+        ));
+    }
+    Ok(records)
+}
+
+/// TAI - UTC in seconds at a UTC Julian date
+fn tai_minus_utc(leaps: &[LeapSecond], jd_utc: f64) -> f64 {
+    leaps
+        .iter()
+        .take_while(|l| l.jd_utc <= jd_utc)
+        .last()
+        .map_or(0.0, |l| l.tai_minus_utc as f64)
+}
+
+/// Build a timescale from the text of `Leap_Second.dat` and
+/// `finals2000A.all`
+pub fn iers_timescale(leap_seconds: &str, finals: &str) -> Result<Timescale> {
+    let leaps = parse_leap_seconds(leap_seconds)?;
+    let records = parse_finals(finals)?;
+
+    let (tt, delta_t): (Vec<f64>, Vec<f64>) = records
+        .iter()
+        .map(|r| {
+            let jd_utc = r.mjd + MJD_ZERO;
+            let tai_utc = tai_minus_utc(&leaps, jd_utc);
+            (
+                jd_utc + (tai_utc + TT_MINUS_TAI_S) / DAY_S,
+                TT_MINUS_TAI_S + tai_utc - r.ut1_minus_utc,
+            )
+        })
+        .unzip();
+
+    Ok(Timescale::new(
+        Some((tt, delta_t)),
+        leaps.iter().map(|l| l.jd_utc).collect(),
+        leaps.iter().map(|l| l.tai_minus_utc).collect(),
+        Some(GREGORIAN_START),
+    ))
+}
+
+/// Download `Leap_Second.dat` and `finals2000A.all` into the cache, unless
+/// they are already there, and return their paths
+pub fn download_iers() -> Result<(PathBuf, PathBuf)> {
+    fetch_iers(&CachePolicy::default())
+}
+
+/// `Leap_Second.dat` and `finals2000A.all` as `policy` provides them
+pub(crate) fn fetch_iers(policy: &CachePolicy) -> Result<(PathBuf, PathBuf)> {
+    Ok((
+        policy.fetch("leap_seconds", LEAP_SECOND_URL, "Leap_Second.dat", None)?,
+        policy.fetch("iers_finals", FINALS_URL, "finals2000A.all", None)?,
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use approx::assert_relative_eq;
+
+    const LEAP_SECONDS: &str = "\
+#  File expires on 28 June 2025
+#    MJD        Date        TAI-UTC (s)
+#           day month year
+#    ---    --------------   ------
+#
+    41317.0    1  1 1972       10
+    41499.0    1  7 1972       11
+    57204.0    1  7 2015       36
+    57754.0    1  1 2017       37
+";
+
+    /// A `finals2000A.all` line with the UT1 - UTC columns filled in
+    fn finals_line(mjd: f64, flag: char, ut1_minus_utc: Option<f64>) -> String {
+        let (year, month, day) = (20, 1, 1);
+        let ut1 = ut1_minus_utc.map_or(" ".repeat(10), |v| format!("{:10.7}", v));
+        format!(
+            "{:2}{:2}{:2} {:8.2} {} {:9.6}{:9.6} {:9.6}{:9.6}  {}{}{:10.7}",
+            year, month, day, mjd, flag, 0.0766, 0.00003, 0.2824, 0.00003, flag, ut1, 0.0000066
+        )
+    }
+
+    #[test]
+    fn test_parse_files() {
+        let leaps = parse_leap_seconds(LEAP_SECONDS).unwrap();
+        assert_eq!(leaps.len(), 4);
+        assert_relative_eq!(leaps[0].jd_utc, 2_441_317.5);
+        assert_eq!(leaps[3].tai_minus_utc, 37);
+
+        let finals = [
+            finals_line(58849.0, 'I', Some(-0.1771554)),
+            finals_line(58850.0, 'P', Some(-0.1779)),
+            finals_line(58851.0, 'P', None),
+        ]
+        .join("\n");
+        let records = parse_finals(&finals).unwrap();
+        assert_eq!(records.len(), 2);
+        assert_relative_eq!(records[0].ut1_minus_utc, -0.1771554);
+        assert_relative_eq!(records[0].pm_x_arcsec, 0.0766);
+        assert_relative_eq!(records[0].pm_y_arcsec, 0.2824);
+        assert!(!records[0].predicted && records[1].predicted);
+
+        assert!(parse_leap_seconds("# nothing\n").is_err());
+        assert!(parse_finals("").is_err());
+    }
+
+    #[test]
+    fn test_timescale_delta_t() {
+        let finals = (0..10)
+            .map(|i| {
+                finals_line(
+                    58849.0 + i as f64,
+                    'I',
+                    Some(-0.1771554 - 0.0003 * i as f64),
+                )
+            })
+            .collect::<Vec<_>>()
+            .join("\n");
+        let ts = iers_timescale(LEAP_SECONDS, &finals).unwrap();
+
+        // 2020-01-01: 32.184 + 37 + 0.1771554
+        let t = ts.utc((2020, 1, 1));
+        assert_relative_eq!(t.delta_t(), 69.361_155_4, epsilon = 1e-6);
+        assert_relative_eq!(t.dut1(), -0.177_155_4, epsilon = 1e-6);
+        assert_eq!(t.leap_seconds(), 37.0);
+
+        let noon = ts.utc((2020, 1, 3, 12, 0, 0.0));
+        assert_relative_eq!(noon.dut1(), -0.1771554 - 0.0003 * 2.5, epsilon = 1e-6);
+    }
+}
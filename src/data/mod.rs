@@ -3,11 +3,47 @@
 //! This module provides functionality for downloading, caching, and loading
 //! astronomical datasets like star catalogs.
 
+#[cfg(feature = "async")]
+mod async_download;
+mod cache;
 mod downloader;
 mod gaia_downloader;
+#[cfg(feature = "gaia-tap")]
+mod gaia_tap;
+mod iers;
+mod mpc;
+mod progress;
+mod recorder;
 
-pub use downloader::{download_hipparcos, ensure_cache_dir, get_cache_dir};
+#[cfg(feature = "async")]
+pub use async_download::{
+    download_gaia_file_async, download_gaia_file_with_progress_async, download_hipparcos_async,
+    download_iers_async, download_openngc_async,
+};
+#[cfg(feature = "async")]
+pub(crate) use async_download::{fetch_hipparcos_async, fetch_iers_async, fetch_openngc_async};
+pub use cache::{CachePolicy, DatasetSource};
+pub use downloader::{
+    download_hipparcos, download_hipparcos_with_progress, download_openngc, ensure_cache_dir,
+    get_cache_dir, sha256_file, DownloadError, DownloadErrorKind, RetryPolicy, OPENNGC_URL,
+};
+pub(crate) use downloader::{fetch_hipparcos, fetch_openngc};
 pub use gaia_downloader::{
-    download_gaia_catalog, download_gaia_file, ensure_gaia_cache_dir, get_gaia_cache_dir,
-    list_cached_gaia_files,
+    download_gaia_catalog, download_gaia_catalog_with_progress, download_gaia_file,
+    download_gaia_file_with_progress, download_gaia_file_with_sha256, ensure_gaia_cache_dir,
+    get_gaia_cache_dir, list_cached_gaia_files,
+};
+#[cfg(feature = "gaia-tap")]
+pub use gaia_tap::{GaiaTapClient, GAIA_TAP_URL};
+pub(crate) use iers::fetch_iers;
+pub use iers::{
+    download_iers, iers_timescale, parse_finals, parse_leap_seconds, EopRecord, LeapSecond,
+    FINALS_URL, LEAP_SECOND_URL,
+};
+pub use mpc::{
+    download_comet_elements, download_mpcorb, SmallBodyCatalog, COMET_ELEMENTS_URL, MPCORB_URL,
+};
+pub use progress::{ProgressEvent, ProgressReporter};
+pub use recorder::{
+    AccessLog, AccessRecorder, BundleManifest, EopEntry, PinnedFile, ReplayBundle, SegmentAccess,
 };
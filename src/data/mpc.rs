@@ -0,0 +1,212 @@
+//! Minor Planet Center orbit files
+//!
+//! `MPCORB.DAT` holds osculating elements for every numbered and
+//! multi-opposition minor planet, and `CometEls.txt` those of the comets.
+//! [`download_mpcorb`] and [`download_comet_elements`] fetch them into the
+//! cache directory, and a [`SmallBodyCatalog`] reads them and finds bodies
+//! by designation or name for [`crate::keplerlib`] to propagate:
+//!
+//! ```no_run
+//! use starfield::data::{download_comet_elements, SmallBodyCatalog};
+//!
+//! let comets = SmallBodyCatalog::from_comet_file(download_comet_elements()?)?;
+//! let orbit = comets.get("Halley").expect("in the file").orbit();
+//! let position = orbit.position_at(2_460_000.5);
+//! # Ok::<(), starfield::StarfieldError>(())
+//! ```
+//!
+//! `MPCORB.DAT` runs to over a million lines and a few hundred megabytes;
+//! header lines and malformed orbits are skipped.
+
+use std::path::{Path, PathBuf};
+
+use super::downloader::{
+    decompress_gzip, download_file, ensure_cache_dir, file_exists_and_not_empty,
+};
+use crate::keplerlib::elements::{parse_mpc_comets, parse_mpcorb, SmallBodyElements};
+use crate::keplerlib::KeplerOrbit;
+use crate::{Result, StarfieldError};
+
+/// Elements of all catalogued minor planets, gzipped
+pub const MPCORB_URL: &str = "https://minorplanetcenter.net/iau/MPCORB/MPCORB.DAT.gz";
+
+/// Elements of all catalogued comets
+pub const COMET_ELEMENTS_URL: &str = "https://minorplanetcenter.net/iau/MPCORB/CometEls.txt";
+
+/// Download `MPCORB.DAT` into the cache directory unless it is already
+/// there, and return its path
+pub fn download_mpcorb() -> Result<PathBuf> {
+    let cache_dir = ensure_cache_dir().map_err(StarfieldError::IoError)?;
+    let dat_path = cache_dir.join("MPCORB.DAT");
+    if !file_exists_and_not_empty(&dat_path) {
+        let gz_path = cache_dir.join("MPCORB.DAT.gz");
+        download_file(MPCORB_URL, &gz_path)?;
+        decompress_gzip(&gz_path, &dat_path)?;
+        std::fs::remove_file(&gz_path).map_err(StarfieldError::IoError)?;
+    }
+    Ok(dat_path)
+}
+
+/// Download `CometEls.txt` into the cache directory unless it is already
+/// there, and return its path
+///
+/// Comet elements change with every apparition; delete the cached file to
+/// fetch fresh ones.
+pub fn download_comet_elements() -> Result<PathBuf> {
+    let cache_dir = ensure_cache_dir().map_err(StarfieldError::IoError)?;
+    let path = cache_dir.join("CometEls.txt");
+    if !file_exists_and_not_empty(&path) {
+        download_file(COMET_ELEMENTS_URL, &path)?;
+    }
+    Ok(path)
+}
+
+/// Designation in a form that ignores case and spacing
+fn normalize(name: &str) -> String {
+    name.split_whitespace()
+        .collect::<String>()
+        .to_ascii_lowercase()
+}
+
+/// The pieces of a normalized designation a body can be looked up by:
+/// number and name of a minor planet, designation and name of a comet,
+/// and number and name of a periodic comet
+fn designation_parts(designation: &str) -> impl Iterator<Item = String> {
+    let designation = normalize(designation);
+    let parts: Vec<String> = designation
+        .split(['(', ')'])
+        .filter(|part| !part.is_empty())
+        .flat_map(|part| match part.split_once('/') {
+            Some((number, name)) if number.starts_with(|c: char| c.is_ascii_digit()) => {
+                vec![number.to_string(), name.to_string()]
+            }
+            _ => vec![part.to_string()],
+        })
+        .collect();
+    parts.into_iter()
+}
+
+/// Comets and minor planets with osculating elements
+#[derive(Debug, Clone, Default)]
+pub struct SmallBodyCatalog {
+    entries: Vec<SmallBodyElements>,
+}
+
+impl SmallBodyCatalog {
+    /// A catalog of already parsed elements
+    pub fn new(entries: Vec<SmallBodyElements>) -> Self {
+        Self { entries }
+    }
+
+    /// Parse the text of `MPCORB.DAT`
+    pub fn parse_mpcorb(text: &str) -> Result<Self> {
+        parse_mpcorb(text).map(Self::new)
+    }
+
+    /// Parse the text of `CometEls.txt`
+    pub fn parse_comets(text: &str) -> Result<Self> {
+        parse_mpc_comets(text).map(Self::new)
+    }
+
+    /// Load `MPCORB.DAT` from disk
+    pub fn from_mpcorb_file<P: AsRef<Path>>(path: P) -> Result<Self> {
+        Self::parse_mpcorb(&std::fs::read_to_string(path)?)
+    }
+
+    /// Load `CometEls.txt` from disk
+    pub fn from_comet_file<P: AsRef<Path>>(path: P) -> Result<Self> {
+        Self::parse_comets(&std::fs::read_to_string(path)?)
+    }
+
+    /// Add the entries of another catalog, to search comets and minor
+    /// planets together
+    pub fn extend(&mut self, other: SmallBodyCatalog) {
+        self.entries.extend(other.entries);
+    }
+
+    /// Look up a body, ignoring case and spacing
+    ///
+    /// Matches the full designation (`(1) Ceres`, `1P/Halley`,
+    /// `C/2020 F3 (NEOWISE)`), the name alone (`Ceres`, `Halley`,
+    /// `NEOWISE`) or the number or designation alone (`1`, `1P`,
+    /// `C/2020 F3`).
+    pub fn get(&self, name: &str) -> Option<&SmallBodyElements> {
+        let wanted = normalize(name);
+        if wanted.is_empty() {
+            return None;
+        }
+        let bare = wanted.trim_start_matches('(').trim_end_matches(')');
+        self.entries
+            .iter()
+            .find(|e| normalize(&e.designation) == wanted)
+            .or_else(|| {
+                self.entries
+                    .iter()
+                    .find(|e| designation_parts(&e.designation).any(|part| part == bare))
+            })
+    }
+
+    /// Two-body orbit of a body, by designation or name as for
+    /// [`SmallBodyCatalog::get`]
+    pub fn orbit(&self, name: &str) -> Option<KeplerOrbit> {
+        self.get(name).map(SmallBodyElements::orbit)
+    }
+
+    /// All entries
+    pub fn entries(&self) -> &[SmallBodyElements] {
+        &self.entries
+    }
+
+    /// Number of bodies
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Whether the catalog is empty
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A `CometEls.txt` line for a comet with perihelion in 2020 July
+    fn comet_line(designation: &str, name: &str, q: f64, e: f64) -> String {
+        format!(
+            "{:<12}  2020 07  3.6807 {:>9.6}  {:>8.6}   37.2786   61.0104  128.9375  20200628  12.0  3.2  {:<56} MPEC 2020-P07",
+            designation, q, e, name
+        )
+    }
+
+    #[test]
+    fn test_catalog_lookup() {
+        let text = [
+            comet_line("0001P", "1P/Halley", 0.574978, 0.967157),
+            comet_line("    CK20F030", "C/2020 F3 (NEOWISE)", 0.294_719, 0.999_176),
+            comet_line("0002P", "2P/Encke", 0.339_6, 0.847_8),
+        ]
+        .join("\n");
+        let mut catalog = SmallBodyCatalog::parse_comets(&text).unwrap();
+        assert_eq!(catalog.len(), 3);
+
+        assert_eq!(catalog.get("1P/Halley").unwrap().designation, "1P/Halley");
+        assert_eq!(catalog.get("halley").unwrap().designation, "1P/Halley");
+        assert_eq!(catalog.get("2p").unwrap().designation, "2P/Encke");
+        let neowise = catalog.get("NEOWISE").unwrap().clone();
+        assert_eq!(catalog.get("c/2020 f3").unwrap(), &neowise);
+        assert!(catalog.get("C").is_none());
+        assert!(catalog.get("").is_none());
+        assert!(catalog.orbit("Encke").is_some());
+
+        catalog.extend(SmallBodyCatalog::new(vec![SmallBodyElements {
+            designation: "(1) Ceres".to_string(),
+            ..neowise
+        }]));
+        assert_eq!(catalog.get("(1)").unwrap().designation, "(1) Ceres");
+        assert_eq!(catalog.get("1").unwrap().designation, "(1) Ceres");
+        assert_eq!(catalog.get("CERES").unwrap().designation, "(1) Ceres");
+        assert_eq!(catalog.entries().len(), 4);
+    }
+}
@@ -0,0 +1,196 @@
+//! Progress reporting for downloads and catalog loads
+//!
+//! The library writes nothing to stdout or stderr itself: diagnostics go
+//! through the [`log`] facade, and long-running work reports its progress as
+//! [`ProgressEvent`]s to a [`ProgressReporter`] handed to the
+//! [`crate::Loader`] or to the `*_with_progress` download functions.
+//!
+//! ```
+//! use starfield::data::ProgressEvent;
+//! use starfield::Loader;
+//!
+//! let loader = Loader::new().with_progress(|event| {
+//!     if let ProgressEvent::CatalogLoaded { catalog, stars } = event {
+//!         eprintln!("{} ready with {} stars", catalog, stars);
+//!     }
+//! });
+//! # let _ = loader;
+//! ```
+
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+
+/// A step of a download or catalog load
+#[derive(Debug, Clone, PartialEq)]
+pub enum ProgressEvent {
+    /// A download began; `total_bytes` is the size the server announced
+    DownloadStarted {
+        url: String,
+        total_bytes: Option<u64>,
+    },
+    /// Another megabyte of a download arrived
+    Downloaded {
+        url: String,
+        bytes: u64,
+        total_bytes: Option<u64>,
+    },
+    /// A download completed
+    DownloadFinished { url: String, bytes: u64 },
+    /// A catalog file is about to be parsed, the `index`th (from 0) of `total`
+    FileStarted {
+        path: PathBuf,
+        index: usize,
+        total: usize,
+    },
+    /// A catalog file was parsed
+    FileLoaded { path: PathBuf, stars: usize },
+    /// A whole catalog is ready
+    CatalogLoaded { catalog: &'static str, stars: usize },
+}
+
+type Callback = Box<dyn FnMut(ProgressEvent) + Send>;
+
+/// Shared, thread-safe progress callback
+///
+/// Clones call the same closure, which sees events one at a time even when
+/// catalog files are parsed in parallel.
+#[derive(Clone)]
+pub struct ProgressReporter {
+    callback: Arc<Mutex<Callback>>,
+}
+
+impl ProgressReporter {
+    /// Report progress to `callback`
+    pub fn new<F: FnMut(ProgressEvent) + Send + 'static>(callback: F) -> Self {
+        Self {
+            callback: Arc::new(Mutex::new(Box::new(callback))),
+        }
+    }
+
+    /// Pass an event to the callback
+    pub fn report(&self, event: ProgressEvent) {
+        let mut callback = self.callback.lock().unwrap_or_else(|e| e.into_inner());
+        callback(event);
+    }
+}
+
+impl std::fmt::Debug for ProgressReporter {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ProgressReporter").finish_non_exhaustive()
+    }
+}
+
+/// Report an event if there is anyone to report it to
+pub(crate) fn report(progress: Option<&ProgressReporter>, event: impl FnOnce() -> ProgressEvent) {
+    if let Some(progress) = progress {
+        progress.report(event());
+    }
+}
+
+/// Reports a download's progress, one [`ProgressEvent::Downloaded`] per
+/// megabyte received
+pub(crate) struct DownloadProgress<'a> {
+    progress: Option<&'a ProgressReporter>,
+    url: &'a str,
+    total_bytes: Option<u64>,
+    bytes: u64,
+}
+
+impl<'a> DownloadProgress<'a> {
+    const STEP_BYTES: u64 = 1024 * 1024;
+
+    /// Announce the start of a download
+    pub fn start(
+        progress: Option<&'a ProgressReporter>,
+        url: &'a str,
+        total_bytes: Option<u64>,
+    ) -> Self {
+        report(progress, || ProgressEvent::DownloadStarted {
+            url: url.to_string(),
+            total_bytes,
+        });
+        Self {
+            progress,
+            url,
+            total_bytes,
+            bytes: 0,
+        }
+    }
+
+    /// Count `n` more bytes received
+    pub fn advance(&mut self, n: usize) {
+        let before = self.bytes / Self::STEP_BYTES;
+        self.bytes += n as u64;
+        if self.bytes / Self::STEP_BYTES > before {
+            report(self.progress, || ProgressEvent::Downloaded {
+                url: self.url.to_string(),
+                bytes: self.bytes,
+                total_bytes: self.total_bytes,
+            });
+        }
+    }
+
+    /// Announce the end of the download, returning its size
+    pub fn finish(self) -> u64 {
+        report(self.progress, || ProgressEvent::DownloadFinished {
+            url: self.url.to_string(),
+            bytes: self.bytes,
+        });
+        self.bytes
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_download_progress_reports_each_megabyte() {
+        let events = Arc::new(Mutex::new(Vec::new()));
+        let sink = events.clone();
+        let reporter = ProgressReporter::new(move |event| sink.lock().unwrap().push(event));
+
+        let url = "https://example.org/file";
+        let mut download = DownloadProgress::start(Some(&reporter), url, Some(2_500_000));
+        for _ in 0..300 {
+            download.advance(8192);
+        }
+        assert_eq!(download.finish(), 300 * 8192);
+
+        let events = events.lock().unwrap();
+        assert_eq!(events.len(), 4);
+        assert!(matches!(
+            events[0],
+            ProgressEvent::DownloadStarted {
+                total_bytes: Some(2_500_000),
+                ..
+            }
+        ));
+        assert!(matches!(
+            events[1],
+            ProgressEvent::Downloaded {
+                bytes: 1_048_576,
+                ..
+            }
+        ));
+        assert!(matches!(
+            events[2],
+            ProgressEvent::Downloaded {
+                bytes: 2_097_152,
+                ..
+            }
+        ));
+        assert_eq!(
+            events[3],
+            ProgressEvent::DownloadFinished {
+                url: url.to_string(),
+                bytes: 2_457_600
+            }
+        );
+
+        // Without a reporter nothing is built at all
+        let mut silent = DownloadProgress::start(None, url, None);
+        silent.advance(5 * 1024 * 1024);
+        assert_eq!(silent.finish(), 5 * 1024 * 1024);
+    }
+}
@@ -0,0 +1,396 @@
+//! Recording and replaying data accesses for reproducible runs
+//!
+//! Results depend on more than code: catalog files are refreshed, kernels are
+//! superseded and Earth orientation tables grow. An [`AccessRecorder`] handed
+//! to the [`crate::Loader`] (and from there to timescales and kernels) logs
+//! every catalog file, kernel segment and Delta T table row a computation
+//! touched, along with the leap-second table in effect. [`AccessRecorder::export_bundle`] pins those inputs into a
+//! directory, and a [`ReplayBundle`] opened from it serves them back so the
+//! computation can be repeated byte-for-byte after the cache has moved on.
+
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+use super::gaia_downloader::calculate_md5;
+use super::iers::LeapSecond;
+use crate::time::Timescale;
+use crate::{Result, StarfieldError};
+
+/// Name of the manifest file inside a bundle directory
+pub const MANIFEST_FILE: &str = "manifest.json";
+
+/// A kernel segment that was evaluated
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SegmentAccess {
+    /// Kernel file the segment came from, if it was opened from disk
+    pub kernel: Option<PathBuf>,
+    /// NAIF ID of the segment center
+    pub center: i32,
+    /// NAIF ID of the segment target
+    pub target: i32,
+    /// Start of the segment's coverage (TDB Julian date)
+    pub start_jd: f64,
+    /// End of the segment's coverage (TDB Julian date)
+    pub end_jd: f64,
+}
+
+/// A row of an Earth orientation table that fed an interpolation
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct EopEntry {
+    /// TT Julian date of the row
+    pub tt: f64,
+    /// Delta T (TT - UT1) in seconds
+    pub delta_t: f64,
+}
+
+/// Everything recorded so far
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct AccessLog {
+    /// Catalog files read, labelled with the catalog they belong to
+    pub catalog_files: Vec<(String, PathBuf)>,
+    /// Kernel segments evaluated
+    pub kernel_segments: Vec<SegmentAccess>,
+    /// Delta T table rows used, sorted by date
+    pub eop_entries: Vec<EopEntry>,
+    /// Leap-second table of the last timescale the recorder was attached to
+    pub leap_seconds: Vec<LeapSecond>,
+}
+
+/// Shared, thread-safe log of data accesses
+///
+/// Clones share the same log, so one recorder can be handed to a loader, its
+/// timescale and any kernels it opens.
+#[derive(Debug, Clone, Default)]
+pub struct AccessRecorder {
+    log: Arc<Mutex<AccessLog>>,
+}
+
+impl AccessRecorder {
+    /// Create an empty recorder
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn with_log<R>(&self, f: impl FnOnce(&mut AccessLog) -> R) -> R {
+        let mut log = self.log.lock().unwrap_or_else(|e| e.into_inner());
+        f(&mut log)
+    }
+
+    /// Record that a catalog file was read
+    pub fn record_catalog_file<P: AsRef<Path>>(&self, catalog: &str, path: P) {
+        let entry = (catalog.to_string(), path.as_ref().to_path_buf());
+        self.with_log(|log| {
+            if !log.catalog_files.contains(&entry) {
+                log.catalog_files.push(entry);
+            }
+        });
+    }
+
+    /// Record that a kernel segment was evaluated
+    pub fn record_segment(&self, access: SegmentAccess) {
+        self.with_log(|log| {
+            if !log.kernel_segments.contains(&access) {
+                log.kernel_segments.push(access);
+            }
+        });
+    }
+
+    /// Record that a Delta T table row was used
+    pub fn record_eop(&self, entry: EopEntry) {
+        self.with_log(|log| {
+            if let Err(i) = log
+                .eop_entries
+                .binary_search_by(|e| e.tt.total_cmp(&entry.tt))
+            {
+                log.eop_entries.insert(i, entry);
+            }
+        });
+    }
+
+    /// Record the leap-second table in use, replacing any earlier one
+    pub fn record_leap_seconds(&self, table: Vec<LeapSecond>) {
+        self.with_log(|log| log.leap_seconds = table);
+    }
+
+    /// Snapshot of the accesses recorded so far
+    pub fn snapshot(&self) -> AccessLog {
+        self.with_log(|log| log.clone())
+    }
+
+    /// Forget everything recorded so far
+    pub fn clear(&self) {
+        self.with_log(|log| *log = AccessLog::default());
+    }
+
+    /// Copy every recorded input into `dir` and write its manifest
+    ///
+    /// Catalog and kernel files are copied whole, together with their MD5
+    /// checksums, so a replay fails loudly rather than silently reading a
+    /// different file.
+    pub fn export_bundle<P: AsRef<Path>>(&self, dir: P) -> Result<BundleManifest> {
+        let dir = dir.as_ref();
+        fs::create_dir_all(dir)?;
+        let log = self.snapshot();
+
+        let mut manifest = BundleManifest {
+            catalog_files: Vec::new(),
+            kernels: Vec::new(),
+            kernel_segments: log.kernel_segments.clone(),
+            eop_entries: log.eop_entries.clone(),
+            leap_seconds: log.leap_seconds.clone(),
+        };
+
+        for (catalog, path) in &log.catalog_files {
+            let pinned = pin_file(dir, catalog, path, manifest.catalog_files.len())?;
+            manifest.catalog_files.push(pinned);
+        }
+
+        let mut kernels: Vec<&PathBuf> = Vec::new();
+        for kernel in log.kernel_segments.iter().filter_map(|s| s.kernel.as_ref()) {
+            if !kernels.contains(&kernel) {
+                kernels.push(kernel);
+            }
+        }
+        for kernel in kernels {
+            let pinned = pin_file(dir, "spk", kernel, manifest.kernels.len())?;
+            manifest.kernels.push(pinned);
+        }
+
+        let json = serde_json::to_string_pretty(&manifest)
+            .map_err(|e| StarfieldError::DataError(format!("Failed to write manifest: {}", e)))?;
+        fs::write(dir.join(MANIFEST_FILE), json)?;
+
+        Ok(manifest)
+    }
+}
+
+/// A file copied into a bundle
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PinnedFile {
+    /// Catalog name, or `spk` for kernels
+    pub label: String,
+    /// Where the file was read from during recording
+    pub original: PathBuf,
+    /// Path of the copy relative to the bundle directory
+    pub file: PathBuf,
+    /// MD5 checksum of the copy
+    pub md5: String,
+    /// Size of the copy in bytes
+    pub size: u64,
+}
+
+/// Contents of a bundle's `manifest.json`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BundleManifest {
+    pub catalog_files: Vec<PinnedFile>,
+    pub kernels: Vec<PinnedFile>,
+    pub kernel_segments: Vec<SegmentAccess>,
+    pub eop_entries: Vec<EopEntry>,
+    /// Leap-second table in effect while recording; bundles written before
+    /// it was pinned replay with the built-in table
+    #[serde(default)]
+    pub leap_seconds: Vec<LeapSecond>,
+}
+
+fn pin_file(dir: &Path, label: &str, original: &Path, index: usize) -> Result<PinnedFile> {
+    let name = original
+        .file_name()
+        .map(|n| n.to_string_lossy().into_owned())
+        .unwrap_or_else(|| "data".to_string());
+    // The index keeps same-named files from different directories apart
+    let file = PathBuf::from(label).join(format!("{:03}_{}", index, name));
+
+    let target = dir.join(&file);
+    if let Some(parent) = target.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    let size = fs::copy(original, &target)?;
+
+    Ok(PinnedFile {
+        label: label.to_string(),
+        original: original.to_path_buf(),
+        file,
+        md5: calculate_md5(&target)?,
+        size,
+    })
+}
+
+/// A pinned bundle of inputs, served back in place of live data
+#[derive(Debug, Clone)]
+pub struct ReplayBundle {
+    dir: PathBuf,
+    manifest: BundleManifest,
+}
+
+impl ReplayBundle {
+    /// Open a bundle directory, verifying every pinned file's checksum
+    pub fn open<P: AsRef<Path>>(dir: P) -> Result<Self> {
+        let dir = dir.as_ref().to_path_buf();
+        let json = fs::read_to_string(dir.join(MANIFEST_FILE))?;
+        let manifest: BundleManifest = serde_json::from_str(&json)
+            .map_err(|e| StarfieldError::DataError(format!("Invalid bundle manifest: {}", e)))?;
+
+        for pinned in manifest.catalog_files.iter().chain(&manifest.kernels) {
+            let md5 = calculate_md5(dir.join(&pinned.file))?;
+            if md5 != pinned.md5 {
+                return Err(StarfieldError::DataError(format!(
+                    "Bundle file {} does not match its checksum",
+                    pinned.file.display()
+                )));
+            }
+        }
+
+        Ok(Self { dir, manifest })
+    }
+
+    /// The bundle's manifest
+    pub fn manifest(&self) -> &BundleManifest {
+        &self.manifest
+    }
+
+    /// Pinned copies of the files recorded for a catalog, in recording order
+    pub fn catalog_files(&self, catalog: &str) -> Vec<PathBuf> {
+        self.manifest
+            .catalog_files
+            .iter()
+            .filter(|p| p.label == catalog)
+            .map(|p| self.dir.join(&p.file))
+            .collect()
+    }
+
+    /// Pinned copy of a catalog file or kernel recorded from `original`
+    ///
+    /// Paths of the pinned copies themselves, as returned by
+    /// [`ReplayBundle::catalog_files`], resolve to themselves.
+    pub fn pinned_copy<P: AsRef<Path>>(&self, original: P) -> Option<PathBuf> {
+        let original = original.as_ref();
+        self.manifest
+            .catalog_files
+            .iter()
+            .chain(&self.manifest.kernels)
+            .map(|p| (p, self.dir.join(&p.file)))
+            .find(|(p, copy)| p.original == original || copy == original)
+            .map(|(_, copy)| copy)
+    }
+
+    /// A timescale with the recorded leap seconds whose Delta T table holds
+    /// exactly the recorded rows
+    ///
+    /// Delta T is interpolated linearly between neighbouring rows, so every
+    /// value computed during recording is reproduced exactly.
+    pub fn timescale(&self) -> Timescale {
+        let mut ts = Timescale::default();
+        if !self.manifest.leap_seconds.is_empty() {
+            let (dates, offsets) = self
+                .manifest
+                .leap_seconds
+                .iter()
+                .map(|l| (l.jd_utc, l.tai_minus_utc))
+                .unzip();
+            ts = ts.with_leap_seconds(dates, offsets);
+        }
+        if self.manifest.eop_entries.is_empty() {
+            return ts;
+        }
+        let (tt, delta_t) = self
+            .manifest
+            .eop_entries
+            .iter()
+            .map(|e| (e.tt, e.delta_t))
+            .unzip();
+        ts.with_delta_t_table(tt, delta_t)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_recorder_deduplicates_and_sorts() {
+        let recorder = AccessRecorder::new();
+        let shared = recorder.clone();
+        shared.record_catalog_file("gaia", "/a.csv.gz");
+        recorder.record_catalog_file("gaia", "/a.csv.gz");
+        shared.record_eop(EopEntry {
+            tt: 2451546.5,
+            delta_t: 63.9,
+        });
+        recorder.record_eop(EopEntry {
+            tt: 2451545.5,
+            delta_t: 63.8,
+        });
+        recorder.record_eop(EopEntry {
+            tt: 2451546.5,
+            delta_t: 63.9,
+        });
+
+        let log = recorder.snapshot();
+        assert_eq!(log.catalog_files.len(), 1);
+        assert_eq!(log.eop_entries.len(), 2);
+        assert!(log.eop_entries[0].tt < log.eop_entries[1].tt);
+
+        recorder.clear();
+        assert_eq!(shared.snapshot(), AccessLog::default());
+    }
+
+    #[test]
+    fn test_bundle_round_trip() {
+        let source = tempdir().unwrap();
+        let catalog = source.path().join("tile.csv");
+        fs::write(&catalog, "source_id,ra,dec\n1,10.0,20.0\n").unwrap();
+
+        let recorder = AccessRecorder::new();
+        recorder.record_catalog_file("gaia", &catalog);
+        recorder.record_eop(EopEntry {
+            tt: 2451545.0,
+            delta_t: 63.83,
+        });
+        recorder.record_eop(EopEntry {
+            tt: 2451910.0,
+            delta_t: 64.09,
+        });
+
+        let bundle_dir = tempdir().unwrap();
+        recorder.export_bundle(bundle_dir.path()).unwrap();
+
+        // Later changes to the cache do not leak into the replay
+        fs::write(&catalog, "changed").unwrap();
+
+        let bundle = ReplayBundle::open(bundle_dir.path()).unwrap();
+        let files = bundle.catalog_files("gaia");
+        assert_eq!(files.len(), 1);
+        assert_eq!(
+            fs::read_to_string(&files[0]).unwrap(),
+            "source_id,ra,dec\n1,10.0,20.0\n"
+        );
+        assert!(bundle.catalog_files("hipparcos").is_empty());
+        assert_eq!(bundle.pinned_copy(&catalog), Some(files[0].clone()));
+
+        let ts = bundle.timescale();
+        assert_eq!(ts.delta_t(2451545.0), 63.83);
+        assert!((ts.delta_t(2451727.5) - 63.96).abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_tampered_bundle_is_rejected() {
+        let source = tempdir().unwrap();
+        let catalog = source.path().join("hip_main.dat");
+        fs::write(&catalog, "H|1|").unwrap();
+
+        let recorder = AccessRecorder::new();
+        recorder.record_catalog_file("hipparcos", &catalog);
+        let bundle_dir = tempdir().unwrap();
+        let manifest = recorder.export_bundle(bundle_dir.path()).unwrap();
+
+        fs::write(
+            bundle_dir.path().join(&manifest.catalog_files[0].file),
+            "H|2|",
+        )
+        .unwrap();
+        assert!(ReplayBundle::open(bundle_dir.path()).is_err());
+    }
+}
@@ -0,0 +1,123 @@
+//! Zenith distance and airmass for photometric reduction
+//!
+//! Extinction scales with the airmass, the path length through the
+//! atmosphere relative to the path at the zenith. [`airmass`] offers three
+//! standard formulas of the geometric (refraction-free) zenith distance:
+//!
+//! * [`AirmassModel::PlaneParallel`] - sec z, fine to z ≈ 60° and infinite
+//!   at the horizon
+//! * [`AirmassModel::KastenYoung`] - Kasten & Young (1989), fitted to a
+//!   model atmosphere, 37.9 at the horizon
+//! * [`AirmassModel::Pickering`] - Pickering (2002), built on the apparent
+//!   altitude, 38.7 at the horizon
+//!
+//! ```
+//! use starfield::earthlib::{airmass, zenith_distance, AirmassModel};
+//!
+//! // A star at declination +20 seen from latitude 40 N, two hours west
+//! let z = zenith_distance(40.0, 20.0, 2.0);
+//! let x = airmass(z, AirmassModel::KastenYoung).unwrap();
+//! assert!((x - 1.185).abs() < 0.001);
+//! ```
+
+use crate::constants::DEG2RAD;
+
+/// Formula relating airmass to zenith distance
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum AirmassModel {
+    /// sec z, for a flat atmosphere
+    PlaneParallel,
+    /// Kasten & Young (1989), Applied Optics 28, 4735
+    #[default]
+    KastenYoung,
+    /// Pickering (2002), DIO 12, 3
+    Pickering,
+}
+
+/// Zenith distance in degrees, without refraction, of a declination seen
+/// from a latitude at an hour angle in hours
+///
+/// Solves the astronomical (parallactic) triangle of pole, zenith and
+/// object.
+pub fn zenith_distance(latitude_deg: f64, dec_deg: f64, hour_angle_hours: f64) -> f64 {
+    let (sin_lat, cos_lat) = (latitude_deg * DEG2RAD).sin_cos();
+    let (sin_dec, cos_dec) = (dec_deg * DEG2RAD).sin_cos();
+    let cos_z = sin_lat * sin_dec + cos_lat * cos_dec * (hour_angle_hours * 15.0 * DEG2RAD).cos();
+    cos_z.clamp(-1.0, 1.0).acos() / DEG2RAD
+}
+
+/// Airmass at a refraction-free zenith distance in degrees
+///
+/// Returns `None` below the horizon, and for the plane-parallel model at
+/// the horizon itself.
+pub fn airmass(zenith_distance_deg: f64, model: AirmassModel) -> Option<f64> {
+    let z = zenith_distance_deg;
+    if !(0.0..=90.0).contains(&z) {
+        return None;
+    }
+    match model {
+        AirmassModel::PlaneParallel => (z < 90.0).then(|| 1.0 / (z * DEG2RAD).cos()),
+        AirmassModel::KastenYoung => {
+            Some(1.0 / ((z * DEG2RAD).cos() + 0.505_72 * (96.079_95 - z).powf(-1.636_4)))
+        }
+        AirmassModel::Pickering => {
+            let h = 90.0 - z;
+            Some(1.0 / ((h + 244.0 / (165.0 + 47.0 * h.powf(1.1))) * DEG2RAD).sin())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use approx::assert_relative_eq;
+
+    #[test]
+    fn test_airmass_models() {
+        for model in [
+            AirmassModel::PlaneParallel,
+            AirmassModel::KastenYoung,
+            AirmassModel::Pickering,
+        ] {
+            assert_relative_eq!(airmass(0.0, model).unwrap(), 1.0, epsilon = 1e-3);
+            assert!(airmass(90.5, model).is_none());
+            assert!(airmass(-1.0, model).is_none());
+        }
+
+        // The models agree high up and part near the horizon
+        let sec = airmass(60.0, AirmassModel::PlaneParallel).unwrap();
+        assert_relative_eq!(sec, 2.0, epsilon = 1e-12);
+        assert_relative_eq!(
+            airmass(60.0, AirmassModel::KastenYoung).unwrap(),
+            1.994,
+            epsilon = 1e-3
+        );
+        assert_relative_eq!(
+            airmass(60.0, AirmassModel::Pickering).unwrap(),
+            1.993,
+            epsilon = 2e-3
+        );
+        assert!(airmass(90.0, AirmassModel::PlaneParallel).is_none());
+        assert_relative_eq!(
+            airmass(90.0, AirmassModel::KastenYoung).unwrap(),
+            37.92,
+            epsilon = 0.01
+        );
+        assert_relative_eq!(
+            airmass(90.0, AirmassModel::Pickering).unwrap(),
+            38.75,
+            epsilon = 0.01
+        );
+    }
+
+    #[test]
+    fn test_zenith_distance() {
+        // On the meridian the zenith distance is |latitude - declination|
+        assert_relative_eq!(zenith_distance(40.0, 20.0, 0.0), 20.0, epsilon = 1e-12);
+        assert_relative_eq!(zenith_distance(-30.0, 20.0, 0.0), 50.0, epsilon = 1e-12);
+        // and the celestial pole stands at the colatitude all night
+        assert_relative_eq!(zenith_distance(52.0, 90.0, 7.3), 38.0, epsilon = 1e-9);
+        // A star on the equator sets at hour angle 6h seen from anywhere
+        assert_relative_eq!(zenith_distance(35.0, 0.0, 6.0), 90.0, epsilon = 1e-9);
+    }
+}
@@ -1 +1,478 @@
-//! earthlib module\n\n// Placeholder for earthlib implementations
+//! Earth rotation and terrestrial positions
+//!
+//! Sidereal time follows the IAU 2006 expression built on the Earth Rotation
+//! Angle, as in skyfield's `earthlib`. Terrestrial positions use the IERS 2010
+//! reference ellipsoid.
+//!
+//! [`itrs_to_gcrs`] gives the full equinox-based rotation from Earth-fixed
+//! (ITRS) to celestial (GCRS) axes: polar motion, apparent sidereal time,
+//! nutation and precession. The frame bias between J2000 and the GCRS,
+//! 23 milliarcseconds, is neglected, as it is elsewhere in the crate.
+//!
+//! ```
+//! use starfield::earthlib::{itrs_to_gcrs, PolarMotion, itrs_to_gcrs_with};
+//! use starfield::accuracy::NutationModel;
+//! use starfield::time::Timescale;
+//!
+//! let ts = Timescale::default();
+//! let t = ts.utc((2024, 3, 20, 3, 6, 0.0));
+//! let rotation = itrs_to_gcrs(&t);
+//!
+//! // With polar motion from the IERS bulletins
+//! let pole = PolarMotion::new(0.0766, 0.2824);
+//! let precise = itrs_to_gcrs_with(&t, NutationModel::Iau2000B, Some(pole));
+//! assert!((rotation - precise).abs().max() < 1e-5);
+//! ```
+//!
+//! [`transform_state`] carries velocities across as well, adding the
+//! ω × r of the Earth's rotation so that a station at rest on the ground
+//! moves at some 465 m/s in the GCRS:
+//!
+//! ```
+//! use starfield::earthlib::{terra, transform_state, StateDirection};
+//! use starfield::constants::AU_M;
+//! use starfield::time::Timescale;
+//! use nalgebra::Vector3;
+//!
+//! let ts = Timescale::default();
+//! let t = ts.utc((2024, 3, 20, 3, 6, 0.0));
+//! let station = terra(0.0, 0.0, 0.0);
+//! let (_, velocity) = transform_state(&station, &Vector3::zeros(), &t, StateDirection::ItrsToGcrs);
+//! let speed_m_s = velocity.norm() * AU_M / 86400.0;
+//! assert!((speed_m_s - 465.1).abs() < 0.1);
+//! ```
+
+pub mod airmass;
+
+pub use airmass::{airmass, zenith_distance, AirmassModel};
+
+use crate::accuracy::NutationModel;
+use crate::constants::{
+    ASEC2RAD, AU_M, DAY_S, DEG2RAD, EARTH_ANGVEL, EARTH_RADIUS, IERS_2010_INVERSE_EARTH_FLATTENING,
+    J2000, TAU,
+};
+use crate::data::EopRecord;
+use crate::framelib::{rot_x, rot_y, rot_z};
+use crate::nutationlib::{equation_of_the_equinoxes, precession_nutation_matrix};
+use crate::precessionlib::compute_precession;
+use crate::time::{Time, TimeDelta};
+use nalgebra::{Matrix3, Vector3};
+
+/// Earth Rotation Angle at a UT1 Julian date, as a fraction of a revolution in [0, 1)
+pub fn earth_rotation_angle(jd_ut1: f64) -> f64 {
+    let th = 0.779_057_273_264_0 + 0.002_737_811_911_354_48 * (jd_ut1 - J2000);
+    (th + jd_ut1.rem_euclid(1.0)).rem_euclid(1.0)
+}
+
+/// Greenwich Mean Sidereal Time in hours (IAU 2006)
+pub fn sidereal_time(t: &Time) -> f64 {
+    let theta = earth_rotation_angle(t.ut1());
+    let c = (t.tdb() - J2000) / 36525.0;
+
+    // Accumulated precession in right ascension, arcseconds
+    let st = 0.014_506
+        + ((((-0.000_000_036_8 * c - 0.000_029_956) * c - 0.000_000_44) * c + 1.391_581_7) * c
+            + 4_612.156_534)
+            * c;
+
+    (st / 54_000.0 + theta * 24.0).rem_euclid(24.0)
+}
+
+/// Greenwich Mean Sidereal Time in hours (IAU 2006); the same as
+/// [`sidereal_time`]
+pub fn gmst(t: &Time) -> f64 {
+    sidereal_time(t)
+}
+
+/// Greenwich Apparent Sidereal Time in hours: mean sidereal time plus the
+/// equation of the equinoxes
+pub fn gast(t: &Time, model: NutationModel) -> f64 {
+    let eqeq_hours = equation_of_the_equinoxes(t.tt(), model) / TAU * 24.0;
+    (sidereal_time(t) + eqeq_hours).rem_euclid(24.0)
+}
+
+/// Coordinates of the Celestial Intermediate Pole in the ITRS, as published
+/// by the IERS
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PolarMotion {
+    /// Displacement towards the Greenwich meridian, arcseconds
+    pub x_arcsec: f64,
+    /// Displacement towards 90 degrees west, arcseconds
+    pub y_arcsec: f64,
+}
+
+impl PolarMotion {
+    /// Pole coordinates in arcseconds
+    pub fn new(x_arcsec: f64, y_arcsec: f64) -> Self {
+        Self { x_arcsec, y_arcsec }
+    }
+
+    /// Pole coordinates at `t`, interpolated linearly between the daily
+    /// values of a `finals2000A.all` table
+    ///
+    /// Returns `None` when `t` falls outside the table.
+    pub fn from_eop(records: &[EopRecord], t: &Time) -> Option<Self> {
+        let mjd_utc = t.tai() - t.leap_seconds() / DAY_S - 2_400_000.5;
+        let after = records.iter().position(|r| r.mjd > mjd_utc)?;
+        let before = records.get(after.checked_sub(1)?)?;
+        let after = &records[after];
+
+        let f = (mjd_utc - before.mjd) / (after.mjd - before.mjd);
+        let lerp = |a: f64, b: f64| a + f * (b - a);
+        Some(Self::new(
+            lerp(before.pm_x_arcsec, after.pm_x_arcsec),
+            lerp(before.pm_y_arcsec, after.pm_y_arcsec),
+        ))
+    }
+
+    /// Rotation from the ITRS to the Terrestrial Intermediate Reference
+    /// System, W = R3(-s') R2(x) R1(y) of the IERS Conventions
+    ///
+    /// s', the slow drift of the Terrestrial Intermediate Origin, is
+    /// -47 microarcseconds per century.
+    pub fn matrix(&self, jd_tt: f64) -> Matrix3<f64> {
+        let s_prime = -47e-6 * ASEC2RAD * (jd_tt - J2000) / 36525.0;
+        rot_z(s_prime) * rot_y(-self.x_arcsec * ASEC2RAD) * rot_x(-self.y_arcsec * ASEC2RAD)
+    }
+}
+
+/// Rotation from ITRS to GCRS axes, with IAU 2000B nutation and without
+/// polar motion
+///
+/// Multiply an Earth-fixed vector by the result to obtain celestial
+/// coordinates; the transpose performs the inverse rotation.
+pub fn itrs_to_gcrs(t: &Time) -> Matrix3<f64> {
+    itrs_to_gcrs_with(t, NutationModel::Iau2000B, None)
+}
+
+/// Rotation from ITRS to GCRS axes with a chosen nutation model and
+/// optional polar motion
+///
+/// Polar motion moves the pole by up to half an arcsecond, some 15 metres
+/// on the ground.
+pub fn itrs_to_gcrs_with(
+    t: &Time,
+    model: NutationModel,
+    polar_motion: Option<PolarMotion>,
+) -> Matrix3<f64> {
+    let jd_tt = t.tt();
+    let earth_rotation = rot_z(gast(t, model) / 24.0 * TAU);
+    let rotation = precession_nutation_matrix(jd_tt, model).transpose() * earth_rotation;
+    match polar_motion {
+        Some(pole) => rotation * pole.matrix(jd_tt),
+        None => rotation,
+    }
+}
+
+/// Half-interval for differencing interpolated polar motion, days (one hour)
+const POLE_RATE_HALF_STEP: f64 = 1.0 / 24.0;
+
+/// Which way [`transform_state`] converts
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StateDirection {
+    /// Earth-fixed to celestial
+    ItrsToGcrs,
+    /// Celestial to Earth-fixed
+    GcrsToItrs,
+}
+
+/// Convert a position (AU) and velocity (AU/day) between the ITRS and the
+/// GCRS, with IAU 2000B nutation and without polar motion
+///
+/// See [`transform_state_with`].
+pub fn transform_state(
+    position: &Vector3<f64>,
+    velocity: &Vector3<f64>,
+    t: &Time,
+    direction: StateDirection,
+) -> (Vector3<f64>, Vector3<f64>) {
+    transform_state_with(
+        position,
+        velocity,
+        t,
+        direction,
+        NutationModel::Iau2000B,
+        None,
+    )
+}
+
+/// Convert a position (AU) and velocity (AU/day) between the ITRS and the
+/// GCRS, with polar motion interpolated from IERS records when given
+///
+/// The rotation R of [`itrs_to_gcrs_with`] is time-dependent, so a velocity
+/// transforms as `R v + Ṙ r`. Ṙ includes the Earth's rotation, the ω × r
+/// term, and the rate of polar motion, found by differencing the pole over
+/// two hours; the drift of precession and nutation, a part in 10^7 of the
+/// rotation term, is neglected. Outside the records' span polar motion is
+/// left out altogether.
+pub fn transform_state_with(
+    position: &Vector3<f64>,
+    velocity: &Vector3<f64>,
+    t: &Time,
+    direction: StateDirection,
+    model: NutationModel,
+    eop: Option<&[EopRecord]>,
+) -> (Vector3<f64>, Vector3<f64>) {
+    let jd_tt = t.tt();
+    let celestial =
+        precession_nutation_matrix(jd_tt, model).transpose() * rot_z(gast(t, model) / 24.0 * TAU);
+    let spin = Matrix3::new(0.0, -1.0, 0.0, 1.0, 0.0, 0.0, 0.0, 0.0, 0.0) * EARTH_ANGVEL * DAY_S;
+
+    let pole_at = |t: &Time| eop.and_then(|records| PolarMotion::from_eop(records, t));
+    let (wobble, wobble_rate) = match pole_at(t) {
+        Some(pole) => {
+            let rate = match (
+                pole_at(&(t - TimeDelta::days(POLE_RATE_HALF_STEP))),
+                pole_at(&(t + TimeDelta::days(POLE_RATE_HALF_STEP))),
+            ) {
+                (Some(before), Some(after)) => {
+                    (after.matrix(jd_tt) - before.matrix(jd_tt)) / (2.0 * POLE_RATE_HALF_STEP)
+                }
+                _ => Matrix3::zeros(),
+            };
+            (pole.matrix(jd_tt), rate)
+        }
+        None => (Matrix3::identity(), Matrix3::zeros()),
+    };
+
+    let rotation = celestial * wobble;
+    let rotation_rate = celestial * (spin * wobble + wobble_rate);
+    match direction {
+        StateDirection::ItrsToGcrs => (
+            rotation * position,
+            rotation * velocity + rotation_rate * position,
+        ),
+        StateDirection::GcrsToItrs => {
+            let inverse = rotation.transpose();
+            (
+                inverse * position,
+                inverse * (velocity - rotation_rate * (inverse * position)),
+            )
+        }
+    }
+}
+
+/// Rotation from Earth-fixed axes to J2000 equatorial axes
+///
+/// Combines Greenwich Mean Sidereal Time with the inverse of precession.
+/// Nutation and polar motion are neglected, which limits the result to tens
+/// of arcseconds - ample for rise/set and altitude work. See
+/// [`itrs_to_gcrs`] for the full rotation.
+pub fn terrestrial_to_celestial(t: &Time) -> Matrix3<f64> {
+    let gmst = sidereal_time(t) / 24.0 * TAU;
+    compute_precession(t.tdb()).transpose() * rot_z(gmst)
+}
+
+/// Earth-fixed position of a point given its geodetic coordinates
+///
+/// # Arguments
+///
+/// * `latitude` - Geodetic latitude in radians
+/// * `longitude` - Longitude in radians, positive east
+/// * `elevation_m` - Height above the ellipsoid in metres
+///
+/// # Returns
+///
+/// Position vector in AU
+pub fn terra(latitude: f64, longitude: f64, elevation_m: f64) -> Vector3<f64> {
+    let f = 1.0 / IERS_2010_INVERSE_EARTH_FLATTENING;
+    let (sin_lat, cos_lat) = latitude.sin_cos();
+    let (sin_lon, cos_lon) = longitude.sin_cos();
+
+    let c = 1.0 / (cos_lat * cos_lat + (1.0 - f) * (1.0 - f) * sin_lat * sin_lat).sqrt();
+    let s = (1.0 - f) * (1.0 - f) * c;
+
+    let ach = EARTH_RADIUS * c + elevation_m;
+    let ash = EARTH_RADIUS * s + elevation_m;
+
+    Vector3::new(
+        ach * cos_lat * cos_lon,
+        ach * cos_lat * sin_lon,
+        ash * sin_lat,
+    ) / AU_M
+}
+
+/// Refraction-free altitude and azimuth of a direction seen from a site
+///
+/// `direction` is in Earth-fixed axes; latitude and longitude are geodetic,
+/// in degrees. Returns `(altitude, azimuth)` in degrees, azimuth measured
+/// from north through east in [0, 360).
+pub fn altaz_from_terrestrial(
+    direction: &Vector3<f64>,
+    latitude_deg: f64,
+    longitude_deg: f64,
+) -> (f64, f64) {
+    let (sin_lat, cos_lat) = (latitude_deg * DEG2RAD).sin_cos();
+    let (sin_lon, cos_lon) = (longitude_deg * DEG2RAD).sin_cos();
+
+    let up = Vector3::new(cos_lat * cos_lon, cos_lat * sin_lon, sin_lat);
+    let north = Vector3::new(-sin_lat * cos_lon, -sin_lat * sin_lon, cos_lat);
+    let east = Vector3::new(-sin_lon, cos_lon, 0.0);
+
+    let altitude = (direction.dot(&up) / direction.norm())
+        .clamp(-1.0, 1.0)
+        .asin()
+        / DEG2RAD;
+    let azimuth = (direction.dot(&east).atan2(direction.dot(&north)) / DEG2RAD).rem_euclid(360.0);
+
+    (altitude, azimuth)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::time::Timescale;
+    use approx::assert_relative_eq;
+
+    #[test]
+    fn test_era_at_j2000() {
+        assert_relative_eq!(
+            earth_rotation_angle(J2000),
+            0.779_057_273_264,
+            epsilon = 1e-12
+        );
+    }
+
+    #[test]
+    fn test_gmst_j2000() {
+        // GMST at 2000 January 1, 12h UT1 is 18h 41m 50.54841s
+        let ts = Timescale::default();
+        let t = ts.ut1_jd(J2000);
+        assert_relative_eq!(sidereal_time(&t), 18.697_374_558, epsilon = 1e-6);
+    }
+
+    #[test]
+    fn test_gast_and_itrs_to_gcrs() {
+        let ts = Timescale::default();
+        let t = ts.utc((2020, 6, 1, 4, 0, 0.0));
+
+        let eqeq = equation_of_the_equinoxes(t.tt(), NutationModel::Iau2000B);
+        assert_relative_eq!(
+            (gast(&t, NutationModel::Iau2000B) - gmst(&t)) * 3600.0,
+            eqeq / TAU * 86400.0,
+            epsilon = 1e-9
+        );
+        assert_eq!(gast(&t, NutationModel::None), gmst(&t));
+
+        // Orthonormal, and within the ~20" of nutation of the simpler rotation
+        let rotation = itrs_to_gcrs(&t);
+        assert_relative_eq!(
+            rotation * rotation.transpose(),
+            Matrix3::identity(),
+            epsilon = 1e-12
+        );
+        let site = terra(40.0 * DEG2RAD, -105.0 * DEG2RAD, 0.0).normalize();
+        let simple = terrestrial_to_celestial(&t) * site;
+        let angle = (rotation * site).angle(&simple) / ASEC2RAD;
+        assert!(angle > 1.0 && angle < 25.0, "{} arcsec", angle);
+        assert_relative_eq!(
+            itrs_to_gcrs_with(&t, NutationModel::None, None),
+            terrestrial_to_celestial(&t),
+            epsilon = 1e-12
+        );
+    }
+
+    #[test]
+    fn test_polar_motion() {
+        let ts = Timescale::default();
+        let t = ts.utc((2020, 1, 2));
+        let pole = PolarMotion::new(0.2, 0.35);
+
+        // The Celestial Intermediate Pole sits at (x, -y) in the ITRS and
+        // becomes the z axis of the intermediate system
+        let (x, y) = (pole.x_arcsec * ASEC2RAD, pole.y_arcsec * ASEC2RAD);
+        let cip = Vector3::new(x, -y, 1.0).normalize();
+        assert_relative_eq!(pole.matrix(t.tt()) * cip, Vector3::z(), epsilon = 1e-12);
+
+        let record = |mjd: f64, pm_x_arcsec: f64| EopRecord {
+            mjd,
+            ut1_minus_utc: -0.17,
+            pm_x_arcsec,
+            pm_y_arcsec: 0.3,
+            predicted: false,
+        };
+        let records = [
+            record(58849.0, 0.1),
+            record(58850.0, 0.2),
+            record(58851.0, 0.4),
+        ];
+        let interpolated = PolarMotion::from_eop(&records, &(t + TimeDelta::days(0.25))).unwrap();
+        assert_relative_eq!(interpolated.x_arcsec, 0.25, epsilon = 1e-6);
+        assert_relative_eq!(interpolated.y_arcsec, 0.3, epsilon = 1e-12);
+        assert!(PolarMotion::from_eop(&records, &ts.utc((2021, 1, 1))).is_none());
+    }
+
+    #[test]
+    fn test_transform_state() {
+        let ts = Timescale::default();
+        let t = ts.utc((2020, 6, 1, 4, 0, 0.0));
+        let station = terra(35.0 * DEG2RAD, 140.0 * DEG2RAD, 100.0);
+        let at_rest = Vector3::zeros();
+
+        // A station at rest on the ground moves with the rotation, which
+        // matches differencing its celestial position
+        let (position, velocity) =
+            transform_state(&station, &at_rest, &t, StateDirection::ItrsToGcrs);
+        assert_relative_eq!(position, itrs_to_gcrs(&t) * station, epsilon = 1e-15);
+        let h = 30.0 / DAY_S;
+        let differenced = (itrs_to_gcrs(&(&t + TimeDelta::days(h)))
+            - itrs_to_gcrs(&(&t - TimeDelta::days(h))))
+            * station
+            / (2.0 * h);
+        assert_relative_eq!(velocity, differenced, max_relative = 2e-6);
+
+        // and round-trips to rest
+        let (back, back_velocity) =
+            transform_state(&position, &velocity, &t, StateDirection::GcrsToItrs);
+        assert_relative_eq!(back, station, epsilon = 1e-15);
+        assert!(back_velocity.norm() * AU_M / DAY_S < 1e-9);
+
+        // A drifting pole adds a velocity of its own
+        let record = |mjd: f64, pm_x_arcsec: f64| EopRecord {
+            mjd,
+            ut1_minus_utc: -0.2,
+            pm_x_arcsec,
+            pm_y_arcsec: 0.4,
+            predicted: false,
+        };
+        let records = [
+            record(59000.0, 0.10),
+            record(59001.0, 0.11),
+            record(59002.0, 0.12),
+        ];
+        let (_, wobbling) = transform_state_with(
+            &station,
+            &at_rest,
+            &t,
+            StateDirection::ItrsToGcrs,
+            NutationModel::Iau2000B,
+            Some(&records),
+        );
+        let difference_m_s = (wobbling - velocity).norm() * AU_M / DAY_S;
+        assert!(
+            difference_m_s > 1e-4 && difference_m_s < 0.1,
+            "{}",
+            difference_m_s
+        );
+    }
+
+    #[test]
+    fn test_terra_radius() {
+        let equator = terra(0.0, 0.0, 0.0) * AU_M;
+        assert_relative_eq!(equator.x, EARTH_RADIUS, epsilon = 1e-6);
+
+        let pole = terra(90.0 * DEG2RAD, 0.0, 0.0) * AU_M;
+        assert_relative_eq!(pole.z, 6_356_751.9, epsilon = 1.0);
+    }
+
+    #[test]
+    fn test_zenith_and_north() {
+        let site = terra(40.0 * DEG2RAD, -105.0 * DEG2RAD, 0.0);
+        let (alt, _) = altaz_from_terrestrial(&site, 40.0, -105.0);
+        assert!(alt > 89.7);
+
+        let (alt, az) = altaz_from_terrestrial(&Vector3::z(), 40.0, -105.0);
+        assert_relative_eq!(alt, 40.0, epsilon = 1e-9);
+        assert_relative_eq!(az, 0.0, epsilon = 1e-9);
+    }
+}
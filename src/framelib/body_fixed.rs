@@ -0,0 +1,135 @@
+//! Body-fixed frames from binary PCK orientation kernels
+//!
+//! A [`BodyFixed`] frame pairs a loaded [`PCK`] with one of the frames it
+//! describes and converts ICRF vectors into and out of it:
+//!
+//! ```no_run
+//! use std::sync::Arc;
+//! use nalgebra::Vector3;
+//! use starfield::framelib::BodyFixed;
+//! use starfield::jplephem::pck::{MOON_PA_DE421, PCK};
+//! use starfield::time::Timescale;
+//!
+//! let pck = Arc::new(PCK::open("moon_pa_de421_1900-2050.bpc")?);
+//! let moon = BodyFixed::new(pck, MOON_PA_DE421)?;
+//! let t = Timescale::default().utc((2024, 4, 8, 18, 0, 0.0));
+//! let toward_earth = moon.from_icrf(&t, &Vector3::new(1.0, 0.0, 0.0))?;
+//! # Ok::<(), starfield::StarfieldError>(())
+//! ```
+
+use crate::jplephem::{JplEphemError, PCK};
+use crate::time::Time;
+use crate::Result;
+use nalgebra::{Matrix3, Vector3};
+use std::sync::Arc;
+
+/// A rotating frame whose orientation comes from a binary PCK
+#[derive(Debug, Clone)]
+pub struct BodyFixed {
+    pck: Arc<PCK>,
+    frame_id: i32,
+}
+
+impl BodyFixed {
+    /// The frame with NAIF ID `frame_id` from `pck`
+    ///
+    /// Fails if the kernel has no segments for the frame.
+    pub fn new(pck: Arc<PCK>, frame_id: i32) -> Result<Self> {
+        if pck.segments_for(frame_id).next().is_none() {
+            return Err(JplEphemError::FrameNotFound(frame_id).into());
+        }
+        Ok(Self { pck, frame_id })
+    }
+
+    /// NAIF ID of the frame
+    pub fn frame_id(&self) -> i32 {
+        self.frame_id
+    }
+
+    /// Rotation from ICRF to this frame at a time
+    pub fn rotation_at(&self, t: &Time) -> Result<Matrix3<f64>> {
+        Ok(self.pck.rotation_at(self.frame_id, t.tdb())?)
+    }
+
+    /// Rotation from ICRF to this frame at a time, and its time derivative
+    /// per day
+    pub fn rotation_and_rate_at(&self, t: &Time) -> Result<(Matrix3<f64>, Matrix3<f64>)> {
+        Ok(self.pck.rotation_and_rate_at(self.frame_id, t.tdb())?)
+    }
+
+    /// Express an ICRF vector in this frame
+    pub fn from_icrf(&self, t: &Time, v: &Vector3<f64>) -> Result<Vector3<f64>> {
+        Ok(self.rotation_at(t)? * v)
+    }
+
+    /// Express a vector in this frame in ICRF
+    pub fn to_icrf(&self, t: &Time, v: &Vector3<f64>) -> Result<Vector3<f64>> {
+        Ok(self.rotation_at(t)?.transpose() * v)
+    }
+
+    /// Convert an ICRF position and velocity (per day) to this frame,
+    /// including the velocity the frame's rotation adds
+    pub fn state_from_icrf(
+        &self,
+        t: &Time,
+        position: &Vector3<f64>,
+        velocity: &Vector3<f64>,
+    ) -> Result<(Vector3<f64>, Vector3<f64>)> {
+        let (r, dr) = self.rotation_and_rate_at(t)?;
+        Ok((r * position, r * velocity + dr * position))
+    }
+
+    /// Convert a position and velocity (per day) in this frame to ICRF
+    pub fn state_to_icrf(
+        &self,
+        t: &Time,
+        position: &Vector3<f64>,
+        velocity: &Vector3<f64>,
+    ) -> Result<(Vector3<f64>, Vector3<f64>)> {
+        let (r, dr) = self.rotation_and_rate_at(t)?;
+        let position_icrf = r.transpose() * position;
+        Ok((
+            position_icrf,
+            r.transpose() * (velocity - dr * position_icrf),
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::jplephem::pck::testing::{pck, rotating_array};
+    use crate::jplephem::pck::ITRF93;
+    use crate::time::Timescale;
+    use approx::assert_relative_eq;
+
+    #[test]
+    fn test_body_fixed_round_trip() {
+        // A frame spinning about the ICRF pole once a sidereal day
+        let spin = std::f64::consts::TAU * 1.002_737_909;
+        let kernel = Arc::new(pck(&[rotating_array(
+            ITRF93,
+            1,
+            [0.0, 0.0, 0.3],
+            spin,
+            10.0,
+        )]));
+        let frame = BodyFixed::new(kernel.clone(), ITRF93).unwrap();
+        assert!(BodyFixed::new(kernel, 399).is_err());
+
+        let t = Timescale::default().tt_jd(2_451_547.3, None);
+        let position = Vector3::new(6378.0, 0.0, 100.0);
+        let velocity = Vector3::new(0.0, 6378.0 * spin, 0.0);
+
+        // A point co-rotating with the frame is at rest in it
+        let (p, v) = frame.state_from_icrf(&t, &position, &velocity).unwrap();
+        assert_relative_eq!(p.norm(), position.norm(), epsilon = 1e-9);
+        assert_relative_eq!(v, Vector3::zeros(), epsilon = 1e-6);
+
+        let (p2, v2) = frame.state_to_icrf(&t, &p, &v).unwrap();
+        assert_relative_eq!(p2, position, epsilon = 1e-9);
+        assert_relative_eq!(v2, velocity, epsilon = 1e-6);
+        let back = frame.to_icrf(&t, &frame.from_icrf(&t, &position).unwrap());
+        assert_relative_eq!(back.unwrap(), position, epsilon = 1e-9);
+    }
+}
@@ -0,0 +1,186 @@
+//! FK4 (B1950) coordinates
+//!
+//! Catalogs and papers from before the 1980s give positions on the FK4
+//! system, referred to a Besselian equinox such as B1950.0 and including the
+//! elliptic terms of aberration ("E-terms"). [`Equatorial::from_fk4`] takes
+//! such a position to the ICRS, and [`Equatorial::to_fk4`] goes back, for
+//! objects with no proper motion in FK5, following SLALIB's `FK45Z` and the
+//! matrices of Standish (1982):
+//!
+//! ```
+//! use starfield::framelib::inertial::Equatorial;
+//!
+//! // The galactic centre as defined by the IAU in 1958
+//! let b1950 = Equatorial::from_degrees(265.610_846, -28.916_790);
+//! let icrs = Equatorial::from_fk4(&b1950, 1950.0);
+//! assert!((icrs.ra_degrees() - 266.405_10).abs() < 1e-4);
+//! assert!((icrs.dec_degrees() + 28.936_175).abs() < 1e-4);
+//! ```
+
+use super::inertial::{Equatorial, InertialFrame};
+use super::{icrs_to_fk5_j2000, rot_y, rot_z};
+use crate::constants::ASEC2RAD;
+use crate::coordinates::cartesian::Cartesian3;
+use nalgebra::{Matrix3, Vector3};
+
+/// Arcseconds per century in one radian per year
+const PMF: f64 = 100.0 / ASEC2RAD;
+
+/// E-terms of aberration at B1950, in radians
+const E_TERMS: [f64; 3] = [-1.62557e-6, -0.31919e-6, -0.13843e-6];
+
+/// Change in the E-terms, in arcseconds per century
+const E_TERMS_RATE: [f64; 3] = [1.245e-3, -1.580e-3, -0.659e-3];
+
+/// FK4 B1950 position to FK5 J2000 position
+const EM_POSITION: [[f64; 3]; 3] = [
+    [0.999_925_678_2, -0.011_182_061_1, -0.004_857_947_7],
+    [0.011_182_061_0, 0.999_937_478_4, -0.000_027_176_5],
+    [0.004_857_947_9, -0.000_027_147_4, 0.999_988_199_7],
+];
+
+/// FK4 B1950 position to the fictitious FK5 proper motion that the FK4
+/// equinox error introduces
+const EM_VELOCITY: [[f64; 3]; 3] = [
+    [-0.000_551, -0.238_565, 0.435_739],
+    [0.238_514, -0.002_667, -0.008_541],
+    [-0.435_623, 0.012_254, 0.002_117],
+];
+
+/// Rotation from FK4 B1950 to FK5 J2000 for a position observed at the
+/// Besselian epoch `epoch`
+fn fk4_to_fk5(epoch: f64) -> Matrix3<f64> {
+    // Julian years from J2000 to the Besselian epoch
+    let mjd = 15_019.813_52 + (epoch - 1900.0) * 365.242_198_781;
+    let years = (mjd - 51_544.5) / 365.25;
+    let position = Matrix3::from_fn(|i, j| EM_POSITION[i][j]);
+    let velocity = Matrix3::from_fn(|i, j| EM_VELOCITY[i][j]);
+    position + velocity * (years / PMF)
+}
+
+/// E-terms of aberration for a position observed at the Besselian epoch
+/// `epoch`
+fn e_terms(epoch: f64) -> Vector3<f64> {
+    let scale = (epoch - 1950.0) / PMF;
+    Vector3::from_fn(|i, _| E_TERMS[i] + scale * E_TERMS_RATE[i])
+}
+
+/// Newcomb's precession matrix from Besselian equinox `from` to `to`
+///
+/// Multiply an FK4 vector referred to `from` by the returned matrix to refer
+/// it to `to`.
+pub fn fk4_precession(from: f64, to: f64) -> Matrix3<f64> {
+    let big_t = (from - 1850.0) / 100.0;
+    let t = (to - from) / 100.0;
+    let tas2r = t * ASEC2RAD;
+
+    let w = 2303.5548 + (1.39720 + 0.000059 * big_t) * big_t;
+    let zeta = (w + (0.30242 - 0.000269 * big_t + 0.017996 * t) * t) * tas2r;
+    let z = (w + (1.09478 + 0.000387 * big_t + 0.018324 * t) * t) * tas2r;
+    let theta = (2005.1125
+        + (-0.85294 - 0.000365 * big_t) * big_t
+        + (-0.42647 - 0.000365 * big_t - 0.041802 * t) * t)
+        * tas2r;
+
+    rot_z(z) * rot_y(-theta) * rot_z(zeta)
+}
+
+/// FK5 J2000 direction of an FK4 B1950 direction, E-terms included
+fn b1950_to_fk5(fk4: &Vector3<f64>, epoch: f64) -> Vector3<f64> {
+    let a = e_terms(epoch);
+    let without_e_terms = fk4 - a + fk4.dot(&a) * fk4;
+    (fk4_to_fk5(epoch) * without_e_terms).normalize()
+}
+
+impl Equatorial {
+    /// ICRS position of FK4 coordinates referred to the Besselian
+    /// `equinox`, such as 1950.0
+    ///
+    /// The position is taken to be observed at the equinox, for an object
+    /// with no proper motion in FK5.
+    pub fn from_fk4(fk4: &Equatorial, equinox: f64) -> Self {
+        let b1950 = fk4_precession(equinox, 1950.0) * fk4.to_cartesian().to_vector3();
+        let fk5 = b1950_to_fk5(&b1950, equinox);
+        let icrs = icrs_to_fk5_j2000().transpose() * fk5;
+        Equatorial::from_cartesian(Cartesian3::from_vector3(icrs))
+    }
+
+    /// FK4 coordinates, E-terms included, referred to the Besselian
+    /// `equinox`
+    ///
+    /// The inverse of [`Equatorial::from_fk4`].
+    pub fn to_fk4(&self, equinox: f64) -> Equatorial {
+        let fk5 = icrs_to_fk5_j2000() * self.to_cartesian().to_vector3();
+
+        // The E-terms make the forward conversion slightly non-linear, so
+        // refine a first guess from the rotation alone
+        let inverse = fk4_to_fk5(equinox)
+            .try_inverse()
+            .expect("FK4 rotation is invertible");
+        let mut b1950 = (inverse * fk5).normalize();
+        for _ in 0..3 {
+            let error = fk5 - b1950_to_fk5(&b1950, equinox);
+            b1950 = (b1950 + inverse * error).normalize();
+        }
+
+        let fk4 = fk4_precession(1950.0, equinox) * b1950;
+        Equatorial::from_cartesian(Cartesian3::from_vector3(fk4))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::framelib::INERTIAL_FRAMES;
+    use approx::assert_relative_eq;
+
+    /// Separation in arcseconds
+    fn separation(a: &Equatorial, b: &Equatorial) -> f64 {
+        a.angular_distance(b) / ASEC2RAD
+    }
+
+    #[test]
+    fn test_matches_spice_rotation() {
+        // At B1950 the fictitious proper motion brings the FK4 to FK5 matrix
+        // onto the transpose of SPICE's J2000 to FK4 rotation
+        let spice = INERTIAL_FRAMES["FK4"];
+        assert_relative_eq!(fk4_to_fk5(1950.0), spice.transpose(), epsilon = 1e-8);
+    }
+
+    #[test]
+    fn test_published_positions() {
+        // Galactic centre and north pole: IAU 1958 definitions in B1950
+        // and their J2000 equivalents from Murray (1989)
+        let centre =
+            Equatorial::from_fk4(&Equatorial::from_degrees(265.610_846, -28.916_790), 1950.0);
+        assert!(separation(&centre, &Equatorial::from_degrees(266.405_10, -28.936_175)) < 0.05);
+
+        let pole = Equatorial::from_fk4(&Equatorial::from_degrees(192.25, 27.4), 1950.0);
+        assert!(separation(&pole, &Equatorial::from_degrees(192.859_48, 27.128_25)) < 0.3);
+    }
+
+    #[test]
+    fn test_round_trip_and_equinox() {
+        let icrs = Equatorial::from_degrees(83.822_08, -5.391_11);
+        for equinox in [1900.0, 1950.0, 1975.0] {
+            let back = Equatorial::from_fk4(&icrs.to_fk4(equinox), equinox);
+            assert!(separation(&icrs, &back) < 1e-6);
+        }
+
+        // Precessing FK4 coordinates to B1900 changes the position by
+        // about 42' here, but the ICRS result only through the fictitious
+        // proper motion of half an arcsecond per century
+        let b1950 = icrs.to_fk4(1950.0);
+        let b1900 = Equatorial::from_cartesian(Cartesian3::from_vector3(
+            fk4_precession(1950.0, 1900.0) * b1950.to_cartesian().to_vector3(),
+        ));
+        assert!(separation(&b1950, &b1900) > 2000.0);
+        let via_b1900 = Equatorial::from_fk4(&b1900, 1900.0);
+        assert!(separation(&icrs, &via_b1900) < 0.3);
+        assert_relative_eq!(
+            fk4_precession(1900.0, 1950.0) * fk4_precession(1950.0, 1900.0),
+            Matrix3::identity(),
+            epsilon = 1e-12
+        );
+    }
+}
@@ -0,0 +1,142 @@
+//! Reference-frame tags for position vectors
+//!
+//! Vectors in different frames differ by rotations small enough (the 23 mas
+//! ICRS/FK5 frame bias, arcminutes of precession) to go unnoticed when mixed
+//! by mistake. Tagging each vector with its [`Frame`] lets arithmetic refuse
+//! to combine vectors from different frames.
+
+use crate::constants::ASEC2RAD;
+use crate::planetlib::Body;
+use nalgebra::Matrix3;
+use std::fmt;
+use thiserror::Error;
+
+/// Reference frame in which a vector's components are expressed
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Frame {
+    /// International Celestial Reference System (also GCRS axes)
+    Icrs,
+    /// FK5 mean equator and equinox of J2000.0
+    Fk5J2000,
+    /// True equator and equinox of the given TT Julian date
+    TrueOfDate { epoch_tt: f64 },
+    /// International Terrestrial Reference System (Earth-fixed)
+    Itrs,
+    /// Rotating frame fixed to a solar-system body
+    BodyFixed(Body),
+}
+
+impl fmt::Display for Frame {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Frame::Icrs => write!(f, "ICRS"),
+            Frame::Fk5J2000 => write!(f, "FK5 J2000"),
+            Frame::TrueOfDate { epoch_tt } => write!(f, "true of date (JD {} TT)", epoch_tt),
+            Frame::Itrs => write!(f, "ITRS"),
+            Frame::BodyFixed(body) => write!(f, "{} body-fixed", body.name()),
+        }
+    }
+}
+
+/// Error raised when vectors from different frames are combined
+#[derive(Debug, Clone, Copy, PartialEq, Error)]
+#[error("frame mismatch: expected {expected}, found {found}")]
+pub struct FrameMismatch {
+    pub expected: Frame,
+    pub found: Frame,
+}
+
+impl Frame {
+    /// Whether the frame's axes are fixed with respect to distant quasars
+    pub fn is_inertial(&self) -> bool {
+        matches!(self, Frame::Icrs | Frame::Fk5J2000)
+    }
+
+    /// Check that `other` is the same frame as `self`
+    pub fn check(&self, other: &Frame) -> Result<(), FrameMismatch> {
+        if self == other {
+            Ok(())
+        } else {
+            Err(FrameMismatch {
+                expected: *self,
+                found: *other,
+            })
+        }
+    }
+
+    /// Panic in debug builds if `other` is not the same frame as `self`
+    ///
+    /// Release builds skip the comparison, matching the cost model of
+    /// integer overflow checks.
+    #[track_caller]
+    pub fn debug_check(&self, other: &Frame) {
+        debug_assert!(
+            self == other,
+            "{}",
+            FrameMismatch {
+                expected: *self,
+                found: *other,
+            }
+        );
+    }
+}
+
+/// Frame bias rotation from ICRS to the FK5 J2000 mean equator and equinox
+///
+/// Uses the IERS 2003 bias angles, as in skyfield's `framelib`.
+pub fn icrs_to_fk5_j2000() -> Matrix3<f64> {
+    let xi0 = -0.016_617_0 * ASEC2RAD;
+    let eta0 = -0.006_819_2 * ASEC2RAD;
+    let da0 = -0.014_60 * ASEC2RAD;
+
+    let (yx, zx, xy, zy, xz, yz) = (-da0, xi0, da0, eta0, -xi0, -eta0);
+
+    let xx = 1.0 - 0.5 * (yx * yx + zx * zx);
+    let yy = 1.0 - 0.5 * (yx * yx + zy * zy);
+    let zz = 1.0 - 0.5 * (zy * zy + zx * zx);
+
+    Matrix3::new(xx, xy, xz, yx, yy, yz, zx, zy, zz)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use approx::assert_relative_eq;
+
+    #[test]
+    fn test_frame_check() {
+        assert!(Frame::Icrs.check(&Frame::Icrs).is_ok());
+        let err = Frame::Icrs.check(&Frame::Fk5J2000).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "frame mismatch: expected ICRS, found FK5 J2000"
+        );
+
+        let a = Frame::TrueOfDate {
+            epoch_tt: 2_460_000.5,
+        };
+        let b = Frame::TrueOfDate {
+            epoch_tt: 2_460_001.5,
+        };
+        assert!(a.check(&b).is_err());
+        assert!(Frame::BodyFixed(Body::Mars)
+            .check(&Frame::BodyFixed(Body::Moon))
+            .is_err());
+    }
+
+    #[test]
+    #[cfg(debug_assertions)]
+    #[should_panic(expected = "frame mismatch")]
+    fn test_debug_check_panics() {
+        Frame::Itrs.debug_check(&Frame::Icrs);
+    }
+
+    #[test]
+    fn test_frame_bias_is_tiny_rotation() {
+        let b = icrs_to_fk5_j2000();
+        assert_relative_eq!(b * b.transpose(), Matrix3::identity(), epsilon = 1e-13);
+        // The bias amounts to a few tens of milliarcseconds
+        let offset = (b - Matrix3::identity()).abs().max() / ASEC2RAD;
+        assert!((0.01..0.03).contains(&offset), "{}", offset);
+    }
+}
@@ -0,0 +1,356 @@
+//! Horizontal (altitude/azimuth) coordinates for a site and time
+//!
+//! A [`HorizontalFrame`] ties a [`GeographicLocation`] to a [`Time`] and
+//! converts J2000 equatorial directions to telescope-pointing altitude and
+//! azimuth and back, optionally bending the altitude for atmospheric
+//! refraction. Directions are treated as infinitely distant, so no parallax
+//! is applied; for nearby bodies use
+//! [`Apparent::altaz`](crate::positions::icrf::Apparent::altaz).
+
+use crate::accuracy::AccuracyProfile;
+use crate::constants::{DEG2RAD, RAD2DEG, TAU};
+use crate::coordinates::Equatorial;
+use crate::earthlib::{sidereal_time, terrestrial_to_celestial};
+use crate::observers::GeographicLocation;
+use crate::time::Time;
+use nalgebra::{Matrix3, Vector3};
+
+/// Refraction formulae are unreliable below this altitude, in degrees
+const REFRACTION_FLOOR_DEG: f64 = -1.0;
+
+/// Altitude and azimuth in radians
+///
+/// Azimuth is measured from north through east, in [0, 2π).
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Horizontal {
+    /// Altitude above the horizon in radians
+    pub alt: f64,
+    /// Azimuth in radians
+    pub az: f64,
+}
+
+impl Horizontal {
+    /// Create horizontal coordinates from radians, normalizing the azimuth
+    pub fn new(alt: f64, az: f64) -> Self {
+        Self {
+            alt,
+            az: az.rem_euclid(TAU),
+        }
+    }
+
+    /// Create horizontal coordinates from degrees
+    pub fn from_degrees(alt_deg: f64, az_deg: f64) -> Self {
+        Self::new(alt_deg * DEG2RAD, az_deg * DEG2RAD)
+    }
+
+    /// Altitude in degrees
+    pub fn alt_degrees(&self) -> f64 {
+        self.alt * RAD2DEG
+    }
+
+    /// Azimuth in degrees
+    pub fn az_degrees(&self) -> f64 {
+        self.az * RAD2DEG
+    }
+
+    /// Zenith distance in degrees
+    pub fn zenith_distance_degrees(&self) -> f64 {
+        90.0 - self.alt_degrees()
+    }
+}
+
+/// Pressure and temperature at the site, for scaling refraction
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Atmosphere {
+    /// Pressure in millibars
+    pub pressure_mbar: f64,
+    /// Temperature in degrees Celsius
+    pub temperature_c: f64,
+}
+
+impl Default for Atmosphere {
+    /// The standard conditions the refraction formulae are tabulated for
+    fn default() -> Self {
+        Self {
+            pressure_mbar: 1010.0,
+            temperature_c: 10.0,
+        }
+    }
+}
+
+impl Atmosphere {
+    /// Create an atmosphere from pressure (mbar) and temperature (°C)
+    pub fn new(pressure_mbar: f64, temperature_c: f64) -> Self {
+        Self {
+            pressure_mbar,
+            temperature_c,
+        }
+    }
+
+    /// Factor scaling refraction relative to standard conditions
+    fn scale(&self) -> f64 {
+        (self.pressure_mbar / 1010.0) * (283.0 / (273.0 + self.temperature_c))
+    }
+}
+
+/// Atmospheric refraction model (Meeus, Astronomical Algorithms ch. 16)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Refraction {
+    /// Bennett (1982), defined from the apparent altitude; good to 0.07'
+    Bennett,
+    /// Sæmundsson (1986), defined from the true altitude; consistent with
+    /// Bennett to about 0.1'
+    Saemundsson,
+}
+
+impl Refraction {
+    /// Raw formula in arcminutes at standard conditions
+    fn formula_arcmin(&self, alt_deg: f64) -> f64 {
+        match self {
+            Refraction::Bennett => 1.0 / ((alt_deg + 7.31 / (alt_deg + 4.4)) * DEG2RAD).tan(),
+            Refraction::Saemundsson => 1.02 / ((alt_deg + 10.3 / (alt_deg + 5.11)) * DEG2RAD).tan(),
+        }
+    }
+
+    /// Refraction in degrees for a true (geometric) altitude in degrees
+    ///
+    /// Zero below one degree under the horizon, where the formulae fail.
+    pub fn from_true_altitude(&self, alt_deg: f64, atmosphere: &Atmosphere) -> f64 {
+        if alt_deg < REFRACTION_FLOOR_DEG {
+            return 0.0;
+        }
+        match self {
+            Refraction::Saemundsson => self.formula_arcmin(alt_deg) * atmosphere.scale() / 60.0,
+            Refraction::Bennett => {
+                // Solve apparent = true + R(apparent) by fixed-point iteration
+                let mut refraction = 0.0;
+                for _ in 0..10 {
+                    let apparent = alt_deg + refraction;
+                    refraction = self.formula_arcmin(apparent) * atmosphere.scale() / 60.0;
+                }
+                refraction
+            }
+        }
+    }
+
+    /// Refraction in degrees for an apparent (observed) altitude in degrees
+    pub fn from_apparent_altitude(&self, alt_deg: f64, atmosphere: &Atmosphere) -> f64 {
+        if alt_deg < REFRACTION_FLOOR_DEG {
+            return 0.0;
+        }
+        match self {
+            Refraction::Bennett => self.formula_arcmin(alt_deg) * atmosphere.scale() / 60.0,
+            Refraction::Saemundsson => {
+                let mut refraction = 0.0;
+                for _ in 0..10 {
+                    let true_alt = alt_deg - refraction;
+                    refraction = self.formula_arcmin(true_alt) * atmosphere.scale() / 60.0;
+                }
+                refraction
+            }
+        }
+    }
+}
+
+/// The local horizon of a site at a moment
+#[derive(Debug, Clone)]
+pub struct HorizontalFrame {
+    location: GeographicLocation,
+    time: Time,
+    refraction: Option<Refraction>,
+    atmosphere: Atmosphere,
+    /// J2000 equatorial to (north, east, up) axes
+    rotation: Matrix3<f64>,
+}
+
+impl HorizontalFrame {
+    /// Horizon of `location` at `time`, without refraction
+    pub fn new(location: GeographicLocation, time: &Time) -> Self {
+        let (sin_lat, cos_lat) = (location.latitude_deg * DEG2RAD).sin_cos();
+        let (sin_lon, cos_lon) = (location.longitude_deg * DEG2RAD).sin_cos();
+
+        // Rows are the north, east and up directions in Earth-fixed axes
+        let itrs_to_horizon = Matrix3::new(
+            -sin_lat * cos_lon,
+            -sin_lat * sin_lon,
+            cos_lat,
+            -sin_lon,
+            cos_lon,
+            0.0,
+            cos_lat * cos_lon,
+            cos_lat * sin_lon,
+            sin_lat,
+        );
+        let rotation = itrs_to_horizon * terrestrial_to_celestial(time).transpose();
+
+        Self {
+            location,
+            time: time.clone(),
+            refraction: None,
+            atmosphere: Atmosphere::default(),
+            rotation,
+        }
+    }
+
+    /// Apply a refraction model to altitudes
+    pub fn with_refraction(mut self, model: Refraction) -> Self {
+        self.refraction = Some(model);
+        self
+    }
+
+    /// Set the site's pressure and temperature for the refraction model
+    pub fn with_atmosphere(mut self, atmosphere: Atmosphere) -> Self {
+        self.atmosphere = atmosphere;
+        self
+    }
+
+    /// Use the refraction model of an accuracy profile
+    pub fn with_accuracy(mut self, profile: &AccuracyProfile) -> Self {
+        self.refraction = profile.refraction;
+        self
+    }
+
+    /// The site
+    pub fn location(&self) -> &GeographicLocation {
+        &self.location
+    }
+
+    /// The moment
+    pub fn time(&self) -> &Time {
+        &self.time
+    }
+
+    /// Refraction model in use, if any
+    pub fn refraction(&self) -> Option<Refraction> {
+        self.refraction
+    }
+
+    /// Greenwich mean sidereal time in hours
+    pub fn greenwich_sidereal_time(&self) -> f64 {
+        sidereal_time(&self.time)
+    }
+
+    /// Local mean sidereal time in hours
+    pub fn local_sidereal_time(&self) -> f64 {
+        (self.greenwich_sidereal_time() + self.location.longitude_deg / 15.0).rem_euclid(24.0)
+    }
+
+    /// Hour angle in hours, in [-12, 12), of a right ascension of date in degrees
+    pub fn hour_angle(&self, ra_of_date_deg: f64) -> f64 {
+        (self.local_sidereal_time() - ra_of_date_deg / 15.0 + 12.0).rem_euclid(24.0) - 12.0
+    }
+
+    /// Convert a J2000 equatorial direction to altitude and azimuth
+    pub fn from_equatorial(&self, equatorial: &Equatorial) -> Horizontal {
+        let (sin_dec, cos_dec) = equatorial.dec.sin_cos();
+        let (sin_ra, cos_ra) = equatorial.ra.sin_cos();
+        let direction = Vector3::new(cos_dec * cos_ra, cos_dec * sin_ra, sin_dec);
+        let local = self.rotation * direction;
+
+        let alt_deg = local.z.clamp(-1.0, 1.0).asin() * RAD2DEG;
+        let az = local.y.atan2(local.x);
+        let refraction = self.refraction.map_or(0.0, |model| {
+            model.from_true_altitude(alt_deg, &self.atmosphere)
+        });
+
+        Horizontal::new((alt_deg + refraction) * DEG2RAD, az)
+    }
+
+    /// Convert an altitude and azimuth back to a J2000 equatorial direction
+    ///
+    /// With refraction enabled, `horizontal` is taken to be the observed
+    /// (refracted) position.
+    pub fn to_equatorial(&self, horizontal: &Horizontal) -> Equatorial {
+        let alt_deg = horizontal.alt_degrees();
+        let refraction = self.refraction.map_or(0.0, |model| {
+            model.from_apparent_altitude(alt_deg, &self.atmosphere)
+        });
+        let alt = (alt_deg - refraction) * DEG2RAD;
+
+        let (sin_alt, cos_alt) = alt.sin_cos();
+        let (sin_az, cos_az) = horizontal.az.sin_cos();
+        let local = Vector3::new(cos_alt * cos_az, cos_alt * sin_az, sin_alt);
+        let direction = self.rotation.transpose() * local;
+
+        Equatorial::new(
+            direction.y.atan2(direction.x),
+            direction.z.clamp(-1.0, 1.0).asin(),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::time::Timescale;
+    use approx::assert_relative_eq;
+
+    #[test]
+    fn test_refraction_at_horizon_and_altitude() {
+        let atm = Atmosphere::default();
+        // About 34' at the horizon and 1' at 45 degrees
+        let r0 = Refraction::Bennett.from_apparent_altitude(0.0, &atm) * 60.0;
+        assert_relative_eq!(r0, 34.5, epsilon = 0.5);
+        let r45 = Refraction::Saemundsson.from_true_altitude(45.0, &atm) * 60.0;
+        assert_relative_eq!(r45, 1.0, epsilon = 0.05);
+
+        // The two models agree closely and each inverts itself
+        for alt in [2.0, 10.0, 30.0, 80.0] {
+            let b = Refraction::Bennett.from_true_altitude(alt, &atm);
+            let s = Refraction::Saemundsson.from_true_altitude(alt, &atm);
+            assert!((b - s).abs() * 60.0 < 0.2, "{alt}: {b} vs {s}");
+            let back = Refraction::Bennett.from_apparent_altitude(alt + b, &atm);
+            assert_relative_eq!(back, b, epsilon = 1e-9);
+        }
+
+        assert_eq!(Refraction::Bennett.from_true_altitude(-5.0, &atm), 0.0);
+        let thin = Atmosphere::new(700.0, -10.0);
+        assert!(
+            Refraction::Bennett.from_apparent_altitude(10.0, &thin)
+                < Refraction::Bennett.from_apparent_altitude(10.0, &atm)
+        );
+    }
+
+    #[test]
+    fn test_roundtrip_and_agreement_with_observer() {
+        let ts = Timescale::default();
+        let t = ts.utc((2024, 6, 1, 3, 0, 0.0));
+        let site = GeographicLocation::new(31.96, -111.60, 2_096.0);
+        let frame = HorizontalFrame::new(site, &t);
+
+        let star = Equatorial::from_degrees(279.234_7, 38.783_7); // Vega
+        let horizontal = frame.from_equatorial(&star);
+        let back = frame.to_equatorial(&horizontal);
+        assert!(star.angular_distance(&back) < 1e-12);
+
+        // Same answer as the observer's altaz for a distant target
+        let (sin_dec, cos_dec) = star.dec.sin_cos();
+        let far = Vector3::new(cos_dec * star.ra.cos(), cos_dec * star.ra.sin(), sin_dec) * 1e9;
+        let (alt, az) = site.altaz(&far, &t);
+        assert_relative_eq!(horizontal.alt_degrees(), alt, epsilon = 1e-6);
+        assert_relative_eq!(horizontal.az_degrees(), az, epsilon = 1e-6);
+
+        // Refraction lifts the star and is undone on the way back
+        let refracted = frame.clone().with_refraction(Refraction::Bennett);
+        let lifted = refracted.from_equatorial(&star);
+        assert!(lifted.alt > horizontal.alt);
+        assert!(star.angular_distance(&refracted.to_equatorial(&lifted)) < 1e-9);
+    }
+
+    #[test]
+    fn test_sidereal_time_and_hour_angle() {
+        let ts = Timescale::default();
+        let t = ts.utc((2024, 3, 20, 0, 0, 0.0));
+        let site = GeographicLocation::new(0.0, 90.0, 0.0);
+        let frame = HorizontalFrame::new(site, &t);
+
+        let lst = frame.local_sidereal_time();
+        assert_relative_eq!(
+            lst,
+            (frame.greenwich_sidereal_time() + 6.0).rem_euclid(24.0),
+            epsilon = 1e-12
+        );
+        assert_relative_eq!(frame.hour_angle(lst * 15.0), 0.0, epsilon = 1e-12);
+        assert!((-12.0..12.0).contains(&frame.hour_angle(lst * 15.0 + 180.0)));
+    }
+}
@@ -6,10 +6,10 @@ use once_cell::sync::Lazy;
 // Static transformation matrices
 
 // These should be the transformation matrices FROM equatorial TO the other system
-static EQ_TO_EC: Lazy<Matrix3<f64>> =
+pub(super) static EQ_TO_EC: Lazy<Matrix3<f64>> =
     Lazy::new(|| *frame_rotations::INERTIAL_FRAMES.get("ECLIPJ2000").unwrap());
 
-static EC_TO_EQ: Lazy<Matrix3<f64>> = Lazy::new(|| {
+pub(super) static EC_TO_EQ: Lazy<Matrix3<f64>> = Lazy::new(|| {
     frame_rotations::INERTIAL_FRAMES
         .get("ECLIPJ2000")
         .unwrap()
@@ -17,10 +17,10 @@ static EC_TO_EQ: Lazy<Matrix3<f64>> = Lazy::new(|| {
         .unwrap()
 });
 
-static EQ_TO_GAL: Lazy<Matrix3<f64>> =
+pub(super) static EQ_TO_GAL: Lazy<Matrix3<f64>> =
     Lazy::new(|| *frame_rotations::INERTIAL_FRAMES.get("GALACTIC").unwrap());
 
-static GAL_TO_EQ: Lazy<Matrix3<f64>> = Lazy::new(|| {
+pub(super) static GAL_TO_EQ: Lazy<Matrix3<f64>> = Lazy::new(|| {
     frame_rotations::INERTIAL_FRAMES
         .get("GALACTIC")
         .unwrap()
@@ -28,6 +28,17 @@ static GAL_TO_EQ: Lazy<Matrix3<f64>> = Lazy::new(|| {
         .unwrap()
 });
 
+/// Longitude wrapped into [0, 2π), without letting rounding produce 2π
+fn normalize_longitude(lon: f64) -> f64 {
+    let tau = 2.0 * std::f64::consts::PI;
+    let wrapped = lon.rem_euclid(tau);
+    if wrapped < tau {
+        wrapped
+    } else {
+        0.0
+    }
+}
+
 // Marker trait for inertial coordinate systems
 pub trait InertialFrame: Sized {
     fn to_cartesian(&self) -> Cartesian3;
@@ -94,20 +105,72 @@ impl Equatorial {
     }
 }
 
-// Ecliptic coordinates
+// Ecliptic coordinates, referred to the J2000 ecliptic and equinox
 #[derive(Debug, Clone, Copy)]
 pub struct Ecliptic {
     pub lon: f64, // Ecliptic longitude in radians
     pub lat: f64, // Ecliptic latitude in radians
 }
 
-// Galactic coordinates
+impl Ecliptic {
+    /// Create a new Ecliptic coordinate in radians, wrapping the longitude
+    /// into [0, 2π)
+    pub fn new(lon: f64, lat: f64) -> Self {
+        Ecliptic {
+            lon: normalize_longitude(lon),
+            lat,
+        }
+    }
+
+    /// Create a new Ecliptic coordinate with values in degrees
+    pub fn from_degrees(lon_deg: f64, lat_deg: f64) -> Self {
+        Self::new(lon_deg.to_radians(), lat_deg.to_radians())
+    }
+
+    /// Get ecliptic longitude in degrees, in [0, 360)
+    pub fn lon_degrees(&self) -> f64 {
+        normalize_longitude(self.lon).to_degrees()
+    }
+
+    /// Get ecliptic latitude in degrees
+    pub fn lat_degrees(&self) -> f64 {
+        self.lat.to_degrees()
+    }
+}
+
+// Galactic coordinates (IAU 1958 system, via the ICRS)
 #[derive(Debug, Clone, Copy)]
 pub struct Galactic {
     pub lon: f64, // Galactic longitude in radians
     pub lat: f64, // Galactic latitude in radians
 }
 
+impl Galactic {
+    /// Create a new Galactic coordinate in radians, wrapping the longitude
+    /// into [0, 2π)
+    pub fn new(lon: f64, lat: f64) -> Self {
+        Galactic {
+            lon: normalize_longitude(lon),
+            lat,
+        }
+    }
+
+    /// Create a new Galactic coordinate with values in degrees
+    pub fn from_degrees(lon_deg: f64, lat_deg: f64) -> Self {
+        Self::new(lon_deg.to_radians(), lat_deg.to_radians())
+    }
+
+    /// Get galactic longitude in degrees, in [0, 360)
+    pub fn lon_degrees(&self) -> f64 {
+        normalize_longitude(self.lon).to_degrees()
+    }
+
+    /// Get galactic latitude in degrees
+    pub fn lat_degrees(&self) -> f64 {
+        self.lat.to_degrees()
+    }
+}
+
 impl InertialFrame for Equatorial {
     fn to_cartesian(&self) -> Cartesian3 {
         let cos_dec = self.dec.cos();
@@ -568,9 +631,9 @@ mod tests {
         let x_initial = Vector3::new(1.0, 7.0, 9.0);
         println!("x_initial: {:?}", x_initial);
 
-        let xp = EC_TO_EQ.clone() * x_initial;
+        let xp = *EC_TO_EQ * x_initial;
 
-        let x_final = EQ_TO_EC.clone() * xp;
+        let x_final = *EQ_TO_EC * xp;
         println!("x_final: {:?}", x_final);
 
         assert_relative_eq!(x_initial.x, x_final.x, epsilon = 1e-10);
@@ -652,7 +715,7 @@ mod tests {
 
     #[test]
     fn test_ecliptic_to_equatorial_rt_rand() {
-        let mut rng = StdRng::seed_from_u64(23423 as u64);
+        let mut rng = StdRng::seed_from_u64(23423_u64);
         for i in 0..100 {
             println!("Random test {}", i);
             let ra = rng.gen_range(0.0..(2.0 * PI));
@@ -768,4 +831,19 @@ mod tests {
         assert_relative_eq!(galactic.lon, ec2ga.lon, epsilon = 1e-4);
         assert_relative_eq!(galactic.lat, ec2ga.lat, epsilon = 1e-4);
     }
+
+    #[test]
+    fn test_galactic_degrees() {
+        // Sgr A* sits just west of the galactic center
+        let sgr_a = Galactic::from(Equatorial::from_degrees(266.41683, -29.00781));
+        assert_relative_eq!(sgr_a.lon_degrees(), 359.9443, epsilon = 1e-3);
+        assert_relative_eq!(sgr_a.lat_degrees(), -0.0462, epsilon = 1e-3);
+
+        let ngp: Equatorial = Galactic::from_degrees(123.0, 90.0).into();
+        assert_relative_eq!(ngp.ra_degrees(), 192.8595, epsilon = 1e-3);
+        assert_relative_eq!(ngp.dec_degrees(), 27.1283, epsilon = 1e-3);
+
+        assert_relative_eq!(Galactic::from_degrees(-30.0, 0.0).lon_degrees(), 330.0);
+        assert_relative_eq!(Ecliptic::from_degrees(400.0, 5.0).lon_degrees(), 40.0);
+    }
 }
@@ -1,2 +1,43 @@
+//! Reference frames and the rotations between them
+//!
+//! Besides the inertial Equatorial, Ecliptic and Galactic systems, the
+//! [`horizontal`] module provides site- and time-dependent alt/az coordinates
+//! and [`body_fixed`] the rotating frames of binary PCK kernels.
+//! [`CelestialFrame`] converts between the inertial systems referred to the
+//! equator and equinox of any date, and [`inertial::Equatorial::from_fk4`]
+//! reads the B1950 positions of older catalogs.
+
+pub mod body_fixed;
+mod fk4;
+mod frame;
 mod frame_rotations;
+pub mod horizontal;
 pub mod inertial;
+mod of_date;
+
+pub use body_fixed::BodyFixed;
+pub use fk4::fk4_precession;
+pub use frame::{icrs_to_fk5_j2000, Frame, FrameMismatch};
+pub(crate) use frame_rotations::INERTIAL_FRAMES;
+pub use horizontal::{Atmosphere, Horizontal, HorizontalFrame, Refraction};
+pub use of_date::{CelestialFrame, Equinox};
+
+use nalgebra::Matrix3;
+
+/// Rotation matrix that turns a vector by `angle` radians about the x axis
+pub fn rot_x(angle: f64) -> Matrix3<f64> {
+    let (s, c) = angle.sin_cos();
+    Matrix3::new(1.0, 0.0, 0.0, 0.0, c, -s, 0.0, s, c)
+}
+
+/// Rotation matrix that turns a vector by `angle` radians about the y axis
+pub fn rot_y(angle: f64) -> Matrix3<f64> {
+    let (s, c) = angle.sin_cos();
+    Matrix3::new(c, 0.0, s, 0.0, 1.0, 0.0, -s, 0.0, c)
+}
+
+/// Rotation matrix that turns a vector by `angle` radians about the z axis
+pub fn rot_z(angle: f64) -> Matrix3<f64> {
+    let (s, c) = angle.sin_cos();
+    Matrix3::new(c, -s, 0.0, s, c, 0.0, 0.0, 0.0, 1.0)
+}
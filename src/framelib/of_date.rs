@@ -0,0 +1,190 @@
+//! Celestial coordinate systems referred to the equator and equinox of a date
+//!
+//! The `From` conversions between [`Equatorial`](super::inertial::Equatorial),
+//! [`Ecliptic`](super::inertial::Ecliptic) and
+//! [`Galactic`](super::inertial::Galactic) use fixed J2000 rotations. A
+//! [`CelestialFrame`] can also name a system that moves with precession and
+//! nutation, such as the mean ecliptic and equinox of date, and converts
+//! between any two of them at a given time:
+//!
+//! ```
+//! use starfield::framelib::inertial::{Ecliptic, Equatorial};
+//! use starfield::framelib::{CelestialFrame, Equinox};
+//! use starfield::Timescale;
+//!
+//! let ts = Timescale::default();
+//! let t = ts.tt_jd(2_460_676.5, None); // 2025 January 1
+//! let spica = Equatorial::from_degrees(201.298, -11.161);
+//!
+//! let j2000: Ecliptic = CelestialFrame::Equatorial(Equinox::J2000).convert(
+//!     &spica,
+//!     &CelestialFrame::Ecliptic(Equinox::J2000),
+//!     &t,
+//! );
+//! let of_date: Ecliptic = CelestialFrame::Equatorial(Equinox::J2000).convert(
+//!     &spica,
+//!     &CelestialFrame::Ecliptic(Equinox::MeanOfDate),
+//!     &t,
+//! );
+//! // A quarter century of precession, about 50.3" a year
+//! let drift = of_date.lon_degrees() - j2000.lon_degrees();
+//! assert!((drift * 3600.0 - 25.0 * 50.3).abs() < 5.0);
+//! ```
+
+use super::inertial::{InertialFrame, EQ_TO_EC, EQ_TO_GAL};
+use super::rot_x;
+use crate::accuracy::NutationModel;
+use crate::coordinates::cartesian::Cartesian3;
+use crate::nutationlib::{precession_nutation_matrix, true_obliquity};
+use crate::precessionlib::{compute_precession, mean_obliquity};
+use crate::time::Time;
+use nalgebra::Matrix3;
+
+/// Nutation series used for the true equator and equinox of date
+const NUTATION: NutationModel = NutationModel::Iau2000B;
+
+/// Equator and equinox that a coordinate system is referred to
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Equinox {
+    /// Mean equator and equinox of J2000.0
+    J2000,
+    /// Mean equator and equinox of date, moved by precession only
+    MeanOfDate,
+    /// True equator and equinox of date, moved by precession and nutation
+    TrueOfDate,
+}
+
+/// Celestial coordinate system, possibly tied to the date of observation
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CelestialFrame {
+    /// Right ascension and declination
+    Equatorial(Equinox),
+    /// Ecliptic longitude and latitude
+    Ecliptic(Equinox),
+    /// IAU 1958 galactic coordinates, fixed to the J2000 equator
+    Galactic,
+}
+
+impl CelestialFrame {
+    /// Whether the frame's axes stay put as time passes
+    pub fn is_fixed(&self) -> bool {
+        !matches!(
+            self,
+            CelestialFrame::Equatorial(Equinox::MeanOfDate | Equinox::TrueOfDate)
+                | CelestialFrame::Ecliptic(Equinox::MeanOfDate | Equinox::TrueOfDate)
+        )
+    }
+
+    /// Rotation matrix from J2000 equatorial coordinates into this frame
+    fn rotation_from_j2000(&self, time: &Time) -> Matrix3<f64> {
+        match self {
+            CelestialFrame::Equatorial(Equinox::J2000) => Matrix3::identity(),
+            CelestialFrame::Ecliptic(Equinox::J2000) => *EQ_TO_EC,
+            CelestialFrame::Galactic => *EQ_TO_GAL,
+            CelestialFrame::Equatorial(Equinox::MeanOfDate) => compute_precession(time.tdb()),
+            CelestialFrame::Equatorial(Equinox::TrueOfDate) => {
+                precession_nutation_matrix(time.tt(), NUTATION)
+            }
+            CelestialFrame::Ecliptic(Equinox::MeanOfDate) => {
+                rot_x(-mean_obliquity(time.tdb())) * compute_precession(time.tdb())
+            }
+            CelestialFrame::Ecliptic(Equinox::TrueOfDate) => {
+                rot_x(-true_obliquity(time.tt(), NUTATION))
+                    * precession_nutation_matrix(time.tt(), NUTATION)
+            }
+        }
+    }
+
+    /// Rotation matrix taking vectors in this frame into `other` at `time`
+    ///
+    /// Between frames that do not move, `time` is ignored and the same fixed
+    /// J2000 matrices as the `From` conversions are used.
+    pub fn transform_to(&self, other: &CelestialFrame, time: &Time) -> Matrix3<f64> {
+        if self == other {
+            Matrix3::identity()
+        } else if *self == CelestialFrame::Equatorial(Equinox::J2000) {
+            other.rotation_from_j2000(time)
+        } else {
+            // Rotations are orthogonal, so the transpose undoes one
+            other.rotation_from_j2000(time) * self.rotation_from_j2000(time).transpose()
+        }
+    }
+
+    /// Coordinates in this frame re-expressed in `other` at `time`
+    pub fn convert<C: InertialFrame, D: InertialFrame>(
+        &self,
+        coords: &C,
+        other: &CelestialFrame,
+        time: &Time,
+    ) -> D {
+        let rotated = self.transform_to(other, time) * coords.to_cartesian().to_vector3();
+        D::from_cartesian(Cartesian3::from_vector3(rotated))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::constants::{ASEC2RAD, J2000};
+    use crate::framelib::inertial::{Ecliptic, Equatorial, Galactic};
+    use crate::time::Timescale;
+    use approx::assert_relative_eq;
+
+    #[test]
+    fn test_fixed_frames_match_static_conversions() {
+        let ts = Timescale::default();
+        let t = ts.tt_jd(2_470_000.5, None);
+        let eq = Equatorial::from_degrees(266.405, -28.936);
+
+        let gal: Galactic =
+            CelestialFrame::Equatorial(Equinox::J2000).convert(&eq, &CelestialFrame::Galactic, &t);
+        let expected: Galactic = eq.into();
+        assert_relative_eq!(gal.lon, expected.lon, epsilon = 1e-12);
+        assert_relative_eq!(gal.lat, expected.lat, epsilon = 1e-12);
+
+        let ec: Ecliptic =
+            CelestialFrame::Galactic.convert(&gal, &CelestialFrame::Ecliptic(Equinox::J2000), &t);
+        let expected: Ecliptic = eq.into();
+        assert_relative_eq!(ec.lon, expected.lon, epsilon = 1e-12);
+        assert_relative_eq!(ec.lat, expected.lat, epsilon = 1e-12);
+
+        assert!(CelestialFrame::Galactic.is_fixed());
+        assert!(!CelestialFrame::Ecliptic(Equinox::MeanOfDate).is_fixed());
+    }
+
+    #[test]
+    fn test_of_date_frames() {
+        let ts = Timescale::default();
+        let eq = Equatorial::from_degrees(201.298, -11.161);
+        let mean_ecliptic = CelestialFrame::Ecliptic(Equinox::MeanOfDate);
+        let j2000 = CelestialFrame::Equatorial(Equinox::J2000);
+
+        // At J2000 the ecliptic of date is the J2000 ecliptic, up to the
+        // 0.04" between the IAU 2006 and SPICE obliquities
+        let t0 = ts.tt_jd(J2000, None);
+        let at_epoch: Ecliptic = j2000.convert(&eq, &mean_ecliptic, &t0);
+        let fixed: Ecliptic = eq.into();
+        assert!(at_epoch.angle_between(&fixed) < 0.05 * ASEC2RAD);
+
+        // A century on, longitudes have precessed by about 5029" and
+        // latitudes barely moved
+        let t1 = ts.tt_jd(J2000 + 36525.0, None);
+        let later: Ecliptic = j2000.convert(&eq, &mean_ecliptic, &t1);
+        let drift = (later.lon - fixed.lon) / ASEC2RAD;
+        assert!((drift - 5029.0).abs() < 5.0, "{}", drift);
+        assert!((later.lat - fixed.lat).abs() < 50.0 * ASEC2RAD);
+
+        // Nutation separates true from mean by under 20"
+        let true_ecliptic: Ecliptic =
+            j2000.convert(&eq, &CelestialFrame::Ecliptic(Equinox::TrueOfDate), &t1);
+        let nutation = true_ecliptic.angle_between(&later);
+        assert!(nutation > 0.0 && nutation < 20.0 * ASEC2RAD);
+
+        // Converting back undoes the rotation
+        let back: Equatorial = mean_ecliptic.convert(&later, &j2000, &t1);
+        assert_relative_eq!(back.ra, eq.ra, epsilon = 1e-12);
+        assert_relative_eq!(back.dec, eq.dec, epsilon = 1e-12);
+        let r = CelestialFrame::Galactic.transform_to(&mean_ecliptic, &t1);
+        assert_relative_eq!(r * r.transpose(), Matrix3::identity(), epsilon = 1e-14);
+    }
+}
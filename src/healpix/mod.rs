@@ -0,0 +1,611 @@
+//! HEALPix tessellation of the sphere
+//!
+//! HEALPix divides the sphere into `12 nside²` pixels of equal area, laid
+//! out in rings of constant latitude (Górski et al. 2005, ApJ 622, 759).
+//! Pixels can be numbered in two schemes:
+//!
+//! - RING, running around each ring from the north pole down, which suits
+//!   maps and disc queries;
+//! - NESTED, in which the four children of a pixel at the next resolution
+//!   share its number shifted left two bits, which suits sharding a catalog
+//!   since nearby stars get nearby numbers. NESTED needs `nside` to be a
+//!   power of two.
+//!
+//! Numbers match other HEALPix software, so shards and maps can be shared
+//! with it. Positions are longitude and latitude in degrees in whatever
+//! frame the pixels are laid out in; [`Healpix`] takes equatorial positions
+//! directly.
+//!
+//! ```
+//! use starfield::healpix::{Healpix, PixelOrdering};
+//!
+//! let grid = Healpix::new(64, PixelOrdering::Nested)?;
+//! let pixel = grid.ang2pix(83.82, -5.39);
+//! // Pixels overlapping a 1° field around the Orion Nebula
+//! let field = grid.query_disc(83.82, -5.39, 0.5);
+//! assert!(field.contains(&pixel));
+//! # Ok::<(), starfield::StarfieldError>(())
+//! ```
+
+use crate::catalogs::StarPosition;
+use crate::constants::{DEG2RAD, RAD2DEG};
+use crate::coordinates::Equatorial;
+use crate::StarfieldError;
+use std::collections::BTreeMap;
+use std::f64::consts::{FRAC_PI_2, PI};
+
+/// Largest `nside` supported, keeping pixel numbers within 64 bits
+pub const MAX_NSIDE: u32 = 1 << 29;
+
+/// Face offsets of the base pixels in ring units (`jrll`) and longitude
+/// units (`jpll`)
+const FACE_RING: [i64; 12] = [2, 2, 2, 2, 3, 3, 3, 3, 4, 4, 4, 4];
+const FACE_PHI: [i64; 12] = [1, 3, 5, 7, 0, 2, 4, 6, 1, 3, 5, 7];
+
+/// Pixel numbering scheme
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum PixelOrdering {
+    /// Numbered around each ring from the north pole down
+    Ring,
+    /// Numbered hierarchically within each of the 12 base pixels
+    Nested,
+}
+
+/// A HEALPix grid of given resolution and numbering
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Healpix {
+    nside: u32,
+    ordering: PixelOrdering,
+}
+
+impl Healpix {
+    /// Grid with resolution `nside`, which must be a power of two for
+    /// NESTED numbering
+    pub fn new(nside: u32, ordering: PixelOrdering) -> Result<Self, StarfieldError> {
+        if nside == 0 || nside > MAX_NSIDE {
+            return Err(StarfieldError::DataError(format!(
+                "HEALPix nside must be between 1 and {}, got {}",
+                MAX_NSIDE, nside
+            )));
+        }
+        if ordering == PixelOrdering::Nested && !nside.is_power_of_two() {
+            return Err(StarfieldError::DataError(format!(
+                "NESTED HEALPix numbering needs a power of two nside, got {}",
+                nside
+            )));
+        }
+        Ok(Self { nside, ordering })
+    }
+
+    /// Grid at `order`, that is `nside = 2^order`, with NESTED numbering
+    pub fn nested_order(order: u32) -> Result<Self, StarfieldError> {
+        if order > 29 {
+            return Err(StarfieldError::DataError(format!(
+                "HEALPix order must be at most 29, got {}",
+                order
+            )));
+        }
+        Self::new(1 << order, PixelOrdering::Nested)
+    }
+
+    /// Resolution parameter
+    pub fn nside(&self) -> u32 {
+        self.nside
+    }
+
+    /// Pixel numbering
+    pub fn ordering(&self) -> PixelOrdering {
+        self.ordering
+    }
+
+    /// Number of pixels
+    pub fn npix(&self) -> usize {
+        npix(self.nside)
+    }
+
+    /// Area of each pixel in square degrees
+    pub fn pixel_area_deg2(&self) -> f64 {
+        4.0 * PI * RAD2DEG * RAD2DEG / self.npix() as f64
+    }
+
+    /// Pixel containing a position
+    pub fn ang2pix(&self, longitude_deg: f64, latitude_deg: f64) -> usize {
+        match self.ordering {
+            PixelOrdering::Ring => ang2pix_ring(self.nside, longitude_deg, latitude_deg),
+            PixelOrdering::Nested => ang2pix_nest(self.nside, longitude_deg, latitude_deg),
+        }
+    }
+
+    /// Longitude and latitude in degrees of a pixel's center
+    pub fn pix2ang(&self, pixel: usize) -> (f64, f64) {
+        match self.ordering {
+            PixelOrdering::Ring => pix2ang_ring(self.nside, pixel),
+            PixelOrdering::Nested => pix2ang_nest(self.nside, pixel),
+        }
+    }
+
+    /// Pixel containing an equatorial position
+    pub fn pixel_of(&self, position: &Equatorial) -> usize {
+        self.ang2pix(position.ra_degrees(), position.dec_degrees())
+    }
+
+    /// Center of a pixel as an equatorial position
+    pub fn pixel_center(&self, pixel: usize) -> Equatorial {
+        let (lon, lat) = self.pix2ang(pixel);
+        Equatorial::from_degrees(lon, lat)
+    }
+
+    /// Every pixel that overlaps the disc of `radius_deg` around a
+    /// position, in increasing order
+    ///
+    /// The search is inclusive: no overlapping pixel is missed, though a
+    /// few pixels just outside the disc may be returned.
+    pub fn query_disc(&self, longitude_deg: f64, latitude_deg: f64, radius_deg: f64) -> Vec<usize> {
+        let mut pixels = query_disc_ring(self.nside, longitude_deg, latitude_deg, radius_deg);
+        if self.ordering == PixelOrdering::Nested {
+            for pixel in &mut pixels {
+                *pixel = ring2nest(self.nside, *pixel);
+            }
+            pixels.sort_unstable();
+        }
+        pixels
+    }
+
+    /// Group stars by the pixel containing them
+    pub fn partition<S, I>(&self, stars: I) -> BTreeMap<usize, Vec<S>>
+    where
+        S: StarPosition,
+        I: IntoIterator<Item = S>,
+    {
+        let mut shards: BTreeMap<usize, Vec<S>> = BTreeMap::new();
+        for star in stars {
+            shards
+                .entry(self.ang2pix(star.ra(), star.dec()))
+                .or_default()
+                .push(star);
+        }
+        shards
+    }
+}
+
+/// Number of pixels in a grid of resolution `nside`
+pub fn npix(nside: u32) -> usize {
+    12 * nside as usize * nside as usize
+}
+
+/// Longitude in quarter turns, in `[0, 4)`
+fn quarter_turns(longitude_deg: f64) -> f64 {
+    ((longitude_deg * DEG2RAD).rem_euclid(2.0 * PI) / FRAC_PI_2).min(4.0 - 1e-12)
+}
+
+/// RING pixel containing a position (ang2pix)
+pub fn ang2pix_ring(nside: u32, longitude_deg: f64, latitude_deg: f64) -> usize {
+    let n = nside as i64;
+    let z = (latitude_deg.clamp(-90.0, 90.0) * DEG2RAD).sin();
+    let za = z.abs();
+    let tt = quarter_turns(longitude_deg);
+
+    let pixel = if za <= 2.0 / 3.0 {
+        // Equatorial belt
+        let temp1 = n as f64 * (0.5 + tt);
+        let temp2 = n as f64 * z * 0.75;
+        let jp = (temp1 - temp2) as i64;
+        let jm = (temp1 + temp2) as i64;
+        let ring = n + 1 + jp - jm;
+        let kshift = 1 - (ring & 1);
+        let ip = ((jp + jm - n + kshift + 1) / 2).rem_euclid(4 * n);
+        2 * n * (n - 1) + (ring - 1) * 4 * n + ip
+    } else {
+        // Polar caps
+        let tp = tt.fract();
+        let tmp = n as f64 * (3.0 * (1.0 - za)).sqrt();
+        let jp = (tp * tmp) as i64;
+        let jm = ((1.0 - tp) * tmp) as i64;
+        let ring = jp + jm + 1;
+        let ip = ((tt * ring as f64) as i64).rem_euclid(4 * ring);
+        if z > 0.0 {
+            2 * ring * (ring - 1) + ip
+        } else {
+            12 * n * n - 2 * ring * (ring + 1) + ip
+        }
+    };
+    pixel as usize
+}
+
+/// Longitude and latitude in degrees of a RING pixel's center (pix2ang)
+pub fn pix2ang_ring(nside: u32, pixel: usize) -> (f64, f64) {
+    let n = nside as i64;
+    let pix = pixel as i64;
+    let ncap = 2 * n * (n - 1);
+    let npix = 12 * n * n;
+
+    let (z, phi) = if pix < ncap {
+        let ring = (1 + isqrt(1 + 2 * pix)) / 2;
+        let iphi = pix + 1 - 2 * ring * (ring - 1);
+        let z = 1.0 - (ring * ring) as f64 / (3 * n * n) as f64;
+        (z, (iphi as f64 - 0.5) * FRAC_PI_2 / ring as f64)
+    } else if pix < npix - ncap {
+        let ip = pix - ncap;
+        let ring = ip / (4 * n) + n;
+        let iphi = ip % (4 * n) + 1;
+        let shift = if (ring + n) % 2 == 1 { 1.0 } else { 0.5 };
+        let z = (2 * n - ring) as f64 * 2.0 / (3 * n) as f64;
+        (z, (iphi as f64 - shift) * FRAC_PI_2 / n as f64)
+    } else {
+        let ip = npix - pix;
+        let ring = (1 + isqrt(2 * ip - 1)) / 2;
+        let iphi = 4 * ring + 1 - (ip - 2 * ring * (ring - 1));
+        let z = (ring * ring) as f64 / (3 * n * n) as f64 - 1.0;
+        (z, (iphi as f64 - 0.5) * FRAC_PI_2 / ring as f64)
+    };
+    (phi * RAD2DEG, z.clamp(-1.0, 1.0).asin() * RAD2DEG)
+}
+
+/// NESTED pixel containing a position (ang2pix), `nside` a power of two
+pub fn ang2pix_nest(nside: u32, longitude_deg: f64, latitude_deg: f64) -> usize {
+    let n = nside as i64;
+    let z = (latitude_deg.clamp(-90.0, 90.0) * DEG2RAD).sin();
+    let za = z.abs();
+    let tt = quarter_turns(longitude_deg);
+
+    let (face, ix, iy) = if za <= 2.0 / 3.0 {
+        let temp1 = n as f64 * (0.5 + tt);
+        let temp2 = n as f64 * z * 0.75;
+        let jp = (temp1 - temp2) as i64;
+        let jm = (temp1 + temp2) as i64;
+        let (ifp, ifm) = (jp / n, jm / n);
+        let face = if ifp == ifm {
+            ifp | 4
+        } else if ifp < ifm {
+            ifp
+        } else {
+            ifm + 8
+        };
+        (face, jm & (n - 1), n - (jp & (n - 1)) - 1)
+    } else {
+        let ntt = (tt as i64).min(3);
+        let tp = tt - ntt as f64;
+        let tmp = n as f64 * (3.0 * (1.0 - za)).sqrt();
+        let jp = ((tp * tmp) as i64).min(n - 1);
+        let jm = (((1.0 - tp) * tmp) as i64).min(n - 1);
+        if z >= 0.0 {
+            (ntt, n - jm - 1, n - jp - 1)
+        } else {
+            (ntt + 8, jp, jm)
+        }
+    };
+    (face as usize) * (n * n) as usize + interleave(ix as u64, iy as u64) as usize
+}
+
+/// Longitude and latitude in degrees of a NESTED pixel's center (pix2ang)
+pub fn pix2ang_nest(nside: u32, pixel: usize) -> (f64, f64) {
+    let n = nside as i64;
+    let face_pixels = (n * n) as usize;
+    let face = pixel / face_pixels;
+    let (ix, iy) = deinterleave((pixel % face_pixels) as u64);
+    let (ix, iy) = (ix as i64, iy as i64);
+
+    let jr = FACE_RING[face] * n - ix - iy - 1;
+    let (ring, z, kshift) = if jr < n {
+        (jr, 1.0 - (jr * jr) as f64 / (3 * n * n) as f64, 0)
+    } else if jr > 3 * n {
+        let nr = 4 * n - jr;
+        (nr, (nr * nr) as f64 / (3 * n * n) as f64 - 1.0, 0)
+    } else {
+        (n, (2 * n - jr) as f64 * 2.0 / (3 * n) as f64, (jr - n) & 1)
+    };
+
+    let mut jp = (FACE_PHI[face] * ring + ix - iy + 1 + kshift) / 2;
+    if jp > 4 * n {
+        jp -= 4 * n;
+    }
+    if jp < 1 {
+        jp += 4 * n;
+    }
+    let phi = (jp as f64 - (kshift + 1) as f64 * 0.5) * FRAC_PI_2 / ring as f64;
+    (phi * RAD2DEG, z.clamp(-1.0, 1.0).asin() * RAD2DEG)
+}
+
+/// Convert a RING pixel number to NESTED
+pub fn ring2nest(nside: u32, pixel: usize) -> usize {
+    let (lon, lat) = pix2ang_ring(nside, pixel);
+    ang2pix_nest(nside, lon, lat)
+}
+
+/// Convert a NESTED pixel number to RING
+pub fn nest2ring(nside: u32, pixel: usize) -> usize {
+    let (lon, lat) = pix2ang_nest(nside, pixel);
+    ang2pix_ring(nside, lon, lat)
+}
+
+/// RING pixels overlapping a disc, in increasing order
+fn query_disc_ring(
+    nside: u32,
+    longitude_deg: f64,
+    latitude_deg: f64,
+    radius_deg: f64,
+) -> Vec<usize> {
+    let n = nside as i64;
+    // Widen the disc by the largest distance from a pixel center to its
+    // corners, so every pixel reaching into the disc has its center inside
+    let pixel_radius = 1.5 * (4.0 * PI / npix(nside) as f64).sqrt();
+    let radius = radius_deg * DEG2RAD + pixel_radius;
+    if radius >= PI {
+        return (0..npix(nside)).collect();
+    }
+
+    let theta0 = (90.0 - latitude_deg.clamp(-90.0, 90.0)) * DEG2RAD;
+    let phi0 = longitude_deg * DEG2RAD;
+    let (sin_t0, cos_t0) = theta0.sin_cos();
+    let cos_r = radius.cos();
+    let (theta_min, theta_max) = ((theta0 - radius).max(0.0), (theta0 + radius).min(PI));
+
+    let mut pixels = Vec::new();
+    for ring in 1..4 * n {
+        let (z, count, first, shifted) = ring_info(n, ring);
+        let theta = z.acos();
+        if theta < theta_min || theta > theta_max {
+            continue;
+        }
+        let sin_t = (1.0 - z * z).sqrt();
+        let denominator = sin_t * sin_t0;
+        let cos_dphi = if denominator > 1e-15 {
+            (cos_r - z * cos_t0) / denominator
+        } else {
+            -1.0
+        };
+        if cos_dphi > 1.0 {
+            continue;
+        }
+
+        let step = 2.0 * PI / count as f64;
+        let offset = if shifted { 0.5 } else { 0.0 };
+        if cos_dphi <= -1.0 {
+            pixels.extend((0..count).map(|i| (first + i) as usize));
+            continue;
+        }
+        let dphi = cos_dphi.acos();
+        let lo = ((phi0 - dphi) / step - offset).ceil() as i64;
+        let hi = ((phi0 + dphi) / step - offset).floor() as i64;
+        if hi - lo + 1 >= count {
+            pixels.extend((0..count).map(|i| (first + i) as usize));
+        } else {
+            pixels.extend((lo..=hi).map(|i| (first + i.rem_euclid(count)) as usize));
+        }
+    }
+    pixels.sort_unstable();
+    pixels.dedup();
+    pixels
+}
+
+/// For ring `ring` (1 to 4 nside - 1): cos colatitude, pixel count, first
+/// pixel, and whether the pixel centers are offset by half a pixel
+fn ring_info(n: i64, ring: i64) -> (f64, i64, i64, bool) {
+    let npix = 12 * n * n;
+    if ring < n {
+        let z = 1.0 - (ring * ring) as f64 / (3 * n * n) as f64;
+        (z, 4 * ring, 2 * ring * (ring - 1), true)
+    } else if ring <= 3 * n {
+        let z = (2 * n - ring) as f64 * 2.0 / (3 * n) as f64;
+        let first = 2 * n * (n - 1) + (ring - n) * 4 * n;
+        (z, 4 * n, first, (ring + n) % 2 == 0)
+    } else {
+        let south = 4 * n - ring;
+        let z = (south * south) as f64 / (3 * n * n) as f64 - 1.0;
+        (z, 4 * south, npix - 2 * south * (south + 1), true)
+    }
+}
+
+/// Spread the bits of `x` and `y` into the even and odd bits of a number
+fn interleave(x: u64, y: u64) -> u64 {
+    spread_bits(x) | (spread_bits(y) << 1)
+}
+
+/// Inverse of [`interleave`]
+fn deinterleave(v: u64) -> (u64, u64) {
+    (compress_bits(v), compress_bits(v >> 1))
+}
+
+fn spread_bits(v: u64) -> u64 {
+    let mut v = v & 0xffff_ffff;
+    v = (v | (v << 16)) & 0x0000_ffff_0000_ffff;
+    v = (v | (v << 8)) & 0x00ff_00ff_00ff_00ff;
+    v = (v | (v << 4)) & 0x0f0f_0f0f_0f0f_0f0f;
+    v = (v | (v << 2)) & 0x3333_3333_3333_3333;
+    (v | (v << 1)) & 0x5555_5555_5555_5555
+}
+
+fn compress_bits(v: u64) -> u64 {
+    let mut v = v & 0x5555_5555_5555_5555;
+    v = (v | (v >> 1)) & 0x3333_3333_3333_3333;
+    v = (v | (v >> 2)) & 0x0f0f_0f0f_0f0f_0f0f;
+    v = (v | (v >> 4)) & 0x00ff_00ff_00ff_00ff;
+    v = (v | (v >> 8)) & 0x0000_ffff_0000_ffff;
+    (v | (v >> 16)) & 0xffff_ffff
+}
+
+/// Integer square root, exact for the pixel counts of any practical grid
+fn isqrt(x: i64) -> i64 {
+    let mut r = (x as f64).sqrt() as i64;
+    while r * r > x {
+        r -= 1;
+    }
+    while (r + 1) * (r + 1) <= x {
+        r += 1;
+    }
+    r
+}
+
+/// Values on a HEALPix grid, RING ordering
+#[derive(Debug, Clone, PartialEq)]
+pub struct HealpixMap {
+    nside: u32,
+    values: Vec<f64>,
+}
+
+impl HealpixMap {
+    /// Wrap `values`, which must hold one entry per pixel
+    pub fn new(nside: u32, values: Vec<f64>) -> Result<Self, StarfieldError> {
+        Healpix::new(nside, PixelOrdering::Ring)?;
+        if values.len() != npix(nside) {
+            return Err(StarfieldError::DataError(format!(
+                "HEALPix map with nside {} needs {} values, got {}",
+                nside,
+                npix(nside),
+                values.len()
+            )));
+        }
+        Ok(Self { nside, values })
+    }
+
+    /// Evaluate `f(longitude_deg, latitude_deg)` at every pixel center
+    pub fn from_fn(nside: u32, f: impl Fn(f64, f64) -> f64) -> Self {
+        let nside = nside.max(1);
+        let values = (0..npix(nside))
+            .map(|pix| {
+                let (lon, lat) = pix2ang_ring(nside, pix);
+                f(lon, lat)
+            })
+            .collect();
+        Self { nside, values }
+    }
+
+    /// Resolution parameter
+    pub fn nside(&self) -> u32 {
+        self.nside
+    }
+
+    /// Number of pixels
+    pub fn npix(&self) -> usize {
+        self.values.len()
+    }
+
+    /// Pixel values in RING order
+    pub fn values(&self) -> &[f64] {
+        &self.values
+    }
+
+    /// Pixel containing a position
+    pub fn pixel(&self, longitude_deg: f64, latitude_deg: f64) -> usize {
+        ang2pix_ring(self.nside, longitude_deg, latitude_deg)
+    }
+
+    /// Longitude and latitude in degrees of a pixel's center
+    pub fn pixel_center(&self, pixel: usize) -> (f64, f64) {
+        pix2ang_ring(self.nside, pixel)
+    }
+
+    /// Value of the pixel containing a position
+    pub fn value(&self, longitude_deg: f64, latitude_deg: f64) -> f64 {
+        self.values[self.pixel(longitude_deg, latitude_deg)]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::catalogs::{HipparcosCatalog, StarCatalog, StarData};
+
+    #[test]
+    fn test_pixel_centers_roundtrip() {
+        for nside in [1, 2, 4, 7, 16] {
+            for pix in 0..npix(nside) {
+                let (lon, lat) = pix2ang_ring(nside, pix);
+                assert_eq!(ang2pix_ring(nside, lon, lat), pix, "nside {}", nside);
+            }
+        }
+        for nside in [1, 2, 8, 32] {
+            for pix in 0..npix(nside) {
+                let (lon, lat) = pix2ang_nest(nside, pix);
+                assert_eq!(ang2pix_nest(nside, lon, lat), pix, "nside {}", nside);
+                assert_eq!(nest2ring(nside, ring2nest(nside, pix)), pix);
+            }
+        }
+    }
+
+    #[test]
+    fn test_known_pixels() {
+        // The four polar pixels of the base resolution, then the equator
+        assert_eq!(pix2ang_ring(1, 0), (45.0, (2.0f64 / 3.0).asin() * RAD2DEG));
+        assert_eq!(ang2pix_ring(1, 10.0, 89.0), 0);
+        assert_eq!(ang2pix_ring(1, 100.0, 89.0), 1);
+        assert_eq!(ang2pix_ring(1, 0.0, 0.0), 4);
+        assert_eq!(ang2pix_ring(1, 10.0, -89.0), 8);
+        assert_eq!(ang2pix_ring(4, 359.999, -90.0), npix(4) - 1);
+
+        // At nside 1 the schemes differ only in the equatorial faces
+        assert_eq!(ang2pix_nest(1, 100.0, 89.0), 1);
+        assert_eq!(ring2nest(2, 0), 3);
+        assert_eq!(ring2nest(2, 47), 44);
+        // Children of a NESTED pixel share its number shifted two bits
+        let (lon, lat) = pix2ang_nest(8, 4 * 37 + 2);
+        assert_eq!(ang2pix_nest(4, lon, lat), 37);
+
+        let map = HealpixMap::from_fn(8, |_, lat| lat);
+        assert_eq!(map.npix(), 768);
+        assert!((map.value(123.0, 45.0) - 45.0).abs() < 8.0);
+        assert!(HealpixMap::new(2, vec![0.0; 47]).is_err());
+        assert!(HealpixMap::new(2, vec![0.0; 48]).is_ok());
+        assert!(Healpix::new(12, PixelOrdering::Nested).is_err());
+        assert!(Healpix::new(12, PixelOrdering::Ring).is_ok());
+    }
+
+    #[test]
+    fn test_query_disc_is_inclusive() {
+        for ordering in [PixelOrdering::Ring, PixelOrdering::Nested] {
+            let grid = Healpix::new(16, ordering).unwrap();
+            for &(lon, lat, radius) in &[
+                (83.8, -5.4, 3.0),
+                (10.0, 88.0, 5.0),
+                (200.0, -89.9, 1.0),
+                (359.5, 0.2, 8.0),
+            ] {
+                let center = Equatorial::from_degrees(lon, lat);
+                let disc = grid.query_disc(lon, lat, radius);
+                assert!(disc.windows(2).all(|w| w[0] < w[1]));
+                // Every point inside the disc falls in a returned pixel
+                for i in 0..400 {
+                    let bearing = i as f64 * 0.9;
+                    let r = radius * (i % 20) as f64 / 19.0;
+                    let p = offset(&center, bearing, r);
+                    assert!(disc.contains(&grid.pixel_of(&p)), "{:?}", (lon, lat, i));
+                }
+                // but not much else
+                assert!(
+                    (disc.len() as f64)
+                        < 4.0 * (PI * radius * radius) / grid.pixel_area_deg2() + 40.0
+                );
+            }
+        }
+        assert_eq!(
+            Healpix::new(2, PixelOrdering::Ring)
+                .unwrap()
+                .query_disc(0.0, 0.0, 180.0)
+                .len(),
+            48
+        );
+    }
+
+    /// Position `distance_deg` from `center` along a bearing in degrees
+    fn offset(center: &Equatorial, bearing_deg: f64, distance_deg: f64) -> Equatorial {
+        let (sin_d, cos_d) = (distance_deg * DEG2RAD).sin_cos();
+        let (sin_b, cos_b) = (bearing_deg * DEG2RAD).sin_cos();
+        let (sin_dec, cos_dec) = center.dec.sin_cos();
+        let dec = (sin_dec * cos_d + cos_dec * sin_d * cos_b).asin();
+        let ra = center.ra + (sin_b * sin_d * cos_dec).atan2(cos_d - sin_dec * dec.sin());
+        Equatorial::new(ra, dec)
+    }
+
+    #[test]
+    fn test_partition_catalog() {
+        let catalog = HipparcosCatalog::create_synthetic();
+        let grid = Healpix::nested_order(2).unwrap();
+        let shards = grid.partition(catalog.star_data());
+        assert_eq!(shards.values().map(Vec::len).sum::<usize>(), catalog.len());
+        for (pixel, stars) in &shards {
+            assert!(stars
+                .iter()
+                .all(|s: &StarData| grid.pixel_of(&s.position) == *pixel));
+        }
+    }
+}
@@ -0,0 +1,370 @@
+//! Sub-pixel centroiding with uncertainties
+//!
+//! Two estimators refine a rough position, such as one from
+//! [`super::detect_sources`]:
+//!
+//! - [`windowed_centroid`] - an iterated, Gaussian-weighted center of mass
+//!   (SExtractor's `XWIN_IMAGE`), fast and nearly as precise as a fit
+//! - [`fit_gaussian`] - a least-squares fit of a circular Gaussian plus a
+//!   constant background, which also measures the width and flux
+//!
+//! Both propagate pixel noise into a standard error on each coordinate.
+//! Images are taken to be in electrons: each pixel's variance is the
+//! background variance plus the Poisson variance of the signal above it.
+//!
+//! ```
+//! use ndarray::Array2;
+//! use starfield::image::{detect_sources, fit_gaussian, psf, Background, DetectionConfig, GaussianPsf};
+//!
+//! let mut image = Array2::from_elem((32, 32), 100.0);
+//! psf::add_star(&mut image, &GaussianPsf::new(3.0), 14.37, 16.82, 5000.0);
+//! let source = detect_sources(&image, &DetectionConfig::default())[0];
+//! let background = Background { level: 100.0, noise: 10.0 };
+//! let fit = fit_gaussian(&image, &background, source.x, source.y, 6.0).unwrap();
+//! assert!((fit.centroid.x - 14.37).abs() < 1e-3);
+//! assert!(fit.centroid.x_error < 0.05);
+//! ```
+//!
+//! Positions use the renderer's convention, where pixel column `c` spans
+//! `c <= x < c + 1`.
+
+use super::detect::Background;
+use nalgebra::{Matrix5, Vector5};
+use ndarray::Array2;
+
+/// Ratio of a Gaussian's FWHM to its standard deviation
+const FWHM_PER_SIGMA: f64 = 2.354_820_045_030_949;
+
+/// Iterations after which an estimator gives up converging
+const MAX_ITERATIONS: usize = 50;
+
+/// A measured position and its uncertainty
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Centroid {
+    /// Position x, pixels
+    pub x: f64,
+    /// Position y, pixels
+    pub y: f64,
+    /// Standard error of `x`, pixels
+    pub x_error: f64,
+    /// Standard error of `y`, pixels
+    pub y_error: f64,
+    /// Flux above background, electrons
+    pub flux: f64,
+}
+
+/// A circular Gaussian fitted to a source
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct GaussianFit {
+    /// Fitted position, its uncertainty and the total flux of the Gaussian
+    pub centroid: Centroid,
+    /// Peak height above background, electrons per pixel
+    pub amplitude: f64,
+    /// Standard deviation of the profile, pixels
+    pub sigma_px: f64,
+    /// Fitted background, electrons per pixel
+    pub background: f64,
+    /// Reduced chi-square of the fit
+    pub reduced_chi2: f64,
+}
+
+impl GaussianFit {
+    /// Full width at half maximum of the fitted profile, pixels
+    pub fn fwhm_px(&self) -> f64 {
+        self.sigma_px * FWHM_PER_SIGMA
+    }
+}
+
+/// Variance of a pixel reading `value` above `background`
+fn pixel_variance(background: &Background, value: f64) -> f64 {
+    (background.noise * background.noise + (value - background.level).max(0.0)).max(1e-12)
+}
+
+/// Pixels of `image` within `half_width` of `(x, y)`, as (row, column)
+/// ranges, or `None` if the box misses the image
+fn window(
+    image: &Array2<f64>,
+    x: f64,
+    y: f64,
+    half_width: f64,
+) -> Option<(std::ops::Range<usize>, std::ops::Range<usize>)> {
+    let (rows, columns) = image.dim();
+    let span = |center: f64, len: usize| {
+        let first = (center - half_width).floor().max(0.0) as usize;
+        let last = ((center + half_width).ceil().max(0.0) as usize).min(len);
+        (first < last).then_some(first..last)
+    };
+    Some((span(y, rows)?, span(x, columns)?))
+}
+
+/// Gaussian-windowed centroid near `(x, y)`
+///
+/// The window's FWHM should match the source's; each iteration moves the
+/// estimate by twice the weighted mean offset, which converges on the
+/// center of a Gaussian profile. Returns `None` if the window holds no
+/// positive signal or wanders off the image.
+pub fn windowed_centroid(
+    image: &Array2<f64>,
+    background: &Background,
+    x: f64,
+    y: f64,
+    window_fwhm_px: f64,
+) -> Option<Centroid> {
+    let sigma_w = window_fwhm_px / FWHM_PER_SIGMA;
+    let (mut cx, mut cy) = (x, y);
+
+    for _ in 0..MAX_ITERATIONS {
+        let (rows, columns) = window(image, cx, cy, 4.0 * sigma_w)?;
+        let (mut sum, mut sum_dx, mut sum_dy) = (0.0, 0.0, 0.0);
+        for row in rows {
+            for column in columns.clone() {
+                let (dx, dy) = (column as f64 + 0.5 - cx, row as f64 + 0.5 - cy);
+                let w = (-(dx * dx + dy * dy) / (2.0 * sigma_w * sigma_w)).exp();
+                let signal = image[[row, column]] - background.level;
+                sum += w * signal;
+                sum_dx += w * signal * dx;
+                sum_dy += w * signal * dy;
+            }
+        }
+        if sum <= 0.0 {
+            return None;
+        }
+        let (step_x, step_y) = (2.0 * sum_dx / sum, 2.0 * sum_dy / sum);
+        cx += step_x;
+        cy += step_y;
+        if step_x.hypot(step_y) < 1e-6 {
+            break;
+        }
+    }
+
+    // Propagate the pixel variances through the final weighted mean
+    let (rows, columns) = window(image, cx, cy, 4.0 * sigma_w)?;
+    let (mut sum, mut var_x, mut var_y, mut flux) = (0.0, 0.0, 0.0, 0.0);
+    for row in rows {
+        for column in columns.clone() {
+            let (dx, dy) = (column as f64 + 0.5 - cx, row as f64 + 0.5 - cy);
+            let w = (-(dx * dx + dy * dy) / (2.0 * sigma_w * sigma_w)).exp();
+            let value = image[[row, column]];
+            let variance = pixel_variance(background, value);
+            sum += w * (value - background.level);
+            var_x += w * w * variance * dx * dx;
+            var_y += w * w * variance * dy * dy;
+            flux += value - background.level;
+        }
+    }
+    if sum <= 0.0 {
+        return None;
+    }
+
+    Some(Centroid {
+        x: cx,
+        y: cy,
+        x_error: 2.0 * var_x.sqrt() / sum,
+        y_error: 2.0 * var_y.sqrt() / sum,
+        flux,
+    })
+}
+
+/// Fit a circular Gaussian plus background to the pixels within
+/// `half_width_px` of `(x, y)`
+///
+/// Uses Levenberg-Marquardt, weighting each pixel by its variance; the
+/// uncertainties come from the covariance matrix at the solution. Returns
+/// `None` if the fit does not converge to a positive, finite profile.
+pub fn fit_gaussian(
+    image: &Array2<f64>,
+    background: &Background,
+    x: f64,
+    y: f64,
+    half_width_px: f64,
+) -> Option<GaussianFit> {
+    let (rows, columns) = window(image, x, y, half_width_px)?;
+    let pixels: Vec<(f64, f64, f64, f64)> = rows
+        .flat_map(|row| columns.clone().map(move |column| (row, column)))
+        .map(|(row, column)| {
+            let value = image[[row, column]];
+            (
+                column as f64 + 0.5,
+                row as f64 + 0.5,
+                value,
+                1.0 / pixel_variance(background, value),
+            )
+        })
+        .collect();
+    if pixels.len() <= 5 {
+        return None;
+    }
+
+    // Parameters: amplitude, x, y, sigma, background
+    let peak = pixels.iter().map(|p| p.2).fold(f64::NEG_INFINITY, f64::max);
+    let mut params = Vector5::new(peak - background.level, x, y, 1.5, background.level);
+
+    let evaluate = |p: &Vector5<f64>| -> (f64, Matrix5<f64>, Vector5<f64>) {
+        let (mut chi2, mut normal, mut gradient) = (0.0, Matrix5::zeros(), Vector5::zeros());
+        for &(px, py, value, weight) in &pixels {
+            let (dx, dy) = (px - p[1], py - p[2]);
+            let s2 = p[3] * p[3];
+            let r2 = dx * dx + dy * dy;
+            let e = (-r2 / (2.0 * s2)).exp();
+            let residual = value - (p[4] + p[0] * e);
+            let jacobian = Vector5::new(
+                e,
+                p[0] * e * dx / s2,
+                p[0] * e * dy / s2,
+                p[0] * e * r2 / (s2 * p[3]),
+                1.0,
+            );
+            chi2 += weight * residual * residual;
+            normal += weight * jacobian * jacobian.transpose();
+            gradient += weight * residual * jacobian;
+        }
+        (chi2, normal, gradient)
+    };
+
+    let mut lambda = 1e-3;
+    let (mut chi2, mut normal, mut gradient) = evaluate(&params);
+    for _ in 0..MAX_ITERATIONS {
+        let mut damped = normal;
+        for i in 0..5 {
+            damped[(i, i)] *= 1.0 + lambda;
+        }
+        let step = damped.try_inverse()? * gradient;
+        let trial = params + step;
+        if trial[3] <= 0.0 {
+            lambda *= 10.0;
+            continue;
+        }
+
+        let (trial_chi2, trial_normal, trial_gradient) = evaluate(&trial);
+        if trial_chi2 <= chi2 {
+            let converged = chi2 - trial_chi2 < 1e-10 * chi2.max(1.0);
+            params = trial;
+            (chi2, normal, gradient) = (trial_chi2, trial_normal, trial_gradient);
+            lambda = (lambda / 10.0).max(1e-12);
+            if converged {
+                break;
+            }
+        } else {
+            lambda *= 10.0;
+            if lambda > 1e12 {
+                break;
+            }
+        }
+    }
+
+    let covariance = normal.try_inverse()?;
+    let (amplitude, sigma) = (params[0], params[3].abs());
+    if amplitude <= 0.0 || !params.iter().all(|p| p.is_finite()) {
+        return None;
+    }
+
+    Some(GaussianFit {
+        centroid: Centroid {
+            x: params[1],
+            y: params[2],
+            x_error: covariance[(1, 1)].sqrt(),
+            y_error: covariance[(2, 2)].sqrt(),
+            flux: 2.0 * std::f64::consts::PI * amplitude * sigma * sigma,
+        },
+        amplitude,
+        sigma_px: sigma,
+        background: params[4],
+        reduced_chi2: chi2 / (pixels.len() - 5) as f64,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::image::psf::{add_star, GaussianPsf};
+    use crate::image::{NoiseModel, ReadNoise, ShotNoise};
+    use approx::assert_relative_eq;
+
+    /// Centroids of many noisy frames of one star: mean offsets, the scatter
+    /// of x and the mean reported x error
+    fn monte_carlo(
+        measure: impl Fn(&Array2<f64>, &Background) -> Centroid,
+        truth: (f64, f64),
+    ) -> (f64, f64, f64, f64) {
+        let background = Background {
+            level: 100.0,
+            noise: (100.0f64 + 25.0).sqrt(),
+        };
+        let trials = 200;
+        let mut xs = Vec::new();
+        let (mut dy_sum, mut error_sum) = (0.0, 0.0);
+        for seed in 0..trials {
+            let mut image = Array2::from_elem((25, 25), 100.0);
+            add_star(&mut image, &GaussianPsf::new(3.0), truth.0, truth.1, 4000.0);
+            NoiseModel::new(seed)
+                .with(ShotNoise)
+                .with(ReadNoise::new(5.0))
+                .apply(&mut image, 1.0);
+            let centroid = measure(&image, &background);
+            xs.push(centroid.x - truth.0);
+            dy_sum += centroid.y - truth.1;
+            error_sum += centroid.x_error;
+        }
+        let n = trials as f64;
+        let mean_x = xs.iter().sum::<f64>() / n;
+        let scatter = (xs.iter().map(|d| (d - mean_x).powi(2)).sum::<f64>() / (n - 1.0)).sqrt();
+        (mean_x, dy_sum / n, scatter, error_sum / n)
+    }
+
+    #[test]
+    fn test_windowed_centroid_errors_match_scatter() {
+        let truth = (12.3, 11.8);
+        let (dx, dy, scatter, error) = monte_carlo(
+            |image, background| windowed_centroid(image, background, 12.0, 12.0, 3.0).unwrap(),
+            truth,
+        );
+        assert!(dx.abs() < 0.01 && dy.abs() < 0.01, "bias {} {}", dx, dy);
+        assert_relative_eq!(error, scatter, max_relative = 0.25);
+        assert!(scatter < 0.1);
+    }
+
+    #[test]
+    fn test_gaussian_fit_errors_match_scatter() {
+        let truth = (12.6, 12.25);
+        let (dx, dy, scatter, error) = monte_carlo(
+            |image, background| {
+                fit_gaussian(image, background, 12.0, 12.0, 7.0)
+                    .unwrap()
+                    .centroid
+            },
+            truth,
+        );
+        assert!(dx.abs() < 0.01 && dy.abs() < 0.01, "bias {} {}", dx, dy);
+        assert_relative_eq!(error, scatter, max_relative = 0.25);
+    }
+
+    #[test]
+    fn test_gaussian_fit_recovers_profile() {
+        let mut image = Array2::from_elem((31, 31), 50.0);
+        add_star(&mut image, &GaussianPsf::new(4.0), 15.2, 14.7, 10_000.0);
+        let background = Background {
+            level: 50.0,
+            noise: 1.0,
+        };
+        let fit = fit_gaussian(&image, &background, 15.0, 15.0, 10.0).unwrap();
+        assert_relative_eq!(fit.centroid.x, 15.2, epsilon = 1e-4);
+        assert_relative_eq!(fit.centroid.y, 14.7, epsilon = 1e-4);
+        // Sampling at pixel centres widens the profile by a twelfth of a
+        // pixel squared
+        assert_relative_eq!(
+            fit.fwhm_px(),
+            (4.0f64.powi(2) + FWHM_PER_SIGMA.powi(2) / 12.0).sqrt(),
+            max_relative = 1e-3
+        );
+        assert_relative_eq!(fit.centroid.flux, 10_000.0, max_relative = 1e-3);
+        assert_relative_eq!(fit.background, 50.0, epsilon = 0.01);
+
+        let windowed = windowed_centroid(&image, &background, 14.0, 16.0, 4.0).unwrap();
+        assert_relative_eq!(windowed.x, 15.2, epsilon = 1e-4);
+        assert_relative_eq!(windowed.y, 14.7, epsilon = 1e-4);
+
+        // Nothing to measure in an empty frame
+        let flat = Array2::from_elem((31, 31), 50.0);
+        assert!(windowed_centroid(&flat, &background, 15.0, 15.0, 4.0).is_none());
+    }
+}
@@ -0,0 +1,329 @@
+//! Source extraction: finding stars in images
+//!
+//! [`detect_sources`] estimates the sky background with [`super::sigma_clip`],
+//! keeps the pixels more than a threshold above it, groups them into
+//! 8-connected islands and measures each island: its intensity-weighted
+//! centroid, the flux above background inside it (an isophotal flux, which
+//! misses the faint wings of the profile) and its second moments.
+//!
+//! ```
+//! use ndarray::Array2;
+//! use starfield::image::{detect_sources, psf, DetectionConfig, GaussianPsf};
+//!
+//! let mut image = Array2::from_elem((64, 64), 100.0);
+//! psf::add_star(&mut image, &GaussianPsf::new(2.5), 20.3, 40.7, 5000.0);
+//! let sources = detect_sources(&image, &DetectionConfig::default());
+//! assert_eq!(sources.len(), 1);
+//! assert!((sources[0].x - 20.3).abs() < 0.05);
+//! ```
+//!
+//! Positions use the renderer's convention: pixel column `c` spans
+//! `c <= x < c + 1`, so the center of the first pixel is `(0.5, 0.5)`.
+
+use super::{median, sigma_clip};
+use ndarray::Array2;
+
+/// Level and noise of the sky behind the sources
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Background {
+    /// Median of the sigma-clipped image
+    pub level: f64,
+    /// Standard deviation of the sigma-clipped image
+    pub noise: f64,
+}
+
+impl Background {
+    /// Estimate the background of `image`, clipping at `clip_sigma`
+    /// standard deviations for up to `maxiters` iterations so that stars do
+    /// not bias it
+    pub fn estimate(image: &Array2<f64>, clip_sigma: f64, maxiters: usize) -> Self {
+        let clipped = sigma_clip(image, clip_sigma, Some(maxiters), true);
+        let values: Vec<f64> = clipped.iter().copied().filter(|x| x.is_finite()).collect();
+        if values.is_empty() {
+            return Self {
+                level: 0.0,
+                noise: 0.0,
+            };
+        }
+        let level = median(&values);
+        let variance =
+            values.iter().map(|x| (x - level).powi(2)).sum::<f64>() / values.len() as f64;
+        Self {
+            level,
+            noise: variance.sqrt(),
+        }
+    }
+}
+
+/// Settings for [`detect_sources`]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DetectionConfig {
+    /// Detection threshold in background standard deviations
+    pub threshold_sigma: f64,
+    /// Smallest island of pixels kept as a source
+    pub min_pixels: usize,
+    /// Clipping limit for the background estimate, standard deviations
+    pub clip_sigma: f64,
+    /// Clipping iterations for the background estimate
+    pub clip_iterations: usize,
+}
+
+impl Default for DetectionConfig {
+    /// Five-sigma detections of at least three pixels over a 3-sigma
+    /// clipped background
+    fn default() -> Self {
+        Self {
+            threshold_sigma: 5.0,
+            min_pixels: 3,
+            clip_sigma: 3.0,
+            clip_iterations: 5,
+        }
+    }
+}
+
+impl DetectionConfig {
+    /// Set the detection threshold in background standard deviations
+    pub fn with_threshold(mut self, threshold_sigma: f64) -> Self {
+        self.threshold_sigma = threshold_sigma;
+        self
+    }
+
+    /// Set the smallest island of pixels kept as a source
+    pub fn with_min_pixels(mut self, min_pixels: usize) -> Self {
+        self.min_pixels = min_pixels;
+        self
+    }
+
+    /// Set the clipping limit and iterations of the background estimate
+    pub fn with_clipping(mut self, clip_sigma: f64, iterations: usize) -> Self {
+        self.clip_sigma = clip_sigma;
+        self.clip_iterations = iterations;
+        self
+    }
+}
+
+/// A source found in an image
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Source {
+    /// Centroid x, pixels
+    pub x: f64,
+    /// Centroid y, pixels
+    pub y: f64,
+    /// Sum above background over the source's pixels
+    pub flux: f64,
+    /// Brightest pixel above background
+    pub peak: f64,
+    /// Number of pixels above the threshold
+    pub pixels: usize,
+    /// Second central moment along x, square pixels
+    pub x2: f64,
+    /// Second central moment along y, square pixels
+    pub y2: f64,
+    /// Second central cross moment, square pixels
+    pub xy: f64,
+}
+
+impl Source {
+    /// RMS extent along the major axis, pixels
+    pub fn semi_major(&self) -> f64 {
+        ((self.x2 + self.y2) / 2.0 + self.axis_spread()).sqrt()
+    }
+
+    /// RMS extent along the minor axis, pixels
+    pub fn semi_minor(&self) -> f64 {
+        ((self.x2 + self.y2) / 2.0 - self.axis_spread())
+            .max(0.0)
+            .sqrt()
+    }
+
+    /// Angle of the major axis from +x toward +y, radians
+    pub fn orientation(&self) -> f64 {
+        0.5 * (2.0 * self.xy).atan2(self.x2 - self.y2)
+    }
+
+    /// `1 - b/a`: zero for round sources
+    pub fn ellipticity(&self) -> f64 {
+        match self.semi_major() {
+            a if a > 0.0 => 1.0 - self.semi_minor() / a,
+            _ => 0.0,
+        }
+    }
+
+    fn axis_spread(&self) -> f64 {
+        (((self.x2 - self.y2) / 2.0).powi(2) + self.xy * self.xy).sqrt()
+    }
+}
+
+/// Find the sources in `image`, brightest first
+pub fn detect_sources(image: &Array2<f64>, config: &DetectionConfig) -> Vec<Source> {
+    let background = Background::estimate(image, config.clip_sigma, config.clip_iterations);
+    detect_sources_with_background(image, &background, config)
+}
+
+/// Find the sources in `image` above a known background, brightest first
+pub fn detect_sources_with_background(
+    image: &Array2<f64>,
+    background: &Background,
+    config: &DetectionConfig,
+) -> Vec<Source> {
+    let (rows, columns) = image.dim();
+    let threshold = background.level + config.threshold_sigma * background.noise;
+    let mut visited = Array2::from_elem((rows, columns), false);
+    let mut sources = Vec::new();
+    let mut stack = Vec::new();
+    let mut island = Vec::new();
+
+    for ((row, column), &value) in image.indexed_iter() {
+        if visited[[row, column]] || value.is_nan() || value <= threshold {
+            continue;
+        }
+
+        // Flood-fill the 8-connected island of pixels above threshold
+        island.clear();
+        visited[[row, column]] = true;
+        stack.push((row, column));
+        while let Some((r, c)) = stack.pop() {
+            island.push((r, c));
+            for nr in r.saturating_sub(1)..(r + 2).min(rows) {
+                for nc in c.saturating_sub(1)..(c + 2).min(columns) {
+                    if !visited[[nr, nc]] && image[[nr, nc]] > threshold {
+                        visited[[nr, nc]] = true;
+                        stack.push((nr, nc));
+                    }
+                }
+            }
+        }
+
+        if island.len() >= config.min_pixels {
+            if let Some(source) = measure(image, background.level, &island) {
+                sources.push(source);
+            }
+        }
+    }
+
+    sources.sort_by(|a, b| b.flux.total_cmp(&a.flux));
+    sources
+}
+
+/// Centroid, flux and moments of an island of pixels
+fn measure(image: &Array2<f64>, level: f64, island: &[(usize, usize)]) -> Option<Source> {
+    let (mut flux, mut sx, mut sy, mut peak) = (0.0, 0.0, 0.0, f64::NEG_INFINITY);
+    for &(row, column) in island {
+        let value = image[[row, column]] - level;
+        flux += value;
+        sx += value * (column as f64 + 0.5);
+        sy += value * (row as f64 + 0.5);
+        peak = peak.max(value);
+    }
+    if flux <= 0.0 {
+        return None;
+    }
+    let (x, y) = (sx / flux, sy / flux);
+
+    let (mut x2, mut y2, mut xy) = (0.0, 0.0, 0.0);
+    for &(row, column) in island {
+        let value = image[[row, column]] - level;
+        let (dx, dy) = (column as f64 + 0.5 - x, row as f64 + 0.5 - y);
+        x2 += value * dx * dx;
+        y2 += value * dy * dy;
+        xy += value * dx * dy;
+    }
+
+    Some(Source {
+        x,
+        y,
+        flux,
+        peak,
+        pixels: island.len(),
+        x2: x2 / flux,
+        y2: y2 / flux,
+        xy: xy / flux,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::image::psf::{add_star, GaussianPsf, MoffatPsf};
+    use crate::image::{NoiseModel, ReadNoise, ShotNoise};
+    use approx::assert_relative_eq;
+
+    #[test]
+    fn test_finds_rendered_stars_in_noise() {
+        let stars = [
+            (30.2, 20.7, 20_000.0),
+            (90.6, 75.1, 8_000.0),
+            (15.5, 100.25, 3_000.0),
+        ];
+        let mut image = Array2::from_elem((128, 128), 200.0);
+        for &(x, y, flux) in &stars {
+            add_star(&mut image, &GaussianPsf::new(3.0), x, y, flux);
+        }
+        NoiseModel::new(11)
+            .with(ShotNoise)
+            .with(ReadNoise::new(4.0))
+            .apply(&mut image, 1.0);
+
+        // A hot pixel is a single pixel and is rejected
+        image[[5, 120]] += 5_000.0;
+
+        let background = Background::estimate(&image, 3.0, 5);
+        assert_relative_eq!(background.level, 200.0, max_relative = 0.02);
+        assert_relative_eq!(
+            background.noise,
+            (200.0f64 + 16.0).sqrt(),
+            max_relative = 0.1
+        );
+
+        let sources = detect_sources(&image, &DetectionConfig::default());
+        assert_eq!(sources.len(), 3);
+        for (source, &(x, y, flux)) in sources.iter().zip(&stars) {
+            assert!((source.x - x).abs() < 0.15, "{} vs {}", source.x, x);
+            assert!((source.y - y).abs() < 0.15, "{} vs {}", source.y, y);
+            assert!(source.flux < flux && source.flux > 0.6 * flux);
+            assert!(source.ellipticity() < 0.15);
+        }
+    }
+
+    #[test]
+    fn test_shape_moments() {
+        // A point source: all flux in one pixel
+        let mut image = Array2::zeros((9, 9));
+        image[[4, 6]] = 10.0;
+        let flat = Background {
+            level: 0.0,
+            noise: 1.0,
+        };
+        let config = DetectionConfig::default().with_min_pixels(1);
+        let sources = detect_sources_with_background(&image, &flat, &config);
+        assert_eq!(sources.len(), 1);
+        assert_eq!((sources[0].x, sources[0].y), (6.5, 4.5));
+        assert_eq!(sources[0].semi_major(), 0.0);
+
+        // An elongated streak along the diagonal
+        let mut image = Array2::zeros((32, 32));
+        for i in 0..12 {
+            add_star(
+                &mut image,
+                &MoffatPsf::new(2.0, 4.0),
+                10.0 + i as f64,
+                10.0 + i as f64,
+                500.0,
+            );
+        }
+        let sources = detect_sources_with_background(&image, &flat, &config);
+        assert_eq!(sources.len(), 1);
+        let streak = sources[0];
+        assert!(streak.ellipticity() > 0.5);
+        assert_relative_eq!(
+            streak.orientation(),
+            std::f64::consts::FRAC_PI_4,
+            epsilon = 1e-6
+        );
+        assert_relative_eq!(streak.x, 15.5, epsilon = 1e-6);
+
+        // Nothing in an empty frame
+        let empty = Array2::from_elem((16, 16), 7.0);
+        assert!(detect_sources(&empty, &DetectionConfig::default()).is_empty());
+    }
+}
@@ -1,3 +1,21 @@
+//! Image processing utilities
+
+pub mod centroid;
+pub mod detect;
+pub mod noise;
+pub mod psf;
+pub mod render;
+
+pub use centroid::{fit_gaussian, windowed_centroid, Centroid, GaussianFit};
+pub use detect::{
+    detect_sources, detect_sources_with_background, Background, DetectionConfig, Source,
+};
+pub use noise::{
+    Bias, DarkCurrent, DefectMap, FixedPatternNoise, NoiseModel, NoiseSource, ReadNoise, ShotNoise,
+};
+pub use psf::{AiryPsf, GaussianPsf, MoffatPsf, PointSpreadFunction};
+pub use render::{Camera, PlacedStar, Pointing, StarFieldRenderer};
+
 use ndarray::Array2;
 
 /// Performs sigma clipping on a 2D array of f64 values.
@@ -85,7 +103,7 @@ fn median(data: &[f64]) -> f64 {
     sorted_data.sort_by(|a, b| a.partial_cmp(b).unwrap());
 
     let len = sorted_data.len();
-    if len % 2 == 0 {
+    if len.is_multiple_of(2) {
         (sorted_data[len / 2 - 1] + sorted_data[len / 2]) / 2.0
     } else {
         sorted_data[len / 2]
@@ -0,0 +1,436 @@
+//! Detector noise models for synthetic images
+//!
+//! Images are arrays of electrons per pixel. Each effect is a
+//! [`NoiseSource`], and a [`NoiseModel`] applies a list of them in order with
+//! a seeded random number generator, so the same seed always produces the
+//! same frame. A physically sensible order is
+//!
+//! 1. [`DarkCurrent`] - thermal electrons accumulate alongside the signal
+//! 2. [`FixedPatternNoise`] - pixel-to-pixel gain differences
+//! 3. [`ShotNoise`] - Poisson statistics of everything collected so far
+//! 4. [`DefectMap`] - hot and dead pixels
+//! 5. [`ReadNoise`] - Gaussian noise of the readout amplifier
+//! 6. [`Bias`] - the constant pedestal the electronics add to every pixel
+//!
+//! The first, third and fifth are what [`NoiseModel::detector`] builds.
+
+use ndarray::Array2;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+
+/// Mean above which Poisson deviates are drawn from a normal approximation
+const POISSON_NORMAL_THRESHOLD: f64 = 50.0;
+
+/// Standard normal deviate (Box-Muller)
+pub(crate) fn standard_normal<R: Rng + ?Sized>(rng: &mut R) -> f64 {
+    let u1: f64 = 1.0 - rng.gen::<f64>();
+    let u2: f64 = rng.gen();
+    (-2.0 * u1.ln()).sqrt() * (std::f64::consts::TAU * u2).cos()
+}
+
+/// Poisson deviate with the given mean
+///
+/// Exact (Knuth's method) for small means; above
+/// [`POISSON_NORMAL_THRESHOLD`] the normal approximation is indistinguishable
+/// and much faster.
+pub(crate) fn poisson<R: Rng + ?Sized>(rng: &mut R, mean: f64) -> f64 {
+    if mean <= 0.0 || !mean.is_finite() {
+        return 0.0;
+    }
+    if mean > POISSON_NORMAL_THRESHOLD {
+        return (mean + mean.sqrt() * standard_normal(rng)).round().max(0.0);
+    }
+
+    let limit = (-mean).exp();
+    let mut product: f64 = rng.gen();
+    let mut count = 0.0;
+    while product > limit {
+        product *= rng.gen::<f64>();
+        count += 1.0;
+    }
+    count
+}
+
+/// One physical effect that degrades an image
+pub trait NoiseSource: std::fmt::Debug + Send + Sync {
+    /// Apply the effect to `image` (electrons) for an exposure of
+    /// `exposure_s` seconds
+    fn apply(&self, image: &mut Array2<f64>, exposure_s: f64, rng: &mut StdRng);
+}
+
+/// Photon (shot) noise: each pixel becomes a Poisson draw about its value
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ShotNoise;
+
+impl NoiseSource for ShotNoise {
+    fn apply(&self, image: &mut Array2<f64>, _exposure_s: f64, rng: &mut StdRng) {
+        image.mapv_inplace(|electrons| poisson(rng, electrons));
+    }
+}
+
+/// Gaussian read noise of the output amplifier
+#[derive(Debug, Clone, Copy)]
+pub struct ReadNoise {
+    /// RMS noise per pixel in electrons
+    pub sigma_e: f64,
+}
+
+impl ReadNoise {
+    /// Read noise with the given RMS in electrons
+    pub fn new(sigma_e: f64) -> Self {
+        Self { sigma_e }
+    }
+}
+
+impl NoiseSource for ReadNoise {
+    fn apply(&self, image: &mut Array2<f64>, _exposure_s: f64, rng: &mut StdRng) {
+        image.mapv_inplace(|electrons| electrons + self.sigma_e * standard_normal(rng));
+    }
+}
+
+/// Bias level: a constant offset added to every pixel at readout
+///
+/// Real frames sit on a pedestal so that read noise never drives a pixel
+/// negative; apply it last, after [`ReadNoise`].
+#[derive(Debug, Clone, Copy)]
+pub struct Bias {
+    /// Offset in electrons
+    pub level_e: f64,
+}
+
+impl Bias {
+    /// A bias pedestal of `level_e` electrons
+    pub fn new(level_e: f64) -> Self {
+        Self { level_e }
+    }
+}
+
+impl NoiseSource for Bias {
+    fn apply(&self, image: &mut Array2<f64>, _exposure_s: f64, _rng: &mut StdRng) {
+        image.mapv_inplace(|electrons| electrons + self.level_e);
+    }
+}
+
+/// Thermal dark current, scaled with sensor temperature
+///
+/// Dark current roughly doubles for every few degrees of warming; the rate
+/// at `temperature_c` is `rate_at_reference * 2^((T - T_ref) / doubling)`.
+/// Only the mean is added: follow with [`ShotNoise`] for its Poisson
+/// statistics.
+#[derive(Debug, Clone, Copy)]
+pub struct DarkCurrent {
+    /// Dark current at the reference temperature (electrons/pixel/second)
+    pub rate_at_reference: f64,
+    /// Temperature at which `rate_at_reference` applies (Celsius)
+    pub reference_temp_c: f64,
+    /// Warming that doubles the dark current (Celsius)
+    pub doubling_temp_c: f64,
+    /// Operating temperature of the sensor (Celsius)
+    pub temperature_c: f64,
+}
+
+impl DarkCurrent {
+    /// Dark current of `rate` electrons/pixel/second at 20 C, doubling every
+    /// 6.3 C, with the sensor at the reference temperature
+    pub fn new(rate: f64) -> Self {
+        Self {
+            rate_at_reference: rate,
+            reference_temp_c: 20.0,
+            doubling_temp_c: 6.3,
+            temperature_c: 20.0,
+        }
+    }
+
+    /// Set the operating temperature of the sensor
+    pub fn with_temperature(mut self, temperature_c: f64) -> Self {
+        self.temperature_c = temperature_c;
+        self
+    }
+
+    /// Set the temperature rise that doubles the dark current
+    pub fn with_doubling_temperature(mut self, doubling_temp_c: f64) -> Self {
+        self.doubling_temp_c = doubling_temp_c;
+        self
+    }
+
+    /// Dark current at the operating temperature (electrons/pixel/second)
+    pub fn rate(&self) -> f64 {
+        self.rate_at_reference
+            * 2f64.powf((self.temperature_c - self.reference_temp_c) / self.doubling_temp_c)
+    }
+}
+
+impl NoiseSource for DarkCurrent {
+    fn apply(&self, image: &mut Array2<f64>, exposure_s: f64, _rng: &mut StdRng) {
+        let electrons = self.rate() * exposure_s;
+        image.mapv_inplace(|e| e + electrons);
+    }
+}
+
+/// Fixed-pattern noise: per-pixel gain (PRNU) and offset (DSNU) variations
+///
+/// The pattern belongs to the sensor rather than the exposure, so it is drawn
+/// from its own seed and is identical in every frame.
+#[derive(Debug, Clone, Copy)]
+pub struct FixedPatternNoise {
+    /// RMS fractional gain variation (photo-response non-uniformity)
+    pub gain_sigma: f64,
+    /// RMS offset variation in electrons (dark signal non-uniformity)
+    pub offset_sigma_e: f64,
+    /// Seed of the sensor's pattern
+    pub seed: u64,
+}
+
+impl FixedPatternNoise {
+    /// Fixed-pattern noise with the given fractional gain and offset spreads
+    pub fn new(gain_sigma: f64, offset_sigma_e: f64, seed: u64) -> Self {
+        Self {
+            gain_sigma,
+            offset_sigma_e,
+            seed,
+        }
+    }
+}
+
+impl NoiseSource for FixedPatternNoise {
+    fn apply(&self, image: &mut Array2<f64>, _exposure_s: f64, _rng: &mut StdRng) {
+        let mut pattern = StdRng::seed_from_u64(self.seed);
+        image.mapv_inplace(|e| {
+            let gain = 1.0 + self.gain_sigma * standard_normal(&mut pattern);
+            let offset = self.offset_sigma_e * standard_normal(&mut pattern);
+            (e * gain + offset).max(0.0)
+        });
+    }
+}
+
+/// Map of defective pixels
+#[derive(Debug, Clone, Default)]
+pub struct DefectMap {
+    /// Hot pixels as (row, column, extra dark current in electrons/second)
+    pub hot: Vec<(usize, usize, f64)>,
+    /// Dead pixels, which read zero, as (row, column)
+    pub dead: Vec<(usize, usize)>,
+}
+
+impl DefectMap {
+    /// An empty defect map
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a hot pixel with extra dark current in electrons/second
+    pub fn with_hot_pixel(mut self, row: usize, column: usize, rate: f64) -> Self {
+        self.hot.push((row, column, rate));
+        self
+    }
+
+    /// Add a dead pixel
+    pub fn with_dead_pixel(mut self, row: usize, column: usize) -> Self {
+        self.dead.push((row, column));
+        self
+    }
+
+    /// Scatter defects over a sensor of `shape` (rows, columns)
+    ///
+    /// Each pixel is hot with probability `hot_fraction` (with a rate drawn
+    /// uniformly up to `max_hot_rate` electrons/second) and dead with
+    /// probability `dead_fraction`.
+    pub fn random(
+        shape: (usize, usize),
+        hot_fraction: f64,
+        max_hot_rate: f64,
+        dead_fraction: f64,
+        seed: u64,
+    ) -> Self {
+        let mut rng = StdRng::seed_from_u64(seed);
+        let mut map = Self::new();
+        for row in 0..shape.0 {
+            for column in 0..shape.1 {
+                let draw: f64 = rng.gen();
+                if draw < hot_fraction {
+                    map.hot
+                        .push((row, column, rng.gen_range(0.0..=1.0) * max_hot_rate));
+                } else if draw < hot_fraction + dead_fraction {
+                    map.dead.push((row, column));
+                }
+            }
+        }
+        map
+    }
+}
+
+impl NoiseSource for DefectMap {
+    fn apply(&self, image: &mut Array2<f64>, exposure_s: f64, rng: &mut StdRng) {
+        for &(row, column, rate) in &self.hot {
+            if let Some(pixel) = image.get_mut((row, column)) {
+                *pixel += poisson(rng, rate * exposure_s);
+            }
+        }
+        for &(row, column) in &self.dead {
+            if let Some(pixel) = image.get_mut((row, column)) {
+                *pixel = 0.0;
+            }
+        }
+    }
+}
+
+/// An ordered, seedable chain of noise sources
+#[derive(Debug)]
+pub struct NoiseModel {
+    sources: Vec<Box<dyn NoiseSource>>,
+    seed: u64,
+}
+
+impl NoiseModel {
+    /// An empty model (applies no noise) with the given seed
+    pub fn new(seed: u64) -> Self {
+        Self {
+            sources: Vec::new(),
+            seed,
+        }
+    }
+
+    /// A typical detector: dark current, shot noise and read noise
+    pub fn detector(dark: DarkCurrent, read_noise_e: f64, seed: u64) -> Self {
+        Self::new(seed)
+            .with(dark)
+            .with(ShotNoise)
+            .with(ReadNoise::new(read_noise_e))
+    }
+
+    /// Append a noise source; sources are applied in the order added
+    pub fn with<S: NoiseSource + 'static>(mut self, source: S) -> Self {
+        self.sources.push(Box::new(source));
+        self
+    }
+
+    /// Change the seed of the per-exposure randomness
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.seed = seed;
+        self
+    }
+
+    /// Number of noise sources in the chain
+    pub fn len(&self) -> usize {
+        self.sources.len()
+    }
+
+    /// Whether the model applies no noise
+    pub fn is_empty(&self) -> bool {
+        self.sources.is_empty()
+    }
+
+    /// Apply every source to `image` (electrons) in order
+    pub fn apply(&self, image: &mut Array2<f64>, exposure_s: f64) {
+        let mut rng = StdRng::seed_from_u64(self.seed);
+        self.apply_with_rng(image, exposure_s, &mut rng);
+    }
+
+    /// Apply every source using a caller-supplied generator, e.g. to give
+    /// successive frames of a sequence different noise
+    pub fn apply_with_rng(&self, image: &mut Array2<f64>, exposure_s: f64, rng: &mut StdRng) {
+        for source in &self.sources {
+            source.apply(image, exposure_s, rng);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use approx::assert_relative_eq;
+
+    fn mean_and_variance(image: &Array2<f64>) -> (f64, f64) {
+        let n = image.len() as f64;
+        let mean = image.sum() / n;
+        let variance = image.iter().map(|x| (x - mean).powi(2)).sum::<f64>() / n;
+        (mean, variance)
+    }
+
+    #[test]
+    fn test_shot_noise_is_poisson() {
+        let mut rng = StdRng::seed_from_u64(1);
+        for &level in &[3.0, 400.0] {
+            let mut image = Array2::from_elem((200, 200), level);
+            ShotNoise.apply(&mut image, 1.0, &mut rng);
+            let (mean, variance) = mean_and_variance(&image);
+            assert_relative_eq!(mean, level, max_relative = 0.02);
+            assert_relative_eq!(variance, level, max_relative = 0.05);
+            assert!(image.iter().all(|&x| x >= 0.0 && x.fract() == 0.0));
+        }
+    }
+
+    #[test]
+    fn test_dark_current_temperature_scaling() {
+        let dark = DarkCurrent::new(0.1);
+        assert_relative_eq!(dark.rate(), 0.1);
+        assert_relative_eq!(dark.with_temperature(26.3).rate(), 0.2, epsilon = 1e-12);
+        assert_relative_eq!(
+            dark.with_temperature(-5.2).rate(),
+            0.1 / 16.0,
+            epsilon = 1e-12
+        );
+
+        let mut image = Array2::zeros((4, 4));
+        let mut rng = StdRng::seed_from_u64(0);
+        dark.apply(&mut image, 30.0, &mut rng);
+        assert_relative_eq!(image[(2, 2)], 3.0, epsilon = 1e-12);
+    }
+
+    #[test]
+    fn test_model_is_reproducible() {
+        let build = |seed| {
+            NoiseModel::detector(DarkCurrent::new(1.0), 5.0, seed)
+                .with(DefectMap::new().with_dead_pixel(0, 0))
+        };
+        let model = build(42);
+        assert_eq!(model.len(), 4);
+
+        let mut a = Array2::from_elem((32, 32), 100.0);
+        let mut b = a.clone();
+        model.apply(&mut a, 10.0);
+        model.apply(&mut b, 10.0);
+        assert_eq!(a, b);
+        assert_eq!(a[(0, 0)], 0.0);
+
+        let mut c = Array2::from_elem((32, 32), 100.0);
+        build(42).with_seed(43).apply(&mut c, 10.0);
+        assert_ne!(a, c);
+
+        // Read noise on a bias frame adds the expected variance about the
+        // pedestal
+        let mut bias_frame = Array2::zeros((200, 200));
+        NoiseModel::new(7)
+            .with(ReadNoise::new(5.0))
+            .with(Bias::new(500.0))
+            .apply(&mut bias_frame, 1.0);
+        let (mean, variance) = mean_and_variance(&bias_frame);
+        assert_relative_eq!(mean, 500.0, max_relative = 1e-3);
+        assert_relative_eq!(variance, 25.0, max_relative = 0.05);
+    }
+
+    #[test]
+    fn test_fixed_pattern_is_stable_between_frames() {
+        let pattern = FixedPatternNoise::new(0.02, 1.0, 9);
+        let mut first = Array2::from_elem((16, 16), 1000.0);
+        let mut second = first.clone();
+        let mut rng = StdRng::seed_from_u64(1);
+        pattern.apply(&mut first, 1.0, &mut rng);
+        pattern.apply(&mut second, 1.0, &mut rng);
+        assert_eq!(first, second);
+        let (_, variance) = mean_and_variance(&first);
+        assert!(variance > 100.0);
+    }
+
+    #[test]
+    fn test_random_defect_map() {
+        let map = DefectMap::random((100, 100), 0.01, 50.0, 0.005, 3);
+        assert!((50..150).contains(&map.hot.len()), "{}", map.hot.len());
+        assert!((20..90).contains(&map.dead.len()), "{}", map.dead.len());
+
+        let mut image = Array2::zeros((100, 100));
+        let mut rng = StdRng::seed_from_u64(0);
+        map.apply(&mut image, 10.0, &mut rng);
+        assert!(image.sum() > 0.0);
+    }
+}
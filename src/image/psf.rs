@@ -0,0 +1,373 @@
+//! Point-spread functions
+//!
+//! A [`PointSpreadFunction`] describes how the light of a point source is
+//! spread over the focal plane, normalized to unit total flux. Three
+//! profiles are provided, each set by its full width at half maximum:
+//!
+//! - [`GaussianPsf`] - seeing-limited cores, integrated over pixels exactly
+//! - [`MoffatPsf`] - atmospheric seeing with realistic power-law wings
+//! - [`AiryPsf`] - the diffraction pattern of a clear circular aperture
+//!
+//! [`add_star`] draws a source at any sub-pixel position by integrating the
+//! profile over each pixel's area, which is what the
+//! [`super::StarFieldRenderer`] does for every star. [`kernel`] and
+//! [`convolve`] apply a profile to an existing image instead.
+
+use ndarray::Array2;
+use std::f64::consts::PI;
+
+/// Ratio of a Gaussian's FWHM to its standard deviation
+const FWHM_PER_SIGMA: f64 = 2.354_820_045_030_949;
+
+/// FWHM of the Airy pattern in units of λ/D
+const AIRY_FWHM_PER_LAMBDA_OVER_D: f64 = 1.028_993_969_962_188;
+
+/// Sub-samples per pixel side when integrating a profile numerically
+const SUBSAMPLES: usize = 7;
+
+/// A radially symmetric point-spread function of unit total flux
+pub trait PointSpreadFunction: std::fmt::Debug + Send + Sync {
+    /// Full width at half maximum, pixels
+    fn fwhm_px(&self) -> f64;
+
+    /// Flux per square pixel at `(dx, dy)` pixels from the center
+    fn intensity(&self, dx: f64, dy: f64) -> f64;
+
+    /// Distance from the center beyond which the profile is not drawn,
+    /// pixels
+    fn radius_px(&self) -> f64;
+
+    /// Fraction of the flux landing in the pixel spanning `dx..dx + 1`,
+    /// `dy..dy + 1` relative to the center
+    ///
+    /// The default integrates [`Self::intensity`] on a grid of sub-samples.
+    fn pixel_fraction(&self, dx: f64, dy: f64) -> f64 {
+        let step = 1.0 / SUBSAMPLES as f64;
+        let mut sum = 0.0;
+        for i in 0..SUBSAMPLES {
+            for j in 0..SUBSAMPLES {
+                sum += self.intensity(dx + (i as f64 + 0.5) * step, dy + (j as f64 + 0.5) * step);
+            }
+        }
+        sum * step * step
+    }
+}
+
+/// Gaussian profile
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct GaussianPsf {
+    /// Standard deviation, pixels
+    pub sigma_px: f64,
+}
+
+impl GaussianPsf {
+    /// A Gaussian with the given FWHM in pixels
+    pub fn new(fwhm_px: f64) -> Self {
+        Self {
+            sigma_px: fwhm_px / FWHM_PER_SIGMA,
+        }
+    }
+
+    /// Fraction of a 1-D profile falling between `offset` and `offset + 1`
+    fn fraction_1d(&self, offset: f64) -> f64 {
+        let scale = 1.0 / (self.sigma_px * std::f64::consts::SQRT_2);
+        0.5 * (erf((offset + 1.0) * scale) - erf(offset * scale))
+    }
+}
+
+impl PointSpreadFunction for GaussianPsf {
+    fn fwhm_px(&self) -> f64 {
+        self.sigma_px * FWHM_PER_SIGMA
+    }
+
+    fn intensity(&self, dx: f64, dy: f64) -> f64 {
+        let variance = self.sigma_px * self.sigma_px;
+        (-(dx * dx + dy * dy) / (2.0 * variance)).exp() / (2.0 * PI * variance)
+    }
+
+    /// Five standard deviations
+    fn radius_px(&self) -> f64 {
+        (5.0 * self.sigma_px).max(1.0)
+    }
+
+    /// Exact, since the profile separates in x and y
+    fn pixel_fraction(&self, dx: f64, dy: f64) -> f64 {
+        self.fraction_1d(dx) * self.fraction_1d(dy)
+    }
+}
+
+/// Moffat profile, `(1 + r²/α²)^-β`
+///
+/// Smaller β gives heavier wings; β around 2.5 to 4.5 matches
+/// atmospheric seeing, and β → ∞ tends to a Gaussian.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MoffatPsf {
+    /// Core width α, pixels
+    pub alpha_px: f64,
+    /// Power-law index β, greater than 1
+    pub beta: f64,
+}
+
+impl MoffatPsf {
+    /// A Moffat profile with the given FWHM in pixels and index `beta`
+    pub fn new(fwhm_px: f64, beta: f64) -> Self {
+        Self {
+            alpha_px: fwhm_px / (2.0 * (2f64.powf(1.0 / beta) - 1.0).sqrt()),
+            beta,
+        }
+    }
+}
+
+impl PointSpreadFunction for MoffatPsf {
+    fn fwhm_px(&self) -> f64 {
+        2.0 * self.alpha_px * (2f64.powf(1.0 / self.beta) - 1.0).sqrt()
+    }
+
+    fn intensity(&self, dx: f64, dy: f64) -> f64 {
+        let a2 = self.alpha_px * self.alpha_px;
+        (self.beta - 1.0) / (PI * a2) * (1.0 + (dx * dx + dy * dy) / a2).powf(-self.beta)
+    }
+
+    /// The radius enclosing 99.9% of the flux
+    fn radius_px(&self) -> f64 {
+        let r = self.alpha_px * (1e-3f64.powf(1.0 / (1.0 - self.beta)) - 1.0).sqrt();
+        r.max(1.0)
+    }
+}
+
+/// Airy pattern of a clear circular aperture, `[2 J₁(x) / x]²`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct AiryPsf {
+    /// λ/D expressed in pixels
+    pub lambda_over_d_px: f64,
+}
+
+impl AiryPsf {
+    /// An Airy pattern with the given FWHM in pixels
+    pub fn new(fwhm_px: f64) -> Self {
+        Self {
+            lambda_over_d_px: fwhm_px / AIRY_FWHM_PER_LAMBDA_OVER_D,
+        }
+    }
+
+    /// The diffraction pattern of an aperture `aperture_mm` across at
+    /// `wavelength_nm`, on pixels of `pixel_scale_arcsec`
+    pub fn from_optics(wavelength_nm: f64, aperture_mm: f64, pixel_scale_arcsec: f64) -> Self {
+        let lambda_over_d_rad = wavelength_nm * 1e-9 / (aperture_mm * 1e-3);
+        Self {
+            lambda_over_d_px: lambda_over_d_rad.to_degrees() * 3600.0 / pixel_scale_arcsec,
+        }
+    }
+
+    /// Radius of the first dark ring, pixels
+    pub fn first_zero_px(&self) -> f64 {
+        1.219_669_891_266_504_5 * self.lambda_over_d_px
+    }
+}
+
+impl PointSpreadFunction for AiryPsf {
+    fn fwhm_px(&self) -> f64 {
+        AIRY_FWHM_PER_LAMBDA_OVER_D * self.lambda_over_d_px
+    }
+
+    fn intensity(&self, dx: f64, dy: f64) -> f64 {
+        let k = PI / self.lambda_over_d_px;
+        let peak = k * k / (4.0 * PI);
+        let x = k * dx.hypot(dy);
+        if x < 1e-8 {
+            return peak;
+        }
+        let amplitude = 2.0 * bessel_j1(x) / x;
+        peak * amplitude * amplitude
+    }
+
+    /// Ten FWHM, enclosing about 98% of the flux
+    fn radius_px(&self) -> f64 {
+        (10.0 * self.fwhm_px()).max(1.0)
+    }
+}
+
+/// Add `flux` spread by `psf` about pixel coordinates `(x, y)` to `image`
+///
+/// Pixel column `c` spans `c <= x < c + 1` and row `r` spans
+/// `r <= y < r + 1`; light falling off the image is lost.
+pub fn add_star(image: &mut Array2<f64>, psf: &dyn PointSpreadFunction, x: f64, y: f64, flux: f64) {
+    let (rows, columns) = image.dim();
+    let radius = psf.radius_px();
+    let span = |center: f64, len: usize| {
+        let first = (center - radius).floor().max(0.0) as usize;
+        let last = ((center + radius).ceil().max(0.0) as usize).min(len);
+        first..last
+    };
+
+    for row in span(y, rows) {
+        for column in span(x, columns) {
+            let fraction = psf.pixel_fraction(column as f64 - x, row as f64 - y);
+            image[[row, column]] += flux * fraction;
+        }
+    }
+}
+
+/// A square kernel of `psf` centered on its middle pixel, normalized to
+/// sum to one and just large enough to hold [`PointSpreadFunction::radius_px`]
+pub fn kernel(psf: &dyn PointSpreadFunction) -> Array2<f64> {
+    let half = psf.radius_px().ceil() as usize;
+    let size = 2 * half + 1;
+    let mut kernel = Array2::zeros((size, size));
+    let center = half as f64 + 0.5;
+    add_star(&mut kernel, psf, center, center, 1.0);
+    let total = kernel.sum();
+    if total > 0.0 {
+        kernel /= total;
+    }
+    kernel
+}
+
+/// Convolve `image` with `psf`, treating everything beyond its edges as zero
+pub fn convolve(image: &Array2<f64>, psf: &dyn PointSpreadFunction) -> Array2<f64> {
+    let kernel = kernel(psf);
+    let half = kernel.nrows() / 2;
+    let (rows, columns) = image.dim();
+    let mut result = Array2::zeros((rows, columns));
+
+    for ((row, column), &value) in image.indexed_iter() {
+        if value == 0.0 {
+            continue;
+        }
+        for ((kr, kc), &weight) in kernel.indexed_iter() {
+            let (r, c) = (
+                (row + kr).checked_sub(half),
+                (column + kc).checked_sub(half),
+            );
+            if let (Some(r), Some(c)) = (r, c) {
+                if r < rows && c < columns {
+                    result[[r, c]] += value * weight;
+                }
+            }
+        }
+    }
+    result
+}
+
+/// Error function (Abramowitz & Stegun 7.1.26, accurate to 1.5e-7)
+fn erf(x: f64) -> f64 {
+    let t = 1.0 / (1.0 + 0.327_591_1 * x.abs());
+    let polynomial = t
+        * (0.254_829_592
+            + t * (-0.284_496_736
+                + t * (1.421_413_741 + t * (-1.453_152_027 + t * 1.061_405_429))));
+    let magnitude = 1.0 - polynomial * (-x * x).exp();
+    magnitude.copysign(x)
+}
+
+/// Bessel function of the first kind, order one (rational approximations
+/// from Numerical Recipes, accurate to about 1e-8)
+fn bessel_j1(x: f64) -> f64 {
+    let ax = x.abs();
+    if ax < 8.0 {
+        let y = x * x;
+        let numerator = x
+            * (72_362_614_232.0
+                + y * (-7_895_059_235.0
+                    + y * (242_396_853.1
+                        + y * (-2_972_611.439 + y * (15_704.482_60 + y * -30.160_366_06)))));
+        let denominator = 144_725_228_442.0
+            + y * (2_300_535_178.0
+                + y * (18_583_304.74 + y * (99_447.433_94 + y * (376.999_139_7 + y))));
+        numerator / denominator
+    } else {
+        let z = 8.0 / ax;
+        let y = z * z;
+        let xx = ax - 2.356_194_491;
+        let p = 1.0
+            + y * (0.183_105e-2
+                + y * (-0.351_639_649_6e-4 + y * (0.245_752_017_4e-5 + y * -0.240_337_019e-6)));
+        let q = 0.046_874_999_95
+            + y * (-0.200_269_087_3e-3
+                + y * (0.844_919_909_6e-5 + y * (-0.882_289_87e-6 + y * 0.105_787_412e-6)));
+        let value = (std::f64::consts::FRAC_2_PI / ax).sqrt() * (xx.cos() * p - z * xx.sin() * q);
+        value.copysign(x)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use approx::assert_relative_eq;
+
+    fn profiles() -> Vec<Box<dyn PointSpreadFunction>> {
+        vec![
+            Box::new(GaussianPsf::new(3.0)),
+            Box::new(MoffatPsf::new(3.0, 3.5)),
+            Box::new(AiryPsf::new(3.0)),
+        ]
+    }
+
+    #[test]
+    fn test_profiles_have_their_fwhm() {
+        for psf in profiles() {
+            assert_relative_eq!(psf.fwhm_px(), 3.0, epsilon = 1e-12);
+            let half = psf.intensity(1.5, 0.0) / psf.intensity(0.0, 0.0);
+            assert_relative_eq!(half, 0.5, epsilon = 1e-6);
+            // Radial symmetry
+            assert_relative_eq!(
+                psf.intensity(0.9, 1.2),
+                psf.intensity(1.5, 0.0),
+                epsilon = 1e-12
+            );
+        }
+
+        let airy = AiryPsf::from_optics(550.0, 100.0, 0.5);
+        assert_relative_eq!(airy.lambda_over_d_px, 2.268_9, epsilon = 1e-3);
+        assert!(airy.intensity(airy.first_zero_px(), 0.0) < 1e-8 * airy.intensity(0.0, 0.0));
+        assert_relative_eq!(bessel_j1(1.0), 0.440_050_585_7, epsilon = 1e-8);
+        assert_relative_eq!(bessel_j1(-10.0), -0.043_472_746_2, epsilon = 1e-8);
+        assert_relative_eq!(erf(0.5), 0.520_499_877_8, epsilon = 2e-7);
+        assert_relative_eq!(erf(-1.5), -0.966_105_146_5, epsilon = 2e-7);
+    }
+
+    #[test]
+    fn test_add_star_conserves_flux_at_sub_pixel_positions() {
+        // The square drawn around each star holds a little more than its
+        // radius encloses
+        for (psf, captured) in profiles().iter().zip([1.0, 0.9995, 0.9825]) {
+            let size = 2 * psf.radius_px().ceil() as usize + 4;
+            let mut image = Array2::zeros((size, size));
+            let (x, y) = (size as f64 / 2.0 + 0.3, size as f64 / 2.0 - 0.45);
+            add_star(&mut image, psf.as_ref(), x, y, 1000.0);
+
+            assert_relative_eq!(image.sum(), 1000.0 * captured, max_relative = 2e-3);
+            let (mut cx, mut cy) = (0.0, 0.0);
+            for ((row, column), &value) in image.indexed_iter() {
+                cx += (column as f64 + 0.5) * value;
+                cy += (row as f64 + 0.5) * value;
+            }
+            assert_relative_eq!(cx / image.sum(), x, epsilon = 5e-3);
+            assert_relative_eq!(cy / image.sum(), y, epsilon = 5e-3);
+        }
+    }
+
+    #[test]
+    fn test_convolving_a_point_gives_the_kernel() {
+        let psf = MoffatPsf::new(2.0, 4.0);
+        let kernel = kernel(&psf);
+        let half = kernel.nrows() / 2;
+        assert_relative_eq!(kernel.sum(), 1.0, epsilon = 1e-12);
+        assert_eq!(
+            kernel
+                .indexed_iter()
+                .max_by(|a, b| a.1.total_cmp(b.1))
+                .unwrap()
+                .0,
+            (half, half)
+        );
+
+        let size = kernel.nrows() + 10;
+        let mut image = Array2::zeros((size, size));
+        image[[12, 15]] = 50.0;
+        let blurred = convolve(&image, &psf);
+        assert_relative_eq!(blurred.sum(), 50.0, epsilon = 1e-9);
+        assert_relative_eq!(blurred[[12, 15]], 50.0 * kernel[[half, half]]);
+        assert_relative_eq!(blurred[[13, 15]], 50.0 * kernel[[half + 1, half]]);
+    }
+}
@@ -0,0 +1,386 @@
+//! Synthetic star field images
+//!
+//! A [`StarFieldRenderer`] images the stars of any [`StarCatalog`] through a
+//! pinhole [`Camera`] pointed at a [`Pointing`]. A pinhole maps the sky onto
+//! its focal plane by the gnomonic projection, so star positions are exact
+//! at any field size. Each star's magnitude sets its signal in electrons,
+//! which is spread over the pixels by a point-spread function (Gaussian
+//! unless another from [`super::psf`] is given) integrated across each
+//! pixel's area:
+//!
+//! ```
+//! use starfield::catalogs::{BinaryCatalog, MinimalStar};
+//! use starfield::coordinates::Equatorial;
+//! use starfield::image::{Camera, Pointing, StarFieldRenderer};
+//!
+//! let catalog = BinaryCatalog::from_stars(vec![MinimalStar::new(1, 83.82, -5.39, 8.0)], "field");
+//! let renderer = StarFieldRenderer::new(Camera::new(512, 512, 200.0, 5.0))
+//!     .with_psf_fwhm(2.5)
+//!     .with_exposure(10.0);
+//! let pointing = Pointing::new(Equatorial::from_degrees(83.82, -5.39), 0.0);
+//! let image = renderer.render(&catalog, &pointing);
+//! assert_eq!(image.dim(), (512, 512));
+//! ```
+//!
+//! Images are in electrons, ready for [`super::NoiseModel`]. Pixel column
+//! `c` spans `c <= x < c + 1` and row `r` spans `r <= y < r + 1`; with zero
+//! roll, x runs east and y north, as for a
+//! [`crate::catalogs::GuideDetector`].
+
+use super::psf::{self, GaussianPsf, PointSpreadFunction};
+use crate::catalogs::{StarCatalog, StarData};
+use crate::charting::GnomonicProjection;
+use crate::constants::{ASEC2RAD, DEG2RAD, RAD2DEG};
+use crate::coordinates::Equatorial;
+use crate::wcs::{Projection, Wcs};
+use ndarray::Array2;
+use std::sync::Arc;
+
+/// A pinhole camera: a focal length and a grid of square pixels
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Camera {
+    /// Width in pixels
+    pub width_px: usize,
+    /// Height in pixels
+    pub height_px: usize,
+    /// Focal length in units of the pixel pitch
+    pub focal_length_px: f64,
+}
+
+impl Camera {
+    /// A camera with a focal length in millimetres and a pixel pitch in
+    /// micrometres
+    pub fn new(
+        width_px: usize,
+        height_px: usize,
+        focal_length_mm: f64,
+        pixel_pitch_um: f64,
+    ) -> Self {
+        Self {
+            width_px,
+            height_px,
+            focal_length_px: focal_length_mm * 1000.0 / pixel_pitch_um,
+        }
+    }
+
+    /// A camera with a plate scale of `pixel_scale_arcsec` at the center of
+    /// the field
+    pub fn with_plate_scale(width_px: usize, height_px: usize, pixel_scale_arcsec: f64) -> Self {
+        Self {
+            width_px,
+            height_px,
+            focal_length_px: 1.0 / (pixel_scale_arcsec * ASEC2RAD),
+        }
+    }
+
+    /// Plate scale at the center of the field, arcseconds per pixel
+    pub fn pixel_scale_arcsec(&self) -> f64 {
+        1.0 / (self.focal_length_px * ASEC2RAD)
+    }
+
+    /// Full field of view across the width and height, degrees
+    pub fn field_of_view_deg(&self) -> (f64, f64) {
+        let across =
+            |pixels: usize| 2.0 * (pixels as f64 / 2.0 / self.focal_length_px).atan() * RAD2DEG;
+        (across(self.width_px), across(self.height_px))
+    }
+
+    /// Angle from the center of the field to its corners, degrees
+    pub fn corner_radius_deg(&self) -> f64 {
+        let half_diagonal = (self.width_px as f64).hypot(self.height_px as f64) / 2.0;
+        (half_diagonal / self.focal_length_px).atan() * RAD2DEG
+    }
+
+    /// Pixel coordinates of a position, or `None` for positions 90° or more
+    /// from the boresight; the result may fall outside the detector
+    pub fn project(&self, pointing: &Pointing, position: &Equatorial) -> Option<(f64, f64)> {
+        let (east, north) = GnomonicProjection::new(pointing.center).project(position)?;
+        let (sin_roll, cos_roll) = (pointing.roll_deg * DEG2RAD).sin_cos();
+        let x = east * cos_roll - north * sin_roll;
+        let y = east * sin_roll + north * cos_roll;
+        Some((
+            x * self.focal_length_px + self.width_px as f64 / 2.0,
+            y * self.focal_length_px + self.height_px as f64 / 2.0,
+        ))
+    }
+
+    /// Sky position seen at pixel coordinates `(x, y)`
+    pub fn unproject(&self, pointing: &Pointing, x: f64, y: f64) -> Equatorial {
+        let x = (x - self.width_px as f64 / 2.0) / self.focal_length_px;
+        let y = (y - self.height_px as f64 / 2.0) / self.focal_length_px;
+        let (sin_roll, cos_roll) = (pointing.roll_deg * DEG2RAD).sin_cos();
+        let east = x * cos_roll + y * sin_roll;
+        let north = y * cos_roll - x * sin_roll;
+        GnomonicProjection::new(pointing.center).unproject(east, north)
+    }
+
+    /// The FITS `TAN` WCS of images taken at `pointing`
+    ///
+    /// FITS counts pixels from 1 at the center of the first, so WCS
+    /// coordinates are this camera's pixel coordinates plus a half.
+    pub fn wcs(&self, pointing: &Pointing) -> Wcs {
+        Wcs::from_scale(
+            Projection::Tan,
+            pointing.center,
+            (
+                self.width_px as f64 / 2.0 + 0.5,
+                self.height_px as f64 / 2.0 + 0.5,
+            ),
+            self.pixel_scale_arcsec(),
+            pointing.roll_deg,
+        )
+    }
+
+    /// Whether pixel coordinates are on the detector
+    pub fn contains(&self, (x, y): (f64, f64)) -> bool {
+        (0.0..self.width_px as f64).contains(&x) && (0.0..self.height_px as f64).contains(&y)
+    }
+}
+
+/// Where a camera looks: the boresight and the roll about it
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Pointing {
+    /// Sky position at the center of the detector
+    pub center: Equatorial,
+    /// Position angle of the detector's +y axis, degrees east of north
+    pub roll_deg: f64,
+}
+
+impl Pointing {
+    /// Look at `center` with the detector's +y axis at `roll_deg` east of
+    /// north
+    pub fn new(center: Equatorial, roll_deg: f64) -> Self {
+        Self { center, roll_deg }
+    }
+}
+
+/// A star as it lands on the detector
+#[derive(Debug, Clone)]
+pub struct PlacedStar {
+    /// The catalog star
+    pub star: StarData,
+    /// Pixel x coordinate of its image
+    pub x: f64,
+    /// Pixel y coordinate of its image
+    pub y: f64,
+    /// Signal collected over the exposure, electrons
+    pub flux_e: f64,
+}
+
+/// Renders catalog stars into images
+#[derive(Debug, Clone)]
+pub struct StarFieldRenderer {
+    /// The camera taking the image
+    pub camera: Camera,
+    /// How each star's light is spread over the pixels
+    pub psf: Arc<dyn PointSpreadFunction>,
+    /// Magnitude that yields one electron per second
+    pub zero_point_mag: f64,
+    /// Exposure time, seconds
+    pub exposure_s: f64,
+    /// Stars fainter than this are left out
+    pub mag_limit: f64,
+}
+
+impl StarFieldRenderer {
+    /// A renderer for `camera` with a 2-pixel Gaussian PSF, a zero point of
+    /// magnitude 20 and a one-second exposure
+    pub fn new(camera: Camera) -> Self {
+        Self {
+            camera,
+            psf: Arc::new(GaussianPsf::new(2.0)),
+            zero_point_mag: 20.0,
+            exposure_s: 1.0,
+            mag_limit: f64::INFINITY,
+        }
+    }
+
+    /// Use a Gaussian PSF with the given full width at half maximum in
+    /// pixels
+    pub fn with_psf_fwhm(self, fwhm_px: f64) -> Self {
+        self.with_psf(GaussianPsf::new(fwhm_px))
+    }
+
+    /// Spread each star's light with `psf`
+    pub fn with_psf<P: PointSpreadFunction + 'static>(mut self, psf: P) -> Self {
+        self.psf = Arc::new(psf);
+        self
+    }
+
+    /// Set the magnitude that yields one electron per second
+    pub fn with_zero_point(mut self, zero_point_mag: f64) -> Self {
+        self.zero_point_mag = zero_point_mag;
+        self
+    }
+
+    /// Set the exposure time in seconds
+    pub fn with_exposure(mut self, exposure_s: f64) -> Self {
+        self.exposure_s = exposure_s;
+        self
+    }
+
+    /// Leave out stars fainter than `mag_limit`
+    pub fn with_mag_limit(mut self, mag_limit: f64) -> Self {
+        self.mag_limit = mag_limit;
+        self
+    }
+
+    /// Electrons collected from a star of magnitude `magnitude`
+    pub fn flux_electrons(&self, magnitude: f64) -> f64 {
+        10f64.powf(-0.4 * (magnitude - self.zero_point_mag)) * self.exposure_s
+    }
+
+    /// Every star bright enough whose light reaches the detector, with its
+    /// pixel position and signal
+    pub fn place<C: StarCatalog>(&self, catalog: &C, pointing: &Pointing) -> Vec<PlacedStar> {
+        let margin = self.psf.radius_px();
+        let reach_deg =
+            self.camera.corner_radius_deg() + margin * self.camera.pixel_scale_arcsec() / 3600.0;
+        let (width, height) = (self.camera.width_px as f64, self.camera.height_px as f64);
+
+        catalog
+            .stars_in_field(
+                pointing.center.ra_degrees(),
+                pointing.center.dec_degrees(),
+                2.0 * reach_deg,
+            )
+            .into_iter()
+            .filter(|star| star.magnitude <= self.mag_limit)
+            .filter_map(|star| {
+                let (x, y) = self.camera.project(pointing, &star.position)?;
+                let reaches = (-margin..width + margin).contains(&x)
+                    && (-margin..height + margin).contains(&y);
+                reaches.then(|| PlacedStar {
+                    flux_e: self.flux_electrons(star.magnitude),
+                    star,
+                    x,
+                    y,
+                })
+            })
+            .collect()
+    }
+
+    /// Image of a catalog's stars in electrons, `height_px` rows by
+    /// `width_px` columns
+    pub fn render<C: StarCatalog>(&self, catalog: &C, pointing: &Pointing) -> Array2<f64> {
+        let mut image = Array2::zeros((self.camera.height_px, self.camera.width_px));
+        for star in self.place(catalog, pointing) {
+            self.add_star(&mut image, star.x, star.y, star.flux_e);
+        }
+        image
+    }
+
+    /// Add `flux_e` electrons spread by the PSF about pixel coordinates
+    /// `(x, y)`; light falling off the image is lost
+    pub fn add_star(&self, image: &mut Array2<f64>, x: f64, y: f64, flux_e: f64) {
+        psf::add_star(image, self.psf.as_ref(), x, y, flux_e);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::catalogs::{BinaryCatalog, MinimalStar};
+    use approx::assert_relative_eq;
+
+    /// Intensity-weighted centroid of an image, pixel coordinates
+    fn centroid(image: &Array2<f64>) -> (f64, f64) {
+        let (mut sx, mut sy, mut total) = (0.0, 0.0, 0.0);
+        for ((row, column), &value) in image.indexed_iter() {
+            sx += (column as f64 + 0.5) * value;
+            sy += (row as f64 + 0.5) * value;
+            total += value;
+        }
+        (sx / total, sy / total)
+    }
+
+    #[test]
+    fn test_star_lands_on_projected_pixel_with_its_flux() {
+        let camera = Camera::with_plate_scale(64, 48, 2.0);
+        assert_relative_eq!(camera.pixel_scale_arcsec(), 2.0, epsilon = 1e-12);
+        let renderer = StarFieldRenderer::new(camera)
+            .with_psf_fwhm(3.0)
+            .with_zero_point(15.0)
+            .with_exposure(4.0);
+
+        // 10 arcseconds north and 20 east of a boresight on the equator
+        let center = Equatorial::from_degrees(150.0, 0.0);
+        let catalog = BinaryCatalog::from_stars(
+            vec![MinimalStar::new(
+                7,
+                150.0 + 20.0 / 3600.0,
+                10.0 / 3600.0,
+                10.0,
+            )],
+            "one star",
+        );
+
+        let image = renderer.render(&catalog, &Pointing::new(center, 0.0));
+        assert_eq!(image.dim(), (48, 64));
+        assert_relative_eq!(image.sum(), 400.0, max_relative = 1e-6);
+        let (x, y) = centroid(&image);
+        assert_relative_eq!(x, 32.0 + 10.0, epsilon = 1e-3);
+        assert_relative_eq!(y, 24.0 + 5.0, epsilon = 1e-3);
+
+        // Rolling the detector by 90 degrees turns east into +y
+        let placed = renderer.place(&catalog, &Pointing::new(center, 90.0));
+        assert_eq!(placed.len(), 1);
+        assert_relative_eq!(placed[0].x, 32.0 - 5.0, epsilon = 1e-6);
+        assert_relative_eq!(placed[0].y, 24.0 + 10.0, epsilon = 1e-6);
+        assert_relative_eq!(placed[0].flux_e, 400.0, max_relative = 1e-12);
+    }
+
+    #[test]
+    fn test_projection_roundtrip_and_field_edges() {
+        let camera = Camera::new(2000, 1000, 50.0, 10.0);
+        let pointing = Pointing::new(Equatorial::from_degrees(10.0, 60.0), 30.0);
+        for (x, y) in [(0.0, 0.0), (1999.5, 3.0), (1000.0, 500.0), (17.25, 999.0)] {
+            let sky = camera.unproject(&pointing, x, y);
+            let (x2, y2) = camera.project(&pointing, &sky).unwrap();
+            assert_relative_eq!(x2, x, epsilon = 1e-6);
+            assert_relative_eq!(y2, y, epsilon = 1e-6);
+
+            let (wx, wy) = camera.wcs(&pointing).sky_to_pixel(&sky).unwrap();
+            assert_relative_eq!(wx - 0.5, x, epsilon = 1e-6);
+            assert_relative_eq!(wy - 0.5, y, epsilon = 1e-6);
+        }
+
+        // A star just off the detector still lights its edge, one farther
+        // out does not appear, and faint stars can be cut
+        let camera = Camera::with_plate_scale(32, 32, 1.0);
+        let renderer = StarFieldRenderer::new(camera).with_psf_fwhm(2.0);
+        let pointing = Pointing::new(Equatorial::from_degrees(0.0, 0.0), 0.0);
+        let catalog = BinaryCatalog::from_stars(
+            vec![
+                MinimalStar::new(1, 17.0 / 3600.0, 0.0, 5.0),
+                MinimalStar::new(2, 40.0 / 3600.0, 0.0, 5.0),
+                MinimalStar::new(3, 0.0, 0.0, 9.0),
+            ],
+            "edge",
+        );
+        let ids: Vec<u64> = renderer
+            .place(&catalog, &pointing)
+            .iter()
+            .map(|p| p.star.id)
+            .collect();
+        assert_eq!(ids.len(), 2);
+        assert!(ids.contains(&1) && ids.contains(&3));
+        let image = renderer.render(&catalog, &pointing);
+        assert!(image[[16, 31]] > 0.0);
+
+        let bright = renderer.with_mag_limit(6.0).place(&catalog, &pointing);
+        assert_eq!(bright.len(), 1);
+        assert_eq!(bright[0].star.id, 1);
+    }
+
+    #[test]
+    fn test_flux_scales_with_magnitude() {
+        let renderer = StarFieldRenderer::new(Camera::with_plate_scale(8, 8, 1.0));
+        assert_relative_eq!(renderer.flux_electrons(20.0), 1.0);
+        assert_relative_eq!(
+            renderer.flux_electrons(10.0) / renderer.flux_electrons(15.0),
+            100.0,
+            max_relative = 1e-12
+        );
+    }
+}
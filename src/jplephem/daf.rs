@@ -0,0 +1,505 @@
+//! NAIF Double precision Array File (DAF) reader
+//!
+//! DAF is the container format behind SPK and binary PCK kernels. A file is a
+//! sequence of 1024-byte records: a file record, then doubly linked summary
+//! records (each followed by a name record) describing arrays of doubles
+//! stored elsewhere in the file. Array addresses count 8-byte words from 1.
+
+use super::{JplEphemError, Result};
+use byteorder::{BigEndian, ByteOrder, LittleEndian};
+use std::collections::HashSet;
+use std::fs::File;
+use std::io::{BufReader, Read, Seek, SeekFrom};
+use std::path::Path;
+use std::sync::Mutex;
+
+/// Size of a DAF record in bytes
+pub const RECORD_BYTES: usize = 1024;
+
+/// Anything a DAF can be read from
+pub trait DafSource: Read + Seek + Send {}
+impl<T: Read + Seek + Send> DafSource for T {}
+
+/// Byte order of the numbers stored in a DAF
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Endian {
+    Little,
+    Big,
+}
+
+/// One array summary: its name plus ND doubles and NI integers
+#[derive(Debug, Clone, PartialEq)]
+pub struct Summary {
+    pub name: String,
+    pub doubles: Vec<f64>,
+    pub ints: Vec<i32>,
+}
+
+/// An open DAF file
+pub struct DAF {
+    source: Mutex<Box<dyn DafSource>>,
+    /// File identification word, e.g. `DAF/SPK`
+    pub locidw: String,
+    /// Internal file name
+    pub locifn: String,
+    /// Number of double precision components in each summary
+    pub nd: usize,
+    /// Number of integer components in each summary
+    pub ni: usize,
+    /// Record number of the first summary record
+    pub fward: usize,
+    /// Record number of the last summary record
+    pub bward: usize,
+    /// Byte order of the file's numbers
+    pub endian: Endian,
+}
+
+impl std::fmt::Debug for DAF {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("DAF")
+            .field("locidw", &self.locidw)
+            .field("locifn", &self.locifn)
+            .field("nd", &self.nd)
+            .field("ni", &self.ni)
+            .field("endian", &self.endian)
+            .finish()
+    }
+}
+
+fn text(bytes: &[u8]) -> String {
+    String::from_utf8_lossy(bytes)
+        .trim_end_matches(['\0', ' '])
+        .to_string()
+}
+
+impl DAF {
+    /// Open a DAF file from disk
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let file = File::open(path)?;
+        Self::from_source(Box::new(BufReader::new(file)))
+    }
+
+    /// Read a DAF from any seekable source (for example an in-memory cursor)
+    pub fn from_source(mut source: Box<dyn DafSource>) -> Result<Self> {
+        let mut record = [0u8; RECORD_BYTES];
+        source.seek(SeekFrom::Start(0))?;
+        source.read_exact(&mut record)?;
+
+        let locidw = text(&record[0..8]);
+        if !(locidw.starts_with("DAF/") || locidw.starts_with("NAIF/DAF")) {
+            return Err(JplEphemError::InvalidFormat(format!(
+                "not a DAF file (identification word {:?})",
+                locidw
+            )));
+        }
+
+        let endian = match &record[88..96] {
+            b"LTL-IEEE" => Endian::Little,
+            b"BIG-IEEE" => Endian::Big,
+            _ => {
+                // Pre-1990s files lack LOCFMT; ND is always small, which
+                // reveals the byte order
+                let nd = LittleEndian::read_i32(&record[8..12]);
+                if (0..=124).contains(&nd) {
+                    Endian::Little
+                } else {
+                    Endian::Big
+                }
+            }
+        };
+
+        let int = |offset: usize| -> i32 {
+            match endian {
+                Endian::Little => LittleEndian::read_i32(&record[offset..offset + 4]),
+                Endian::Big => BigEndian::read_i32(&record[offset..offset + 4]),
+            }
+        };
+
+        let (nd, ni) = (int(8), int(12));
+        let (fward, bward) = (int(76), int(80));
+        if nd < 0 || ni < 2 || fward < 1 || bward < 1 {
+            return Err(JplEphemError::InvalidFormat(format!(
+                "corrupt file record (ND={}, NI={}, FWARD={}, BWARD={})",
+                nd, ni, fward, bward
+            )));
+        }
+
+        let daf = DAF {
+            source: Mutex::new(source),
+            locidw,
+            locifn: text(&record[16..76]),
+            nd: nd as usize,
+            ni: ni as usize,
+            fward: fward as usize,
+            bward: bward as usize,
+            endian,
+        };
+
+        if daf.summary_size() > 125 {
+            return Err(JplEphemError::InvalidFormat(format!(
+                "summaries of {} words do not fit in a record",
+                daf.summary_size()
+            )));
+        }
+
+        Ok(daf)
+    }
+
+    /// Size of one summary in 8-byte words
+    pub fn summary_size(&self) -> usize {
+        self.nd + self.ni.div_ceil(2)
+    }
+
+    fn read_bytes(&self, offset: u64, buffer: &mut [u8]) -> Result<()> {
+        let mut source = self
+            .source
+            .lock()
+            .map_err(|_| JplEphemError::InvalidFormat("DAF source lock poisoned".into()))?;
+        source.seek(SeekFrom::Start(offset))?;
+        source.read_exact(buffer)?;
+        Ok(())
+    }
+
+    fn read_record(&self, number: usize) -> Result<Vec<u8>> {
+        let mut record = vec![0u8; RECORD_BYTES];
+        self.read_bytes(((number - 1) * RECORD_BYTES) as u64, &mut record)?;
+        Ok(record)
+    }
+
+    fn double(&self, bytes: &[u8]) -> f64 {
+        match self.endian {
+            Endian::Little => LittleEndian::read_f64(bytes),
+            Endian::Big => BigEndian::read_f64(bytes),
+        }
+    }
+
+    fn int(&self, bytes: &[u8]) -> i32 {
+        match self.endian {
+            Endian::Little => LittleEndian::read_i32(bytes),
+            Endian::Big => BigEndian::read_i32(bytes),
+        }
+    }
+
+    /// Read every array summary, following the summary record chain
+    ///
+    /// Each summary record starts with three control words: the record
+    /// numbers of the next and previous summary records (0 at either end of
+    /// the chain) and the number of summaries it holds. A summary packs ND
+    /// doubles followed by NI 4-byte integers, padded to whole words, and
+    /// its name sits at the same index in the record that follows. A chain
+    /// that loops, disagrees with its back links or misses `BWARD` is an
+    /// error rather than a partial result.
+    pub fn summaries(&self) -> Result<Vec<Summary>> {
+        let ss = self.summary_size();
+        let per_record = 125 / ss;
+        let mut summaries = Vec::new();
+        let mut visited = HashSet::new();
+        let mut previous = 0;
+        let mut record_number = self.fward;
+
+        while record_number != 0 {
+            if !visited.insert(record_number) {
+                return Err(JplEphemError::InvalidFormat(format!(
+                    "summary record chain loops back to record {}",
+                    record_number
+                )));
+            }
+
+            let record = self.read_record(record_number)?;
+            let names = self.read_record(record_number + 1)?;
+
+            let control = |offset: usize| -> Option<usize> {
+                let word = self.double(&record[offset..offset + 8]);
+                (word >= 0.0 && word.fract() == 0.0).then_some(word as usize)
+            };
+            let (next, prev, count) = match (control(0), control(8), control(16)) {
+                (Some(next), Some(prev), Some(count)) if count <= per_record => (next, prev, count),
+                _ => {
+                    return Err(JplEphemError::InvalidFormat(format!(
+                        "corrupt summary record {} (NEXT={}, PREV={}, NSUM={})",
+                        record_number,
+                        self.double(&record[0..8]),
+                        self.double(&record[8..16]),
+                        self.double(&record[16..24])
+                    )))
+                }
+            };
+            if prev != previous {
+                return Err(JplEphemError::InvalidFormat(format!(
+                    "summary record {} links back to record {}, expected {}",
+                    record_number, prev, previous
+                )));
+            }
+
+            for i in 0..count {
+                let start = 24 + i * ss * 8;
+                let words = &record[start..start + ss * 8];
+
+                let doubles = (0..self.nd)
+                    .map(|k| self.double(&words[k * 8..k * 8 + 8]))
+                    .collect();
+                let int_bytes = &words[self.nd * 8..];
+                let ints = (0..self.ni)
+                    .map(|k| self.int(&int_bytes[k * 4..k * 4 + 4]))
+                    .collect();
+
+                let nc = ss * 8;
+                let name = text(&names[i * nc..(i + 1) * nc]);
+
+                summaries.push(Summary {
+                    name,
+                    doubles,
+                    ints,
+                });
+            }
+
+            previous = record_number;
+            record_number = next;
+        }
+
+        if previous != self.bward {
+            return Err(JplEphemError::InvalidFormat(format!(
+                "summary record chain ends at record {}, but BWARD is {}",
+                previous, self.bward
+            )));
+        }
+
+        Ok(summaries)
+    }
+
+    /// Read the doubles stored at word addresses `start..=end` (1-based)
+    pub fn read_array(&self, start: usize, end: usize) -> Result<Vec<f64>> {
+        if start == 0 || end < start {
+            return Err(JplEphemError::InvalidFormat(format!(
+                "invalid array bounds {}..={}",
+                start, end
+            )));
+        }
+        let mut bytes = vec![0u8; (end - start + 1) * 8];
+        self.read_bytes(((start - 1) * 8) as u64, &mut bytes)?;
+        Ok(bytes.chunks_exact(8).map(|b| self.double(b)).collect())
+    }
+}
+
+/// Build DAF files in memory for tests
+#[cfg(test)]
+pub(crate) mod testing {
+    use super::RECORD_BYTES;
+
+    /// An array to store: name, summary doubles, summary integers (the
+    /// start/end address pair is appended automatically) and the data
+    pub struct Array {
+        pub name: String,
+        pub doubles: Vec<f64>,
+        pub ints: Vec<i32>,
+        pub data: Vec<f64>,
+    }
+
+    /// Write a little-endian DAF with one summary record per `per_record`
+    /// arrays, so multi-record chains can be exercised
+    pub fn build(
+        locidw: &str,
+        nd: usize,
+        ni: usize,
+        arrays: &[Array],
+        per_record: usize,
+    ) -> Vec<u8> {
+        let ss = nd + ni.div_ceil(2);
+        let groups: Vec<&[Array]> = arrays.chunks(per_record.max(1)).collect();
+
+        // Layout: file record, then for each group a summary record, a name
+        // record and the group's data
+        let mut record_of_group = Vec::new();
+        let mut next_record = 2;
+        let mut data_start = Vec::new();
+        for group in &groups {
+            record_of_group.push(next_record);
+            let mut word = (next_record + 1) * 128 + 1;
+            let mut starts = Vec::new();
+            for array in group.iter() {
+                starts.push(word);
+                word += array.data.len();
+            }
+            data_start.push(starts);
+            next_record = (word - 1).div_ceil(128) + 1;
+        }
+
+        let mut file = vec![0u8; (next_record - 1) * RECORD_BYTES];
+        let put = |file: &mut Vec<u8>, offset: usize, bytes: &[u8]| {
+            file[offset..offset + bytes.len()].copy_from_slice(bytes);
+        };
+
+        put(&mut file, 0, format!("{:<8}", locidw).as_bytes());
+        put(&mut file, 8, &(nd as i32).to_le_bytes());
+        put(&mut file, 12, &(ni as i32).to_le_bytes());
+        put(
+            &mut file,
+            16,
+            format!("{:<60}", "starfield test kernel").as_bytes(),
+        );
+        put(&mut file, 76, &(record_of_group[0] as i32).to_le_bytes());
+        put(
+            &mut file,
+            80,
+            &(*record_of_group.last().unwrap() as i32).to_le_bytes(),
+        );
+        put(
+            &mut file,
+            84,
+            &((next_record as i32 - 1) * 128 + 1).to_le_bytes(),
+        );
+        put(&mut file, 88, b"LTL-IEEE");
+
+        for (g, group) in groups.iter().enumerate() {
+            let record = record_of_group[g];
+            let base = (record - 1) * RECORD_BYTES;
+            let next = record_of_group.get(g + 1).copied().unwrap_or(0) as f64;
+            let prev = if g == 0 {
+                0.0
+            } else {
+                record_of_group[g - 1] as f64
+            };
+            put(&mut file, base, &next.to_le_bytes());
+            put(&mut file, base + 8, &prev.to_le_bytes());
+            put(&mut file, base + 16, &(group.len() as f64).to_le_bytes());
+
+            for (i, array) in group.iter().enumerate() {
+                let start = data_start[g][i];
+                let end = start + array.data.len() - 1;
+
+                let offset = base + 24 + i * ss * 8;
+                for (k, d) in array.doubles.iter().enumerate() {
+                    put(&mut file, offset + k * 8, &d.to_le_bytes());
+                }
+                let mut ints = array.ints.clone();
+                ints.push(start as i32);
+                ints.push(end as i32);
+                for (k, n) in ints.iter().enumerate() {
+                    put(&mut file, offset + nd * 8 + k * 4, &n.to_le_bytes());
+                }
+
+                let name = format!("{:<width$}", array.name, width = ss * 8);
+                put(&mut file, base + RECORD_BYTES + i * ss * 8, name.as_bytes());
+
+                for (k, d) in array.data.iter().enumerate() {
+                    put(&mut file, (start - 1 + k) * 8, &d.to_le_bytes());
+                }
+            }
+        }
+
+        file
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::testing::{build, Array};
+    use super::*;
+    use std::io::Cursor;
+
+    fn arrays(n: usize) -> Vec<Array> {
+        (0..n)
+            .map(|i| Array {
+                name: format!("ARRAY {}", i),
+                doubles: vec![i as f64, -(i as f64)],
+                ints: vec![i as i32, 7, 1, 2],
+                data: (0..10).map(|k| (i * 100 + k) as f64).collect(),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_reads_summaries_across_records() {
+        let bytes = build("DAF/SPK", 2, 6, &arrays(5), 2);
+        let daf = DAF::from_source(Box::new(Cursor::new(bytes))).unwrap();
+        assert_eq!((daf.nd, daf.ni), (2, 6));
+        assert_eq!(daf.endian, Endian::Little);
+
+        let summaries = daf.summaries().unwrap();
+        assert_eq!(summaries.len(), 5);
+        for (i, s) in summaries.iter().enumerate() {
+            assert_eq!(s.name, format!("ARRAY {}", i));
+            assert_eq!(s.doubles, vec![i as f64, -(i as f64)]);
+            assert_eq!(&s.ints[..4], &[i as i32, 7, 1, 2]);
+
+            let data = daf
+                .read_array(s.ints[4] as usize, s.ints[5] as usize)
+                .unwrap();
+            assert_eq!(data.len(), 10);
+            assert_eq!(data[3], (i * 100 + 3) as f64);
+        }
+    }
+
+    #[test]
+    fn test_rejects_broken_summary_chains() {
+        let bytes = build("DAF/SPK", 2, 6, &arrays(5), 2);
+        let parse = |bytes: Vec<u8>| {
+            DAF::from_source(Box::new(Cursor::new(bytes)))
+                .unwrap()
+                .summaries()
+        };
+        let control = |bytes: &mut Vec<u8>, record: usize, word: usize, value: f64| {
+            let offset = (record - 1) * RECORD_BYTES + word * 8;
+            bytes[offset..offset + 8].copy_from_slice(&value.to_le_bytes());
+        };
+        // Record 2 is the first summary record; its NEXT names the second
+        let second = LittleEndian::read_f64(&bytes[RECORD_BYTES..]) as usize;
+        assert!(parse(bytes.clone()).is_ok());
+
+        // NEXT pointing back at the first record loops
+        let mut looped = bytes.clone();
+        control(&mut looped, second, 0, 2.0);
+        assert!(parse(looped).is_err());
+
+        // PREV disagreeing with the forward chain
+        let mut relinked = bytes.clone();
+        control(&mut relinked, second, 1, 7.0);
+        assert!(parse(relinked).is_err());
+
+        // A chain cut short of BWARD
+        let mut truncated = bytes.clone();
+        control(&mut truncated, second, 0, 0.0);
+        assert!(parse(truncated).is_err());
+
+        // Summary counts must be whole and fit in the record
+        let mut fractional = bytes.clone();
+        control(&mut fractional, 2, 2, 1.5);
+        assert!(parse(fractional).is_err());
+        let mut overfull = bytes;
+        control(&mut overfull, 2, 2, 40.0);
+        assert!(parse(overfull).is_err());
+    }
+
+    #[test]
+    fn test_packs_odd_integer_counts() {
+        // Binary PCK layout: NI=5 leaves half a word of padding per summary
+        let arrays: Vec<Array> = (0..3)
+            .map(|i: i32| Array {
+                name: format!("FRAME {}", i),
+                doubles: vec![i as f64, 1.0],
+                ints: vec![31000 + i, 1, 2],
+                data: vec![i as f64; 4],
+            })
+            .collect();
+        let bytes = build("DAF/PCK", 2, 5, &arrays, 10);
+        let daf = DAF::from_source(Box::new(Cursor::new(bytes))).unwrap();
+        assert_eq!(daf.summary_size(), 5);
+
+        let summaries = daf.summaries().unwrap();
+        assert_eq!(summaries.len(), 3);
+        for (i, s) in summaries.iter().enumerate() {
+            assert_eq!(s.name, format!("FRAME {}", i));
+            assert_eq!(&s.ints[..3], &[31000 + i as i32, 1, 2]);
+            let data = daf
+                .read_array(s.ints[3] as usize, s.ints[4] as usize)
+                .unwrap();
+            assert_eq!(data, vec![i as f64; 4]);
+        }
+    }
+
+    #[test]
+    fn test_rejects_non_daf() {
+        let bytes = vec![0u8; RECORD_BYTES];
+        assert!(DAF::from_source(Box::new(Cursor::new(bytes))).is_err());
+    }
+}
@@ -0,0 +1,45 @@
+//! Reading JPL and NAIF SPICE binary kernels
+//!
+//! A Rust counterpart of Brandon Rhodes' `jplephem` package: [`daf`] reads the
+//! Double precision Array File container, [`spk`] evaluates ephemeris
+//! segments from planetary kernels such as DE421, DE440 and DE441,
+//! [`pck`] evaluates body orientation from binary PCK files, and
+//! [`text_kernel`] reads the constants of leapseconds, text PCK and frame
+//! kernels.
+
+pub mod daf;
+pub mod pck;
+pub mod spk;
+pub mod text_kernel;
+
+pub use daf::DAF;
+pub use pck::{PckSegment, PCK};
+pub use spk::{Segment, SPK};
+pub use text_kernel::{KernelPool, KernelValue};
+
+use thiserror::Error;
+
+/// Error type for kernel reading and evaluation
+#[derive(Debug, Error)]
+pub enum JplEphemError {
+    #[error("I/O error: {0}")]
+    Io(#[from] std::io::Error),
+
+    #[error("Invalid kernel: {0}")]
+    InvalidFormat(String),
+
+    #[error("Unsupported segment type {0}")]
+    UnsupportedType(i32),
+
+    #[error("No segment for center {center} and target {target}")]
+    SegmentNotFound { center: i32, target: i32 },
+
+    #[error("No orientation segment for frame {0}")]
+    FrameNotFound(i32),
+
+    #[error("Date JD {jd} is outside the coverage JD {start} to {end}")]
+    OutOfRange { jd: f64, start: f64, end: f64 },
+}
+
+/// Result type for kernel operations
+pub type Result<T> = std::result::Result<T, JplEphemError>;
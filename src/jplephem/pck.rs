@@ -0,0 +1,354 @@
+//! Binary PCK (Planetary Constants Kernel) orientation files
+//!
+//! A binary PCK is a DAF whose arrays give the orientation of a body-fixed
+//! frame as three Euler angles over a span of TDB: the right ascension φ
+//! and declination δ of the body's pole and the prime meridian angle w,
+//! relative to an inertial reference frame. Supported segment types:
+//!
+//! * Type 2 - Chebyshev angles, rates by differentiation
+//! * Type 3 - Chebyshev angles and rates
+//!
+//! The high-accuracy Earth kernels (`earth_latest_high_prec.bpc`) give the
+//! ITRF93 frame relative to ECLIPJ2000, and the lunar kernels
+//! (`moon_pa_de421_1900-2050.bpc`) the lunar principal-axis frame relative
+//! to J2000; both reference frames are handled, so [`PCK::rotation_at`]
+//! always starts from ICRF axes. See [`crate::framelib::BodyFixed`] for
+//! converting vectors.
+
+use super::daf::DAF;
+use super::spk::{chebyshev, ChebyshevDirectory};
+use super::{JplEphemError, Result};
+use crate::constants::{DAY_S, J2000};
+use crate::framelib::{rot_x, rot_z, INERTIAL_FRAMES};
+use nalgebra::Matrix3;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+/// NAIF frame ID of the ITRF93 Earth-fixed frame
+pub const ITRF93: i32 = 3000;
+/// NAIF frame ID of the lunar principal-axis frame of DE421
+pub const MOON_PA_DE421: i32 = 31006;
+/// NAIF frame ID of the lunar principal-axis frame of DE440
+pub const MOON_PA_DE440: i32 = 31008;
+
+/// NAIF frame ID of the J2000 (ICRF) equatorial frame
+const J2000_FRAME: i32 = 1;
+/// NAIF frame ID of the J2000 mean ecliptic frame
+const ECLIPJ2000_FRAME: i32 = 17;
+
+/// One PCK segment: the orientation of frame `body` relative to `frame`
+#[derive(Debug)]
+pub struct PckSegment {
+    daf: Arc<DAF>,
+    /// Segment name from the DAF name record
+    pub source: String,
+    /// Start of coverage in TDB seconds past J2000
+    pub start_second: f64,
+    /// End of coverage in TDB seconds past J2000
+    pub end_second: f64,
+    /// NAIF ID of the body-fixed frame
+    pub body: i32,
+    /// NAIF ID of the inertial reference frame
+    pub frame: i32,
+    /// PCK data type
+    pub data_type: i32,
+    /// First word of the segment's data
+    pub start_i: usize,
+    /// Last word of the segment's data
+    pub end_i: usize,
+    /// Start of coverage as a TDB Julian date
+    pub start_jd: f64,
+    /// End of coverage as a TDB Julian date
+    pub end_jd: f64,
+    directory: Option<ChebyshevDirectory>,
+}
+
+impl PckSegment {
+    fn new(daf: Arc<DAF>, source: String, doubles: &[f64], ints: &[i32]) -> Result<Self> {
+        if doubles.len() < 2 || ints.len() < 5 {
+            return Err(JplEphemError::InvalidFormat(format!(
+                "segment {:?} has a malformed summary",
+                source
+            )));
+        }
+
+        let (start_i, end_i) = (ints[3] as usize, ints[4] as usize);
+        let data_type = ints[2];
+        let directory = match data_type {
+            2 => Some(ChebyshevDirectory::read(&daf, end_i, 3, &source)?),
+            3 => Some(ChebyshevDirectory::read(&daf, end_i, 6, &source)?),
+            _ => None,
+        };
+
+        Ok(PckSegment {
+            daf,
+            source,
+            start_second: doubles[0],
+            end_second: doubles[1],
+            body: ints[0],
+            frame: ints[1],
+            data_type,
+            start_i,
+            end_i,
+            start_jd: J2000 + doubles[0] / DAY_S,
+            end_jd: J2000 + doubles[1] / DAY_S,
+            directory,
+        })
+    }
+
+    /// Whether the segment covers a TDB Julian date
+    pub fn covers(&self, jd_tdb: f64) -> bool {
+        (self.start_jd..=self.end_jd).contains(&jd_tdb)
+    }
+
+    /// Euler angles φ, δ, w in radians and their rates in radians per day
+    /// at a TDB Julian date
+    pub fn angles(&self, jd_tdb: f64) -> Result<([f64; 3], [f64; 3])> {
+        if !self.covers(jd_tdb) {
+            return Err(JplEphemError::OutOfRange {
+                jd: jd_tdb,
+                start: self.start_jd,
+                end: self.end_jd,
+            });
+        }
+        let directory = self
+            .directory
+            .as_ref()
+            .ok_or(JplEphemError::UnsupportedType(self.data_type))?;
+        let et = (jd_tdb - J2000) * DAY_S;
+        let components = if self.data_type == 2 { 3 } else { 6 };
+        let (values, rates) = chebyshev(&self.daf, self.start_i, directory, components, et)?;
+
+        let angles = [values[0], values[1], values[2]];
+        let rates = if self.data_type == 3 {
+            // Stored rates are in radians per second
+            [values[3] * DAY_S, values[4] * DAY_S, values[5] * DAY_S]
+        } else {
+            [rates[0], rates[1], rates[2]]
+        };
+        Ok((angles, rates))
+    }
+
+    /// Rotation from ICRF to the body-fixed frame at a TDB Julian date,
+    /// and its time derivative per day
+    pub fn rotation_and_rate(&self, jd_tdb: f64) -> Result<(Matrix3<f64>, Matrix3<f64>)> {
+        let reference = match self.frame {
+            J2000_FRAME => Matrix3::identity(),
+            ECLIPJ2000_FRAME => INERTIAL_FRAMES["ECLIPJ2000"],
+            other => {
+                return Err(JplEphemError::InvalidFormat(format!(
+                    "segment {:?} is relative to unsupported frame {}",
+                    self.source, other
+                )))
+            }
+        };
+
+        let ([phi, delta, w], [phi_dot, delta_dot, w_dot]) = self.angles(jd_tdb)?;
+        let (rw, rd, rp) = (rot_z(-w), rot_x(-delta), rot_z(-phi));
+        let rotation = rw * rd * rp;
+        let rate = -w_dot * rot_z_rate(-w) * rd * rp
+            - delta_dot * rw * rot_x_rate(-delta) * rp
+            - phi_dot * rw * rd * rot_z_rate(-phi);
+        Ok((rotation * reference, rate * reference))
+    }
+}
+
+/// Derivative of [`rot_x`] with respect to its angle
+fn rot_x_rate(angle: f64) -> Matrix3<f64> {
+    let (s, c) = angle.sin_cos();
+    Matrix3::new(0.0, 0.0, 0.0, 0.0, -s, -c, 0.0, c, -s)
+}
+
+/// Derivative of [`rot_z`] with respect to its angle
+fn rot_z_rate(angle: f64) -> Matrix3<f64> {
+    let (s, c) = angle.sin_cos();
+    Matrix3::new(-s, -c, 0.0, c, -s, 0.0, 0.0, 0.0, 0.0)
+}
+
+/// A binary PCK orientation kernel
+#[derive(Debug)]
+pub struct PCK {
+    /// The underlying DAF file
+    pub daf: Arc<DAF>,
+    /// Segments in file order
+    pub segments: Vec<PckSegment>,
+    path: Option<PathBuf>,
+}
+
+impl PCK {
+    /// Open a binary PCK file
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let mut pck = Self::from_daf(DAF::open(path.as_ref())?)?;
+        pck.path = Some(path.as_ref().to_path_buf());
+        Ok(pck)
+    }
+
+    /// Interpret an already opened DAF as a binary PCK
+    pub fn from_daf(daf: DAF) -> Result<Self> {
+        if daf.nd != 2 || daf.ni != 5 {
+            return Err(JplEphemError::InvalidFormat(format!(
+                "PCK summaries must have ND=2, NI=5 (found ND={}, NI={})",
+                daf.nd, daf.ni
+            )));
+        }
+
+        let daf = Arc::new(daf);
+        let segments = daf
+            .summaries()?
+            .into_iter()
+            .map(|s| PckSegment::new(daf.clone(), s.name, &s.doubles, &s.ints))
+            .collect::<Result<Vec<_>>>()?;
+
+        Ok(PCK {
+            daf,
+            segments,
+            path: None,
+        })
+    }
+
+    /// File the kernel was opened from, if any
+    pub fn path(&self) -> Option<&Path> {
+        self.path.as_deref()
+    }
+
+    /// All segments for a body-fixed frame
+    pub fn segments_for(&self, body: i32) -> impl Iterator<Item = &PckSegment> {
+        self.segments.iter().filter(move |s| s.body == body)
+    }
+
+    /// The segment for a body-fixed frame covering a TDB Julian date
+    ///
+    /// When several segments cover the date, the last one in the file wins.
+    pub fn segment_at(&self, body: i32, jd_tdb: f64) -> Result<&PckSegment> {
+        let mut candidates = self.segments_for(body).peekable();
+        if candidates.peek().is_none() {
+            return Err(JplEphemError::FrameNotFound(body));
+        }
+        candidates
+            .filter(|s| s.covers(jd_tdb))
+            .last()
+            .ok_or_else(|| {
+                let (start, end) = self
+                    .segments_for(body)
+                    .fold((f64::INFINITY, f64::NEG_INFINITY), |(a, b), s| {
+                        (a.min(s.start_jd), b.max(s.end_jd))
+                    });
+                JplEphemError::OutOfRange {
+                    jd: jd_tdb,
+                    start,
+                    end,
+                }
+            })
+    }
+
+    /// Rotation from ICRF to a body-fixed frame at a TDB Julian date
+    pub fn rotation_at(&self, body: i32, jd_tdb: f64) -> Result<Matrix3<f64>> {
+        Ok(self.rotation_and_rate_at(body, jd_tdb)?.0)
+    }
+
+    /// Rotation from ICRF to a body-fixed frame at a TDB Julian date, and
+    /// its time derivative per day
+    pub fn rotation_and_rate_at(
+        &self,
+        body: i32,
+        jd_tdb: f64,
+    ) -> Result<(Matrix3<f64>, Matrix3<f64>)> {
+        self.segment_at(body, jd_tdb)?.rotation_and_rate(jd_tdb)
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod testing {
+    use super::super::daf::testing::{build, Array};
+    use super::*;
+    use std::io::Cursor;
+
+    /// A one-record type 2 segment for frame `body` relative to `frame`
+    /// with φ and δ fixed and w = w0 + rate t over `days` days from J2000,
+    /// the rate in radians per day
+    pub fn rotating_array(body: i32, frame: i32, angles: [f64; 3], rate: f64, days: f64) -> Array {
+        let length = days * DAY_S;
+        let radius = length / 2.0;
+        let rate = rate / DAY_S;
+        let mut data = vec![radius, radius];
+        data.extend([angles[0], 0.0]);
+        data.extend([angles[1], 0.0]);
+        // w(s) = w0 + rate (radius + radius s)
+        data.extend([angles[2] + rate * radius, rate * radius]);
+        let rsize = data.len() as f64;
+        data.extend([0.0, length, rsize, 1.0]);
+        Array {
+            name: format!("TEST FRAME {}", body),
+            doubles: vec![0.0, length],
+            ints: vec![body, frame, 2],
+            data,
+        }
+    }
+
+    pub fn pck(arrays: &[Array]) -> PCK {
+        let bytes = build("DAF/PCK", 2, 5, arrays, 3);
+        PCK::from_daf(DAF::from_source(Box::new(Cursor::new(bytes))).unwrap()).unwrap()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::testing::{pck, rotating_array};
+    use super::*;
+    use approx::assert_relative_eq;
+    use nalgebra::Vector3;
+    use std::f64::consts::FRAC_PI_2;
+
+    #[test]
+    fn test_pole_and_prime_meridian() {
+        // Pole at the ICRF pole, prime meridian turning at one radian a day
+        let kernel = pck(&[rotating_array(MOON_PA_DE421, 1, [0.0, 0.0, 0.0], 1.0, 10.0)]);
+
+        // The body axes start on the ICRF axes
+        let r = kernel.rotation_at(MOON_PA_DE421, J2000).unwrap();
+        assert_relative_eq!(r, Matrix3::identity(), epsilon = 1e-12);
+
+        // A quarter turn later the ICRF y axis lies along body x
+        let r = kernel
+            .rotation_at(MOON_PA_DE421, J2000 + FRAC_PI_2)
+            .unwrap();
+        assert_relative_eq!(r * Vector3::y(), Vector3::x(), epsilon = 1e-9);
+
+        let (_, rate) = kernel
+            .rotation_and_rate_at(MOON_PA_DE421, J2000 + 3.0)
+            .unwrap();
+        let h = 1e-3;
+        let ahead = kernel.rotation_at(MOON_PA_DE421, J2000 + 3.0 + h).unwrap();
+        let behind = kernel.rotation_at(MOON_PA_DE421, J2000 + 3.0 - h).unwrap();
+        assert_relative_eq!(rate, (ahead - behind) / (2.0 * h), epsilon = 1e-5);
+
+        assert!(matches!(
+            kernel.rotation_at(ITRF93, J2000),
+            Err(JplEphemError::FrameNotFound(ITRF93))
+        ));
+        assert!(matches!(
+            kernel.rotation_at(MOON_PA_DE421, J2000 + 11.0),
+            Err(JplEphemError::OutOfRange { .. })
+        ));
+    }
+
+    #[test]
+    fn test_ecliptic_reference_frame() {
+        // Body axes on the ecliptic axes
+        let kernel = pck(&[rotating_array(ITRF93, 17, [0.0, 0.0, 0.0], 0.0, 1.0)]);
+        let r = kernel.rotation_at(ITRF93, J2000).unwrap();
+        let ecliptic_pole = INERTIAL_FRAMES["ECLIPJ2000"].transpose() * Vector3::z();
+        assert_relative_eq!(r * ecliptic_pole, Vector3::z(), epsilon = 1e-12);
+        assert!(r.is_orthogonal(1e-12));
+
+        // Tilting the pole by δ about the node at φ
+        let kernel = pck(&[rotating_array(ITRF93, 1, [FRAC_PI_2, 0.5, 0.0], 0.0, 1.0)]);
+        let r = kernel.rotation_at(ITRF93, J2000).unwrap();
+        let pole = r.transpose() * Vector3::z();
+        assert_relative_eq!(
+            pole,
+            Vector3::new(0.5f64.sin(), 0.0, 0.5f64.cos()),
+            epsilon = 1e-12
+        );
+    }
+}
@@ -0,0 +1,1030 @@
+//! SPK (Spacecraft and Planet Kernel) ephemeris files
+//!
+//! An SPK is a DAF whose arrays are segments giving the position of a target
+//! relative to a center over a span of TDB. Supported segment types:
+//!
+//! * Type 1 - modified difference arrays (small-body and older mission kernels)
+//! * Type 2 - Chebyshev position, velocity by differentiation (JPL DE series)
+//! * Type 3 - Chebyshev position and velocity
+//! * Type 9 - Lagrange interpolation of unequally spaced states
+//! * Type 13 - Hermite interpolation of unequally spaced states
+//! * Type 21 - extended modified difference arrays (Horizons small-body
+//!   kernels)
+//!
+//! Long-span kernels such as DE441 split each body into several segments; the
+//! segment covering the requested date is used, later segments taking
+//! precedence as in SPICE. Kernels with a time ephemeris (the `t` variants of
+//! the DE series) also carry TT-TDB as a segment, exposed through
+//! [`SPK::tt_minus_tdb`].
+
+use super::daf::DAF;
+use super::{JplEphemError, Result};
+use crate::constants::{DAY_S, J2000};
+use crate::data::{AccessRecorder, SegmentAccess};
+use nalgebra::Vector3;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, OnceLock};
+
+/// Pseudo-body used as the center of time ephemeris segments
+pub const TIME_EPHEMERIS_CENTER: i32 = 1_000_000_000;
+/// Pseudo-body whose "position" x component is TT-TDB in seconds
+pub const TIME_EPHEMERIS_TARGET: i32 = 1_000_000_001;
+
+/// Difference line dimension of a type 1 record
+const MDA_MAXDIM: usize = 15;
+
+/// Words in a modified difference array record of a given dimension
+fn mda_record_size(maxdim: usize) -> usize {
+    4 * maxdim + 11
+}
+
+/// How a segment's data is laid out
+#[derive(Debug)]
+enum SegmentData {
+    /// Evenly spaced Chebyshev records (types 2 and 3)
+    Chebyshev {
+        directory: ChebyshevDirectory,
+        components: usize,
+    },
+    /// Modified difference array records and their final epochs (types 1
+    /// and 21)
+    DifferenceLines {
+        n: usize,
+        maxdim: usize,
+        epochs: OnceLock<Vec<f64>>,
+    },
+    /// States at unequally spaced epochs, interpolated over a window of
+    /// `window` states (types 9 and 13)
+    DiscreteStates {
+        n: usize,
+        window: usize,
+        hermite: bool,
+        epochs: OnceLock<Vec<f64>>,
+    },
+    /// A segment type this reader cannot evaluate
+    Unsupported,
+}
+
+/// One SPK segment: the motion of `target` relative to `center`
+#[derive(Debug)]
+pub struct Segment {
+    daf: Arc<DAF>,
+    /// Segment name from the DAF name record
+    pub source: String,
+    /// Start of coverage in TDB seconds past J2000
+    pub start_second: f64,
+    /// End of coverage in TDB seconds past J2000
+    pub end_second: f64,
+    /// NAIF ID of the target body
+    pub target: i32,
+    /// NAIF ID of the center body
+    pub center: i32,
+    /// NAIF ID of the reference frame (1 = J2000)
+    pub frame: i32,
+    /// SPK data type
+    pub data_type: i32,
+    /// First word of the segment's data
+    pub start_i: usize,
+    /// Last word of the segment's data
+    pub end_i: usize,
+    /// Start of coverage as a TDB Julian date
+    pub start_jd: f64,
+    /// End of coverage as a TDB Julian date
+    pub end_jd: f64,
+    data: SegmentData,
+}
+
+impl Segment {
+    fn new(daf: Arc<DAF>, source: String, doubles: &[f64], ints: &[i32]) -> Result<Self> {
+        if doubles.len() < 2 || ints.len() < 6 {
+            return Err(JplEphemError::InvalidFormat(format!(
+                "segment {:?} has a malformed summary",
+                source
+            )));
+        }
+
+        let (start_i, end_i) = (ints[4] as usize, ints[5] as usize);
+        let data_type = ints[3];
+
+        let data = match data_type {
+            2 | 3 => {
+                let components = if data_type == 2 { 3 } else { 6 };
+                SegmentData::Chebyshev {
+                    directory: ChebyshevDirectory::read(&daf, end_i, components, &source)?,
+                    components,
+                }
+            }
+            1 | 21 => {
+                let (n, maxdim) = if data_type == 1 {
+                    (daf.read_array(end_i, end_i)?[0] as usize, MDA_MAXDIM)
+                } else {
+                    let trailer = daf.read_array(end_i - 1, end_i)?;
+                    (trailer[1] as usize, trailer[0] as usize)
+                };
+                let rsize = mda_record_size(maxdim);
+                if n == 0 || maxdim < 2 || start_i + n * (rsize + 1) + n / 100 > end_i + 1 {
+                    return Err(JplEphemError::InvalidFormat(format!(
+                        "segment {:?} has a corrupt record count",
+                        source
+                    )));
+                }
+                SegmentData::DifferenceLines {
+                    n,
+                    maxdim,
+                    epochs: OnceLock::new(),
+                }
+            }
+            9 | 13 => {
+                // Type 9 stores the polynomial degree, type 13 the window
+                // size less one
+                let trailer = daf.read_array(end_i - 1, end_i)?;
+                let (window, n) = (trailer[0] as usize + 1, trailer[1] as usize);
+                if window < 2 || n < window || start_i + 7 * n + (n - 1) / 100 + 1 > end_i {
+                    return Err(JplEphemError::InvalidFormat(format!(
+                        "segment {:?} has a corrupt state directory",
+                        source
+                    )));
+                }
+                SegmentData::DiscreteStates {
+                    n,
+                    window,
+                    hermite: data_type == 13,
+                    epochs: OnceLock::new(),
+                }
+            }
+            _ => SegmentData::Unsupported,
+        };
+
+        Ok(Segment {
+            daf,
+            source,
+            start_second: doubles[0],
+            end_second: doubles[1],
+            target: ints[0],
+            center: ints[1],
+            frame: ints[2],
+            data_type,
+            start_i,
+            end_i,
+            start_jd: J2000 + doubles[0] / DAY_S,
+            end_jd: J2000 + doubles[1] / DAY_S,
+            data,
+        })
+    }
+
+    /// Whether the segment covers a TDB Julian date
+    pub fn covers(&self, jd_tdb: f64) -> bool {
+        (self.start_jd..=self.end_jd).contains(&jd_tdb)
+    }
+
+    fn seconds(&self, jd_tdb: f64) -> Result<f64> {
+        if !self.covers(jd_tdb) {
+            return Err(JplEphemError::OutOfRange {
+                jd: jd_tdb,
+                start: self.start_jd,
+                end: self.end_jd,
+            });
+        }
+        Ok((jd_tdb - J2000) * DAY_S)
+    }
+
+    /// Position of the target in km at a TDB Julian date
+    pub fn compute(&self, jd_tdb: f64) -> Result<Vector3<f64>> {
+        Ok(self.compute_and_differentiate(jd_tdb)?.0)
+    }
+
+    /// Position (km) and velocity (km/day) of the target at a TDB Julian date
+    pub fn compute_and_differentiate(&self, jd_tdb: f64) -> Result<(Vector3<f64>, Vector3<f64>)> {
+        let et = self.seconds(jd_tdb)?;
+        match &self.data {
+            SegmentData::Chebyshev { .. } => {
+                let (values, rates) = self.chebyshev(et)?;
+                let position = Vector3::new(values[0], values[1], values[2]);
+                let velocity = if self.data_type == 3 {
+                    // Stored velocity is in km/s
+                    Vector3::new(values[3], values[4], values[5]) * DAY_S
+                } else {
+                    Vector3::new(rates[0], rates[1], rates[2])
+                };
+                Ok((position, velocity))
+            }
+            SegmentData::DifferenceLines { .. } => {
+                let (position, velocity) = self.difference_lines(et)?;
+                Ok((position, velocity * DAY_S))
+            }
+            SegmentData::DiscreteStates { .. } => {
+                let (position, velocity) = self.discrete_states(et)?;
+                Ok((position, velocity * DAY_S))
+            }
+            SegmentData::Unsupported => Err(JplEphemError::UnsupportedType(self.data_type)),
+        }
+    }
+
+    /// Evaluate every Chebyshev component and its rate (per day) at `et`
+    fn chebyshev(&self, et: f64) -> Result<(Vec<f64>, Vec<f64>)> {
+        let SegmentData::Chebyshev {
+            directory,
+            components,
+        } = &self.data
+        else {
+            return Err(JplEphemError::UnsupportedType(self.data_type));
+        };
+        chebyshev(&self.daf, self.start_i, directory, *components, et)
+    }
+
+    /// The `n` epochs stored from word `first` on, read once and kept
+    fn epochs<'a>(
+        &self,
+        lock: &'a OnceLock<Vec<f64>>,
+        first: usize,
+        n: usize,
+    ) -> Result<&'a [f64]> {
+        Ok(match lock.get() {
+            Some(epochs) => epochs,
+            None => {
+                let loaded = self.daf.read_array(first, first + n - 1)?;
+                lock.get_or_init(|| loaded)
+            }
+        })
+    }
+
+    /// Evaluate a type 1 or 21 segment at `et`: position in km, velocity in
+    /// km/s
+    ///
+    /// A direct translation of SPICELIB's SPKE21, of which SPKE01 is the
+    /// case `maxdim` = 15.
+    fn difference_lines(&self, et: f64) -> Result<(Vector3<f64>, Vector3<f64>)> {
+        let SegmentData::DifferenceLines { n, maxdim, epochs } = &self.data else {
+            return Err(JplEphemError::UnsupportedType(self.data_type));
+        };
+        let (n, m) = (*n, *maxdim);
+        let rsize = mda_record_size(m);
+        let epochs = self.epochs(epochs, self.start_i + n * rsize, n)?;
+
+        // Each record is valid up to and including its final epoch
+        let index = epochs.partition_point(|&epoch| epoch < et).min(n - 1);
+        let start = self.start_i + index * rsize;
+        let record = self.daf.read_array(start, start + rsize - 1)?;
+
+        let tl = record[0];
+        let g = &record[1..=m];
+        let refpos = [record[m + 1], record[m + 3], record[m + 5]];
+        let refvel = [record[m + 2], record[m + 4], record[m + 6]];
+        let dt = |j: usize, i: usize| record[m + 7 + (j - 1) + i * m];
+        let kqmax1 = record[4 * m + 7] as usize;
+        let kq = [
+            record[4 * m + 8] as usize,
+            record[4 * m + 9] as usize,
+            record[4 * m + 10] as usize,
+        ];
+
+        if !(2..=m + 1).contains(&kqmax1) || kq.iter().any(|&k| k > m) {
+            return Err(JplEphemError::InvalidFormat(format!(
+                "segment {:?} has a corrupt difference line record",
+                self.source
+            )));
+        }
+
+        let delta = et - tl;
+        let mut tp = delta;
+        let mq2 = kqmax1 - 2;
+        let mut ks = kqmax1 - 1;
+
+        // 1-based working arrays as in the Fortran original
+        let mut fc = vec![0.0; m + 2];
+        let mut wc = vec![0.0; m + 1];
+        let mut w = vec![0.0; m + 3];
+        fc[1] = 1.0;
+        for j in 1..=mq2 {
+            fc[j + 1] = tp / g[j - 1];
+            wc[j] = delta / g[j - 1];
+            tp = delta + g[j - 1];
+        }
+        for (j, wj) in w.iter_mut().enumerate().take(kqmax1 + 1).skip(1) {
+            *wj = 1.0 / j as f64;
+        }
+
+        let mut jx = 0;
+        let mut ks1 = ks as isize - 1;
+        while ks >= 2 {
+            jx += 1;
+            for j in 1..=jx {
+                w[j + ks] = fc[j + 1] * w[(j as isize + ks1) as usize] - wc[j] * w[j + ks];
+            }
+            ks = ks1 as usize;
+            ks1 -= 1;
+        }
+
+        let mut position = Vector3::zeros();
+        for i in 0..3 {
+            let sum: f64 = (1..=kq[i]).rev().map(|j| dt(j, i) * w[j + ks]).sum();
+            position[i] = refpos[i] + delta * (refvel[i] + delta * sum);
+        }
+
+        for j in 1..=jx {
+            w[j + ks] = fc[j + 1] * w[(j as isize + ks1) as usize] - wc[j] * w[j + ks];
+        }
+        ks -= 1;
+
+        let mut velocity = Vector3::zeros();
+        for i in 0..3 {
+            let sum: f64 = (1..=kq[i]).rev().map(|j| dt(j, i) * w[j + ks]).sum();
+            velocity[i] = refvel[i] + delta * sum;
+        }
+
+        Ok((position, velocity))
+    }
+
+    /// Evaluate a type 9 or 13 segment at `et`: position in km, velocity in
+    /// km/s
+    ///
+    /// As in SPICELIB's SPKR09 and SPKR13, an even window is centred on the
+    /// interval containing `et` and an odd one on the nearest epoch, both
+    /// shifted to stay inside the segment. Type 9 interpolates each state
+    /// component with a Lagrange polynomial; type 13 fits a Hermite
+    /// polynomial to positions and velocities and differentiates it.
+    fn discrete_states(&self, et: f64) -> Result<(Vector3<f64>, Vector3<f64>)> {
+        let SegmentData::DiscreteStates {
+            n,
+            window,
+            hermite,
+            epochs,
+        } = &self.data
+        else {
+            return Err(JplEphemError::UnsupportedType(self.data_type));
+        };
+        let (n, window) = (*n, *window);
+        let epochs = self.epochs(epochs, self.start_i + 6 * n, n)?;
+
+        let first = if window % 2 == 1 {
+            let upper = epochs.partition_point(|&epoch| epoch < et).min(n - 1);
+            let nearest = if upper > 0 && et - epochs[upper - 1] <= epochs[upper] - et {
+                upper - 1
+            } else {
+                upper
+            };
+            nearest.saturating_sub(window / 2)
+        } else {
+            let low = epochs.partition_point(|&epoch| epoch <= et).max(1) - 1;
+            (low + 1).saturating_sub(window / 2)
+        }
+        .min(n - window);
+
+        let start = self.start_i + 6 * first;
+        let states = self.daf.read_array(start, start + 6 * window - 1)?;
+        // Times relative to the window keep the polynomials well conditioned
+        let t0 = epochs[first];
+        let ts: Vec<f64> = epochs[first..first + window]
+            .iter()
+            .map(|e| e - t0)
+            .collect();
+        let x = et - t0;
+        let component =
+            |c: usize| -> Vec<f64> { states.iter().skip(c).step_by(6).copied().collect() };
+
+        let mut position = Vector3::zeros();
+        let mut velocity = Vector3::zeros();
+        if *hermite {
+            for i in 0..3 {
+                (position[i], velocity[i]) =
+                    hermite_interpolate(&ts, &component(i), &component(i + 3), x);
+            }
+        } else {
+            let weights = lagrange_weights(&ts, x);
+            let interpolate =
+                |c: usize| component(c).iter().zip(&weights).map(|(y, w)| y * w).sum();
+            for i in 0..3 {
+                position[i] = interpolate(i);
+                velocity[i] = interpolate(i + 3);
+            }
+        }
+        Ok((position, velocity))
+    }
+
+    /// TT-TDB in seconds from a time ephemeris segment
+    fn time_difference(&self, jd_tdb: f64) -> Result<f64> {
+        let et = self.seconds(jd_tdb)?;
+        Ok(self.chebyshev(et)?.0[0])
+    }
+}
+
+/// Layout of evenly spaced Chebyshev records, from a segment's trailer
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct ChebyshevDirectory {
+    /// Start of the first record in TDB seconds past J2000
+    pub init: f64,
+    /// Length of each record's interval in seconds
+    pub intlen: f64,
+    /// Words per record
+    pub rsize: usize,
+    /// Number of records
+    pub n: usize,
+}
+
+impl ChebyshevDirectory {
+    /// Read the four-word trailer ending at word `end_i`, checking it
+    /// leaves room for `components` coefficient sets
+    pub fn read(daf: &DAF, end_i: usize, components: usize, source: &str) -> Result<Self> {
+        let trailer = daf.read_array(end_i - 3, end_i)?;
+        let (rsize, n) = (trailer[2] as usize, trailer[3] as usize);
+        if rsize < 2 + components || n == 0 || trailer[1] <= 0.0 {
+            return Err(JplEphemError::InvalidFormat(format!(
+                "segment {:?} has a corrupt Chebyshev directory",
+                source
+            )));
+        }
+        Ok(Self {
+            init: trailer[0],
+            intlen: trailer[1],
+            rsize,
+            n,
+        })
+    }
+}
+
+/// Evaluate every Chebyshev component and its rate (per day) at `et` in
+/// the records starting at word `start_i`
+pub(crate) fn chebyshev(
+    daf: &DAF,
+    start_i: usize,
+    directory: &ChebyshevDirectory,
+    components: usize,
+    et: f64,
+) -> Result<(Vec<f64>, Vec<f64>)> {
+    let ChebyshevDirectory {
+        init,
+        intlen,
+        rsize,
+        n,
+    } = *directory;
+    let index = (((et - init) / intlen).floor().max(0.0) as usize).min(n - 1);
+    let start = start_i + index * rsize;
+    let record = daf.read_array(start, start + rsize - 1)?;
+
+    let (mid, radius) = (record[0], record[1]);
+    let degree = (rsize - 2) / components;
+    let s = (et - mid) / radius;
+
+    // Chebyshev polynomials and their derivatives with respect to s
+    let mut t = vec![0.0; degree];
+    let mut dt = vec![0.0; degree];
+    t[0] = 1.0;
+    if degree > 1 {
+        t[1] = s;
+        dt[1] = 1.0;
+    }
+    for k in 2..degree {
+        t[k] = 2.0 * s * t[k - 1] - t[k - 2];
+        dt[k] = 2.0 * t[k - 1] + 2.0 * s * dt[k - 1] - dt[k - 2];
+    }
+
+    let mut values = Vec::with_capacity(components);
+    let mut rates = Vec::with_capacity(components);
+    for c in 0..components {
+        let coefficients = &record[2 + c * degree..2 + (c + 1) * degree];
+        values.push(coefficients.iter().zip(&t).map(|(a, b)| a * b).sum());
+        let per_s: f64 = coefficients.iter().zip(&dt).map(|(a, b)| a * b).sum();
+        rates.push(per_s / radius * DAY_S);
+    }
+
+    Ok((values, rates))
+}
+
+/// Weights of the Lagrange polynomial through the abscissas `ts` at `x`
+fn lagrange_weights(ts: &[f64], x: f64) -> Vec<f64> {
+    (0..ts.len())
+        .map(|i| {
+            (0..ts.len())
+                .filter(|&j| j != i)
+                .map(|j| (x - ts[j]) / (ts[i] - ts[j]))
+                .product()
+        })
+        .collect()
+}
+
+/// Value and derivative at `x` of the Hermite polynomial matching values
+/// `f` and derivatives `df` at the abscissas `ts`
+///
+/// Uses Newton divided differences over the doubled abscissas.
+fn hermite_interpolate(ts: &[f64], f: &[f64], df: &[f64], x: f64) -> (f64, f64) {
+    let m = 2 * ts.len();
+    let z: Vec<f64> = ts.iter().flat_map(|&t| [t, t]).collect();
+    let mut q: Vec<f64> = f.iter().flat_map(|&v| [v, v]).collect();
+    let mut coefficients = vec![q[0]];
+    for order in 1..m {
+        // Column `order` of the table, overwriting from the bottom up
+        for i in (order..m).rev() {
+            q[i] = if order == 1 && i % 2 == 1 {
+                df[i / 2]
+            } else {
+                (q[i] - q[i - 1]) / (z[i] - z[i - order])
+            };
+        }
+        coefficients.push(q[order]);
+    }
+
+    let mut value = coefficients[m - 1];
+    let mut derivative = 0.0;
+    for k in (0..m - 1).rev() {
+        derivative = derivative * (x - z[k]) + value;
+        value = value * (x - z[k]) + coefficients[k];
+    }
+    (value, derivative)
+}
+
+/// An SPK ephemeris kernel
+#[derive(Debug)]
+pub struct SPK {
+    /// The underlying DAF file
+    pub daf: Arc<DAF>,
+    /// Segments in file order
+    pub segments: Vec<Segment>,
+    path: Option<PathBuf>,
+    recorder: Option<AccessRecorder>,
+}
+
+impl SPK {
+    /// Open an SPK file
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let mut spk = Self::from_daf(DAF::open(path.as_ref())?)?;
+        spk.path = Some(path.as_ref().to_path_buf());
+        Ok(spk)
+    }
+
+    /// Interpret an already opened DAF as an SPK
+    pub fn from_daf(daf: DAF) -> Result<Self> {
+        if daf.nd != 2 || daf.ni != 6 {
+            return Err(JplEphemError::InvalidFormat(format!(
+                "SPK summaries must have ND=2, NI=6 (found ND={}, NI={})",
+                daf.nd, daf.ni
+            )));
+        }
+
+        let daf = Arc::new(daf);
+        let segments = daf
+            .summaries()?
+            .into_iter()
+            .map(|s| Segment::new(daf.clone(), s.name, &s.doubles, &s.ints))
+            .collect::<Result<Vec<_>>>()?;
+
+        Ok(SPK {
+            daf,
+            segments,
+            path: None,
+            recorder: None,
+        })
+    }
+
+    /// Log every segment this kernel evaluates to `recorder`
+    pub fn with_recorder(mut self, recorder: AccessRecorder) -> Self {
+        self.recorder = Some(recorder);
+        self
+    }
+
+    /// File the kernel was opened from, if any
+    pub fn path(&self) -> Option<&Path> {
+        self.path.as_deref()
+    }
+
+    /// All segments for a center/target pair
+    pub fn segments_for(&self, center: i32, target: i32) -> impl Iterator<Item = &Segment> {
+        self.segments
+            .iter()
+            .filter(move |s| s.center == center && s.target == target)
+    }
+
+    /// The segment for a center/target pair covering a TDB Julian date
+    ///
+    /// When several segments cover the date, the last one in the file wins.
+    pub fn segment_at(&self, center: i32, target: i32, jd_tdb: f64) -> Result<&Segment> {
+        let mut candidates = self.segments_for(center, target).peekable();
+        if candidates.peek().is_none() {
+            return Err(JplEphemError::SegmentNotFound { center, target });
+        }
+
+        let last = candidates.filter(|s| s.covers(jd_tdb)).last();
+
+        if let (Some(recorder), Some(segment)) = (&self.recorder, last) {
+            recorder.record_segment(SegmentAccess {
+                kernel: self.path.clone(),
+                center,
+                target,
+                start_jd: segment.start_jd,
+                end_jd: segment.end_jd,
+            });
+        }
+
+        last.ok_or_else(|| {
+            let (start, end) = self
+                .segments_for(center, target)
+                .fold((f64::INFINITY, f64::NEG_INFINITY), |(a, b), s| {
+                    (a.min(s.start_jd), b.max(s.end_jd))
+                });
+            JplEphemError::OutOfRange {
+                jd: jd_tdb,
+                start,
+                end,
+            }
+        })
+    }
+
+    /// Position of `target` relative to `center` in km
+    pub fn compute(&self, center: i32, target: i32, jd_tdb: f64) -> Result<Vector3<f64>> {
+        self.segment_at(center, target, jd_tdb)?.compute(jd_tdb)
+    }
+
+    /// Position (km) and velocity (km/day) of `target` relative to `center`
+    pub fn compute_and_differentiate(
+        &self,
+        center: i32,
+        target: i32,
+        jd_tdb: f64,
+    ) -> Result<(Vector3<f64>, Vector3<f64>)> {
+        self.segment_at(center, target, jd_tdb)?
+            .compute_and_differentiate(jd_tdb)
+    }
+
+    /// Position (km) and velocity (km/day) of `target` relative to the
+    /// solar-system barycenter (NAIF ID 0)
+    ///
+    /// Kernels store most bodies relative to an intermediate center, so the
+    /// segments are chained until the barycenter is reached: the Earth in
+    /// DE421, for instance, is the Earth-Moon barycenter (3) relative to 0
+    /// plus the Earth (399) relative to 3.
+    pub fn barycentric_state(
+        &self,
+        target: i32,
+        jd_tdb: f64,
+    ) -> Result<(Vector3<f64>, Vector3<f64>)> {
+        let mut position = Vector3::zeros();
+        let mut velocity = Vector3::zeros();
+        let mut body = target;
+
+        // No real kernel nests bodies more than a few levels deep
+        for _ in 0..8 {
+            if body == 0 {
+                return Ok((position, velocity));
+            }
+            // Centers can differ between spans, so pick the segment covering
+            // the date, falling back to any segment to report the range
+            let center = self
+                .segments
+                .iter()
+                .rev()
+                .find(|s| s.target == body && s.covers(jd_tdb))
+                .or_else(|| self.segments.iter().find(|s| s.target == body))
+                .map(|s| s.center)
+                .ok_or(JplEphemError::SegmentNotFound {
+                    center: 0,
+                    target: body,
+                })?;
+            let (p, v) = self.compute_and_differentiate(center, body, jd_tdb)?;
+            position += p;
+            velocity += v;
+            body = center;
+        }
+        Err(JplEphemError::InvalidFormat(format!(
+            "segment chain for {} does not reach the barycenter",
+            target
+        )))
+    }
+
+    /// Whether the kernel carries a TT-TDB time ephemeris
+    pub fn has_time_ephemeris(&self) -> bool {
+        self.segments_for(TIME_EPHEMERIS_CENTER, TIME_EPHEMERIS_TARGET)
+            .next()
+            .is_some()
+    }
+
+    /// TT-TDB in seconds at a TDB Julian date, from the kernel's time ephemeris
+    pub fn tt_minus_tdb(&self, jd_tdb: f64) -> Result<f64> {
+        self.segment_at(TIME_EPHEMERIS_CENTER, TIME_EPHEMERIS_TARGET, jd_tdb)?
+            .time_difference(jd_tdb)
+    }
+
+    /// Convert a TT Julian date to TDB using the kernel's time ephemeris
+    ///
+    /// This is the time scale coupling the ephemeris was integrated with,
+    /// and unlike the series in [`crate::time::Time::tdb`] it holds across
+    /// the full span of long kernels such as DE441.
+    pub fn tdb_from_tt(&self, jd_tt: f64) -> Result<f64> {
+        let mut jd_tdb = jd_tt;
+        // TT-TDB changes by nanoseconds over its own size, so this converges at once
+        for _ in 0..3 {
+            jd_tdb = jd_tt - self.tt_minus_tdb(jd_tdb)? / DAY_S;
+        }
+        Ok(jd_tdb)
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod testing {
+    use super::super::daf::testing::{build, Array};
+    use super::*;
+    use std::io::Cursor;
+
+    /// One-record Chebyshev segment: x = 1000 + 200 T1 + 30 T2, y = -50 T1,
+    /// z = 7, over `days` days starting at `start_jd`
+    pub fn chebyshev_array(target: i32, start_jd: f64, days: f64, data_type: i32) -> Array {
+        let start = (start_jd - J2000) * DAY_S;
+        let length = days * DAY_S;
+        let radius = length / 2.0;
+
+        let mut data = vec![start + radius, radius];
+        data.extend([1000.0, 200.0, 30.0]);
+        data.extend([0.0, -50.0, 0.0]);
+        data.extend([7.0, 0.0, 0.0]);
+        if data_type == 3 {
+            // Stored velocities (km/s) for a type 3 record
+            data.extend([0.5, 0.0, 0.0]);
+            data.extend([-0.25, 0.0, 0.0]);
+            data.extend([0.0, 0.0, 0.0]);
+        }
+        let rsize = data.len() as f64;
+        data.extend([start, length, rsize, 1.0]);
+
+        Array {
+            name: format!("TEST {}", target),
+            doubles: vec![start, start + length],
+            ints: vec![target, 0, 1, data_type],
+            data,
+        }
+    }
+
+    pub fn spk(arrays: &[Array]) -> SPK {
+        let bytes = build("DAF/SPK", 2, 6, arrays, 3);
+        SPK::from_daf(DAF::from_source(Box::new(Cursor::new(bytes))).unwrap()).unwrap()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::daf::testing::Array;
+    use super::testing::{chebyshev_array, spk};
+    use super::*;
+    use approx::assert_relative_eq;
+
+    #[test]
+    fn test_type2_chebyshev() {
+        let kernel = spk(&[chebyshev_array(499, J2000, 32.0, 2)]);
+
+        // At the midpoint s = 0: x = 1000 - 30, derivative 200 per unit s
+        let (p, v) = kernel
+            .compute_and_differentiate(0, 499, J2000 + 16.0)
+            .unwrap();
+        assert_relative_eq!(p, Vector3::new(970.0, 0.0, 7.0), epsilon = 1e-9);
+        assert_relative_eq!(v.x, 200.0 / 16.0, epsilon = 1e-9);
+        assert_relative_eq!(v.y, -50.0 / 16.0, epsilon = 1e-9);
+
+        // At the end s = 1: x = 1000 + 200 + 30
+        let p = kernel.compute(0, 499, J2000 + 32.0).unwrap();
+        assert_relative_eq!(p.x, 1230.0, epsilon = 1e-9);
+
+        assert!(matches!(
+            kernel.compute(0, 499, J2000 + 33.0),
+            Err(JplEphemError::OutOfRange { .. })
+        ));
+        assert!(matches!(
+            kernel.compute(0, 599, J2000),
+            Err(JplEphemError::SegmentNotFound { .. })
+        ));
+    }
+
+    #[test]
+    fn test_type3_stored_velocity() {
+        let kernel = spk(&[chebyshev_array(301, J2000, 2.0, 3)]);
+        let (_, v) = kernel
+            .compute_and_differentiate(0, 301, J2000 + 1.0)
+            .unwrap();
+        assert_relative_eq!(v.x, 0.5 * DAY_S, epsilon = 1e-9);
+        assert_relative_eq!(v.y, -0.25 * DAY_S, epsilon = 1e-9);
+    }
+
+    #[test]
+    fn test_long_span_segments_are_chained() {
+        // DE441 stores each body in two halves; dates pick the right one
+        let mut early = chebyshev_array(5, J2000 - 100.0, 100.0, 2);
+        let late = chebyshev_array(5, J2000, 100.0, 2);
+        early.data[2] = -1000.0;
+        let kernel = spk(&[early, late]);
+
+        assert_eq!(kernel.segments_for(0, 5).count(), 2);
+        assert!(kernel.compute(0, 5, J2000 - 50.0).unwrap().x < 0.0);
+        assert!(kernel.compute(0, 5, J2000 + 50.0).unwrap().x > 0.0);
+        // The shared boundary goes to the later segment
+        assert!(kernel.compute(0, 5, J2000).unwrap().x > 0.0);
+    }
+
+    #[test]
+    fn test_recorder_logs_segments_used() {
+        let recorder = AccessRecorder::new();
+        let kernel = spk(&[
+            chebyshev_array(5, J2000 - 100.0, 100.0, 2),
+            chebyshev_array(5, J2000, 100.0, 2),
+        ])
+        .with_recorder(recorder.clone());
+
+        kernel.compute(0, 5, J2000 + 10.0).unwrap();
+        kernel.compute(0, 5, J2000 + 20.0).unwrap();
+        let log = recorder.snapshot();
+        assert_eq!(log.kernel_segments.len(), 1);
+        assert_eq!(log.kernel_segments[0].start_jd, J2000);
+    }
+
+    #[test]
+    fn test_time_ephemeris() {
+        // Constant TT-TDB of 1.5 ms
+        let mut array = chebyshev_array(TIME_EPHEMERIS_TARGET, J2000 - 10.0, 20.0, 2);
+        array.ints[1] = TIME_EPHEMERIS_CENTER;
+        array.data[2..11].copy_from_slice(&[0.0015, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0]);
+        let kernel = spk(&[array]);
+
+        assert!(kernel.has_time_ephemeris());
+        assert_relative_eq!(kernel.tt_minus_tdb(J2000).unwrap(), 0.0015, epsilon = 1e-15);
+        let tdb = kernel.tdb_from_tt(J2000).unwrap();
+        // A TDB Julian date only resolves to about 40 microseconds
+        assert_relative_eq!((J2000 - tdb) * DAY_S, 0.0015, epsilon = 1e-4);
+    }
+
+    /// Modified difference array segment with a single record: constant
+    /// acceleration plus an optional cubic term in x; type 1 when `maxdim`
+    /// is 15, type 21 otherwise
+    fn mda_array(maxdim: usize, accel: [f64; 3], cubic: f64) -> Array {
+        let m = maxdim;
+        let mut record = vec![0.0; mda_record_size(m)];
+        record[0] = 0.0; // TL: reference epoch at J2000
+        for g in record.iter_mut().take(m + 1).skip(1) {
+            *g = 3600.0;
+        }
+        // REFPOS/REFVEL interleaved
+        record[m + 1..m + 7].copy_from_slice(&[100.0, 1.0, 200.0, 0.0, 300.0, -1.0]);
+        for i in 0..3 {
+            record[m + 7 + i * m] = accel[i];
+        }
+        record[m + 8] = cubic;
+        record[4 * m + 7] = 3.0; // KQMAX1
+        record[4 * m + 8..4 * m + 11].copy_from_slice(&[2.0, 1.0, 1.0]);
+
+        let data_type = if m == MDA_MAXDIM { 1 } else { 21 };
+        let mut data = record;
+        data.push(86_400.0); // final epoch of the record
+        if data_type == 21 {
+            data.push(m as f64); // MAXDIM
+        }
+        data.push(1.0); // N
+
+        Array {
+            name: "MDA".into(),
+            doubles: vec![-86_400.0, 86_400.0],
+            ints: vec![2_000_001, 10, 1, data_type],
+            data,
+        }
+    }
+
+    #[test]
+    fn test_type1_difference_lines() {
+        let kernel = spk(&[mda_array(MDA_MAXDIM, [2e-6, 0.0, -4e-6], 0.0)]);
+        let dt = 3600.0;
+        let jd = J2000 + dt / DAY_S;
+        let (p, v) = kernel.compute_and_differentiate(10, 2_000_001, jd).unwrap();
+
+        // x = x0 + v t + a t^2 / 2
+        assert_relative_eq!(p.x, 100.0 + dt + 1e-6 * dt * dt, epsilon = 1e-3);
+        assert_relative_eq!(p.y, 200.0, epsilon = 1e-9);
+        assert_relative_eq!(p.z, 300.0 - dt - 2e-6 * dt * dt, epsilon = 1e-3);
+        assert_relative_eq!(v.x / DAY_S, 1.0 + 2e-6 * dt, epsilon = 1e-9);
+    }
+
+    #[test]
+    fn test_type1_velocity_matches_position() {
+        let kernel = spk(&[mda_array(MDA_MAXDIM, [2e-6, 0.0, -4e-6], 5e-9)]);
+        let jd = J2000 + 0.3;
+        let h = 1e-2;
+        let ahead = kernel.compute(10, 2_000_001, jd + h).unwrap();
+        let behind = kernel.compute(10, 2_000_001, jd - h).unwrap();
+        let (_, v) = kernel.compute_and_differentiate(10, 2_000_001, jd).unwrap();
+        assert_relative_eq!((ahead - behind) / (2.0 * h), v, max_relative = 1e-6);
+    }
+
+    #[test]
+    fn test_type21_extended_difference_lines() {
+        let jd = J2000 + 0.3;
+        let type1 = spk(&[mda_array(MDA_MAXDIM, [2e-6, 0.0, -4e-6], 5e-9)]);
+        let type21 = spk(&[mda_array(25, [2e-6, 0.0, -4e-6], 5e-9)]);
+        assert_eq!(type21.segments[0].data_type, 21);
+
+        let (p1, v1) = type1.compute_and_differentiate(10, 2_000_001, jd).unwrap();
+        let (p21, v21) = type21.compute_and_differentiate(10, 2_000_001, jd).unwrap();
+        assert_relative_eq!(p21, p1, epsilon = 1e-9);
+        assert_relative_eq!(v21, v1, epsilon = 1e-9);
+    }
+
+    /// States of x = 1000 + 2 t + 1e-6 t^2 - 1e-12 t^3, y = 5 t, z = -3,
+    /// with t in seconds past J2000, at unequal steps, as a type 9 or 13
+    /// segment with the given window parameter
+    fn discrete_state_array(data_type: i32, parameter: usize) -> (Array, impl Fn(f64) -> [f64; 6]) {
+        let state = |t: f64| {
+            [
+                1000.0 + 2.0 * t + 1e-6 * t * t - 1e-12 * t * t * t,
+                5.0 * t,
+                -3.0,
+                2.0 + 2e-6 * t - 3e-12 * t * t,
+                5.0,
+                0.0,
+            ]
+        };
+        let epochs: Vec<f64> = (0..12).map(|k| 3600.0 * (k * k + k) as f64).collect();
+        let mut data: Vec<f64> = epochs.iter().flat_map(|&t| state(t)).collect();
+        data.extend(&epochs);
+        data.extend([parameter as f64, epochs.len() as f64]);
+        let array = Array {
+            name: "STATES".into(),
+            doubles: vec![epochs[0], *epochs.last().unwrap()],
+            ints: vec![-77, 399, 1, data_type],
+            data,
+        };
+        (array, state)
+    }
+
+    #[test]
+    fn test_type9_and_13_interpolate_states() {
+        // Degree 3 and a window of 2 are both exact for a cubic
+        for (data_type, parameter) in [(9, 3), (9, 4), (13, 1), (13, 2)] {
+            let (array, state) = discrete_state_array(data_type, parameter);
+            let kernel = spk(&[array]);
+            for t in [0.0, 5000.0, 100_000.0, 420_000.0, 475_200.0] {
+                let (p, v) = kernel
+                    .compute_and_differentiate(399, -77, J2000 + t / DAY_S)
+                    .unwrap();
+                let expected = state(t);
+                assert_relative_eq!(
+                    p,
+                    Vector3::new(expected[0], expected[1], expected[2]),
+                    max_relative = 1e-9
+                );
+                assert_relative_eq!(
+                    v / DAY_S,
+                    Vector3::new(expected[3], expected[4], expected[5]),
+                    max_relative = 1e-9
+                );
+            }
+        }
+
+        let (mut array, _) = discrete_state_array(9, 20);
+        array.name = "TOO WIDE".into();
+        let bytes = super::super::daf::testing::build("DAF/SPK", 2, 6, &[array], 3);
+        let daf = DAF::from_source(Box::new(std::io::Cursor::new(bytes))).unwrap();
+        assert!(matches!(
+            SPK::from_daf(daf),
+            Err(JplEphemError::InvalidFormat(_))
+        ));
+    }
+
+    #[test]
+    fn test_barycentric_state_chains_centers() {
+        // Earth (399) about the Earth-Moon barycenter (3) about the barycenter
+        let emb = chebyshev_array(3, J2000, 32.0, 2);
+        let mut earth = chebyshev_array(399, J2000, 32.0, 2);
+        earth.ints[1] = 3;
+        let kernel = spk(&[emb, earth]);
+
+        let jd = J2000 + 16.0;
+        let (p, v) = kernel.barycentric_state(399, jd).unwrap();
+        let (p3, v3) = kernel.compute_and_differentiate(0, 3, jd).unwrap();
+        assert_relative_eq!(p, p3 * 2.0, epsilon = 1e-9);
+        assert_relative_eq!(v, v3 * 2.0, epsilon = 1e-9);
+
+        assert_eq!(kernel.barycentric_state(0, jd).unwrap().0, Vector3::zeros());
+        assert!(matches!(
+            kernel.barycentric_state(499, jd),
+            Err(JplEphemError::SegmentNotFound { .. })
+        ));
+    }
+
+    #[test]
+    fn test_barycentric_state_selects_segment_by_coverage() {
+        // The Moon about the barycenter for one span, about the EMB after it
+        let early = chebyshev_array(301, J2000 - 100.0, 100.0, 2);
+        let mut late = chebyshev_array(301, J2000, 32.0, 2);
+        late.ints[1] = 3;
+        let emb = chebyshev_array(3, J2000, 32.0, 2);
+        let kernel = spk(&[early, late, emb]);
+
+        let (p, _) = kernel.barycentric_state(301, J2000 - 50.0).unwrap();
+        assert_relative_eq!(
+            p,
+            kernel.compute(0, 301, J2000 - 50.0).unwrap(),
+            epsilon = 1e-9
+        );
+        let (p, _) = kernel.barycentric_state(301, J2000 + 16.0).unwrap();
+        assert_relative_eq!(
+            p,
+            kernel.compute(0, 3, J2000 + 16.0).unwrap() * 2.0,
+            epsilon = 1e-9
+        );
+        assert!(matches!(
+            kernel.barycentric_state(301, J2000 + 40.0),
+            Err(JplEphemError::OutOfRange { .. })
+        ));
+    }
+}
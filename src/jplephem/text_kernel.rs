@@ -0,0 +1,452 @@
+//! SPICE text kernels: leapseconds (LSK), text PCK and frame (FK) files
+//!
+//! A text kernel is free text in which `\begindata` and `\begintext` markers
+//! switch between data and commentary. Data sections assign values to
+//! variables in a kernel pool:
+//!
+//! ```text
+//! \begindata
+//! DELTET/DELTA_T_A = 32.184
+//! DELTET/DELTA_AT  = ( 10, @1972-JAN-1
+//!                      11, @1972-JUL-1 )
+//! BODY399_RADII    = ( 6378.1366 6378.1366 6356.7519 )
+//! FRAME_IAU_MOON   = 10020
+//! OBJECT_NAME      = 'It''s quoted'
+//! BODY399_RADII   += ( 1.0 )
+//! \begintext
+//! ```
+//!
+//! Values are numbers (with `D` or `E` exponents), quoted strings, or
+//! `@`-dates, which the pool stores as seconds past J2000 of the calendar
+//! date read without any time scale, as SPICE does. `=` replaces a
+//! variable and `+=` appends to it; loading several kernels into one
+//! [`KernelPool`] lets later ones override earlier ones.
+
+use super::{JplEphemError, Result};
+use crate::constants::{DAY_S, J2000};
+use crate::time::Timescale;
+use std::collections::HashMap;
+use std::path::Path;
+
+/// A value in the kernel pool
+#[derive(Debug, Clone, PartialEq)]
+pub enum KernelValue {
+    /// A number, or an `@`-date as seconds past J2000
+    Number(f64),
+    /// A quoted string, without its quotes
+    Text(String),
+}
+
+impl KernelValue {
+    /// The number, if this is one
+    pub fn as_number(&self) -> Option<f64> {
+        match self {
+            KernelValue::Number(x) => Some(*x),
+            KernelValue::Text(_) => None,
+        }
+    }
+
+    /// The string, if this is one
+    pub fn as_text(&self) -> Option<&str> {
+        match self {
+            KernelValue::Number(_) => None,
+            KernelValue::Text(s) => Some(s),
+        }
+    }
+}
+
+/// Variables loaded from text kernels
+#[derive(Debug, Clone, Default)]
+pub struct KernelPool {
+    variables: HashMap<String, Vec<KernelValue>>,
+}
+
+/// A lexical element of a data section
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Word(String),
+    Text(String),
+    Assign,
+    Append,
+    Open,
+    Close,
+}
+
+impl KernelPool {
+    /// An empty pool
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Parse the text of a kernel into a new pool
+    pub fn parse(text: &str) -> Result<Self> {
+        let mut pool = Self::new();
+        pool.load_str(text)?;
+        Ok(pool)
+    }
+
+    /// Read a kernel file into a new pool
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let mut pool = Self::new();
+        pool.load(path)?;
+        Ok(pool)
+    }
+
+    /// Read a kernel file into this pool
+    pub fn load<P: AsRef<Path>>(&mut self, path: P) -> Result<()> {
+        self.load_str(&std::fs::read_to_string(path)?)
+    }
+
+    /// Add the assignments in the text of a kernel to this pool
+    pub fn load_str(&mut self, text: &str) -> Result<()> {
+        let tokens = tokenize(&data_sections(text))?;
+        let mut tokens = tokens.into_iter().peekable();
+
+        while let Some(token) = tokens.next() {
+            let Token::Word(name) = token else {
+                return Err(invalid(format!(
+                    "expected a variable name, found {:?}",
+                    token
+                )));
+            };
+            let append = match tokens.next() {
+                Some(Token::Assign) => false,
+                Some(Token::Append) => true,
+                other => {
+                    return Err(invalid(format!(
+                        "expected = or += after {}, found {:?}",
+                        name, other
+                    )))
+                }
+            };
+
+            let mut values = Vec::new();
+            match tokens.next() {
+                Some(Token::Open) => loop {
+                    match tokens.next() {
+                        Some(Token::Close) => break,
+                        Some(token) => values.push(value(token, &name)?),
+                        None => return Err(invalid(format!("unclosed ( in {}", name))),
+                    }
+                },
+                Some(token) => values.push(value(token, &name)?),
+                None => return Err(invalid(format!("no value for {}", name))),
+            }
+
+            if append {
+                self.variables.entry(name).or_default().extend(values);
+            } else {
+                self.variables.insert(name, values);
+            }
+        }
+        Ok(())
+    }
+
+    /// The values of a variable
+    pub fn get(&self, name: &str) -> Option<&[KernelValue]> {
+        self.variables.get(name).map(Vec::as_slice)
+    }
+
+    /// The values of a numeric variable
+    pub fn numbers(&self, name: &str) -> Option<Vec<f64>> {
+        self.get(name)?.iter().map(KernelValue::as_number).collect()
+    }
+
+    /// The single value of a numeric variable
+    pub fn number(&self, name: &str) -> Option<f64> {
+        match self.get(name)? {
+            [value] => value.as_number(),
+            _ => None,
+        }
+    }
+
+    /// The values of a string variable
+    pub fn strings(&self, name: &str) -> Option<Vec<&str>> {
+        self.get(name)?.iter().map(KernelValue::as_text).collect()
+    }
+
+    /// Names of every variable in the pool, in no particular order
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.variables.keys().map(String::as_str)
+    }
+
+    /// Number of variables
+    pub fn len(&self) -> usize {
+        self.variables.len()
+    }
+
+    /// Whether the pool is empty
+    pub fn is_empty(&self) -> bool {
+        self.variables.is_empty()
+    }
+
+    /// A text PCK constant `BODY<id>_<item>`, such as `RADII` or `POLE_RA`
+    pub fn body_constant(&self, naif_id: i32, item: &str) -> Option<Vec<f64>> {
+        self.numbers(&format!("BODY{}_{}", naif_id, item))
+    }
+
+    /// The three radii of a body's reference ellipsoid in km
+    pub fn body_radii_km(&self, naif_id: i32) -> Option<[f64; 3]> {
+        self.body_constant(naif_id, "RADII")?.try_into().ok()
+    }
+
+    /// A body's GM in km^3/s^2
+    pub fn body_gm(&self, naif_id: i32) -> Option<f64> {
+        self.body_constant(naif_id, "GM")?.first().copied()
+    }
+
+    /// The ID of a frame defined in a frame kernel, from `FRAME_<name>`
+    pub fn frame_id(&self, name: &str) -> Option<i32> {
+        Some(self.number(&format!("FRAME_{}", name.to_ascii_uppercase()))? as i32)
+    }
+
+    /// The name of a frame defined in a frame kernel, from
+    /// `FRAME_<id>_NAME`
+    pub fn frame_name(&self, id: i32) -> Option<&str> {
+        match self.get(&format!("FRAME_{}_NAME", id))? {
+            [value] => value.as_text(),
+            _ => None,
+        }
+    }
+
+    /// Leap seconds from a leapseconds kernel's `DELTET/DELTA_AT`: the
+    /// Julian dates (UTC midnights) from which each TAI-UTC offset holds,
+    /// and the offsets in seconds, ready for [`Timescale::new`]
+    pub fn leap_seconds(&self) -> Option<(Vec<f64>, Vec<i32>)> {
+        let pairs = self.numbers("DELTET/DELTA_AT")?;
+        if pairs.is_empty() || pairs.len() % 2 != 0 {
+            return None;
+        }
+        Some(
+            pairs
+                .chunks(2)
+                .map(|pair| (J2000 + pair[1] / DAY_S, pair[0] as i32))
+                .unzip(),
+        )
+    }
+}
+
+/// Shorthand for a format error
+fn invalid(message: String) -> JplEphemError {
+    JplEphemError::InvalidFormat(format!("text kernel: {}", message))
+}
+
+/// The lines between each `\begindata` and the next `\begintext`
+fn data_sections(text: &str) -> String {
+    let mut data = String::new();
+    let mut in_data = false;
+    for line in text.lines() {
+        match line.trim() {
+            "\\begindata" => in_data = true,
+            "\\begintext" => in_data = false,
+            _ if in_data => {
+                data.push_str(line);
+                data.push('\n');
+            }
+            _ => {}
+        }
+    }
+    data
+}
+
+/// Split a data section into tokens
+fn tokenize(data: &str) -> Result<Vec<Token>> {
+    let mut tokens = Vec::new();
+    let mut chars = data.chars().peekable();
+    while let Some(&c) = chars.peek() {
+        match c {
+            c if c.is_whitespace() || c == ',' => {
+                chars.next();
+            }
+            '=' => {
+                chars.next();
+                tokens.push(Token::Assign);
+            }
+            '(' => {
+                chars.next();
+                tokens.push(Token::Open);
+            }
+            ')' => {
+                chars.next();
+                tokens.push(Token::Close);
+            }
+            '\'' => {
+                chars.next();
+                let mut text = String::new();
+                loop {
+                    match chars.next() {
+                        // A doubled quote stands for one quote
+                        Some('\'') if chars.peek() == Some(&'\'') => {
+                            chars.next();
+                            text.push('\'');
+                        }
+                        Some('\'') => break,
+                        Some(c) => text.push(c),
+                        None => return Err(invalid(format!("unterminated string '{}", text))),
+                    }
+                }
+                tokens.push(Token::Text(text));
+            }
+            _ => {
+                let mut word = String::new();
+                while let Some(&c) = chars.peek() {
+                    if c.is_whitespace() || "=(),'".contains(c) {
+                        break;
+                    }
+                    chars.next();
+                    if c == '+' && chars.peek() == Some(&'=') {
+                        if !word.is_empty() {
+                            tokens.push(Token::Word(std::mem::take(&mut word)));
+                        }
+                        chars.next();
+                        tokens.push(Token::Append);
+                        break;
+                    }
+                    word.push(c);
+                }
+                if !word.is_empty() {
+                    tokens.push(Token::Word(word));
+                }
+            }
+        }
+    }
+    Ok(tokens)
+}
+
+/// The value a token stands for on the right of an assignment
+fn value(token: Token, name: &str) -> Result<KernelValue> {
+    match token {
+        Token::Text(text) => Ok(KernelValue::Text(text)),
+        Token::Word(word) => {
+            let parsed = match word.strip_prefix('@') {
+                Some(date) => parse_date(date),
+                None => word.replace(['D', 'd'], "E").parse().ok(),
+            };
+            parsed
+                .map(KernelValue::Number)
+                .ok_or_else(|| invalid(format!("bad value {:?} for {}", word, name)))
+        }
+        other => Err(invalid(format!("unexpected {:?} in {}", other, name))),
+    }
+}
+
+/// Seconds past J2000 of a kernel date such as `1972-JAN-1` or
+/// `2017-JAN-01/00:00:00.5`
+fn parse_date(date: &str) -> Option<f64> {
+    let (day_part, time_part) = match date.split_once(['/', 'T']) {
+        Some((day, time)) => (day, Some(time)),
+        None => (date, None),
+    };
+    let mut fields = day_part.split('-');
+    let year: i32 = fields.next()?.parse().ok()?;
+    let month = fields.next()?;
+    let month: u32 = match month.parse() {
+        Ok(month) => month,
+        Err(_) => {
+            const MONTHS: [&str; 12] = [
+                "JAN", "FEB", "MAR", "APR", "MAY", "JUN", "JUL", "AUG", "SEP", "OCT", "NOV", "DEC",
+            ];
+            MONTHS.iter().position(|m| m.eq_ignore_ascii_case(month))? as u32 + 1
+        }
+    };
+    let day: u32 = fields.next()?.parse().ok()?;
+    if fields.next().is_some() || !(1..=12).contains(&month) || !(1..=31).contains(&day) {
+        return None;
+    }
+
+    let mut seconds = 0.0;
+    if let Some(time) = time_part {
+        for (field, scale) in time.split(':').zip([3600.0, 60.0, 1.0]) {
+            seconds += field.parse::<f64>().ok()? * scale;
+        }
+    }
+
+    // Julian day numbers count from noon
+    let midnight = Timescale::builtin().julian_day(year, month, day) as f64 - 0.5;
+    Some((midnight - J2000) * DAY_S + seconds)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use approx::assert_relative_eq;
+
+    const KERNEL: &str = r"KPL/LSK
+
+Leapseconds and a few planetary constants.
+
+\begindata
+
+DELTET/DELTA_T_A       =   32.184
+DELTET/K               =    1.657D-3
+DELTET/DELTA_AT        = ( 10,   @1972-JAN-1
+                           11,   @1972-JUL-1
+                           37,   @2017-JAN-1 )
+
+\begintext
+
+BODY399_RADII = ( 1 2 3 ) is commentary here.
+
+\begindata
+BODY399_RADII     = ( 6378.1366   6378.1366   6356.7519 )
+BODY301_POLE_RA   = ( 269.9949 0.0031 0. )
+BODY301_POLE_RA  += 1.5E0
+FRAME_IAU_MOON    = 10020
+FRAME_10020_NAME  = 'IAU_MOON'
+NOTE              = ( 'It''s', 'two' )
+EPOCH             = @2000-JAN-01/12:00:00
+\begintext
+";
+
+    #[test]
+    fn test_parse_kernel_pool() {
+        let pool = KernelPool::parse(KERNEL).unwrap();
+        assert_eq!(pool.len(), 9);
+        assert_eq!(pool.number("DELTET/DELTA_T_A"), Some(32.184));
+        assert_relative_eq!(pool.number("DELTET/K").unwrap(), 1.657e-3);
+        assert_eq!(
+            pool.body_radii_km(399),
+            Some([6378.1366, 6378.1366, 6356.7519])
+        );
+        assert_eq!(
+            pool.body_constant(301, "POLE_RA"),
+            Some(vec![269.9949, 0.0031, 0.0, 1.5])
+        );
+        assert_eq!(pool.frame_id("iau_moon"), Some(10020));
+        assert_eq!(pool.frame_name(10020), Some("IAU_MOON"));
+        assert_eq!(pool.strings("NOTE"), Some(vec!["It's", "two"]));
+        assert_eq!(pool.number("EPOCH"), Some(0.0));
+        assert!(pool.numbers("NOTE").is_none());
+
+        let (dates, offsets) = pool.leap_seconds().unwrap();
+        assert_eq!(dates, vec![2_441_317.5, 2_441_499.5, 2_457_754.5]);
+        assert_eq!(offsets, vec![10, 11, 37]);
+        let ts = Timescale::new(None, dates, offsets, None);
+        let t = ts.utc((2020, 1, 1));
+        assert_eq!(t.leap_seconds(), 37.0);
+    }
+
+    #[test]
+    fn test_later_kernels_override() {
+        let mut pool = KernelPool::parse("\\begindata\nA = 1\nB = ( 'x' )\n").unwrap();
+        pool.load_str("\\begindata\nA = ( 2, 3 )\nB += 'y'\n")
+            .unwrap();
+        assert_eq!(pool.numbers("A"), Some(vec![2.0, 3.0]));
+        assert_eq!(pool.strings("B"), Some(vec!["x", "y"]));
+
+        for bad in [
+            "A = ( 1",
+            "A 1",
+            "= 1",
+            "A = 'open",
+            "A = @1972-FOO-1",
+            "A = 1.2.3",
+        ] {
+            assert!(
+                KernelPool::parse(&format!("\\begindata\n{}\n", bad)).is_err(),
+                "{}",
+                bad
+            );
+        }
+    }
+}
@@ -0,0 +1,412 @@
+//! Small-body element files from the MPC and JPL
+//!
+//! * [`parse_mpc_comets`] reads the Minor Planet Center's `CometEls.txt`
+//! * [`parse_mpcorb`] reads its `MPCORB.DAT` (or the daily `NEA.txt` and
+//!   similar extracts, which share the format)
+//! * [`parse_jpl_elements`] reads JPL's `ELEMENTS.COMET`, `ELEMENTS.NUMBR`
+//!   and `ELEMENTS.UNNUM`, locating columns from the dashed rule under the
+//!   header
+//!
+//! All three give [`SmallBodyElements`], which hold perihelion distance and
+//! time whatever the source, so that comets on open orbits and asteroids
+//! are handled alike. Angles are referred to the J2000 ecliptic and dates
+//! are TT, which is taken as TDB.
+
+use super::{KeplerOrbit, GM_SUN_AU3_DAY2};
+use crate::constants::{DEG2RAD, TAU};
+use crate::time::Timescale;
+use crate::{Result, StarfieldError};
+use std::collections::HashMap;
+
+/// Modified Julian Date zero point, for JPL epochs
+const MJD_ZERO: f64 = 2_400_000.5;
+
+/// Osculating heliocentric elements of a comet or asteroid
+#[derive(Debug, Clone, PartialEq)]
+pub struct SmallBodyElements {
+    /// Name or designation, as readable as the source gives it
+    pub designation: String,
+    /// Perihelion distance, AU
+    pub perihelion_distance_au: f64,
+    /// Eccentricity; 1 or more for an open orbit
+    pub eccentricity: f64,
+    /// Inclination to the J2000 ecliptic, degrees
+    pub inclination_deg: f64,
+    /// Longitude of the ascending node, J2000, degrees
+    pub ascending_node_deg: f64,
+    /// Argument of perihelion, J2000, degrees
+    pub argument_of_perihelion_deg: f64,
+    /// TT Julian date of perihelion passage
+    pub perihelion_jd: f64,
+    /// TT Julian date of osculation, if given
+    pub epoch_jd: Option<f64>,
+    /// Absolute magnitude (H for asteroids, total magnitude M1 for comets)
+    pub absolute_magnitude: Option<f64>,
+    /// Magnitude slope (G for asteroids, K for comets)
+    pub slope_parameter: Option<f64>,
+}
+
+impl SmallBodyElements {
+    /// The two-body orbit through these elements
+    pub fn orbit(&self) -> KeplerOrbit {
+        KeplerOrbit::from_perihelion(
+            self.perihelion_distance_au,
+            self.eccentricity,
+            self.inclination_deg,
+            self.ascending_node_deg,
+            self.argument_of_perihelion_deg,
+            self.perihelion_jd,
+        )
+    }
+
+    /// Semi-major axis in AU, or `None` for an open orbit
+    pub fn semi_major_axis_au(&self) -> Option<f64> {
+        (self.eccentricity < 1.0).then(|| self.perihelion_distance_au / (1.0 - self.eccentricity))
+    }
+
+    /// Parse one line of `CometEls.txt`, or `None` if it is malformed
+    pub fn parse_mpc_comet_line(line: &str) -> Option<Self> {
+        let field = |start: usize, end: usize| line.get(start - 1..end.min(line.len()));
+        let number = |start, end| field(start, end)?.trim().parse::<f64>().ok();
+
+        let year: i32 = field(15, 18)?.trim().parse().ok()?;
+        let month: u32 = field(20, 21)?.trim().parse().ok()?;
+        let day = number(23, 29)?;
+        let epoch = field(82, 89)
+            .map(str::trim)
+            .filter(|s| s.len() == 8)
+            .and_then(|s| {
+                Some(tt_jd(
+                    s[0..4].parse().ok()?,
+                    s[4..6].parse().ok()?,
+                    s[6..8].parse().ok()?,
+                ))
+            });
+        let name = field(103, 158).map(str::trim).unwrap_or_default();
+
+        Some(Self {
+            designation: if name.is_empty() {
+                field(1, 12)?.trim().to_string()
+            } else {
+                name.to_string()
+            },
+            perihelion_distance_au: number(31, 39)?,
+            eccentricity: number(42, 49)?,
+            argument_of_perihelion_deg: number(52, 59)?,
+            ascending_node_deg: number(62, 69)?,
+            inclination_deg: number(72, 79)?,
+            perihelion_jd: tt_jd(year, month, day),
+            epoch_jd: epoch,
+            absolute_magnitude: number(92, 95),
+            slope_parameter: number(97, 100),
+        })
+    }
+
+    /// Parse one line of `MPCORB.DAT`, or `None` for headers and malformed
+    /// lines
+    pub fn parse_mpcorb_line(line: &str) -> Option<Self> {
+        let field = |start: usize, end: usize| line.get(start - 1..end);
+        let number = |start, end| field(start, end)?.trim().parse::<f64>().ok();
+
+        let epoch_jd = unpack_epoch(field(21, 25)?)?;
+        let semi_major_axis = number(93, 103)?;
+        let eccentricity = number(71, 79)?;
+        let readable = line.get(166..line.len().min(194)).map(str::trim);
+
+        Some(Self {
+            designation: match readable {
+                Some(name) if !name.is_empty() => name.to_string(),
+                _ => field(1, 7)?.trim().to_string(),
+            },
+            perihelion_distance_au: semi_major_axis * (1.0 - eccentricity),
+            eccentricity,
+            inclination_deg: number(60, 68)?,
+            ascending_node_deg: number(49, 57)?,
+            argument_of_perihelion_deg: number(38, 46)?,
+            perihelion_jd: perihelion_from_mean_anomaly(semi_major_axis, number(27, 35)?, epoch_jd),
+            epoch_jd: Some(epoch_jd),
+            absolute_magnitude: number(9, 13),
+            slope_parameter: number(15, 19),
+        })
+    }
+}
+
+/// TT Julian date of a Gregorian date with a fractional day
+fn tt_jd(year: i32, month: u32, day: f64) -> f64 {
+    let ts = Timescale::default();
+    ts.julian_day(year, month, day.trunc() as u32) as f64 - 0.5 + day.fract()
+}
+
+/// Julian date of an MPC packed epoch such as `K2555` (2025 May 5.0 TT)
+fn unpack_epoch(packed: &str) -> Option<f64> {
+    let digit = |c: char| match c {
+        '1'..='9' => c.to_digit(10),
+        'A'..='V' => Some(c as u32 - 'A' as u32 + 10),
+        _ => None,
+    };
+    let chars: Vec<char> = packed.chars().collect();
+    if chars.len() != 5 {
+        return None;
+    }
+    let century = match chars[0] {
+        'I' => 1800,
+        'J' => 1900,
+        'K' => 2000,
+        _ => return None,
+    };
+    let year = century + packed.get(1..3)?.parse::<i32>().ok()?;
+    Some(tt_jd(year, digit(chars[3])?, digit(chars[4])? as f64))
+}
+
+/// Date of the perihelion nearest the epoch of an elliptical orbit
+fn perihelion_from_mean_anomaly(
+    semi_major_axis_au: f64,
+    mean_anomaly_deg: f64,
+    epoch_jd: f64,
+) -> f64 {
+    let mean_motion = (GM_SUN_AU3_DAY2 / semi_major_axis_au.powi(3)).sqrt();
+    let anomaly = (mean_anomaly_deg * DEG2RAD + TAU / 2.0).rem_euclid(TAU) - TAU / 2.0;
+    epoch_jd - anomaly / mean_motion
+}
+
+/// Collect the parsed lines of a file, failing if a non-empty file has none
+fn collect(
+    text: &str,
+    what: &str,
+    parse: impl Fn(&str) -> Option<SmallBodyElements>,
+) -> Result<Vec<SmallBodyElements>> {
+    let elements: Vec<_> = text.lines().filter_map(parse).collect();
+    if elements.is_empty() && !text.trim().is_empty() {
+        return Err(StarfieldError::DataError(format!("no {} found", what)));
+    }
+    Ok(elements)
+}
+
+/// Parse the MPC's `CometEls.txt`, skipping malformed lines
+pub fn parse_mpc_comets(text: &str) -> Result<Vec<SmallBodyElements>> {
+    collect(
+        text,
+        "comet elements",
+        SmallBodyElements::parse_mpc_comet_line,
+    )
+}
+
+/// Parse the MPC's `MPCORB.DAT`, skipping the header and malformed lines
+pub fn parse_mpcorb(text: &str) -> Result<Vec<SmallBodyElements>> {
+    collect(
+        text,
+        "minor planet elements",
+        SmallBodyElements::parse_mpcorb_line,
+    )
+}
+
+/// Parse a JPL small-body element file (`ELEMENTS.COMET`, `ELEMENTS.NUMBR`
+/// or `ELEMENTS.UNNUM`)
+///
+/// The header line names the columns and the rule of dashes below it gives
+/// their extents. Comet files give perihelion distance `q` and time `Tp`
+/// (`YYYYMMDD.ddddd`); asteroid files give `a` and mean anomaly `M`.
+/// Epochs are MJD.
+pub fn parse_jpl_elements(text: &str) -> Result<Vec<SmallBodyElements>> {
+    let lines: Vec<&str> = text.lines().collect();
+    let rule = lines
+        .iter()
+        .position(|line| line.starts_with('-') && line.trim().chars().all(|c| c == '-' || c == ' '))
+        .filter(|&i| i > 0)
+        .ok_or_else(|| StarfieldError::DataError("no column rule in JPL elements".to_string()))?;
+
+    // Column extents from the runs of dashes, named from the header above
+    let mut columns = Vec::new();
+    let mut start = None;
+    for (i, c) in lines[rule].char_indices().chain([(lines[rule].len(), ' ')]) {
+        match (c, start) {
+            ('-', None) => start = Some(i),
+            (' ', Some(s)) => {
+                columns.push(s..i);
+                start = None;
+            }
+            _ => {}
+        }
+    }
+    // Header words are not always inside their column's dashes, so each
+    // goes to the column nearest its middle
+    let mut names = vec![String::new(); columns.len()];
+    let mut offset = 0;
+    for word in lines[rule - 1].split_whitespace() {
+        let start = offset + lines[rule - 1][offset..].find(word).unwrap_or(0);
+        offset = start + word.len();
+        let middle = start + word.len() / 2;
+        let distance = |span: &std::ops::Range<usize>| {
+            span.start.saturating_sub(middle) + middle.saturating_sub(span.end - 1)
+        };
+        if let Some(column) = (0..columns.len()).min_by_key(|&i| distance(&columns[i])) {
+            if !names[column].is_empty() {
+                names[column].push(' ');
+            }
+            names[column].push_str(word);
+        }
+    }
+
+    let parse = |line: &str| -> Option<SmallBodyElements> {
+        let values: HashMap<&str, &str> = names
+            .iter()
+            .zip(&columns)
+            .map(|(name, span)| {
+                let end = span.end.min(line.len());
+                (
+                    name.as_str(),
+                    line.get(span.start.min(end)..end).unwrap_or("").trim(),
+                )
+            })
+            .collect();
+        let number = |name: &str| values.get(name)?.parse::<f64>().ok();
+
+        let epoch_jd = number("Epoch").map(|mjd| mjd + MJD_ZERO);
+        let eccentricity = number("e")?;
+        let (perihelion_distance_au, perihelion_jd) = match (number("q"), values.get("Tp")) {
+            (Some(q), Some(tp)) => {
+                let (date, fraction) = tp.split_once('.').unwrap_or((tp, "0"));
+                if date.len() != 8 {
+                    return None;
+                }
+                let day = date[6..8].parse::<f64>().ok()?
+                    + format!("0.{}", fraction).parse::<f64>().ok()?;
+                (
+                    q,
+                    tt_jd(date[0..4].parse().ok()?, date[4..6].parse().ok()?, day),
+                )
+            }
+            _ => {
+                let a = number("a")?;
+                (
+                    a * (1.0 - eccentricity),
+                    perihelion_from_mean_anomaly(a, number("M")?, epoch_jd?),
+                )
+            }
+        };
+
+        let first = values
+            .get(names.first()?.as_str())
+            .copied()
+            .unwrap_or_default();
+        let designation = match values.get("Name") {
+            Some(name) if !name.is_empty() && names[0] == "Num" => format!("({}) {}", first, name),
+            _ => first.to_string(),
+        };
+        if designation.is_empty() {
+            return None;
+        }
+
+        Some(SmallBodyElements {
+            designation,
+            perihelion_distance_au,
+            eccentricity,
+            inclination_deg: number("i")?,
+            ascending_node_deg: number("Node")?,
+            argument_of_perihelion_deg: number("w")?,
+            perihelion_jd,
+            epoch_jd,
+            absolute_magnitude: number("H"),
+            slope_parameter: number("G"),
+        })
+    };
+
+    collect(&lines[rule + 1..].join("\n"), "JPL elements", parse)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use approx::assert_relative_eq;
+
+    const HALLEY: &str = "0001P         1986 02  9.4660  0.574978  0.967157  111.8646   58.8601  162.2422  19860205   4.0  6.0  1P/Halley                                                 98, 1083";
+
+    #[test]
+    fn test_parse_mpc_files() {
+        let halley = parse_mpc_comets(&format!("\n{}\nnonsense\n", HALLEY)).unwrap();
+        assert_eq!(halley.len(), 1);
+        let halley = &halley[0];
+        assert_eq!(halley.designation, "1P/Halley");
+        assert_relative_eq!(halley.perihelion_jd, 2_446_470.966, epsilon = 1e-6);
+        assert_eq!(halley.epoch_jd, Some(2_446_466.5));
+        assert_eq!(halley.inclination_deg, 162.2422);
+        assert_relative_eq!(halley.semi_major_axis_au().unwrap(), 17.507, epsilon = 1e-3);
+
+        // MPCORB.DAT columns, Ceres at epoch 2025 May 5.0
+        let mut ceres = format!(
+            "{:<7} {:>5} {:>5} {:<5} {:>9}  {:>9}  {:>9}  {:>9}  {:>9} {:>11} {:>11}",
+            "00001",
+            "3.34",
+            "0.15",
+            "K2555",
+            "188.70269",
+            "73.27343",
+            "80.25221",
+            "10.58780",
+            "0.0794013",
+            "0.21424651",
+            "2.7660512"
+        );
+        ceres = format!("{:<166}{:<28}20241101", ceres, "(1) Ceres");
+        let parsed = parse_mpcorb(&format!("MPCORB header\n{}\n", ceres)).unwrap();
+        let ceres = &parsed[0];
+        assert_eq!(ceres.designation, "(1) Ceres");
+        assert_eq!(ceres.epoch_jd, Some(2_460_800.5));
+        assert_eq!(ceres.absolute_magnitude, Some(3.34));
+        assert_relative_eq!(
+            ceres.semi_major_axis_au().unwrap(),
+            2.766_051_2,
+            epsilon = 1e-9
+        );
+
+        // The orbit is back at mean anomaly 188.7 degrees at the epoch
+        let orbit = ceres.orbit();
+        let at_epoch = KeplerOrbit::from_mean_anomaly(
+            2.766_051_2,
+            0.079_401_3,
+            10.5878,
+            80.25221,
+            73.27343,
+            188.70269,
+            2_460_800.5,
+        );
+        assert_relative_eq!(
+            orbit.position_at(2_460_800.5),
+            at_epoch.position,
+            epsilon = 1e-9
+        );
+
+        assert!(parse_mpcorb("not an orbit").is_err());
+        assert!(parse_mpc_comets("").unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_parse_jpl_elements() {
+        let comets = concat!(
+            " Num  Name                                     Epoch      q           e        i         w        Node          Tp       Ref\n",
+            "------------------------------------------- ------- ----------- ---------- --------- --------- --------- -------------- ------------\n",
+            "  1P/Halley                                   39857  0.57471959 0.96714291 162.26269 111.33249  58.42008 19860205.89532 JPL J863/77\n",
+        );
+        let halley = &parse_jpl_elements(comets).unwrap()[0];
+        assert_eq!(halley.designation, "1P/Halley");
+        assert_eq!(halley.epoch_jd, Some(2_439_857.5));
+        assert_relative_eq!(halley.perihelion_jd, 2_446_467.395_32, epsilon = 1e-6);
+        assert_eq!(halley.ascending_node_deg, 58.42008);
+
+        let numbered = concat!(
+            " Num   Name              Epoch      a          e        i         w        Node        M         H    G   Ref\n",
+            "------ ----------------- ----- ---------- ---------- --------- --------- --------- ----------- ----- ----- ----------\n",
+            "     1 Ceres             60800  2.7660512 0.07940130  10.58780  73.27343  80.25221 188.7026900  3.34  0.15 JPL 48\n",
+        );
+        let ceres = &parse_jpl_elements(numbered).unwrap()[0];
+        assert_eq!(ceres.designation, "(1) Ceres");
+        assert_eq!(ceres.slope_parameter, Some(0.15));
+        assert_relative_eq!(
+            ceres.perihelion_distance_au,
+            2.766_051_2 * (1.0 - 0.0794013),
+            epsilon = 1e-12
+        );
+
+        assert!(parse_jpl_elements("no rule here").is_err());
+    }
+}
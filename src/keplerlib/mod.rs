@@ -0,0 +1,338 @@
+//! Two-body propagation of comet and asteroid orbits
+//!
+//! A [`KeplerOrbit`] is a heliocentric state vector at an epoch, moved to
+//! any other date with the universal-variable formulation of Kepler's
+//! problem (Vallado, *Fundamentals of Astrodynamics*, algorithm 8), which
+//! handles elliptical, parabolic and hyperbolic orbits alike - needed for
+//! the many long-period comets with eccentricities at or above 1.
+//!
+//! Orbits come from osculating elements, either perihelion distance and
+//! time as catalogued for comets or semi-major axis and mean anomaly as for
+//! asteroids, and [`elements`] reads those from the Minor Planet Center's
+//! `CometEls.txt` and `MPCORB.DAT` and from JPL's small-body element files,
+//! the same flow as skyfield's `mpc.load_comets_dataframe`:
+//!
+//! ```
+//! use starfield::keplerlib::elements::parse_mpc_comets;
+//! use starfield::planetlib::{Body, Ephemeris};
+//! use starfield::time::Timescale;
+//!
+//! let text = "0001P         1986 02  9.4660  0.574978  0.967157  111.8646   58.8601  162.2422  19860205   4.0  6.0  1P/Halley                                                 98, 1083";
+//! let halley = &parse_mpc_comets(text)?[0];
+//! let orbit = halley.orbit();
+//!
+//! let ts = Timescale::default();
+//! let t = ts.utc((1986, 4, 11));
+//! let eph = Ephemeris::new();
+//! let earth = eph.get_state(Body::Earth, t.tdb()).unwrap();
+//! let sun = eph.get_state(Body::Sun, t.tdb()).unwrap();
+//! let (position, light_time) = orbit.astrometric_from(&(earth.position - sun.position), t.tdb());
+//!
+//! // Closest approach, 0.42 AU, low in the southern sky
+//! assert!((position.norm() - 0.42).abs() < 0.01);
+//! assert!(position.z / position.norm() < -0.7);
+//! println!("{:.1} light-minutes away", light_time * 1440.0);
+//! # Ok::<(), starfield::StarfieldError>(())
+//! ```
+//!
+//! Positions are in AU and velocities in AU/day, in J2000 equatorial
+//! (ICRF-aligned) axes, at TDB Julian dates. Planetary perturbations are
+//! ignored, so positions are good for weeks to months either side of the
+//! epoch of the elements.
+
+pub mod elements;
+
+use crate::constants::{AU_KM, C_AUDAY, DAY_S, DEG2RAD, GM_SUN};
+use crate::framelib::{rot_x, rot_z, INERTIAL_FRAMES};
+use crate::planetlib::elements::solve_kepler;
+use nalgebra::Vector3;
+
+/// The Sun's gravitational parameter in AU^3/day^2
+pub const GM_SUN_AU3_DAY2: f64 = GM_SUN * DAY_S * DAY_S / (AU_KM * AU_KM * AU_KM);
+
+/// Convergence threshold of the universal anomaly, relative to its size
+const ANOMALY_TOLERANCE: f64 = 1e-14;
+
+/// Iteration cap of the universal-anomaly solver
+const MAX_ITERATIONS: usize = 50;
+
+/// Light-time iterations in [`KeplerOrbit::astrometric_from`]
+const LIGHT_TIME_ITERATIONS: usize = 3;
+
+/// Stumpff functions C(z) and S(z)
+fn stumpff(z: f64) -> (f64, f64) {
+    if z > 1e-4 {
+        let s = z.sqrt();
+        ((1.0 - s.cos()) / z, (s - s.sin()) / (s * z))
+    } else if z < -1e-4 {
+        let s = (-z).sqrt();
+        ((s.cosh() - 1.0) / -z, (s.sinh() - s) / (s * -z))
+    } else {
+        // Series about z = 0, where the closed forms cancel badly
+        (
+            0.5 - z / 24.0 + z * z / 720.0,
+            1.0 / 6.0 - z / 120.0 + z * z / 5040.0,
+        )
+    }
+}
+
+/// Move a state `dt` days along its two-body orbit about a mass of
+/// gravitational parameter `gm` (AU^3/day^2)
+///
+/// Solves for the universal anomaly with the Laguerre-Conway iteration,
+/// which converges from a crude starting guess for any eccentricity.
+pub fn propagate(
+    position: &Vector3<f64>,
+    velocity: &Vector3<f64>,
+    dt: f64,
+    gm: f64,
+) -> (Vector3<f64>, Vector3<f64>) {
+    if dt == 0.0 {
+        return (*position, *velocity);
+    }
+    let sqrt_gm = gm.sqrt();
+    let r0 = position.norm();
+    let radial = position.dot(velocity) / sqrt_gm;
+    let alpha = 2.0 / r0 - velocity.norm_squared() / gm;
+
+    // Universal Kepler equation F(x) = 0 and its derivatives
+    let equation = |x: f64| {
+        let z = alpha * x * x;
+        let (c, s) = stumpff(z);
+        let f = radial * x * x * c + (1.0 - alpha * r0) * x.powi(3) * s + r0 * x - sqrt_gm * dt;
+        let df = radial * x * (1.0 - z * s) + (1.0 - alpha * r0) * x * x * c + r0;
+        let ddf = radial * (1.0 - z * c) + (1.0 - alpha * r0) * x * (1.0 - z * s);
+        (f, df, ddf)
+    };
+
+    let mut x = sqrt_gm * dt * if alpha > 0.0 { alpha } else { 1.0 / r0 };
+    let n: f64 = 5.0;
+    for _ in 0..MAX_ITERATIONS {
+        let (f, df, ddf) = equation(x);
+        let root = ((n - 1.0).powi(2) * df * df - n * (n - 1.0) * f * ddf)
+            .abs()
+            .sqrt();
+        let step = n * f / (df + df.signum() * root);
+        x -= step;
+        if step.abs() <= ANOMALY_TOLERANCE * x.abs().max(1.0) {
+            break;
+        }
+    }
+
+    let z = alpha * x * x;
+    let (c, s) = stumpff(z);
+    let f = 1.0 - x * x / r0 * c;
+    let g = dt - x.powi(3) / sqrt_gm * s;
+    let new_position = f * position + g * velocity;
+    let r = new_position.norm();
+    let f_dot = sqrt_gm / (r * r0) * (z * x * s - x);
+    let g_dot = 1.0 - x * x / r * c;
+    (new_position, f_dot * position + g_dot * velocity)
+}
+
+/// Rotation from the orbital plane (x toward perihelion) to J2000
+/// equatorial axes, angles in degrees referred to the J2000 ecliptic
+fn perifocal_to_equatorial(
+    inclination_deg: f64,
+    ascending_node_deg: f64,
+    argument_of_perihelion_deg: f64,
+) -> nalgebra::Matrix3<f64> {
+    INERTIAL_FRAMES["ECLIPJ2000"].transpose()
+        * rot_z(ascending_node_deg * DEG2RAD)
+        * rot_x(inclination_deg * DEG2RAD)
+        * rot_z(argument_of_perihelion_deg * DEG2RAD)
+}
+
+/// A heliocentric two-body orbit, held as a state vector at an epoch
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct KeplerOrbit {
+    /// Position at the epoch, AU
+    pub position: Vector3<f64>,
+    /// Velocity at the epoch, AU/day
+    pub velocity: Vector3<f64>,
+    /// TDB Julian date of the state
+    pub epoch_jd: f64,
+    /// Gravitational parameter of the central body, AU^3/day^2
+    pub gm: f64,
+}
+
+impl KeplerOrbit {
+    /// An orbit about the Sun through a state at `epoch_jd`
+    pub fn new(position: Vector3<f64>, velocity: Vector3<f64>, epoch_jd: f64) -> Self {
+        Self {
+            position,
+            velocity,
+            epoch_jd,
+            gm: GM_SUN_AU3_DAY2,
+        }
+    }
+
+    /// Use another central gravitational parameter, in AU^3/day^2
+    pub fn with_gm(mut self, gm: f64) -> Self {
+        self.gm = gm;
+        self
+    }
+
+    /// An orbit from its perihelion distance (AU), eccentricity, angles
+    /// referred to the J2000 ecliptic (degrees) and TDB Julian date of
+    /// perihelion, as comet elements are given; any eccentricity
+    pub fn from_perihelion(
+        perihelion_distance_au: f64,
+        eccentricity: f64,
+        inclination_deg: f64,
+        ascending_node_deg: f64,
+        argument_of_perihelion_deg: f64,
+        perihelion_jd: f64,
+    ) -> Self {
+        let speed = (GM_SUN_AU3_DAY2 * (1.0 + eccentricity) / perihelion_distance_au).sqrt();
+        let rotation = perifocal_to_equatorial(
+            inclination_deg,
+            ascending_node_deg,
+            argument_of_perihelion_deg,
+        );
+        Self::new(
+            rotation * Vector3::new(perihelion_distance_au, 0.0, 0.0),
+            rotation * Vector3::new(0.0, speed, 0.0),
+            perihelion_jd,
+        )
+    }
+
+    /// An elliptical orbit from its semi-major axis (AU), eccentricity,
+    /// angles referred to the J2000 ecliptic and mean anomaly at `epoch_jd`
+    /// (degrees), as asteroid elements are given
+    pub fn from_mean_anomaly(
+        semi_major_axis_au: f64,
+        eccentricity: f64,
+        inclination_deg: f64,
+        ascending_node_deg: f64,
+        argument_of_perihelion_deg: f64,
+        mean_anomaly_deg: f64,
+        epoch_jd: f64,
+    ) -> Self {
+        let (a, e) = (semi_major_axis_au, eccentricity);
+        let anomaly = solve_kepler(
+            (mean_anomaly_deg * DEG2RAD).rem_euclid(crate::constants::TAU),
+            e,
+        );
+        let (sin_e, cos_e) = anomaly.sin_cos();
+        let b = (1.0 - e * e).sqrt();
+        let r = a * (1.0 - e * cos_e);
+        let speed = (GM_SUN_AU3_DAY2 * a).sqrt() / r;
+
+        let rotation = perifocal_to_equatorial(
+            inclination_deg,
+            ascending_node_deg,
+            argument_of_perihelion_deg,
+        );
+        Self::new(
+            rotation * Vector3::new(a * (cos_e - e), a * b * sin_e, 0.0),
+            rotation * Vector3::new(-speed * sin_e, speed * b * cos_e, 0.0),
+            epoch_jd,
+        )
+    }
+
+    /// Heliocentric position (AU) and velocity (AU/day) at a TDB Julian date
+    pub fn state_at(&self, jd: f64) -> (Vector3<f64>, Vector3<f64>) {
+        propagate(&self.position, &self.velocity, jd - self.epoch_jd, self.gm)
+    }
+
+    /// Heliocentric position in AU at a TDB Julian date
+    pub fn position_at(&self, jd: f64) -> Vector3<f64> {
+        self.state_at(jd).0
+    }
+
+    /// Vector from a heliocentric `observer` position (AU) to the body as
+    /// seen at TDB Julian date `jd`, corrected for light time, and the
+    /// light time in days
+    ///
+    /// [`Ephemeris`](crate::planetlib::Ephemeris) positions are barycentric,
+    /// so subtract the Sun's position from the observer's first.
+    pub fn astrometric_from(&self, observer: &Vector3<f64>, jd: f64) -> (Vector3<f64>, f64) {
+        let mut light_time = 0.0;
+        let mut vector = self.position_at(jd) - observer;
+        for _ in 0..LIGHT_TIME_ITERATIONS {
+            light_time = vector.norm() / C_AUDAY;
+            vector = self.position_at(jd - light_time) - observer;
+        }
+        (vector, light_time)
+    }
+
+    /// Semi-major axis in AU; negative for a hyperbolic orbit and infinite
+    /// for a parabolic one
+    pub fn semi_major_axis_au(&self) -> f64 {
+        1.0 / (2.0 / self.position.norm() - self.velocity.norm_squared() / self.gm)
+    }
+
+    /// Eccentricity
+    pub fn eccentricity(&self) -> f64 {
+        let (r, v) = (&self.position, &self.velocity);
+        let e = (v.norm_squared() - self.gm / r.norm()) * r - r.dot(v) * v;
+        e.norm() / self.gm
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::planetlib::OrbitalElements;
+    use approx::assert_relative_eq;
+
+    #[test]
+    fn test_propagation_conserves_the_orbit() {
+        // Elliptical, near-parabolic and hyperbolic
+        for e in [0.2, 0.999_9, 1.0, 1.5] {
+            let orbit = KeplerOrbit::from_perihelion(0.8, e, 30.0, 40.0, 50.0, 2_460_000.5);
+            let energy =
+                |r: &Vector3<f64>, v: &Vector3<f64>| v.norm_squared() / 2.0 - orbit.gm / r.norm();
+            let momentum = orbit.position.cross(&orbit.velocity);
+
+            for dt in [-400.0, -3.0, 0.5, 120.0] {
+                let (r, v) = orbit.state_at(orbit.epoch_jd + dt);
+                assert_relative_eq!(r.cross(&v), momentum, max_relative = 1e-9);
+                assert_relative_eq!(
+                    energy(&r, &v),
+                    energy(&orbit.position, &orbit.velocity),
+                    epsilon = 1e-12
+                );
+                // and going back returns to the start
+                let (r0, v0) = propagate(&r, &v, -dt, orbit.gm);
+                assert_relative_eq!(r0, orbit.position, epsilon = 1e-10);
+                assert_relative_eq!(v0, orbit.velocity, epsilon = 1e-12);
+            }
+            assert_relative_eq!(orbit.eccentricity(), e, epsilon = 1e-12);
+        }
+    }
+
+    #[test]
+    fn test_agrees_with_mean_anomaly_elements() {
+        let elements = OrbitalElements {
+            epoch_jd: 2_460_600.5,
+            semi_major_axis_au: 2.7675,
+            eccentricity: 0.0796,
+            inclination_deg: 10.587,
+            ascending_node_deg: 80.25,
+            argument_of_perihelion_deg: 73.30,
+            mean_anomaly_deg: 145.8,
+        };
+        let orbit = KeplerOrbit::from_mean_anomaly(
+            2.7675,
+            0.0796,
+            10.587,
+            80.25,
+            73.30,
+            145.8,
+            2_460_600.5,
+        );
+        assert_relative_eq!(orbit.semi_major_axis_au(), 2.7675, epsilon = 1e-10);
+
+        // Propagating the state follows Kepler's equation a period later
+        for dt in [0.0, 90.0, 1_000.0] {
+            let jd = elements.epoch_jd + dt;
+            assert_relative_eq!(
+                orbit.position_at(jd),
+                elements.heliocentric_position(jd),
+                epsilon = 1e-10
+            );
+        }
+    }
+}
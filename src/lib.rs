@@ -7,28 +7,40 @@ use crate::catalogs::StarCatalog;
 use std::path::Path;
 use thiserror::Error;
 
+pub mod accuracy;
 pub mod almanac;
 pub mod catalogs;
 pub mod celestial;
+pub mod charting;
 pub mod constants;
 pub mod coordinates;
 pub mod data;
 pub mod earthlib;
 pub mod errors;
 pub mod framelib;
+pub mod healpix;
 pub mod image;
+pub mod jplephem;
+pub mod keplerlib;
 pub mod nutationlib;
+pub mod observers;
+pub mod output;
 pub mod planetlib;
 pub mod positions;
 pub mod precessionlib;
 #[cfg(feature = "python-tests")]
 pub mod pybridge;
+pub mod satellites;
+pub mod searchlib;
 pub mod time;
+pub mod tracking;
 pub mod units;
+pub mod validation;
+pub mod wcs;
 
 // Re-export commonly used types
 pub use coordinates::Equatorial;
-pub use time::{CalendarTuple, Time, Timescale};
+pub use time::{CalendarTuple, Time, TimeDelta, Timescale};
 
 /// Main error type for the starfield library
 #[derive(Debug, Error)]
@@ -47,20 +59,57 @@ pub enum StarfieldError {
 
     #[error("Object not found: {0}")]
     ObjectNotFound(String),
+
+    #[error("Download error: {0}")]
+    DownloadError(Box<data::DownloadError>),
+}
+
+impl From<data::DownloadError> for StarfieldError {
+    fn from(err: data::DownloadError) -> Self {
+        StarfieldError::DownloadError(Box::new(err))
+    }
+}
+
+impl From<jplephem::JplEphemError> for StarfieldError {
+    fn from(err: jplephem::JplEphemError) -> Self {
+        match err {
+            jplephem::JplEphemError::Io(e) => StarfieldError::IoError(e),
+            other => StarfieldError::DataError(other.to_string()),
+        }
+    }
 }
 
 /// Result type for starfield operations
 pub type Result<T> = std::result::Result<T, StarfieldError>;
 
+/// JPL kernels [`Loader::find_ephemeris_kernel`] looks for, in order of preference
+pub const EPHEMERIS_KERNELS: &[&str] = &["de440s.bsp", "de440.bsp", "de421.bsp"];
+
 /// Entry point for loading standard astronomical data
 pub struct Loader {
     data_dir: Option<std::path::PathBuf>,
+    accuracy: accuracy::AccuracyProfile,
+    recorder: Option<data::AccessRecorder>,
+    replay: Option<data::ReplayBundle>,
+    progress: Option<data::ProgressReporter>,
+    cache: data::CachePolicy,
+    #[cfg(feature = "parallel")]
+    threads: Option<usize>,
 }
 
 impl Loader {
     /// Create a new loader with default data directory
     pub fn new() -> Self {
-        Self { data_dir: None }
+        Self {
+            data_dir: None,
+            accuracy: accuracy::AccuracyProfile::default(),
+            recorder: None,
+            replay: None,
+            progress: None,
+            cache: data::CachePolicy::default(),
+            #[cfg(feature = "parallel")]
+            threads: None,
+        }
     }
 
     /// Set a custom data directory
@@ -69,18 +118,295 @@ impl Loader {
         self
     }
 
+    /// Set the accuracy profile for everything this loader builds
+    pub fn with_accuracy(mut self, profile: accuracy::AccuracyProfile) -> Self {
+        self.accuracy = profile;
+        self
+    }
+
+    /// Get the accuracy profile in use
+    pub fn accuracy(&self) -> &accuracy::AccuracyProfile {
+        &self.accuracy
+    }
+
+    /// Record every catalog file, kernel segment and Delta T row used
+    pub fn with_recorder(mut self, recorder: data::AccessRecorder) -> Self {
+        self.recorder = Some(recorder);
+        self
+    }
+
+    /// Serve data from a pinned bundle instead of the cache
+    pub fn with_replay(mut self, bundle: data::ReplayBundle) -> Self {
+        self.replay = Some(bundle);
+        self
+    }
+
+    /// Report download and catalog loading progress to `callback`
+    ///
+    /// Nothing is printed either way; see [`data::ProgressEvent`].
+    pub fn with_progress<F>(mut self, callback: F) -> Self
+    where
+        F: FnMut(data::ProgressEvent) + Send + 'static,
+    {
+        self.progress = Some(data::ProgressReporter::new(callback));
+        self
+    }
+
+    fn report(&self, event: impl FnOnce() -> data::ProgressEvent) {
+        if let Some(progress) = &self.progress {
+            progress.report(event());
+        }
+    }
+
+    /// Never download; fail instead when a file is not cached
+    ///
+    /// Cached files are used however old they are.
+    pub fn offline(mut self, offline: bool) -> Self {
+        self.cache = self.cache.offline(offline);
+        self
+    }
+
+    /// Download cached files again once they are older than `ttl`
+    pub fn with_cache_ttl(mut self, ttl: std::time::Duration) -> Self {
+        self.cache = self.cache.with_ttl(ttl);
+        self
+    }
+
+    /// Cache downloads in `dir` instead of `~/.cache/starfield`
+    pub fn with_cache_dir<P: AsRef<Path>>(mut self, dir: P) -> Self {
+        self.cache = self.cache.with_cache_dir(dir);
+        self
+    }
+
+    /// Download every dataset by file name from `base_url`
+    pub fn with_mirror(mut self, base_url: &str) -> Self {
+        self.cache = self.cache.with_mirror(base_url);
+        self
+    }
+
+    /// Download `dataset` from `url`; see [`data::CachePolicy`] for the
+    /// dataset names
+    pub fn with_dataset_url(mut self, dataset: &str, url: &str) -> Self {
+        self.cache = self.cache.with_dataset_url(dataset, url);
+        self
+    }
+
+    /// Read `dataset` from a local file instead of downloading it
+    pub fn with_dataset_path<P: AsRef<Path>>(mut self, dataset: &str, path: P) -> Self {
+        self.cache = self.cache.with_dataset_path(dataset, path);
+        self
+    }
+
+    /// Retry and resume failed downloads as `retry` says
+    pub fn with_retry(mut self, retry: data::RetryPolicy) -> Self {
+        self.cache = self.cache.with_retry(retry);
+        self
+    }
+
+    /// Reject a download of `dataset` whose SHA-256 digest is not `sha256`
+    pub fn with_dataset_sha256(mut self, dataset: &str, sha256: &str) -> Self {
+        self.cache = self.cache.with_dataset_sha256(dataset, sha256);
+        self
+    }
+
+    /// Replace the whole cache policy
+    pub fn with_cache_policy(mut self, policy: data::CachePolicy) -> Self {
+        self.cache = policy;
+        self
+    }
+
+    /// Get the cache policy in use
+    pub fn cache_policy(&self) -> &data::CachePolicy {
+        &self.cache
+    }
+
+    /// Total size in bytes of the download cache
+    pub fn cache_size(&self) -> Result<u64> {
+        self.cache.cache_size()
+    }
+
+    /// Delete everything in the download cache and return the number of
+    /// bytes freed
+    pub fn clear_cache(&self) -> Result<u64> {
+        self.cache.clear_cache()
+    }
+
+    /// Parse catalog files on `threads` threads instead of rayon's global
+    /// pool, which has one per core
+    #[cfg(feature = "parallel")]
+    pub fn with_threads(mut self, threads: usize) -> Self {
+        self.threads = Some(threads);
+        self
+    }
+
     /// Load the Hipparcos star catalog with a specified magnitude limit
     pub fn load_hipparcos_catalog(
         &self,
         magnitude_limit: f64,
     ) -> Result<catalogs::HipparcosCatalog> {
-        use crate::data::download_hipparcos;
+        // Download/cache the Hipparcos catalog, unless replaying a bundle
+        let dat_path = match self.pinned_hipparcos()? {
+            Some(path) => path,
+            None => data::fetch_hipparcos(&self.cache, self.progress.as_ref())?,
+        };
+        self.hipparcos_from(dat_path, magnitude_limit)
+    }
 
-        // Download/cache the Hipparcos catalog
-        let dat_path = download_hipparcos()?;
+    /// [`Loader::load_hipparcos_catalog`], downloading without blocking
+    #[cfg(feature = "async")]
+    pub async fn load_hipparcos_catalog_async(
+        &self,
+        magnitude_limit: f64,
+    ) -> Result<catalogs::HipparcosCatalog> {
+        let dat_path = match self.pinned_hipparcos()? {
+            Some(path) => path,
+            None => data::fetch_hipparcos_async(&self.cache, self.progress.as_ref()).await?,
+        };
+        self.hipparcos_from(dat_path, magnitude_limit)
+    }
+
+    /// The bundle's Hipparcos catalog when replaying
+    fn pinned_hipparcos(&self) -> Result<Option<std::path::PathBuf>> {
+        self.replay
+            .as_ref()
+            .map(|bundle| {
+                bundle
+                    .catalog_files("hipparcos")
+                    .into_iter()
+                    .next()
+                    .ok_or_else(|| {
+                        StarfieldError::DataError("Bundle has no Hipparcos catalog".to_string())
+                    })
+            })
+            .transpose()
+    }
+
+    /// Record, load and report the Hipparcos catalog at `dat_path`
+    fn hipparcos_from(
+        &self,
+        dat_path: std::path::PathBuf,
+        magnitude_limit: f64,
+    ) -> Result<catalogs::HipparcosCatalog> {
+        if let Some(recorder) = &self.recorder {
+            recorder.record_catalog_file("hipparcos", &dat_path);
+        }
 
         // Load the catalog
-        catalogs::HipparcosCatalog::from_dat_file(dat_path, magnitude_limit)
+        self.report(|| data::ProgressEvent::FileStarted {
+            path: dat_path.clone(),
+            index: 0,
+            total: 1,
+        });
+        let catalog = catalogs::HipparcosCatalog::from_dat_file(&dat_path, magnitude_limit)?;
+        self.report(|| data::ProgressEvent::FileLoaded {
+            path: dat_path,
+            stars: catalog.len(),
+        });
+        self.report(|| data::ProgressEvent::CatalogLoaded {
+            catalog: "hipparcos",
+            stars: catalog.len(),
+        });
+        Ok(catalog)
+    }
+
+    /// Load the NGC and IC objects, with the bundled Messier catalog merged in
+    ///
+    /// Uses an `NGC.csv` from the data directory, or fetches OpenNGC's
+    /// copy as the cache policy allows.
+    pub fn load_deep_sky_catalog(&self) -> Result<catalogs::DeepSkyCatalog> {
+        let path = match self.local_deep_sky() {
+            Some(path) => path,
+            None => {
+                let path = data::fetch_openngc(&self.cache)?;
+                if let Some(recorder) = &self.recorder {
+                    recorder.record_catalog_file("openngc", &path);
+                }
+                path
+            }
+        };
+        self.deep_sky_from(path)
+    }
+
+    /// [`Loader::load_deep_sky_catalog`], downloading without blocking
+    #[cfg(feature = "async")]
+    pub async fn load_deep_sky_catalog_async(&self) -> Result<catalogs::DeepSkyCatalog> {
+        let path = match self.local_deep_sky() {
+            Some(path) => path,
+            None => {
+                let path = data::fetch_openngc_async(&self.cache).await?;
+                if let Some(recorder) = &self.recorder {
+                    recorder.record_catalog_file("openngc", &path);
+                }
+                path
+            }
+        };
+        self.deep_sky_from(path)
+    }
+
+    /// `NGC.csv` from the replay bundle or the data directory
+    fn local_deep_sky(&self) -> Option<std::path::PathBuf> {
+        let in_data_dir = self
+            .data_dir
+            .as_ref()
+            .is_some_and(|dir| dir.join("NGC.csv").is_file());
+        if self.replay.is_some() || in_data_dir {
+            self.resolve_catalog_path("openngc", "NGC.csv")
+        } else {
+            None
+        }
+    }
+
+    /// Parse OpenNGC's `path` and merge in the Messier catalog
+    fn deep_sky_from(&self, path: std::path::PathBuf) -> Result<catalogs::DeepSkyCatalog> {
+        let mut catalog = catalogs::DeepSkyCatalog::from_file(path)?;
+        catalog.merge(catalogs::DeepSkyCatalog::messier());
+        Ok(catalog)
+    }
+
+    /// Locate a catalog file or shard directory
+    ///
+    /// Looks for `path` as given, then under the data directory and the
+    /// download cache; when replaying a bundle only pinned copies of those
+    /// candidates are considered. Files found are recorded under `label`.
+    pub fn resolve_catalog_path<P: AsRef<Path>>(
+        &self,
+        label: &str,
+        path: P,
+    ) -> Option<std::path::PathBuf> {
+        let path = path.as_ref();
+        let mut candidates = vec![path.to_path_buf()];
+        if path.is_relative() {
+            candidates.extend(self.data_dir.iter().map(|dir| dir.join(path)));
+            candidates.push(self.cache.cache_dir().join(path));
+        }
+        // A replay never falls back to live files
+        let found = match &self.replay {
+            Some(bundle) => candidates.iter().find_map(|c| bundle.pinned_copy(c))?,
+            None => candidates
+                .into_iter()
+                .find(|candidate| candidate.exists())?,
+        };
+        if let Some(recorder) = self.recorder.as_ref().filter(|_| found.is_file()) {
+            recorder.record_catalog_file(label, &found);
+        }
+        Some(found)
+    }
+
+    /// The pinned copy of `path` when replaying a bundle, or `path` itself
+    ///
+    /// A file missing from the bundle is an error rather than a silent
+    /// switch to live data.
+    fn replay_path<P: AsRef<Path>>(&self, path: P) -> Result<std::path::PathBuf> {
+        let path = path.as_ref();
+        match &self.replay {
+            Some(bundle) => bundle.pinned_copy(path).ok_or_else(|| {
+                StarfieldError::DataError(format!(
+                    "{} is not pinned in the replay bundle",
+                    path.display()
+                ))
+            }),
+            None => Ok(path.to_path_buf()),
+        }
     }
 
     /// Load the Gaia star catalog from a specific file (CSV or gzipped CSV) with a magnitude limit
@@ -89,39 +415,147 @@ impl Loader {
         path: P,
         magnitude_limit: f64,
     ) -> Result<catalogs::GaiaCatalog> {
-        // Load the catalog from the provided file
-        catalogs::GaiaCatalog::from_file(path, magnitude_limit)
+        self.load_gaia_file(path, magnitude_limit, 0, 1)
     }
 
-    /// Load the Gaia star catalog from all cached files (CSV or gzipped CSV) with a magnitude limit
-    pub fn load_gaia_catalog(&self, magnitude_limit: f64) -> Result<catalogs::GaiaCatalog> {
-        use crate::data::list_cached_gaia_files;
+    /// Load the `index`th of `total` Gaia files, reporting progress
+    fn load_gaia_file<P: AsRef<Path>>(
+        &self,
+        path: P,
+        magnitude_limit: f64,
+        index: usize,
+        total: usize,
+    ) -> Result<catalogs::GaiaCatalog> {
+        let path = self.replay_path(path)?;
+        if let Some(recorder) = &self.recorder {
+            recorder.record_catalog_file("gaia", &path);
+        }
+
+        self.report(|| data::ProgressEvent::FileStarted {
+            path: path.clone(),
+            index,
+            total,
+        });
+        let catalog = catalogs::GaiaCatalog::from_file(&path, magnitude_limit)?;
+        self.report(|| data::ProgressEvent::FileLoaded {
+            path,
+            stars: catalog.len(),
+        });
+        Ok(catalog)
+    }
+
+    /// Load each file in turn and merge them in order
+    #[cfg(not(feature = "parallel"))]
+    fn load_and_merge_gaia_files(
+        &self,
+        files: &[std::path::PathBuf],
+        magnitude_limit: f64,
+    ) -> Result<catalogs::GaiaCatalog> {
+        // Load the first file to initialize the catalog
+        let mut catalog = self.load_gaia_file(&files[0], magnitude_limit, 0, files.len())?;
 
-        // Get list of all cached Gaia files
-        let files = list_cached_gaia_files()?;
+        // Load the rest of the files and merge them into the catalog
+        for (index, file) in files.iter().enumerate().skip(1) {
+            let additional_catalog =
+                self.load_gaia_file(file, magnitude_limit, index, files.len())?;
+            catalog.merge(additional_catalog)?;
+        }
+        Ok(catalog)
+    }
 
+    /// Parse the files concurrently, then merge them in order so that
+    /// duplicates resolve the same way as a sequential load
+    #[cfg(feature = "parallel")]
+    fn load_and_merge_gaia_files(
+        &self,
+        files: &[std::path::PathBuf],
+        magnitude_limit: f64,
+    ) -> Result<catalogs::GaiaCatalog> {
+        use rayon::prelude::*;
+
+        let load = || {
+            files
+                .par_iter()
+                .enumerate()
+                .map(|(index, file)| self.load_gaia_file(file, magnitude_limit, index, files.len()))
+                .collect::<Result<Vec<_>>>()
+        };
+        let catalogs = match self.threads {
+            Some(threads) => rayon::ThreadPoolBuilder::new()
+                .num_threads(threads)
+                .build()
+                .map_err(|e| StarfieldError::DataError(e.to_string()))?
+                .install(load),
+            None => load(),
+        }?;
+
+        let mut catalogs = catalogs.into_iter();
+        let mut catalog = catalogs.next().unwrap_or_default();
+        for additional_catalog in catalogs {
+            catalog.merge(additional_catalog)?;
+        }
+        Ok(catalog)
+    }
+
+    /// A client for on-demand Gaia cone searches, caching its results in
+    /// the data directory when one is set and following offline mode
+    #[cfg(feature = "gaia-tap")]
+    pub fn gaia_tap_client(&self) -> data::GaiaTapClient {
+        let client = data::GaiaTapClient::new().offline(self.cache.is_offline());
+        match &self.data_dir {
+            Some(dir) => client.with_cache_dir(dir.join("gaia_tap")),
+            None => client,
+        }
+    }
+
+    /// Stream the Gaia star catalog from all cached files without loading it
+    ///
+    /// Yields the stars brighter than `magnitude_limit` one at a time; see
+    /// [`catalogs::GaiaCatalogReader`].
+    pub fn stream_gaia_catalog(&self, magnitude_limit: f64) -> Result<catalogs::GaiaCatalogReader> {
+        use crate::data::list_cached_gaia_files;
+
+        let files = match &self.replay {
+            Some(bundle) => bundle.catalog_files("gaia"),
+            None => list_cached_gaia_files()?,
+        };
         if files.is_empty() {
             return Err(StarfieldError::DataError(
                 "No Gaia catalog files found in cache. Use the gaia_downloader tool to download them.".to_string()
             ));
         }
+        if let Some(recorder) = &self.recorder {
+            for file in &files {
+                recorder.record_catalog_file("gaia", file);
+            }
+        }
+        catalogs::GaiaCatalogReader::from_files(files, magnitude_limit)
+    }
 
-        println!("Loading Gaia catalog from {} cached files...", files.len());
+    /// Load the Gaia star catalog from all cached files (CSV or gzipped CSV) with a magnitude limit
+    pub fn load_gaia_catalog(&self, magnitude_limit: f64) -> Result<catalogs::GaiaCatalog> {
+        use crate::data::list_cached_gaia_files;
 
-        // Load the first file to initialize the catalog
-        let mut catalog = self.load_gaia_catalog_from_file(&files[0], magnitude_limit)?;
+        // Get list of all cached Gaia files, or the pinned ones when replaying
+        let files = match &self.replay {
+            Some(bundle) => bundle.catalog_files("gaia"),
+            None => list_cached_gaia_files()?,
+        };
 
-        // Load the rest of the files and merge them into the catalog
-        for file in files.iter().skip(1) {
-            println!("Loading additional file: {}", file.display());
-            let additional_catalog = self.load_gaia_catalog_from_file(file, magnitude_limit)?;
-            catalog.merge(additional_catalog)?;
+        if files.is_empty() {
+            return Err(StarfieldError::DataError(
+                "No Gaia catalog files found in cache. Use the gaia_downloader tool to download them.".to_string()
+            ));
         }
 
-        println!(
-            "Successfully loaded Gaia catalog with {} stars",
-            catalog.len()
-        );
+        log::info!("Loading Gaia catalog from {} cached files", files.len());
+        let catalog = self.load_and_merge_gaia_files(&files, magnitude_limit)?;
+
+        log::info!("Loaded Gaia catalog with {} stars", catalog.len());
+        self.report(|| data::ProgressEvent::CatalogLoaded {
+            catalog: "gaia",
+            stars: catalog.len(),
+        });
         Ok(catalog)
     }
 
@@ -131,16 +565,126 @@ impl Loader {
     }
 
     /// Load planetary ephemeris
+    ///
+    /// Uses the first JPL kernel among [`EPHEMERIS_KERNELS`] found by
+    /// [`Loader::find_ephemeris_kernel`], falling back to the built-in
+    /// analytic ephemeris when there is none. Both give states relative to
+    /// the solar-system barycenter.
     pub fn load_ephemeris(&self) -> Result<planetlib::Ephemeris> {
-        // This is a placeholder until we implement proper ephemeris calculations
-        Ok(planetlib::Ephemeris::new())
+        match self.find_ephemeris_kernel() {
+            Some(path) => self.load_ephemeris_from(path),
+            None => Ok(planetlib::Ephemeris::new()),
+        }
+    }
+
+    /// First JPL kernel among [`EPHEMERIS_KERNELS`] in the data directory
+    /// or the download cache, if any
+    ///
+    /// When replaying, only kernels pinned in the bundle are found.
+    pub fn find_ephemeris_kernel(&self) -> Option<std::path::PathBuf> {
+        let dirs: Vec<_> = self
+            .data_dir
+            .iter()
+            .cloned()
+            .chain(std::iter::once(self.cache.cache_dir()))
+            .collect();
+        EPHEMERIS_KERNELS
+            .iter()
+            .flat_map(|name| dirs.iter().map(move |dir| dir.join(name)))
+            .find_map(|path| match &self.replay {
+                Some(bundle) => bundle.pinned_copy(&path),
+                None => path.is_file().then_some(path),
+            })
+    }
+
+    /// Load a planetary ephemeris from a JPL DE kernel such as `de440s.bsp`
+    pub fn load_ephemeris_from<P: AsRef<Path>>(&self, path: P) -> Result<planetlib::Ephemeris> {
+        Ok(planetlib::Ephemeris::from_kernel(self.load_spk(path)?))
+    }
+
+    /// Open an SPK ephemeris kernel
+    pub fn load_spk<P: AsRef<Path>>(&self, path: P) -> Result<jplephem::SPK> {
+        let spk = jplephem::SPK::open(self.replay_path(path)?)?;
+        Ok(match &self.recorder {
+            Some(recorder) => spk.with_recorder(recorder.clone()),
+            None => spk,
+        })
     }
 
     /// Load a timescale for time conversions
     pub fn timescale(&self) -> time::Timescale {
         // For now, we return a default timescale with basic data
         // In the future, this could load delta_t data and leap second files
-        time::Timescale::default()
+        self.configure_timescale(match &self.replay {
+            Some(bundle) => bundle.timescale(),
+            None => time::Timescale::default(),
+        })
+    }
+
+    /// Load a timescale with the IERS leap seconds and daily UT1 - UTC
+    ///
+    /// Uses `Leap_Second.dat` and `finals2000A.all` from the data directory
+    /// when both are there, and otherwise fetches them as the cache policy
+    /// allows. The resulting Delta T table is exact from 1973 to a year ahead.
+    pub fn timescale_from_iers(&self) -> Result<time::Timescale> {
+        if let Some(bundle) = &self.replay {
+            return Ok(self.configure_timescale(bundle.timescale()));
+        }
+        let (leap_path, finals_path) = match self.local_iers() {
+            Some(paths) => paths,
+            None => data::fetch_iers(&self.cache)?,
+        };
+        self.iers_timescale_from(leap_path, finals_path)
+    }
+
+    /// [`Loader::timescale_from_iers`], downloading without blocking
+    #[cfg(feature = "async")]
+    pub async fn timescale_from_iers_async(&self) -> Result<time::Timescale> {
+        if let Some(bundle) = &self.replay {
+            return Ok(self.configure_timescale(bundle.timescale()));
+        }
+        let (leap_path, finals_path) = match self.local_iers() {
+            Some(paths) => paths,
+            None => data::fetch_iers_async(&self.cache).await?,
+        };
+        self.iers_timescale_from(leap_path, finals_path)
+    }
+
+    /// `Leap_Second.dat` and `finals2000A.all` from the data directory
+    fn local_iers(&self) -> Option<(std::path::PathBuf, std::path::PathBuf)> {
+        let dir = self.data_dir.as_ref()?;
+        let (leap, finals) = (dir.join("Leap_Second.dat"), dir.join("finals2000A.all"));
+        (leap.is_file() && finals.is_file()).then_some((leap, finals))
+    }
+
+    /// Record and parse the IERS files into a timescale
+    fn iers_timescale_from(
+        &self,
+        leap_path: std::path::PathBuf,
+        finals_path: std::path::PathBuf,
+    ) -> Result<time::Timescale> {
+        if let Some(recorder) = &self.recorder {
+            recorder.record_catalog_file("iers", &leap_path);
+            recorder.record_catalog_file("iers", &finals_path);
+        }
+
+        let ts = data::iers_timescale(
+            &std::fs::read_to_string(&leap_path)?,
+            &std::fs::read_to_string(&finals_path)?,
+        )?;
+        Ok(self.configure_timescale(ts))
+    }
+
+    /// Attach the recorder and apply the accuracy profile's Delta T model
+    fn configure_timescale(&self, ts: time::Timescale) -> time::Timescale {
+        let ts = match &self.recorder {
+            Some(recorder) => ts.with_recorder(recorder.clone()),
+            None => ts,
+        };
+        match self.accuracy.delta_t {
+            accuracy::DeltaTModel::Polynomial => ts.with_polynomial_delta_t(),
+            accuracy::DeltaTModel::Table => ts,
+        }
     }
 }
 
@@ -215,6 +759,224 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_loader_accuracy_profile() {
+        let loader = Loader::new();
+        assert_eq!(loader.accuracy(), &accuracy::AccuracyProfile::standard());
+
+        let fast = Loader::new().with_accuracy(accuracy::AccuracyProfile::fast());
+        assert_eq!(fast.accuracy().delta_t, accuracy::DeltaTModel::Polynomial);
+        assert!(!fast.timescale().has_delta_t_table());
+    }
+
+    #[test]
+    fn test_iers_timescale_follows_accuracy_profile() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(
+            dir.path().join("Leap_Second.dat"),
+            "    41317.0    1  1 1972       10\n    57754.0    1  1 2017       37\n",
+        )
+        .unwrap();
+        std::fs::write(
+            dir.path().join("finals2000A.all"),
+            "20 1 1 58849.00 I  0.076600 0.000030  0.282400 0.000030  I-0.1771554 0.0000066\n",
+        )
+        .unwrap();
+
+        let standard = Loader::new().with_data_dir(dir.path());
+        assert!(standard.timescale_from_iers().unwrap().has_delta_t_table());
+
+        let fast = standard.with_accuracy(accuracy::AccuracyProfile::fast());
+        let ts = fast.timescale_from_iers().unwrap();
+        assert!(!ts.has_delta_t_table());
+        assert_eq!(
+            ts.delta_t(2458849.5),
+            time::Timescale::default()
+                .with_polynomial_delta_t()
+                .delta_t(2458849.5)
+        );
+    }
+
+    #[test]
+    fn test_loader_replays_recorded_delta_t() {
+        let recorder = data::AccessRecorder::new();
+        let ts = Loader::new()
+            .with_recorder(recorder.clone())
+            .timescale()
+            .with_delta_t_table(
+                vec![2451545.0, 2451910.0, 2452275.0],
+                vec![63.8, 64.1, 64.3],
+            );
+        let recorded = ts.delta_t(2451600.0);
+
+        let dir = tempfile::tempdir().unwrap();
+        let manifest = recorder.export_bundle(dir.path()).unwrap();
+        assert_eq!(manifest.eop_entries.len(), 2);
+
+        let bundle = data::ReplayBundle::open(dir.path()).unwrap();
+        let replayed = Loader::new().with_replay(bundle).timescale();
+        assert_eq!(replayed.delta_t(2451600.0), recorded);
+    }
+
+    #[test]
+    fn test_replay_pins_leap_seconds_and_rejects_unpinned_files() {
+        let recorder = data::AccessRecorder::new();
+        // A table with one leap second more than the built-in one
+        let leap_seconds = data::parse_leap_seconds(
+            "41317.0 1 1 1972 10\n57754.0 1 1 2017 37\n61041.0 1 1 2026 38\n",
+        )
+        .unwrap();
+        let ts = time::Timescale::default()
+            .with_leap_seconds(
+                leap_seconds.iter().map(|l| l.jd_utc).collect(),
+                leap_seconds.iter().map(|l| l.tai_minus_utc).collect(),
+            )
+            .with_recorder(recorder.clone());
+        let t = ts.utc((2026, 6, 1));
+        assert_eq!(t.leap_seconds(), 38.0);
+
+        let dir = tempfile::tempdir().unwrap();
+        let manifest = recorder.export_bundle(dir.path()).unwrap();
+        assert_eq!(manifest.leap_seconds, leap_seconds);
+
+        let bundle = data::ReplayBundle::open(dir.path()).unwrap();
+        let loader = Loader::new().with_replay(bundle);
+        let replayed = loader.timescale().utc((2026, 6, 1));
+        assert_eq!(replayed.tai(), t.tai());
+
+        // Files outside the bundle are not read behind its back
+        let live = dir.path().join("live.csv");
+        std::fs::write(&live, "source_id,ra,dec,phot_g_mean_mag\n").unwrap();
+        assert!(matches!(
+            loader.load_gaia_catalog_from_file(&live, 10.0),
+            Err(StarfieldError::DataError(_))
+        ));
+        assert!(loader.load_spk(&live).is_err());
+        assert!(loader.resolve_catalog_path("gaia", &live).is_none());
+    }
+
+    #[test]
+    fn test_loader_merges_gaia_files_in_order() {
+        let header = "solution_id,source_id,ra,ra_error,dec,dec_error,parallax,parallax_error,pmra,pmdec,phot_g_mean_flux,phot_g_mean_mag,phot_bp_mean_mag,phot_variable_flag,l,b,ecl_lon,ecl_lat";
+        let dir = tempfile::tempdir().unwrap();
+        let files: Vec<_> = (0..4)
+            .map(|i| {
+                let path = dir.path().join(format!("gaia_{}.csv", i));
+                // Every file repeats source 1, with its own RA
+                let rows = format!(
+                    "{}\n1,1,{}.0,0.1,5.0,0.1,,,,,1e6,8.0,8.5,NOT_AVAILABLE,0,0,0,0\n1,{},10.0,0.1,5.0,0.1,,,,,1e6,8.0,8.5,NOT_AVAILABLE,0,0,0,0\n",
+                    header,
+                    i,
+                    100 + i
+                );
+                std::fs::write(&path, rows).unwrap();
+                path
+            })
+            .collect();
+
+        let events = std::sync::Arc::new(std::sync::Mutex::new(Vec::new()));
+        let sink = events.clone();
+        let loader = Loader::new().with_progress(move |event| sink.lock().unwrap().push(event));
+        #[cfg(feature = "parallel")]
+        let loader = loader.with_threads(2);
+        let catalog = loader.load_and_merge_gaia_files(&files, 12.0).unwrap();
+        assert_eq!(catalog.len(), 5);
+        assert_eq!(catalog.get_star(1).unwrap().ra, 0.0);
+
+        // Each file is announced and then reported with its star count
+        let events = events.lock().unwrap();
+        assert_eq!(events.len(), 8);
+        for (i, file) in files.iter().enumerate() {
+            assert!(events.contains(&data::ProgressEvent::FileStarted {
+                path: file.clone(),
+                index: i,
+                total: 4,
+            }));
+            assert!(events.contains(&data::ProgressEvent::FileLoaded {
+                path: file.clone(),
+                stars: 2,
+            }));
+        }
+    }
+
+    #[test]
+    fn test_loader_finds_ephemeris_kernel() {
+        use crate::jplephem::daf::testing::build;
+        use crate::jplephem::spk::testing::chebyshev_array;
+
+        let dir = tempfile::tempdir().unwrap();
+        let loader = Loader::new().with_data_dir(dir.path());
+        assert!(loader.find_ephemeris_kernel().is_none());
+
+        let array = chebyshev_array(4, constants::J2000, 32.0, 2);
+        std::fs::write(
+            dir.path().join("de421.bsp"),
+            build("DAF/SPK", 2, 6, &[array], 3),
+        )
+        .unwrap();
+
+        let path = loader.find_ephemeris_kernel().unwrap();
+        assert_eq!(path, dir.path().join("de421.bsp"));
+        let eph = loader.load_ephemeris().unwrap();
+        assert!(eph.kernel().is_some());
+        assert!(eph
+            .get_state(planetlib::Body::Mars, constants::J2000 + 1.0)
+            .is_ok());
+    }
+
+    #[test]
+    fn test_loader_offline_cache() {
+        let dir = tempfile::tempdir().unwrap();
+        let loader = Loader::new().with_cache_dir(dir.path()).offline(true);
+        assert!(loader.timescale_from_iers().is_err());
+        assert!(loader.load_deep_sky_catalog().is_err());
+        assert_eq!(loader.cache_size().unwrap(), 0);
+
+        let ngc = dir.path().join("NGC.csv");
+        std::fs::write(
+            &ngc,
+            "Name;Type;RA;Dec;MajAx;MinAx;V-Mag;M;Common names\n\
+             NGC0891;G;02:22:33.41;+42:20:56.9;13.5;2.5;9.9;;\n",
+        )
+        .unwrap();
+        let catalog = loader.load_deep_sky_catalog().unwrap();
+        assert!(catalog.get("NGC 891").is_some());
+        assert!(catalog.get("M31").is_some());
+
+        let size = std::fs::metadata(&ngc).unwrap().len();
+        assert_eq!(loader.cache_size().unwrap(), size);
+        assert_eq!(loader.clear_cache().unwrap(), size);
+        assert!(loader.load_deep_sky_catalog().is_err());
+    }
+
+    #[cfg(feature = "async")]
+    #[tokio::test]
+    async fn test_loader_async_downloads() {
+        fn assert_send<T: Send>(future: T) -> T {
+            future
+        }
+
+        let dir = tempfile::tempdir().unwrap();
+        let loader = Loader::new().with_cache_dir(dir.path()).offline(true);
+        // Services spawn these onto multi-threaded runtimes
+        assert!(assert_send(loader.timescale_from_iers_async())
+            .await
+            .is_err());
+        assert!(assert_send(loader.load_hipparcos_catalog_async(6.0))
+            .await
+            .is_err());
+
+        std::fs::write(
+            dir.path().join("NGC.csv"),
+            "Name;Type;RA;Dec;MajAx;MinAx;V-Mag;M;Common names\n",
+        )
+        .unwrap();
+        let catalog = assert_send(loader.load_deep_sky_catalog_async())
+            .await
+            .unwrap();
+        assert_eq!(catalog.len(), 110);
+    }
+
     #[test]
     fn test_synthetic_hipparcos() {
         // Instead of downloading the catalog, we'll use a synthetic one for testing
@@ -1 +1,280 @@
-//! nutationlib module\n\n// Placeholder for nutationlib implementations
+//! Nutation of the Earth's axis
+//!
+//! Implements the IAU 2000B model of McCarthy & Luzum (2003): the 77
+//! largest luni-solar terms of IAU 2000A plus fixed offsets standing in for
+//! the planetary terms, good to a milliarcsecond between 1995 and 2050.
+//! The full 1365-term IAU 2000A series is not provided; its extra
+//! precision is below what the rest of the crate's reductions keep.
+//!
+//! The coefficients and fundamental arguments follow SOFA's `nut00b`.
+//! [`NutationModel`] selects how much of the series to sum, so that the
+//! reductions of an [`crate::accuracy::AccuracyProfile`] can trade
+//! precision for speed.
+//!
+//! ```
+//! use starfield::accuracy::NutationModel;
+//! use starfield::nutationlib::{nutation_angles, nutation_matrix};
+//!
+//! let jd_tt = 2_460_000.5;
+//! let (dpsi, deps) = nutation_angles(jd_tt, NutationModel::Iau2000B);
+//! assert!(dpsi.abs() < 20.0_f64.to_radians() / 3600.0);
+//!
+//! // Mean-of-date vectors to true-of-date
+//! let n = nutation_matrix(jd_tt, NutationModel::Iau2000B);
+//! # let _ = (deps, n);
+//! ```
+
+use crate::accuracy::NutationModel;
+use crate::constants::{ASEC2RAD, J2000, TAU};
+use crate::framelib::{rot_x, rot_z};
+use crate::precessionlib::{compute_precession, mean_obliquity};
+use nalgebra::Matrix3;
+
+/// Arcseconds in a full turn
+const TURN_ARCSEC: f64 = 1_296_000.0;
+
+/// Units of the series coefficients (0.1 microarcsecond) in radians
+const COEFFICIENT_UNIT: f64 = ASEC2RAD / 1e7;
+
+/// Nutation in longitude standing in for the planetary terms (-0.135 mas)
+const PLANETARY_DPSI: f64 = -0.135e-3 * ASEC2RAD;
+
+/// Nutation in obliquity standing in for the planetary terms (+0.388 mas)
+const PLANETARY_DEPS: f64 = 0.388e-3 * ASEC2RAD;
+
+/// Terms summed by [`NutationModel::Low`], which leaves errors of a few
+/// tenths of an arcsecond
+const LOW_PRECISION_TERMS: usize = 10;
+
+/// One term of the luni-solar series: multipliers of l, l', F, D and Ω,
+/// then the longitude coefficients (sin, sin·t, cos) and the obliquity
+/// coefficients (cos, cos·t, sin), in units of 0.1 µas
+type Term = ([i8; 5], [f64; 3], [f64; 3]);
+
+/// The IAU 2000B luni-solar series, largest terms first
+#[rustfmt::skip]
+const IAU2000B_TERMS: [Term; 77] = [
+    ([ 0, 0, 0, 0, 1], [-172064161.0, -174666.0,  33386.0], [92052331.0,  9086.0, 15377.0]),
+    ([ 0, 0, 2,-2, 2], [ -13170906.0,   -1675.0, -13696.0], [ 5730336.0, -3015.0, -4587.0]),
+    ([ 0, 0, 2, 0, 2], [  -2276413.0,    -234.0,   2796.0], [  978459.0,  -485.0,  1374.0]),
+    ([ 0, 0, 0, 0, 2], [   2074554.0,     207.0,   -698.0], [ -897492.0,   470.0,  -291.0]),
+    ([ 0, 1, 0, 0, 0], [   1475877.0,   -3633.0,  11817.0], [   73871.0,  -184.0, -1924.0]),
+    ([ 0, 1, 2,-2, 2], [   -516821.0,    1226.0,   -524.0], [  224386.0,  -677.0,  -174.0]),
+    ([ 1, 0, 0, 0, 0], [    711159.0,      73.0,   -872.0], [   -6750.0,     0.0,   358.0]),
+    ([ 0, 0, 2, 0, 1], [   -387298.0,    -367.0,    380.0], [  200728.0,    18.0,   318.0]),
+    ([ 1, 0, 2, 0, 2], [   -301461.0,     -36.0,    816.0], [  129025.0,   -63.0,   367.0]),
+    ([ 0,-1, 2,-2, 2], [    215829.0,    -494.0,    111.0], [  -95929.0,   299.0,   132.0]),
+    ([ 0, 0, 2,-2, 1], [    128227.0,     137.0,    181.0], [  -68982.0,    -9.0,    39.0]),
+    ([-1, 0, 2, 0, 2], [    123457.0,      11.0,     19.0], [  -53311.0,    32.0,    -4.0]),
+    ([-1, 0, 0, 2, 0], [    156994.0,      10.0,   -168.0], [   -1235.0,     0.0,    82.0]),
+    ([ 1, 0, 0, 0, 1], [     63110.0,      63.0,     27.0], [  -33228.0,     0.0,    -9.0]),
+    ([-1, 0, 0, 0, 1], [    -57976.0,     -63.0,   -189.0], [   31429.0,     0.0,   -75.0]),
+    ([-1, 0, 2, 2, 2], [    -59641.0,     -11.0,    149.0], [   25543.0,   -11.0,    66.0]),
+    ([ 1, 0, 2, 0, 1], [    -51613.0,     -42.0,    129.0], [   26366.0,     0.0,    78.0]),
+    ([-2, 0, 2, 0, 1], [     45893.0,      50.0,     31.0], [  -24236.0,   -10.0,    20.0]),
+    ([ 0, 0, 0, 2, 0], [     63384.0,      11.0,   -150.0], [   -1220.0,     0.0,    29.0]),
+    ([ 0, 0, 2, 2, 2], [    -38571.0,      -1.0,    158.0], [   16452.0,   -11.0,    68.0]),
+    ([ 0,-2, 2,-2, 2], [     32481.0,       0.0,      0.0], [  -13870.0,     0.0,     0.0]),
+    ([-2, 0, 0, 2, 0], [    -47722.0,       0.0,    -18.0], [     477.0,     0.0,   -25.0]),
+    ([ 2, 0, 2, 0, 2], [    -31046.0,      -1.0,    131.0], [   13238.0,   -11.0,    59.0]),
+    ([ 1, 0, 2,-2, 2], [     28593.0,       0.0,     -1.0], [  -12338.0,    10.0,    -3.0]),
+    ([-1, 0, 2, 0, 1], [     20441.0,      21.0,     10.0], [  -10758.0,     0.0,    -3.0]),
+    ([ 2, 0, 0, 0, 0], [     29243.0,       0.0,    -74.0], [    -609.0,     0.0,    13.0]),
+    ([ 0, 0, 2, 0, 0], [     25887.0,       0.0,    -66.0], [    -550.0,     0.0,    11.0]),
+    ([ 0, 1, 0, 0, 1], [    -14053.0,     -25.0,     79.0], [    8551.0,    -2.0,   -45.0]),
+    ([-1, 0, 0, 2, 1], [     15164.0,      10.0,     11.0], [   -8001.0,     0.0,    -1.0]),
+    ([ 0, 2, 2,-2, 2], [    -15794.0,      72.0,    -16.0], [    6850.0,   -42.0,    -5.0]),
+    ([ 0, 0,-2, 2, 0], [     21783.0,       0.0,     13.0], [    -167.0,     0.0,    13.0]),
+    ([ 1, 0, 0,-2, 1], [    -12873.0,     -10.0,    -37.0], [    6953.0,     0.0,   -14.0]),
+    ([ 0,-1, 0, 0, 1], [    -12654.0,      11.0,     63.0], [    6415.0,     0.0,    26.0]),
+    ([-1, 0, 2, 2, 1], [    -10204.0,       0.0,     25.0], [    5222.0,     0.0,    15.0]),
+    ([ 0, 2, 0, 0, 0], [     16707.0,     -85.0,    -10.0], [     168.0,    -1.0,    10.0]),
+    ([ 1, 0, 2, 2, 2], [     -7691.0,       0.0,     44.0], [    3268.0,     0.0,    19.0]),
+    ([-2, 0, 2, 0, 0], [    -11024.0,       0.0,    -14.0], [     104.0,     0.0,     2.0]),
+    ([ 0, 1, 2, 0, 2], [      7566.0,     -21.0,    -11.0], [   -3250.0,     0.0,    -5.0]),
+    ([ 0, 0, 2, 2, 1], [     -6637.0,     -11.0,     25.0], [    3353.0,     0.0,    14.0]),
+    ([ 0,-1, 2, 0, 2], [     -7141.0,      21.0,      8.0], [    3070.0,     0.0,     4.0]),
+    ([ 0, 0, 0, 2, 1], [     -6302.0,     -11.0,      2.0], [    3272.0,     0.0,     4.0]),
+    ([ 1, 0, 2,-2, 1], [      5800.0,      10.0,      2.0], [   -3045.0,     0.0,    -1.0]),
+    ([ 2, 0, 2,-2, 2], [      6443.0,       0.0,     -7.0], [   -2768.0,     0.0,    -4.0]),
+    ([-2, 0, 0, 2, 1], [     -5774.0,     -11.0,    -15.0], [    3041.0,     0.0,    -5.0]),
+    ([ 2, 0, 2, 0, 1], [     -5350.0,       0.0,     21.0], [    2695.0,     0.0,    12.0]),
+    ([ 0,-1, 2,-2, 1], [     -4752.0,     -11.0,     -3.0], [    2719.0,     0.0,    -3.0]),
+    ([ 0, 0, 0,-2, 1], [     -4940.0,     -11.0,    -21.0], [    2720.0,     0.0,    -9.0]),
+    ([-1,-1, 0, 2, 0], [      7350.0,       0.0,     -8.0], [     -51.0,     0.0,     4.0]),
+    ([ 2, 0, 0,-2, 1], [      4065.0,       0.0,      6.0], [   -2206.0,     0.0,     1.0]),
+    ([ 1, 0, 0, 2, 0], [      6579.0,       0.0,    -24.0], [    -199.0,     0.0,     2.0]),
+    ([ 0, 1, 2,-2, 1], [      3579.0,       0.0,      5.0], [   -1900.0,     0.0,     1.0]),
+    ([ 1,-1, 0, 0, 0], [      4725.0,       0.0,     -6.0], [     -41.0,     0.0,     3.0]),
+    ([-2, 0, 2, 0, 2], [     -3075.0,       0.0,     -2.0], [    1313.0,     0.0,    -1.0]),
+    ([ 3, 0, 2, 0, 2], [     -2904.0,       0.0,     15.0], [    1233.0,     0.0,     7.0]),
+    ([ 0,-1, 0, 2, 0], [      4348.0,       0.0,    -10.0], [     -81.0,     0.0,     2.0]),
+    ([ 1,-1, 2, 0, 2], [     -2878.0,       0.0,      8.0], [    1232.0,     0.0,     4.0]),
+    ([ 0, 0, 0, 1, 0], [     -4230.0,       0.0,      5.0], [     -20.0,     0.0,    -2.0]),
+    ([-1,-1, 2, 2, 2], [     -2819.0,       0.0,      7.0], [    1207.0,     0.0,     3.0]),
+    ([-1, 0, 2, 0, 0], [     -4056.0,       0.0,      5.0], [      40.0,     0.0,    -2.0]),
+    ([ 0,-1, 2, 2, 2], [     -2647.0,       0.0,     11.0], [    1129.0,     0.0,     5.0]),
+    ([-2, 0, 0, 0, 1], [     -2294.0,       0.0,    -10.0], [    1266.0,     0.0,    -4.0]),
+    ([ 1, 1, 2, 0, 2], [      2481.0,       0.0,     -7.0], [   -1062.0,     0.0,    -3.0]),
+    ([ 2, 0, 0, 0, 1], [      2179.0,       0.0,     -2.0], [   -1129.0,     0.0,    -2.0]),
+    ([-1, 1, 0, 1, 0], [      3276.0,       0.0,      1.0], [      -9.0,     0.0,     0.0]),
+    ([ 1, 1, 0, 0, 0], [     -3389.0,       0.0,      5.0], [      35.0,     0.0,    -2.0]),
+    ([ 1, 0, 2, 0, 0], [      3339.0,       0.0,    -13.0], [    -107.0,     0.0,     1.0]),
+    ([-1, 0, 2,-2, 1], [     -1987.0,       0.0,     -6.0], [    1073.0,     0.0,    -2.0]),
+    ([ 1, 0, 0, 0, 2], [     -1981.0,       0.0,      0.0], [     854.0,     0.0,     0.0]),
+    ([-1, 0, 0, 1, 0], [      4026.0,       0.0,   -353.0], [    -553.0,     0.0,  -139.0]),
+    ([ 0, 0, 2, 1, 2], [      1660.0,       0.0,     -5.0], [    -710.0,     0.0,    -2.0]),
+    ([-1, 0, 2, 4, 2], [     -1521.0,       0.0,      9.0], [     647.0,     0.0,     4.0]),
+    ([-1, 1, 0, 1, 1], [      1314.0,       0.0,      0.0], [    -700.0,     0.0,     0.0]),
+    ([ 0,-2, 2,-2, 1], [     -1283.0,       0.0,      0.0], [     672.0,     0.0,     0.0]),
+    ([ 1, 0, 2, 2, 1], [     -1331.0,       0.0,      8.0], [     663.0,     0.0,     4.0]),
+    ([-2, 0, 2, 2, 2], [      1383.0,       0.0,     -2.0], [    -594.0,     0.0,    -2.0]),
+    ([-1, 0, 0, 0, 2], [      1405.0,       0.0,      4.0], [    -610.0,     0.0,     2.0]),
+    ([ 1, 1, 2,-2, 2], [      1290.0,       0.0,      0.0], [    -556.0,     0.0,     0.0]),
+];
+
+/// Delaunay arguments l, l', F, D and Ω in radians at `t` Julian
+/// centuries from J2000 (Simon et al. 1994, linear terms as used by
+/// IAU 2000B)
+fn fundamental_arguments(t: f64) -> [f64; 5] {
+    let angle = |at_epoch: f64, rate: f64| ((at_epoch + rate * t) % TURN_ARCSEC) * ASEC2RAD % TAU;
+    [
+        angle(485_868.249_036, 1_717_915_923.217_8),
+        angle(1_287_104.793_05, 129_596_581.048_1),
+        angle(335_779.526_232, 1_739_527_262.847_8),
+        angle(1_072_260.703_69, 1_602_961_601.209_0),
+        angle(450_160.398_036, -6_962_890.543_1),
+    ]
+}
+
+/// Sum the first `terms` terms of the series, without the planetary offsets
+fn luni_solar(jd_tt: f64, terms: usize) -> (f64, f64) {
+    let t = (jd_tt - J2000) / 36525.0;
+    let arguments = fundamental_arguments(t);
+
+    let (mut dpsi, mut deps) = (0.0, 0.0);
+    // Smallest terms first, to limit rounding
+    for (multipliers, longitude, obliquity) in IAU2000B_TERMS[..terms].iter().rev() {
+        let argument: f64 = multipliers
+            .iter()
+            .zip(arguments)
+            .map(|(&n, angle)| n as f64 * angle)
+            .sum();
+        let (sin, cos) = argument.sin_cos();
+        dpsi += (longitude[0] + longitude[1] * t) * sin + longitude[2] * cos;
+        deps += (obliquity[0] + obliquity[1] * t) * cos + obliquity[2] * sin;
+    }
+    (dpsi * COEFFICIENT_UNIT, deps * COEFFICIENT_UNIT)
+}
+
+/// Nutation in longitude and obliquity (Δψ, Δε) in radians by IAU 2000B
+///
+/// # Arguments
+///
+/// * `jd_tt` - Julian date in TT (TDB may be used with negligible error)
+pub fn iau2000b(jd_tt: f64) -> (f64, f64) {
+    let (dpsi, deps) = luni_solar(jd_tt, IAU2000B_TERMS.len());
+    (dpsi + PLANETARY_DPSI, deps + PLANETARY_DEPS)
+}
+
+/// Nutation in longitude and obliquity (Δψ, Δε) in radians, summing as much
+/// of the series as `model` asks for
+pub fn nutation_angles(jd_tt: f64, model: NutationModel) -> (f64, f64) {
+    match model {
+        NutationModel::None => (0.0, 0.0),
+        NutationModel::Low => luni_solar(jd_tt, LOW_PRECISION_TERMS),
+        NutationModel::Iau2000B => iau2000b(jd_tt),
+    }
+}
+
+/// True obliquity of the ecliptic in radians: the mean obliquity plus the
+/// nutation in obliquity
+pub fn true_obliquity(jd_tt: f64, model: NutationModel) -> f64 {
+    mean_obliquity(jd_tt) + nutation_angles(jd_tt, model).1
+}
+
+/// Equation of the equinoxes in radians: the right ascension of the mean
+/// equinox measured from the true one, Δψ cos ε
+///
+/// The complementary terms of the IAU 2000 definition, under 3
+/// milliarcseconds, are omitted. Add to mean sidereal time for apparent
+/// sidereal time.
+pub fn equation_of_the_equinoxes(jd_tt: f64, model: NutationModel) -> f64 {
+    nutation_angles(jd_tt, model).0 * mean_obliquity(jd_tt).cos()
+}
+
+/// Nutation matrix from the mean equator and equinox of date to the true
+/// equator and equinox of date
+///
+/// Multiply a mean-of-date vector by the returned matrix to obtain its
+/// true-of-date coordinates; the transpose performs the inverse rotation.
+pub fn nutation_matrix(jd_tt: f64, model: NutationModel) -> Matrix3<f64> {
+    let (dpsi, deps) = nutation_angles(jd_tt, model);
+    let epsilon = mean_obliquity(jd_tt);
+    rot_x(epsilon + deps) * rot_z(dpsi) * rot_x(-epsilon)
+}
+
+/// Combined precession and nutation from J2000 to the true equator and
+/// equinox of date
+pub fn precession_nutation_matrix(jd_tt: f64, model: NutationModel) -> Matrix3<f64> {
+    nutation_matrix(jd_tt, model) * compute_precession(jd_tt)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use approx::assert_relative_eq;
+    use nalgebra::Vector3;
+
+    #[test]
+    fn test_iau2000b_matches_sofa() {
+        // SOFA's test case for iauNut00b
+        let (dpsi, deps) = iau2000b(2_400_000.5 + 53_736.0);
+        assert_relative_eq!(dpsi, -0.963_255_229_114_836_3e-5, epsilon = 1e-13);
+        assert_relative_eq!(deps, 0.406_319_710_662_115_9e-4, epsilon = 1e-13);
+    }
+
+    #[test]
+    fn test_truncated_models() {
+        let half_arcsec = 0.5 * ASEC2RAD;
+        for i in 0..40 {
+            let jd = J2000 + i as f64 * 250.0;
+            let (dpsi, deps) = iau2000b(jd);
+            let (low_dpsi, low_deps) = nutation_angles(jd, NutationModel::Low);
+            assert!((dpsi - low_dpsi).abs() < half_arcsec);
+            assert!((deps - low_deps).abs() < half_arcsec);
+            assert!(dpsi.abs() < 20.0 * ASEC2RAD && deps.abs() < 11.0 * ASEC2RAD);
+        }
+        assert_eq!(nutation_angles(J2000, NutationModel::None), (0.0, 0.0));
+        assert_relative_eq!(
+            nutation_matrix(J2000, NutationModel::None),
+            Matrix3::identity(),
+            epsilon = 1e-15
+        );
+    }
+
+    #[test]
+    fn test_nutation_matrix_moves_the_equinox() {
+        let jd = 2_451_545.0 + 3_000.0;
+        let (dpsi, deps) = iau2000b(jd);
+        let n = nutation_matrix(jd, NutationModel::Iau2000B);
+
+        // The mean equinox lies at right ascension Δψ cos ε on the true
+        // equator, and the true pole is tilted from the mean one by about
+        // Δε, Δψ sin ε
+        let equinox = n * Vector3::x();
+        assert_relative_eq!(
+            equinox.y.atan2(equinox.x),
+            equation_of_the_equinoxes(jd, NutationModel::Iau2000B),
+            max_relative = 1e-4
+        );
+        let pole = n.transpose() * Vector3::z();
+        let epsilon = mean_obliquity(jd);
+        assert_relative_eq!(
+            pole.angle(&Vector3::z()),
+            deps.hypot(dpsi * epsilon.sin()),
+            max_relative = 1e-4
+        );
+        assert_relative_eq!(true_obliquity(jd, NutationModel::Iau2000B), epsilon + deps);
+    }
+}
@@ -0,0 +1,114 @@
+//! Observers located on or moving over the Earth's surface
+
+pub mod observe;
+pub mod trajectory;
+
+pub use observe::ObserverAt;
+pub use trajectory::Trajectory;
+
+use crate::constants::DEG2RAD;
+use crate::earthlib::{altaz_from_terrestrial, terra, terrestrial_to_celestial};
+use crate::framelib::Frame;
+use crate::positions::trace;
+use crate::time::Time;
+use nalgebra::Vector3;
+use serde::{Deserialize, Serialize};
+
+/// A geodetic location on the Earth's surface
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct GeographicLocation {
+    /// Geodetic latitude in degrees, positive north
+    pub latitude_deg: f64,
+    /// Longitude in degrees, positive east
+    pub longitude_deg: f64,
+    /// Height above the ellipsoid in metres
+    pub elevation_m: f64,
+}
+
+impl GeographicLocation {
+    /// Create a location from geodetic latitude and longitude in degrees and
+    /// elevation in metres
+    pub fn new(latitude_deg: f64, longitude_deg: f64, elevation_m: f64) -> Self {
+        Self {
+            latitude_deg,
+            longitude_deg,
+            elevation_m,
+        }
+    }
+
+    /// Earth-fixed position of the site in AU
+    pub fn itrs_position(&self) -> Vector3<f64> {
+        terra(
+            self.latitude_deg * DEG2RAD,
+            self.longitude_deg * DEG2RAD,
+            self.elevation_m,
+        )
+    }
+
+    /// Geocentric position of the site in J2000 equatorial axes, in AU
+    pub fn geocentric_position(&self, t: &Time) -> Vector3<f64> {
+        let rotation = terrestrial_to_celestial(t);
+        let itrs = self.itrs_position();
+        trace::record_rotation(
+            "Earth rotation and precession (ITRS to J2000)",
+            Frame::Itrs,
+            Frame::Fk5J2000,
+            &rotation,
+            &itrs,
+        );
+        rotation * itrs
+    }
+
+    /// Altitude and azimuth in degrees of a target at a geocentric J2000
+    /// position (AU)
+    ///
+    /// Topocentric parallax is applied, so nearby targets like the Moon come
+    /// out correctly; atmospheric refraction is not.
+    pub fn altaz(&self, geocentric: &Vector3<f64>, t: &Time) -> (f64, f64) {
+        let to_terrestrial = terrestrial_to_celestial(t).transpose();
+        trace::record_rotation(
+            "Earth rotation and precession (J2000 to ITRS)",
+            Frame::Fk5J2000,
+            Frame::Itrs,
+            &to_terrestrial,
+            geocentric,
+        );
+        let terrestrial = to_terrestrial * geocentric;
+        let topocentric = terrestrial - self.itrs_position();
+        trace::record_correction(
+            "topocentric parallax",
+            Frame::Itrs,
+            &terrestrial,
+            &topocentric,
+        );
+        altaz_from_terrestrial(&topocentric, self.latitude_deg, self.longitude_deg)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::time::Timescale;
+    use approx::assert_relative_eq;
+
+    #[test]
+    fn test_celestial_pole_altitude_equals_latitude() {
+        let ts = Timescale::default();
+        let t = ts.utc((2024, 6, 1, 3, 0, 0.0));
+        let site = GeographicLocation::new(51.48, 0.0, 46.0);
+
+        // A very distant target along the J2000 pole, precessed slightly
+        let (alt, az) = site.altaz(&(Vector3::z() * 1e6), &t);
+        assert_relative_eq!(alt, 51.48, epsilon = 0.2);
+        assert!(!(1.0..359.0).contains(&az));
+    }
+
+    #[test]
+    fn test_site_radius() {
+        let ts = Timescale::default();
+        let t = ts.utc((2024, 6, 1));
+        let site = GeographicLocation::new(-30.24, -70.74, 2_700.0);
+        let r_km = site.geocentric_position(&t).norm() * crate::constants::AU_KM;
+        assert!((6_372.0..6_377.0).contains(&r_km), "{}", r_km);
+    }
+}
@@ -0,0 +1,362 @@
+//! Astrometric, apparent and topocentric positions, skyfield style
+//!
+//! An observer is placed at a time with [`GeographicLocation::at`] (or at
+//! the Earth's centre with [`ObserverAt::geocenter`]) and then observes a
+//! body:
+//!
+//! ```
+//! use starfield::observers::GeographicLocation;
+//! use starfield::planetlib::{Body, Ephemeris};
+//! use starfield::positions::icrf::IcrfVector;
+//! use starfield::time::Timescale;
+//!
+//! let ts = Timescale::default();
+//! let eph = Ephemeris::new();
+//! let greenwich = GeographicLocation::new(51.4769, -0.0005, 46.0);
+//!
+//! let t = ts.utc((2024, 3, 1, 22, 0, 0.0));
+//! let apparent = greenwich.at(&eph, &t)?.observe(Body::Jupiter)?.apparent(&eph)?;
+//! let (ra, dec, distance) = apparent.radec();
+//! let (alt, az) = apparent.altaz()?;
+//! # Ok::<(), starfield::planetlib::PlanetError>(())
+//! ```
+//!
+//! Observations are the [`crate::positions::icrf`] types, and
+//! [`IcrfVector`](crate::positions::icrf::IcrfVector) is needed in scope
+//! for `radec`:
+//!
+//! * [`Astrometric`] positions are corrected for light time only and are
+//!   comparable with star catalog positions.
+//! * [`Apparent`](crate::positions::icrf::Apparent) positions add
+//!   gravitational deflection by the Sun and the aberration due to the
+//!   observer's velocity, including the Earth's rotation for a surface
+//!   observer.
+//!
+//! [`ObserverAt::astrometric_radec_of`] runs the chain backwards, reducing
+//! an observed altitude and azimuth to an astrometric position.
+//!
+//! Nutation is neglected in
+//! [`Apparent::altaz`](crate::positions::icrf::Apparent::altaz).
+
+use super::{GeographicLocation, Trajectory};
+use crate::constants::RAD2DEG;
+use crate::earthlib::{transform_state, StateDirection};
+use crate::framelib::{Frame, Horizontal, HorizontalFrame};
+use crate::planetlib::{Body, Ephemeris, PlanetError};
+use crate::positions::icrf::{deflect_and_aberrate, Astrometric, Barycentric};
+use crate::positions::Position;
+use crate::time::Time;
+use nalgebra::Vector3;
+
+/// Convergence threshold of the apparent-to-astrometric inversion, as a
+/// unit-vector difference (about 0.2 µas)
+const INVERSE_TOLERANCE: f64 = 1e-12;
+
+/// Iteration cap for the inversion, which normally converges in three
+const MAX_INVERSE_ITERATIONS: usize = 10;
+
+/// Right ascension (degrees in [0, 360)), declination (degrees) and length
+fn radec_of(v: &Vector3<f64>) -> (f64, f64, f64) {
+    let r = v.norm();
+    let ra = (v.y.atan2(v.x) * RAD2DEG).rem_euclid(360.0);
+    let dec = (v.z / r).asin() * RAD2DEG;
+    (ra, dec, r)
+}
+
+/// An observer's position and velocity at a moment
+#[derive(Debug, Clone)]
+pub struct ObserverAt<'a> {
+    ephemeris: &'a Ephemeris,
+    location: Option<GeographicLocation>,
+    time: Time,
+    /// Position in AU in the ephemeris' origin, ICRS axes
+    position: Vector3<f64>,
+    /// Velocity in AU/day
+    velocity: Vector3<f64>,
+}
+
+impl GeographicLocation {
+    /// Place an observer at this site at time `t`
+    pub fn at<'a>(
+        &self,
+        ephemeris: &'a Ephemeris,
+        t: &Time,
+    ) -> Result<ObserverAt<'a>, PlanetError> {
+        let mut observer = ObserverAt::geocenter(ephemeris, t)?;
+        let (_, velocity) = transform_state(
+            &self.itrs_position(),
+            &Vector3::zeros(),
+            t,
+            StateDirection::ItrsToGcrs,
+        );
+
+        observer.location = Some(*self);
+        observer.position += self.geocentric_position(t);
+        observer.velocity += velocity;
+        Ok(observer)
+    }
+}
+
+impl Trajectory {
+    /// Place an observer wherever the trajectory is at time `t`, moving with
+    /// the platform and the Earth's rotation
+    pub fn at<'a>(
+        &self,
+        ephemeris: &'a Ephemeris,
+        t: &Time,
+    ) -> Result<ObserverAt<'a>, PlanetError> {
+        if self.is_fixed() {
+            return self.location_at(t).at(ephemeris, t);
+        }
+        let mut observer = ObserverAt::geocenter(ephemeris, t)?;
+        observer.location = Some(self.location_at(t));
+        observer.position += self.geocentric_position(t);
+        observer.velocity += self.geocentric_velocity(t);
+        Ok(observer)
+    }
+}
+
+impl<'a> ObserverAt<'a> {
+    /// Place an observer at the Earth's centre at time `t`
+    pub fn geocenter(ephemeris: &'a Ephemeris, t: &Time) -> Result<Self, PlanetError> {
+        let earth = ephemeris.get_state(Body::Earth, t.tdb())?;
+        Ok(Self {
+            ephemeris,
+            location: None,
+            time: t.clone(),
+            position: earth.position.coords,
+            velocity: earth.velocity,
+        })
+    }
+
+    /// Site of the observer at the time of observation, or `None` at the
+    /// geocenter
+    pub fn location(&self) -> Option<&GeographicLocation> {
+        self.location.as_ref()
+    }
+
+    /// Time of the observation
+    pub fn time(&self) -> &Time {
+        &self.time
+    }
+
+    /// Observer position in AU, ICRS axes
+    pub fn position(&self) -> &Vector3<f64> {
+        &self.position
+    }
+
+    /// Observer velocity in AU/day, ICRS axes
+    pub fn velocity(&self) -> &Vector3<f64> {
+        &self.velocity
+    }
+
+    /// The observer as a position from the ephemeris origin
+    pub fn barycentric(&self) -> Barycentric {
+        Barycentric {
+            position: Position::new(self.position, Frame::Icrs),
+            velocity: self.velocity,
+            time: self.time.clone(),
+            target: None,
+            location: self.location,
+        }
+    }
+
+    /// Light-time corrected position of `body` as seen by this observer
+    pub fn observe(&self, body: Body) -> Result<Astrometric, PlanetError> {
+        if body == Body::Earth {
+            return Err(PlanetError::DataError(
+                "an Earth-based observer cannot observe the Earth".to_string(),
+            ));
+        }
+        self.barycentric().observe(self.ephemeris, body)
+    }
+
+    /// Astrometric right ascension and declination in degrees of a distant
+    /// source observed at `observed` altitude and azimuth
+    ///
+    /// This inverts the chain behind [`Astrometric::apparent`] and
+    /// [`HorizontalFrame::from_equatorial`]: refraction is removed with
+    /// `frame`'s model, then deflection and aberration (annual and diurnal)
+    /// are removed by iterating the forward correction until it reproduces
+    /// the observed direction. `frame` must be for this observer's site and
+    /// time. Use it to reduce measurements from alt-az instruments to
+    /// catalog-comparable positions.
+    pub fn astrometric_radec_of(
+        &self,
+        observed: &Horizontal,
+        frame: &HorizontalFrame,
+    ) -> Result<(f64, f64), PlanetError> {
+        if self.location.as_ref() != Some(frame.location()) {
+            return Err(PlanetError::DataError(
+                "the horizontal frame must be for the observer's site".to_string(),
+            ));
+        }
+
+        let target = frame.to_equatorial(observed);
+        let (sin_dec, cos_dec) = target.dec.sin_cos();
+        let (sin_ra, cos_ra) = target.ra.sin_cos();
+        let target = Vector3::new(cos_dec * cos_ra, cos_dec * sin_ra, sin_dec);
+        let sun = self.sun_position(0.0);
+
+        let mut astrometric = target;
+        for _ in 0..MAX_INVERSE_ITERATIONS {
+            let (_, apparent) = self.deflect_and_aberrate(&astrometric, Some(astrometric), &sun);
+            let residual = target - apparent;
+            astrometric = (astrometric + residual).normalize();
+            if residual.norm() < INVERSE_TOLERANCE {
+                break;
+            }
+        }
+
+        let (ra, dec, _) = radec_of(&astrometric);
+        Ok((ra, dec))
+    }
+
+    /// Position of the Sun `light_time_days` before the observation
+    fn sun_position(&self, light_time_days: f64) -> Vector3<f64> {
+        self.ephemeris
+            .position(Body::Sun, self.time.tdb() - light_time_days)
+            .unwrap_or_default()
+    }
+
+    /// Deflect the unit direction `p` by the Sun and then aberrate it,
+    /// returning both stages
+    ///
+    /// See [`crate::positions::icrf::deflect_and_aberrate`].
+    fn deflect_and_aberrate(
+        &self,
+        p: &Vector3<f64>,
+        q: Option<Vector3<f64>>,
+        sun: &Vector3<f64>,
+    ) -> (Vector3<f64>, Vector3<f64>) {
+        deflect_and_aberrate(p, q, &(self.position - sun), &self.velocity)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::constants::ASEC2RAD;
+    use crate::coordinates::Equatorial;
+    use crate::framelib::Refraction;
+    use crate::positions::icrf::IcrfVector;
+    use crate::time::Timescale;
+    use approx::assert_relative_eq;
+
+    #[test]
+    fn test_aberration_magnitude() {
+        // Annual aberration displaces the Sun by about 20.5"
+        let ts = Timescale::default();
+        let eph = Ephemeris::new();
+        let t = ts.utc((2024, 4, 1));
+        let astrometric = ObserverAt::geocenter(&eph, &t)
+            .unwrap()
+            .observe(Body::Sun)
+            .unwrap();
+        let apparent = astrometric.apparent(&eph).unwrap();
+        let shift = astrometric
+            .position
+            .vector()
+            .angle(apparent.position.vector())
+            / ASEC2RAD;
+        assert_relative_eq!(shift, 20.5, epsilon = 0.4);
+        assert!(apparent.altaz().is_err());
+    }
+
+    #[test]
+    fn test_topocentric_moon_parallax() {
+        let ts = Timescale::default();
+        let eph = Ephemeris::new();
+        let t = ts.utc((2024, 6, 1, 3, 0, 0.0));
+        let site = GeographicLocation::new(-30.24, -70.74, 2_700.0);
+
+        let geocentric = ObserverAt::geocenter(&eph, &t)
+            .unwrap()
+            .observe(Body::Moon)
+            .unwrap()
+            .apparent(&eph)
+            .unwrap();
+        let topocentric = site
+            .at(&eph, &t)
+            .unwrap()
+            .observe(Body::Moon)
+            .unwrap()
+            .apparent(&eph)
+            .unwrap();
+
+        // Horizontal parallax is about 57', so the shift is up to ~1 degree
+        let shift = geocentric.separation_arcsec(&topocentric);
+        assert!((0.0..3_600.0).contains(&shift));
+
+        // The topocentric altitude agrees with the parallax-corrected one
+        let (alt, az) = topocentric.altaz().unwrap();
+        let geocentric_vector = geocentric.position.vector();
+        let (alt2, az2) = site.altaz(geocentric_vector, &t);
+        assert_relative_eq!(alt, alt2, epsilon = 0.01);
+        assert_relative_eq!(az, az2, epsilon = 0.05);
+
+        // Horizontal coordinates carry Earth-orientation errors on top
+        let radec_error = topocentric.accuracy(&eph).total_arcsec();
+        assert!(topocentric.altaz_accuracy(&eph).total_arcsec() > radec_error);
+    }
+
+    #[test]
+    fn test_precession_to_date() {
+        let ts = Timescale::default();
+        let eph = Ephemeris::new();
+        let t = ts.utc((2050, 1, 1));
+        let apparent = ObserverAt::geocenter(&eph, &t)
+            .unwrap()
+            .observe(Body::Mars)
+            .unwrap()
+            .apparent(&eph)
+            .unwrap();
+        let (ra, dec, r) = apparent.radec();
+        let (ra_date, dec_date, r_date) = apparent.radec_of_date();
+        assert_relative_eq!(r, r_date, max_relative = 1e-12);
+        // Fifty years of precession moves positions by up to ~0.7 degrees
+        let moved = ((ra - ra_date).powi(2) * (dec.to_radians().cos()).powi(2)
+            + (dec - dec_date).powi(2))
+        .sqrt();
+        assert!((0.1..1.0).contains(&moved), "{}", moved);
+        assert!(ObserverAt::geocenter(&eph, &t)
+            .unwrap()
+            .observe(Body::Earth)
+            .is_err());
+    }
+
+    #[test]
+    fn test_altaz_to_astrometric_roundtrip() {
+        let ts = Timescale::default();
+        let eph = Ephemeris::new();
+        let t = ts.utc((2024, 9, 1, 10, 30, 0.0));
+        let site = GeographicLocation::new(-24.63, -70.40, 2_635.0);
+        let observer = site.at(&eph, &t).unwrap();
+        let frame = HorizontalFrame::new(site, &t).with_refraction(Refraction::Bennett);
+
+        // Canopus, observed through the forward chain
+        let (ra, dec) = (95.988_f64, -52.696_f64);
+        let (sin_dec, cos_dec) = dec.to_radians().sin_cos();
+        let (sin_ra, cos_ra) = ra.to_radians().sin_cos();
+        let p = Vector3::new(cos_dec * cos_ra, cos_dec * sin_ra, sin_dec);
+        let sun = observer.sun_position(0.0);
+        let (_, apparent) = observer.deflect_and_aberrate(&p, Some(p), &sun);
+        let (apparent_ra, apparent_dec, _) = radec_of(&apparent);
+        let observed = frame.from_equatorial(&Equatorial::from_degrees(apparent_ra, apparent_dec));
+        assert!(observed.alt_degrees() > 10.0);
+
+        let (ra_back, dec_back) = observer.astrometric_radec_of(&observed, &frame).unwrap();
+        let error = Equatorial::from_degrees(ra_back, dec_back)
+            .angular_distance(&Equatorial::from_degrees(ra, dec))
+            / ASEC2RAD;
+        assert!(error < 1e-3, "{}", error);
+
+        // Skipping the inversion leaves the ~20" of aberration in place
+        let naive = frame.to_equatorial(&observed);
+        let naive_error = naive.angular_distance(&Equatorial::from_degrees(ra, dec)) / ASEC2RAD;
+        assert!((5.0..30.0).contains(&naive_error), "{}", naive_error);
+
+        let geocenter = ObserverAt::geocenter(&eph, &t).unwrap();
+        assert!(geocenter.astrometric_radec_of(&observed, &frame).is_err());
+    }
+}
@@ -0,0 +1,232 @@
+//! Observers that move: aircraft, ships and balloons
+//!
+//! A [`Trajectory`] gives the observer's geodetic location at any time,
+//! either from a sampled path or from a callback, and stands in for a
+//! fixed [`GeographicLocation`] wherever the observer may be moving:
+//!
+//! ```
+//! use starfield::observers::{GeographicLocation, Trajectory};
+//! use starfield::planetlib::{Body, Ephemeris};
+//! use starfield::time::Timescale;
+//!
+//! let ts = Timescale::default();
+//! // Two fixes of an airliner crossing the Atlantic at 11 km
+//! let flight = Trajectory::sampled(vec![
+//!     (ts.utc((2024, 3, 1, 1, 0, 0.0)), GeographicLocation::new(52.0, -20.0, 11_000.0)),
+//!     (ts.utc((2024, 3, 1, 3, 0, 0.0)), GeographicLocation::new(54.0, -40.0, 11_000.0)),
+//! ])?;
+//!
+//! let t = ts.utc((2024, 3, 1, 2, 0, 0.0));
+//! assert!((flight.location_at(&t).longitude_deg + 30.0).abs() < 1e-6);
+//! let eph = Ephemeris::new();
+//! let (alt, az) = flight.at(&eph, &t)?.observe(Body::Moon)?.apparent(&eph)?.altaz()?;
+//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! ```
+//!
+//! Sampled paths are interpolated linearly in latitude, longitude and
+//! height, taking the short way across the antimeridian, and hold their
+//! first and last fixes outside the sampled span. The observer's velocity
+//! is differentiated from the path, so aberration and tracking rates
+//! include the platform's own motion.
+
+use super::GeographicLocation;
+use crate::constants::DAY_S;
+use crate::time::{Time, TimeDelta};
+use crate::{Result, StarfieldError};
+use nalgebra::Vector3;
+use std::fmt;
+use std::sync::Arc;
+
+/// Half-width of the interval used to differentiate the path, in seconds
+const VELOCITY_HALF_STEP_S: f64 = 0.5;
+
+/// Location of an observer as a function of time
+#[derive(Clone)]
+pub struct Trajectory {
+    path: Path,
+}
+
+#[derive(Clone)]
+enum Path {
+    Fixed(GeographicLocation),
+    /// Fixes sorted by TT Julian date
+    Sampled(Vec<(f64, GeographicLocation)>),
+    Function(Arc<dyn Fn(&Time) -> GeographicLocation + Send + Sync>),
+}
+
+impl Trajectory {
+    /// An observer that stays at `location`
+    pub fn fixed(location: GeographicLocation) -> Self {
+        Self {
+            path: Path::Fixed(location),
+        }
+    }
+
+    /// An observer following a path of timed fixes, in any order
+    ///
+    /// Fails if there are no fixes or two share a time.
+    pub fn sampled(fixes: Vec<(Time, GeographicLocation)>) -> Result<Self> {
+        let mut fixes: Vec<(f64, GeographicLocation)> =
+            fixes.into_iter().map(|(t, loc)| (t.tt(), loc)).collect();
+        if fixes.is_empty() {
+            return Err(StarfieldError::DataError(
+                "a sampled trajectory needs at least one fix".to_string(),
+            ));
+        }
+        fixes.sort_by(|a, b| a.0.total_cmp(&b.0));
+        if fixes.windows(2).any(|pair| pair[0].0 == pair[1].0) {
+            return Err(StarfieldError::DataError(
+                "two trajectory fixes share the same time".to_string(),
+            ));
+        }
+        Ok(Self {
+            path: Path::Sampled(fixes),
+        })
+    }
+
+    /// An observer whose location at each time is given by `f`
+    pub fn from_fn(f: impl Fn(&Time) -> GeographicLocation + Send + Sync + 'static) -> Self {
+        Self {
+            path: Path::Function(Arc::new(f)),
+        }
+    }
+
+    /// Whether the observer never moves
+    pub fn is_fixed(&self) -> bool {
+        matches!(self.path, Path::Fixed(_))
+    }
+
+    /// Where the observer is at `t`
+    pub fn location_at(&self, t: &Time) -> GeographicLocation {
+        match &self.path {
+            Path::Fixed(location) => *location,
+            Path::Sampled(fixes) => interpolate(fixes, t.tt()),
+            Path::Function(f) => f(t),
+        }
+    }
+
+    /// Geocentric position of the observer in J2000 equatorial axes, in AU
+    pub fn geocentric_position(&self, t: &Time) -> Vector3<f64> {
+        self.location_at(t).geocentric_position(t)
+    }
+
+    /// Geocentric velocity of the observer in J2000 equatorial axes, in
+    /// AU/day, including the Earth's rotation
+    pub fn geocentric_velocity(&self, t: &Time) -> Vector3<f64> {
+        let half_step = VELOCITY_HALF_STEP_S / DAY_S;
+        let before = self.geocentric_position(&(t - TimeDelta::days(half_step)));
+        let after = self.geocentric_position(&(t + TimeDelta::days(half_step)));
+        (after - before) / (2.0 * half_step)
+    }
+
+    /// Altitude and azimuth in degrees of a target at a geocentric J2000
+    /// position (AU), from wherever the observer is at `t`
+    ///
+    /// See [`GeographicLocation::altaz`].
+    pub fn altaz(&self, geocentric: &Vector3<f64>, t: &Time) -> (f64, f64) {
+        self.location_at(t).altaz(geocentric, t)
+    }
+}
+
+impl From<GeographicLocation> for Trajectory {
+    fn from(location: GeographicLocation) -> Self {
+        Self::fixed(location)
+    }
+}
+
+impl fmt::Debug for Trajectory {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.path {
+            Path::Fixed(location) => f.debug_tuple("Trajectory::Fixed").field(location).finish(),
+            Path::Sampled(fixes) => f
+                .debug_struct("Trajectory::Sampled")
+                .field("fixes", &fixes.len())
+                .finish(),
+            Path::Function(_) => f.write_str("Trajectory::Function"),
+        }
+    }
+}
+
+/// Location along sorted fixes at TT Julian date `jd`
+fn interpolate(fixes: &[(f64, GeographicLocation)], jd: f64) -> GeographicLocation {
+    let after = fixes.partition_point(|(t, _)| *t <= jd);
+    if after == 0 {
+        return fixes[0].1;
+    }
+    if after == fixes.len() {
+        return fixes[after - 1].1;
+    }
+
+    let (t0, a) = fixes[after - 1];
+    let (t1, b) = fixes[after];
+    let f = (jd - t0) / (t1 - t0);
+    let dlon = (b.longitude_deg - a.longitude_deg + 180.0).rem_euclid(360.0) - 180.0;
+    let longitude = (a.longitude_deg + f * dlon + 180.0).rem_euclid(360.0) - 180.0;
+    GeographicLocation::new(
+        a.latitude_deg + f * (b.latitude_deg - a.latitude_deg),
+        longitude,
+        a.elevation_m + f * (b.elevation_m - a.elevation_m),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::constants::AU_KM;
+    use crate::time::Timescale;
+    use approx::assert_relative_eq;
+
+    #[test]
+    fn test_sampled_path_interpolation() {
+        let ts = Timescale::default();
+        let t0 = ts.utc((2024, 1, 10, 0, 0, 0.0));
+        // Eastward across the antimeridian, climbing; fixes out of order
+        let path = Trajectory::sampled(vec![
+            (
+                &t0 + TimeDelta::hours(1.0),
+                GeographicLocation::new(-17.0, -178.0, 2_000.0),
+            ),
+            (t0.clone(), GeographicLocation::new(-18.0, 178.0, 0.0)),
+        ])
+        .unwrap();
+
+        let mid = path.location_at(&(&t0 + TimeDelta::minutes(30.0)));
+        assert_relative_eq!(mid.latitude_deg, -17.5, epsilon = 1e-6);
+        assert_relative_eq!(mid.longitude_deg.abs(), 180.0, epsilon = 1e-6);
+        assert_relative_eq!(mid.elevation_m, 1_000.0, epsilon = 1e-3);
+
+        // Held at the ends
+        assert_eq!(
+            path.location_at(&(&t0 - TimeDelta::days(1.0))).elevation_m,
+            0.0
+        );
+        assert_eq!(
+            path.location_at(&(&t0 + TimeDelta::days(1.0))).elevation_m,
+            2_000.0
+        );
+
+        assert!(Trajectory::sampled(Vec::new()).is_err());
+        let twice = GeographicLocation::new(0.0, 0.0, 0.0);
+        assert!(Trajectory::sampled(vec![(t0.clone(), twice), (t0, twice)]).is_err());
+    }
+
+    #[test]
+    fn test_velocity_includes_platform_motion() {
+        let ts = Timescale::default();
+        let t = ts.utc((2024, 1, 10, 12, 0, 0.0));
+        let kms = |v: Vector3<f64>| v.norm() * AU_KM / DAY_S;
+
+        // At the equator the ground turns at 0.465 km/s
+        let ground = Trajectory::fixed(GeographicLocation::new(0.0, 10.0, 0.0));
+        assert_relative_eq!(kms(ground.geocentric_velocity(&t)), 0.465, epsilon = 0.002);
+
+        // An aircraft flying east at 250 m/s adds to it
+        let start = t.clone();
+        let flight = Trajectory::from_fn(move |now| {
+            let east_deg = 250.0 * (now.tt() - start.tt()) * DAY_S / 111_320.0;
+            GeographicLocation::new(0.0, 10.0 + east_deg, 0.0)
+        });
+        assert!(!flight.is_fixed());
+        assert_relative_eq!(kms(flight.geocentric_velocity(&t)), 0.715, epsilon = 0.002);
+    }
+}
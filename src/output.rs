@@ -0,0 +1,358 @@
+//! Serializable results for tools and data interchange
+//!
+//! Command-line tools and other programs exchange results as JSON built
+//! from these types rather than parsing printed text. Each document is
+//! wrapped in an envelope naming its schema and version:
+//!
+//! ```json
+//! { "schema": "starfield/ephemeris-row", "version": 1, "data": { ... } }
+//! ```
+//!
+//! Within a version, fields are only ever added, as optional fields, so
+//! older readers keep working; removing or changing a field bumps the
+//! version and [`JsonOutput::from_json`] rejects documents of other
+//! versions.
+//!
+//! ```
+//! use starfield::output::{EphemerisRow, JsonOutput};
+//! use starfield::planetlib::{Body, Ephemeris};
+//! use starfield::time::Timescale;
+//!
+//! let ts = Timescale::default();
+//! let t = ts.utc((2024, 3, 1, 22, 0, 0.0));
+//! let row = EphemerisRow::compute(&Ephemeris::new(), Body::Jupiter, &t, None)?;
+//! let json = row.to_json()?;
+//! assert_eq!(EphemerisRow::from_json(&json)?, row);
+//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! ```
+
+use crate::catalogs::{StarData, WindowQuery, WindowResult};
+use crate::constants::RAD2DEG;
+use crate::coordinates::Equatorial;
+use crate::observers::{GeographicLocation, ObserverAt};
+use crate::planetlib::{Body, Ephemeris, PlanetError};
+use crate::positions::icrf::IcrfVector;
+use crate::time::Time;
+use crate::{Result, StarfieldError};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+
+/// Version of every schema in this module
+pub const SCHEMA_VERSION: u32 = 1;
+
+/// Decimal places of the seconds in UTC timestamps
+const TIMESTAMP_PLACES: usize = 3;
+
+/// A result that can be written and read as a versioned JSON document
+pub trait JsonOutput: Serialize + DeserializeOwned {
+    /// Name of the document's schema
+    const SCHEMA: &'static str;
+
+    /// The result as a pretty-printed JSON document
+    fn to_json(&self) -> Result<String> {
+        let envelope = Envelope {
+            schema: Self::SCHEMA.to_string(),
+            version: SCHEMA_VERSION,
+            data: self,
+        };
+        serde_json::to_string_pretty(&envelope)
+            .map_err(|e| StarfieldError::DataError(format!("Cannot write JSON: {}", e)))
+    }
+
+    /// Read a document written by [`JsonOutput::to_json`]
+    fn from_json(text: &str) -> Result<Self> {
+        let envelope: Envelope<Self> = serde_json::from_str(text)
+            .map_err(|e| StarfieldError::DataError(format!("Invalid JSON document: {}", e)))?;
+        if envelope.schema != Self::SCHEMA || envelope.version != SCHEMA_VERSION {
+            return Err(StarfieldError::DataError(format!(
+                "Expected a {} version {} document, got {} version {}",
+                Self::SCHEMA,
+                SCHEMA_VERSION,
+                envelope.schema,
+                envelope.version
+            )));
+        }
+        Ok(envelope.data)
+    }
+}
+
+/// Wrapper naming a document's schema and version
+#[derive(Serialize, Deserialize)]
+struct Envelope<T> {
+    schema: String,
+    version: u32,
+    data: T,
+}
+
+/// UTC timestamp of `t` in ISO 8601 form
+fn timestamp(t: &Time) -> Result<String> {
+    t.utc_iso('T', TIMESTAMP_PLACES)
+        .map_err(|e| StarfieldError::TimeError(e.to_string()))
+}
+
+/// Stars found around a position
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ConeSearchResult {
+    /// Catalog the stars came from
+    pub catalog: String,
+    /// Right ascension of the center in degrees
+    pub center_ra_deg: f64,
+    /// Declination of the center in degrees
+    pub center_dec_deg: f64,
+    /// Search radius in degrees
+    pub radius_deg: f64,
+    /// Julian year of the star positions, if known
+    pub epoch: Option<f64>,
+    /// Stars found, nearest the center first
+    pub stars: Vec<ConeSearchStar>,
+}
+
+/// One star of a [`ConeSearchResult`]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ConeSearchStar {
+    /// Catalog identifier
+    pub id: u64,
+    /// Right ascension in degrees
+    pub ra_deg: f64,
+    /// Declination in degrees
+    pub dec_deg: f64,
+    /// Apparent magnitude
+    pub magnitude: f64,
+    /// B-V color index, if known
+    pub b_v: Option<f64>,
+    /// Distance from the center in degrees
+    pub separation_deg: f64,
+}
+
+impl ConeSearchResult {
+    /// Result of a search of `catalog` within `radius_deg` of `center`
+    pub fn new(catalog: &str, center: &Equatorial, radius_deg: f64, stars: &[StarData]) -> Self {
+        let mut stars: Vec<ConeSearchStar> = stars
+            .iter()
+            .map(|star| ConeSearchStar {
+                id: star.id,
+                ra_deg: star.ra_deg(),
+                dec_deg: star.dec_deg(),
+                magnitude: star.magnitude,
+                b_v: star.b_v,
+                separation_deg: center.angular_distance(&star.position) * RAD2DEG,
+            })
+            .collect();
+        stars.sort_by(|a, b| a.separation_deg.total_cmp(&b.separation_deg));
+
+        Self {
+            catalog: catalog.to_string(),
+            center_ra_deg: center.ra_degrees(),
+            center_dec_deg: center.dec_degrees(),
+            radius_deg,
+            epoch: None,
+            stars,
+        }
+    }
+
+    /// Record the Julian year of the star positions
+    pub fn with_epoch(mut self, epoch: f64) -> Self {
+        self.epoch = Some(epoch);
+        self
+    }
+
+    /// Result of an executed [`WindowQuery`]
+    pub fn from_window(query: &WindowQuery, result: &WindowResult) -> Self {
+        let search = Self::new(
+            &result.catalog,
+            &query.center,
+            query.fov_deg / 2.0,
+            &result.stars,
+        );
+        match result.epoch {
+            Some(epoch) => search.with_epoch(epoch),
+            None => search,
+        }
+    }
+}
+
+impl JsonOutput for ConeSearchResult {
+    const SCHEMA: &'static str = "starfield/cone-search";
+}
+
+/// Where a satellite is at one moment of a pass
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PassEvent {
+    /// UTC time in ISO 8601 form
+    pub time_utc: String,
+    /// TT Julian date
+    pub tt_jd: f64,
+    /// Altitude in degrees
+    pub altitude_deg: f64,
+    /// Azimuth in degrees, from north through east
+    pub azimuth_deg: f64,
+    /// Distance from the observer in km
+    pub range_km: f64,
+}
+
+impl PassEvent {
+    /// Event at `t` with the satellite at the given altitude, azimuth and
+    /// range
+    pub fn new(t: &Time, altitude_deg: f64, azimuth_deg: f64, range_km: f64) -> Result<Self> {
+        Ok(Self {
+            time_utc: timestamp(t)?,
+            tt_jd: t.tt(),
+            altitude_deg,
+            azimuth_deg,
+            range_km,
+        })
+    }
+}
+
+/// A satellite pass over an observer
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PassPrediction {
+    /// Satellite name, if known
+    pub satellite: Option<String>,
+    /// NORAD catalog number
+    pub catalog_number: u32,
+    /// When the satellite rises above the pass's altitude limit
+    pub rise: PassEvent,
+    /// When it is highest
+    pub culmination: PassEvent,
+    /// When it sets below the limit
+    pub set: PassEvent,
+}
+
+impl PassPrediction {
+    /// Length of the pass in minutes
+    pub fn duration_minutes(&self) -> f64 {
+        (self.set.tt_jd - self.rise.tt_jd) * 1440.0
+    }
+}
+
+impl JsonOutput for PassPrediction {
+    const SCHEMA: &'static str = "starfield/pass-prediction";
+}
+
+impl JsonOutput for Vec<PassPrediction> {
+    const SCHEMA: &'static str = "starfield/pass-predictions";
+}
+
+/// Position of a solar system body at one time
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct EphemerisRow {
+    /// Name of the body
+    pub body: String,
+    /// UTC time in ISO 8601 form
+    pub time_utc: String,
+    /// TT Julian date
+    pub tt_jd: f64,
+    /// Apparent right ascension in degrees, ICRS axes
+    pub ra_deg: f64,
+    /// Apparent declination in degrees, ICRS axes
+    pub dec_deg: f64,
+    /// Distance in AU
+    pub distance_au: f64,
+    /// Altitude in degrees, for a surface observer
+    pub altitude_deg: Option<f64>,
+    /// Azimuth in degrees, for a surface observer
+    pub azimuth_deg: Option<f64>,
+}
+
+impl EphemerisRow {
+    /// Apparent position of `body` at `t`, seen from `location` or from
+    /// the geocenter
+    pub fn compute(
+        ephemeris: &Ephemeris,
+        body: Body,
+        t: &Time,
+        location: Option<&GeographicLocation>,
+    ) -> std::result::Result<Self, PlanetError> {
+        let observer = match location {
+            Some(location) => location.at(ephemeris, t)?,
+            None => ObserverAt::geocenter(ephemeris, t)?,
+        };
+        let apparent = observer.observe(body)?.apparent(ephemeris)?;
+        let (ra_deg, dec_deg, distance_au) = apparent.radec();
+        let altaz = match location {
+            Some(_) => Some(apparent.altaz()?),
+            None => None,
+        };
+
+        Ok(Self {
+            body: body.name().to_string(),
+            time_utc: timestamp(t).map_err(|e| PlanetError::TimeError(e.to_string()))?,
+            tt_jd: t.tt(),
+            ra_deg,
+            dec_deg,
+            distance_au,
+            altitude_deg: altaz.map(|(alt, _)| alt),
+            azimuth_deg: altaz.map(|(_, az)| az),
+        })
+    }
+}
+
+impl JsonOutput for EphemerisRow {
+    const SCHEMA: &'static str = "starfield/ephemeris-row";
+}
+
+impl JsonOutput for Vec<EphemerisRow> {
+    const SCHEMA: &'static str = "starfield/ephemeris";
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::time::{TimeDelta, Timescale};
+
+    #[test]
+    fn test_cone_search_round_trip() {
+        let center = Equatorial::from_degrees(10.0, 20.0);
+        let stars = [
+            StarData::new(7, 10.5, 20.0, 6.1, None),
+            StarData::new(3, 10.1, 20.0, 4.2, Some(0.6)),
+        ];
+        let result = ConeSearchResult::new("Test", &center, 1.0, &stars).with_epoch(2016.0);
+        assert_eq!(result.stars[0].id, 3);
+        assert!((result.stars[1].separation_deg - 0.47).abs() < 0.01);
+
+        let json = result.to_json().unwrap();
+        assert!(json.contains("\"schema\": \"starfield/cone-search\""));
+        assert_eq!(ConeSearchResult::from_json(&json).unwrap(), result);
+
+        // Documents of another schema or version are refused
+        assert!(EphemerisRow::from_json(&json).is_err());
+        let newer = json.replace("\"version\": 1", "\"version\": 2");
+        assert!(ConeSearchResult::from_json(&newer).is_err());
+    }
+
+    #[test]
+    fn test_ephemeris_rows_and_passes() {
+        let ts = Timescale::default();
+        let eph = Ephemeris::new();
+        let t = ts.utc((2024, 3, 1, 22, 0, 0.0));
+        let site = GeographicLocation::new(51.4769, 0.0, 46.0);
+
+        let geocentric = EphemerisRow::compute(&eph, Body::Mars, &t, None).unwrap();
+        assert_eq!(geocentric.time_utc, "2024-03-01T22:00:00.000Z");
+        assert!(geocentric.altitude_deg.is_none());
+        let topocentric = EphemerisRow::compute(&eph, Body::Mars, &t, Some(&site)).unwrap();
+        assert!(topocentric.altitude_deg.unwrap() < 0.0);
+
+        let rows = vec![geocentric, topocentric];
+        assert_eq!(
+            Vec::<EphemerisRow>::from_json(&rows.to_json().unwrap()).unwrap(),
+            rows
+        );
+
+        let pass = PassPrediction {
+            satellite: Some("ISS (ZARYA)".to_string()),
+            catalog_number: 25544,
+            rise: PassEvent::new(&t, 10.0, 250.0, 1_400.0).unwrap(),
+            culmination: PassEvent::new(&(&t + TimeDelta::minutes(3.0)), 60.0, 180.0, 480.0)
+                .unwrap(),
+            set: PassEvent::new(&(&t + TimeDelta::minutes(6.0)), 10.0, 110.0, 1_400.0).unwrap(),
+        };
+        assert!((pass.duration_minutes() - 6.0).abs() < 1e-6);
+        assert_eq!(
+            PassPrediction::from_json(&pass.to_json().unwrap()).unwrap(),
+            pass
+        );
+    }
+}
@@ -0,0 +1,214 @@
+//! Comparison of ephemeris providers
+//!
+//! [`compare_ephemerides`] samples one body from two sources over a span
+//! and reports how far apart they put it, so the cost of using the analytic
+//! model instead of a DE440 kernel, or a fitted [`EphemerisTable`] instead
+//! of its source, can be read off for the bodies and dates that matter:
+//!
+//! ```
+//! use starfield::planetlib::{compare_ephemerides, Body, Ephemeris, EphemerisTable, TableConfig};
+//! use starfield::time::Timescale;
+//!
+//! let ts = Timescale::default();
+//! let t0 = ts.tt_jd(2_460_000.5, None);
+//! let t1 = ts.tt_jd(2_460_030.5, None);
+//!
+//! let analytic = Ephemeris::new();
+//! let config = TableConfig::default().with_tolerance_km(1.0);
+//! let table = EphemerisTable::fit(&analytic, &[Body::Earth, Body::Mars], t0.tdb(), t1.tdb(), &config)?;
+//!
+//! let stats = compare_ephemerides(&analytic, &table, Body::Mars, &t0, &t1, 0.5)?;
+//! println!("{}", stats);
+//! assert!(stats.max_difference_km < 5.0);
+//! # Ok::<(), starfield::planetlib::PlanetError>(())
+//! ```
+//!
+//! Providers may use different origins - an [`Ephemeris`] is barycentric,
+//! while a Horizons table may be heliocentric - so bodies are compared as
+//! seen from the Earth's centre, and the Earth itself as seen from the Sun.
+//! Horizons vector tables, or any other source, take part by implementing
+//! [`EphemerisProvider`].
+
+use super::{Body, Ephemeris, EphemerisTable, PlanetError, PlanetState};
+use crate::constants::{ASEC2RAD, AU_KM};
+use crate::time::Time;
+use std::fmt;
+
+/// A source of body states at TDB Julian dates
+pub trait EphemerisProvider {
+    /// Position (AU) and velocity (AU/day) of `body` in J2000 equatorial
+    /// axes, relative to the provider's own origin
+    fn state(&self, body: Body, jd: f64) -> Result<PlanetState, PlanetError>;
+
+    /// State of `body` relative to `center`
+    ///
+    /// Override this for sources tabulated about a particular centre.
+    fn relative_state(
+        &self,
+        body: Body,
+        center: Body,
+        jd: f64,
+    ) -> Result<PlanetState, PlanetError> {
+        let target = self.state(body, jd)?;
+        let center = self.state(center, jd)?;
+        Ok(PlanetState {
+            position: (target.position - center.position).into(),
+            velocity: target.velocity - center.velocity,
+        })
+    }
+}
+
+impl EphemerisProvider for Ephemeris {
+    fn state(&self, body: Body, jd: f64) -> Result<PlanetState, PlanetError> {
+        self.get_state(body, jd)
+    }
+}
+
+impl EphemerisProvider for EphemerisTable {
+    fn state(&self, body: Body, jd: f64) -> Result<PlanetState, PlanetError> {
+        self.get_state(body, jd)
+    }
+}
+
+/// How far two providers put a body over a span of dates
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct EphemerisComparison {
+    /// The body compared
+    pub body: Body,
+    /// Body the positions were measured from
+    pub center: Body,
+    /// Number of dates sampled
+    pub samples: usize,
+    /// Largest position difference, km
+    pub max_difference_km: f64,
+    /// Root-mean-square position difference, km
+    pub rms_difference_km: f64,
+    /// TDB Julian date of the largest position difference
+    pub max_difference_jd: f64,
+    /// Largest difference in direction seen from the centre, arcseconds
+    pub max_angle_arcsec: f64,
+    /// Root-mean-square difference in direction, arcseconds
+    pub rms_angle_arcsec: f64,
+    /// Largest velocity difference, m/s
+    pub max_velocity_difference_m_s: f64,
+}
+
+impl fmt::Display for EphemerisComparison {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} from {}, {} samples: max {:.3} km (JD {:.4}), rms {:.3} km; \
+             max {:.3}\", rms {:.3}\"; max velocity {:.4} m/s",
+            self.body.name(),
+            self.center.name(),
+            self.samples,
+            self.max_difference_km,
+            self.max_difference_jd,
+            self.rms_difference_km,
+            self.max_angle_arcsec,
+            self.rms_angle_arcsec,
+            self.max_velocity_difference_m_s
+        )
+    }
+}
+
+/// Compare the positions two providers give `body` from `t0` to `t1`
+/// inclusive, every `step_days`
+///
+/// The body is measured from the Earth's centre, or from the Sun when it
+/// is the Earth.
+pub fn compare_ephemerides(
+    provider_a: &impl EphemerisProvider,
+    provider_b: &impl EphemerisProvider,
+    body: Body,
+    t0: &Time,
+    t1: &Time,
+    step_days: f64,
+) -> Result<EphemerisComparison, PlanetError> {
+    let (start, end) = (t0.tdb(), t1.tdb());
+    let valid_span = step_days > 0.0 && end >= start;
+    if !valid_span {
+        return Err(PlanetError::TimeError(format!(
+            "cannot step {} days from JD {} to {}",
+            step_days, start, end
+        )));
+    }
+    let center = if body == Body::Earth {
+        Body::Sun
+    } else {
+        Body::Earth
+    };
+
+    let steps = ((end - start) / step_days).floor() as usize;
+    let mut comparison = EphemerisComparison {
+        body,
+        center,
+        samples: 0,
+        max_difference_km: 0.0,
+        rms_difference_km: 0.0,
+        max_difference_jd: start,
+        max_angle_arcsec: 0.0,
+        rms_angle_arcsec: 0.0,
+        max_velocity_difference_m_s: 0.0,
+    };
+    let (mut sum_squares_km, mut sum_squares_arcsec) = (0.0, 0.0);
+
+    for i in 0..=steps {
+        let jd = (start + i as f64 * step_days).min(end);
+        let a = provider_a.relative_state(body, center, jd)?;
+        let b = provider_b.relative_state(body, center, jd)?;
+
+        let difference_km = (a.position - b.position).norm() * AU_KM;
+        let angle_arcsec = a.position.coords.angle(&b.position.coords) / ASEC2RAD;
+        let velocity_m_s = (a.velocity - b.velocity).norm() * AU_KM * 1000.0 / 86400.0;
+
+        if difference_km > comparison.max_difference_km {
+            comparison.max_difference_km = difference_km;
+            comparison.max_difference_jd = jd;
+        }
+        comparison.max_angle_arcsec = comparison.max_angle_arcsec.max(angle_arcsec);
+        comparison.max_velocity_difference_m_s =
+            comparison.max_velocity_difference_m_s.max(velocity_m_s);
+        sum_squares_km += difference_km * difference_km;
+        sum_squares_arcsec += angle_arcsec * angle_arcsec;
+        comparison.samples += 1;
+    }
+
+    let n = comparison.samples as f64;
+    comparison.rms_difference_km = (sum_squares_km / n).sqrt();
+    comparison.rms_angle_arcsec = (sum_squares_arcsec / n).sqrt();
+    Ok(comparison)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::time::Timescale;
+
+    #[test]
+    fn test_compare_ephemerides() {
+        let ts = Timescale::default();
+        let t0 = ts.tt_jd(2_460_000.5, None);
+        let t1 = ts.tt_jd(2_460_010.5, None);
+        let analytic = Ephemeris::new();
+
+        let same = compare_ephemerides(&analytic, &analytic, Body::Mars, &t0, &t1, 1.0).unwrap();
+        assert_eq!(same.samples, 11);
+        assert_eq!(same.max_difference_km, 0.0);
+
+        // A different Earth/Moon mass ratio moves the Earth about the
+        // Earth-Moon barycenter by hundreds of km
+        let heavier_moon = Ephemeris::new().with_earth_moon_mass_ratio(70.0);
+        let earth =
+            compare_ephemerides(&analytic, &heavier_moon, Body::Earth, &t0, &t1, 0.25).unwrap();
+        assert_eq!(earth.center, Body::Sun);
+        assert_eq!(earth.samples, 41);
+        assert!(earth.max_difference_km > 100.0, "{}", earth);
+        assert!(earth.rms_difference_km <= earth.max_difference_km);
+        assert!(earth.rms_angle_arcsec <= earth.max_angle_arcsec);
+        assert!((t0.tdb()..=t1.tdb()).contains(&earth.max_difference_jd));
+
+        assert!(compare_ephemerides(&analytic, &analytic, Body::Mars, &t1, &t0, 1.0).is_err());
+        assert!(compare_ephemerides(&analytic, &analytic, Body::Mars, &t0, &t1, 0.0).is_err());
+    }
+}
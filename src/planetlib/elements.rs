@@ -0,0 +1,386 @@
+//! Approximate planetary positions from mean Keplerian elements
+//!
+//! Uses the element set and secular rates of E. M. Standish, "Keplerian
+//! Elements for Approximate Positions of the Major Planets" (JPL, Table 1),
+//! which is fit to DE405 over 1800 AD – 2050 AD. Expected errors are a few
+//! arcminutes for the outer planets and well under one for the inner ones.
+//!
+//! [`OrbitalElements`] propagates the osculating elements of a minor planet,
+//! as published by the Minor Planet Center, on a two-body orbit.
+
+use super::Body;
+use crate::constants::{AU_KM, DAY_S, DEG2RAD, GM_SUN, J2000, TAU};
+use crate::framelib::INERTIAL_FRAMES;
+use nalgebra::Vector3;
+
+/// Mean orbital elements at J2000 and their rates per Julian century
+///
+/// Order: semi-major axis (AU), eccentricity, inclination (deg),
+/// mean longitude (deg), longitude of perihelion (deg), longitude of the
+/// ascending node (deg).
+type ElementRow = ([f64; 6], [f64; 6]);
+
+const MERCURY: ElementRow = (
+    [
+        0.387_099_27,
+        0.205_635_93,
+        7.004_979_02,
+        252.250_323_50,
+        77.457_796_28,
+        48.330_765_93,
+    ],
+    [
+        0.000_000_37,
+        0.000_019_06,
+        -0.005_947_49,
+        149_472.674_111_75,
+        0.160_476_89,
+        -0.125_340_81,
+    ],
+);
+
+const VENUS: ElementRow = (
+    [
+        0.723_335_66,
+        0.006_776_72,
+        3.394_676_05,
+        181.979_099_50,
+        131.602_467_18,
+        76.679_842_55,
+    ],
+    [
+        0.000_003_90,
+        -0.000_041_07,
+        -0.000_788_90,
+        58_517.815_387_29,
+        0.002_683_29,
+        -0.277_694_18,
+    ],
+);
+
+const EARTH_MOON_BARYCENTER: ElementRow = (
+    [
+        1.000_002_61,
+        0.016_711_23,
+        -0.000_015_31,
+        100.464_571_66,
+        102.937_681_93,
+        0.0,
+    ],
+    [
+        0.000_005_62,
+        -0.000_043_92,
+        -0.012_946_68,
+        35_999.372_449_81,
+        0.323_273_64,
+        0.0,
+    ],
+);
+
+const MARS: ElementRow = (
+    [
+        1.523_710_34,
+        0.093_394_10,
+        1.849_691_42,
+        -4.553_432_05,
+        -23.943_629_59,
+        49.559_538_91,
+    ],
+    [
+        0.000_018_47,
+        0.000_078_82,
+        -0.008_131_31,
+        19_140.302_684_99,
+        0.444_410_88,
+        -0.292_573_43,
+    ],
+);
+
+const JUPITER: ElementRow = (
+    [
+        5.202_887_00,
+        0.048_386_24,
+        1.304_396_95,
+        34.396_440_51,
+        14.728_479_83,
+        100.473_909_09,
+    ],
+    [
+        -0.000_116_07,
+        -0.000_132_53,
+        -0.001_837_14,
+        3_034.746_127_75,
+        0.212_526_68,
+        0.204_691_06,
+    ],
+);
+
+const SATURN: ElementRow = (
+    [
+        9.536_675_94,
+        0.053_861_79,
+        2.485_991_87,
+        49.954_244_23,
+        92.598_878_31,
+        113.662_424_48,
+    ],
+    [
+        -0.001_250_60,
+        -0.000_509_91,
+        0.001_936_09,
+        1_222.493_622_01,
+        -0.418_972_16,
+        -0.288_677_94,
+    ],
+);
+
+const URANUS: ElementRow = (
+    [
+        19.189_164_64,
+        0.047_257_44,
+        0.772_637_83,
+        313.238_104_51,
+        170.954_276_30,
+        74.016_925_03,
+    ],
+    [
+        -0.001_961_76,
+        -0.000_043_97,
+        -0.002_429_39,
+        428.482_027_85,
+        0.408_052_81,
+        0.042_405_89,
+    ],
+);
+
+const NEPTUNE: ElementRow = (
+    [
+        30.069_922_76,
+        0.008_590_48,
+        1.770_043_47,
+        -55.120_029_69,
+        44.964_762_27,
+        131.784_225_74,
+    ],
+    [
+        0.000_262_91,
+        0.000_051_05,
+        0.000_353_72,
+        218.459_453_25,
+        -0.322_414_64,
+        -0.005_086_64,
+    ],
+);
+
+const PLUTO: ElementRow = (
+    [
+        39.482_116_75,
+        0.248_827_30,
+        17.140_012_06,
+        238.929_038_33,
+        224.068_916_29,
+        110.303_936_84,
+    ],
+    [
+        -0.000_315_96,
+        0.000_051_70,
+        0.000_048_18,
+        145.207_805_15,
+        -0.040_629_42,
+        -0.011_834_82,
+    ],
+);
+
+/// Element row for a body orbiting the Sun, if the table covers it
+///
+/// Earth itself is not listed: the table describes the Earth-Moon barycenter.
+fn element_row(body: Body) -> Option<&'static ElementRow> {
+    match body {
+        Body::Mercury => Some(&MERCURY),
+        Body::Venus => Some(&VENUS),
+        Body::EarthMoonBarycenter => Some(&EARTH_MOON_BARYCENTER),
+        Body::Mars => Some(&MARS),
+        Body::Jupiter => Some(&JUPITER),
+        Body::Saturn => Some(&SATURN),
+        Body::Uranus => Some(&URANUS),
+        Body::Neptune => Some(&NEPTUNE),
+        Body::Pluto => Some(&PLUTO),
+        Body::Sun | Body::Earth | Body::Moon | Body::Custom(_) => None,
+    }
+}
+
+/// Solve Kepler's equation `M = E - e sin E` for the eccentric anomaly
+pub(crate) fn solve_kepler(mean_anomaly: f64, eccentricity: f64) -> f64 {
+    let mut e_anom = mean_anomaly + eccentricity * mean_anomaly.sin();
+    for _ in 0..30 {
+        let delta = (e_anom - eccentricity * e_anom.sin() - mean_anomaly)
+            / (1.0 - eccentricity * e_anom.cos());
+        e_anom -= delta;
+        if delta.abs() < 1e-15 {
+            break;
+        }
+    }
+    e_anom
+}
+
+/// Heliocentric position in AU referred to the J2000 ecliptic and equinox
+///
+/// Returns `None` for bodies not covered by the element table.
+pub fn heliocentric_ecliptic(body: Body, jd_tdb: f64) -> Option<Vector3<f64>> {
+    let (base, rate) = element_row(body)?;
+    let t = (jd_tdb - J2000) / 36525.0;
+
+    let a = base[0] + rate[0] * t;
+    let e = base[1] + rate[1] * t;
+    let incl = (base[2] + rate[2] * t) * DEG2RAD;
+    let mean_long = (base[3] + rate[3] * t) * DEG2RAD;
+    let long_peri = (base[4] + rate[4] * t) * DEG2RAD;
+    let long_node = (base[5] + rate[5] * t) * DEG2RAD;
+
+    let arg_peri = long_peri - long_node;
+    let mean_anomaly = (mean_long - long_peri).rem_euclid(TAU);
+    Some(orbit_position(
+        a,
+        e,
+        incl,
+        long_node,
+        arg_peri,
+        mean_anomaly,
+    ))
+}
+
+/// Position in the reference plane of the elements, in the units of `a`;
+/// angles in radians
+fn orbit_position(
+    a: f64,
+    e: f64,
+    incl: f64,
+    long_node: f64,
+    arg_peri: f64,
+    mean_anomaly: f64,
+) -> Vector3<f64> {
+    let ecc_anomaly = solve_kepler(mean_anomaly, e);
+
+    // Position in the orbital plane, x toward perihelion
+    let xp = a * (ecc_anomaly.cos() - e);
+    let yp = a * (1.0 - e * e).sqrt() * ecc_anomaly.sin();
+
+    let (sw, cw) = arg_peri.sin_cos();
+    let (so, co) = long_node.sin_cos();
+    let (si, ci) = incl.sin_cos();
+
+    Vector3::new(
+        (cw * co - sw * so * ci) * xp + (-sw * co - cw * so * ci) * yp,
+        (cw * so + sw * co * ci) * xp + (-sw * so + cw * co * ci) * yp,
+        (sw * si) * xp + (cw * si) * yp,
+    )
+}
+
+/// Osculating heliocentric elements of an asteroid or comet on an
+/// elliptical orbit, referred to the J2000 ecliptic and equinox
+///
+/// Propagation is two-body, ignoring planetary perturbations: good to
+/// seconds of arc for a few weeks either side of the epoch, which is why the
+/// MPC publishes fresh elements every 200 days.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct OrbitalElements {
+    /// Epoch of osculation, TT Julian date
+    pub epoch_jd: f64,
+    /// Semi-major axis in AU
+    pub semi_major_axis_au: f64,
+    /// Eccentricity, below 1
+    pub eccentricity: f64,
+    /// Inclination in degrees
+    pub inclination_deg: f64,
+    /// Longitude of the ascending node in degrees
+    pub ascending_node_deg: f64,
+    /// Argument of perihelion in degrees
+    pub argument_of_perihelion_deg: f64,
+    /// Mean anomaly at the epoch in degrees
+    pub mean_anomaly_deg: f64,
+}
+
+impl OrbitalElements {
+    /// Mean motion in degrees per day
+    pub fn mean_motion_deg_per_day(&self) -> f64 {
+        let a_km = self.semi_major_axis_au * AU_KM;
+        (GM_SUN / a_km.powi(3)).sqrt() * DAY_S / DEG2RAD
+    }
+
+    /// Heliocentric position in AU referred to the J2000 ecliptic and equinox
+    pub fn heliocentric_ecliptic(&self, jd_tdb: f64) -> Vector3<f64> {
+        let mean_anomaly =
+            self.mean_anomaly_deg + self.mean_motion_deg_per_day() * (jd_tdb - self.epoch_jd);
+        orbit_position(
+            self.semi_major_axis_au,
+            self.eccentricity,
+            self.inclination_deg * DEG2RAD,
+            self.ascending_node_deg * DEG2RAD,
+            self.argument_of_perihelion_deg * DEG2RAD,
+            (mean_anomaly * DEG2RAD).rem_euclid(TAU),
+        )
+    }
+
+    /// Heliocentric position in AU, J2000 equatorial axes
+    pub fn heliocentric_position(&self, jd_tdb: f64) -> Vector3<f64> {
+        INERTIAL_FRAMES["ECLIPJ2000"].transpose() * self.heliocentric_ecliptic(jd_tdb)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use approx::assert_relative_eq;
+
+    #[test]
+    fn test_kepler_solver() {
+        for &e in &[0.0, 0.1, 0.5, 0.9] {
+            for i in 0..12 {
+                let m = i as f64 * 0.5;
+                let big_e = solve_kepler(m, e);
+                assert_relative_eq!(big_e - e * big_e.sin(), m, epsilon = 1e-12);
+            }
+        }
+    }
+
+    #[test]
+    fn test_orbit_radii() {
+        let jd = 2_460_000.5;
+        for (body, a) in [
+            (Body::Mercury, 0.387),
+            (Body::EarthMoonBarycenter, 1.0),
+            (Body::Jupiter, 5.2),
+            (Body::Neptune, 30.07),
+        ] {
+            let r = heliocentric_ecliptic(body, jd).unwrap().norm();
+            assert!((r - a).abs() / a < 0.25, "{:?} at {} AU", body, r);
+        }
+        assert!(heliocentric_ecliptic(Body::Moon, jd).is_none());
+    }
+
+    #[test]
+    fn test_osculating_elements() {
+        // The Earth-Moon barycenter's own elements reproduce a one-year orbit
+        let elements = OrbitalElements {
+            epoch_jd: J2000,
+            semi_major_axis_au: 1.000_001,
+            eccentricity: 0.0167,
+            inclination_deg: 0.0,
+            ascending_node_deg: 0.0,
+            argument_of_perihelion_deg: 102.9,
+            mean_anomaly_deg: 357.5,
+        };
+        assert_relative_eq!(elements.mean_motion_deg_per_day(), 0.985_6, epsilon = 1e-4);
+
+        let start = elements.heliocentric_ecliptic(J2000);
+        let year = 360.0 / elements.mean_motion_deg_per_day();
+        assert_relative_eq!(
+            elements.heliocentric_ecliptic(J2000 + year),
+            start,
+            epsilon = 1e-9
+        );
+        let emb = heliocentric_ecliptic(Body::EarthMoonBarycenter, J2000).unwrap();
+        assert!(start.angle(&emb) < 0.01);
+        assert!(elements.heliocentric_position(J2000).z.abs() > 0.3);
+    }
+}
@@ -0,0 +1,123 @@
+//! Conversions between the Earth-Moon barycenter and the centre of the Earth
+//!
+//! Many ephemerides (JPL kernels included) describe the heliocentric or
+//! barycentric motion of the Earth-Moon barycenter (EMB) and give the Earth and
+//! Moon only as offsets from it. These helpers split or recombine those states
+//! using the geocentric Moon and the Earth/Moon mass ratio: the EMB lies on the
+//! Earth-Moon line at a fraction `1 / (1 + EMRAT)` of the way to the Moon.
+
+use super::PlanetState;
+
+/// Fraction of the geocentric Moon vector separating the Earth's centre from
+/// the Earth-Moon barycenter
+pub fn emb_fraction(earth_moon_mass_ratio: f64) -> f64 {
+    1.0 / (1.0 + earth_moon_mass_ratio)
+}
+
+/// Scale a state by a constant factor
+fn scaled(state: &PlanetState, factor: f64) -> PlanetState {
+    PlanetState {
+        position: state.position * factor,
+        velocity: state.velocity * factor,
+    }
+}
+
+/// Offset of the Earth-Moon barycenter from the Earth's centre
+pub fn emb_offset_from_earth(
+    moon_geocentric: &PlanetState,
+    earth_moon_mass_ratio: f64,
+) -> PlanetState {
+    scaled(moon_geocentric, emb_fraction(earth_moon_mass_ratio))
+}
+
+/// Convert an Earth-Moon barycenter state into the state of the Earth's centre
+///
+/// `emb` may be referred to any origin (Sun, solar-system barycenter); the
+/// result shares that origin. `moon_geocentric` is the Moon relative to the
+/// Earth's centre, in the same axes and units.
+pub fn earth_from_emb(
+    emb: &PlanetState,
+    moon_geocentric: &PlanetState,
+    earth_moon_mass_ratio: f64,
+) -> PlanetState {
+    let offset = emb_offset_from_earth(moon_geocentric, earth_moon_mass_ratio);
+    PlanetState {
+        position: emb.position - offset.position.coords,
+        velocity: emb.velocity - offset.velocity,
+    }
+}
+
+/// Convert a state of the Earth's centre into the Earth-Moon barycenter state
+pub fn emb_from_earth(
+    earth: &PlanetState,
+    moon_geocentric: &PlanetState,
+    earth_moon_mass_ratio: f64,
+) -> PlanetState {
+    let offset = emb_offset_from_earth(moon_geocentric, earth_moon_mass_ratio);
+    PlanetState {
+        position: earth.position + offset.position.coords,
+        velocity: earth.velocity + offset.velocity,
+    }
+}
+
+/// Convert an Earth-Moon barycenter state into the state of the Moon
+pub fn moon_from_emb(
+    emb: &PlanetState,
+    moon_geocentric: &PlanetState,
+    earth_moon_mass_ratio: f64,
+) -> PlanetState {
+    let factor = 1.0 - emb_fraction(earth_moon_mass_ratio);
+    let offset = scaled(moon_geocentric, factor);
+    PlanetState {
+        position: emb.position + offset.position.coords,
+        velocity: emb.velocity + offset.velocity,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::constants::EARTH_MOON_MASS_RATIO;
+    use approx::assert_relative_eq;
+    use nalgebra::{Point3, Vector3};
+
+    fn state(p: [f64; 3], v: [f64; 3]) -> PlanetState {
+        PlanetState {
+            position: Point3::new(p[0], p[1], p[2]),
+            velocity: Vector3::new(v[0], v[1], v[2]),
+        }
+    }
+
+    #[test]
+    fn test_emb_roundtrip() {
+        let emb = state([0.9, 0.4, 0.17], [-0.007, 0.015, 0.006]);
+        let moon = state([0.002, -0.0015, 0.0003], [0.0004, 0.0005, -0.0001]);
+
+        let earth = earth_from_emb(&emb, &moon, EARTH_MOON_MASS_RATIO);
+        let back = emb_from_earth(&earth, &moon, EARTH_MOON_MASS_RATIO);
+
+        assert_relative_eq!(back.position, emb.position, epsilon = 1e-15);
+        assert_relative_eq!(back.velocity, emb.velocity, epsilon = 1e-15);
+    }
+
+    #[test]
+    fn test_mass_weighted_barycenter() {
+        let emb = state([1.0, 0.0, 0.0], [0.0, 0.017, 0.0]);
+        let moon_geo = state([0.00257, 0.0, 0.0], [0.0, 0.0006, 0.0]);
+
+        let earth = earth_from_emb(&emb, &moon_geo, EARTH_MOON_MASS_RATIO);
+        let moon = moon_from_emb(&emb, &moon_geo, EARTH_MOON_MASS_RATIO);
+
+        // Moon - Earth recovers the geocentric Moon
+        assert_relative_eq!(
+            moon.position - earth.position,
+            moon_geo.position.coords,
+            epsilon = 1e-15
+        );
+
+        // Mass-weighted mean of Earth and Moon is the barycenter
+        let weighted = (earth.position.coords * EARTH_MOON_MASS_RATIO + moon.position.coords)
+            / (1.0 + EARTH_MOON_MASS_RATIO);
+        assert_relative_eq!(weighted, emb.position.coords, epsilon = 1e-15);
+    }
+}
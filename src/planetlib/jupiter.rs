@@ -0,0 +1,169 @@
+//! Jupiter central meridian longitudes and Great Red Spot transits
+//!
+//! Central meridian longitudes follow the low-accuracy method of Meeus,
+//! *Astronomical Algorithms* (2nd ed.), chapter 43, which includes light time
+//! and is good to about half a degree (a little over a minute of time). The
+//! correction for phase is not applied.
+
+use crate::constants::{DEG2RAD, J2000};
+use crate::time::Time;
+
+/// Rotation rate of Jupiter's System II in degrees per day (period 9h 55m 40.632s)
+pub const SYSTEM_II_RATE: f64 = 870.270_005_3;
+
+/// Longitudes of Jupiter's central meridian as seen from Earth, in degrees
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CentralMeridian {
+    /// System I longitude (equatorial zone)
+    pub system_i: f64,
+    /// System II longitude (temperate belts, including the Great Red Spot)
+    pub system_ii: f64,
+}
+
+/// Compute Jupiter's central meridian longitudes at a TT Julian date
+pub fn central_meridian(jd_tt: f64) -> CentralMeridian {
+    let d = jd_tt - J2000;
+
+    let v = (172.74 + 0.001_115_88 * d) * DEG2RAD;
+    let m = (357.529 + 0.985_600_3 * d) * DEG2RAD;
+    let n = (20.020 + 0.083_085_3 * d + 0.329 * v.sin()) * DEG2RAD;
+    let j = 66.115 + 0.902_517_9 * d - 0.329 * v.sin();
+
+    // Equations of centre of the Earth and Jupiter (degrees)
+    let a = 1.915 * m.sin() + 0.020 * (2.0 * m).sin();
+    let b = 5.555 * n.sin() + 0.168 * (2.0 * n).sin();
+    let k = (j + a - b) * DEG2RAD;
+
+    // Radius vectors of the Earth and Jupiter, and their separation (AU)
+    let big_r = 1.000_14 - 0.016_71 * m.cos() - 0.000_14 * (2.0 * m).cos();
+    let r = 5.208_72 - 0.252_08 * n.cos() - 0.006_11 * (2.0 * n).cos();
+    let delta = (r * r + big_r * big_r - 2.0 * r * big_r * k.cos()).sqrt();
+
+    // Phase angle of Jupiter seen from Earth (degrees)
+    let psi = (big_r / delta * k.sin()).asin() / DEG2RAD;
+
+    // Light time in days is delta / 173
+    let d_light = d - delta / 173.0;
+
+    CentralMeridian {
+        system_i: (210.98 + 877.816_908_8 * d_light + psi - b).rem_euclid(360.0),
+        system_ii: (187.23 + 870.186_908_8 * d_light + psi - b).rem_euclid(360.0),
+    }
+}
+
+/// Wrap an angle in degrees into [-180, 180)
+fn wrap_180(angle: f64) -> f64 {
+    (angle + 180.0).rem_euclid(360.0) - 180.0
+}
+
+/// Position model for the Great Red Spot in System II longitude
+///
+/// The spot drifts slowly and irregularly in System II, so its longitude has
+/// to come from recent observations (for example the JUPOS database or ALPO
+/// reports). Update the model as new measurements become available.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct GreatRedSpot {
+    /// System II longitude in degrees at the reference epoch
+    pub longitude: f64,
+    /// TT Julian date at which `longitude` was measured
+    pub epoch_jd: f64,
+    /// Drift in System II longitude, degrees per day
+    pub drift_per_day: f64,
+}
+
+impl GreatRedSpot {
+    /// Create a model with the spot at a fixed System II longitude measured at `epoch`
+    pub fn new(longitude_deg: f64, epoch: &Time) -> Self {
+        Self {
+            longitude: longitude_deg.rem_euclid(360.0),
+            epoch_jd: epoch.tt(),
+            drift_per_day: 0.0,
+        }
+    }
+
+    /// Set a linear drift rate in degrees per day
+    pub fn with_drift(mut self, drift_per_day: f64) -> Self {
+        self.drift_per_day = drift_per_day;
+        self
+    }
+
+    /// System II longitude of the spot at a TT Julian date
+    pub fn longitude_at(&self, jd_tt: f64) -> f64 {
+        (self.longitude + self.drift_per_day * (jd_tt - self.epoch_jd)).rem_euclid(360.0)
+    }
+
+    /// Predict the times at which the spot crosses Jupiter's central meridian
+    /// between `start` and `end`
+    ///
+    /// Transits are as seen from the Earth's centre, including light time;
+    /// whether Jupiter is observable at each transit is left to the caller.
+    pub fn transits(&self, start: &Time, end: &Time) -> Vec<Time> {
+        let ts = start.timescale();
+        let (jd_start, jd_end) = (start.tt(), end.tt());
+        let rate = SYSTEM_II_RATE - self.drift_per_day;
+
+        // Difference between central meridian and spot longitude, near zero at transit
+        let offset = |jd: f64| wrap_180(central_meridian(jd).system_ii - self.longitude_at(jd));
+
+        let mut transits = Vec::new();
+        let mut jd = jd_start + (-offset(jd_start)).rem_euclid(360.0) / rate;
+
+        while jd <= jd_end + 1.0 {
+            // Newton refinement; the rate is nearly constant over a rotation
+            for _ in 0..4 {
+                jd -= offset(jd) / rate;
+            }
+
+            if jd > jd_end {
+                break;
+            }
+            if jd >= jd_start {
+                transits.push(ts.tt_jd(jd, None));
+            }
+
+            jd += 360.0 / rate;
+        }
+
+        transits
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::time::Timescale;
+    use approx::assert_relative_eq;
+
+    #[test]
+    fn test_meeus_example_43a() {
+        // 1992 December 16, 0h UT (JDE 2448972.50068); Meeus gives
+        // omega1 = 268.06 and omega2 = 72.74 including the phase correction
+        let cm = central_meridian(2_448_972.500_68);
+        assert!(wrap_180(cm.system_i - 268.06).abs() < 0.6);
+        assert!(wrap_180(cm.system_ii - 72.74).abs() < 0.6);
+    }
+
+    #[test]
+    fn test_grs_transits() {
+        let ts = Timescale::default();
+        let start = ts.tt_jd(2_460_310.5, None);
+        let end = ts.tt_jd(2_460_313.5, None);
+
+        let grs = GreatRedSpot::new(55.0, &start).with_drift(0.05);
+        let transits = grs.transits(&start, &end);
+
+        // One transit per System II rotation
+        assert!((7..=8).contains(&transits.len()), "{}", transits.len());
+
+        for pair in transits.windows(2) {
+            let period_hours = (pair[1].tt() - pair[0].tt()) * 24.0;
+            assert_relative_eq!(period_hours, 9.926, epsilon = 0.01);
+        }
+
+        for t in &transits {
+            assert!(t.tt() >= start.tt() && t.tt() <= end.tt());
+            let cm = central_meridian(t.tt()).system_ii;
+            assert!(wrap_180(cm - grs.longitude_at(t.tt())).abs() < 1e-6);
+        }
+    }
+}
@@ -1,6 +1,45 @@
 //! Planetary ephemeris calculations module
+//!
+//! The built-in [`Ephemeris`] is a low-precision analytic model: mean
+//! Keplerian elements for the planets and the Earth-Moon barycenter, and a
+//! truncated ELP-2000/82 series for the Moon. The Sun is placed about the
+//! solar-system barycenter by the masses of the planets, so positions are
+//! barycentric, in AU, referred to J2000 equatorial (ICRF-aligned) axes.
+//!
+//! An [`Ephemeris`] can instead be backed by a JPL DE kernel (DE421, DE440,
+//! ...) through [`Ephemeris::from_kernel`], in which case positions and
+//! velocities come from the kernel, with the same barycentric origin.
 
+pub mod compare;
+pub mod elements;
+pub mod emb;
+pub mod jupiter;
+pub mod moon;
+pub mod orientation;
+pub mod physical;
+pub mod registry;
+pub mod resample;
+pub mod subpoint;
+pub mod table;
+
+pub use compare::{compare_ephemerides, EphemerisComparison, EphemerisProvider};
+pub use elements::OrbitalElements;
+pub use emb::{earth_from_emb, emb_from_earth, moon_from_emb};
+pub use jupiter::{central_meridian, GreatRedSpot};
+pub use orientation::{rotational_elements, RotationalElements};
+pub use physical::{physical_ephemeris, PhysicalEphemeris, SurfacePoint};
+pub use registry::{custom_body, register_body, CustomBody};
+pub use resample::StateSeries;
+pub use subpoint::{planetographic_to_planetocentric, sub_point, SubPoint};
+pub use table::{EphemerisTable, TableConfig};
+
+use crate::constants::{AU_KM, EARTH_MOON_MASS_RATIO, GM_SUN};
+use crate::framelib::INERTIAL_FRAMES;
+use crate::jplephem::{JplEphemError, SPK};
+use crate::time::TimeArray;
 use nalgebra::{Point3, Vector3};
+use ndarray::Array2;
+use std::sync::Arc;
 use thiserror::Error;
 
 /// Error type for planetary calculations
@@ -16,7 +55,20 @@ pub enum PlanetError {
     TimeError(String),
 }
 
+impl From<JplEphemError> for PlanetError {
+    fn from(err: JplEphemError) -> Self {
+        match err {
+            JplEphemError::SegmentNotFound { .. } => PlanetError::NotFound(err.to_string()),
+            JplEphemError::OutOfRange { .. } => PlanetError::TimeError(err.to_string()),
+            other => PlanetError::DataError(other.to_string()),
+        }
+    }
+}
+
 /// Enum representing the major solar system bodies
+///
+/// Other bodies are added with [`register_body`] and appear as
+/// [`Body::Custom`].
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum Body {
     Sun,
@@ -24,15 +76,34 @@ pub enum Body {
     Venus,
     Earth,
     Moon,
+    EarthMoonBarycenter,
     Mars,
     Jupiter,
     Saturn,
     Uranus,
     Neptune,
     Pluto,
+    /// A body added with [`register_body`], by NAIF ID
+    Custom(i32),
 }
 
 impl Body {
+    /// Every built-in body, Sun first
+    pub const ALL: [Body; 12] = [
+        Body::Sun,
+        Body::Mercury,
+        Body::Venus,
+        Body::Earth,
+        Body::Moon,
+        Body::EarthMoonBarycenter,
+        Body::Mars,
+        Body::Jupiter,
+        Body::Saturn,
+        Body::Uranus,
+        Body::Neptune,
+        Body::Pluto,
+    ];
+
     /// Get the body's name as a string
     pub fn name(&self) -> &'static str {
         match self {
@@ -41,12 +112,104 @@ impl Body {
             Body::Venus => "Venus",
             Body::Earth => "Earth",
             Body::Moon => "Moon",
+            Body::EarthMoonBarycenter => "Earth-Moon Barycenter",
             Body::Mars => "Mars",
             Body::Jupiter => "Jupiter",
             Body::Saturn => "Saturn",
             Body::Uranus => "Uranus",
             Body::Neptune => "Neptune",
             Body::Pluto => "Pluto",
+            Body::Custom(id) => custom_body(*id).map_or("unregistered body", |b| b.name),
+        }
+    }
+
+    /// Equatorial and polar radii in km (IAU WGCCRE 2015 values)
+    ///
+    /// The Earth-Moon barycenter has no physical extent and returns zeros.
+    pub fn radii_km(&self) -> (f64, f64) {
+        match self {
+            Body::Sun => (695_700.0, 695_700.0),
+            Body::Mercury => (2_440.53, 2_438.26),
+            Body::Venus => (6_051.8, 6_051.8),
+            Body::Earth => (6_378.136_6, 6_356.751_9),
+            Body::Moon => (1_737.4, 1_737.4),
+            Body::EarthMoonBarycenter => (0.0, 0.0),
+            Body::Mars => (3_396.19, 3_376.20),
+            Body::Jupiter => (71_492.0, 66_854.0),
+            Body::Saturn => (60_268.0, 54_364.0),
+            Body::Uranus => (25_559.0, 24_973.0),
+            Body::Neptune => (24_764.0, 24_341.0),
+            Body::Pluto => (1_188.3, 1_188.3),
+            Body::Custom(id) => custom_body(*id).map_or((0.0, 0.0), |b| (b.radius_km, b.radius_km)),
+        }
+    }
+
+    /// Gravitational parameter in km^3/s^2 (DE440 values)
+    ///
+    /// The outer planets and Mars include their satellites; a custom body
+    /// has one only if it was registered with it.
+    pub fn gm_km3_s2(&self) -> Option<f64> {
+        Some(match self {
+            Body::Sun => GM_SUN,
+            Body::Mercury => 22_031.868_551,
+            Body::Venus => 324_858.592,
+            Body::Earth => 398_600.435_507,
+            Body::Moon => 4_902.800_118,
+            Body::EarthMoonBarycenter => 403_503.235_625,
+            Body::Mars => 42_828.375_816,
+            Body::Jupiter => 126_712_764.1,
+            Body::Saturn => 37_940_584.841_8,
+            Body::Uranus => 5_794_556.4,
+            Body::Neptune => 6_836_527.100_58,
+            Body::Pluto => 975.5,
+            Body::Custom(id) => return custom_body(*id)?.gm_km3_s2,
+        })
+    }
+
+    /// NAIF integer ID code of the body's centre
+    pub fn naif_id(&self) -> i32 {
+        match self {
+            Body::Sun => 10,
+            Body::Mercury => 199,
+            Body::Venus => 299,
+            Body::Earth => 399,
+            Body::Moon => 301,
+            Body::EarthMoonBarycenter => 3,
+            Body::Mars => 499,
+            Body::Jupiter => 599,
+            Body::Saturn => 699,
+            Body::Uranus => 799,
+            Body::Neptune => 899,
+            Body::Pluto => 999,
+            Body::Custom(id) => *id,
+        }
+    }
+
+    /// Look up a body, built-in or registered, by its NAIF integer ID code
+    pub fn from_naif_id(id: i32) -> Option<Body> {
+        Self::ALL
+            .into_iter()
+            .find(|body| body.naif_id() == id)
+            .or_else(|| custom_body(id).map(|_| Body::Custom(id)))
+    }
+
+    /// Look up a body, built-in or registered, by its name, ignoring case
+    pub fn from_name(name: &str) -> Option<Body> {
+        let name = name.trim();
+        Self::ALL
+            .into_iter()
+            .find(|body| body.name().eq_ignore_ascii_case(name))
+            .or_else(|| registry::find_by_name(name).map(Body::Custom))
+    }
+
+    /// NAIF ID of the barycenter of the body's planetary system, if it has one
+    ///
+    /// DE kernels such as DE440 only carry the outer planets as system
+    /// barycenters.
+    pub fn naif_system_barycenter(&self) -> Option<i32> {
+        match self.naif_id() {
+            id @ 199..=999 if id % 100 == 99 => Some(id / 100),
+            _ => None,
         }
     }
 }
@@ -60,27 +223,262 @@ pub struct PlanetState {
     pub velocity: Vector3<f64>,
 }
 
-/// Placeholder structure for planetary ephemeris
-#[derive(Debug)]
+/// Step in days used for numerical velocities
+const VELOCITY_STEP_DAYS: f64 = 0.01;
+
+/// Sun/body mass ratios of the bodies that move the Sun about the
+/// solar-system barycenter (IAU 2009 System of Astronomical Constants)
+const SUN_MASS_RATIOS: [(Body, f64); 8] = [
+    (Body::Mercury, 6_023_600.0),
+    (Body::Venus, 408_523.71),
+    (Body::EarthMoonBarycenter, 328_900.56),
+    (Body::Mars, 3_098_708.0),
+    (Body::Jupiter, 1_047.348_6),
+    (Body::Saturn, 3_497.898),
+    (Body::Uranus, 22_902.98),
+    (Body::Neptune, 19_412.24),
+];
+
+/// Planetary ephemeris: the built-in analytic model or a JPL kernel
+#[derive(Debug, Clone)]
 pub struct Ephemeris {
-    // This will be implemented in a future version
+    /// Earth/Moon mass ratio used to split the Earth-Moon barycenter
+    earth_moon_mass_ratio: f64,
+    /// Kernel supplying positions in place of the analytic model
+    kernel: Option<Arc<SPK>>,
 }
 
 impl Ephemeris {
-    /// Create a new empty ephemeris
+    /// Create an ephemeris using the IAU 2009 Earth/Moon mass ratio
     pub fn new() -> Self {
-        Self {}
+        Self {
+            earth_moon_mass_ratio: EARTH_MOON_MASS_RATIO,
+            kernel: None,
+        }
+    }
+
+    /// Create an ephemeris backed by a JPL DE kernel
+    ///
+    /// Bodies are looked up by NAIF ID and chained to the solar-system
+    /// barycenter. A planet whose centre is missing from the kernel (the
+    /// outer planets in DE440) falls back to its system barycenter.
+    pub fn from_kernel(kernel: SPK) -> Self {
+        Self {
+            earth_moon_mass_ratio: EARTH_MOON_MASS_RATIO,
+            kernel: Some(Arc::new(kernel)),
+        }
+    }
+
+    /// Get the kernel backing this ephemeris, if any
+    pub fn kernel(&self) -> Option<&SPK> {
+        self.kernel.as_deref()
     }
 
-    /// Placeholder for getting a planet's state at a given time
-    pub fn get_state(&self, _body: Body, _jd: f64) -> Result<PlanetState, PlanetError> {
-        // In a real implementation, this would calculate positions
-        // For now, return a dummy position at the origin
+    /// Override the Earth/Moon mass ratio (e.g. to match a JPL kernel's `EMRAT`)
+    pub fn with_earth_moon_mass_ratio(mut self, ratio: f64) -> Self {
+        self.earth_moon_mass_ratio = ratio;
+        self
+    }
+
+    /// Get the Earth/Moon mass ratio used by this ephemeris
+    pub fn earth_moon_mass_ratio(&self) -> f64 {
+        self.earth_moon_mass_ratio
+    }
+
+    /// Get a body's state at a TDB Julian date
+    ///
+    /// Position is in AU and velocity in AU/day, in J2000 equatorial axes,
+    /// relative to the solar-system barycenter for either backend.
+    pub fn get_state(&self, body: Body, jd: f64) -> Result<PlanetState, PlanetError> {
+        if !jd.is_finite() {
+            return Err(PlanetError::TimeError(format!(
+                "non-finite Julian date {}",
+                jd
+            )));
+        }
+
+        if let Some(kernel) = &self.kernel {
+            let (position, velocity) = self.kernel_state(kernel, body, jd)?;
+            return Ok(PlanetState {
+                position: Point3::from(position),
+                velocity,
+            });
+        }
+
+        let position = self.position(body, jd)?;
+        let ahead = self.position(body, jd + VELOCITY_STEP_DAYS)?;
+        let behind = self.position(body, jd - VELOCITY_STEP_DAYS)?;
+
         Ok(PlanetState {
-            position: Point3::new(0.0, 0.0, 0.0),
-            velocity: Vector3::new(0.0, 0.0, 0.0),
+            position: Point3::from(position),
+            velocity: (ahead - behind) / (2.0 * VELOCITY_STEP_DAYS),
         })
     }
+
+    /// Get a body's positions at every time in an array
+    ///
+    /// Rows are the times and columns x, y, z in AU, in the same axes and
+    /// with the same origin as [`Ephemeris::get_state`]. Velocities are not
+    /// computed, which makes this several times cheaper per epoch than
+    /// calling `get_state` in a loop.
+    pub fn get_positions(&self, body: Body, times: &TimeArray) -> Result<Array2<f64>, PlanetError> {
+        let tdb = times.tdb();
+        let mut positions = Array2::zeros((tdb.len(), 3));
+        for (jd, mut row) in tdb.iter().zip(positions.rows_mut()) {
+            if !jd.is_finite() {
+                return Err(PlanetError::TimeError(format!(
+                    "non-finite Julian date {}",
+                    jd
+                )));
+            }
+            let position = self.position(body, *jd)?;
+            row.assign(&ndarray::arr1(position.as_slice()));
+        }
+        Ok(positions)
+    }
+
+    /// Get the Moon's state relative to the Earth's centre (AU, AU/day)
+    pub fn geocentric_moon(&self, jd: f64) -> Result<PlanetState, PlanetError> {
+        if !jd.is_finite() {
+            return Err(PlanetError::TimeError(format!(
+                "non-finite Julian date {}",
+                jd
+            )));
+        }
+
+        if self.kernel.is_some() {
+            let moon = self.get_state(Body::Moon, jd)?;
+            let earth = self.get_state(Body::Earth, jd)?;
+            return Ok(PlanetState {
+                position: Point3::from(moon.position - earth.position),
+                velocity: moon.velocity - earth.velocity,
+            });
+        }
+
+        Ok(analytic_geocentric_moon(jd))
+    }
+
+    /// Convert an Earth-Moon barycenter state into an Earth-centre state using
+    /// this ephemeris' Moon and mass ratio
+    pub fn earth_from_emb(&self, emb: &PlanetState, jd: f64) -> Result<PlanetState, PlanetError> {
+        let moon = self.geocentric_moon(jd)?;
+        Ok(earth_from_emb(emb, &moon, self.earth_moon_mass_ratio))
+    }
+
+    /// Convert an Earth-centre state into an Earth-Moon barycenter state using
+    /// this ephemeris' Moon and mass ratio
+    pub fn emb_from_earth(&self, earth: &PlanetState, jd: f64) -> Result<PlanetState, PlanetError> {
+        let moon = self.geocentric_moon(jd)?;
+        Ok(emb_from_earth(earth, &moon, self.earth_moon_mass_ratio))
+    }
+
+    /// Barycentric position in AU, J2000 equatorial axes
+    pub(crate) fn position(&self, body: Body, jd: f64) -> Result<Vector3<f64>, PlanetError> {
+        match &self.kernel {
+            Some(kernel) => Ok(self.kernel_state(kernel, body, jd)?.0),
+            None => Ok(self.heliocentric(body, jd)? + analytic_sun(jd)),
+        }
+    }
+
+    /// Heliocentric position of a body in the analytic model, in AU
+    fn heliocentric(&self, body: Body, jd: f64) -> Result<Vector3<f64>, PlanetError> {
+        let fraction = emb::emb_fraction(self.earth_moon_mass_ratio);
+        Ok(match body {
+            Body::Sun => Vector3::zeros(),
+            Body::Earth => {
+                let moon = moon::geocentric_position_km(jd) / AU_KM;
+                self.heliocentric(Body::EarthMoonBarycenter, jd)? - moon * fraction
+            }
+            Body::Moon => {
+                let moon = moon::geocentric_position_km(jd) / AU_KM;
+                self.heliocentric(Body::EarthMoonBarycenter, jd)? + moon * (1.0 - fraction)
+            }
+            Body::Custom(id) => custom_body(id)
+                .and_then(|b| b.elements)
+                .ok_or_else(|| {
+                    PlanetError::NotFound(format!(
+                        "{} has no orbital elements; load a kernel for it",
+                        body.name()
+                    ))
+                })?
+                .heliocentric_position(jd),
+            _ => {
+                let ecliptic = elements::heliocentric_ecliptic(body, jd).ok_or_else(|| {
+                    PlanetError::NotFound(format!(
+                        "{} has no orbital elements; load a kernel for it",
+                        body.name()
+                    ))
+                })?;
+                INERTIAL_FRAMES["ECLIPJ2000"].transpose() * ecliptic
+            }
+        })
+    }
+
+    /// Barycentric position (AU) and velocity (AU/day) of a body from a kernel
+    ///
+    /// Kernels that carry only the Earth-Moon barycenter get the Earth and
+    /// Moon split out of it with the analytic Moon and this ephemeris' mass
+    /// ratio.
+    fn kernel_state(
+        &self,
+        kernel: &SPK,
+        body: Body,
+        jd: f64,
+    ) -> Result<(Vector3<f64>, Vector3<f64>), PlanetError> {
+        let state = match kernel.barycentric_state(body.naif_id(), jd) {
+            Err(JplEphemError::SegmentNotFound { target, .. }) if target == body.naif_id() => {
+                match body {
+                    Body::Earth | Body::Moon => {
+                        let (position, velocity) =
+                            kernel.barycentric_state(Body::EarthMoonBarycenter.naif_id(), jd)?;
+                        let emb = PlanetState {
+                            position: Point3::from(position / AU_KM),
+                            velocity: velocity / AU_KM,
+                        };
+                        let moon = analytic_geocentric_moon(jd);
+                        let state = match body {
+                            Body::Earth => earth_from_emb(&emb, &moon, self.earth_moon_mass_ratio),
+                            _ => moon_from_emb(&emb, &moon, self.earth_moon_mass_ratio),
+                        };
+                        return Ok((state.position.coords, state.velocity));
+                    }
+                    _ => match body.naif_system_barycenter() {
+                        Some(barycenter) => kernel.barycentric_state(barycenter, jd),
+                        None => Err(JplEphemError::SegmentNotFound { center: 0, target }),
+                    },
+                }
+            }
+            other => other,
+        };
+        let (position, velocity) = state?;
+        Ok((position / AU_KM, velocity / AU_KM))
+    }
+}
+
+/// The Sun's position relative to the solar-system barycenter from the
+/// mean elements of the planets, in AU, J2000 equatorial axes
+fn analytic_sun(jd: f64) -> Vector3<f64> {
+    let (weighted, total) = SUN_MASS_RATIOS.iter().fold(
+        (Vector3::zeros(), 1.0),
+        |(weighted, total), &(body, ratio)| {
+            let position = elements::heliocentric_ecliptic(body, jd).unwrap_or_default();
+            (weighted + position / ratio, total + 1.0 / ratio)
+        },
+    );
+    INERTIAL_FRAMES["ECLIPJ2000"].transpose() * (-weighted / total)
+}
+
+/// The Moon's state relative to the Earth's centre from the ELP-2000/82
+/// series (AU, AU/day)
+fn analytic_geocentric_moon(jd: f64) -> PlanetState {
+    let position = moon::geocentric_position_km(jd) / AU_KM;
+    let ahead = moon::geocentric_position_km(jd + VELOCITY_STEP_DAYS) / AU_KM;
+    let behind = moon::geocentric_position_km(jd - VELOCITY_STEP_DAYS) / AU_KM;
+
+    PlanetState {
+        position: Point3::from(position),
+        velocity: (ahead - behind) / (2.0 * VELOCITY_STEP_DAYS),
+    }
 }
 
 impl Default for Ephemeris {
@@ -88,3 +486,181 @@ impl Default for Ephemeris {
         Self::new()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::constants::{DAY_S, J2000};
+    use crate::time::TimeDelta;
+    use approx::assert_relative_eq;
+
+    #[test]
+    fn test_earth_sun_distance() {
+        let eph = Ephemeris::new();
+        // Near perihelion (early January) and aphelion (early July) 2024
+        let from_sun = |jd| {
+            let earth = eph.get_state(Body::Earth, jd).unwrap();
+            let sun = eph.get_state(Body::Sun, jd).unwrap();
+            (earth.position - sun.position, earth.velocity - sun.velocity)
+        };
+        let (jan, jan_velocity) = from_sun(2_460_314.5);
+        let (jul, _) = from_sun(2_460_496.5);
+        assert_relative_eq!(jan.norm(), 0.9833, epsilon = 2e-3);
+        assert_relative_eq!(jul.norm(), 1.0167, epsilon = 2e-3);
+
+        // Orbital speed is about 0.0172 AU/day
+        assert_relative_eq!(jan_velocity.norm(), 0.0172, epsilon = 5e-4);
+    }
+
+    #[test]
+    fn test_emb_split_consistency() {
+        let eph = Ephemeris::new();
+        let jd = J2000 + 1234.5;
+
+        let earth = eph.get_state(Body::Earth, jd).unwrap();
+        let moon = eph.get_state(Body::Moon, jd).unwrap();
+        let emb = eph.get_state(Body::EarthMoonBarycenter, jd).unwrap();
+
+        let from_emb = eph.earth_from_emb(&emb, jd).unwrap();
+        assert_relative_eq!(from_emb.position, earth.position, epsilon = 1e-12);
+        assert_relative_eq!(from_emb.velocity, earth.velocity, epsilon = 1e-12);
+
+        let back = eph.emb_from_earth(&earth, jd).unwrap();
+        assert_relative_eq!(back.position, emb.position, epsilon = 1e-12);
+
+        // Earth-Moon separation is ~0.0026 AU
+        let separation = (moon.position - earth.position).norm();
+        assert!((0.0023..0.0028).contains(&separation));
+    }
+
+    #[test]
+    fn test_positions_over_time_array() {
+        let ts = crate::time::Timescale::default();
+        let t0 = ts.tt_jd(J2000, None);
+        let times = ts.linspace(&t0, &(&t0 + TimeDelta::days(365.0)), 50);
+        let eph = Ephemeris::new();
+
+        let positions = eph.get_positions(Body::Mars, &times).unwrap();
+        assert_eq!(positions.dim(), (50, 3));
+        for (i, t) in times.iter().enumerate() {
+            let state = eph.get_state(Body::Mars, t.tdb()).unwrap();
+            for k in 0..3 {
+                assert_relative_eq!(positions[(i, k)], state.position[k], epsilon = 1e-12);
+            }
+        }
+    }
+
+    #[test]
+    fn test_rejects_non_finite_dates() {
+        let eph = Ephemeris::new();
+        assert!(eph.get_state(Body::Mars, f64::NAN).is_err());
+    }
+
+    #[test]
+    fn test_kernel_backed_states() {
+        use crate::jplephem::spk::testing::{chebyshev_array, spk};
+
+        let mut earth = chebyshev_array(399, J2000, 32.0, 2);
+        earth.ints[1] = 3;
+        let mut moon = chebyshev_array(301, J2000, 32.0, 2);
+        moon.ints[1] = 3;
+        moon.data[2] = -1000.0;
+        let kernel = spk(&[
+            chebyshev_array(3, J2000, 32.0, 2),
+            earth,
+            moon,
+            chebyshev_array(5, J2000, 32.0, 2),
+        ]);
+        let eph = Ephemeris::from_kernel(kernel);
+        let jd = J2000 + 16.0;
+
+        // Earth is EMB (0 -> 3) plus the Earth offset (3 -> 399)
+        let (emb, emb_velocity) = eph
+            .kernel()
+            .unwrap()
+            .compute_and_differentiate(0, 3, jd)
+            .unwrap();
+        let state = eph.get_state(Body::Earth, jd).unwrap();
+        assert_relative_eq!(state.position.coords, emb * 2.0 / AU_KM, epsilon = 1e-15);
+        assert_relative_eq!(state.velocity, emb_velocity * 2.0 / AU_KM, epsilon = 1e-15);
+
+        // Jupiter's centre is missing, so its system barycenter stands in
+        let jupiter = eph.get_state(Body::Jupiter, jd).unwrap();
+        assert_relative_eq!(jupiter.position.coords, emb / AU_KM, epsilon = 1e-15);
+
+        let moon = eph.geocentric_moon(jd).unwrap();
+        assert_relative_eq!(moon.position.x * AU_KM, -2000.0, epsilon = 1e-6);
+
+        assert!(matches!(
+            eph.get_state(Body::Mars, jd),
+            Err(PlanetError::NotFound(_))
+        ));
+        assert!(matches!(
+            eph.get_state(Body::Earth, J2000 + 40.0),
+            Err(PlanetError::TimeError(_))
+        ));
+    }
+
+    #[test]
+    fn test_kernel_with_only_emb_splits_earth_and_moon() {
+        use crate::jplephem::spk::testing::{chebyshev_array, spk};
+
+        let eph = Ephemeris::from_kernel(spk(&[chebyshev_array(3, J2000, 32.0, 2)]));
+        let jd = J2000 + 16.0;
+        let emb = eph.get_state(Body::EarthMoonBarycenter, jd).unwrap();
+        let earth = eph.get_state(Body::Earth, jd).unwrap();
+        let moon = eph.get_state(Body::Moon, jd).unwrap();
+
+        // The Earth sits ~4,700 km from the EMB, on the far side from the Moon
+        let offset_km = (earth.position - emb.position).norm() * AU_KM;
+        assert!((4_000.0..5_000.0).contains(&offset_km), "{}", offset_km);
+        let geocentric = eph.geocentric_moon(jd).unwrap();
+        assert_relative_eq!(
+            geocentric.position.coords,
+            analytic_geocentric_moon(jd).position.coords,
+            epsilon = 1e-12
+        );
+        assert_relative_eq!(
+            moon.position - earth.position,
+            geocentric.position.coords,
+            epsilon = 1e-12
+        );
+    }
+
+    #[test]
+    fn test_bodies_without_elements_are_errors() {
+        let eph = Ephemeris::new();
+        assert!(matches!(
+            eph.get_state(Body::Custom(-999_999), J2000),
+            Err(PlanetError::NotFound(_))
+        ));
+    }
+
+    #[test]
+    fn test_backends_share_the_barycentric_origin() {
+        use crate::jplephem::daf::testing::Array;
+        use crate::jplephem::spk::testing::spk;
+
+        // The Sun relative to the solar-system barycenter at J2000 in DE440
+        let sun_au = Vector3::new(-7.139_8e-3, -2.644_3e-3, -9.213e-4);
+        let start = -16.0 * DAY_S;
+        let radius = 16.0 * DAY_S;
+        let mut data = vec![start + radius, radius];
+        for coordinate in sun_au.iter() {
+            data.extend([coordinate * AU_KM, 0.0, 0.0]);
+        }
+        data.extend([start, 2.0 * radius, 11.0, 1.0]);
+        let kernel = Ephemeris::from_kernel(spk(&[Array {
+            name: "SUN".to_string(),
+            doubles: vec![start, start + 2.0 * radius],
+            ints: vec![10, 0, 1, 2],
+            data,
+        }]));
+
+        let from_kernel = kernel.get_state(Body::Sun, J2000).unwrap();
+        let analytic = Ephemeris::new().get_state(Body::Sun, J2000).unwrap();
+        assert_relative_eq!(from_kernel.position, Point3::from(sun_au), epsilon = 1e-12);
+        // Agreement to ~3,000 km, against an offset of ~1.1 million km
+        assert_relative_eq!(analytic.position, from_kernel.position, epsilon = 2e-5);
+    }
+}
@@ -0,0 +1,245 @@
+//! Geocentric position of the Moon
+//!
+//! Implements the truncated ELP-2000/82 series given in chapter 47 of Meeus,
+//! *Astronomical Algorithms* (2nd ed.), good to roughly 10″ in longitude and
+//! 4″ in latitude.
+
+use crate::constants::{DEG2RAD, J2000, TAU};
+use crate::framelib::rot_x;
+use crate::precessionlib::{compute_precession, mean_obliquity};
+use nalgebra::Vector3;
+
+/// Periodic terms for longitude (1e-6 deg) and distance (1e-3 km)
+///
+/// Columns: D, M, M', F, Σl, Σr
+const LON_DIST_TERMS: [(i8, i8, i8, i8, f64, f64); 60] = [
+    (0, 0, 1, 0, 6_288_774.0, -20_905_355.0),
+    (2, 0, -1, 0, 1_274_027.0, -3_699_111.0),
+    (2, 0, 0, 0, 658_314.0, -2_955_968.0),
+    (0, 0, 2, 0, 213_618.0, -569_925.0),
+    (0, 1, 0, 0, -185_116.0, 48_888.0),
+    (0, 0, 0, 2, -114_332.0, -3_149.0),
+    (2, 0, -2, 0, 58_793.0, 246_158.0),
+    (2, -1, -1, 0, 57_066.0, -152_138.0),
+    (2, 0, 1, 0, 53_322.0, -170_733.0),
+    (2, -1, 0, 0, 45_758.0, -204_586.0),
+    (0, 1, -1, 0, -40_923.0, -129_620.0),
+    (1, 0, 0, 0, -34_720.0, 108_743.0),
+    (0, 1, 1, 0, -30_383.0, 104_755.0),
+    (2, 0, 0, -2, 15_327.0, 10_321.0),
+    (0, 0, 1, 2, -12_528.0, 0.0),
+    (0, 0, 1, -2, 10_980.0, 79_661.0),
+    (4, 0, -1, 0, 10_675.0, -34_782.0),
+    (0, 0, 3, 0, 10_034.0, -23_210.0),
+    (4, 0, -2, 0, 8_548.0, -21_636.0),
+    (2, 1, -1, 0, -7_888.0, 24_208.0),
+    (2, 1, 0, 0, -6_766.0, 30_824.0),
+    (1, 0, -1, 0, -5_163.0, -8_379.0),
+    (1, 1, 0, 0, 4_987.0, -16_675.0),
+    (2, -1, 1, 0, 4_036.0, -12_831.0),
+    (2, 0, 2, 0, 3_994.0, -10_445.0),
+    (4, 0, 0, 0, 3_861.0, -11_650.0),
+    (2, 0, -3, 0, 3_665.0, 14_403.0),
+    (0, 1, -2, 0, -2_689.0, -7_003.0),
+    (2, 0, -1, 2, -2_602.0, 0.0),
+    (2, -1, -2, 0, 2_390.0, 10_056.0),
+    (1, 0, 1, 0, -2_348.0, 6_322.0),
+    (2, -2, 0, 0, 2_236.0, -9_884.0),
+    (0, 1, 2, 0, -2_120.0, 5_751.0),
+    (0, 2, 0, 0, -2_069.0, 0.0),
+    (2, -2, -1, 0, 2_048.0, -4_950.0),
+    (2, 0, 1, -2, -1_773.0, 4_130.0),
+    (2, 0, 0, 2, -1_595.0, 0.0),
+    (4, -1, -1, 0, 1_215.0, -3_958.0),
+    (0, 0, 2, 2, -1_110.0, 0.0),
+    (3, 0, -1, 0, -892.0, 3_258.0),
+    (2, 1, 1, 0, -810.0, 2_616.0),
+    (4, -1, -2, 0, 759.0, -1_897.0),
+    (0, 2, -1, 0, -713.0, -2_117.0),
+    (2, 2, -1, 0, -700.0, 2_354.0),
+    (2, 1, -2, 0, 691.0, 0.0),
+    (2, -1, 0, -2, 596.0, 0.0),
+    (4, 0, 1, 0, 549.0, -1_423.0),
+    (0, 0, 4, 0, 537.0, -1_117.0),
+    (4, -1, 0, 0, 520.0, -1_571.0),
+    (1, 0, -2, 0, -487.0, -1_739.0),
+    (2, 1, 0, -2, -399.0, 0.0),
+    (0, 0, 2, -2, -381.0, -4_421.0),
+    (1, 1, 1, 0, 351.0, 0.0),
+    (3, 0, -2, 0, -340.0, 0.0),
+    (4, 0, -3, 0, 330.0, 0.0),
+    (2, -1, 2, 0, 327.0, 0.0),
+    (0, 2, 1, 0, -323.0, 1_165.0),
+    (1, 1, -1, 0, 299.0, 0.0),
+    (2, 0, 3, 0, 294.0, 0.0),
+    (2, 0, -1, -2, 0.0, 8_752.0),
+];
+
+/// Periodic terms for latitude (1e-6 deg)
+///
+/// Columns: D, M, M', F, Σb
+const LAT_TERMS: [(i8, i8, i8, i8, f64); 60] = [
+    (0, 0, 0, 1, 5_128_122.0),
+    (0, 0, 1, 1, 280_602.0),
+    (0, 0, 1, -1, 277_693.0),
+    (2, 0, 0, -1, 173_237.0),
+    (2, 0, -1, 1, 55_413.0),
+    (2, 0, -1, -1, 46_271.0),
+    (2, 0, 0, 1, 32_573.0),
+    (0, 0, 2, 1, 17_198.0),
+    (2, 0, 1, -1, 9_266.0),
+    (0, 0, 2, -1, 8_822.0),
+    (2, -1, 0, -1, 8_216.0),
+    (2, 0, -2, -1, 4_324.0),
+    (2, 0, 1, 1, 4_200.0),
+    (2, 1, 0, -1, -3_359.0),
+    (2, -1, -1, 1, 2_463.0),
+    (2, -1, 0, 1, 2_211.0),
+    (2, -1, -1, -1, 2_065.0),
+    (0, 1, -1, -1, -1_870.0),
+    (4, 0, -1, -1, 1_828.0),
+    (0, 1, 0, 1, -1_794.0),
+    (0, 0, 0, 3, -1_749.0),
+    (0, 1, -1, 1, -1_565.0),
+    (1, 0, 0, 1, -1_491.0),
+    (0, 1, 1, 1, -1_475.0),
+    (0, 1, 1, -1, -1_410.0),
+    (0, 1, 0, -1, -1_344.0),
+    (1, 0, 0, -1, -1_335.0),
+    (0, 0, 3, 1, 1_107.0),
+    (4, 0, 0, -1, 1_021.0),
+    (4, 0, -1, 1, 833.0),
+    (0, 0, 1, -3, 777.0),
+    (4, 0, -2, 1, 671.0),
+    (2, 0, 0, -3, 607.0),
+    (2, 0, 2, -1, 596.0),
+    (2, -1, 1, -1, 491.0),
+    (2, 0, -2, 1, -451.0),
+    (0, 0, 3, -1, 439.0),
+    (2, 0, 2, 1, 422.0),
+    (2, 0, -3, -1, 421.0),
+    (2, 1, -1, 1, -366.0),
+    (2, 1, 0, 1, -351.0),
+    (4, 0, 0, 1, 331.0),
+    (2, -1, 1, 1, 315.0),
+    (2, -2, 0, -1, 302.0),
+    (0, 0, 1, 3, -283.0),
+    (2, 1, 1, -1, -229.0),
+    (1, 1, 0, -1, 223.0),
+    (1, 1, 0, 1, 223.0),
+    (0, 1, -2, -1, -220.0),
+    (2, 1, -1, -1, -220.0),
+    (1, 0, 1, 1, -185.0),
+    (2, -1, -2, -1, 181.0),
+    (0, 1, 2, 1, -177.0),
+    (4, 0, -2, -1, 176.0),
+    (4, -1, -1, -1, 166.0),
+    (1, 0, 1, -1, -164.0),
+    (4, 0, 1, -1, 132.0),
+    (1, 0, -1, -1, -119.0),
+    (4, -1, 0, -1, 115.0),
+    (2, -2, 0, 1, 107.0),
+];
+
+/// Geocentric ecliptic longitude, latitude (radians) and distance (km) of the
+/// Moon, referred to the mean ecliptic and equinox of date
+pub fn geocentric_ecliptic_of_date(jd_tdb: f64) -> (f64, f64, f64) {
+    let t = (jd_tdb - J2000) / 36525.0;
+    let t2 = t * t;
+    let t3 = t2 * t;
+    let t4 = t3 * t;
+
+    // Mean longitude, elongation, anomalies and argument of latitude (degrees)
+    let l_prime = 218.316_447_7 + 481_267.881_234_21 * t - 0.001_578_6 * t2 + t3 / 538_841.0
+        - t4 / 65_194_000.0;
+    let d = 297.850_192_1 + 445_267.111_403_4 * t - 0.001_881_9 * t2 + t3 / 545_868.0
+        - t4 / 113_065_000.0;
+    let m = 357.529_109_2 + 35_999.050_290_9 * t - 0.000_153_6 * t2 + t3 / 24_490_000.0;
+    let m_prime = 134.963_396_4 + 477_198.867_505_5 * t + 0.008_741_4 * t2 + t3 / 69_699.0
+        - t4 / 14_712_000.0;
+    let f = 93.272_095_0 + 483_202.017_523_3 * t - 0.003_653_9 * t2 - t3 / 3_526_000.0
+        + t4 / 863_310_000.0;
+
+    let a1 = (119.75 + 131.849 * t) * DEG2RAD;
+    let a2 = (53.09 + 479_264.290 * t) * DEG2RAD;
+    let a3 = (313.45 + 481_266.484 * t) * DEG2RAD;
+
+    // Eccentricity of Earth's orbit damps the terms involving M
+    let e = 1.0 - 0.002_516 * t - 0.000_007_4 * t2;
+    let e_factor = |m_mult: i8| match m_mult.abs() {
+        1 => e,
+        2 => e * e,
+        _ => 1.0,
+    };
+
+    let (d_r, m_r, mp_r, f_r) = (d * DEG2RAD, m * DEG2RAD, m_prime * DEG2RAD, f * DEG2RAD);
+    let lp_r = l_prime * DEG2RAD;
+    let argument = |cd: i8, cm: i8, cmp: i8, cf: i8| {
+        cd as f64 * d_r + cm as f64 * m_r + cmp as f64 * mp_r + cf as f64 * f_r
+    };
+
+    let mut sum_l = 0.0;
+    let mut sum_r = 0.0;
+    for &(cd, cm, cmp, cf, sl, sr) in LON_DIST_TERMS.iter() {
+        let arg = argument(cd, cm, cmp, cf);
+        let ef = e_factor(cm);
+        sum_l += sl * ef * arg.sin();
+        sum_r += sr * ef * arg.cos();
+    }
+
+    let mut sum_b = 0.0;
+    for &(cd, cm, cmp, cf, sb) in LAT_TERMS.iter() {
+        sum_b += sb * e_factor(cm) * argument(cd, cm, cmp, cf).sin();
+    }
+
+    // Additive terms for Venus, Jupiter and the flattening of the Earth
+    sum_l += 3958.0 * a1.sin() + 1962.0 * (lp_r - f_r).sin() + 318.0 * a2.sin();
+    sum_b += -2235.0 * lp_r.sin()
+        + 382.0 * a3.sin()
+        + 175.0 * (a1 - f_r).sin()
+        + 175.0 * (a1 + f_r).sin()
+        + 127.0 * (lp_r - mp_r).sin()
+        - 115.0 * (lp_r + mp_r).sin();
+
+    let longitude = ((l_prime + sum_l / 1_000_000.0) * DEG2RAD).rem_euclid(TAU);
+    let latitude = (sum_b / 1_000_000.0) * DEG2RAD;
+    let distance_km = 385_000.56 + sum_r / 1000.0;
+
+    (longitude, latitude, distance_km)
+}
+
+/// Geocentric position of the Moon in km, in J2000 equatorial (ICRF) axes
+pub fn geocentric_position_km(jd_tdb: f64) -> Vector3<f64> {
+    let (lon, lat, dist) = geocentric_ecliptic_of_date(jd_tdb);
+    let ecliptic = Vector3::new(
+        dist * lat.cos() * lon.cos(),
+        dist * lat.cos() * lon.sin(),
+        dist * lat.sin(),
+    );
+
+    // Ecliptic of date -> equator of date -> J2000 equator
+    let equator_of_date = rot_x(mean_obliquity(jd_tdb)) * ecliptic;
+    compute_precession(jd_tdb).transpose() * equator_of_date
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use approx::assert_relative_eq;
+
+    #[test]
+    fn test_meeus_example_47a() {
+        // 1992 April 12, 0h TD
+        let (lon, lat, dist) = geocentric_ecliptic_of_date(2_448_724.5);
+        assert_relative_eq!(lon / DEG2RAD, 133.162_655, epsilon = 1e-5);
+        assert_relative_eq!(lat / DEG2RAD, -3.229_126, epsilon = 1e-5);
+        assert_relative_eq!(dist, 368_409.7, epsilon = 0.1);
+    }
+
+    #[test]
+    fn test_equatorial_distance_preserved() {
+        let jd = 2_460_000.5;
+        let (_, _, dist) = geocentric_ecliptic_of_date(jd);
+        assert_relative_eq!(geocentric_position_km(jd).norm(), dist, epsilon = 1e-6);
+    }
+}
@@ -0,0 +1,187 @@
+//! Rotational elements of the Sun, Moon and planets
+//!
+//! Pole directions and prime-meridian angles follow the IAU Working Group on
+//! Cartographic Coordinates and Rotational Elements reports (Archinal et al.
+//! 2011/2018). Small periodic terms are omitted for the planets; the Moon
+//! includes its full set of libration terms.
+
+use super::Body;
+use crate::constants::{DEG2RAD, J2000};
+use crate::framelib::{rot_x, rot_z};
+use nalgebra::{Matrix3, Vector3};
+use std::f64::consts::FRAC_PI_2;
+
+/// Orientation of a body at an instant: north pole direction and prime meridian
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RotationalElements {
+    /// Right ascension of the north pole in radians (ICRF)
+    pub pole_ra: f64,
+    /// Declination of the north pole in radians (ICRF)
+    pub pole_dec: f64,
+    /// Angle of the prime meridian measured along the equator, in radians
+    pub prime_meridian: f64,
+    /// Whether the body spins in the positive sense about its north pole
+    pub prograde: bool,
+}
+
+impl RotationalElements {
+    /// Unit vector toward the north pole in ICRF axes
+    pub fn pole(&self) -> Vector3<f64> {
+        Vector3::new(
+            self.pole_dec.cos() * self.pole_ra.cos(),
+            self.pole_dec.cos() * self.pole_ra.sin(),
+            self.pole_dec.sin(),
+        )
+    }
+
+    /// Rotation from ICRF axes to the body-fixed frame
+    ///
+    /// In body-fixed axes z points to the north pole and x to the prime
+    /// meridian on the equator.
+    pub fn icrf_to_body_fixed(&self) -> Matrix3<f64> {
+        rot_z(-self.prime_meridian)
+            * rot_x(-(FRAC_PI_2 - self.pole_dec))
+            * rot_z(-(FRAC_PI_2 + self.pole_ra))
+    }
+}
+
+/// Compute the rotational elements of a body at a TDB Julian date
+///
+/// Returns `None` for bodies without an IAU rotation model (the Earth-Moon
+/// barycenter).
+pub fn rotational_elements(body: Body, jd_tdb: f64) -> Option<RotationalElements> {
+    let d = jd_tdb - J2000;
+    let t = d / 36525.0;
+
+    // (pole RA, pole Dec, prime meridian) in degrees
+    let (ra, dec, w) = match body {
+        Body::Sun => (286.13, 63.87, 84.176 + 14.184_400_0 * d),
+        Body::Mercury => (
+            281.0103 - 0.0328 * t,
+            61.4155 - 0.0049 * t,
+            329.5988 + 6.138_510_8 * d,
+        ),
+        Body::Venus => (272.76, 67.16, 160.20 - 1.481_368_8 * d),
+        Body::Earth => (
+            0.00 - 0.641 * t,
+            90.00 - 0.557 * t,
+            190.147 + 360.985_623_5 * d,
+        ),
+        Body::Moon => moon_elements(d, t),
+        Body::Mars => (
+            317.681_43 - 0.1061 * t,
+            52.886_50 - 0.0609 * t,
+            176.630 + 350.891_982_26 * d,
+        ),
+        Body::Jupiter => (
+            268.056_595 - 0.006_499 * t,
+            64.495_303 + 0.002_413 * t,
+            284.95 + 870.536_000_0 * d,
+        ),
+        Body::Saturn => (
+            40.589 - 0.036 * t,
+            83.537 - 0.004 * t,
+            38.90 + 810.793_902_4 * d,
+        ),
+        Body::Uranus => (257.311, -15.175, 203.81 - 501.160_092_8 * d),
+        Body::Neptune => {
+            let n = (357.85 + 52.316 * t) * DEG2RAD;
+            (
+                299.36 + 0.70 * n.sin(),
+                43.46 - 0.51 * n.cos(),
+                253.18 + 536.312_849_2 * d - 0.48 * n.sin(),
+            )
+        }
+        Body::Pluto => (132.993, -6.163, 302.695 + 56.362_522_5 * d),
+        Body::EarthMoonBarycenter | Body::Custom(_) => return None,
+    };
+
+    Some(RotationalElements {
+        pole_ra: ra * DEG2RAD,
+        pole_dec: dec * DEG2RAD,
+        prime_meridian: (w * DEG2RAD).rem_euclid(std::f64::consts::TAU),
+        prograde: !matches!(body, Body::Venus | Body::Uranus | Body::Pluto),
+    })
+}
+
+/// Lunar pole and prime meridian including the libration terms E1-E13
+fn moon_elements(d: f64, t: f64) -> (f64, f64, f64) {
+    let e = |a: f64, b: f64| (a + b * d) * DEG2RAD;
+    let e1 = e(125.045, -0.052_992_1);
+    let e2 = e(250.089, -0.105_984_2);
+    let e3 = e(260.008, 13.012_000_9);
+    let e4 = e(176.625, 13.340_715_4);
+    let e5 = e(357.529, 0.985_600_3);
+    let e6 = e(311.589, 26.405_708_4);
+    let e7 = e(134.963, 13.064_993_0);
+    let e8 = e(276.617, 0.328_714_6);
+    let e9 = e(34.226, 1.748_487_7);
+    let e10 = e(15.134, -0.158_976_3);
+    let e11 = e(119.743, 0.003_609_6);
+    let e12 = e(239.961, 0.164_357_3);
+    let e13 = e(25.053, 12.959_008_8);
+
+    let ra = 269.9949 + 0.0031 * t - 3.8787 * e1.sin() - 0.1204 * e2.sin() + 0.0700 * e3.sin()
+        - 0.0172 * e4.sin()
+        + 0.0072 * e6.sin()
+        - 0.0052 * e10.sin()
+        + 0.0043 * e13.sin();
+
+    let dec = 66.5392 + 0.0130 * t + 1.5419 * e1.cos() + 0.0239 * e2.cos() - 0.0278 * e3.cos()
+        + 0.0068 * e4.cos()
+        - 0.0029 * e6.cos()
+        + 0.0009 * e7.cos()
+        + 0.0008 * e10.cos()
+        - 0.0009 * e13.cos();
+
+    let w = 38.3213 + 13.176_358_15 * d - 1.4e-12 * d * d + 3.5610 * e1.sin() + 0.1208 * e2.sin()
+        - 0.0642 * e3.sin()
+        + 0.0158 * e4.sin()
+        + 0.0252 * e5.sin()
+        - 0.0066 * e6.sin()
+        - 0.0047 * e7.sin()
+        - 0.0046 * e8.sin()
+        + 0.0028 * e9.sin()
+        + 0.0052 * e10.sin()
+        + 0.0040 * e11.sin()
+        + 0.0019 * e12.sin()
+        - 0.0044 * e13.sin();
+
+    (ra, dec, w)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use approx::assert_relative_eq;
+
+    #[test]
+    fn test_body_fixed_pole_maps_to_z() {
+        for body in [
+            Body::Sun,
+            Body::Moon,
+            Body::Mars,
+            Body::Jupiter,
+            Body::Uranus,
+        ] {
+            let el = rotational_elements(body, J2000 + 5000.0).unwrap();
+            let z = el.icrf_to_body_fixed() * el.pole();
+            assert_relative_eq!(z, Vector3::z(), epsilon = 1e-12);
+        }
+    }
+
+    #[test]
+    fn test_earth_prime_meridian_tracks_sidereal_time() {
+        // The IAU Earth model is a rough stand-in for sidereal time: at J2000
+        // Greenwich faces ~280.46 deg of right ascension
+        let el = rotational_elements(Body::Earth, J2000).unwrap();
+        let m = el.icrf_to_body_fixed().transpose();
+        let greenwich = m * Vector3::x();
+        let ra = greenwich
+            .y
+            .atan2(greenwich.x)
+            .rem_euclid(std::f64::consts::TAU)
+            / DEG2RAD;
+        assert_relative_eq!(ra, 280.147, epsilon = 0.01);
+    }
+}
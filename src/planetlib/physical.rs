@@ -0,0 +1,328 @@
+//! Physical ephemerides of the Sun, Moon and planets
+//!
+//! Collects the quantities an observing almanac tabulates for a body - apparent
+//! size, brightness, phase, elongation, the sub-observer and sub-solar points
+//! and the orientation of the rotation axis on the sky - into a single
+//! [`PhysicalEphemeris`]. Positions include light time but not aberration or
+//! refraction. Magnitudes use the formulas of the *Astronomical Almanac* as
+//! given by Meeus, *Astronomical Algorithms* (2nd ed.), chapter 41, with the
+//! Mallama & Hilton (2018) curve for the Earth.
+
+use super::orientation::rotational_elements;
+use super::{Body, Ephemeris, PlanetError};
+use crate::constants::{AU_KM, RAD2DEG};
+use crate::positions::light_time::solve_light_time;
+use crate::time::Time;
+use nalgebra::Vector3;
+
+/// A point on a body's surface in planetocentric coordinates
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SurfacePoint {
+    /// Planetocentric latitude in degrees
+    pub latitude_deg: f64,
+    /// Planetocentric longitude in degrees, increasing eastward, in [0, 360)
+    pub longitude_deg: f64,
+}
+
+impl SurfacePoint {
+    /// Point where a body-fixed direction vector pierces the surface
+    pub(crate) fn from_body_fixed(v: &Vector3<f64>) -> Self {
+        let n = v.norm();
+        Self {
+            latitude_deg: (v.z / n).asin() * RAD2DEG,
+            longitude_deg: (v.y.atan2(v.x) * RAD2DEG).rem_euclid(360.0),
+        }
+    }
+}
+
+/// Physical ephemeris of a body as seen by an observer at one instant
+#[derive(Debug, Clone, PartialEq)]
+pub struct PhysicalEphemeris {
+    /// The observed body
+    pub body: Body,
+    /// Distance from the observer in AU
+    pub distance_au: f64,
+    /// Distance from the Sun in AU
+    pub sun_distance_au: f64,
+    /// One-way light time from the body to the observer in days
+    pub light_time_days: f64,
+    /// Apparent equatorial diameter in arcseconds
+    pub apparent_diameter_arcsec: f64,
+    /// Visual magnitude
+    pub magnitude: f64,
+    /// Sun-body-observer angle in degrees
+    pub phase_angle_deg: f64,
+    /// Fraction of the disk that is illuminated, from 0 to 1
+    pub illuminated_fraction: f64,
+    /// Sun-observer-body angle in degrees
+    pub elongation_deg: f64,
+    /// Point on the body closest to the observer
+    pub sub_observer: SurfacePoint,
+    /// Point on the body with the Sun at the zenith
+    pub sub_solar: SurfacePoint,
+    /// Position angle of the body's north pole, measured from celestial
+    /// north through east, in degrees in [0, 360)
+    pub pole_position_angle_deg: f64,
+}
+
+/// Angle between two vectors in degrees; zero if either vanishes
+fn separation_deg(a: &Vector3<f64>, b: &Vector3<f64>) -> f64 {
+    if a.norm() == 0.0 || b.norm() == 0.0 {
+        return 0.0;
+    }
+    a.angle(b) * RAD2DEG
+}
+
+/// Visual magnitude from the distances in AU, phase angle in degrees and,
+/// for Saturn, the ring geometry
+fn visual_magnitude(body: Body, r: f64, delta: f64, i: f64, rings: (f64, f64)) -> f64 {
+    let distance_term = 5.0 * (r * delta).log10();
+    match body {
+        Body::Sun => -26.74 + 5.0 * delta.log10(),
+        Body::Mercury => {
+            -0.42 + distance_term + 0.0380 * i - 0.000_273 * i * i + 0.000_002 * i * i * i
+        }
+        Body::Venus => {
+            -4.40 + distance_term + 0.0009 * i + 0.000_239 * i * i - 0.000_000_65 * i * i * i
+        }
+        Body::Earth | Body::EarthMoonBarycenter => {
+            -3.99 + distance_term - 1.060e-3 * i + 2.054e-4 * i * i
+        }
+        Body::Moon => 0.23 + distance_term + 0.026 * i + 4.0e-9 * i.powi(4),
+        Body::Mars => -1.52 + distance_term + 0.016 * i,
+        Body::Jupiter => -9.40 + distance_term + 0.005 * i,
+        Body::Saturn => {
+            let (b, delta_u) = rings;
+            let sin_b = b.sin().abs();
+            -8.88 + distance_term + 0.044 * delta_u - 2.60 * sin_b + 1.25 * sin_b * sin_b
+        }
+        Body::Uranus => -7.19 + distance_term,
+        Body::Neptune => -6.87 + distance_term,
+        Body::Pluto => -1.00 + distance_term,
+        Body::Custom(id) => super::custom_body(id)
+            .and_then(|b| b.absolute_magnitude)
+            .map_or(f64::NAN, |h| h + distance_term),
+    }
+}
+
+impl Ephemeris {
+    /// Position of `body` when the light reaching `observer_pos` at `jd`
+    /// left it, and the light time in days, from [`solve_light_time`]
+    pub(crate) fn retarded_position(
+        &self,
+        body: Body,
+        observer_pos: &Vector3<f64>,
+        jd: f64,
+    ) -> Result<(Vector3<f64>, f64), PlanetError> {
+        let solution = solve_light_time(self, body, observer_pos, &Vector3::zeros(), jd)?;
+        Ok((
+            observer_pos + solution.position.vector(),
+            solution.light_time_days,
+        ))
+    }
+
+    /// Compute the physical ephemeris of `body` as seen from the centre of
+    /// `observer` at `time`
+    pub fn physical_ephemeris(
+        &self,
+        body: Body,
+        time: &Time,
+        observer: Body,
+    ) -> Result<PhysicalEphemeris, PlanetError> {
+        if body == observer {
+            return Err(PlanetError::DataError(format!(
+                "{} cannot observe itself",
+                body.name()
+            )));
+        }
+        let elements_at = |jd: f64| {
+            rotational_elements(body, jd).ok_or_else(|| {
+                PlanetError::DataError(format!("no rotation model for {}", body.name()))
+            })
+        };
+
+        let jd = time.tdb();
+        if !jd.is_finite() {
+            return Err(PlanetError::TimeError(format!(
+                "non-finite Julian date {}",
+                jd
+            )));
+        }
+
+        let observer_pos = self.position(observer, jd)?;
+        let (target, light_time) = self.retarded_position(body, &observer_pos, jd)?;
+        let sun = self.position(Body::Sun, jd - light_time)?;
+        let elements = elements_at(jd - light_time)?;
+
+        let to_observer = observer_pos - target;
+        let to_sun = sun - target;
+        let delta = to_observer.norm();
+        let r = to_sun.norm();
+
+        let phase_angle = separation_deg(&to_observer, &to_sun);
+        let elongation = separation_deg(&-to_observer, &(sun - observer_pos));
+
+        let (equatorial_radius, _) = body.radii_km();
+        let apparent_diameter =
+            2.0 * (equatorial_radius / (delta * AU_KM)).asin() * RAD2DEG * 3600.0;
+
+        // Saturnicentric latitude of the observer above the ring plane and the
+        // ring-plane longitude difference between Sun and observer
+        let pole = elements.pole();
+        let ring_latitude = (pole.dot(&to_observer) / delta).asin();
+        let in_plane = |v: &Vector3<f64>| v - pole * pole.dot(v);
+        let ring_longitude_difference = separation_deg(&in_plane(&to_observer), &in_plane(&to_sun));
+
+        let rotation = elements.icrf_to_body_fixed();
+        let sub_observer = SurfacePoint::from_body_fixed(&(rotation * to_observer));
+        let sub_solar = if r > 0.0 {
+            SurfacePoint::from_body_fixed(&(rotation * to_sun))
+        } else {
+            // The Sun lights itself from every direction
+            sub_observer
+        };
+
+        // Position angle of the pole about the line of sight
+        let line_of_sight = -to_observer;
+        let ra = line_of_sight.y.atan2(line_of_sight.x);
+        let dec = (line_of_sight.z / delta).asin();
+        let (ra0, dec0) = (elements.pole_ra, elements.pole_dec);
+        let pole_angle = (dec0.cos() * (ra0 - ra).sin())
+            .atan2(dec0.sin() * dec.cos() - dec0.cos() * dec.sin() * (ra0 - ra).cos());
+
+        Ok(PhysicalEphemeris {
+            body,
+            distance_au: delta,
+            sun_distance_au: r,
+            light_time_days: light_time,
+            apparent_diameter_arcsec: apparent_diameter,
+            magnitude: visual_magnitude(
+                body,
+                r,
+                delta,
+                phase_angle,
+                (ring_latitude, ring_longitude_difference),
+            ),
+            phase_angle_deg: phase_angle,
+            illuminated_fraction: (1.0 + (phase_angle / RAD2DEG).cos()) / 2.0,
+            elongation_deg: elongation,
+            sub_observer,
+            sub_solar,
+            pole_position_angle_deg: (pole_angle * RAD2DEG).rem_euclid(360.0),
+        })
+    }
+}
+
+/// Compute the physical ephemeris of `body` seen from `observer` using the
+/// built-in analytic ephemeris
+///
+/// # Examples
+///
+/// ```
+/// use starfield::planetlib::{physical_ephemeris, Body};
+/// use starfield::time::Timescale;
+///
+/// let ts = Timescale::default();
+/// let t = ts.tt((2024, 1, 1));
+/// let jupiter = physical_ephemeris(Body::Jupiter, &t, Body::Earth).unwrap();
+/// assert!(jupiter.apparent_diameter_arcsec > 30.0);
+/// ```
+pub fn physical_ephemeris(
+    body: Body,
+    time: &Time,
+    observer: Body,
+) -> Result<PhysicalEphemeris, PlanetError> {
+    Ephemeris::new().physical_ephemeris(body, time, observer)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::constants::C_AUDAY;
+    use crate::positions::trace;
+    use crate::time::Timescale;
+    use approx::assert_relative_eq;
+
+    #[test]
+    fn test_mars_2003_opposition() {
+        // Closest approach on 2003 August 27: 0.3727 AU, 25.1", V = -2.9
+        let ts = Timescale::default();
+        let t = ts.tt_jd(2_452_878.9, None);
+        let mars = physical_ephemeris(Body::Mars, &t, Body::Earth).unwrap();
+
+        assert_relative_eq!(mars.distance_au, 0.3727, epsilon = 2e-3);
+        assert_relative_eq!(mars.apparent_diameter_arcsec, 25.1, epsilon = 0.2);
+        assert_relative_eq!(mars.magnitude, -2.9, epsilon = 0.15);
+        assert!(mars.elongation_deg > 170.0);
+        assert!(mars.illuminated_fraction > 0.99);
+        assert_relative_eq!(
+            mars.light_time_days,
+            mars.distance_au / C_AUDAY,
+            epsilon = 1e-9
+        );
+    }
+
+    #[test]
+    fn test_light_time_is_traced() {
+        let ts = Timescale::default();
+        let t = ts.tt_jd(2_452_878.9, None);
+        let (mars, trace) = trace::traced(|| physical_ephemeris(Body::Mars, &t, Body::Earth));
+        let mars = mars.unwrap();
+
+        let step = trace.find("light time to Mars").next().unwrap();
+        assert_relative_eq!(step.after.norm(), mars.distance_au, epsilon = 1e-12);
+        // Mars moves about 15" relative to the Earth during the 3 minute light time
+        assert!((1.0..60.0).contains(&step.shift_arcsec()));
+    }
+
+    #[test]
+    fn test_venus_geometry_consistency() {
+        let ts = Timescale::default();
+        for day in (0..600).step_by(50) {
+            let t = ts.tt_jd(2_460_000.5 + day as f64, None);
+            let venus = physical_ephemeris(Body::Venus, &t, Body::Earth).unwrap();
+
+            // Venus never strays more than ~47 degrees from the Sun
+            assert!(venus.elongation_deg < 48.0);
+            assert!((0.0..=1.0).contains(&venus.illuminated_fraction));
+            assert!(
+                (-4.9..-3.7).contains(&venus.magnitude),
+                "{}",
+                venus.magnitude
+            );
+
+            // Sub-solar and sub-observer points are separated by the phase angle
+            let (a, b) = (venus.sub_observer, venus.sub_solar);
+            let to_vec = |p: SurfacePoint| {
+                let (lat, lon) = (p.latitude_deg / RAD2DEG, p.longitude_deg / RAD2DEG);
+                Vector3::new(lat.cos() * lon.cos(), lat.cos() * lon.sin(), lat.sin())
+            };
+            assert_relative_eq!(
+                separation_deg(&to_vec(a), &to_vec(b)),
+                venus.phase_angle_deg,
+                epsilon = 1e-6
+            );
+        }
+    }
+
+    #[test]
+    fn test_moon_from_earth() {
+        let ts = Timescale::default();
+        let t = ts.tt((2024, 3, 10));
+        let moon = physical_ephemeris(Body::Moon, &t, Body::Earth).unwrap();
+
+        let arcmin = moon.apparent_diameter_arcsec / 60.0;
+        assert!((29.0..34.0).contains(&arcmin), "{}", arcmin);
+
+        // Optical libration in latitude stays within about 7 degrees
+        assert!(moon.sub_observer.latitude_deg.abs() < 7.5);
+    }
+
+    #[test]
+    fn test_rejects_self_observation() {
+        let ts = Timescale::default();
+        let t = ts.tt((2024, 1, 1));
+        assert!(physical_ephemeris(Body::Earth, &t, Body::Earth).is_err());
+        assert!(physical_ephemeris(Body::EarthMoonBarycenter, &t, Body::Earth).is_err());
+    }
+}
@@ -0,0 +1,169 @@
+//! User-defined bodies
+//!
+//! [`Body`] names the major bodies, and [`register_body`] adds others -
+//! asteroids, comets, spacecraft, minor moons - as [`Body::Custom`] values
+//! keyed by NAIF ID, so they can be passed wherever a `Body` is taken:
+//!
+//! ```
+//! use starfield::planetlib::{register_body, Body, CustomBody, Ephemeris, OrbitalElements};
+//!
+//! // Ceres, with osculating elements for the analytic ephemeris
+//! let elements = OrbitalElements {
+//!     epoch_jd: 2_460_600.5,
+//!     semi_major_axis_au: 2.7675,
+//!     eccentricity: 0.0796,
+//!     inclination_deg: 10.587,
+//!     ascending_node_deg: 80.25,
+//!     argument_of_perihelion_deg: 73.30,
+//!     mean_anomaly_deg: 145.8,
+//! };
+//! let ceres = register_body(
+//!     CustomBody::new(2_000_001, "Ceres", 469.7)
+//!         .with_gm(62.6284)
+//!         .with_elements(elements),
+//! )?;
+//!
+//! assert_eq!(Body::from_name("ceres"), Some(ceres));
+//! let state = Ephemeris::new().get_state(ceres, 2_460_600.5)?;
+//! assert!((state.position.coords.norm() - 2.77).abs() < 0.3);
+//! # Ok::<(), starfield::planetlib::PlanetError>(())
+//! ```
+//!
+//! A kernel-backed [`Ephemeris`](super::Ephemeris) looks a custom body up
+//! by its NAIF ID, so a body from a small-body or spacecraft kernel only
+//! needs registering to be found. The analytic ephemeris uses the body's
+//! osculating elements, when it has them.
+//!
+//! The registry is global and lives for the whole program. Registering an
+//! ID again replaces the earlier entry.
+
+use super::{Body, OrbitalElements, PlanetError};
+use once_cell::sync::Lazy;
+use std::collections::HashMap;
+use std::sync::RwLock;
+
+/// Registered bodies by NAIF ID
+static REGISTRY: Lazy<RwLock<HashMap<i32, CustomBody>>> = Lazy::new(Default::default);
+
+/// Description of a user-defined body
+#[derive(Debug, Clone, PartialEq)]
+pub struct CustomBody {
+    /// NAIF integer ID
+    pub naif_id: i32,
+    /// Display name
+    pub name: &'static str,
+    /// Mean radius in km
+    pub radius_km: f64,
+    /// Gravitational parameter in km^3/s^2
+    pub gm_km3_s2: Option<f64>,
+    /// Absolute magnitude H, for estimating visual magnitudes
+    pub absolute_magnitude: Option<f64>,
+    /// Heliocentric osculating elements, for the analytic ephemeris
+    pub elements: Option<OrbitalElements>,
+}
+
+impl CustomBody {
+    /// A body with an ID, a name and a mean radius in km
+    ///
+    /// The name is kept for the rest of the program so that
+    /// [`Body::name`] can return it.
+    pub fn new(naif_id: i32, name: impl Into<String>, radius_km: f64) -> Self {
+        Self {
+            naif_id,
+            name: Box::leak(name.into().into_boxed_str()),
+            radius_km,
+            gm_km3_s2: None,
+            absolute_magnitude: None,
+            elements: None,
+        }
+    }
+
+    /// Set the gravitational parameter in km^3/s^2
+    pub fn with_gm(mut self, gm_km3_s2: f64) -> Self {
+        self.gm_km3_s2 = Some(gm_km3_s2);
+        self
+    }
+
+    /// Set the absolute magnitude H
+    pub fn with_absolute_magnitude(mut self, h: f64) -> Self {
+        self.absolute_magnitude = Some(h);
+        self
+    }
+
+    /// Set heliocentric osculating elements
+    pub fn with_elements(mut self, elements: OrbitalElements) -> Self {
+        self.elements = Some(elements);
+        self
+    }
+}
+
+/// Register a body and return the [`Body`] that refers to it
+///
+/// Fails if the ID or the name belongs to one of the built-in bodies.
+pub fn register_body(body: CustomBody) -> Result<Body, PlanetError> {
+    if let Some(builtin) = Body::ALL
+        .into_iter()
+        .find(|b| b.naif_id() == body.naif_id || b.name().eq_ignore_ascii_case(body.name))
+    {
+        return Err(PlanetError::DataError(format!(
+            "{} (NAIF {}) clashes with the built-in {}",
+            body.name,
+            body.naif_id,
+            builtin.name()
+        )));
+    }
+    let id = body.naif_id;
+    REGISTRY
+        .write()
+        .unwrap_or_else(|e| e.into_inner())
+        .insert(id, body);
+    Ok(Body::Custom(id))
+}
+
+/// The registered description of a NAIF ID, if any
+pub fn custom_body(naif_id: i32) -> Option<CustomBody> {
+    REGISTRY
+        .read()
+        .unwrap_or_else(|e| e.into_inner())
+        .get(&naif_id)
+        .cloned()
+}
+
+/// The ID of a registered body with this name, ignoring case
+pub(crate) fn find_by_name(name: &str) -> Option<i32> {
+    REGISTRY
+        .read()
+        .unwrap_or_else(|e| e.into_inner())
+        .values()
+        .find(|body| body.name.eq_ignore_ascii_case(name))
+        .map(|body| body.naif_id)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::planetlib::Ephemeris;
+
+    #[test]
+    fn test_register_custom_body() {
+        let body =
+            register_body(CustomBody::new(-999_001, "Test Probe", 0.005).with_gm(1e-12)).unwrap();
+        assert_eq!(body, Body::Custom(-999_001));
+        assert_eq!(body.name(), "Test Probe");
+        assert_eq!(body.naif_id(), -999_001);
+        assert_eq!(body.radii_km(), (0.005, 0.005));
+        assert_eq!(body.gm_km3_s2(), Some(1e-12));
+        assert_eq!(Body::from_naif_id(-999_001), Some(body));
+        assert_eq!(Body::from_name(" test probe"), Some(body));
+
+        // Without elements or a kernel there is nowhere to find it
+        assert!(matches!(
+            Ephemeris::new().get_state(body, 2_460_000.5),
+            Err(PlanetError::NotFound(_))
+        ));
+
+        assert!(register_body(CustomBody::new(499, "Not Mars", 1.0)).is_err());
+        assert!(register_body(CustomBody::new(-999_002, "moon", 1.0)).is_err());
+        assert!(custom_body(-999_002).is_none());
+    }
+}
@@ -0,0 +1,250 @@
+//! Resampling of computed state series
+//!
+//! A [`StateSeries`] holds positions and velocities at increasing Julian
+//! dates, however they were computed, and densifies them with piecewise
+//! cubic Hermite interpolation: each interval is the unique cubic matching
+//! the position and velocity at both ends. Using the velocities makes the
+//! error fall with the fourth power of the step, so a series sampled every
+//! few days reproduces a planet's orbit to a few kilometres, and one sampled
+//! every minute does about as well for a satellite:
+//!
+//! ```
+//! use starfield::planetlib::{Body, Ephemeris};
+//! use starfield::time::Timescale;
+//!
+//! let ts = Timescale::default();
+//! let t0 = ts.utc((2024, 1, 1));
+//! let t1 = ts.utc((2024, 7, 1));
+//!
+//! let coarse = Ephemeris::new().sample_states(Body::Mars, &ts.linspace(&t0, &t1, 46))?;
+//! let hourly = coarse.resample(&ts.linspace(&t0, &t1, 182 * 24 + 1))?;
+//! assert_eq!(hourly.len(), 4369);
+//! # Ok::<(), starfield::planetlib::PlanetError>(())
+//! ```
+//!
+//! Units are whatever the samples use, with velocities per day; series
+//! built from an [`Ephemeris`] are in AU and AU/day at TDB Julian dates.
+
+use super::{Body, Ephemeris, PlanetError, PlanetState};
+use crate::time::TimeArray;
+use nalgebra::{Point3, Vector3};
+use ndarray::Array2;
+use std::cmp::Ordering;
+
+/// Positions and velocities sampled at increasing Julian dates
+#[derive(Debug, Clone, PartialEq)]
+pub struct StateSeries {
+    jd: Vec<f64>,
+    positions: Vec<Vector3<f64>>,
+    velocities: Vec<Vector3<f64>>,
+}
+
+impl StateSeries {
+    /// Build a series, checking that the columns have one entry per date
+    /// and that the dates strictly increase
+    pub fn new(
+        jd: Vec<f64>,
+        positions: Vec<Vector3<f64>>,
+        velocities: Vec<Vector3<f64>>,
+    ) -> Result<Self, PlanetError> {
+        if positions.len() != jd.len() || velocities.len() != jd.len() {
+            return Err(PlanetError::DataError(format!(
+                "{} dates but {} positions and {} velocities",
+                jd.len(),
+                positions.len(),
+                velocities.len()
+            )));
+        }
+        if let Some(pair) = jd
+            .windows(2)
+            .find(|pair| pair[1].partial_cmp(&pair[0]) != Some(Ordering::Greater))
+        {
+            return Err(PlanetError::TimeError(format!(
+                "dates must increase, but {} is followed by {}",
+                pair[0], pair[1]
+            )));
+        }
+        Ok(Self {
+            jd,
+            positions,
+            velocities,
+        })
+    }
+
+    /// Number of samples
+    pub fn len(&self) -> usize {
+        self.jd.len()
+    }
+
+    /// Whether the series has no samples
+    pub fn is_empty(&self) -> bool {
+        self.jd.is_empty()
+    }
+
+    /// Sample dates
+    pub fn jd(&self) -> &[f64] {
+        &self.jd
+    }
+
+    /// Sampled positions
+    pub fn positions(&self) -> &[Vector3<f64>] {
+        &self.positions
+    }
+
+    /// Sampled velocities, per day
+    pub fn velocities(&self) -> &[Vector3<f64>] {
+        &self.velocities
+    }
+
+    /// Positions as an array with one row per sample, like
+    /// [`Ephemeris::get_positions`]
+    pub fn positions_array(&self) -> Array2<f64> {
+        Array2::from_shape_fn((self.len(), 3), |(i, j)| self.positions[i][j])
+    }
+
+    /// First and last sample dates
+    pub fn span(&self) -> Option<(f64, f64)> {
+        Some((*self.jd.first()?, *self.jd.last()?))
+    }
+
+    /// Interpolated state at `jd`, which must lie within the span
+    pub fn state_at(&self, jd: f64) -> Result<PlanetState, PlanetError> {
+        let (first, last) = self
+            .span()
+            .ok_or_else(|| PlanetError::DataError("empty state series".to_string()))?;
+        if !(first..=last).contains(&jd) {
+            return Err(PlanetError::TimeError(format!(
+                "{} is outside the series span {} to {}",
+                jd, first, last
+            )));
+        }
+        if self.len() == 1 {
+            return Ok(PlanetState {
+                position: Point3::from(self.positions[0]),
+                velocity: self.velocities[0],
+            });
+        }
+
+        let i = self
+            .jd
+            .partition_point(|&t| t <= jd)
+            .clamp(1, self.len() - 1)
+            - 1;
+        let h = self.jd[i + 1] - self.jd[i];
+        let s = (jd - self.jd[i]) / h;
+        let (s2, s3) = (s * s, s * s * s);
+        let (p0, p1) = (self.positions[i], self.positions[i + 1]);
+        let (m0, m1) = (self.velocities[i] * h, self.velocities[i + 1] * h);
+
+        let position = p0 * (2.0 * s3 - 3.0 * s2 + 1.0)
+            + m0 * (s3 - 2.0 * s2 + s)
+            + p1 * (3.0 * s2 - 2.0 * s3)
+            + m1 * (s3 - s2);
+        let velocity = (p0 * (6.0 * s2 - 6.0 * s)
+            + m0 * (3.0 * s2 - 4.0 * s + 1.0)
+            + p1 * (6.0 * s - 6.0 * s2)
+            + m1 * (3.0 * s2 - 2.0 * s))
+            / h;
+        Ok(PlanetState {
+            position: Point3::from(position),
+            velocity,
+        })
+    }
+
+    /// Interpolate onto new dates, which must lie within the span
+    pub fn resample_jd(&self, jd: &[f64]) -> Result<StateSeries, PlanetError> {
+        let states = jd
+            .iter()
+            .map(|&t| self.state_at(t))
+            .collect::<Result<Vec<_>, _>>()?;
+        StateSeries::new(
+            jd.to_vec(),
+            states.iter().map(|s| s.position.coords).collect(),
+            states.iter().map(|s| s.velocity).collect(),
+        )
+    }
+
+    /// Interpolate onto the TDB dates of `times`
+    pub fn resample(&self, times: &TimeArray) -> Result<StateSeries, PlanetError> {
+        self.resample_jd(&times.tdb().to_vec())
+    }
+}
+
+impl Ephemeris {
+    /// Sample a body's state at every time in an array, as a series that
+    /// can be resampled onto a finer grid
+    pub fn sample_states(&self, body: Body, times: &TimeArray) -> Result<StateSeries, PlanetError> {
+        let jd = times.tdb().to_vec();
+        let states = jd
+            .iter()
+            .map(|&t| self.get_state(body, t))
+            .collect::<Result<Vec<_>, _>>()?;
+        StateSeries::new(
+            jd,
+            states.iter().map(|s| s.position.coords).collect(),
+            states.iter().map(|s| s.velocity).collect(),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::time::{TimeDelta, Timescale};
+    use approx::assert_relative_eq;
+
+    #[test]
+    fn test_cubic_is_reproduced() {
+        // x = t³ - 2t, exactly representable by one Hermite cubic
+        let jd = vec![0.0, 1.5, 4.0];
+        let x = |t: f64| Vector3::new(t * t * t - 2.0 * t, 1.0, -t);
+        let v = |t: f64| Vector3::new(3.0 * t * t - 2.0, 0.0, -1.0);
+        let series = StateSeries::new(
+            jd.clone(),
+            jd.iter().map(|&t| x(t)).collect(),
+            jd.iter().map(|&t| v(t)).collect(),
+        )
+        .unwrap();
+
+        let dense = series.resample_jd(&[0.0, 0.7, 1.5, 3.9, 4.0]).unwrap();
+        for (t, (p, vel)) in dense
+            .jd()
+            .iter()
+            .zip(dense.positions().iter().zip(dense.velocities()))
+        {
+            assert_relative_eq!(*p, x(*t), epsilon = 1e-12);
+            assert_relative_eq!(*vel, v(*t), epsilon = 1e-12);
+        }
+        assert_eq!(dense.positions_array().shape(), &[5, 3]);
+
+        assert!(series.state_at(4.1).is_err());
+        assert!(StateSeries::new(vec![1.0, 1.0], vec![x(1.0); 2], vec![v(1.0); 2]).is_err());
+        assert!(StateSeries::new(vec![1.0], vec![], vec![]).is_err());
+    }
+
+    #[test]
+    fn test_densified_ephemeris_matches_direct() {
+        let ts = Timescale::default();
+        let eph = Ephemeris::new();
+        let t0 = ts.utc((2024, 1, 1));
+        let t1 = &t0 + TimeDelta::days(60.0);
+
+        let coarse = eph
+            .sample_states(Body::Mars, &ts.linspace(&t0, &t1, 16))
+            .unwrap();
+        let fine_times = ts.linspace(&t0, &t1, 241);
+        let fine = coarse.resample(&fine_times).unwrap();
+        let direct = eph.get_positions(Body::Mars, &fine_times).unwrap();
+
+        let worst = (0..fine.len())
+            .map(|i| {
+                let p = fine.positions()[i];
+                (0..3)
+                    .map(|j| (p[j] - direct[[i, j]]).abs())
+                    .fold(0.0, f64::max)
+            })
+            .fold(0.0, f64::max);
+        // 4-day steps leave errors of a couple of kilometres at most
+        assert!(worst < 2e-8, "{}", worst);
+    }
+}
@@ -0,0 +1,331 @@
+//! Sub-observer, sub-solar and sub-stellar points, and surface illumination
+//!
+//! A sub-point is where the line from a body's centre toward some object
+//! (an observer, the Sun, a star) pierces the reference ellipsoid. Points are
+//! given both in planetocentric coordinates (latitude measured at the centre,
+//! longitude positive east) and in planetographic coordinates (latitude of
+//! the surface normal; longitude increasing with time as seen from afar, so
+//! positive west for prograde rotators other than the Earth, Moon and Sun),
+//! following the IAU WGCCRE conventions.
+
+use super::orientation::{rotational_elements, RotationalElements};
+use super::physical::SurfacePoint;
+use super::{Body, Ephemeris, PlanetError};
+use crate::constants::{AU_KM, DEG2RAD, RAD2DEG};
+use crate::time::Time;
+use nalgebra::Vector3;
+
+/// A surface point below some direction, in both coordinate systems
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SubPoint {
+    /// Latitude from the centre, longitude positive east
+    pub planetocentric: SurfacePoint,
+    /// Latitude of the surface normal, longitude in the IAU sense for the body
+    pub planetographic: SurfacePoint,
+    /// The point in body-fixed axes, in km
+    pub surface_km: Vector3<f64>,
+}
+
+/// Whether planetographic longitudes of `body` increase westward
+fn westward_longitudes(body: Body, elements: &RotationalElements) -> bool {
+    elements.prograde && !matches!(body, Body::Earth | Body::Moon | Body::Sun)
+}
+
+fn elements_for(body: Body, jd_tdb: f64) -> Result<RotationalElements, PlanetError> {
+    rotational_elements(body, jd_tdb)
+        .ok_or_else(|| PlanetError::DataError(format!("no rotation model for {}", body.name())))
+}
+
+/// Outward normal of the reference ellipsoid at a body-fixed surface point
+fn surface_normal(body: Body, point: &Vector3<f64>) -> Vector3<f64> {
+    let (a, b) = body.radii_km();
+    Vector3::new(point.x / (a * a), point.y / (a * a), point.z / (b * b)).normalize()
+}
+
+impl SubPoint {
+    /// Sub-point of a direction given in body-fixed axes
+    fn from_body_fixed(
+        body: Body,
+        elements: &RotationalElements,
+        direction: &Vector3<f64>,
+    ) -> Self {
+        let (a, b) = body.radii_km();
+        let u = direction.normalize();
+        let scale = 1.0 / ((u.x * u.x + u.y * u.y) / (a * a) + u.z * u.z / (b * b)).sqrt();
+        let surface_km = u * scale;
+
+        let planetocentric = SurfacePoint::from_body_fixed(&surface_km);
+        let normal = surface_normal(body, &surface_km);
+        let east = planetocentric.longitude_deg;
+        let planetographic = SurfacePoint {
+            latitude_deg: normal.z.asin() * RAD2DEG,
+            longitude_deg: if westward_longitudes(body, elements) {
+                (360.0 - east).rem_euclid(360.0)
+            } else {
+                east
+            },
+        };
+
+        Self {
+            planetocentric,
+            planetographic,
+            surface_km,
+        }
+    }
+}
+
+/// Sub-point on `body` of a direction given in ICRF axes, at a TDB Julian date
+pub fn sub_point(
+    body: Body,
+    jd_tdb: f64,
+    direction: &Vector3<f64>,
+) -> Result<SubPoint, PlanetError> {
+    if direction.norm() == 0.0 {
+        return Err(PlanetError::DataError("zero direction vector".to_string()));
+    }
+    let elements = elements_for(body, jd_tdb)?;
+    let body_fixed = elements.icrf_to_body_fixed() * direction;
+    Ok(SubPoint::from_body_fixed(body, &elements, &body_fixed))
+}
+
+/// Convert planetographic coordinates on `body` to planetocentric ones
+pub fn planetographic_to_planetocentric(
+    body: Body,
+    jd_tdb: f64,
+    point: &SurfacePoint,
+) -> Result<SurfacePoint, PlanetError> {
+    let elements = elements_for(body, jd_tdb)?;
+    let (a, b) = body.radii_km();
+    let latitude = ((b * b) / (a * a) * (point.latitude_deg * DEG2RAD).tan()).atan();
+    let longitude = if westward_longitudes(body, &elements) {
+        (360.0 - point.longitude_deg).rem_euclid(360.0)
+    } else {
+        point.longitude_deg
+    };
+    Ok(SurfacePoint {
+        latitude_deg: latitude * RAD2DEG,
+        longitude_deg: longitude,
+    })
+}
+
+impl Ephemeris {
+    /// Body-fixed directions from `body` to `observer` and to the Sun, with
+    /// the body's orientation, all at the time light left it for `observer`
+    fn body_fixed_geometry(
+        &self,
+        body: Body,
+        time: &Time,
+        observer: Body,
+    ) -> Result<(RotationalElements, Vector3<f64>, Vector3<f64>), PlanetError> {
+        if body == observer {
+            return Err(PlanetError::DataError(format!(
+                "{} cannot observe itself",
+                body.name()
+            )));
+        }
+        let jd = time.tdb();
+        let observer_pos = self.position(observer, jd)?;
+        let (target, light_time) = self.retarded_position(body, &observer_pos, jd)?;
+        let sun = self.position(Body::Sun, jd - light_time)?;
+
+        let elements = elements_for(body, jd - light_time)?;
+        let rotation = elements.icrf_to_body_fixed();
+        Ok((
+            elements,
+            rotation * (observer_pos - target),
+            rotation * (sun - target),
+        ))
+    }
+
+    /// Point on `body` directly below `observer` at `time`
+    pub fn sub_observer_point(
+        &self,
+        body: Body,
+        time: &Time,
+        observer: Body,
+    ) -> Result<SubPoint, PlanetError> {
+        let (elements, to_observer, _) = self.body_fixed_geometry(body, time, observer)?;
+        Ok(SubPoint::from_body_fixed(body, &elements, &to_observer))
+    }
+
+    /// Point on `body` with the Sun at the zenith, as seen by `observer`
+    pub fn sub_solar_point(
+        &self,
+        body: Body,
+        time: &Time,
+        observer: Body,
+    ) -> Result<SubPoint, PlanetError> {
+        if body == Body::Sun {
+            return Err(PlanetError::DataError(
+                "the Sun has no sub-solar point".to_string(),
+            ));
+        }
+        let (elements, _, to_sun) = self.body_fixed_geometry(body, time, observer)?;
+        Ok(SubPoint::from_body_fixed(body, &elements, &to_sun))
+    }
+
+    /// Point on `body` with a star (ICRS RA/Dec in degrees) at the zenith
+    pub fn sub_stellar_point(
+        &self,
+        body: Body,
+        time: &Time,
+        ra_deg: f64,
+        dec_deg: f64,
+    ) -> Result<SubPoint, PlanetError> {
+        let (ra, dec) = (ra_deg * DEG2RAD, dec_deg * DEG2RAD);
+        let direction = Vector3::new(dec.cos() * ra.cos(), dec.cos() * ra.sin(), dec.sin());
+        sub_point(body, time.tdb(), &direction)
+    }
+
+    /// Solar incidence angle in degrees at a planetocentric surface point
+    ///
+    /// The angle between the local vertical and the Sun: 0 with the Sun
+    /// overhead, above 90 on the night side, in the body's own shadow.
+    pub fn solar_incidence_deg(
+        &self,
+        body: Body,
+        time: &Time,
+        observer: Body,
+        point: &SurfacePoint,
+    ) -> Result<f64, PlanetError> {
+        let (elements, _, to_sun) = self.body_fixed_geometry(body, time, observer)?;
+        let (lat, lon) = (point.latitude_deg * DEG2RAD, point.longitude_deg * DEG2RAD);
+        let direction = Vector3::new(lat.cos() * lon.cos(), lat.cos() * lon.sin(), lat.sin());
+        let surface = SubPoint::from_body_fixed(body, &elements, &direction).surface_km;
+
+        let normal = surface_normal(body, &surface);
+        let sun_from_point = to_sun - surface / AU_KM;
+        Ok(normal.angle(&sun_from_point) * RAD2DEG)
+    }
+
+    /// Whether the Sun is above the horizon at a planetocentric surface point
+    pub fn is_sunlit(
+        &self,
+        body: Body,
+        time: &Time,
+        observer: Body,
+        point: &SurfacePoint,
+    ) -> Result<bool, PlanetError> {
+        Ok(self.solar_incidence_deg(body, time, observer, point)? < 90.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::time::Timescale;
+    use approx::assert_relative_eq;
+
+    #[test]
+    fn test_earth_subsolar_latitude_follows_seasons() {
+        let ts = Timescale::default();
+        let eph = Ephemeris::new();
+
+        let equinox = ts.utc((2024, 3, 20, 3, 6, 0.0));
+        let p = eph
+            .sub_solar_point(Body::Earth, &equinox, Body::Moon)
+            .unwrap();
+        assert!(p.planetocentric.latitude_deg.abs() < 0.05);
+
+        let solstice = ts.utc((2024, 6, 20, 20, 51, 0.0));
+        let p = eph
+            .sub_solar_point(Body::Earth, &solstice, Body::Moon)
+            .unwrap();
+        assert_relative_eq!(p.planetocentric.latitude_deg, 23.44, epsilon = 0.05);
+
+        // Geodetic latitude exceeds geocentric by about 0.15 degrees here
+        let (a, b) = Body::Earth.radii_km();
+        let expected = ((a * a) / (b * b) * (23.44f64 * DEG2RAD).tan()).atan() * RAD2DEG;
+        assert_relative_eq!(p.planetographic.latitude_deg, expected, epsilon = 0.05);
+        // Earth longitudes are east-positive in both systems
+        assert_eq!(
+            p.planetographic.longitude_deg,
+            p.planetocentric.longitude_deg
+        );
+    }
+
+    #[test]
+    fn test_longitude_conventions() {
+        let ts = Timescale::default();
+        let eph = Ephemeris::new();
+        let t = ts.tt((2024, 1, 1));
+
+        // Mars rotates prograde: planetographic longitude is west-positive
+        let mars = eph.sub_observer_point(Body::Mars, &t, Body::Earth).unwrap();
+        assert_relative_eq!(
+            (mars.planetographic.longitude_deg + mars.planetocentric.longitude_deg)
+                .rem_euclid(360.0),
+            0.0,
+            epsilon = 1e-9
+        );
+
+        // Venus rotates retrograde: both systems use east longitudes
+        let venus = eph
+            .sub_observer_point(Body::Venus, &t, Body::Earth)
+            .unwrap();
+        assert_eq!(
+            venus.planetographic.longitude_deg,
+            venus.planetocentric.longitude_deg
+        );
+
+        let back =
+            planetographic_to_planetocentric(Body::Mars, t.tdb(), &mars.planetographic).unwrap();
+        assert_relative_eq!(
+            back.latitude_deg,
+            mars.planetocentric.latitude_deg,
+            epsilon = 1e-9
+        );
+        assert_relative_eq!(
+            back.longitude_deg,
+            mars.planetocentric.longitude_deg,
+            epsilon = 1e-9
+        );
+    }
+
+    #[test]
+    fn test_sub_stellar_point_of_pole() {
+        let ts = Timescale::default();
+        let eph = Ephemeris::new();
+        let t = ts.tt((2024, 1, 1));
+        let elements = rotational_elements(Body::Jupiter, t.tdb()).unwrap();
+        let p = eph
+            .sub_stellar_point(
+                Body::Jupiter,
+                &t,
+                elements.pole_ra * RAD2DEG,
+                elements.pole_dec * RAD2DEG,
+            )
+            .unwrap();
+        assert_relative_eq!(p.planetocentric.latitude_deg, 90.0, epsilon = 1e-6);
+        let (_, polar) = Body::Jupiter.radii_km();
+        assert_relative_eq!(p.surface_km.norm(), polar, epsilon = 1e-6);
+    }
+
+    #[test]
+    fn test_illumination() {
+        let ts = Timescale::default();
+        let eph = Ephemeris::new();
+        let t = ts.tt((2024, 5, 1));
+        let sub_solar = eph.sub_solar_point(Body::Mars, &t, Body::Earth).unwrap();
+
+        let noon = eph
+            .solar_incidence_deg(Body::Mars, &t, Body::Earth, &sub_solar.planetocentric)
+            .unwrap();
+        // The sub-point lies under the centre line, so on an oblate body the
+        // local vertical there is tilted by the geographic-centric difference
+        let tilt =
+            (sub_solar.planetographic.latitude_deg - sub_solar.planetocentric.latitude_deg).abs();
+        assert!(noon <= tilt + 1e-6, "{} {}", noon, tilt);
+
+        let antipode = SurfacePoint {
+            latitude_deg: -sub_solar.planetocentric.latitude_deg,
+            longitude_deg: (sub_solar.planetocentric.longitude_deg + 180.0).rem_euclid(360.0),
+        };
+        assert!(!eph
+            .is_sunlit(Body::Mars, &t, Body::Earth, &antipode)
+            .unwrap());
+        assert!(eph
+            .is_sunlit(Body::Mars, &t, Body::Earth, &sub_solar.planetocentric)
+            .unwrap());
+    }
+}
@@ -0,0 +1,507 @@
+//! Precomputed Chebyshev ephemeris tables
+//!
+//! An [`EphemerisTable`] samples an [`Ephemeris`] over a span of dates and
+//! fits each body with piecewise Chebyshev polynomials, splitting granules
+//! until every one reproduces the source to within a tolerance. The result
+//! is a self-contained mini-kernel that evaluates in a few dozen flops and
+//! can be written to disk or embedded in a binary with `include_bytes!`:
+//!
+//! ```
+//! use starfield::planetlib::{Body, Ephemeris, EphemerisTable, TableConfig};
+//!
+//! let config = TableConfig::default().with_tolerance_km(1.0);
+//! let table =
+//!     EphemerisTable::fit(&Ephemeris::new(), &[Body::Mars], 2_460_000.5, 2_460_100.5, &config)
+//!         .unwrap();
+//!
+//! // Ship `table.to_bytes()` with the application, then:
+//! let table = EphemerisTable::from_bytes(&table.to_bytes()).unwrap();
+//! let mars = table.get_state(Body::Mars, 2_460_050.5).unwrap();
+//! ```
+//!
+//! The file format is little-endian: magic bytes, a version byte, the
+//! polynomial degree and tolerance, then for each body its NAIF ID and
+//! granules of `[start_jd, end_jd, x coefficients, y coefficients, z
+//! coefficients]`, with positions in km.
+
+use super::{Body, Ephemeris, PlanetError, PlanetState};
+use crate::constants::AU_KM;
+use byteorder::{LittleEndian, ReadBytesExt, WriteBytesExt};
+use nalgebra::{Point3, Vector3};
+use std::collections::BTreeMap;
+use std::f64::consts::PI;
+use std::fs::File;
+use std::io::{BufReader, BufWriter, Read, Write};
+use std::path::Path;
+
+/// Magic bytes identifying an ephemeris table file
+pub const TABLE_MAGIC: &[u8; 6] = b"SFEPHT";
+
+/// Current ephemeris table format version
+pub const TABLE_VERSION: u8 = 1;
+
+/// Settings controlling how an [`EphemerisTable`] is fitted
+#[derive(Debug, Clone, PartialEq)]
+pub struct TableConfig {
+    /// Largest allowed position error in km
+    pub tolerance_km: f64,
+    /// Degree of the Chebyshev polynomial in each granule
+    pub degree: usize,
+    /// Length of the granules the span is first divided into, in days
+    pub max_granule_days: f64,
+    /// Granules are never split below this length, in days
+    pub min_granule_days: f64,
+}
+
+impl Default for TableConfig {
+    fn default() -> Self {
+        Self {
+            tolerance_km: 1.0,
+            degree: 12,
+            max_granule_days: 32.0,
+            min_granule_days: 0.125,
+        }
+    }
+}
+
+impl TableConfig {
+    /// Set the largest allowed position error in km
+    pub fn with_tolerance_km(mut self, tolerance_km: f64) -> Self {
+        self.tolerance_km = tolerance_km;
+        self
+    }
+
+    /// Set the Chebyshev polynomial degree
+    pub fn with_degree(mut self, degree: usize) -> Self {
+        self.degree = degree;
+        self
+    }
+
+    /// Set the initial granule length in days
+    pub fn with_max_granule_days(mut self, days: f64) -> Self {
+        self.max_granule_days = days;
+        self
+    }
+
+    /// Set the shortest granule length in days
+    pub fn with_min_granule_days(mut self, days: f64) -> Self {
+        self.min_granule_days = days;
+        self
+    }
+}
+
+/// One Chebyshev fit covering `[start_jd, end_jd]`
+#[derive(Debug, Clone, PartialEq)]
+struct Granule {
+    start_jd: f64,
+    end_jd: f64,
+    /// x, y and z coefficients, `degree + 1` each, for positions in km
+    coefficients: Vec<f64>,
+}
+
+impl Granule {
+    /// Position (km) and velocity (km/day) at `jd`
+    fn evaluate(&self, jd: f64) -> (Vector3<f64>, Vector3<f64>) {
+        let n = self.coefficients.len() / 3;
+        let radius = (self.end_jd - self.start_jd) / 2.0;
+        let s = ((jd - self.start_jd) / radius - 1.0).clamp(-1.0, 1.0);
+
+        // Chebyshev polynomials and their derivatives with respect to s
+        let mut t = vec![0.0; n];
+        let mut dt = vec![0.0; n];
+        t[0] = 1.0;
+        if n > 1 {
+            t[1] = s;
+            dt[1] = 1.0;
+        }
+        for k in 2..n {
+            t[k] = 2.0 * s * t[k - 1] - t[k - 2];
+            dt[k] = 2.0 * t[k - 1] + 2.0 * s * dt[k - 1] - dt[k - 2];
+        }
+
+        let mut position = Vector3::zeros();
+        let mut velocity = Vector3::zeros();
+        for c in 0..3 {
+            let coefficients = &self.coefficients[c * n..(c + 1) * n];
+            position[c] = coefficients.iter().zip(&t).map(|(a, b)| a * b).sum();
+            let per_s: f64 = coefficients.iter().zip(&dt).map(|(a, b)| a * b).sum();
+            velocity[c] = per_s / radius;
+        }
+        (position, velocity)
+    }
+}
+
+/// Piecewise Chebyshev approximation of an ephemeris over a fixed span
+#[derive(Debug, Clone, PartialEq)]
+pub struct EphemerisTable {
+    degree: usize,
+    tolerance_km: f64,
+    /// Granules in date order, by NAIF ID
+    bodies: BTreeMap<i32, Vec<Granule>>,
+}
+
+impl EphemerisTable {
+    /// Fit `bodies` from `ephemeris` between two TDB Julian dates
+    ///
+    /// Fails if a granule cannot reach the tolerance before it would be
+    /// split below [`TableConfig::min_granule_days`].
+    pub fn fit(
+        ephemeris: &Ephemeris,
+        bodies: &[Body],
+        start_jd: f64,
+        end_jd: f64,
+        config: &TableConfig,
+    ) -> Result<Self, PlanetError> {
+        if !(start_jd.is_finite() && end_jd.is_finite() && start_jd < end_jd) {
+            return Err(PlanetError::TimeError(format!(
+                "invalid table span {} to {}",
+                start_jd, end_jd
+            )));
+        }
+        if config.degree == 0 || config.tolerance_km <= 0.0 || config.max_granule_days <= 0.0 {
+            return Err(PlanetError::DataError(format!(
+                "invalid table configuration {:?}",
+                config
+            )));
+        }
+
+        let mut table = Self {
+            degree: config.degree,
+            tolerance_km: config.tolerance_km,
+            bodies: BTreeMap::new(),
+        };
+
+        let count = ((end_jd - start_jd) / config.max_granule_days).ceil() as usize;
+        let length = (end_jd - start_jd) / count as f64;
+        for &body in bodies {
+            let mut granules = Vec::new();
+            for i in 0..count {
+                let start = start_jd + i as f64 * length;
+                let end = if i + 1 == count {
+                    end_jd
+                } else {
+                    start + length
+                };
+                fit_span(ephemeris, body, start, end, config, &mut granules)?;
+            }
+            table.bodies.insert(body.naif_id(), granules);
+        }
+        Ok(table)
+    }
+
+    /// Polynomial degree of every granule
+    pub fn degree(&self) -> usize {
+        self.degree
+    }
+
+    /// Position tolerance the table was fitted to, in km
+    pub fn tolerance_km(&self) -> f64 {
+        self.tolerance_km
+    }
+
+    /// Bodies the table covers
+    pub fn bodies(&self) -> Vec<Body> {
+        self.bodies
+            .keys()
+            .filter_map(|&id| Body::from_naif_id(id))
+            .collect()
+    }
+
+    /// First and last TDB Julian dates covered, if the table is not empty
+    pub fn span(&self) -> Option<(f64, f64)> {
+        let granules = self.bodies.values().next()?;
+        Some((granules.first()?.start_jd, granules.last()?.end_jd))
+    }
+
+    /// Total number of granules across all bodies
+    pub fn granule_count(&self) -> usize {
+        self.bodies.values().map(Vec::len).sum()
+    }
+
+    /// Get a body's state at a TDB Julian date
+    ///
+    /// Position is in AU and velocity in AU/day, in the axes and origin of
+    /// the ephemeris the table was fitted from.
+    pub fn get_state(&self, body: Body, jd: f64) -> Result<PlanetState, PlanetError> {
+        let granules = self
+            .bodies
+            .get(&body.naif_id())
+            .ok_or_else(|| PlanetError::NotFound(format!("{} is not in the table", body.name())))?;
+
+        let index = granules.partition_point(|g| g.end_jd < jd);
+        let granule = granules
+            .get(index)
+            .filter(|g| g.start_jd <= jd && jd.is_finite())
+            .ok_or_else(|| {
+                PlanetError::TimeError(format!("Julian date {} is outside the table", jd))
+            })?;
+
+        let (position, velocity) = granule.evaluate(jd);
+        Ok(PlanetState {
+            position: Point3::from(position / AU_KM),
+            velocity: velocity / AU_KM,
+        })
+    }
+
+    /// Write the table in its binary format
+    pub fn write<W: Write>(&self, writer: &mut W) -> std::io::Result<()> {
+        writer.write_all(TABLE_MAGIC)?;
+        writer.write_u8(TABLE_VERSION)?;
+        writer.write_u32::<LittleEndian>(self.degree as u32)?;
+        writer.write_f64::<LittleEndian>(self.tolerance_km)?;
+        writer.write_u32::<LittleEndian>(self.bodies.len() as u32)?;
+
+        for (&id, granules) in &self.bodies {
+            writer.write_i32::<LittleEndian>(id)?;
+            writer.write_u32::<LittleEndian>(granules.len() as u32)?;
+            for granule in granules {
+                writer.write_f64::<LittleEndian>(granule.start_jd)?;
+                writer.write_f64::<LittleEndian>(granule.end_jd)?;
+                for &c in &granule.coefficients {
+                    writer.write_f64::<LittleEndian>(c)?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Read a table written by [`EphemerisTable::write`]
+    pub fn read<R: Read>(reader: &mut R) -> Result<Self, PlanetError> {
+        let io = |e: std::io::Error| PlanetError::DataError(format!("ephemeris table: {}", e));
+
+        let mut magic = [0u8; 6];
+        reader.read_exact(&mut magic).map_err(io)?;
+        if &magic != TABLE_MAGIC {
+            return Err(PlanetError::DataError(
+                "not an ephemeris table: incorrect magic bytes".to_string(),
+            ));
+        }
+        let version = reader.read_u8().map_err(io)?;
+        if version != TABLE_VERSION {
+            return Err(PlanetError::DataError(format!(
+                "unsupported ephemeris table version {}",
+                version
+            )));
+        }
+
+        let degree = reader.read_u32::<LittleEndian>().map_err(io)? as usize;
+        let tolerance_km = reader.read_f64::<LittleEndian>().map_err(io)?;
+        let body_count = reader.read_u32::<LittleEndian>().map_err(io)?;
+
+        let mut bodies = BTreeMap::new();
+        for _ in 0..body_count {
+            let id = reader.read_i32::<LittleEndian>().map_err(io)?;
+            let granule_count = reader.read_u32::<LittleEndian>().map_err(io)?;
+            let mut granules = Vec::new();
+            for _ in 0..granule_count {
+                let start_jd = reader.read_f64::<LittleEndian>().map_err(io)?;
+                let end_jd = reader.read_f64::<LittleEndian>().map_err(io)?;
+                let mut coefficients = vec![0.0; 3 * (degree + 1)];
+                reader
+                    .read_f64_into::<LittleEndian>(&mut coefficients)
+                    .map_err(io)?;
+                granules.push(Granule {
+                    start_jd,
+                    end_jd,
+                    coefficients,
+                });
+            }
+            bodies.insert(id, granules);
+        }
+
+        Ok(Self {
+            degree,
+            tolerance_km,
+            bodies,
+        })
+    }
+
+    /// Read a table from memory, e.g. one embedded with `include_bytes!`
+    pub fn from_bytes(mut bytes: &[u8]) -> Result<Self, PlanetError> {
+        Self::read(&mut bytes)
+    }
+
+    /// Serialize the table to a byte vector
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        self.write(&mut bytes)
+            .expect("writing to a Vec cannot fail");
+        bytes
+    }
+
+    /// Save the table to a file
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<(), PlanetError> {
+        let io = |e: std::io::Error| PlanetError::DataError(format!("ephemeris table: {}", e));
+        let mut writer = BufWriter::new(File::create(path).map_err(io)?);
+        self.write(&mut writer).map_err(io)?;
+        writer.flush().map_err(io)
+    }
+
+    /// Load a table from a file
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self, PlanetError> {
+        let file = File::open(path)
+            .map_err(|e| PlanetError::DataError(format!("ephemeris table: {}", e)))?;
+        Self::read(&mut BufReader::new(file))
+    }
+}
+
+/// Fit `[start, end]`, halving it until each piece meets the tolerance
+fn fit_span(
+    ephemeris: &Ephemeris,
+    body: Body,
+    start: f64,
+    end: f64,
+    config: &TableConfig,
+    granules: &mut Vec<Granule>,
+) -> Result<(), PlanetError> {
+    let granule = fit_granule(ephemeris, body, start, end, config.degree)?;
+
+    // Check between the fitting nodes, where the error peaks
+    let checks = 2 * (config.degree + 1);
+    let mut worst: f64 = 0.0;
+    for k in 0..=checks {
+        let jd = start + (end - start) * k as f64 / checks as f64;
+        let truth = ephemeris.position(body, jd)? * AU_KM;
+        worst = worst.max((granule.evaluate(jd).0 - truth).norm());
+    }
+    if worst <= config.tolerance_km {
+        granules.push(granule);
+        return Ok(());
+    }
+
+    let half = (end - start) / 2.0;
+    if half < config.min_granule_days {
+        return Err(PlanetError::DataError(format!(
+            "{} cannot be fitted to {} km with degree {} (error {:.3} km over {} days)",
+            body.name(),
+            config.tolerance_km,
+            config.degree,
+            worst,
+            end - start
+        )));
+    }
+    fit_span(ephemeris, body, start, start + half, config, granules)?;
+    fit_span(ephemeris, body, start + half, end, config, granules)
+}
+
+/// Chebyshev interpolation of a body's position at the Chebyshev nodes
+fn fit_granule(
+    ephemeris: &Ephemeris,
+    body: Body,
+    start: f64,
+    end: f64,
+    degree: usize,
+) -> Result<Granule, PlanetError> {
+    let n = degree + 1;
+    let mid = (start + end) / 2.0;
+    let radius = (end - start) / 2.0;
+
+    let angles: Vec<f64> = (0..n).map(|k| PI * (k as f64 + 0.5) / n as f64).collect();
+    let samples = angles
+        .iter()
+        .map(|a| Ok(ephemeris.position(body, mid + radius * a.cos())? * AU_KM))
+        .collect::<Result<Vec<_>, PlanetError>>()?;
+
+    let mut coefficients = vec![0.0; 3 * n];
+    for c in 0..3 {
+        for j in 0..n {
+            let sum: f64 = angles
+                .iter()
+                .zip(&samples)
+                .map(|(a, p)| p[c] * (j as f64 * a).cos())
+                .sum();
+            let scale = if j == 0 { 1.0 } else { 2.0 };
+            coefficients[c * n + j] = scale * sum / n as f64;
+        }
+    }
+
+    Ok(Granule {
+        start_jd: start,
+        end_jd: end,
+        coefficients,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::constants::J2000;
+    use approx::assert_relative_eq;
+
+    #[test]
+    fn test_fit_meets_tolerance() {
+        let eph = Ephemeris::new();
+        let config = TableConfig::default().with_tolerance_km(0.5);
+        let (start, end) = (J2000, J2000 + 400.0);
+        let table = EphemerisTable::fit(
+            &eph,
+            &[Body::Mercury, Body::Moon, Body::Jupiter],
+            start,
+            end,
+            &config,
+        )
+        .unwrap();
+
+        assert_eq!(table.span(), Some((start, end)));
+        assert_eq!(table.bodies().len(), 3);
+
+        for body in [Body::Mercury, Body::Moon, Body::Jupiter] {
+            for i in 0..97 {
+                let jd = start + 400.0 * i as f64 / 96.0 + 0.0137;
+                let jd = jd.min(end);
+                let fitted = table.get_state(body, jd).unwrap();
+                let truth = eph.get_state(body, jd).unwrap();
+                let error_km = (fitted.position - truth.position).norm() * AU_KM;
+                assert!(error_km < 0.5, "{} off by {} km", body.name(), error_km);
+                assert_relative_eq!(fitted.velocity, truth.velocity, epsilon = 1e-6);
+            }
+        }
+
+        // The fast-moving Moon needs more granules than Jupiter
+        let counts: BTreeMap<_, _> = table.bodies.iter().map(|(k, v)| (*k, v.len())).collect();
+        assert!(counts[&301] > counts[&599]);
+    }
+
+    #[test]
+    fn test_bytes_roundtrip() {
+        let eph = Ephemeris::new();
+        let table = EphemerisTable::fit(
+            &eph,
+            &[Body::Mars],
+            J2000,
+            J2000 + 100.0,
+            &TableConfig::default(),
+        )
+        .unwrap();
+
+        let restored = EphemerisTable::from_bytes(&table.to_bytes()).unwrap();
+        assert_eq!(restored, table);
+        assert!(restored.get_state(Body::Mars, J2000 + 50.0).is_ok());
+
+        assert!(matches!(
+            restored.get_state(Body::Venus, J2000),
+            Err(PlanetError::NotFound(_))
+        ));
+        assert!(matches!(
+            restored.get_state(Body::Mars, J2000 + 101.0),
+            Err(PlanetError::TimeError(_))
+        ));
+        assert!(EphemerisTable::from_bytes(b"BINCAT\x01").is_err());
+    }
+
+    #[test]
+    fn test_unreachable_tolerance_is_reported() {
+        let config = TableConfig::default()
+            .with_degree(1)
+            .with_tolerance_km(1e-6)
+            .with_min_granule_days(4.0);
+        let result = EphemerisTable::fit(
+            &Ephemeris::new(),
+            &[Body::Moon],
+            J2000,
+            J2000 + 32.0,
+            &config,
+        );
+        assert!(matches!(result, Err(PlanetError::DataError(_))));
+    }
+}
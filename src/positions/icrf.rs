@@ -0,0 +1,431 @@
+//! Typed ICRF position vectors, after skyfield's `positionlib`
+//!
+//! Each type holds a position and velocity in ICRS axes at a time, and says
+//! by its type what the vector is measured from and which corrections it
+//! carries:
+//!
+//! * [`Barycentric`] - from the solar-system barycenter, geometric
+//! * [`Geocentric`] - from the Earth's centre, geometric
+//! * [`Astrometric`] - from an observer, corrected for light time
+//! * [`Apparent`] - an astrometric position further corrected for
+//!   gravitational deflection by the Sun and aberration
+//!
+//! The chain runs `Barycentric::observe` → `Astrometric::apparent`, as in
+//! skyfield, and every type answers [`IcrfVector::radec`],
+//! [`IcrfVector::distance`] and [`IcrfVector::separation_from`]. Observers
+//! on the Earth's surface start the same chain from
+//! [`crate::observers::ObserverAt::observe`], and their apparent positions
+//! also answer [`Apparent::altaz`].
+//!
+//! ```
+//! use starfield::planetlib::{Body, Ephemeris};
+//! use starfield::positions::icrf::{Barycentric, IcrfVector};
+//! use starfield::time::Timescale;
+//!
+//! let ts = Timescale::default();
+//! let eph = Ephemeris::new();
+//! let t = ts.utc((2024, 3, 1));
+//!
+//! let earth = Barycentric::at(&eph, Body::Earth, &t)?;
+//! let mars = earth.observe(&eph, Body::Mars)?.apparent(&eph)?;
+//! let (ra, dec, distance) = mars.radec();
+//! let venus = earth.observe(&eph, Body::Venus)?.apparent(&eph)?;
+//! let angle = mars.separation_from(&venus).unwrap();
+//! # Ok::<(), starfield::planetlib::PlanetError>(())
+//! ```
+
+use super::light_time::solve_light_time;
+use super::trace;
+use super::Position;
+use crate::accuracy::AccuracyEstimate;
+use crate::constants::{ASEC2RAD, AU_M, C, C_AUDAY, GS, RAD2DEG};
+use crate::framelib::{Frame, FrameMismatch};
+use crate::observers::GeographicLocation;
+use crate::planetlib::{Body, Ephemeris, PlanetError};
+use crate::precessionlib::compute_precession;
+use crate::time::Time;
+use nalgebra::Vector3;
+
+/// Limb-grazing cutoff for the deflection denominator, as in SOFA's `iauLd`
+const DEFLECTION_LIMIT: f64 = 1e-9;
+
+/// Deflect the unit direction `p` by the Sun and then aberrate it,
+/// returning both stages (SOFA `iauLd` and `iauAb`)
+///
+/// `q` is the unit direction from the Sun to the source, which for a
+/// source at infinity is `p` itself; `None` skips deflection, as for light
+/// from the Sun. `sun_to_observer` is in AU and `observer_velocity` in
+/// AU/day.
+pub(crate) fn deflect_and_aberrate(
+    p: &Vector3<f64>,
+    q: Option<Vector3<f64>>,
+    sun_to_observer: &Vector3<f64>,
+    observer_velocity: &Vector3<f64>,
+) -> (Vector3<f64>, Vector3<f64>) {
+    let schwarzschild_au = 2.0 * GS / (C * C) / AU_M;
+    let sun_distance = sun_to_observer.norm();
+    let e = sun_to_observer / sun_distance;
+
+    let deflected = match q {
+        Some(q) => {
+            let w = schwarzschild_au / sun_distance / q.dot(&(q + e)).max(DEFLECTION_LIMIT);
+            (p + w * p.cross(&e.cross(&q))).normalize()
+        }
+        None => *p,
+    };
+
+    let v = observer_velocity / C_AUDAY;
+    let bm1 = (1.0 - v.norm_squared()).sqrt();
+    let pdv = deflected.dot(&v);
+    let w1 = 1.0 + pdv / (1.0 + bm1);
+    let w2 = schwarzschild_au / sun_distance;
+    let aberrated = (deflected * bm1 + v * w1 + (v - deflected * pdv) * w2).normalize();
+    (deflected, aberrated)
+}
+
+/// Behaviour shared by every ICRF position type
+pub trait IcrfVector {
+    /// Position in AU, ICRS axes
+    fn position(&self) -> &Position;
+
+    /// Velocity in AU/day, ICRS axes
+    fn velocity(&self) -> &Vector3<f64>;
+
+    /// Time of the position
+    fn time(&self) -> &Time;
+
+    /// Body the vector points to, if it is a body
+    fn target(&self) -> Option<Body>;
+
+    /// Right ascension (degrees in [0, 360)), declination (degrees) and
+    /// distance in AU
+    fn radec(&self) -> (f64, f64, f64) {
+        let v = self.position().vector();
+        let r = v.norm();
+        let ra = (v.y.atan2(v.x) * RAD2DEG).rem_euclid(360.0);
+        (ra, (v.z / r).asin() * RAD2DEG, r)
+    }
+
+    /// Length of the vector in AU
+    fn distance(&self) -> f64 {
+        self.position().distance()
+    }
+
+    /// Angle to another position in radians
+    fn separation_from(&self, other: &impl IcrfVector) -> Result<f64, FrameMismatch> {
+        self.position().separation_from(other.position())
+    }
+}
+
+macro_rules! impl_icrf_vector {
+    ($($ty:ty),*) => {$(
+        impl IcrfVector for $ty {
+            fn position(&self) -> &Position {
+                &self.position
+            }
+
+            fn velocity(&self) -> &Vector3<f64> {
+                &self.velocity
+            }
+
+            fn time(&self) -> &Time {
+                &self.time
+            }
+
+            fn target(&self) -> Option<Body> {
+                self.target
+            }
+        }
+    )*};
+}
+
+impl_icrf_vector!(Barycentric, Geocentric, Astrometric, Apparent);
+
+/// Geometric position of a body or observer from the solar-system barycenter
+#[derive(Debug, Clone)]
+pub struct Barycentric {
+    /// Position in AU
+    pub position: Position,
+    /// Velocity in AU/day
+    pub velocity: Vector3<f64>,
+    /// Time of the position
+    pub time: Time,
+    /// Body at the position, or `None` for an arbitrary point
+    pub target: Option<Body>,
+    /// Site of a surface observer at the position, if it is one
+    pub location: Option<GeographicLocation>,
+}
+
+impl Barycentric {
+    /// Position of `body` at `t`
+    pub fn at(ephemeris: &Ephemeris, body: Body, t: &Time) -> Result<Self, PlanetError> {
+        let state = ephemeris.get_state(body, t.tdb())?;
+        Ok(Self {
+            position: Position::new(state.position.coords, Frame::Icrs),
+            velocity: state.velocity,
+            time: t.clone(),
+            target: Some(body),
+            location: None,
+        })
+    }
+
+    /// Position of `body` as seen from here, corrected for light time
+    pub fn observe(&self, ephemeris: &Ephemeris, body: Body) -> Result<Astrometric, PlanetError> {
+        if self.target == Some(body) {
+            return Err(PlanetError::DataError(format!(
+                "{} cannot observe itself",
+                body.name()
+            )));
+        }
+        let solution = solve_light_time(
+            ephemeris,
+            body,
+            self.position.vector(),
+            &self.velocity,
+            self.time.tdb(),
+        )?;
+        Ok(Astrometric {
+            position: solution.position,
+            velocity: solution.velocity,
+            time: self.time.clone(),
+            target: Some(body),
+            observer: self.clone(),
+            light_time_days: solution.light_time_days,
+        })
+    }
+
+    /// Geometric position of the same point from the Earth's centre
+    pub fn geocentric(&self, ephemeris: &Ephemeris) -> Result<Geocentric, PlanetError> {
+        let earth = ephemeris.get_state(Body::Earth, self.time.tdb())?;
+        Ok(Geocentric {
+            position: Position::new(self.position.vector() - earth.position.coords, Frame::Icrs),
+            velocity: self.velocity - earth.velocity,
+            time: self.time.clone(),
+            target: self.target,
+        })
+    }
+}
+
+/// Geometric position from the Earth's centre, as for a satellite
+#[derive(Debug, Clone)]
+pub struct Geocentric {
+    /// Position in AU
+    pub position: Position,
+    /// Velocity in AU/day
+    pub velocity: Vector3<f64>,
+    /// Time of the position
+    pub time: Time,
+    /// Body at the position, or `None` for a satellite or arbitrary point
+    pub target: Option<Body>,
+}
+
+impl Geocentric {
+    /// A geocentric vector in AU and AU/day, such as a satellite's
+    pub fn new(position: Vector3<f64>, velocity: Vector3<f64>, time: &Time) -> Self {
+        Self {
+            position: Position::new(position, Frame::Icrs),
+            velocity,
+            time: time.clone(),
+            target: None,
+        }
+    }
+
+    /// The same point from the solar-system barycenter
+    pub fn barycentric(&self, ephemeris: &Ephemeris) -> Result<Barycentric, PlanetError> {
+        let earth = ephemeris.get_state(Body::Earth, self.time.tdb())?;
+        Ok(Barycentric {
+            position: Position::new(self.position.vector() + earth.position.coords, Frame::Icrs),
+            velocity: self.velocity + earth.velocity,
+            time: self.time.clone(),
+            target: self.target,
+            location: None,
+        })
+    }
+}
+
+/// Position of a body from an observer, corrected for light time
+#[derive(Debug, Clone)]
+pub struct Astrometric {
+    /// Observer-to-body vector in AU
+    pub position: Position,
+    /// Velocity of the body relative to the observer in AU/day
+    pub velocity: Vector3<f64>,
+    /// Time of observation
+    pub time: Time,
+    /// Body observed
+    pub target: Option<Body>,
+    /// Where the observer was
+    pub observer: Barycentric,
+    /// Light travel time in days
+    pub light_time_days: f64,
+}
+
+impl Astrometric {
+    /// Expected error of this position given the ephemeris in use
+    pub fn accuracy(&self, ephemeris: &Ephemeris) -> AccuracyEstimate {
+        self.target.map_or_else(AccuracyEstimate::new, |body| {
+            AccuracyEstimate::for_position(ephemeris, body)
+        })
+    }
+
+    /// Apply gravitational deflection by the Sun and the aberration due to
+    /// the observer's motion
+    pub fn apparent(&self, ephemeris: &Ephemeris) -> Result<Apparent, PlanetError> {
+        let distance = self.position.distance();
+        let p = self.position.vector() / distance;
+        let observer = self.observer.position.vector();
+        let sun = ephemeris
+            .get_state(Body::Sun, self.time.tdb() - self.light_time_days)?
+            .position
+            .coords;
+        let q = (self.target != Some(Body::Sun))
+            .then(|| (observer + self.position.vector() - sun).normalize());
+
+        let (deflected, aberrated) =
+            deflect_and_aberrate(&p, q, &(observer - sun), &self.observer.velocity);
+        trace::record_correction(
+            "gravitational deflection by the Sun",
+            Frame::Icrs,
+            &p,
+            &deflected,
+        );
+        trace::record_correction("aberration", Frame::Icrs, &deflected, &aberrated);
+
+        Ok(Apparent {
+            position: Position::new(aberrated * distance, Frame::Icrs),
+            velocity: self.velocity,
+            time: self.time.clone(),
+            target: self.target,
+            observer: self.observer.clone(),
+        })
+    }
+}
+
+/// Position of a body as it actually appears to an observer
+#[derive(Debug, Clone)]
+pub struct Apparent {
+    /// Apparent observer-to-body vector in AU, GCRS axes
+    pub position: Position,
+    /// Velocity of the body relative to the observer in AU/day
+    pub velocity: Vector3<f64>,
+    /// Time of observation
+    pub time: Time,
+    /// Body observed
+    pub target: Option<Body>,
+    /// Where the observer was
+    pub observer: Barycentric,
+}
+
+impl Apparent {
+    /// Right ascension and declination in degrees referred to the mean
+    /// equator and equinox of the observation date, and distance in AU
+    pub fn radec_of_date(&self) -> (f64, f64, f64) {
+        let vector = self.position.vector();
+        let epoch_tt = self.time.tt();
+        let rotation = compute_precession(self.time.tdb());
+        trace::record_rotation(
+            "precession to date",
+            Frame::Icrs,
+            Frame::TrueOfDate { epoch_tt },
+            &rotation,
+            vector,
+        );
+        let of_date = rotation * vector;
+        let r = of_date.norm();
+        let ra = (of_date.y.atan2(of_date.x) * RAD2DEG).rem_euclid(360.0);
+        (ra, (of_date.z / r).asin() * RAD2DEG, r)
+    }
+
+    /// Altitude and azimuth in degrees, without refraction
+    ///
+    /// Only available for an observer at a surface site. Nutation is
+    /// neglected.
+    pub fn altaz(&self) -> Result<(f64, f64), PlanetError> {
+        let location = self.observer.location.as_ref().ok_or_else(|| {
+            PlanetError::DataError("altitude and azimuth need an observer location".to_string())
+        })?;
+        // `altaz` takes a geocentric vector and removes the site itself
+        let geocentric = self.position.vector() + location.geocentric_position(&self.time);
+        Ok(location.altaz(&geocentric, &self.time))
+    }
+
+    /// Expected error of [`IcrfVector::radec`] given the ephemeris in use
+    pub fn accuracy(&self, ephemeris: &Ephemeris) -> AccuracyEstimate {
+        self.target.map_or_else(AccuracyEstimate::new, |body| {
+            AccuracyEstimate::for_apparent(ephemeris, body)
+        })
+    }
+
+    /// Expected error of [`Apparent::altaz`], which adds the Delta T
+    /// source of the observation time and the neglected nutation
+    pub fn altaz_accuracy(&self, ephemeris: &Ephemeris) -> AccuracyEstimate {
+        self.target.map_or_else(AccuracyEstimate::new, |body| {
+            AccuracyEstimate::for_horizontal(ephemeris, body, &self.time)
+        })
+    }
+
+    /// Angle between this position and another apparent position, in arcseconds
+    pub fn separation_arcsec(&self, other: &Apparent) -> f64 {
+        self.position.vector().angle(other.position.vector()) / ASEC2RAD
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::observers::ObserverAt;
+    use crate::time::Timescale;
+    use approx::assert_relative_eq;
+
+    #[test]
+    fn test_chain_matches_observer_pipeline() {
+        let ts = Timescale::default();
+        let eph = Ephemeris::new();
+        let t = ts.utc((2024, 3, 1));
+
+        let earth = Barycentric::at(&eph, Body::Earth, &t).unwrap();
+        let geocenter = ObserverAt::geocenter(&eph, &t).unwrap();
+        for body in [Body::Sun, Body::Moon, Body::Jupiter] {
+            let astrometric = earth.observe(&eph, body).unwrap();
+            let apparent = astrometric.apparent(&eph).unwrap();
+
+            let observed = geocenter.observe(body).unwrap();
+            let expected = observed.apparent(&eph).unwrap();
+            assert_relative_eq!(
+                apparent.position.vector(),
+                expected.position.vector(),
+                epsilon = 1e-15
+            );
+            assert_eq!(observed.light_time_days, astrometric.light_time_days);
+            assert_eq!(expected.target, Some(body));
+            assert!(expected.altaz().is_err());
+        }
+        assert!(earth.observe(&eph, Body::Earth).is_err());
+        assert!(geocenter.observe(Body::Earth).is_err());
+    }
+
+    #[test]
+    fn test_geocentric_round_trip() {
+        let ts = Timescale::default();
+        let eph = Ephemeris::new();
+        let t = ts.utc((2024, 3, 1));
+
+        let moon = Barycentric::at(&eph, Body::Moon, &t).unwrap();
+        let geocentric = moon.geocentric(&eph).unwrap();
+        // The Moon is 356,000-407,000 km from the Earth
+        assert!((0.0023..0.0028).contains(&geocentric.distance()));
+        assert_eq!(geocentric.target(), Some(Body::Moon));
+
+        let back = geocentric.barycentric(&eph).unwrap();
+        assert_relative_eq!(
+            back.position.vector(),
+            moon.position.vector(),
+            epsilon = 1e-15
+        );
+        assert_relative_eq!(back.separation_from(&moon).unwrap(), 0.0, epsilon = 1e-7);
+
+        let satellite = Geocentric::new(Vector3::new(4.5e-5, 0.0, 0.0), Vector3::zeros(), &t);
+        assert_eq!(satellite.radec().0, 0.0);
+        assert!(satellite.target().is_none());
+    }
+}
@@ -0,0 +1,171 @@
+//! Light-time correction of ephemeris positions
+//!
+//! A body is seen where it was when the light now arriving left it.
+//! [`solve_light_time`] finds that emission time by Newton's method on
+//! `|r(t - τ) - o| = c τ`, using the body's velocity from
+//! [`Ephemeris::get_state`], and stops once successive light times agree to
+//! [`LIGHT_TIME_TOLERANCE_DAYS`]; two or three iterations suffice for any
+//! solar-system body.
+//!
+//! ```
+//! use starfield::planetlib::{Body, Ephemeris};
+//! use starfield::positions::light_time::astrometric_position;
+//! use starfield::time::Timescale;
+//!
+//! let ts = Timescale::default();
+//! let eph = Ephemeris::new();
+//! let t = ts.utc((2024, 3, 1));
+//! let jupiter = astrometric_position(&eph, Body::Jupiter, Body::Earth, &t)?;
+//! println!("{:.1} light-minutes away", jupiter.light_time_days * 1440.0);
+//! # Ok::<(), starfield::planetlib::PlanetError>(())
+//! ```
+
+use super::trace;
+use super::Position;
+use crate::constants::{C_AUDAY, DAY_S};
+use crate::framelib::Frame;
+use crate::planetlib::{Body, Ephemeris, PlanetError};
+use crate::time::Time;
+use nalgebra::Vector3;
+
+/// Convergence threshold of the light time, in days (86 ns)
+pub const LIGHT_TIME_TOLERANCE_DAYS: f64 = 1e-12;
+
+/// Iteration cap; convergence normally takes two or three
+const MAX_ITERATIONS: usize = 10;
+
+/// A light-time corrected position
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LightTimeSolution {
+    /// Observer-to-body vector in AU, ICRS axes
+    pub position: Position,
+    /// Velocity of the body relative to the observer at the emission time,
+    /// AU/day
+    pub velocity: Vector3<f64>,
+    /// Light travel time in days
+    pub light_time_days: f64,
+    /// Newton iterations taken
+    pub iterations: usize,
+}
+
+impl LightTimeSolution {
+    /// Distance from observer to body in AU
+    pub fn distance_au(&self) -> f64 {
+        self.position.distance()
+    }
+}
+
+/// Position of `body` as seen from `observer_position` at TDB Julian date
+/// `jd`, antedated for light time
+///
+/// `observer_position` is in AU relative to the solar-system barycenter,
+/// and `observer_velocity` in AU/day; pass zeros for a fixed observer.
+pub fn solve_light_time(
+    ephemeris: &Ephemeris,
+    body: Body,
+    observer_position: &Vector3<f64>,
+    observer_velocity: &Vector3<f64>,
+    jd: f64,
+) -> Result<LightTimeSolution, PlanetError> {
+    let mut light_time = 0.0;
+    for iteration in 1..=MAX_ITERATIONS {
+        let state = ephemeris.get_state(body, jd - light_time)?;
+        let vector = state.position.coords - observer_position;
+        let distance = vector.norm();
+
+        // f(τ) = |r(t - τ) - o| - c τ, and f'(τ) = -(u · v) - c
+        let residual = distance - C_AUDAY * light_time;
+        let slope = -vector.dot(&state.velocity) / distance - C_AUDAY;
+        let step = residual / slope;
+        light_time -= step;
+
+        if step.abs() < LIGHT_TIME_TOLERANCE_DAYS {
+            let state = ephemeris.get_state(body, jd - light_time)?;
+            let vector = state.position.coords - observer_position;
+            if trace::is_tracing() {
+                let geometric = ephemeris.get_state(body, jd)?.position.coords - observer_position;
+                trace::record_correction(
+                    &format!(
+                        "light time to {} ({:.3} s)",
+                        body.name(),
+                        light_time * DAY_S
+                    ),
+                    Frame::Icrs,
+                    &geometric,
+                    &vector,
+                );
+            }
+            return Ok(LightTimeSolution {
+                position: Position::new(vector, Frame::Icrs),
+                velocity: state.velocity - observer_velocity,
+                light_time_days: light_time,
+                iterations: iteration,
+            });
+        }
+    }
+    Err(PlanetError::DataError(format!(
+        "light time to {} did not converge",
+        body.name()
+    )))
+}
+
+/// Astrometric position of `body` as seen from the centre of `observer` at
+/// time `t`
+pub fn astrometric_position(
+    ephemeris: &Ephemeris,
+    body: Body,
+    observer: Body,
+    t: &Time,
+) -> Result<LightTimeSolution, PlanetError> {
+    if body == observer {
+        return Err(PlanetError::DataError(format!(
+            "{} cannot observe itself",
+            body.name()
+        )));
+    }
+    let jd = t.tdb();
+    let state = ephemeris.get_state(observer, jd)?;
+    solve_light_time(ephemeris, body, &state.position.coords, &state.velocity, jd)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::observers::ObserverAt;
+    use crate::time::Timescale;
+    use approx::assert_relative_eq;
+
+    #[test]
+    fn test_light_time_consistency() {
+        let ts = Timescale::default();
+        let eph = Ephemeris::new();
+        let t = ts.utc((2024, 3, 1));
+
+        for body in [Body::Sun, Body::Moon, Body::Mars, Body::Neptune] {
+            let solution = astrometric_position(&eph, body, Body::Earth, &t).unwrap();
+            // The light time is the distance at the speed of light
+            assert_relative_eq!(
+                solution.light_time_days * C_AUDAY,
+                solution.distance_au(),
+                max_relative = 1e-12
+            );
+            assert!(solution.iterations <= 4, "{:?}", body);
+
+            // and agrees with the observer pipeline
+            let observed = ObserverAt::geocenter(&eph, &t)
+                .unwrap()
+                .observe(body)
+                .unwrap();
+            assert_relative_eq!(
+                solution.light_time_days,
+                observed.light_time_days,
+                max_relative = 1e-9
+            );
+        }
+
+        // Sunlight takes about 8.3 minutes
+        let sun = astrometric_position(&eph, Body::Sun, Body::Earth, &t).unwrap();
+        assert!((sun.light_time_days * 1440.0 - 8.2).abs() < 0.2);
+        assert!(astrometric_position(&eph, Body::Earth, Body::Earth, &t).is_err());
+    }
+}
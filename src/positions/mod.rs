@@ -1 +1,126 @@
-//! positions module\n\n// Placeholder for positions implementations
+//! Position vectors tagged with their reference frame
+
+pub mod icrf;
+pub mod light_time;
+pub mod trace;
+
+use crate::framelib::{Frame, FrameMismatch};
+use nalgebra::{Matrix3, Vector3};
+use std::ops::{Add, Neg, Sub};
+
+/// A Cartesian position (AU) whose components belong to a known frame
+///
+/// The `+` and `-` operators check frames with [`Frame::debug_check`];
+/// [`Position::checked_add`] and [`Position::checked_sub`] always check and
+/// return an error instead.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Position {
+    vector: Vector3<f64>,
+    frame: Frame,
+}
+
+impl Position {
+    /// Tag a vector in AU with its frame
+    pub fn new(vector: Vector3<f64>, frame: Frame) -> Self {
+        Self { vector, frame }
+    }
+
+    /// Cartesian components in AU
+    pub fn vector(&self) -> &Vector3<f64> {
+        &self.vector
+    }
+
+    /// Frame the components are expressed in
+    pub fn frame(&self) -> Frame {
+        self.frame
+    }
+
+    /// Length of the vector in AU
+    pub fn distance(&self) -> f64 {
+        self.vector.norm()
+    }
+
+    /// Add two positions after checking they share a frame
+    pub fn checked_add(&self, other: &Position) -> Result<Position, FrameMismatch> {
+        self.frame.check(&other.frame)?;
+        Ok(Position::new(self.vector + other.vector, self.frame))
+    }
+
+    /// Subtract two positions after checking they share a frame
+    pub fn checked_sub(&self, other: &Position) -> Result<Position, FrameMismatch> {
+        self.frame.check(&other.frame)?;
+        Ok(Position::new(self.vector - other.vector, self.frame))
+    }
+
+    /// Angle between two positions in radians, after checking frames
+    pub fn separation_from(&self, other: &Position) -> Result<f64, FrameMismatch> {
+        self.frame.check(&other.frame)?;
+        Ok(self.vector.angle(&other.vector))
+    }
+
+    /// Rotate into another frame
+    ///
+    /// `rotation` must map the components from `self.frame()` into `to`;
+    /// the caller vouches for that pairing.
+    pub fn rotated(&self, rotation: &Matrix3<f64>, to: Frame) -> Position {
+        trace::record_rotation("frame rotation", self.frame, to, rotation, &self.vector);
+        Position::new(rotation * self.vector, to)
+    }
+}
+
+impl Add for Position {
+    type Output = Position;
+
+    #[track_caller]
+    fn add(self, other: Position) -> Position {
+        self.frame.debug_check(&other.frame);
+        Position::new(self.vector + other.vector, self.frame)
+    }
+}
+
+impl Sub for Position {
+    type Output = Position;
+
+    #[track_caller]
+    fn sub(self, other: Position) -> Position {
+        self.frame.debug_check(&other.frame);
+        Position::new(self.vector - other.vector, self.frame)
+    }
+}
+
+impl Neg for Position {
+    type Output = Position;
+
+    fn neg(self) -> Position {
+        Position::new(-self.vector, self.frame)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::framelib::icrs_to_fk5_j2000;
+
+    #[test]
+    fn test_checked_arithmetic() {
+        let a = Position::new(Vector3::new(1.0, 0.0, 0.0), Frame::Icrs);
+        let b = Position::new(Vector3::new(0.0, 1.0, 0.0), Frame::Icrs);
+        let c = a.checked_sub(&b).unwrap();
+        assert_eq!(c.vector(), &Vector3::new(1.0, -1.0, 0.0));
+        assert_eq!((a + b).frame(), Frame::Icrs);
+
+        let fk5 = b.rotated(&icrs_to_fk5_j2000(), Frame::Fk5J2000);
+        assert!(a.checked_add(&fk5).is_err());
+        assert!(a.separation_from(&fk5).is_err());
+        assert!(a.separation_from(&b).is_ok());
+    }
+
+    #[test]
+    #[cfg(debug_assertions)]
+    #[should_panic(expected = "frame mismatch")]
+    fn test_mixed_frames_panic_in_debug() {
+        let a = Position::new(Vector3::x(), Frame::Icrs);
+        let b = Position::new(Vector3::x(), Frame::Itrs);
+        let _ = a - b;
+    }
+}
@@ -0,0 +1,281 @@
+//! Opt-in tracing of the transformations applied to positions
+//!
+//! Wrapping a computation in [`traced`] records every rotation and
+//! correction the library applies along the way: its name, the frames
+//! involved, the matrix, and how far it moved the vector. The resulting
+//! [`TransformTrace`] prints as a report, which makes it easy to see which
+//! step accounts for an arcsecond-level disagreement with another tool.
+//!
+//! ```
+//! use starfield::observers::GeographicLocation;
+//! use starfield::positions::trace::traced;
+//! use starfield::time::Timescale;
+//! use nalgebra::Vector3;
+//!
+//! let ts = Timescale::default();
+//! let t = ts.utc((2024, 6, 1, 3, 0, 0.0));
+//! let site = GeographicLocation::new(51.48, 0.0, 46.0);
+//!
+//! let ((alt, az), trace) = traced(|| site.altaz(&Vector3::new(0.0, 0.0, 1e6), &t));
+//! println!("{alt:.4} {az:.4}\n{trace}");
+//! assert_eq!(trace.len(), 2);
+//! ```
+//!
+//! Outside [`traced`] nothing is recorded and the hooks cost a thread-local
+//! lookup.
+
+use crate::constants::ASEC2RAD;
+use crate::framelib::Frame;
+use nalgebra::{Matrix3, Vector3};
+use std::cell::RefCell;
+use std::fmt;
+
+thread_local! {
+    static ACTIVE: RefCell<Option<TransformTrace>> = const { RefCell::new(None) };
+}
+
+/// What kind of transformation a step applied
+#[derive(Debug, Clone, PartialEq)]
+pub enum StepKind {
+    /// A change of axes by a rotation matrix
+    Rotation {
+        /// Frame of the input vector
+        from: Frame,
+        /// Frame of the output vector
+        to: Frame,
+        /// The rotation applied
+        matrix: Matrix3<f64>,
+    },
+    /// A physical correction that moves the vector within its frame
+    /// (parallax, light time, aberration, ...)
+    Correction {
+        /// Frame both vectors are expressed in
+        frame: Frame,
+    },
+}
+
+/// One recorded transformation
+#[derive(Debug, Clone, PartialEq)]
+pub struct TraceStep {
+    /// Human-readable name of the step
+    pub name: String,
+    /// Rotation or correction details
+    pub kind: StepKind,
+    /// Vector before the step
+    pub before: Vector3<f64>,
+    /// Vector after the step
+    pub after: Vector3<f64>,
+}
+
+impl TraceStep {
+    /// Angle in arcseconds between the vector before and after the step
+    ///
+    /// For a rotation this is how far the particular vector moved relative
+    /// to the axes, which is at most [`TraceStep::rotation_angle_arcsec`].
+    pub fn shift_arcsec(&self) -> f64 {
+        if self.before.norm() == 0.0 || self.after.norm() == 0.0 {
+            return 0.0;
+        }
+        self.before.angle(&self.after) / ASEC2RAD
+    }
+
+    /// Change in the vector's length, in the vector's units
+    pub fn length_change(&self) -> f64 {
+        self.after.norm() - self.before.norm()
+    }
+
+    /// Total rotation angle of a rotation step's matrix in arcseconds, or
+    /// `None` for a correction
+    pub fn rotation_angle_arcsec(&self) -> Option<f64> {
+        match &self.kind {
+            StepKind::Rotation { matrix, .. } => {
+                let cos = ((matrix.trace() - 1.0) / 2.0).clamp(-1.0, 1.0);
+                Some(cos.acos() / ASEC2RAD)
+            }
+            StepKind::Correction { .. } => None,
+        }
+    }
+}
+
+/// Ordered record of the transformations applied during a computation
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct TransformTrace {
+    steps: Vec<TraceStep>,
+}
+
+impl TransformTrace {
+    /// Recorded steps in the order they were applied
+    pub fn steps(&self) -> &[TraceStep] {
+        &self.steps
+    }
+
+    /// Number of recorded steps
+    pub fn len(&self) -> usize {
+        self.steps.len()
+    }
+
+    /// Whether nothing was recorded
+    pub fn is_empty(&self) -> bool {
+        self.steps.is_empty()
+    }
+
+    /// Steps whose name contains `pattern`
+    pub fn find<'a>(&'a self, pattern: &'a str) -> impl Iterator<Item = &'a TraceStep> + 'a {
+        self.steps.iter().filter(move |s| s.name.contains(pattern))
+    }
+
+    /// Render the trace as a text report
+    pub fn report(&self) -> String {
+        self.to_string()
+    }
+}
+
+impl fmt::Display for TransformTrace {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "{} transformation step(s)", self.steps.len())?;
+        for (i, step) in self.steps.iter().enumerate() {
+            match &step.kind {
+                StepKind::Rotation { from, to, matrix } => {
+                    writeln!(
+                        f,
+                        "{:>3}. {} [rotation {} -> {}, {:.3}\" about its axis]",
+                        i + 1,
+                        step.name,
+                        from,
+                        to,
+                        step.rotation_angle_arcsec().unwrap_or_default()
+                    )?;
+                    for row in matrix.row_iter() {
+                        writeln!(
+                            f,
+                            "       [{:>+.12} {:>+.12} {:>+.12}]",
+                            row[0], row[1], row[2]
+                        )?;
+                    }
+                }
+                StepKind::Correction { frame } => {
+                    writeln!(f, "{:>3}. {} [correction in {}]", i + 1, step.name, frame)?;
+                }
+            }
+            writeln!(
+                f,
+                "       direction moved {:.6}\", length changed {:+.3e}",
+                step.shift_arcsec(),
+                step.length_change()
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// Run `f`, recording every transformation it applies
+///
+/// Traces nest: an inner `traced` call captures its own steps, which do
+/// not appear in the outer trace.
+pub fn traced<T>(f: impl FnOnce() -> T) -> (T, TransformTrace) {
+    let outer = ACTIVE.with(|active| active.replace(Some(TransformTrace::default())));
+    let result = f();
+    let trace = ACTIVE.with(|active| active.replace(outer));
+    (result, trace.unwrap_or_default())
+}
+
+/// Whether a trace is being recorded on this thread
+pub fn is_tracing() -> bool {
+    ACTIVE.with(|active| active.borrow().is_some())
+}
+
+fn record(step: impl FnOnce() -> TraceStep) {
+    ACTIVE.with(|active| {
+        if let Some(trace) = active.borrow_mut().as_mut() {
+            trace.steps.push(step());
+        }
+    });
+}
+
+/// Record a rotation of `before` by `matrix` from one frame into another
+pub(crate) fn record_rotation(
+    name: &str,
+    from: Frame,
+    to: Frame,
+    matrix: &Matrix3<f64>,
+    before: &Vector3<f64>,
+) {
+    record(|| TraceStep {
+        name: name.to_string(),
+        kind: StepKind::Rotation {
+            from,
+            to,
+            matrix: *matrix,
+        },
+        before: *before,
+        after: matrix * before,
+    });
+}
+
+/// Record a correction that moved `before` to `after` within `frame`
+pub(crate) fn record_correction(
+    name: &str,
+    frame: Frame,
+    before: &Vector3<f64>,
+    after: &Vector3<f64>,
+) {
+    record(|| TraceStep {
+        name: name.to_string(),
+        kind: StepKind::Correction { frame },
+        before: *before,
+        after: *after,
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::framelib::{icrs_to_fk5_j2000, rot_z};
+    use crate::positions::Position;
+    use approx::assert_relative_eq;
+
+    #[test]
+    fn test_records_only_inside_traced() {
+        let p = Position::new(Vector3::x(), Frame::Icrs);
+        let _ = p.rotated(&icrs_to_fk5_j2000(), Frame::Fk5J2000);
+        assert!(!is_tracing());
+
+        let (fk5, trace) = traced(|| {
+            assert!(is_tracing());
+            p.rotated(&icrs_to_fk5_j2000(), Frame::Fk5J2000)
+        });
+        assert_eq!(trace.len(), 1);
+        let step = &trace.steps()[0];
+        assert_eq!(&step.after, fk5.vector());
+        // The frame bias is about 23 mas
+        let angle = step.rotation_angle_arcsec().unwrap();
+        assert!((0.01..0.05).contains(&angle), "{}", angle);
+        assert!(trace.report().contains("ICRS -> FK5 J2000"));
+    }
+
+    #[test]
+    fn test_nested_traces_are_separate() {
+        let (inner, outer) = traced(|| {
+            record_correction("outer", Frame::Icrs, &Vector3::x(), &Vector3::y());
+            let (_, inner) = traced(|| {
+                record_rotation(
+                    "inner",
+                    Frame::Icrs,
+                    Frame::Icrs,
+                    &rot_z(ASEC2RAD),
+                    &Vector3::x(),
+                )
+            });
+            inner
+        });
+        assert_eq!(outer.len(), 1);
+        assert_relative_eq!(
+            outer.steps()[0].shift_arcsec(),
+            324_000.0,
+            max_relative = 1e-12
+        );
+        assert_eq!(inner.len(), 1);
+        assert_relative_eq!(inner.steps()[0].shift_arcsec(), 1.0, epsilon = 1e-3);
+        assert_eq!(inner.find("inner").count(), 1);
+    }
+}
@@ -1 +1,95 @@
-//! precessionlib module\n\n// Placeholder for precessionlib implementations
+//! Precession of the equator and ecliptic
+//!
+//! Implements the IAU 2006 (P03) precession model of Capitaine et al. (2003),
+//! following the conventions of skyfield's `precessionlib`.
+
+use crate::constants::{ASEC2RAD, J2000};
+use crate::framelib::{rot_y, rot_z};
+use nalgebra::Matrix3;
+
+/// Mean obliquity of the ecliptic in radians (IAU 2006)
+///
+/// # Arguments
+///
+/// * `jd_tdb` - Julian date in TDB (TT may be used with negligible error)
+pub fn mean_obliquity(jd_tdb: f64) -> f64 {
+    let t = (jd_tdb - J2000) / 36525.0;
+
+    let epsilon = ((((-0.000_000_043_4 * t - 0.000_000_576) * t + 0.002_003_40) * t - 0.000_183_1)
+        * t
+        - 46.836_769)
+        * t
+        + 84_381.406;
+
+    epsilon * ASEC2RAD
+}
+
+/// Compute the precession matrix from the J2000 mean equator and equinox to
+/// the mean equator and equinox of date
+///
+/// Multiply a J2000 vector by the returned matrix to obtain its mean-of-date
+/// coordinates; the transpose performs the inverse rotation.
+pub fn compute_precession(jd_tdb: f64) -> Matrix3<f64> {
+    let t = (jd_tdb - J2000) / 36525.0;
+
+    let zeta = (((((-0.000_000_317_3 * t - 0.000_005_971) * t + 0.018_018_28) * t + 0.298_849_9)
+        * t
+        + 2_306.083_227)
+        * t
+        + 2.650_545)
+        * ASEC2RAD;
+
+    let z = (((((-0.000_000_290_4 * t - 0.000_028_596) * t + 0.018_268_37) * t + 1.092_734_8) * t
+        + 2_306.077_181)
+        * t
+        - 2.650_545)
+        * ASEC2RAD;
+
+    let theta = (((((-0.000_000_127_4 * t - 0.000_007_089) * t - 0.041_822_64) * t - 0.429_493_4)
+        * t
+        + 2_004.191_903)
+        * t)
+        * ASEC2RAD;
+
+    rot_z(z) * rot_y(-theta) * rot_z(zeta)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::constants::DEG2RAD;
+    use approx::assert_relative_eq;
+    use nalgebra::Vector3;
+
+    #[test]
+    fn test_mean_obliquity_j2000() {
+        assert_relative_eq!(
+            mean_obliquity(J2000),
+            84_381.406 * ASEC2RAD,
+            epsilon = 1e-15
+        );
+    }
+
+    #[test]
+    fn test_precession_identity_at_j2000() {
+        let p = compute_precession(J2000);
+        assert_relative_eq!(p, Matrix3::identity(), epsilon = 1e-12);
+    }
+
+    #[test]
+    fn test_precession_theta_persei() {
+        // Meeus, Astronomical Algorithms, example 21.b: theta Persei from the
+        // J2000 mean place (already advanced for proper motion) to 2028 Nov 13.19 TD
+        let ra0 = 41.054_063 * DEG2RAD;
+        let dec0 = 49.227_750 * DEG2RAD;
+        let v = Vector3::new(dec0.cos() * ra0.cos(), dec0.cos() * ra0.sin(), dec0.sin());
+
+        let p = compute_precession(2_462_088.69) * v;
+        let ra = p.y.atan2(p.x) / DEG2RAD;
+        let dec = p.z.asin() / DEG2RAD;
+
+        // Meeus uses the IAU 1976 model; P03 agrees to well under an arcsecond here
+        assert_relative_eq!(ra, 41.547_214, epsilon = 1e-4);
+        assert_relative_eq!(dec, 49.348_483, epsilon = 1e-4);
+    }
+}
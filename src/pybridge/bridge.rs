@@ -48,7 +48,7 @@ impl PyRustBridge {
             let get_result_code = "_result = rust.get_result()";
 
             for code_block in [helper_code, code, get_result_code] {
-                println!("Running code block: \n{}", code_block);
+                log::debug!("Running code block: \n{}", code_block);
                 match py.run(code_block, Some(globals), None) {
                     Ok(_) => {}
                     Err(e) => {
@@ -0,0 +1,174 @@
+//! Close-approach screening between two satellites
+//!
+//! Both satellites are sampled on a coarse grid to find local minima of
+//! their separation. Each minimum is then refined by Newton iteration on
+//! the relative position and velocity, whose dot product vanishes at the
+//! time of closest approach. The coarse step must be short enough that two
+//! approaches never fall in the same pair of steps; ten seconds is ample
+//! for objects in low Earth orbit.
+
+use super::{EarthSatellite, SatelliteError, TemeState};
+use crate::constants::DAY_S;
+use crate::time::Time;
+use nalgebra::Vector3;
+
+/// Coarse sampling step in seconds
+const SCREENING_STEP_S: f64 = 10.0;
+
+/// Convergence threshold of the refinement in seconds
+const REFINE_TOLERANCE_S: f64 = 1e-6;
+
+/// A close approach between two satellites
+#[derive(Debug, Clone)]
+pub struct Conjunction {
+    /// Time of closest approach
+    pub time: Time,
+    /// Separation at closest approach in km
+    pub miss_distance_km: f64,
+    /// Relative speed at closest approach in km/s
+    pub relative_speed_km_s: f64,
+    /// Position of the second satellite relative to the first, km, TEME
+    pub relative_position_km: Vector3<f64>,
+}
+
+/// Relative position and velocity of `b` with respect to `a` at `t`
+fn relative(a: &EarthSatellite, b: &EarthSatellite, t: &Time) -> Result<TemeState, SatelliteError> {
+    let (sa, sb) = (a.at(t)?, b.at(t)?);
+    Ok(TemeState {
+        position_km: sb.position_km - sa.position_km,
+        velocity_km_s: sb.velocity_km_s - sa.velocity_km_s,
+    })
+}
+
+/// Every local minimum of the separation between `start` and `end` closer
+/// than `threshold_km`
+pub fn find_conjunctions(
+    a: &EarthSatellite,
+    b: &EarthSatellite,
+    start: &Time,
+    end: &Time,
+    threshold_km: f64,
+) -> Result<Vec<Conjunction>, SatelliteError> {
+    let ts = start.timescale();
+    let (jd_start, jd_end) = (start.tt(), end.tt());
+    let step = SCREENING_STEP_S / DAY_S;
+    let steps = ((jd_end - jd_start) / step).ceil().max(1.0) as usize;
+
+    let distances = (0..=steps)
+        .map(|i| {
+            let jd = (jd_start + i as f64 * step).min(jd_end);
+            relative(a, b, &ts.tt_jd(jd, None)).map(|s| (jd, s.position_km.norm()))
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let mut conjunctions = Vec::new();
+    for i in 0..distances.len() {
+        let (jd, distance) = distances[i];
+        let before = i.checked_sub(1).map_or(f64::INFINITY, |j| distances[j].1);
+        let after = distances.get(i + 1).map_or(f64::INFINITY, |d| d.1);
+        if distance > before || distance > after {
+            continue;
+        }
+        let lo = distances[i.saturating_sub(1)].0;
+        let hi = distances[(i + 1).min(distances.len() - 1)].0;
+        let conjunction = refine(a, b, jd, lo, hi)?;
+        if conjunction.miss_distance_km <= threshold_km {
+            conjunctions.push(conjunction);
+        }
+    }
+    Ok(conjunctions)
+}
+
+/// The closest approach between `start` and `end`
+pub fn closest_approach(
+    a: &EarthSatellite,
+    b: &EarthSatellite,
+    start: &Time,
+    end: &Time,
+) -> Result<Conjunction, SatelliteError> {
+    find_conjunctions(a, b, start, end, f64::INFINITY)?
+        .into_iter()
+        .min_by(|x, y| x.miss_distance_km.total_cmp(&y.miss_distance_km))
+        .ok_or_else(|| SatelliteError::Propagation("empty screening window".to_string()))
+}
+
+/// Newton iteration for the time in `[lo, hi]` (TT Julian dates) at which
+/// the relative velocity is perpendicular to the relative position
+fn refine(
+    a: &EarthSatellite,
+    b: &EarthSatellite,
+    jd: f64,
+    lo: f64,
+    hi: f64,
+) -> Result<Conjunction, SatelliteError> {
+    let ts = a.epoch().timescale();
+    let mut jd = jd;
+    let mut state = relative(a, b, &ts.tt_jd(jd, None))?;
+    for _ in 0..20 {
+        let speed_sq = state.velocity_km_s.norm_squared();
+        if speed_sq == 0.0 {
+            break;
+        }
+        let dt_s = -state.position_km.dot(&state.velocity_km_s) / speed_sq;
+        let next = (jd + dt_s / DAY_S).clamp(lo, hi);
+        let moved_s = (next - jd).abs() * DAY_S;
+        jd = next;
+        state = relative(a, b, &ts.tt_jd(jd, None))?;
+        if moved_s < REFINE_TOLERANCE_S {
+            break;
+        }
+    }
+
+    Ok(Conjunction {
+        time: ts.tt_jd(jd, None),
+        miss_distance_km: state.position_km.norm(),
+        relative_speed_km_s: state.velocity_km_s.norm(),
+        relative_position_km: state.position_km,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::satellites::{Tle, ISS_TLE};
+    use crate::time::{TimeDelta, Timescale};
+    use approx::assert_relative_eq;
+
+    fn iss_tle() -> Tle {
+        Tle::parse(ISS_TLE.0, ISS_TLE.1).unwrap()
+    }
+
+    #[test]
+    fn test_crossing_orbits() {
+        let ts = Timescale::default();
+        let a = EarthSatellite::from_tle(iss_tle(), &ts).unwrap();
+        // Same orbit tilted by 8 degrees: the two meet near the nodes
+        let mut tle = iss_tle();
+        tle.inclination_deg += 8.0;
+        let b = EarthSatellite::from_tle(tle, &ts).unwrap();
+
+        let start = a.epoch().clone();
+        let end = &start + TimeDelta::days(0.25);
+        let closest = closest_approach(&a, &b, &start, &end).unwrap();
+        assert!(
+            closest.miss_distance_km < 50.0,
+            "{}",
+            closest.miss_distance_km
+        );
+        // Crossing at 2 v sin(4°) for v ~ 7.7 km/s
+        assert_relative_eq!(closest.relative_speed_km_s, 1.07, epsilon = 0.1);
+
+        // The refined time is a true minimum
+        let at = |offset_s: f64| {
+            let t = &closest.time + TimeDelta::seconds(offset_s);
+            relative(&a, &b, &t).unwrap().position_km.norm()
+        };
+        assert!(at(-0.5) > closest.miss_distance_km);
+        assert!(at(0.5) > closest.miss_distance_km);
+
+        // Node crossings come twice per orbit
+        let all = find_conjunctions(&a, &b, &start, &end, 100.0).unwrap();
+        assert!((7..=8).contains(&all.len()), "{}", all.len());
+        assert!(all.iter().all(|c| c.miss_distance_km <= 100.0));
+    }
+}
@@ -0,0 +1,170 @@
+//! From SGP4's TEME frame to the sky
+//!
+//! SGP4 works in the True Equator, Mean Equinox frame of the moment. Its
+//! equinox differs from the mean equinox of date only by the equation of
+//! the equinoxes, a nutation term of at most 1.2″ that the crate neglects
+//! elsewhere too, so TEME is taken to J2000 by undoing precession alone:
+//! well inside the few hundred metres to which SGP4 itself is good.
+//!
+//! ```
+//! use starfield::observers::GeographicLocation;
+//! use starfield::satellites::EarthSatellite;
+//! use starfield::time::Timescale;
+//!
+//! let ts = Timescale::default();
+//! let iss = EarthSatellite::parse(
+//!     "1 25544U 98067A   08264.51782528 -.00002182  00000-0 -11606-4 0  2927",
+//!     "2 25544  51.6416 247.4627 0006703 130.5360 325.0288 15.72125391563537",
+//!     &ts,
+//! )?;
+//! let t = ts.utc((2008, 9, 20, 14, 0, 0.0));
+//! let below = iss.subpoint(&t)?;
+//! println!("over {:.2}°, {:.2}° at {:.0} km", below.latitude_deg, below.longitude_deg,
+//!     below.elevation_m / 1000.0);
+//!
+//! let site = GeographicLocation::new(51.48, 0.0, 46.0);
+//! let (alt, az, range_km) = iss.altaz(&site, &t)?;
+//! # Ok::<(), starfield::satellites::SatelliteError>(())
+//! ```
+
+use super::{EarthSatellite, SatelliteError};
+use crate::constants::{AU_KM, AU_M, EARTH_RADIUS, IERS_2010_INVERSE_EARTH_FLATTENING, RAD2DEG};
+use crate::coordinates::Equatorial;
+use crate::earthlib::terrestrial_to_celestial;
+use crate::observers::GeographicLocation;
+use crate::precessionlib::compute_precession;
+use crate::time::Time;
+use nalgebra::{Matrix3, Vector3};
+
+/// Rotation from TEME at `t` to J2000 equatorial axes
+pub fn teme_to_j2000(t: &Time) -> Matrix3<f64> {
+    compute_precession(t.tdb()).transpose()
+}
+
+impl EarthSatellite {
+    /// Geocentric position at `t` in J2000 equatorial axes, in AU
+    pub fn geocentric_position(&self, t: &Time) -> Result<Vector3<f64>, SatelliteError> {
+        Ok(teme_to_j2000(t) * self.at(t)?.position_km / AU_KM)
+    }
+
+    /// Geocentric direction of the satellite at `t`, J2000
+    pub fn equatorial(&self, t: &Time) -> Result<Equatorial, SatelliteError> {
+        Ok(direction(&self.geocentric_position(t)?))
+    }
+
+    /// Direction of the satellite from `location` at `t`, J2000, for
+    /// pointing a telescope
+    pub fn topocentric_equatorial(
+        &self,
+        location: &GeographicLocation,
+        t: &Time,
+    ) -> Result<Equatorial, SatelliteError> {
+        Ok(direction(
+            &(self.geocentric_position(t)? - location.geocentric_position(t)),
+        ))
+    }
+
+    /// Altitude and azimuth in degrees and range in km of the satellite
+    /// from `location` at `t`, without refraction
+    pub fn altaz(
+        &self,
+        location: &GeographicLocation,
+        t: &Time,
+    ) -> Result<(f64, f64, f64), SatelliteError> {
+        let geocentric = self.geocentric_position(t)?;
+        let (alt, az) = location.altaz(&geocentric, t);
+        let range = (geocentric - location.geocentric_position(t)).norm() * AU_KM;
+        Ok((alt, az, range))
+    }
+
+    /// Point on the ellipsoid directly beneath the satellite at `t`, with
+    /// the satellite's height above it as the elevation
+    pub fn subpoint(&self, t: &Time) -> Result<GeographicLocation, SatelliteError> {
+        let itrs = terrestrial_to_celestial(t).transpose() * self.geocentric_position(t)?;
+        Ok(geodetic(&(itrs * AU_M)))
+    }
+}
+
+/// Unit direction of a J2000 vector
+fn direction(v: &Vector3<f64>) -> Equatorial {
+    Equatorial::new(v.y.atan2(v.x), (v.z / v.norm()).asin())
+}
+
+/// Geodetic latitude, longitude and height of an Earth-fixed position in
+/// metres, by Bowring's iteration
+pub(crate) fn geodetic(position: &Vector3<f64>) -> GeographicLocation {
+    let f = 1.0 / IERS_2010_INVERSE_EARTH_FLATTENING;
+    let e2 = f * (2.0 - f);
+    let p = position.x.hypot(position.y);
+
+    let mut latitude = position.z.atan2(p * (1.0 - e2));
+    let mut height = 0.0;
+    for _ in 0..5 {
+        let sin_lat = latitude.sin();
+        let n = EARTH_RADIUS / (1.0 - e2 * sin_lat * sin_lat).sqrt();
+        height = p / latitude.cos() - n;
+        latitude = position.z.atan2(p * (1.0 - e2 * n / (n + height)));
+    }
+
+    GeographicLocation::new(
+        latitude * RAD2DEG,
+        position.y.atan2(position.x) * RAD2DEG,
+        height,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::satellites::iss;
+    use crate::time::Timescale;
+    use approx::assert_relative_eq;
+
+    #[test]
+    fn test_geodetic_inverts_terra() {
+        for (lat, lon, h) in [
+            (0.0, 10.0, 0.0),
+            (51.5, -0.1, 400_000.0),
+            (-89.0, 170.0, 3_000.0),
+        ] {
+            let itrs = crate::earthlib::terra(
+                lat * crate::constants::DEG2RAD,
+                lon * crate::constants::DEG2RAD,
+                h,
+            );
+            let back = geodetic(&(itrs * AU_M));
+            assert_relative_eq!(back.latitude_deg, lat, epsilon = 1e-9);
+            assert_relative_eq!(back.longitude_deg, lon, epsilon = 1e-9);
+            assert_relative_eq!(back.elevation_m, h, epsilon = 1e-3);
+        }
+    }
+
+    #[test]
+    fn test_overhead_from_the_subpoint() {
+        let ts = Timescale::default();
+        let iss = iss(&ts);
+        let t = ts.utc((2008, 9, 20, 14, 0, 0.0));
+
+        let below = iss.subpoint(&t).unwrap();
+        assert!((300_000.0..450_000.0).contains(&below.elevation_m));
+        assert!(below.latitude_deg.abs() <= 52.0);
+
+        // Seen from the ground beneath, the satellite is at the zenith
+        let ground = GeographicLocation::new(below.latitude_deg, below.longitude_deg, 0.0);
+        let (alt, _, range_km) = iss.altaz(&ground, &t).unwrap();
+        assert_relative_eq!(alt, 90.0, epsilon = 1e-6);
+        assert_relative_eq!(range_km, below.elevation_m / 1000.0, epsilon = 1e-6);
+
+        // The rotation keeps distances, and from the subpoint the geocentric
+        // and topocentric directions differ only by the angle between the
+        // geodetic and geocentric verticals
+        let teme = iss.at(&t).unwrap().position_km.norm();
+        let j2000 = iss.geocentric_position(&t).unwrap().norm() * AU_KM;
+        assert_relative_eq!(teme, j2000, epsilon = 1e-6);
+        let parallax = iss
+            .equatorial(&t)
+            .unwrap()
+            .angular_distance(&iss.topocentric_equatorial(&ground, &t).unwrap());
+        assert!(parallax * RAD2DEG < 0.25);
+    }
+}
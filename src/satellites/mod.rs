@@ -0,0 +1,121 @@
+//! Earth satellites from two-line element sets and OMMs
+//!
+//! [`EarthSatellite`] pairs a parsed [`Tle`] (or an element set read from a
+//! CCSDS Orbit Mean-Elements Message with [`Tle::parse_omm`]) with an
+//! [`Sgp4`] model and propagates it to any [`Time`]. States come out in
+//! SGP4's TEME frame; [`frames`] takes them on to J2000, the subpoint and
+//! an observer's altitude and azimuth:
+//!
+//! ```
+//! use starfield::satellites::EarthSatellite;
+//! use starfield::time::Timescale;
+//!
+//! let ts = Timescale::default();
+//! let iss = EarthSatellite::parse(
+//!     "1 25544U 98067A   08264.51782528 -.00002182  00000-0 -11606-4 0  2927",
+//!     "2 25544  51.6416 247.4627 0006703 130.5360 325.0288 15.72125391563537",
+//!     &ts,
+//! )?;
+//! let state = iss.at(&ts.utc((2008, 9, 20, 14, 0, 0.0)))?;
+//! println!("{:.1} km from the geocenter", state.position_km.norm());
+//! # Ok::<(), starfield::satellites::SatelliteError>(())
+//! ```
+
+pub mod conjunction;
+pub mod frames;
+pub mod omm;
+pub mod orbit_plane;
+pub mod sgp4;
+pub mod tle;
+pub mod uncertainty;
+
+pub use conjunction::{closest_approach, find_conjunctions, Conjunction};
+pub use frames::teme_to_j2000;
+pub use orbit_plane::{NodeCrossing, OrbitPlane};
+pub use sgp4::{Sgp4, TemeState};
+pub use tle::Tle;
+pub use uncertainty::{
+    ElementCovariance, PositionUncertainty, UncertaintyEllipsoid, UncertaintyMethod,
+};
+
+use crate::time::{Time, Timescale};
+use thiserror::Error;
+
+/// Error type for satellite element sets and propagation
+#[derive(Debug, Error)]
+pub enum SatelliteError {
+    #[error("Invalid element set: {0}")]
+    InvalidTle(String),
+
+    #[error("Checksum mismatch on TLE line {line}")]
+    Checksum { line: u8 },
+
+    #[error("Deep-space orbits (period of 225 minutes or more) are not supported")]
+    DeepSpace,
+
+    #[error("Propagation failed: {0}")]
+    Propagation(String),
+
+    #[error("Invalid covariance: {0}")]
+    InvalidCovariance(String),
+}
+
+/// A satellite whose orbit is described by a two-line element set
+#[derive(Debug, Clone)]
+pub struct EarthSatellite {
+    tle: Tle,
+    model: Sgp4,
+    epoch: Time,
+}
+
+impl EarthSatellite {
+    /// Build from a parsed element set
+    pub fn from_tle(tle: Tle, ts: &Timescale) -> Result<Self, SatelliteError> {
+        let model = Sgp4::new(&tle)?;
+        let epoch = tle.epoch(ts);
+        Ok(Self { tle, model, epoch })
+    }
+
+    /// Parse the two element lines
+    pub fn parse(line1: &str, line2: &str, ts: &Timescale) -> Result<Self, SatelliteError> {
+        Self::from_tle(Tle::parse(line1, line2)?, ts)
+    }
+
+    /// Name from the element set's title line, if any
+    pub fn name(&self) -> Option<&str> {
+        self.tle.name.as_deref()
+    }
+
+    /// The element set
+    pub fn tle(&self) -> &Tle {
+        &self.tle
+    }
+
+    /// Epoch of the element set
+    pub fn epoch(&self) -> &Time {
+        &self.epoch
+    }
+
+    /// Minutes from the element set's epoch to `t`
+    pub fn minutes_since_epoch(&self, t: &Time) -> f64 {
+        (t.tt() - self.epoch.tt()) * 1440.0
+    }
+
+    /// TEME state at `t`
+    pub fn at(&self, t: &Time) -> Result<TemeState, SatelliteError> {
+        self.model.propagate(self.minutes_since_epoch(t))
+    }
+}
+
+/// The ISS element set of 2008 September 20 that the satellite tests share
+#[cfg(test)]
+pub(crate) const ISS_TLE: (&str, &str) = (
+    "1 25544U 98067A   08264.51782528 -.00002182  00000-0 -11606-4 0  2927",
+    "2 25544  51.6416 247.4627 0006703 130.5360 325.0288 15.72125391563537",
+);
+
+/// The ISS from [`ISS_TLE`]
+#[cfg(test)]
+pub(crate) fn iss(ts: &Timescale) -> EarthSatellite {
+    EarthSatellite::parse(ISS_TLE.0, ISS_TLE.1, ts).unwrap()
+}
@@ -0,0 +1,273 @@
+//! Orbit Mean-Elements Messages
+//!
+//! CCSDS OMMs carry the same mean elements as two-line element sets without
+//! the fixed columns, and are how CelesTrak and Space-Track now publish
+//! them, since five-digit catalog numbers are running out. All three
+//! encodings are read: KVN (`KEY = value` lines), XML and JSON, numbers in
+//! JSON being accepted as numbers or strings.
+//!
+//! ```
+//! use starfield::satellites::{EarthSatellite, Tle};
+//! use starfield::time::Timescale;
+//!
+//! let json = r#"[{
+//!     "OBJECT_NAME": "ISS (ZARYA)", "OBJECT_ID": "1998-067A",
+//!     "EPOCH": "2008-09-20T12:25:40.104192", "MEAN_MOTION": 15.72125391,
+//!     "ECCENTRICITY": 0.0006703, "INCLINATION": 51.6416,
+//!     "RA_OF_ASC_NODE": 247.4627, "ARG_OF_PERICENTER": 130.536,
+//!     "MEAN_ANOMALY": 325.0288, "NORAD_CAT_ID": 25544,
+//!     "BSTAR": -1.1606e-5, "MEAN_MOTION_DOT": -2.182e-5, "MEAN_MOTION_DDOT": 0
+//! }]"#;
+//! let tles = Tle::parse_omm(json)?;
+//! let iss = EarthSatellite::from_tle(tles[0].clone(), &Timescale::default())?;
+//! assert_eq!(iss.name(), Some("ISS (ZARYA)"));
+//! # Ok::<(), starfield::satellites::SatelliteError>(())
+//! ```
+
+use super::{SatelliteError, Tle};
+use chrono::{Datelike, NaiveDateTime, Timelike};
+use std::collections::HashMap;
+
+/// Fields of one message, by keyword
+type Fields = HashMap<String, String>;
+
+impl Tle {
+    /// Parse every message in an OMM document, in KVN, XML or JSON
+    pub fn parse_omm(text: &str) -> Result<Vec<Self>, SatelliteError> {
+        let messages = match text.trim_start().chars().next() {
+            Some('[') | Some('{') => json_messages(text)?,
+            Some('<') => xml_messages(text),
+            Some(_) => kvn_messages(text),
+            None => Vec::new(),
+        };
+        if messages.is_empty() {
+            return Err(SatelliteError::InvalidTle(
+                "no OMM messages found".to_string(),
+            ));
+        }
+        messages.iter().map(tle_from_fields).collect()
+    }
+}
+
+/// Messages of a JSON document: one object or an array of them
+fn json_messages(text: &str) -> Result<Vec<Fields>, SatelliteError> {
+    let value: serde_json::Value = serde_json::from_str(text)
+        .map_err(|e| SatelliteError::InvalidTle(format!("invalid OMM JSON: {}", e)))?;
+    let objects = match value {
+        serde_json::Value::Array(items) => items,
+        object => vec![object],
+    };
+    objects
+        .into_iter()
+        .map(|object| match object {
+            serde_json::Value::Object(map) => Ok(map
+                .into_iter()
+                .filter_map(|(key, value)| {
+                    let text = match value {
+                        serde_json::Value::String(s) => s,
+                        serde_json::Value::Number(n) => n.to_string(),
+                        _ => return None,
+                    };
+                    Some((key.to_uppercase(), text))
+                })
+                .collect()),
+            _ => Err(SatelliteError::InvalidTle(
+                "OMM JSON entries must be objects".to_string(),
+            )),
+        })
+        .collect()
+}
+
+/// Messages of an XML document, one per `<omm>` element
+fn xml_messages(text: &str) -> Vec<Fields> {
+    text.split("<omm")
+        .skip(1)
+        .map(|message| {
+            let mut fields = Fields::new();
+            let mut rest = message;
+            while let Some(open) = rest.find('<') {
+                rest = &rest[open + 1..];
+                let Some(close) = rest.find('>') else { break };
+                let tag = &rest[..close];
+                rest = &rest[close + 1..];
+                let end = format!("</{}>", tag);
+                if tag.starts_with('/') || tag.contains(' ') || !rest.contains(&end) {
+                    continue;
+                }
+                let value = &rest[..rest.find('<').unwrap_or(rest.len())];
+                if rest[value.len()..].starts_with(&end) {
+                    fields.insert(tag.to_uppercase(), value.trim().to_string());
+                }
+            }
+            fields
+        })
+        .collect()
+}
+
+/// Messages of a KVN document, each opened by its version keyword
+fn kvn_messages(text: &str) -> Vec<Fields> {
+    let mut messages: Vec<Fields> = Vec::new();
+    for line in text.lines() {
+        let Some((key, value)) = line.split_once('=') else {
+            continue;
+        };
+        let key = key.trim().to_uppercase();
+        if key == "CCSDS_OMM_VERS" || messages.is_empty() {
+            messages.push(Fields::new());
+        }
+        // Units may follow a value in square brackets
+        let value = value.split('[').next().unwrap_or("").trim();
+        if let Some(fields) = messages.last_mut() {
+            fields.insert(key, value.to_string());
+        }
+    }
+    messages
+}
+
+/// Build an element set from one message's fields
+fn tle_from_fields(fields: &Fields) -> Result<Tle, SatelliteError> {
+    if let Some(theory) = fields.get("MEAN_ELEMENT_THEORY") {
+        if !theory.to_uppercase().starts_with("SGP4") {
+            return Err(SatelliteError::InvalidTle(format!(
+                "mean elements for {} cannot be propagated with SGP4",
+                theory
+            )));
+        }
+    }
+
+    let text = |key: &str| fields.get(key).map(String::as_str);
+    let number = |key: &str| -> Result<f64, SatelliteError> {
+        text(key)
+            .ok_or_else(|| SatelliteError::InvalidTle(format!("OMM is missing {}", key)))?
+            .parse()
+            .map_err(|_| SatelliteError::InvalidTle(format!("could not parse {}", key)))
+    };
+    let optional = |key: &str| text(key).map_or(Ok(0.0), |_| number(key));
+
+    let epoch = text("EPOCH")
+        .ok_or_else(|| SatelliteError::InvalidTle("OMM is missing EPOCH".to_string()))?;
+    let epoch = NaiveDateTime::parse_from_str(epoch.trim_end_matches('Z'), "%Y-%m-%dT%H:%M:%S%.f")
+        .map_err(|_| SatelliteError::InvalidTle(format!("could not parse EPOCH {}", epoch)))?;
+    let seconds = epoch.num_seconds_from_midnight() as f64 + epoch.nanosecond() as f64 * 1e-9;
+
+    Ok(Tle {
+        name: text("OBJECT_NAME").map(str::to_string),
+        catalog_number: number("NORAD_CAT_ID")? as u32,
+        classification: text("CLASSIFICATION_TYPE")
+            .and_then(|c| c.chars().next())
+            .unwrap_or('U'),
+        international_designator: text("OBJECT_ID").map_or_else(String::new, designator),
+        epoch_year: epoch.year(),
+        epoch_day: epoch.ordinal() as f64 + seconds / 86_400.0,
+        mean_motion_dot: optional("MEAN_MOTION_DOT")?,
+        mean_motion_ddot: optional("MEAN_MOTION_DDOT")?,
+        bstar: optional("BSTAR")?,
+        element_number: optional("ELEMENT_SET_NO")? as u32,
+        inclination_deg: number("INCLINATION")?,
+        raan_deg: number("RA_OF_ASC_NODE")?,
+        eccentricity: number("ECCENTRICITY")?,
+        arg_perigee_deg: number("ARG_OF_PERICENTER")?,
+        mean_anomaly_deg: number("MEAN_ANOMALY")?,
+        mean_motion: number("MEAN_MOTION")?,
+        revolution_number: optional("REV_AT_EPOCH")? as u32,
+    })
+}
+
+/// Two-line element style designator, `98067A`, from an OMM object ID,
+/// `1998-067A`
+fn designator(object_id: &str) -> String {
+    match object_id.split_once('-') {
+        Some((year, piece)) if year.len() == 4 => format!("{}{}", &year[2..], piece),
+        _ => object_id.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::satellites::ISS_TLE;
+    use approx::assert_relative_eq;
+
+    const ISS_KVN: &str = "CCSDS_OMM_VERS = 2.0
+CREATION_DATE = 2008-09-21T00:00:00
+ORIGINATOR = TEST
+OBJECT_NAME = ISS (ZARYA)
+OBJECT_ID = 1998-067A
+CENTER_NAME = EARTH
+REF_FRAME = TEME
+TIME_SYSTEM = UTC
+MEAN_ELEMENT_THEORY = SGP4
+EPOCH = 2008-09-20T12:25:40.104192
+MEAN_MOTION = 15.72125391 [rev/day]
+ECCENTRICITY = .0006703
+INCLINATION = 51.6416 [deg]
+RA_OF_ASC_NODE = 247.4627 [deg]
+ARG_OF_PERICENTER = 130.5360 [deg]
+MEAN_ANOMALY = 325.0288 [deg]
+EPHEMERIS_TYPE = 0
+CLASSIFICATION_TYPE = U
+NORAD_CAT_ID = 25544
+ELEMENT_SET_NO = 292
+REV_AT_EPOCH = 56353
+BSTAR = -.11606E-4
+MEAN_MOTION_DOT = -.00002182
+MEAN_MOTION_DDOT = 0.0";
+
+    #[test]
+    fn test_encodings_match_the_tle() {
+        let tle = Tle::parse(ISS_TLE.0, ISS_TLE.1).unwrap();
+
+        let xml = format!(
+            "<?xml version=\"1.0\"?><ndm><omm id=\"CCSDS_OMM_VERS\" version=\"2.0\"><body><segment>{}</segment></body></omm></ndm>",
+            ISS_KVN
+                .lines()
+                .map(|line| {
+                    let (key, value) = line.split_once(" = ").unwrap();
+                    let value = value.split(" [").next().unwrap();
+                    format!("<{0}>{1}</{0}>", key, value)
+                })
+                .collect::<String>()
+        );
+        let json = format!(
+            "[{{{}}}]",
+            ISS_KVN
+                .lines()
+                .map(|line| {
+                    let (key, value) = line.split_once(" = ").unwrap();
+                    format!("\"{}\": \"{}\"", key, value.split(" [").next().unwrap())
+                })
+                .collect::<Vec<_>>()
+                .join(", ")
+        );
+
+        for text in [ISS_KVN.to_string(), xml, json] {
+            let parsed = Tle::parse_omm(&text).unwrap();
+            assert_eq!(parsed.len(), 1);
+            let omm = &parsed[0];
+            assert_eq!(omm.name.as_deref(), Some("ISS (ZARYA)"));
+            assert_eq!(omm.international_designator, "98067A");
+            assert_eq!(omm.catalog_number, tle.catalog_number);
+            assert_eq!(omm.epoch_year, 2008);
+            assert_relative_eq!(omm.epoch_day, tle.epoch_day, epsilon = 1e-9);
+            assert_relative_eq!(omm.bstar, tle.bstar, max_relative = 1e-12);
+            assert_eq!(omm.revolution_number, tle.revolution_number);
+            assert_eq!(
+                (omm.inclination_deg, omm.eccentricity, omm.mean_motion),
+                (tle.inclination_deg, tle.eccentricity, tle.mean_motion)
+            );
+        }
+    }
+
+    #[test]
+    fn test_rejects_other_theories_and_missing_fields() {
+        let sgp = ISS_KVN.replace("= SGP4", "= DSST");
+        assert!(Tle::parse_omm(&sgp).is_err());
+        let missing = ISS_KVN.replace("MEAN_MOTION = 15.72125391 [rev/day]\n", "");
+        assert!(Tle::parse_omm(&missing).is_err());
+        assert!(Tle::parse_omm("").is_err());
+
+        let two = format!("{}\n{}", ISS_KVN, ISS_KVN.replace("25544", "25545"));
+        let parsed = Tle::parse_omm(&two).unwrap();
+        assert_eq!(parsed[1].catalog_number, 25545);
+    }
+}
@@ -0,0 +1,238 @@
+//! Orbit plane geometry for thermal and power analysis
+//!
+//! The orbit plane is taken from the osculating angular momentum `r × v` of
+//! the SGP4 state, so it follows the nodal regression and short-period
+//! wobble of the real orbit rather than the mean elements. The beta angle
+//! is the Sun's elevation above that plane: near ±90° a satellite stays in
+//! sunlight all orbit, while near 0° it spends the longest time in the
+//! Earth's shadow.
+//!
+//! States are in TEME and the Sun in the ICRS; the half-degree precession
+//! between the two frames is well below what thermal budgets resolve.
+
+use super::{EarthSatellite, SatelliteError};
+use crate::almanac::geocentric_position;
+use crate::constants::{DAY_S, RAD2DEG};
+use crate::planetlib::{Body, Ephemeris};
+use crate::time::{Time, TimeArray};
+use nalgebra::Vector3;
+
+/// Sampling step in seconds when searching for node crossings
+const NODE_SEARCH_STEP_S: f64 = 60.0;
+
+/// Convergence threshold of the node crossing refinement in seconds
+const NODE_TOLERANCE_S: f64 = 1e-4;
+
+/// Orientation of the orbit plane at one instant
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct OrbitPlane {
+    /// Unit orbit normal, along the angular momentum, TEME
+    pub normal: Vector3<f64>,
+    /// Unit vector towards the ascending node, TEME
+    pub ascending_node: Vector3<f64>,
+    /// Specific angular momentum in km²/s
+    pub angular_momentum_km2_s: f64,
+}
+
+impl OrbitPlane {
+    /// Inclination to the equator in degrees
+    pub fn inclination_deg(&self) -> f64 {
+        self.normal.z.clamp(-1.0, 1.0).acos() * RAD2DEG
+    }
+
+    /// Right ascension of the ascending node in degrees
+    pub fn node_ra_deg(&self) -> f64 {
+        (self.ascending_node.y.atan2(self.ascending_node.x) * RAD2DEG).rem_euclid(360.0)
+    }
+
+    /// Angle in degrees of `direction` above the plane, positive on the
+    /// side the normal points to
+    pub fn elevation_deg(&self, direction: &Vector3<f64>) -> f64 {
+        (self.normal.dot(direction) / direction.norm())
+            .clamp(-1.0, 1.0)
+            .asin()
+            * RAD2DEG
+    }
+}
+
+/// A crossing of the equatorial plane
+#[derive(Debug, Clone)]
+pub struct NodeCrossing {
+    /// Time of the crossing
+    pub time: Time,
+    /// Whether the satellite is moving north
+    pub ascending: bool,
+    /// Position at the crossing in km, TEME
+    pub position_km: Vector3<f64>,
+}
+
+impl EarthSatellite {
+    /// Orbit plane at `t`
+    pub fn orbit_plane(&self, t: &Time) -> Result<OrbitPlane, SatelliteError> {
+        let state = self.at(t)?;
+        let h = state.position_km.cross(&state.velocity_km_s);
+        let angular_momentum_km2_s = h.norm();
+        if angular_momentum_km2_s == 0.0 {
+            return Err(SatelliteError::Propagation(
+                "radial trajectory has no orbit plane".to_string(),
+            ));
+        }
+        let normal = h / angular_momentum_km2_s;
+        let node = Vector3::z().cross(&normal);
+        // An equatorial orbit has no node; measure from the x axis instead
+        let ascending_node = if node.norm() > 1e-12 {
+            node.normalize()
+        } else {
+            Vector3::x()
+        };
+        Ok(OrbitPlane {
+            normal,
+            ascending_node,
+            angular_momentum_km2_s,
+        })
+    }
+
+    /// Unit orbit normal at `t`, TEME
+    pub fn orbit_normal(&self, t: &Time) -> Result<Vector3<f64>, SatelliteError> {
+        Ok(self.orbit_plane(t)?.normal)
+    }
+
+    /// Beta angle at `t`: the Sun's elevation above the orbit plane in
+    /// degrees, positive when the Sun is on the side of the orbit normal
+    pub fn beta_angle(&self, ephemeris: &Ephemeris, t: &Time) -> Result<f64, SatelliteError> {
+        let sun = geocentric_position(ephemeris, Body::Sun, t);
+        Ok(self.orbit_plane(t)?.elevation_deg(&sun))
+    }
+
+    /// Beta angle at each of `times`
+    pub fn beta_angles(
+        &self,
+        ephemeris: &Ephemeris,
+        times: &TimeArray,
+    ) -> Result<Vec<f64>, SatelliteError> {
+        times
+            .iter()
+            .map(|t| self.beta_angle(ephemeris, &t))
+            .collect()
+    }
+
+    /// Every crossing of the equatorial plane between `start` and `end`
+    pub fn node_crossings(
+        &self,
+        start: &Time,
+        end: &Time,
+    ) -> Result<Vec<NodeCrossing>, SatelliteError> {
+        let ts = start.timescale();
+        let z = |jd: f64| Ok::<_, SatelliteError>(self.at(&ts.tt_jd(jd, None))?.position_km.z);
+        let (jd_start, jd_end) = (start.tt(), end.tt());
+        let step = NODE_SEARCH_STEP_S / DAY_S;
+
+        let mut crossings = Vec::new();
+        let (mut lo, mut z_lo) = (jd_start, z(jd_start)?);
+        while lo < jd_end {
+            let hi = (lo + step).min(jd_end);
+            let z_hi = z(hi)?;
+            if z_lo.signum() != z_hi.signum() && z_hi != 0.0 {
+                let jd = refine_node(lo, z_lo, hi, z_hi, &z)?;
+                let time = ts.tt_jd(jd, None);
+                let state = self.at(&time)?;
+                crossings.push(NodeCrossing {
+                    time,
+                    ascending: state.velocity_km_s.z > 0.0,
+                    position_km: state.position_km,
+                });
+            }
+            (lo, z_lo) = (hi, z_hi);
+        }
+        Ok(crossings)
+    }
+}
+
+/// Regula falsi (Illinois variant) for the zero of `z` in `[lo, hi]`
+fn refine_node(
+    mut lo: f64,
+    mut z_lo: f64,
+    mut hi: f64,
+    mut z_hi: f64,
+    z: &impl Fn(f64) -> Result<f64, SatelliteError>,
+) -> Result<f64, SatelliteError> {
+    let mut jd = lo;
+    for _ in 0..50 {
+        jd = hi - z_hi * (hi - lo) / (z_hi - z_lo);
+        let z_mid = z(jd)?;
+        if z_mid.signum() == z_hi.signum() {
+            (hi, z_hi) = (jd, z_mid);
+            z_lo /= 2.0;
+        } else {
+            (lo, z_lo) = (jd, z_mid);
+            z_hi /= 2.0;
+        }
+        if (hi - lo) * DAY_S < NODE_TOLERANCE_S || z_mid == 0.0 {
+            break;
+        }
+    }
+    Ok(jd)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::satellites::iss;
+    use crate::time::{TimeDelta, Timescale};
+    use approx::assert_relative_eq;
+
+    #[test]
+    fn test_plane_matches_elements() {
+        let sat = iss(&Timescale::default());
+        let plane = sat.orbit_plane(sat.epoch()).unwrap();
+        assert_relative_eq!(plane.inclination_deg(), 51.64, epsilon = 0.1);
+        assert_relative_eq!(plane.node_ra_deg(), 247.46, epsilon = 0.1);
+        assert_relative_eq!(plane.normal.norm(), 1.0, epsilon = 1e-12);
+        assert_relative_eq!(
+            plane.normal.dot(&plane.ascending_node),
+            0.0,
+            epsilon = 1e-12
+        );
+        // h = sqrt(mu a) for a near-circular orbit at about 6725 km
+        assert_relative_eq!(plane.angular_momentum_km2_s, 51_800.0, max_relative = 0.01);
+    }
+
+    #[test]
+    fn test_node_crossings_alternate() {
+        let sat = iss(&Timescale::default());
+        let start = sat.epoch().clone();
+        let end = &start + TimeDelta::days(0.25);
+        let nodes = sat.node_crossings(&start, &end).unwrap();
+
+        // About 15.7 orbits per day, two nodes each
+        assert!((7..=8).contains(&nodes.len()), "{}", nodes.len());
+        for pair in nodes.windows(2) {
+            assert_ne!(pair[0].ascending, pair[1].ascending);
+            let half_period_min = (pair[1].time.tt() - pair[0].time.tt()) * 1440.0;
+            assert_relative_eq!(half_period_min, 1440.0 / 15.72 / 2.0, epsilon = 1.0);
+        }
+        assert!(nodes.iter().all(|n| n.position_km.z.abs() < 1e-3));
+    }
+
+    #[test]
+    fn test_beta_angle_tracks_sun() {
+        let sat = iss(&Timescale::default());
+        let eph = Ephemeris::new();
+        let t = sat.epoch().clone();
+        let beta = sat.beta_angle(&eph, &t).unwrap();
+        assert!(beta.abs() <= 51.64 + 23.44 + 0.1);
+
+        // Beta agrees with the Sun's elevation computed from the normal
+        let sun = geocentric_position(&eph, Body::Sun, &t);
+        let normal = sat.orbit_normal(&t).unwrap();
+        assert_relative_eq!(beta, 90.0 - normal.angle(&sun) * RAD2DEG, epsilon = 1e-9);
+
+        let ts = t.timescale();
+        let betas = sat
+            .beta_angles(&eph, &ts.linspace(&t, &(&t + TimeDelta::days(1.0)), 5))
+            .unwrap();
+        assert_eq!(betas.len(), 5);
+        // Nodal regression and the Sun move beta by a few degrees a day
+        assert!((betas[4] - betas[0]).abs() < 10.0);
+    }
+}
@@ -0,0 +1,421 @@
+//! The SGP4 orbit propagator
+//!
+//! A port of the near-Earth branch of Vallado's revised SGP4 (AIAA
+//! 2006-6753) with WGS-72 constants, the combination element sets are
+//! fitted with. Positions are in kilometres and velocities in km/s in the
+//! TEME frame (true equator, mean equinox of date). Deep-space orbits,
+//! with periods of 225 minutes or more, need the SDP4 resonance terms and
+//! are rejected.
+
+use super::tle::Tle;
+use super::SatelliteError;
+use crate::constants::{DEG2RAD, TAU};
+use nalgebra::Vector3;
+
+/// WGS-72 Earth radius in km
+pub const EARTH_RADIUS_KM: f64 = 6378.135;
+/// WGS-72 gravitational parameter in km³/s²
+const MU: f64 = 398_600.8;
+/// WGS-72 zonal harmonics
+const J2: f64 = 0.001_082_616;
+const J3: f64 = -0.000_002_538_81;
+const J4: f64 = -0.000_001_655_97;
+const J3OJ2: f64 = J3 / J2;
+
+/// Square root of GM in Earth radii^1.5 per minute
+fn xke() -> f64 {
+    60.0 / (EARTH_RADIUS_KM.powi(3) / MU).sqrt()
+}
+
+/// Position and velocity in the TEME frame
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TemeState {
+    /// Position in km
+    pub position_km: Vector3<f64>,
+    /// Velocity in km/s
+    pub velocity_km_s: Vector3<f64>,
+}
+
+/// SGP4 model initialised from one element set
+#[derive(Debug, Clone)]
+pub struct Sgp4 {
+    // Epoch elements, angles in radians and mean motion in rad/min
+    bstar: f64,
+    ecco: f64,
+    argpo: f64,
+    inclo: f64,
+    mo: f64,
+    no_unkozai: f64,
+    nodeo: f64,
+    // Derived coefficients
+    isimp: bool,
+    aycof: f64,
+    con41: f64,
+    cc1: f64,
+    cc4: f64,
+    cc5: f64,
+    d2: f64,
+    d3: f64,
+    d4: f64,
+    delmo: f64,
+    eta: f64,
+    argpdot: f64,
+    omgcof: f64,
+    sinmao: f64,
+    t2cof: f64,
+    t3cof: f64,
+    t4cof: f64,
+    t5cof: f64,
+    x1mth2: f64,
+    x7thm1: f64,
+    mdot: f64,
+    nodedot: f64,
+    xlcof: f64,
+    xmcof: f64,
+    nodecf: f64,
+}
+
+impl Sgp4 {
+    /// Initialise the model from an element set
+    pub fn new(tle: &Tle) -> Result<Self, SatelliteError> {
+        let xke = xke();
+        let no_kozai = tle.mean_motion * TAU / 1440.0;
+        let ecco = tle.eccentricity;
+        let inclo = tle.inclination_deg * DEG2RAD;
+        let argpo = tle.arg_perigee_deg * DEG2RAD;
+        let bstar = tle.bstar;
+        if no_kozai <= 0.0 || !(0.0..1.0).contains(&ecco) {
+            return Err(SatelliteError::InvalidTle(
+                "mean motion and eccentricity are out of range".to_string(),
+            ));
+        }
+
+        // Recover the original mean motion from the Kozai value
+        let eccsq = ecco * ecco;
+        let omeosq = 1.0 - eccsq;
+        let rteosq = omeosq.sqrt();
+        let cosio = inclo.cos();
+        let cosio2 = cosio * cosio;
+        let ak = (xke / no_kozai).powf(2.0 / 3.0);
+        let d1 = 0.75 * J2 * (3.0 * cosio2 - 1.0) / (rteosq * omeosq);
+        let del = d1 / (ak * ak);
+        let adel = ak * (1.0 - del * del - del * (1.0 / 3.0 + 134.0 * del * del / 81.0));
+        let del = d1 / (adel * adel);
+        let no_unkozai = no_kozai / (1.0 + del);
+
+        if TAU / no_unkozai >= 225.0 {
+            return Err(SatelliteError::DeepSpace);
+        }
+
+        let ao = (xke / no_unkozai).powf(2.0 / 3.0);
+        let sinio = inclo.sin();
+        let po = ao * omeosq;
+        let con42 = 1.0 - 5.0 * cosio2;
+        let con41 = -con42 - cosio2 - cosio2;
+        let posq = po * po;
+        let rp = ao * (1.0 - ecco);
+
+        // Perigees below 220 km use a simplified drag model
+        let isimp = rp < 220.0 / EARTH_RADIUS_KM + 1.0;
+        let mut sfour = 78.0 / EARTH_RADIUS_KM + 1.0;
+        let mut qzms24 = ((120.0 - 78.0) / EARTH_RADIUS_KM).powi(4);
+        let perigee_km = (rp - 1.0) * EARTH_RADIUS_KM;
+        if perigee_km < 156.0 {
+            sfour = if perigee_km < 98.0 {
+                20.0
+            } else {
+                perigee_km - 78.0
+            };
+            qzms24 = ((120.0 - sfour) / EARTH_RADIUS_KM).powi(4);
+            sfour = sfour / EARTH_RADIUS_KM + 1.0;
+        }
+
+        let pinvsq = 1.0 / posq;
+        let tsi = 1.0 / (ao - sfour);
+        let eta = ao * ecco * tsi;
+        let etasq = eta * eta;
+        let eeta = ecco * eta;
+        let psisq = (1.0 - etasq).abs();
+        let coef = qzms24 * tsi.powi(4);
+        let coef1 = coef / psisq.powf(3.5);
+        let cc2 = coef1
+            * no_unkozai
+            * (ao * (1.0 + 1.5 * etasq + eeta * (4.0 + etasq))
+                + 0.375 * J2 * tsi / psisq * con41 * (8.0 + 3.0 * etasq * (8.0 + etasq)));
+        let cc1 = bstar * cc2;
+        let cc3 = if ecco > 1e-4 {
+            -2.0 * coef * tsi * J3OJ2 * no_unkozai * sinio / ecco
+        } else {
+            0.0
+        };
+        let x1mth2 = 1.0 - cosio2;
+        let cc4 = 2.0
+            * no_unkozai
+            * coef1
+            * ao
+            * omeosq
+            * (eta * (2.0 + 0.5 * etasq) + ecco * (0.5 + 2.0 * etasq)
+                - J2 * tsi / (ao * psisq)
+                    * (-3.0 * con41 * (1.0 - 2.0 * eeta + etasq * (1.5 - 0.5 * eeta))
+                        + 0.75
+                            * x1mth2
+                            * (2.0 * etasq - eeta * (1.0 + etasq))
+                            * (2.0 * argpo).cos()));
+        let cc5 = 2.0 * coef1 * ao * omeosq * (1.0 + 2.75 * (etasq + eeta) + eeta * etasq);
+
+        let cosio4 = cosio2 * cosio2;
+        let temp1 = 1.5 * J2 * pinvsq * no_unkozai;
+        let temp2 = 0.5 * temp1 * J2 * pinvsq;
+        let temp3 = -0.46875 * J4 * pinvsq * pinvsq * no_unkozai;
+        let mdot = no_unkozai
+            + 0.5 * temp1 * rteosq * con41
+            + 0.0625 * temp2 * rteosq * (13.0 - 78.0 * cosio2 + 137.0 * cosio4);
+        let argpdot = -0.5 * temp1 * con42
+            + 0.0625 * temp2 * (7.0 - 114.0 * cosio2 + 395.0 * cosio4)
+            + temp3 * (3.0 - 36.0 * cosio2 + 49.0 * cosio4);
+        let xhdot1 = -temp1 * cosio;
+        let nodedot = xhdot1
+            + (0.5 * temp2 * (4.0 - 19.0 * cosio2) + 2.0 * temp3 * (3.0 - 7.0 * cosio2)) * cosio;
+
+        let omgcof = bstar * cc3 * argpo.cos();
+        let xmcof = if ecco > 1e-4 {
+            -2.0 / 3.0 * coef * bstar / eeta
+        } else {
+            0.0
+        };
+        let nodecf = 3.5 * omeosq * xhdot1 * cc1;
+        let t2cof = 1.5 * cc1;
+        // Avoid dividing by zero for retrograde equatorial orbits
+        let xlcof_denominator = if (cosio + 1.0).abs() > 1.5e-12 {
+            1.0 + cosio
+        } else {
+            1.5e-12
+        };
+        let xlcof = -0.25 * J3OJ2 * sinio * (3.0 + 5.0 * cosio) / xlcof_denominator;
+        let aycof = -0.5 * J3OJ2 * sinio;
+        let mo = tle.mean_anomaly_deg * DEG2RAD;
+        let delmo = (1.0 + eta * mo.cos()).powi(3);
+        let sinmao = mo.sin();
+        let x7thm1 = 7.0 * cosio2 - 1.0;
+
+        let (mut d2, mut d3, mut d4) = (0.0, 0.0, 0.0);
+        let (mut t3cof, mut t4cof, mut t5cof) = (0.0, 0.0, 0.0);
+        if !isimp {
+            let cc1sq = cc1 * cc1;
+            d2 = 4.0 * ao * tsi * cc1sq;
+            let temp = d2 * tsi * cc1 / 3.0;
+            d3 = (17.0 * ao + sfour) * temp;
+            d4 = 0.5 * temp * ao * tsi * (221.0 * ao + 31.0 * sfour) * cc1;
+            t3cof = d2 + 2.0 * cc1sq;
+            t4cof = 0.25 * (3.0 * d3 + cc1 * (12.0 * d2 + 10.0 * cc1sq));
+            t5cof = 0.2
+                * (3.0 * d4 + 12.0 * cc1 * d3 + 6.0 * d2 * d2 + 15.0 * cc1sq * (2.0 * d2 + cc1sq));
+        }
+
+        Ok(Self {
+            bstar,
+            ecco,
+            argpo,
+            inclo,
+            mo,
+            no_unkozai,
+            nodeo: tle.raan_deg * DEG2RAD,
+            isimp,
+            aycof,
+            con41,
+            cc1,
+            cc4,
+            cc5,
+            d2,
+            d3,
+            d4,
+            delmo,
+            eta,
+            argpdot,
+            omgcof,
+            sinmao,
+            t2cof,
+            t3cof,
+            t4cof,
+            t5cof,
+            x1mth2,
+            x7thm1,
+            mdot,
+            nodedot,
+            xlcof,
+            xmcof,
+            nodecf,
+        })
+    }
+
+    /// State `minutes` after the element set's epoch
+    pub fn propagate(&self, minutes: f64) -> Result<TemeState, SatelliteError> {
+        let xke = xke();
+        let t = minutes;
+
+        // Secular gravity and atmospheric drag
+        let xmdf = self.mo + self.mdot * t;
+        let argpdf = self.argpo + self.argpdot * t;
+        let nodedf = self.nodeo + self.nodedot * t;
+        let mut argpm = argpdf;
+        let mut mm = xmdf;
+        let t2 = t * t;
+        let mut nodem = nodedf + self.nodecf * t2;
+        let mut tempa = 1.0 - self.cc1 * t;
+        let mut tempe = self.bstar * self.cc4 * t;
+        let mut templ = self.t2cof * t2;
+
+        if !self.isimp {
+            let delomg = self.omgcof * t;
+            let delm = self.xmcof * ((1.0 + self.eta * xmdf.cos()).powi(3) - self.delmo);
+            let temp = delomg + delm;
+            mm = xmdf + temp;
+            argpm = argpdf - temp;
+            let t3 = t2 * t;
+            let t4 = t3 * t;
+            tempa -= self.d2 * t2 + self.d3 * t3 + self.d4 * t4;
+            tempe += self.bstar * self.cc5 * (mm.sin() - self.sinmao);
+            templ += self.t3cof * t3 + t4 * (self.t4cof + t * self.t5cof);
+        }
+
+        let am = (xke / self.no_unkozai).powf(2.0 / 3.0) * tempa * tempa;
+        let nm = xke / am.powf(1.5);
+        let mut em = self.ecco - tempe;
+        if !(-0.001..1.0).contains(&em) || am <= 0.0 {
+            return Err(SatelliteError::Propagation(format!(
+                "mean eccentricity {:.6} out of range {:.1} minutes from epoch",
+                em, minutes
+            )));
+        }
+        em = em.max(1e-6);
+        mm += self.no_unkozai * templ;
+        let xlm = mm + argpm + nodem;
+        nodem %= TAU;
+        argpm %= TAU;
+        let xlm = xlm % TAU;
+
+        // Long-period periodics
+        let (sinip, cosip) = self.inclo.sin_cos();
+        let axnl = em * argpm.cos();
+        let temp = 1.0 / (am * (1.0 - em * em));
+        let aynl = em * argpm.sin() + temp * self.aycof;
+        let xl = xlm + temp * self.xlcof * axnl;
+
+        // Kepler's equation
+        let u = (xl - nodem) % TAU;
+        let mut eo1 = u;
+        let (mut sineo1, mut coseo1) = (0.0, 0.0);
+        for _ in 0..10 {
+            (sineo1, coseo1) = eo1.sin_cos();
+            let step =
+                (u - aynl * coseo1 + axnl * sineo1 - eo1) / (1.0 - coseo1 * axnl - sineo1 * aynl);
+            eo1 += step.clamp(-0.95, 0.95);
+            if step.abs() < 1e-12 {
+                break;
+            }
+        }
+
+        // Short-period periodics
+        let ecose = axnl * coseo1 + aynl * sineo1;
+        let esine = axnl * sineo1 - aynl * coseo1;
+        let el2 = axnl * axnl + aynl * aynl;
+        let pl = am * (1.0 - el2);
+        if pl < 0.0 {
+            return Err(SatelliteError::Propagation(format!(
+                "semi-latus rectum is negative {:.1} minutes from epoch",
+                minutes
+            )));
+        }
+        let rl = am * (1.0 - ecose);
+        let rdotl = am.sqrt() * esine / rl;
+        let rvdotl = pl.sqrt() / rl;
+        let betal = (1.0 - el2).sqrt();
+        let temp = esine / (1.0 + betal);
+        let sinu = am / rl * (sineo1 - aynl - axnl * temp);
+        let cosu = am / rl * (coseo1 - axnl + aynl * temp);
+        let su = sinu.atan2(cosu);
+        let sin2u = (cosu + cosu) * sinu;
+        let cos2u = 1.0 - 2.0 * sinu * sinu;
+        let temp = 1.0 / pl;
+        let temp1 = 0.5 * J2 * temp;
+        let temp2 = temp1 * temp;
+
+        let mrt = rl * (1.0 - 1.5 * temp2 * betal * self.con41) + 0.5 * temp1 * self.x1mth2 * cos2u;
+        let su = su - 0.25 * temp2 * self.x7thm1 * sin2u;
+        let xnode = nodem + 1.5 * temp2 * cosip * sin2u;
+        let xinc = self.inclo + 1.5 * temp2 * cosip * sinip * cos2u;
+        let mvt = rdotl - nm * temp1 * self.x1mth2 * sin2u / xke;
+        let rvdot = rvdotl + nm * temp1 * (self.x1mth2 * cos2u + 1.5 * self.con41) / xke;
+
+        if mrt < 1.0 {
+            return Err(SatelliteError::Propagation(format!(
+                "satellite has decayed {:.1} minutes from epoch",
+                minutes
+            )));
+        }
+
+        // Orientation vectors
+        let (sinsu, cossu) = su.sin_cos();
+        let (snod, cnod) = xnode.sin_cos();
+        let (sini, cosi) = xinc.sin_cos();
+        let xmx = -snod * cosi;
+        let xmy = cnod * cosi;
+        let u = Vector3::new(
+            xmx * sinsu + cnod * cossu,
+            xmy * sinsu + snod * cossu,
+            sini * sinsu,
+        );
+        let v = Vector3::new(
+            xmx * cossu - cnod * sinsu,
+            xmy * cossu - snod * sinsu,
+            sini * cossu,
+        );
+
+        let km_per_s = EARTH_RADIUS_KM * xke / 60.0;
+        Ok(TemeState {
+            position_km: u * (mrt * EARTH_RADIUS_KM),
+            velocity_km_s: (u * mvt + v * rvdot) * km_per_s,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use approx::assert_relative_eq;
+
+    #[test]
+    fn test_vallado_verification_case() {
+        // Satellite 00005 from Vallado's SGP4 verification set
+        let tle = Tle::parse(
+            "1 00005U 58002B   00179.78495062  .00000023  00000-0  28098-4 0  4753",
+            "2 00005  34.2682 348.7242 1859667 331.7664  19.3264 10.82419157413667",
+        )
+        .unwrap();
+        let sgp4 = Sgp4::new(&tle).unwrap();
+
+        let at_epoch = sgp4.propagate(0.0).unwrap();
+        let expected = Vector3::new(7_022.465_292_66, -1_400.082_967_55, 0.039_951_55);
+        assert_relative_eq!(at_epoch.position_km, expected, epsilon = 1e-4);
+        let expected = Vector3::new(1.893_841_015, 6.405_893_759, 4.534_807_250);
+        assert_relative_eq!(at_epoch.velocity_km_s, expected, epsilon = 1e-7);
+
+        let later = sgp4.propagate(360.0).unwrap();
+        let expected = Vector3::new(-7_154.031_202_02, -3_783.176_825_04, -3_536.194_122_94);
+        assert_relative_eq!(later.position_km, expected, epsilon = 1e-4);
+        let expected = Vector3::new(4.741_887_409, -4.151_817_765, -2.093_935_425);
+        assert_relative_eq!(later.velocity_km_s, expected, epsilon = 1e-7);
+    }
+
+    #[test]
+    fn test_rejects_deep_space() {
+        let mut tle = Tle::parse(
+            "1 00005U 58002B   00179.78495062  .00000023  00000-0  28098-4 0  4753",
+            "2 00005  34.2682 348.7242 1859667 331.7664  19.3264 10.82419157413667",
+        )
+        .unwrap();
+        // Geostationary
+        tle.mean_motion = 1.0027;
+        assert!(matches!(Sgp4::new(&tle), Err(SatelliteError::DeepSpace)));
+    }
+}
@@ -0,0 +1,244 @@
+//! Two-line element sets
+//!
+//! Parses the fixed-column NORAD format, optionally preceded by a name
+//! line, and validates the modulo-10 checksums.
+
+use super::SatelliteError;
+use crate::time::{Time, TimeDelta, Timescale};
+
+/// Mean orbital elements from a two-line element set
+#[derive(Debug, Clone, PartialEq)]
+pub struct Tle {
+    /// Satellite name from the title line, if there was one
+    pub name: Option<String>,
+    /// NORAD catalog number
+    pub catalog_number: u32,
+    /// Classification: U, C or S
+    pub classification: char,
+    /// International designator, e.g. `98067A`
+    pub international_designator: String,
+    /// Full four-digit epoch year
+    pub epoch_year: i32,
+    /// Day of the year of the epoch, 1.0 being January 1 at 0h UTC
+    pub epoch_day: f64,
+    /// First derivative of the mean motion divided by two, rev/day²
+    pub mean_motion_dot: f64,
+    /// Second derivative of the mean motion divided by six, rev/day³
+    pub mean_motion_ddot: f64,
+    /// SGP4 drag term in inverse Earth radii
+    pub bstar: f64,
+    /// Element set number
+    pub element_number: u32,
+    /// Inclination in degrees
+    pub inclination_deg: f64,
+    /// Right ascension of the ascending node in degrees
+    pub raan_deg: f64,
+    /// Eccentricity
+    pub eccentricity: f64,
+    /// Argument of perigee in degrees
+    pub arg_perigee_deg: f64,
+    /// Mean anomaly in degrees
+    pub mean_anomaly_deg: f64,
+    /// Mean motion in revolutions per day
+    pub mean_motion: f64,
+    /// Revolution number at epoch
+    pub revolution_number: u32,
+}
+
+impl Tle {
+    /// Parse the two element lines
+    pub fn parse(line1: &str, line2: &str) -> Result<Self, SatelliteError> {
+        let (line1, line2) = (line1.trim_end(), line2.trim_end());
+        check_line(line1, '1')?;
+        check_line(line2, '2')?;
+
+        let catalog_number = parse_field(line1, 2, 7, "catalog number")?;
+        if parse_field::<u32>(line2, 2, 7, "catalog number")? != catalog_number {
+            return Err(SatelliteError::InvalidTle(
+                "catalog numbers on the two lines differ".to_string(),
+            ));
+        }
+
+        let two_digit_year: i32 = parse_field(line1, 18, 20, "epoch year")?;
+        // The format's pivot: 57-99 are 1957-1999
+        let epoch_year = if two_digit_year < 57 {
+            2000 + two_digit_year
+        } else {
+            1900 + two_digit_year
+        };
+
+        Ok(Self {
+            name: None,
+            catalog_number,
+            classification: line1.chars().nth(7).unwrap_or('U'),
+            international_designator: column(line1, 9, 17).trim().to_string(),
+            epoch_year,
+            epoch_day: parse_field(line1, 20, 32, "epoch day")?,
+            mean_motion_dot: parse_field(line1, 33, 43, "mean motion derivative")?,
+            mean_motion_ddot: parse_exponent_field(column(line1, 44, 52), "second derivative")?,
+            bstar: parse_exponent_field(column(line1, 53, 61), "BSTAR")?,
+            element_number: parse_field(line1, 64, 68, "element number").unwrap_or(0),
+            inclination_deg: parse_field(line2, 8, 16, "inclination")?,
+            raan_deg: parse_field(line2, 17, 25, "right ascension of node")?,
+            eccentricity: format!("0.{}", column(line2, 26, 33).trim())
+                .parse()
+                .map_err(|_| invalid("eccentricity"))?,
+            arg_perigee_deg: parse_field(line2, 34, 42, "argument of perigee")?,
+            mean_anomaly_deg: parse_field(line2, 43, 51, "mean anomaly")?,
+            mean_motion: parse_field(line2, 52, 63, "mean motion")?,
+            revolution_number: parse_field(line2, 63, 68, "revolution number").unwrap_or(0),
+        })
+    }
+
+    /// Parse a name line followed by the two element lines
+    pub fn parse_with_name(name: &str, line1: &str, line2: &str) -> Result<Self, SatelliteError> {
+        let mut tle = Self::parse(line1, line2)?;
+        // Some sources prefix the title line with "0 "
+        let name = name.trim();
+        let name = name.strip_prefix("0 ").unwrap_or(name);
+        tle.name = Some(name.trim().to_string());
+        Ok(tle)
+    }
+
+    /// Parse every element set in a text file, with or without name lines
+    pub fn parse_all(text: &str) -> Result<Vec<Self>, SatelliteError> {
+        let lines: Vec<&str> = text.lines().filter(|l| !l.trim().is_empty()).collect();
+        let mut sets = Vec::new();
+        let mut i = 0;
+        while i < lines.len() {
+            if lines[i].starts_with("1 ") && i + 1 < lines.len() {
+                sets.push(Self::parse(lines[i], lines[i + 1])?);
+                i += 2;
+            } else if i + 2 < lines.len() {
+                sets.push(Self::parse_with_name(lines[i], lines[i + 1], lines[i + 2])?);
+                i += 3;
+            } else {
+                return Err(SatelliteError::InvalidTle(format!(
+                    "incomplete element set at line {}",
+                    i + 1
+                )));
+            }
+        }
+        Ok(sets)
+    }
+
+    /// Epoch of the elements
+    pub fn epoch(&self, ts: &Timescale) -> Time {
+        ts.utc((self.epoch_year, 1, 1)) + TimeDelta::days(self.epoch_day - 1.0)
+    }
+}
+
+fn invalid(field: &str) -> SatelliteError {
+    SatelliteError::InvalidTle(format!("could not parse {}", field))
+}
+
+/// Characters `start..end` (0-based) of a line, clipped to its length
+fn column(line: &str, start: usize, end: usize) -> &str {
+    line.get(start..end.min(line.len())).unwrap_or("")
+}
+
+fn parse_field<T: std::str::FromStr>(
+    line: &str,
+    start: usize,
+    end: usize,
+    field: &str,
+) -> Result<T, SatelliteError> {
+    column(line, start, end)
+        .trim()
+        .parse()
+        .map_err(|_| invalid(field))
+}
+
+/// Parse a field with an implied leading decimal point and a trailing
+/// exponent, such as ` 28098-4` for 0.28098e-4
+fn parse_exponent_field(text: &str, field: &str) -> Result<f64, SatelliteError> {
+    let text = text.trim();
+    if text.is_empty() {
+        return Ok(0.0);
+    }
+    let (sign, rest) = match text.as_bytes()[0] {
+        b'-' => (-1.0, &text[1..]),
+        b'+' => (1.0, &text[1..]),
+        _ => (1.0, text),
+    };
+    let split = rest
+        .rfind(['-', '+'])
+        .filter(|&i| i > 0)
+        .ok_or_else(|| invalid(field))?;
+    let mantissa: f64 = format!("0.{}", &rest[..split])
+        .parse()
+        .map_err(|_| invalid(field))?;
+    let exponent: i32 = rest[split..].parse().map_err(|_| invalid(field))?;
+    Ok(sign * mantissa * 10f64.powi(exponent))
+}
+
+/// Check a line's number and, if present, its checksum
+fn check_line(line: &str, number: char) -> Result<(), SatelliteError> {
+    if line.len() < 63 || !line.starts_with(number) {
+        return Err(SatelliteError::InvalidTle(format!(
+            "line {} is missing or too short",
+            number
+        )));
+    }
+    if let Some(expected) = line.chars().nth(68).and_then(|c| c.to_digit(10)) {
+        let sum: u32 = line
+            .chars()
+            .take(68)
+            .map(|c| match c {
+                '-' => 1,
+                c => c.to_digit(10).unwrap_or(0),
+            })
+            .sum();
+        if sum % 10 != expected {
+            return Err(SatelliteError::Checksum {
+                line: number.to_digit(10).unwrap_or(0) as u8,
+            });
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use approx::assert_relative_eq;
+
+    const ISS: &str = "ISS (ZARYA)
+1 25544U 98067A   08264.51782528 -.00002182  00000-0 -11606-4 0  2927
+2 25544  51.6416 247.4627 0006703 130.5360 325.0288 15.72125391563537";
+
+    #[test]
+    fn test_parse_iss() {
+        let tles = Tle::parse_all(ISS).unwrap();
+        assert_eq!(tles.len(), 1);
+        let tle = &tles[0];
+        assert_eq!(tle.name.as_deref(), Some("ISS (ZARYA)"));
+        assert_eq!(tle.catalog_number, 25544);
+        assert_eq!(tle.international_designator, "98067A");
+        assert_eq!(tle.epoch_year, 2008);
+        assert_relative_eq!(tle.epoch_day, 264.51782528);
+        assert_relative_eq!(tle.mean_motion_dot, -0.00002182);
+        assert_relative_eq!(tle.bstar, -0.11606e-4, max_relative = 1e-12);
+        assert_relative_eq!(tle.eccentricity, 0.0006703);
+        assert_relative_eq!(tle.mean_motion, 15.72125391);
+        assert_eq!(tle.revolution_number, 56353);
+
+        let ts = Timescale::default();
+        let epoch = tle.epoch(&ts).utc_calendar().unwrap();
+        assert_eq!(
+            (epoch.year, epoch.month, epoch.day, epoch.hour),
+            (2008, 9, 20, 12)
+        );
+    }
+
+    #[test]
+    fn test_rejects_bad_checksum() {
+        let lines: Vec<&str> = ISS.lines().collect();
+        let corrupted = lines[2].replace("51.6416", "51.6417");
+        assert!(matches!(
+            Tle::parse(lines[1], &corrupted),
+            Err(SatelliteError::Checksum { line: 2 })
+        ));
+        assert!(Tle::parse(lines[1], "2 25544").is_err());
+    }
+}
@@ -0,0 +1,296 @@
+//! Position uncertainty from element-set covariance
+//!
+//! Element sets come without error bars, but operators and orbit
+//! determination tools can supply a covariance for the mean elements at
+//! epoch. [`EarthSatellite::propagate_uncertainty`] pushes that covariance
+//! through SGP4 to a prediction time, either with a symmetric set of sigma
+//! points (2n SGP4 runs, exact for linear dynamics) or by Monte Carlo
+//! sampling (slower, but captures the curvature of long in-track arcs),
+//! and summarises the resulting position spread as an ellipsoid and as
+//! radial, in-track and cross-track sigmas.
+
+use super::{EarthSatellite, SatelliteError, Sgp4, Tle};
+use crate::time::Time;
+use nalgebra::{Matrix3, SMatrix, SVector, SymmetricEigen, Vector3};
+use rand::{Rng, SeedableRng};
+
+/// Number of uncertain elements
+const N: usize = 7;
+
+/// Covariance of the mean elements at epoch
+///
+/// Rows and columns are, in order: inclination, right ascension of the
+/// ascending node, argument of perigee and mean anomaly in degrees,
+/// eccentricity, mean motion in revolutions per day, and BSTAR in inverse
+/// Earth radii, so the matrix is in the squares and products of those
+/// units.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ElementCovariance {
+    matrix: SMatrix<f64, N, N>,
+}
+
+impl ElementCovariance {
+    /// Wrap a full covariance matrix
+    pub fn new(matrix: SMatrix<f64, N, N>) -> Self {
+        Self { matrix }
+    }
+
+    /// Uncorrelated elements with the given standard deviations, in the
+    /// order of the matrix rows
+    pub fn from_sigmas(sigmas: [f64; N]) -> Self {
+        let variances = SVector::<f64, N>::from_iterator(sigmas.iter().map(|s| s * s));
+        Self {
+            matrix: SMatrix::from_diagonal(&variances),
+        }
+    }
+
+    /// The covariance matrix
+    pub fn matrix(&self) -> &SMatrix<f64, N, N> {
+        &self.matrix
+    }
+
+    /// Matrix square root `S` with `S Sᵀ = P`, tolerating zero variances
+    fn square_root(&self) -> Result<SMatrix<f64, N, N>, SatelliteError> {
+        let symmetric = (self.matrix + self.matrix.transpose()) * 0.5;
+        let eigen = SymmetricEigen::new(symmetric);
+        let largest = eigen.eigenvalues.amax();
+        if eigen
+            .eigenvalues
+            .iter()
+            .any(|&v| v < -1e-12 * largest.max(1e-300))
+        {
+            return Err(SatelliteError::InvalidCovariance(
+                "element covariance is not positive semi-definite".to_string(),
+            ));
+        }
+        let roots = eigen.eigenvalues.map(|v| v.max(0.0).sqrt());
+        Ok(eigen.eigenvectors * SMatrix::from_diagonal(&roots))
+    }
+}
+
+/// How to push the covariance through SGP4
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UncertaintyMethod {
+    /// Symmetric sigma points, two per element
+    SigmaPoints,
+    /// Random draws from the element distribution
+    MonteCarlo {
+        /// Number of draws
+        samples: usize,
+        /// Seed, so results are reproducible
+        seed: u64,
+    },
+}
+
+/// Spread of predicted positions at one time
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PositionUncertainty {
+    /// Mean position in km, TEME
+    pub mean_position_km: Vector3<f64>,
+    /// Mean velocity in km/s, TEME
+    pub mean_velocity_km_s: Vector3<f64>,
+    /// Position covariance in km², TEME
+    pub covariance_km2: Matrix3<f64>,
+}
+
+/// A 1-sigma uncertainty ellipsoid
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct UncertaintyEllipsoid {
+    /// Semi-axis lengths in km, largest first
+    pub semi_axes_km: Vector3<f64>,
+    /// Unit axis directions as columns, matching `semi_axes_km`
+    pub axes: Matrix3<f64>,
+}
+
+impl PositionUncertainty {
+    /// Principal axes of the 1-sigma ellipsoid
+    pub fn ellipsoid(&self) -> UncertaintyEllipsoid {
+        let eigen = SymmetricEigen::new(self.covariance_km2);
+        let mut order = [0, 1, 2];
+        order.sort_by(|&a, &b| eigen.eigenvalues[b].total_cmp(&eigen.eigenvalues[a]));
+        UncertaintyEllipsoid {
+            semi_axes_km: Vector3::from_iterator(
+                order.iter().map(|&i| eigen.eigenvalues[i].max(0.0).sqrt()),
+            ),
+            axes: Matrix3::from_columns(&order.map(|i| eigen.eigenvectors.column(i).into_owned())),
+        }
+    }
+
+    /// Standard deviations in km along the radial, in-track and
+    /// cross-track directions of the mean orbit
+    pub fn radial_in_track_cross_track_km(&self) -> Vector3<f64> {
+        let radial = self.mean_position_km.normalize();
+        let cross = self
+            .mean_position_km
+            .cross(&self.mean_velocity_km_s)
+            .normalize();
+        let in_track = cross.cross(&radial);
+        let ric =
+            Matrix3::from_rows(&[radial.transpose(), in_track.transpose(), cross.transpose()]);
+        let rotated = ric * self.covariance_km2 * ric.transpose();
+        rotated.diagonal().map(|v| v.max(0.0).sqrt())
+    }
+}
+
+/// Element set displaced by `delta`, in the covariance's element order
+fn perturbed(tle: &Tle, delta: &SVector<f64, N>) -> Tle {
+    let mut tle = tle.clone();
+    tle.inclination_deg += delta[0];
+    tle.raan_deg += delta[1];
+    tle.arg_perigee_deg += delta[2];
+    tle.mean_anomaly_deg += delta[3];
+    tle.eccentricity += delta[4];
+    tle.mean_motion += delta[5];
+    tle.bstar += delta[6];
+    tle
+}
+
+/// Standard normal draw by the Box-Muller transform
+fn standard_normal(rng: &mut impl Rng) -> f64 {
+    let u1: f64 = rng.gen_range(f64::EPSILON..1.0);
+    let u2: f64 = rng.gen();
+    (-2.0 * u1.ln()).sqrt() * (std::f64::consts::TAU * u2).cos()
+}
+
+impl EarthSatellite {
+    /// Propagate an element covariance to time `t`
+    pub fn propagate_uncertainty(
+        &self,
+        covariance: &ElementCovariance,
+        t: &Time,
+        method: UncertaintyMethod,
+    ) -> Result<PositionUncertainty, SatelliteError> {
+        let root = covariance.square_root()?;
+        let deltas: Vec<SVector<f64, N>> = match method {
+            UncertaintyMethod::SigmaPoints => {
+                let scale = (N as f64).sqrt();
+                (0..N)
+                    .flat_map(|i| {
+                        let column = root.column(i) * scale;
+                        [column.into_owned(), -column]
+                    })
+                    .collect()
+            }
+            UncertaintyMethod::MonteCarlo { samples, seed } => {
+                let mut rng = rand::rngs::StdRng::seed_from_u64(seed);
+                (0..samples.max(2))
+                    .map(|_| root * SVector::<f64, N>::from_fn(|_, _| standard_normal(&mut rng)))
+                    .collect()
+            }
+        };
+
+        let minutes = self.minutes_since_epoch(t);
+        let states = deltas
+            .iter()
+            .map(|delta| Sgp4::new(&perturbed(self.tle(), delta))?.propagate(minutes))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let count = states.len() as f64;
+        let mean_position_km = states.iter().map(|s| s.position_km).sum::<Vector3<f64>>() / count;
+        let mean_velocity_km_s =
+            states.iter().map(|s| s.velocity_km_s).sum::<Vector3<f64>>() / count;
+        // Sigma points carry exact weights; samples need Bessel's correction
+        let denominator = match method {
+            UncertaintyMethod::SigmaPoints => count,
+            UncertaintyMethod::MonteCarlo { .. } => count - 1.0,
+        };
+        let covariance_km2 = states
+            .iter()
+            .map(|s| {
+                let d = s.position_km - mean_position_km;
+                d * d.transpose()
+            })
+            .sum::<Matrix3<f64>>()
+            / denominator;
+
+        Ok(PositionUncertainty {
+            mean_position_km,
+            mean_velocity_km_s,
+            covariance_km2,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::satellites::iss;
+    use crate::time::{TimeDelta, Timescale};
+    use approx::assert_relative_eq;
+
+    #[test]
+    fn test_mean_anomaly_uncertainty_is_in_track() {
+        let ts = Timescale::default();
+        let sat = iss(&ts);
+        let sigma_m_deg = 0.01;
+        let covariance =
+            ElementCovariance::from_sigmas([0.0, 0.0, 0.0, sigma_m_deg, 0.0, 0.0, 0.0]);
+        let t = sat.epoch() + TimeDelta::days(0.1);
+
+        let u = sat
+            .propagate_uncertainty(&covariance, &t, UncertaintyMethod::SigmaPoints)
+            .unwrap();
+        let nominal = sat.at(&t).unwrap();
+        assert!((u.mean_position_km - nominal.position_km).norm() < 0.01);
+
+        // An arc of 0.01 degrees at ~6,720 km
+        let ric = u.radial_in_track_cross_track_km();
+        let expected = u.mean_position_km.norm() * sigma_m_deg.to_radians();
+        assert_relative_eq!(ric[1], expected, max_relative = 0.05);
+        assert!(ric[0] < 0.01 * ric[1] && ric[2] < 0.01 * ric[1], "{}", ric);
+
+        let ellipsoid = u.ellipsoid();
+        assert_relative_eq!(ellipsoid.semi_axes_km[0], ric.norm(), max_relative = 1e-6);
+        let along = ellipsoid
+            .axes
+            .column(0)
+            .dot(&u.mean_velocity_km_s.normalize());
+        assert!(along.abs() > 0.99);
+    }
+
+    #[test]
+    fn test_monte_carlo_agrees_and_mean_motion_error_grows() {
+        let ts = Timescale::default();
+        let sat = iss(&ts);
+        let covariance = ElementCovariance::from_sigmas([1e-3, 1e-3, 0.0, 1e-3, 0.0, 1e-4, 1e-5]);
+
+        let day = sat.epoch() + TimeDelta::days(1.0);
+        let sigma = sat
+            .propagate_uncertainty(&covariance, &day, UncertaintyMethod::SigmaPoints)
+            .unwrap()
+            .ellipsoid();
+        let sampled = sat
+            .propagate_uncertainty(
+                &covariance,
+                &day,
+                UncertaintyMethod::MonteCarlo {
+                    samples: 2_000,
+                    seed: 7,
+                },
+            )
+            .unwrap()
+            .ellipsoid();
+        assert_relative_eq!(
+            sampled.semi_axes_km[0],
+            sigma.semi_axes_km[0],
+            max_relative = 0.1
+        );
+
+        let three_days = sat.epoch() + TimeDelta::days(3.0);
+        let later = sat
+            .propagate_uncertainty(&covariance, &three_days, UncertaintyMethod::SigmaPoints)
+            .unwrap()
+            .ellipsoid();
+        assert!(later.semi_axes_km[0] > 2.0 * sigma.semi_axes_km[0]);
+
+        let mut bad = *covariance.matrix();
+        bad[(0, 0)] = -1.0;
+        assert!(sat
+            .propagate_uncertainty(
+                &ElementCovariance::new(bad),
+                &day,
+                UncertaintyMethod::SigmaPoints
+            )
+            .is_err());
+    }
+}
@@ -0,0 +1,258 @@
+//! Routines for finding the times at which things happen
+//!
+//! Mirrors skyfield's `searchlib`: a function of time is sampled on a regular
+//! grid and each change between samples is narrowed down by bisection.
+//!
+//! The `*_events` variants also report how well each time is known, as a
+//! [`SearchEvent`] carrying an uncertainty: the final bisection bracket, and
+//! for continuous functions the time the function takes to move through its
+//! own error at the local slope.
+
+use crate::constants::DAY_S;
+use crate::time::Time;
+
+/// Default precision of located events: one millisecond, in days
+pub const DEFAULT_EPSILON_DAYS: f64 = 0.001 / DAY_S;
+
+/// Baseline for estimating the slope of a continuous function: one minute
+const SLOPE_BASELINE_DAYS: f64 = 60.0 / DAY_S;
+
+/// A located event and how precisely its time is known
+#[derive(Debug, Clone)]
+pub struct SearchEvent<T> {
+    /// Best estimate of the time of the event
+    pub time: Time,
+    /// Value the function takes from the event onwards
+    pub value: T,
+    /// Half-width of the interval that contains the event, in days
+    pub uncertainty_days: f64,
+}
+
+impl<T> SearchEvent<T> {
+    /// Uncertainty of the event time in seconds
+    pub fn uncertainty_seconds(&self) -> f64 {
+        self.uncertainty_days * DAY_S
+    }
+
+    /// Earliest time the event could have happened
+    pub fn earliest(&self) -> Time {
+        let ts = self.time.timescale();
+        ts.tt_jd(self.time.tt() - self.uncertainty_days, None)
+    }
+
+    /// Latest time the event could have happened
+    pub fn latest(&self) -> Time {
+        let ts = self.time.timescale();
+        ts.tt_jd(self.time.tt() + self.uncertainty_days, None)
+    }
+}
+
+/// Find the moments between `start` and `end` at which a discrete function
+/// of time changes value
+///
+/// `f` is sampled every `step_days`, so the step must be shorter than the
+/// briefest state you need to catch. Each change is bisected down to
+/// [`DEFAULT_EPSILON_DAYS`]. Returns the time of each change together with
+/// the value the function takes from then on.
+pub fn find_discrete<T, F>(start: &Time, end: &Time, step_days: f64, f: F) -> Vec<(Time, T)>
+where
+    T: PartialEq,
+    F: FnMut(&Time) -> T,
+{
+    let ts = start.timescale();
+    bracket_changes(start, end, step_days, f)
+        .into_iter()
+        .map(|(_, b, value)| (ts.tt_jd(b, None), value))
+        .collect()
+}
+
+/// Like [`find_discrete`], reporting each event with its uncertainty
+///
+/// A discrete function only says which side of the change a time is on, so
+/// the uncertainty is half the final bisection bracket: the search tolerance.
+/// The reported time is the bracket's midpoint, where [`find_discrete`]
+/// reports its right-hand end.
+pub fn find_discrete_events<T, F>(
+    start: &Time,
+    end: &Time,
+    step_days: f64,
+    f: F,
+) -> Vec<SearchEvent<T>>
+where
+    T: PartialEq,
+    F: FnMut(&Time) -> T,
+{
+    let ts = start.timescale();
+    bracket_changes(start, end, step_days, f)
+        .into_iter()
+        .map(|(a, b, value)| SearchEvent {
+            time: ts.tt_jd(0.5 * (a + b), None),
+            value,
+            uncertainty_days: 0.5 * (b - a),
+        })
+        .collect()
+}
+
+/// Bisect each change of `f` down to a bracket of TT Julian dates no wider
+/// than [`DEFAULT_EPSILON_DAYS`], returned with the value after the change
+fn bracket_changes<T, F>(start: &Time, end: &Time, step_days: f64, mut f: F) -> Vec<(f64, f64, T)>
+where
+    T: PartialEq,
+    F: FnMut(&Time) -> T,
+{
+    let ts = start.timescale();
+    let (jd_start, jd_end) = (start.tt(), end.tt());
+    if step_days <= 0.0 || step_days.is_nan() || jd_end <= jd_start {
+        return Vec::new();
+    }
+
+    let steps = ((jd_end - jd_start) / step_days).ceil() as usize;
+    let mut brackets = Vec::new();
+
+    let mut jd_lo = jd_start;
+    let mut value_lo = f(start);
+
+    for i in 1..=steps {
+        let jd_hi = (jd_start + i as f64 * step_days).min(jd_end);
+        let value_hi = f(&ts.tt_jd(jd_hi, None));
+        if value_hi == value_lo {
+            jd_lo = jd_hi;
+            continue;
+        }
+
+        // Bisect, keeping the first sample's value on the left
+        let (mut a, mut b) = (jd_lo, jd_hi);
+        while b - a > DEFAULT_EPSILON_DAYS {
+            let mid = 0.5 * (a + b);
+            if f(&ts.tt_jd(mid, None)) == value_lo {
+                a = mid;
+            } else {
+                b = mid;
+            }
+        }
+
+        brackets.push((a, b, value_hi));
+
+        // The value may change more than once inside a step; carry on from
+        // the sample at the end of it
+        jd_lo = jd_hi;
+        value_lo = f(&ts.tt_jd(jd_hi, None));
+    }
+
+    brackets
+}
+
+/// Find where a continuous function of time crosses zero, with error bars
+///
+/// `value_tolerance` is how far `f` itself may be off, in its own units (an
+/// altitude good to 0.01 degrees, say). Near a crossing where `f` changes at
+/// rate `f'`, that error shifts the crossing by `value_tolerance / |f'|`,
+/// capped at half of `step_days` where `f` is flat; this is combined in
+/// quadrature with the bisection tolerance. Each event's value
+/// is `true` for an upward crossing and `false` for a downward one, so rising
+/// and setting can be told apart.
+pub fn find_zero_crossings<F>(
+    start: &Time,
+    end: &Time,
+    step_days: f64,
+    value_tolerance: f64,
+    mut f: F,
+) -> Vec<SearchEvent<bool>>
+where
+    F: FnMut(&Time) -> f64,
+{
+    let ts = start.timescale();
+    let h = SLOPE_BASELINE_DAYS.min(0.5 * step_days);
+
+    let mut events = find_discrete_events(start, end, step_days, |t| f(t) > 0.0);
+    for event in &mut events {
+        let jd = event.time.tt();
+        let slope = (f(&ts.tt_jd(jd + h, None)) - f(&ts.tt_jd(jd - h, None))) / (2.0 * h);
+        // `min` also catches the NaN of a zero tolerance over a zero slope
+        let slope_term = (value_tolerance.abs() / slope.abs()).min(0.5 * step_days);
+        event.uncertainty_days = event.uncertainty_days.hypot(slope_term);
+    }
+    events
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::constants::TAU;
+    use crate::time::Timescale;
+    use approx::assert_relative_eq;
+
+    #[test]
+    fn test_find_discrete_threshold() {
+        let ts = Timescale::default();
+        let start = ts.tt_jd(2_460_000.0, None);
+        let end = ts.tt_jd(2_460_003.0, None);
+
+        // Changes at every quarter day
+        let events = find_discrete(&start, &end, 0.1, |t| (t.tt() * 4.0).floor() as i64);
+        assert_eq!(events.len(), 12);
+        for (i, (t, value)) in events.iter().enumerate() {
+            let expected = 2_460_000.0 + (i + 1) as f64 * 0.25;
+            assert_relative_eq!(t.tt(), expected, epsilon = 2.0 * DEFAULT_EPSILON_DAYS);
+            assert_eq!(*value, (expected * 4.0) as i64);
+        }
+    }
+
+    #[test]
+    fn test_discrete_events_bracket_the_change() {
+        let ts = Timescale::default();
+        let start = ts.tt_jd(2_460_000.0, None);
+        let end = ts.tt_jd(2_460_001.0, None);
+        let change = 2_460_000.3;
+
+        let events = find_discrete_events(&start, &end, 0.1, |t| t.tt() >= change);
+        assert_eq!(events.len(), 1);
+        let event = &events[0];
+        assert!(event.uncertainty_days <= 0.5 * DEFAULT_EPSILON_DAYS);
+        assert!(event.earliest().tt() <= change && change <= event.latest().tt());
+    }
+
+    #[test]
+    fn test_zero_crossing_uncertainty_follows_slope() {
+        let ts = Timescale::default();
+        let start = ts.tt_jd(2_460_000.0, None);
+        let end = ts.tt_jd(2_460_001.0, None);
+
+        // A sine with a one-day period: rising at 0.5, setting at 0.0 and 1.0
+        let wave = |t: &Time| -(TAU * (t.tt() - 2_460_000.0)).sin();
+        let events = find_zero_crossings(&start, &end, 0.05, 1e-3, wave);
+        assert_eq!(events.len(), 1);
+        assert!(events[0].value);
+        assert_relative_eq!(events[0].time.tt(), 2_460_000.5, epsilon = 1e-6);
+
+        // Slope is 2 pi per day, so a 1e-3 error is worth ~13.75 seconds
+        assert_relative_eq!(
+            events[0].uncertainty_seconds(),
+            1e-3 / TAU * DAY_S,
+            max_relative = 1e-3
+        );
+
+        // A steeper function is timed more precisely
+        let steep = find_zero_crossings(&start, &end, 0.05, 1e-3, |t| 10.0 * wave(t));
+        assert!(steep[0].uncertainty_days < events[0].uncertainty_days / 5.0);
+
+        // Crossing at an inflection the function is flat, and the error bar
+        // is capped at half a step rather than infinite
+        let flat = find_zero_crossings(&start, &end, 0.05, 1e-3, |t| {
+            (t.tt() - 2_460_000.52).powi(3)
+        });
+        assert_eq!(flat.len(), 1);
+        assert_relative_eq!(flat[0].time.tt(), 2_460_000.52, epsilon = 1e-6);
+        assert_relative_eq!(flat[0].uncertainty_days, 0.025, max_relative = 1e-6);
+        for event in &flat {
+            assert!(event.uncertainty_days <= 0.025 + DEFAULT_EPSILON_DAYS);
+        }
+    }
+
+    #[test]
+    fn test_find_discrete_empty_range() {
+        let ts = Timescale::default();
+        let t = ts.tt_jd(2_460_000.0, None);
+        assert!(find_discrete(&t, &t, 0.1, |t| t.tt() > 0.0).is_empty());
+    }
+}
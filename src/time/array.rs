@@ -0,0 +1,190 @@
+//! Arrays of times
+//!
+//! A [`TimeArray`] stores many epochs as `ndarray` vectors of whole Julian
+//! days and TT fractions, so that converting a few thousand epochs to TDB
+//! or UT1 is one pass over contiguous memory rather than thousands of
+//! [`Time`] values, each carrying its own copy of the [`Timescale`].
+//!
+//! ```
+//! use starfield::time::Timescale;
+//!
+//! let ts = Timescale::default();
+//! let t0 = ts.utc((2024, 1, 1));
+//! let t1 = ts.utc((2024, 2, 1));
+//! let times = ts.linspace(&t0, &t1, 1_000);
+//! let tdb = times.tdb();
+//! assert_eq!(tdb.len(), 1_000);
+//! ```
+
+use super::{Time, Timescale};
+use crate::constants::{DAY_S, TT_MINUS_TAI};
+use ndarray::Array1;
+use std::ops::{Add, Sub};
+
+/// A one-dimensional array of times sharing a timescale
+#[derive(Debug, Clone)]
+pub struct TimeArray {
+    /// Timescale used to create these times
+    ts: Timescale,
+    /// Whole Julian day numbers
+    whole: Array1<f64>,
+    /// TT fractions of day
+    tt_fraction: Array1<f64>,
+}
+
+impl TimeArray {
+    /// Get the timescale these times were created with
+    pub fn timescale(&self) -> &Timescale {
+        &self.ts
+    }
+
+    /// Number of times in the array
+    pub fn len(&self) -> usize {
+        self.whole.len()
+    }
+
+    /// Whether the array holds no times
+    pub fn is_empty(&self) -> bool {
+        self.whole.is_empty()
+    }
+
+    /// Get the TT (Terrestrial Time) as Julian dates
+    pub fn tt(&self) -> Array1<f64> {
+        &self.whole + &self.tt_fraction
+    }
+
+    /// Get the TAI (International Atomic Time) as Julian dates
+    pub fn tai(&self) -> Array1<f64> {
+        self.tt() - TT_MINUS_TAI
+    }
+
+    /// Get the TDB (Barycentric Dynamical Time) as Julian dates
+    pub fn tdb(&self) -> Array1<f64> {
+        self.tt()
+            .mapv_into(|tt| tt + Time::tdb_minus_tt(tt) / DAY_S)
+    }
+
+    /// Get Delta-T in seconds (TT - UT1)
+    pub fn delta_t(&self) -> Array1<f64> {
+        self.tt().mapv_into(|tt| self.ts.delta_t(tt))
+    }
+
+    /// Get the UT1 (Universal Time) as Julian dates
+    pub fn ut1(&self) -> Array1<f64> {
+        self.tt().mapv_into(|tt| tt - self.ts.delta_t(tt) / DAY_S)
+    }
+
+    /// Get the time at `index` as a scalar [`Time`]
+    pub fn get(&self, index: usize) -> Option<Time> {
+        let whole = *self.whole.get(index)?;
+        Some(self.ts.tt_jd(whole, Some(self.tt_fraction[index])))
+    }
+
+    /// Iterate over the times as scalar [`Time`] values
+    pub fn iter(&self) -> impl Iterator<Item = Time> + '_ {
+        (0..self.len()).filter_map(|i| self.get(i))
+    }
+}
+
+impl Timescale {
+    /// Create an array of times from TT Julian dates
+    pub fn tt_jd_array(&self, jd: &[f64]) -> TimeArray {
+        let jd = Array1::from(jd.to_vec());
+        let whole = jd.mapv(f64::floor);
+        TimeArray {
+            ts: self.clone(),
+            tt_fraction: jd - &whole,
+            whole,
+        }
+    }
+
+    /// Gather scalar times into an array
+    pub fn time_array(&self, times: &[Time]) -> TimeArray {
+        TimeArray {
+            ts: self.clone(),
+            whole: times.iter().map(|t| t.whole).collect(),
+            tt_fraction: times.iter().map(|t| t.tt_fraction).collect(),
+        }
+    }
+
+    /// Create an array of `num` times equally spaced from `t0` to `t1`
+    /// inclusive; a single time when `num` is below two
+    pub fn linspace(&self, t0: &Time, t1: &Time, num: usize) -> TimeArray {
+        if num < 2 {
+            return self.time_array(std::slice::from_ref(t0));
+        }
+        let steps = Array1::linspace(0.0, 1.0, num);
+        TimeArray {
+            ts: self.clone(),
+            whole: steps.mapv(|s| t0.whole + s * (t1.whole - t0.whole)),
+            tt_fraction: steps.mapv(|s| t0.tt_fraction + s * (t1.tt_fraction - t0.tt_fraction)),
+        }
+    }
+}
+
+impl Add<f64> for TimeArray {
+    type Output = TimeArray;
+
+    fn add(self, days: f64) -> Self::Output {
+        let whole_days = days.floor();
+        TimeArray {
+            ts: self.ts,
+            whole: self.whole + whole_days,
+            tt_fraction: self.tt_fraction + (days - whole_days),
+        }
+    }
+}
+
+impl Sub<f64> for TimeArray {
+    type Output = TimeArray;
+
+    fn sub(self, days: f64) -> Self::Output {
+        self + -days
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::constants::J2000;
+    use crate::time::TimeDelta;
+    use approx::assert_relative_eq;
+
+    #[test]
+    fn test_linspace_matches_scalar_times() {
+        let ts = Timescale::default();
+        let t0 = ts.utc((2024, 3, 1, 6, 0, 0.0));
+        let t1 = &t0 + TimeDelta::days(10.0);
+        let times = ts.linspace(&t0, &t1, 41);
+
+        assert_eq!(times.len(), 41);
+        assert_relative_eq!(times.tt()[0], t0.tt(), epsilon = 1e-9);
+        assert_relative_eq!(times.tt()[40], t1.tt(), epsilon = 1e-9);
+
+        let tdb = times.tdb();
+        let ut1 = times.ut1();
+        for (i, t) in times.iter().enumerate() {
+            assert_relative_eq!(t.tt(), t0.tt() + i as f64 * 0.25, epsilon = 1e-9);
+            assert_relative_eq!(tdb[i], t.tdb(), epsilon = 1e-12);
+            assert_relative_eq!(ut1[i], t.ut1(), epsilon = 1e-12);
+        }
+        assert!(times.get(41).is_none());
+        assert_eq!(ts.linspace(&t0, &t1, 1).len(), 1);
+    }
+
+    #[test]
+    fn test_array_arithmetic() {
+        let ts = Timescale::default();
+        let times = ts.tt_jd_array(&[J2000, J2000 + 0.5, J2000 + 1.75]);
+        let later = times.clone() + 1.5;
+        let earlier = times.clone() - 0.25;
+        for i in 0..times.len() {
+            assert_relative_eq!(later.tt()[i] - times.tt()[i], 1.5, epsilon = 1e-10);
+            assert_relative_eq!(times.tt()[i] - earlier.tt()[i], 0.25, epsilon = 1e-10);
+        }
+
+        let gathered = ts.time_array(&[later.get(2).unwrap(), earlier.get(0).unwrap()]);
+        assert_relative_eq!(gathered.tt()[0], J2000 + 3.25, epsilon = 1e-10);
+        assert_relative_eq!(gathered.tt()[1], J2000 - 0.25, epsilon = 1e-10);
+    }
+}
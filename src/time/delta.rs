@@ -0,0 +1,287 @@
+//! Spans of time
+//!
+//! Adding a bare `f64` to a [`Time`] counts days, which is easy to mistake
+//! for seconds. A [`TimeDelta`] says its unit where it is built, and keeps
+//! whole days and nanoseconds apart so that a long span adds no rounding
+//! error of its own:
+//!
+//! ```
+//! use starfield::time::{TimeDelta, Timescale};
+//!
+//! let ts = Timescale::default();
+//! let t = ts.utc((2024, 1, 31, 12, 0, 0.0));
+//!
+//! let later = &t + TimeDelta::hours(36.0);
+//! assert_eq!(later.utc_calendar()?.day, 2);
+//! assert_eq!((&later - &t).as_hours(), 36.0);
+//!
+//! // Calendar months have their own lengths
+//! let next_month = t.add_calendar(1, 0)?;
+//! assert_eq!(next_month.utc_calendar()?.day, 29);
+//! # Ok::<(), starfield::time::TimeError>(())
+//! ```
+
+use super::Time;
+use crate::constants::DAY_S;
+use std::fmt;
+use std::ops::{Add, Mul, Neg, Sub};
+
+/// Nanoseconds in a day
+const DAY_NS: i64 = 86_400_000_000_000;
+
+/// A signed span of time, held as whole days and nanoseconds
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+pub struct TimeDelta {
+    /// Whole days, rounded towards negative infinity
+    days: i64,
+    /// Nanoseconds beyond the whole days, in [0, one day)
+    nanos: i64,
+}
+
+impl TimeDelta {
+    /// No time at all
+    pub const ZERO: TimeDelta = TimeDelta { days: 0, nanos: 0 };
+
+    /// Build from whole days and a nanosecond count of any size, saturating
+    /// at the longest span that fits
+    fn from_parts(days: i64, nanos: i64) -> Self {
+        Self {
+            days: days.saturating_add(nanos.div_euclid(DAY_NS)),
+            nanos: nanos.rem_euclid(DAY_NS),
+        }
+    }
+
+    /// A span of `days` days, to the nearest nanosecond
+    pub fn days(days: f64) -> Self {
+        let whole = days.floor();
+        Self::from_parts(
+            whole as i64,
+            ((days - whole) * DAY_NS as f64).round() as i64,
+        )
+    }
+
+    /// A span of `hours` hours
+    pub fn hours(hours: f64) -> Self {
+        Self::seconds(hours * 3600.0)
+    }
+
+    /// A span of `minutes` minutes
+    pub fn minutes(minutes: f64) -> Self {
+        Self::seconds(minutes * 60.0)
+    }
+
+    /// A span of `seconds` seconds, to the nearest nanosecond
+    pub fn seconds(seconds: f64) -> Self {
+        let whole_days = (seconds / DAY_S).floor();
+        let rest = seconds - whole_days * DAY_S;
+        Self::from_parts(whole_days as i64, (rest * 1e9).round() as i64)
+    }
+
+    /// A span of `nanos` nanoseconds, exactly
+    pub fn nanoseconds(nanos: i64) -> Self {
+        Self::from_parts(0, nanos)
+    }
+
+    /// Length in days
+    pub fn as_days(&self) -> f64 {
+        self.days as f64 + self.nanos as f64 / DAY_NS as f64
+    }
+
+    /// Length in hours
+    pub fn as_hours(&self) -> f64 {
+        self.as_seconds() / 3600.0
+    }
+
+    /// Length in minutes
+    pub fn as_minutes(&self) -> f64 {
+        self.as_seconds() / 60.0
+    }
+
+    /// Length in seconds
+    pub fn as_seconds(&self) -> f64 {
+        self.days as f64 * DAY_S + self.nanos as f64 * 1e-9
+    }
+
+    /// Whole days and the fraction of a day beyond them, as [`Time`]
+    /// stores its dates
+    pub(super) fn split_days(&self) -> (f64, f64) {
+        (self.days as f64, self.nanos as f64 / DAY_NS as f64)
+    }
+
+    /// Whether the span runs backwards
+    pub fn is_negative(&self) -> bool {
+        self.days < 0
+    }
+
+    /// Length of the span regardless of direction
+    pub fn abs(&self) -> Self {
+        if self.is_negative() {
+            -*self
+        } else {
+            *self
+        }
+    }
+}
+
+impl fmt::Display for TimeDelta {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} s", self.as_seconds())
+    }
+}
+
+impl Add for TimeDelta {
+    type Output = TimeDelta;
+
+    fn add(self, other: TimeDelta) -> TimeDelta {
+        TimeDelta::from_parts(
+            self.days.saturating_add(other.days),
+            self.nanos + other.nanos,
+        )
+    }
+}
+
+impl Sub for TimeDelta {
+    type Output = TimeDelta;
+
+    fn sub(self, other: TimeDelta) -> TimeDelta {
+        self + -other
+    }
+}
+
+impl Neg for TimeDelta {
+    type Output = TimeDelta;
+
+    fn neg(self) -> TimeDelta {
+        TimeDelta::from_parts(self.days.saturating_neg(), -self.nanos)
+    }
+}
+
+impl Mul<f64> for TimeDelta {
+    type Output = TimeDelta;
+
+    fn mul(self, factor: f64) -> TimeDelta {
+        TimeDelta::seconds(self.as_seconds() * factor)
+    }
+}
+
+impl From<chrono::Duration> for TimeDelta {
+    fn from(duration: chrono::Duration) -> Self {
+        let seconds = duration.num_seconds();
+        let nanos = duration.subsec_nanos() as i64;
+        TimeDelta::from_parts(
+            seconds.div_euclid(86_400),
+            seconds.rem_euclid(86_400) * 1_000_000_000 + nanos,
+        )
+    }
+}
+
+impl Add<TimeDelta> for &Time {
+    type Output = Time;
+
+    fn add(self, delta: TimeDelta) -> Time {
+        let (days, fraction) = delta.split_days();
+        self.shifted(days, fraction)
+    }
+}
+
+impl Add<TimeDelta> for Time {
+    type Output = Time;
+
+    fn add(self, delta: TimeDelta) -> Time {
+        &self + delta
+    }
+}
+
+impl Sub<TimeDelta> for &Time {
+    type Output = Time;
+
+    fn sub(self, delta: TimeDelta) -> Time {
+        self + -delta
+    }
+}
+
+impl Sub<TimeDelta> for Time {
+    type Output = Time;
+
+    fn sub(self, delta: TimeDelta) -> Time {
+        &self + -delta
+    }
+}
+
+impl Sub<&Time> for &Time {
+    type Output = TimeDelta;
+
+    /// TT interval from `other` to `self`
+    fn sub(self, other: &Time) -> TimeDelta {
+        let (whole, fraction) = self.tt_parts();
+        let (other_whole, other_fraction) = other.tt_parts();
+        TimeDelta::from_parts(
+            (whole - other_whole) as i64,
+            ((fraction - other_fraction) * DAY_NS as f64).round() as i64,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::time::Timescale;
+
+    #[test]
+    fn test_units_and_normalization() {
+        assert_eq!(TimeDelta::hours(1.5), TimeDelta::minutes(90.0));
+        assert_eq!(TimeDelta::days(1.0), TimeDelta::seconds(86_400.0));
+        assert_eq!(TimeDelta::seconds(-1.0).as_seconds(), -1.0);
+        assert!(TimeDelta::seconds(-1.0).is_negative());
+        assert_eq!(TimeDelta::seconds(-1.0).abs(), TimeDelta::seconds(1.0));
+        assert_eq!(
+            TimeDelta::days(2.0) - TimeDelta::seconds(1.0),
+            TimeDelta::days(1.0) + TimeDelta::seconds(86_399.0)
+        );
+        assert_eq!(TimeDelta::minutes(10.0) * 0.5, TimeDelta::minutes(5.0));
+        assert_eq!(
+            TimeDelta::from(chrono::Duration::milliseconds(-1500)),
+            TimeDelta::nanoseconds(-1_500_000_000)
+        );
+        assert!(TimeDelta::seconds(-5.0) < TimeDelta::ZERO);
+    }
+
+    #[test]
+    fn test_long_spans_stay_exact() {
+        // A thousand years in seconds loses nothing to the split
+        let millennium = TimeDelta::days(365_250.0) + TimeDelta::nanoseconds(1);
+        assert_eq!(millennium.as_seconds(), 365_250.0 * 86_400.0);
+        assert_eq!(
+            millennium - TimeDelta::days(365_250.0),
+            TimeDelta::nanoseconds(1)
+        );
+    }
+
+    #[test]
+    fn test_huge_spans_saturate() {
+        let far = TimeDelta::days(1e30);
+        assert_eq!(far.as_days(), i64::MAX as f64);
+        assert_eq!(TimeDelta::seconds(1e30), TimeDelta::seconds(1e31));
+        assert_eq!(far + far, far);
+        assert!((-far).is_negative() && !(-TimeDelta::days(-1e30)).is_negative());
+        assert_eq!(TimeDelta::days(-1e30) - far, TimeDelta::days(-1e30));
+        assert_eq!(TimeDelta::days(f64::NAN), TimeDelta::ZERO);
+    }
+
+    #[test]
+    fn test_time_arithmetic() {
+        let ts = Timescale::default();
+        let t = ts.utc((2024, 3, 10, 6, 0, 0.0));
+
+        let later = &t + TimeDelta::minutes(90.0);
+        let cal = later.utc_calendar().unwrap();
+        assert_eq!((cal.hour, cal.minute), (7, 30));
+        assert!(((&later - &t).as_seconds() - 5400.0).abs() < 1e-6);
+        assert!(((&t - TimeDelta::days(10.0)).tt() - (t.tt() - 10.0)).abs() < 1e-12);
+
+        // Across a leap second, elapsed SI seconds differ from clock time
+        let before = ts.utc((2016, 12, 31, 23, 59, 59.0));
+        let after = ts.utc((2017, 1, 1, 0, 0, 0.0));
+        assert!(((&after - &before).as_seconds() - 2.0).abs() < 1e-4);
+    }
+}
@@ -0,0 +1,289 @@
+//! ISO 8601 and RFC 3339 timestamps
+//!
+//! Accepts the extended calendar form used by RFC 3339, such as
+//! `2024-03-20T12:34:56.789Z`, with any number of fractional-second digits
+//! (after `.` or `,`), a `T`, `t` or space between date and time, and a
+//! zone of `Z` or a `±hh:mm`, `±hhmm` or `±hh` offset. A bare date is
+//! midnight, and a timestamp without a zone is taken to be UTC. Leap
+//! seconds (`23:59:60`) are kept, rather than rolled into the next minute,
+//! and rejected in minutes that the leap-second table does not extend.
+
+use super::{Result, Time, TimeError, Timescale};
+
+/// A parsed timestamp, with the offset already removed
+struct Timestamp {
+    year: i32,
+    month: u32,
+    day: u32,
+    hour: u32,
+    minute: u32,
+    second: f64,
+}
+
+/// Cursor over the bytes of a timestamp
+struct Cursor<'a> {
+    text: &'a str,
+    pos: usize,
+}
+
+impl<'a> Cursor<'a> {
+    fn error(&self, what: &str) -> TimeError {
+        TimeError::ParseError(format!(
+            "{} at position {} in {:?}",
+            what, self.pos, self.text
+        ))
+    }
+
+    fn peek(&self) -> Option<u8> {
+        self.text.as_bytes().get(self.pos).copied()
+    }
+
+    fn done(&self) -> bool {
+        self.pos == self.text.len()
+    }
+
+    /// Consume `byte` if it is next
+    fn eat(&mut self, byte: u8) -> bool {
+        if self.peek() == Some(byte) {
+            self.pos += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn expect(&mut self, byte: u8) -> Result<()> {
+        if self.eat(byte) {
+            Ok(())
+        } else {
+            Err(self.error(&format!("expected '{}'", byte as char)))
+        }
+    }
+
+    /// Exactly `count` decimal digits
+    fn digits(&mut self, count: usize, field: &str) -> Result<u32> {
+        let end = self.pos + count;
+        match self.text.get(self.pos..end) {
+            Some(s) if s.bytes().all(|b| b.is_ascii_digit()) => {
+                self.pos = end;
+                Ok(s.parse().unwrap_or(0))
+            }
+            _ => Err(self.error(&format!("expected {} digits of {}", count, field))),
+        }
+    }
+
+    /// A decimal fraction after `.` or `,`, or zero if there is none
+    fn fraction(&mut self) -> Result<f64> {
+        if !(self.eat(b'.') || self.eat(b',')) {
+            return Ok(0.0);
+        }
+        let start = self.pos;
+        while self.peek().is_some_and(|b| b.is_ascii_digit()) {
+            self.pos += 1;
+        }
+        if self.pos == start {
+            return Err(self.error("expected fractional digits"));
+        }
+        Ok(format!("0.{}", &self.text[start..self.pos])
+            .parse()
+            .unwrap_or(0.0))
+    }
+}
+
+/// Check that a field lies in `range`
+fn check(value: u32, range: std::ops::RangeInclusive<u32>, field: &str) -> Result<u32> {
+    if range.contains(&value) {
+        Ok(value)
+    } else {
+        Err(TimeError::ParseError(format!(
+            "{} {} is out of range",
+            field, value
+        )))
+    }
+}
+
+fn parse(ts: &Timescale, text: &str) -> Result<Timestamp> {
+    let mut c = Cursor {
+        text: text.trim(),
+        pos: 0,
+    };
+
+    let sign = if c.eat(b'-') {
+        -1
+    } else {
+        c.eat(b'+');
+        1
+    };
+    let year = sign * c.digits(4, "year")? as i32;
+    c.expect(b'-')?;
+    let month = check(c.digits(2, "month")?, 1..=12, "month")?;
+    c.expect(b'-')?;
+    let day = c.digits(2, "day")?;
+    if day == 0
+        || ts.julian_day_to_calendar_date(ts.julian_day(year, month, day)) != (year, month, day)
+    {
+        return Err(TimeError::ParseError(format!(
+            "day {} is not in {:04}-{:02}",
+            day, year, month
+        )));
+    }
+
+    let (mut hour, mut minute, mut second, mut offset_minutes) = (0, 0, 0.0, 0i64);
+    if !c.done() {
+        if !(c.eat(b'T') || c.eat(b't') || c.eat(b' ')) {
+            return Err(c.error("expected 'T' between date and time"));
+        }
+        hour = check(c.digits(2, "hour")?, 0..=23, "hour")?;
+        c.expect(b':')?;
+        minute = check(c.digits(2, "minute")?, 0..=59, "minute")?;
+        if c.eat(b':') {
+            second = check(c.digits(2, "second")?, 0..=60, "second")? as f64;
+            second += c.fraction()?;
+        }
+
+        match c.peek() {
+            None => {}
+            Some(b'Z' | b'z') => c.pos += 1,
+            Some(b @ (b'+' | b'-')) => {
+                c.pos += 1;
+                let hours = check(c.digits(2, "offset hours")?, 0..=23, "offset hours")?;
+                let minutes = if c.done() {
+                    0
+                } else {
+                    c.eat(b':');
+                    check(c.digits(2, "offset minutes")?, 0..=59, "offset minutes")?
+                };
+                let magnitude = (hours * 60 + minutes) as i64;
+                offset_minutes = if b == b'-' { -magnitude } else { magnitude };
+            }
+            Some(_) => return Err(c.error("expected 'Z' or a UTC offset")),
+        }
+    }
+    if !c.done() {
+        return Err(c.error("unexpected trailing characters"));
+    }
+
+    // Shift whole minutes so that a leap second keeps its 60th second
+    let mut year = year;
+    let (mut month, mut day) = (month, day);
+    if offset_minutes != 0 {
+        let local = hour as i64 * 60 + minute as i64 - offset_minutes;
+        let days = local.div_euclid(1440);
+        let local = local.rem_euclid(1440);
+        (hour, minute) = ((local / 60) as u32, (local % 60) as u32);
+        (year, month, day) =
+            ts.julian_day_to_calendar_date(ts.julian_day(year, month, day) + days as i32);
+    }
+
+    Ok(Timestamp {
+        year,
+        month,
+        day,
+        hour,
+        minute,
+        second,
+    })
+}
+
+impl Timescale {
+    /// Parse an ISO 8601 or RFC 3339 timestamp
+    ///
+    /// ```
+    /// use starfield::time::Timescale;
+    ///
+    /// let ts = Timescale::default();
+    /// let t = ts.from_iso8601("2024-03-20T14:34:56.789+02:00")?;
+    /// assert_eq!(t.utc_calendar()?.hour, 12);
+    /// # Ok::<(), starfield::time::TimeError>(())
+    /// ```
+    pub fn from_iso8601(&self, text: &str) -> Result<Time> {
+        let s = parse(self, text)?;
+        if s.second >= 60.0
+            && !self.minute_has_leap_second(s.year, s.month, s.day, s.hour, s.minute)
+        {
+            return Err(TimeError::ParseError(format!(
+                "no leap second at {:04}-{:02}-{:02}T{:02}:{:02}Z in {:?}",
+                s.year, s.month, s.day, s.hour, s.minute, text
+            )));
+        }
+        Ok(self.utc((s.year, s.month, s.day, s.hour, s.minute, s.second)))
+    }
+}
+
+impl Time {
+    /// Parse an ISO 8601 or RFC 3339 timestamp with the default timescale
+    pub fn parse(text: &str) -> Result<Self> {
+        Timescale::builtin().from_iso8601(text)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use approx::assert_relative_eq;
+
+    #[test]
+    fn test_parse_rfc3339() {
+        let ts = Timescale::default();
+        let expected = ts.utc((2024, 3, 20, 12, 34, 56.789));
+
+        for text in [
+            "2024-03-20T12:34:56.789Z",
+            "2024-03-20t12:34:56,789z",
+            "2024-03-20 12:34:56.789",
+            "2024-03-20T14:34:56.789+02:00",
+            "2024-03-20T07:04:56.789-0530",
+            "2024-03-21T00:34:56.789+12",
+        ] {
+            let t = ts.from_iso8601(text).unwrap();
+            assert_relative_eq!(t.tt(), expected.tt(), epsilon = 1e-9);
+        }
+
+        // Offsets that carry across a month and a year boundary
+        let t = Time::parse("2023-12-31T22:30:00-03:00").unwrap();
+        let cal = t.utc_calendar().unwrap();
+        assert_eq!((cal.year, cal.month, cal.day, cal.hour), (2024, 1, 1, 1));
+
+        let midnight = ts.from_iso8601("2024-02-29").unwrap();
+        assert_relative_eq!(midnight.tt(), ts.utc((2024, 2, 29)).tt(), epsilon = 1e-12);
+    }
+
+    #[test]
+    fn test_leap_second() {
+        let ts = Timescale::default();
+        let leap = ts.from_iso8601("2016-12-31T23:59:60.5Z").unwrap();
+        let after = ts.from_iso8601("2017-01-01T00:00:00Z").unwrap();
+        assert_relative_eq!((&after - &leap).as_seconds(), 0.5, epsilon = 1e-4);
+        assert_eq!(leap.utc_iso('T', 1).unwrap(), "2016-12-31T23:59:60.5Z");
+
+        // A leap second at 23:59:60 local time, an hour ahead of UTC
+        let local = ts.from_iso8601("2017-01-01T00:59:60.5+01:00").unwrap();
+        assert_relative_eq!(local.tt(), leap.tt(), epsilon = 1e-9);
+
+        for text in ["2024-12-31T23:59:60Z", "2016-12-31T23:58:60Z"] {
+            assert!(matches!(
+                ts.from_iso8601(text),
+                Err(TimeError::ParseError(_))
+            ));
+        }
+    }
+
+    #[test]
+    fn test_reports_errors() {
+        for text in [
+            "",
+            "2024-3-20",
+            "2024-02-30",
+            "2024-13-01T00:00Z",
+            "2024-03-20T25:00Z",
+            "2024-03-20T12:34:56.Z",
+            "2024-03-20T12:34:56 UTC",
+            "2024-03-20T12:34:56+2",
+        ] {
+            match Time::parse(text) {
+                Err(TimeError::ParseError(_)) => {}
+                other => panic!("{:?} gave {:?}", text, other),
+            }
+        }
+    }
+}
@@ -0,0 +1,510 @@
+//! Pointing streams for telescope mounts
+//!
+//! A [`Tracker`] follows a star or solar system body from a site and
+//! produces [`TrackingSample`]s at a fixed cadence: where the target is in
+//! equatorial, hour angle and alt/az terms, and how fast each axis must
+//! move to stay on it. Mount control loops can consume
+//! [`Tracker::stream`] directly:
+//!
+//! ```
+//! use starfield::observers::GeographicLocation;
+//! use starfield::planetlib::{Body, Ephemeris};
+//! use starfield::time::Timescale;
+//! use starfield::tracking::{Tracker, TrackingTarget};
+//!
+//! let ts = Timescale::default();
+//! let eph = Ephemeris::new();
+//! let site = GeographicLocation::new(19.82, -155.47, 4_205.0);
+//! let tracker = Tracker::new(&eph, site, TrackingTarget::Body(Body::Moon)).with_cadence(5.0);
+//!
+//! for sample in tracker.stream(&ts.utc((2024, 6, 14, 8, 0, 0.0))).take(3) {
+//!     let sample = sample?;
+//!     println!(
+//!         "HA {:+.5}h  rate {:.3}\"/s  alt {:.3}",
+//!         sample.hour_angle_hours,
+//!         sample.hour_angle_rate,
+//!         sample.horizontal.alt_degrees()
+//!     );
+//! }
+//! # Ok::<(), starfield::planetlib::PlanetError>(())
+//! ```
+//!
+//! Rates are central differences over one second. Star positions are used
+//! as given, without proper motion or aberration. Mounts on aircraft or
+//! ships can be tracked from a moving [`Trajectory`], in which case the
+//! rates include the platform's motion (but not its attitude).
+//!
+//! Each sample also carries the parallactic angle, by which the sky turns
+//! in the focal plane of an alt-az telescope, and its rate, for driving an
+//! image derotator; [`Tracker::field_rotation`] integrates it over an
+//! exposure. The angle is measured from the true celestial pole of date,
+//! so precession and nutation are included.
+
+use crate::accuracy::{AccuracyProfile, NutationModel};
+use crate::constants::{DAY_S, DEG2RAD, RAD2DEG, TAU};
+use crate::coordinates::Equatorial;
+use crate::earthlib::sidereal_time;
+use crate::framelib::{rot_z, Atmosphere, Horizontal, HorizontalFrame, Refraction};
+use crate::nutationlib::{equation_of_the_equinoxes, precession_nutation_matrix};
+use crate::observers::{GeographicLocation, Trajectory};
+use crate::planetlib::{Body, Ephemeris, PlanetError};
+use crate::precessionlib::compute_precession;
+use crate::time::{Time, TimeDelta};
+use nalgebra::Vector3;
+
+/// Half-width of the interval used to differentiate positions, in seconds
+const RATE_HALF_STEP_S: f64 = 0.5;
+
+/// Step in seconds at which field rotation is integrated over an exposure
+const FIELD_ROTATION_STEP_S: f64 = 5.0;
+
+/// What a [`Tracker`] follows
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TrackingTarget {
+    /// A fixed J2000 direction, such as a catalog star
+    Star(Equatorial),
+    /// A solar system body, observed topocentrically
+    Body(Body),
+}
+
+/// Standard hour-angle drive rates offered by mount controllers
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TrackingRate {
+    /// Follows the stars
+    Sidereal,
+    /// Follows the mean Sun
+    Solar,
+    /// Follows the mean Moon
+    Lunar,
+}
+
+impl TrackingRate {
+    /// Hour-angle rate in arcseconds per second of time
+    pub fn arcsec_per_second(&self) -> f64 {
+        match self {
+            TrackingRate::Sidereal => 15.041_067,
+            TrackingRate::Solar => 15.0,
+            TrackingRate::Lunar => 14.685,
+        }
+    }
+}
+
+/// Where a target is at one moment and how it is moving
+#[derive(Debug, Clone)]
+pub struct TrackingSample {
+    /// Time of the sample
+    pub time: Time,
+    /// Direction of the target, J2000 axes
+    pub position: Equatorial,
+    /// Hour angle in hours, in [-12, 12)
+    pub hour_angle_hours: f64,
+    /// Declination referred to the equator of date, in degrees
+    pub dec_of_date_deg: f64,
+    /// Altitude and azimuth, refracted if the tracker has a refraction model
+    pub horizontal: Horizontal,
+    /// Hour-angle rate in arcseconds per second
+    pub hour_angle_rate: f64,
+    /// Declination rate in arcseconds per second
+    pub dec_rate: f64,
+    /// Altitude rate in arcseconds per second
+    pub alt_rate: f64,
+    /// Azimuth rate in arcseconds per second
+    pub az_rate: f64,
+    /// Parallactic angle in degrees: the position angle of the zenith at
+    /// the target, from the celestial pole through east
+    pub parallactic_angle_deg: f64,
+    /// Rate of field rotation in arcseconds per second, the rate at which a
+    /// derotator must turn
+    pub field_rotation_rate: f64,
+}
+
+/// Follows a target from a site
+#[derive(Debug, Clone)]
+pub struct Tracker<'a> {
+    ephemeris: &'a Ephemeris,
+    observer: Trajectory,
+    target: TrackingTarget,
+    cadence_s: f64,
+    refraction: Option<Refraction>,
+    atmosphere: Atmosphere,
+    nutation: NutationModel,
+}
+
+/// Instantaneous pointing, before differentiation
+struct Pointing {
+    position: Equatorial,
+    hour_angle_hours: f64,
+    dec_of_date_deg: f64,
+    horizontal: Horizontal,
+    parallactic_angle_deg: f64,
+}
+
+impl<'a> Tracker<'a> {
+    /// Track `target` from a site or a moving observer, sampling once per
+    /// second
+    pub fn new(
+        ephemeris: &'a Ephemeris,
+        observer: impl Into<Trajectory>,
+        target: TrackingTarget,
+    ) -> Self {
+        Self {
+            ephemeris,
+            observer: observer.into(),
+            target,
+            cadence_s: 1.0,
+            refraction: None,
+            atmosphere: Atmosphere::default(),
+            nutation: NutationModel::Iau2000B,
+        }
+    }
+
+    /// Set the interval between samples in seconds
+    pub fn with_cadence(mut self, seconds: f64) -> Self {
+        self.cadence_s = seconds;
+        self
+    }
+
+    /// Report refracted altitudes using `model`
+    pub fn with_refraction(mut self, model: Refraction) -> Self {
+        self.refraction = Some(model);
+        self
+    }
+
+    /// Set the site's pressure and temperature for the refraction model
+    pub fn with_atmosphere(mut self, atmosphere: Atmosphere) -> Self {
+        self.atmosphere = atmosphere;
+        self
+    }
+
+    /// Set how much of the nutation series the parallactic angle includes
+    pub fn with_nutation(mut self, model: NutationModel) -> Self {
+        self.nutation = model;
+        self
+    }
+
+    /// Use the nutation and refraction models of an accuracy profile
+    pub fn with_accuracy(mut self, profile: &AccuracyProfile) -> Self {
+        self.nutation = profile.nutation;
+        self.refraction = profile.refraction;
+        self
+    }
+
+    /// The tracked target
+    pub fn target(&self) -> TrackingTarget {
+        self.target
+    }
+
+    /// Interval between samples in seconds
+    pub fn cadence(&self) -> f64 {
+        self.cadence_s
+    }
+
+    /// Pointing and rates at time `t`
+    pub fn sample(&self, t: &Time) -> Result<TrackingSample, PlanetError> {
+        let half_step = RATE_HALF_STEP_S / DAY_S;
+        let now = self.pointing(t)?;
+        let before = self.pointing(&(t - TimeDelta::days(half_step)))?;
+        let after = self.pointing(&(t + TimeDelta::days(half_step)))?;
+
+        let per_second = 3_600.0 / (2.0 * RATE_HALF_STEP_S);
+        Ok(TrackingSample {
+            time: t.clone(),
+            position: now.position,
+            hour_angle_hours: now.hour_angle_hours,
+            dec_of_date_deg: now.dec_of_date_deg,
+            horizontal: now.horizontal,
+            hour_angle_rate: wrapped(
+                after.hour_angle_hours * 15.0 - before.hour_angle_hours * 15.0,
+            ) * per_second,
+            dec_rate: (after.dec_of_date_deg - before.dec_of_date_deg) * per_second,
+            alt_rate: (after.horizontal.alt_degrees() - before.horizontal.alt_degrees())
+                * per_second,
+            az_rate: wrapped(after.horizontal.az_degrees() - before.horizontal.az_degrees())
+                * per_second,
+            parallactic_angle_deg: now.parallactic_angle_deg,
+            field_rotation_rate: wrapped(
+                after.parallactic_angle_deg - before.parallactic_angle_deg,
+            ) * per_second,
+        })
+    }
+
+    /// Total field rotation in degrees over an exposure of `exposure_s`
+    /// seconds starting at `start`
+    ///
+    /// The parallactic angle is followed in short steps, so that the
+    /// half-turn it makes when a target crosses the meridian near the
+    /// zenith is counted rather than wrapped away.
+    pub fn field_rotation(&self, start: &Time, exposure_s: f64) -> Result<f64, PlanetError> {
+        let steps = (exposure_s.abs() / FIELD_ROTATION_STEP_S).ceil().max(1.0) as usize;
+        let step_days = exposure_s / steps as f64 / DAY_S;
+
+        let mut previous = self.pointing(start)?.parallactic_angle_deg;
+        let mut total = 0.0;
+        for i in 1..=steps {
+            let angle = self
+                .pointing(&(start + TimeDelta::days(i as f64 * step_days)))?
+                .parallactic_angle_deg;
+            total += wrapped(angle - previous);
+            previous = angle;
+        }
+        Ok(total)
+    }
+
+    /// Endless stream of samples starting at `start`, spaced by the cadence
+    pub fn stream(&self, start: &Time) -> TrackingStream<'_, 'a> {
+        TrackingStream {
+            tracker: self,
+            start: start.clone(),
+            index: 0,
+        }
+    }
+
+    fn pointing(&self, t: &Time) -> Result<Pointing, PlanetError> {
+        let vector = match self.target {
+            TrackingTarget::Star(position) => {
+                let (sin_dec, cos_dec) = position.dec.sin_cos();
+                let (sin_ra, cos_ra) = position.ra.sin_cos();
+                Vector3::new(cos_dec * cos_ra, cos_dec * sin_ra, sin_dec)
+            }
+            TrackingTarget::Body(body) => *self
+                .observer
+                .at(self.ephemeris, t)?
+                .observe(body)?
+                .apparent(self.ephemeris)?
+                .position
+                .vector(),
+        };
+        let position = Equatorial::new(vector.y.atan2(vector.x), (vector.z / vector.norm()).asin());
+
+        let of_date = compute_precession(t.tdb()) * vector;
+        let ra_of_date_deg = of_date.y.atan2(of_date.x) * RAD2DEG;
+        let dec_of_date_deg = (of_date.z / of_date.norm()).asin() * RAD2DEG;
+
+        let location = self.observer.location_at(t);
+        let parallactic_angle_deg = parallactic_angle(&location, &position, t, self.nutation);
+        let mut frame = HorizontalFrame::new(location, t).with_atmosphere(self.atmosphere);
+        if let Some(model) = self.refraction {
+            frame = frame.with_refraction(model);
+        }
+
+        Ok(Pointing {
+            position,
+            hour_angle_hours: frame.hour_angle(ra_of_date_deg),
+            dec_of_date_deg,
+            horizontal: frame.from_equatorial(&position),
+            parallactic_angle_deg,
+        })
+    }
+}
+
+/// Parallactic angle in degrees of a J2000 direction seen from `location`
+/// at `t`: the position angle of the zenith, measured from the true
+/// celestial pole of date through east
+///
+/// Positive west of the meridian and negative east of it. The pole is that
+/// of `nutation`'s truncation of the series; polar motion is neglected.
+pub fn parallactic_angle(
+    location: &GeographicLocation,
+    direction: &Equatorial,
+    t: &Time,
+    nutation: NutationModel,
+) -> f64 {
+    let jd = t.tdb();
+    let (sin_dec, cos_dec) = direction.dec.sin_cos();
+    let (sin_ra, cos_ra) = direction.ra.sin_cos();
+    let target = precession_nutation_matrix(jd, nutation)
+        * Vector3::new(cos_dec * cos_ra, cos_dec * sin_ra, sin_dec);
+
+    // Zenith on the true equator of date, turned by apparent sidereal time
+    let apparent_sidereal = sidereal_time(t) / 24.0 * TAU + equation_of_the_equinoxes(jd, nutation);
+    let (sin_lat, cos_lat) = (location.latitude_deg * DEG2RAD).sin_cos();
+    let (sin_lon, cos_lon) = (location.longitude_deg * DEG2RAD).sin_cos();
+    let zenith =
+        rot_z(apparent_sidereal) * Vector3::new(cos_lat * cos_lon, cos_lat * sin_lon, sin_lat);
+
+    // North and east at the target, both of length cos δ
+    let pole = Vector3::z();
+    let north = pole - pole.dot(&target) * target;
+    let east = pole.cross(&target);
+    zenith.dot(&east).atan2(zenith.dot(&north)) * RAD2DEG
+}
+
+/// Difference of two angles in degrees, wrapped into [-180, 180)
+fn wrapped(difference_deg: f64) -> f64 {
+    (difference_deg + 180.0).rem_euclid(360.0) - 180.0
+}
+
+/// Iterator over a [`Tracker`]'s samples, see [`Tracker::stream`]
+#[derive(Debug, Clone)]
+pub struct TrackingStream<'t, 'a> {
+    tracker: &'t Tracker<'a>,
+    start: Time,
+    index: u64,
+}
+
+impl Iterator for TrackingStream<'_, '_> {
+    type Item = Result<TrackingSample, PlanetError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        // Offsets from the start avoid accumulating rounding over long runs
+        let offset_days = self.index as f64 * self.tracker.cadence_s / DAY_S;
+        self.index += 1;
+        Some(
+            self.tracker
+                .sample(&(&self.start + TimeDelta::days(offset_days))),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::observers::GeographicLocation;
+    use crate::time::Timescale;
+    use approx::assert_relative_eq;
+
+    fn mauna_kea() -> GeographicLocation {
+        GeographicLocation::new(19.82, -155.47, 4_205.0)
+    }
+
+    #[test]
+    fn test_star_moves_at_sidereal_rate() {
+        let ts = Timescale::default();
+        let eph = Ephemeris::new();
+        let vega = TrackingTarget::Star(Equatorial::from_degrees(279.23, 38.78));
+        let tracker = Tracker::new(&eph, mauna_kea(), vega).with_cadence(10.0);
+
+        let samples: Vec<TrackingSample> = tracker
+            .stream(&ts.utc((2024, 7, 1, 10, 0, 0.0)))
+            .take(3)
+            .collect::<Result<_, _>>()
+            .unwrap();
+
+        for sample in &samples {
+            assert_relative_eq!(
+                sample.hour_angle_rate,
+                TrackingRate::Sidereal.arcsec_per_second(),
+                epsilon = 1e-3
+            );
+            assert!(sample.dec_rate.abs() < 1e-4);
+        }
+
+        // Consecutive samples are a cadence apart and agree with the rates
+        let step_s = (samples[1].time.tt() - samples[0].time.tt()) * DAY_S;
+        assert_relative_eq!(step_s, 10.0, epsilon = 1e-4);
+        let moved =
+            (samples[1].horizontal.alt_degrees() - samples[0].horizontal.alt_degrees()) * 3_600.0;
+        let expected = (samples[0].alt_rate + samples[1].alt_rate) / 2.0 * 10.0;
+        assert_relative_eq!(moved, expected, epsilon = 0.01);
+    }
+
+    #[test]
+    fn test_sun_and_moon_rates() {
+        let ts = Timescale::default();
+        let eph = Ephemeris::new();
+        let t = ts.utc((2024, 6, 14, 22, 0, 0.0));
+
+        let sun = Tracker::new(&eph, mauna_kea(), TrackingTarget::Body(Body::Sun))
+            .sample(&t)
+            .unwrap();
+        assert_relative_eq!(
+            sun.hour_angle_rate,
+            TrackingRate::Solar.arcsec_per_second(),
+            epsilon = 0.01
+        );
+
+        // The Moon's rate varies with its orbit and topocentric parallax
+        let moon = Tracker::new(&eph, mauna_kea(), TrackingTarget::Body(Body::Moon))
+            .sample(&t)
+            .unwrap();
+        assert_relative_eq!(
+            moon.hour_angle_rate,
+            TrackingRate::Lunar.arcsec_per_second(),
+            epsilon = 0.3
+        );
+        assert!(moon.dec_rate.abs() < 0.3);
+    }
+
+    #[test]
+    fn test_parallactic_angle_and_field_rotation() {
+        let ts = Timescale::default();
+        let eph = Ephemeris::new();
+        let site = mauna_kea();
+        let star = Equatorial::from_degrees(279.23, 38.78);
+        let tracker = Tracker::new(&eph, site, TrackingTarget::Star(star));
+
+        for hour in [6, 8, 10] {
+            let sample = tracker.sample(&ts.utc((2024, 7, 1, hour, 0, 0.0))).unwrap();
+
+            // The classical formula, from hour angle and declination of date
+            let h = sample.hour_angle_hours * 15.0 * DEG2RAD;
+            let dec = sample.dec_of_date_deg * DEG2RAD;
+            let lat = site.latitude_deg * DEG2RAD;
+            let q = h.sin().atan2(lat.tan() * dec.cos() - dec.sin() * h.cos()) * RAD2DEG;
+            assert_relative_eq!(sample.parallactic_angle_deg, q, epsilon = 0.01);
+            assert_eq!(sample.parallactic_angle_deg > 0.0, h.sin() > 0.0);
+
+            // dq/dt = -ω cos φ cos A / cos h, azimuth from north
+            let (alt, az) = (
+                sample.horizontal.alt_degrees() * DEG2RAD,
+                sample.horizontal.az_degrees() * DEG2RAD,
+            );
+            let rate =
+                -TrackingRate::Sidereal.arcsec_per_second() * lat.cos() * az.cos() / alt.cos();
+            assert_relative_eq!(sample.field_rotation_rate, rate, epsilon = 0.01);
+
+            // Over a short exposure the rotation is the rate times the time
+            let t = &sample.time - TimeDelta::seconds(30.0);
+            let rotation = tracker.field_rotation(&t, 60.0).unwrap() * 3_600.0;
+            assert_relative_eq!(rotation, rate * 60.0, max_relative = 1e-3);
+        }
+
+        // A target passing a degree north of the zenith turns the field by
+        // roughly 180° - 2 atan(1° / 7°) in the hour around its transit
+        let overhead = Equatorial::from_degrees(279.23, site.latitude_deg + 1.0);
+        let transit = Tracker::new(&eph, site, TrackingTarget::Star(overhead));
+        let t = ts.utc((2024, 7, 1, 10, 0, 0.0));
+        let hour_angle = transit.sample(&t).unwrap().hour_angle_hours;
+        let start = t - TimeDelta::days(hour_angle / 24.0 * 0.997_27 + 30.0 / 1440.0);
+        let rotation = transit.field_rotation(&start, 3_600.0).unwrap();
+        assert_relative_eq!(rotation.abs(), 164.0, epsilon = 3.0);
+    }
+
+    #[test]
+    fn test_refraction_raises_altitude() {
+        let ts = Timescale::default();
+        let eph = Ephemeris::new();
+        let t = ts.utc((2024, 6, 14, 22, 0, 0.0));
+        let target = TrackingTarget::Body(Body::Sun);
+
+        let plain = Tracker::new(&eph, mauna_kea(), target).sample(&t).unwrap();
+        let refracted = Tracker::new(&eph, mauna_kea(), target)
+            .with_refraction(Refraction::Bennett)
+            .sample(&t)
+            .unwrap();
+        assert!(plain.horizontal.alt_degrees() > 5.0);
+        assert!(refracted.horizontal.alt_degrees() > plain.horizontal.alt_degrees());
+        assert_relative_eq!(
+            plain.horizontal.az,
+            refracted.horizontal.az,
+            epsilon = 1e-12
+        );
+        assert_relative_eq!(wrapped(359.0 - 1.0), -2.0, epsilon = 1e-12);
+
+        // Accuracy profiles carry their refraction model to the tracker
+        let standard = Tracker::new(&eph, mauna_kea(), target)
+            .with_accuracy(&AccuracyProfile::standard())
+            .sample(&t)
+            .unwrap();
+        let fast = Tracker::new(&eph, mauna_kea(), target)
+            .with_accuracy(&AccuracyProfile::fast())
+            .sample(&t)
+            .unwrap();
+        assert_relative_eq!(
+            standard.horizontal.alt,
+            refracted.horizontal.alt,
+            epsilon = 1e-12
+        );
+        assert!(fast.horizontal.alt < standard.horizontal.alt);
+    }
+}
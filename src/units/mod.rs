@@ -1 +1,305 @@
-//! units module\n\n// Placeholder for units implementations
+//! Angles with sexagesimal formatting and parsing
+//!
+//! An [`Angle`] stores radians and reads out in degrees or hours, either as
+//! decimals or as the sexagesimal strings almanacs print, so computed
+//! positions can be checked against published ones by eye:
+//!
+//! ```
+//! use starfield::units::Angle;
+//!
+//! let ra = Angle::from_degrees(78.634_458);
+//! assert_eq!(ra.hstr(2), "05h 14m 32.27s");
+//!
+//! let dec: Angle = "-08° 12′ 05.9″".parse()?;
+//! assert!((dec.degrees() + 8.201_639).abs() < 1e-6);
+//! assert_eq!(format!("{:.1}", dec), "-08° 12′ 05.9″");
+//! # Ok::<(), starfield::StarfieldError>(())
+//! ```
+
+use crate::constants::{DEG2RAD, RAD2DEG};
+use crate::{Result, StarfieldError};
+use std::fmt;
+use std::str::FromStr;
+
+/// An angle, stored in radians
+#[derive(Debug, Clone, Copy, Default, PartialEq, PartialOrd)]
+pub struct Angle {
+    radians: f64,
+}
+
+impl Angle {
+    /// An angle of `radians`
+    pub fn from_radians(radians: f64) -> Self {
+        Self { radians }
+    }
+
+    /// An angle of `degrees`
+    pub fn from_degrees(degrees: f64) -> Self {
+        Self::from_radians(degrees * DEG2RAD)
+    }
+
+    /// An angle of `hours`, 15 degrees each
+    pub fn from_hours(hours: f64) -> Self {
+        Self::from_degrees(hours * 15.0)
+    }
+
+    /// The angle in radians
+    pub fn radians(&self) -> f64 {
+        self.radians
+    }
+
+    /// The angle in degrees
+    pub fn degrees(&self) -> f64 {
+        self.radians * RAD2DEG
+    }
+
+    /// The angle in hours
+    pub fn hours(&self) -> f64 {
+        self.degrees() / 15.0
+    }
+
+    /// Hours, minutes and seconds, each carrying the sign of the angle
+    pub fn hms(&self) -> (f64, f64, f64) {
+        sexagesimal(self.hours())
+    }
+
+    /// Degrees, arcminutes and arcseconds, each carrying the sign of the
+    /// angle
+    pub fn dms(&self) -> (f64, f64, f64) {
+        sexagesimal(self.degrees())
+    }
+
+    /// The angle in hours as `05h 14m 32.27s`, seconds to `places` decimals
+    pub fn hstr(&self, places: usize) -> String {
+        format_sexagesimal(self.hours(), places, ["h", "m", "s"])
+    }
+
+    /// The angle in degrees as `-08° 12′ 05.9″`, arcseconds to `places`
+    /// decimals
+    pub fn dstr(&self, places: usize) -> String {
+        format_sexagesimal(self.degrees(), places, ["°", "′", "″"])
+    }
+
+    /// Parse hours, minutes and seconds such as `05h 14m 32.27s` or
+    /// `5:14:32.27`
+    ///
+    /// Trailing fields may be left off, and the last field given may have
+    /// decimals.
+    /// Each unit mark must match its field, and degree marks are rejected.
+    pub fn parse_hours(text: &str) -> Result<Self> {
+        match parse_sexagesimal(text)? {
+            (_, Some(UnitMark::Degrees)) => Err(invalid_sexagesimal(text)),
+            (hours, _) => Ok(Self::from_hours(hours)),
+        }
+    }
+
+    /// Parse degrees, arcminutes and arcseconds such as `-08° 12′ 05.9″`,
+    /// `-8d12'05.9"` or `-8 12 5.9`
+    ///
+    /// Trailing fields may be left off, and the last field given may have
+    /// decimals.
+    /// Each unit mark must match its field, and an hour mark is rejected.
+    pub fn parse_degrees(text: &str) -> Result<Self> {
+        match parse_sexagesimal(text)? {
+            (_, Some(UnitMark::Hours)) => Err(invalid_sexagesimal(text)),
+            (degrees, _) => Ok(Self::from_degrees(degrees)),
+        }
+    }
+}
+
+/// Parses hours when the first field is marked `h`, and degrees otherwise
+impl FromStr for Angle {
+    type Err = StarfieldError;
+
+    fn from_str(text: &str) -> Result<Self> {
+        match parse_sexagesimal(text)? {
+            (hours, Some(UnitMark::Hours)) => Ok(Self::from_hours(hours)),
+            (degrees, _) => Ok(Self::from_degrees(degrees)),
+        }
+    }
+}
+
+/// Formats as degrees, arcminutes and arcseconds; the precision sets the
+/// arcsecond decimals and defaults to 1
+impl fmt::Display for Angle {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.dstr(f.precision().unwrap_or(1)))
+    }
+}
+
+/// Split a value into signed units, sixtieths and 3600ths
+fn sexagesimal(value: f64) -> (f64, f64, f64) {
+    let sign = value.signum();
+    let value = value.abs();
+    let units = value.trunc();
+    let minutes = ((value - units) * 60.0).trunc();
+    let seconds = (value - units) * 3600.0 - minutes * 60.0;
+    (sign * units, sign * minutes, sign * seconds)
+}
+
+/// Format a value as `UU? MM? SS.ss?`, rounding the seconds to `places`
+/// decimals and carrying into the minutes and units
+fn format_sexagesimal(value: f64, places: usize, marks: [&str; 3]) -> String {
+    let scale = 10f64.powi(places as i32);
+    let ticks = (value.abs() * 3600.0 * scale).round();
+    let per_minute = 60.0 * scale;
+    let units = (ticks / (60.0 * per_minute)).trunc();
+    let minutes = ((ticks - units * 60.0 * per_minute) / per_minute).trunc();
+    let seconds = (ticks - units * 60.0 * per_minute - minutes * per_minute) / scale;
+
+    let sign = if value < 0.0 && ticks > 0.0 { "-" } else { "" };
+    let width = if places > 0 { places + 3 } else { 2 };
+    format!(
+        "{}{:02}{} {:02}{} {:0width$.places$}{}",
+        sign,
+        units,
+        marks[0],
+        minutes,
+        marks[1],
+        seconds,
+        marks[2],
+        width = width,
+        places = places
+    )
+}
+
+/// The mark on the first sexagesimal field
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum UnitMark {
+    Hours,
+    Degrees,
+}
+
+fn invalid_sexagesimal(text: &str) -> StarfieldError {
+    StarfieldError::DataError(format!("invalid sexagesimal angle: {:?}", text))
+}
+
+/// Read a signed value from up to three sexagesimal fields separated by
+/// unit marks, colons or spaces, with the mark on the first field if any
+///
+/// A unit mark must name the field it ends: `h`, `d` or `°` the first,
+/// `m`, `′` or `'` the second and `s`, `″` or `"` the third.
+fn parse_sexagesimal(text: &str) -> Result<(f64, Option<UnitMark>)> {
+    let invalid = || invalid_sexagesimal(text);
+
+    let trimmed = text.trim();
+    let (negative, rest) = match trimmed.strip_prefix(['-', '−']) {
+        Some(rest) => (true, rest),
+        None => (false, trimmed.strip_prefix('+').unwrap_or(trimmed)),
+    };
+
+    let mut fields = Vec::new();
+    let mut field = String::new();
+    let mut unit_mark = None;
+    for c in rest.replace("deg", "d").chars() {
+        let position = match c {
+            'h' | 'H' | 'd' | '°' => Some(0),
+            'm' | '′' | '\'' => Some(1),
+            's' | '″' | '"' => Some(2),
+            _ => None,
+        };
+        match c {
+            '0'..='9' | '.' => field.push(c),
+            _ if position.is_some() || c == ':' => {
+                if field.is_empty() || position.is_some_and(|p| p != fields.len()) {
+                    return Err(invalid());
+                }
+                if position == Some(0) {
+                    unit_mark = Some(if matches!(c, 'h' | 'H') {
+                        UnitMark::Hours
+                    } else {
+                        UnitMark::Degrees
+                    });
+                }
+                fields.push(std::mem::take(&mut field));
+            }
+            c if c.is_whitespace() => {
+                if !field.is_empty() {
+                    fields.push(std::mem::take(&mut field));
+                }
+            }
+            _ => return Err(invalid()),
+        }
+    }
+    if !field.is_empty() {
+        fields.push(field);
+    }
+    if fields.is_empty() || fields.len() > 3 {
+        return Err(invalid());
+    }
+
+    let mut value = 0.0;
+    for (i, field) in fields.iter().enumerate() {
+        let number: f64 = field.parse().map_err(|_| invalid())?;
+        let last = i + 1 == fields.len();
+        if (!last && field.contains('.')) || (i > 0 && number >= 60.0) {
+            return Err(invalid());
+        }
+        value += number / 60f64.powi(i as i32);
+    }
+    Ok((if negative { -value } else { value }, unit_mark))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use approx::assert_relative_eq;
+
+    #[test]
+    fn test_sexagesimal_formatting() {
+        let angle = Angle::from_hours(5.0 + 14.0 / 60.0 + 32.27 / 3600.0);
+        assert_eq!(angle.hstr(2), "05h 14m 32.27s");
+        assert_eq!(angle.hstr(0), "05h 14m 32s");
+        let (h, m, s) = angle.hms();
+        assert_eq!((h, m), (5.0, 14.0));
+        assert_relative_eq!(s, 32.27, epsilon = 1e-9);
+
+        // Rounding carries into the minutes and degrees
+        let angle = Angle::from_degrees(-(12.0 + 59.0 / 60.0 + 59.96 / 3600.0));
+        assert_eq!(angle.dstr(1), "-13° 00′ 00.0″");
+        assert_eq!(format!("{:.2}", angle), "-12° 59′ 59.96″");
+        assert_eq!(angle.dms().1, -59.0);
+        assert_eq!(Angle::from_degrees(-1e-9).dstr(1), "00° 00′ 00.0″");
+    }
+
+    #[test]
+    fn test_sexagesimal_parsing() {
+        let ra: Angle = "05h 14m 32.27s".parse().unwrap();
+        assert_relative_eq!(ra.hours(), 5.242_297_2, epsilon = 1e-7);
+        assert_eq!(Angle::parse_hours("5:14:32.27").unwrap(), ra);
+
+        let dec: Angle = "-08° 12′ 05.9″".parse().unwrap();
+        assert_relative_eq!(dec.degrees(), -8.201_638_9, epsilon = 1e-7);
+        for text in ["-8d12'05.9\"", "−8 12 5.9", "-08deg 12' 05.9\""] {
+            assert_relative_eq!(
+                text.parse::<Angle>().unwrap().degrees(),
+                dec.degrees(),
+                epsilon = 1e-12
+            );
+        }
+        assert_eq!(Angle::parse_degrees("+45").unwrap().degrees(), 45.0);
+        assert_eq!(dec.dstr(1).parse::<Angle>().unwrap().dstr(1), dec.dstr(1));
+
+        for bad in ["", "12x 30", "12 75 00", "1.5 30", "1 2 3 4", "°30"] {
+            assert!(bad.parse::<Angle>().is_err(), "{:?}", bad);
+        }
+    }
+
+    #[test]
+    fn test_sexagesimal_marks_must_match_their_fields() {
+        for bad in ["30m", "12″", "5d 30s", "5h 30d", "5 30h", "5° 30′ 12″ 4"] {
+            assert!(
+                matches!(bad.parse::<Angle>(), Err(StarfieldError::DataError(_))),
+                "{:?}",
+                bad
+            );
+        }
+        assert!(Angle::parse_hours("-08° 12′ 05.9″").is_err());
+        assert!(Angle::parse_degrees("05h 14m 32.27s").is_err());
+
+        // Unmarked and colon-separated fields take either unit
+        assert_eq!(Angle::parse_hours("5 30").unwrap().hours(), 5.5);
+        assert_eq!(Angle::parse_degrees("5:30").unwrap().degrees(), 5.5);
+        assert_eq!("12 30m".parse::<Angle>().unwrap().degrees(), 12.5);
+    }
+}
@@ -0,0 +1,279 @@
+//! Comparison of apparent positions with published reference values
+//!
+//! A [`ReferencePosition`] is a published right ascension and declination
+//! of a body, geocentric or seen from a site, referred to the true equator
+//! and equinox of date as almanacs tabulate them. [`compare`] computes the
+//! same position with a given ephemeris and reports the separation, and
+//! [`validate`] runs a whole set of references.
+//!
+//! [`reference_positions`] returns the bundled set: worked examples from
+//! Meeus' *Astronomical Algorithms*, whose reductions follow the
+//! Astronomical Almanac. Positions transcribed from the Almanac itself, or
+//! from any other source, can be checked in the same way by loading them
+//! with [`parse_reference_positions`]:
+//!
+//! ```
+//! use starfield::planetlib::Ephemeris;
+//! use starfield::time::Timescale;
+//! use starfield::validation::{reference_positions, validate};
+//!
+//! let ts = Timescale::default();
+//! let eph = Ephemeris::new();
+//! let report = validate(&eph, &ts, &reference_positions()?)?;
+//! println!("{}", report);
+//! assert!(report.passed());
+//! # Ok::<(), starfield::planetlib::PlanetError>(())
+//! ```
+//!
+//! The allowed error of each comparison is the reference's own tolerance
+//! plus the expected error of the ephemeris in use (see
+//! [`AccuracyEstimate::for_apparent`]), so the built-in analytic ephemeris
+//! is held to a looser standard than a JPL kernel.
+
+use crate::accuracy::{AccuracyEstimate, NutationModel};
+use crate::constants::{ASEC2RAD, DEG2RAD, RAD2DEG};
+use crate::nutationlib::precession_nutation_matrix;
+use crate::observers::{GeographicLocation, ObserverAt};
+use crate::planetlib::{Body, Ephemeris, PlanetError};
+use crate::time::{Time, Timescale};
+use nalgebra::Vector3;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+/// The bundled reference positions, as JSON
+const REFERENCE_POSITIONS: &str = include_str!("reference_positions.json");
+
+/// Time scale of a reference epoch
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum EpochScale {
+    /// Terrestrial Time, which almanacs tabulate in
+    #[serde(rename = "TT")]
+    Tt,
+    /// Universal Time
+    #[serde(rename = "UT1")]
+    Ut1,
+}
+
+/// A published apparent position of a body
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ReferencePosition {
+    /// Where the value was published
+    pub source: String,
+    /// Name of the body, as in [`Body::name`]
+    pub body: String,
+    /// Julian date of the epoch
+    pub jd: f64,
+    /// Time scale of `jd`
+    pub scale: EpochScale,
+    /// Observing site, or `None` for a geocentric position
+    pub site: Option<GeographicLocation>,
+    /// Apparent right ascension in degrees, true equator and equinox of date
+    pub ra_deg: f64,
+    /// Apparent declination in degrees, true equator and equinox of date
+    pub dec_deg: f64,
+    /// Agreement expected of an exact ephemeris, in arcseconds
+    pub tolerance_arcsec: f64,
+}
+
+impl ReferencePosition {
+    /// The body the position is for
+    pub fn body(&self) -> Result<Body, PlanetError> {
+        Body::from_name(&self.body).ok_or_else(|| PlanetError::NotFound(self.body.clone()))
+    }
+
+    /// The epoch of the position on `ts`
+    pub fn time(&self, ts: &Timescale) -> Time {
+        match self.scale {
+            EpochScale::Tt => ts.tt_jd(self.jd, None),
+            EpochScale::Ut1 => ts.ut1_jd(self.jd),
+        }
+    }
+}
+
+/// A computed position set against its reference
+#[derive(Debug, Clone, PartialEq)]
+pub struct Comparison {
+    /// The published position
+    pub reference: ReferencePosition,
+    /// Computed apparent right ascension in degrees, true equinox of date
+    pub ra_deg: f64,
+    /// Computed apparent declination in degrees, true equator of date
+    pub dec_deg: f64,
+    /// Angle between the computed and published positions, arcseconds
+    pub separation_arcsec: f64,
+    /// Largest separation accepted: the reference's tolerance plus the
+    /// expected error of the ephemeris, arcseconds
+    pub allowed_arcsec: f64,
+}
+
+impl Comparison {
+    /// Whether the computed position is within the allowed error
+    pub fn passed(&self) -> bool {
+        self.separation_arcsec <= self.allowed_arcsec
+    }
+
+    /// Right ascension residual (computed minus published) in seconds of
+    /// time
+    pub fn ra_residual_s(&self) -> f64 {
+        let residual_deg = (self.ra_deg - self.reference.ra_deg + 180.0).rem_euclid(360.0) - 180.0;
+        residual_deg * 240.0
+    }
+
+    /// Declination residual (computed minus published) in arcseconds
+    pub fn dec_residual_arcsec(&self) -> f64 {
+        (self.dec_deg - self.reference.dec_deg) * 3600.0
+    }
+}
+
+/// The outcome of comparing a set of reference positions
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ValidationReport {
+    /// One comparison per reference, in the order given
+    pub comparisons: Vec<Comparison>,
+}
+
+impl ValidationReport {
+    /// Whether every comparison passed
+    pub fn passed(&self) -> bool {
+        self.comparisons.iter().all(Comparison::passed)
+    }
+
+    /// The comparisons that failed
+    pub fn failures(&self) -> impl Iterator<Item = &Comparison> {
+        self.comparisons.iter().filter(|c| !c.passed())
+    }
+
+    /// The comparison with the largest separation relative to its allowed
+    /// error
+    pub fn worst(&self) -> Option<&Comparison> {
+        self.comparisons.iter().max_by(|a, b| {
+            let ratio = |c: &Comparison| c.separation_arcsec / c.allowed_arcsec;
+            ratio(a).total_cmp(&ratio(b))
+        })
+    }
+}
+
+impl fmt::Display for ValidationReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "{:<8} {:>14} {:>9} {:>9} {:>9} {:>9}  source",
+            "body", "JD", "dRA (s)", "dDec (\")", "sep (\")", "allowed"
+        )?;
+        for c in &self.comparisons {
+            writeln!(
+                f,
+                "{:<8} {:>14.6} {:>9.3} {:>9.2} {:>9.2} {:>9.2}{} {}",
+                c.reference.body,
+                c.reference.jd,
+                c.ra_residual_s(),
+                c.dec_residual_arcsec(),
+                c.separation_arcsec,
+                c.allowed_arcsec,
+                if c.passed() { " " } else { "*" },
+                c.reference.source
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// Unit vector towards a right ascension and declination in degrees
+fn unit_vector(ra_deg: f64, dec_deg: f64) -> Vector3<f64> {
+    let (sin_dec, cos_dec) = (dec_deg * DEG2RAD).sin_cos();
+    let (sin_ra, cos_ra) = (ra_deg * DEG2RAD).sin_cos();
+    Vector3::new(cos_dec * cos_ra, cos_dec * sin_ra, sin_dec)
+}
+
+/// Compute the position a reference gives and compare the two
+///
+/// The apparent position from [`crate::positions::icrf::Apparent`] is rotated to
+/// the true equator and equinox of date with IAU 2000B nutation.
+pub fn compare(
+    ephemeris: &Ephemeris,
+    ts: &Timescale,
+    reference: &ReferencePosition,
+) -> Result<Comparison, PlanetError> {
+    let body = reference.body()?;
+    let t = reference.time(ts);
+    let observer = match &reference.site {
+        Some(site) => site.at(ephemeris, &t)?,
+        None => ObserverAt::geocenter(ephemeris, &t)?,
+    };
+    let apparent = observer.observe(body)?.apparent(ephemeris)?;
+    let of_date =
+        precession_nutation_matrix(t.tt(), NutationModel::Iau2000B) * apparent.position.vector();
+
+    let ra_deg = (of_date.y.atan2(of_date.x) * RAD2DEG).rem_euclid(360.0);
+    let dec_deg = (of_date.z / of_date.norm()).asin() * RAD2DEG;
+    let separation_arcsec =
+        of_date.angle(&unit_vector(reference.ra_deg, reference.dec_deg)) / ASEC2RAD;
+    let allowed_arcsec =
+        reference.tolerance_arcsec + AccuracyEstimate::for_apparent(ephemeris, body).total_arcsec();
+
+    Ok(Comparison {
+        reference: reference.clone(),
+        ra_deg,
+        dec_deg,
+        separation_arcsec,
+        allowed_arcsec,
+    })
+}
+
+/// Compare every reference position
+pub fn validate(
+    ephemeris: &Ephemeris,
+    ts: &Timescale,
+    references: &[ReferencePosition],
+) -> Result<ValidationReport, PlanetError> {
+    let comparisons = references
+        .iter()
+        .map(|reference| compare(ephemeris, ts, reference))
+        .collect::<Result<_, _>>()?;
+    Ok(ValidationReport { comparisons })
+}
+
+/// Read reference positions from a JSON array of [`ReferencePosition`]
+pub fn parse_reference_positions(json: &str) -> Result<Vec<ReferencePosition>, PlanetError> {
+    serde_json::from_str(json)
+        .map_err(|e| PlanetError::DataError(format!("bad reference positions: {}", e)))
+}
+
+/// The bundled reference positions
+pub fn reference_positions() -> Result<Vec<ReferencePosition>, PlanetError> {
+    parse_reference_positions(REFERENCE_POSITIONS)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bundled_references_parse() {
+        let references = reference_positions().unwrap();
+        assert!(references.len() >= 4);
+        for reference in &references {
+            assert!(reference.body().is_ok(), "{}", reference.body);
+            assert!(reference.tolerance_arcsec > 0.0);
+        }
+        assert!(references.iter().any(|r| r.site.is_some()));
+        assert!(parse_reference_positions("[{\"body\": \"Moon\"}]").is_err());
+    }
+
+    #[test]
+    fn test_report_flags_a_bad_reference() {
+        let ts = Timescale::default();
+        let eph = Ephemeris::new();
+        let mut reference = reference_positions().unwrap().remove(1);
+        let good = compare(&eph, &ts, &reference).unwrap();
+        assert!(good.passed(), "{:?}", good);
+
+        reference.dec_deg += 0.1;
+        let report = validate(&eph, &ts, &[reference]).unwrap();
+        assert!(!report.passed());
+        let worst = report.worst().unwrap();
+        assert!((worst.dec_residual_arcsec() + 360.0).abs() < good.allowed_arcsec);
+        assert_eq!(report.failures().count(), 1);
+        assert!(report.to_string().contains("Venus"));
+    }
+}
@@ -0,0 +1,306 @@
+//! World Coordinate System (WCS) projections
+//!
+//! A [`Wcs`] maps pixel coordinates to sky positions the way a FITS header
+//! does (Calabretta & Greisen 2002): pixel offsets from `CRPIX` are turned
+//! into intermediate world coordinates in degrees by the `CD` matrix, a
+//! zenithal [`Projection`] turns those into native spherical coordinates
+//! about the reference point `CRVAL`, and a rotation carries them onto the
+//! celestial sphere.
+//!
+//! ```
+//! use starfield::coordinates::Equatorial;
+//! use starfield::wcs::{Projection, Wcs};
+//!
+//! let orion = Equatorial::from_degrees(83.82, -5.39);
+//! let wcs = Wcs::from_scale(Projection::Tan, orion, (1024.5, 1024.5), 1.5, 0.0);
+//! let (x, y) = wcs.sky_to_pixel(&Equatorial::from_degrees(83.9, -5.3)).unwrap();
+//! let back = wcs.pixel_to_sky(x, y).unwrap();
+//! assert!((back.ra_degrees() - 83.9).abs() < 1e-9);
+//! ```
+//!
+//! Pixel coordinates follow FITS: the center of the first pixel is
+//! `(1, 1)`. With a positive `CD1_1` and zero rotation, x runs east and y
+//! north; images displayed with east to the left have a negative `CD1_1`.
+
+use crate::constants::{DEG2RAD, RAD2DEG};
+use crate::coordinates::Equatorial;
+
+/// A zenithal projection from the native sphere onto the plane
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Projection {
+    /// Gnomonic (`TAN`): great circles are straight lines; the hemisphere
+    /// about the reference point only
+    Tan,
+    /// Stereographic (`STG`): conformal, so small shapes are preserved;
+    /// everything but the antipode of the reference point
+    Stg,
+    /// Orthographic (`SIN`): the sphere as seen from infinitely far away;
+    /// the hemisphere about the reference point only
+    Sin,
+}
+
+impl Projection {
+    /// The three-letter FITS code
+    pub fn code(&self) -> &'static str {
+        match self {
+            Projection::Tan => "TAN",
+            Projection::Stg => "STG",
+            Projection::Sin => "SIN",
+        }
+    }
+
+    /// The projection named by a FITS `CTYPEi` value such as `RA---TAN`
+    pub fn from_ctype(ctype: &str) -> Option<Self> {
+        match ctype.trim().get(5..8)? {
+            "TAN" => Some(Projection::Tan),
+            "STG" => Some(Projection::Stg),
+            "SIN" => Some(Projection::Sin),
+            _ => None,
+        }
+    }
+
+    /// Radius in the plane (degrees) of native latitude `theta` (radians),
+    /// or `None` where the projection does not reach
+    fn radius(&self, theta: f64) -> Option<f64> {
+        match self {
+            Projection::Tan if theta > 0.0 => Some(RAD2DEG / theta.tan()),
+            Projection::Stg if theta > -std::f64::consts::FRAC_PI_2 + 1e-12 => {
+                Some(2.0 * RAD2DEG * ((std::f64::consts::FRAC_PI_2 - theta) / 2.0).tan())
+            }
+            Projection::Sin if theta > -1e-12 => Some(RAD2DEG * theta.cos()),
+            _ => None,
+        }
+    }
+
+    /// Native latitude (radians) at radius `r` (degrees) in the plane
+    fn latitude(&self, r: f64) -> Option<f64> {
+        let r = r * DEG2RAD;
+        match self {
+            Projection::Tan => Some((1.0 / r).atan()),
+            Projection::Stg => Some(std::f64::consts::FRAC_PI_2 - 2.0 * (r / 2.0).atan()),
+            Projection::Sin if r <= 1.0 => Some(r.acos()),
+            Projection::Sin => None,
+        }
+    }
+}
+
+/// A celestial WCS: projection, reference pixel, reference point and `CD`
+/// matrix
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Wcs {
+    /// Projection from the native sphere to the plane
+    pub projection: Projection,
+    /// Reference pixel (`CRPIX1`, `CRPIX2`)
+    pub crpix: (f64, f64),
+    /// Sky position of the reference pixel (`CRVAL1`, `CRVAL2`)
+    pub crval: Equatorial,
+    /// Linear transformation from pixel offsets to intermediate world
+    /// coordinates, degrees per pixel (`CDi_j`, row `i`, column `j`)
+    pub cd: [[f64; 2]; 2],
+}
+
+impl Wcs {
+    /// A WCS from its FITS parameters
+    pub fn new(
+        projection: Projection,
+        crval: Equatorial,
+        crpix: (f64, f64),
+        cd: [[f64; 2]; 2],
+    ) -> Self {
+        Self {
+            projection,
+            crpix,
+            crval,
+            cd,
+        }
+    }
+
+    /// A WCS with square pixels of `scale_arcsec`, whose +y axis points
+    /// `rotation_deg` east of north
+    pub fn from_scale(
+        projection: Projection,
+        crval: Equatorial,
+        crpix: (f64, f64),
+        scale_arcsec: f64,
+        rotation_deg: f64,
+    ) -> Self {
+        let scale = scale_arcsec / 3600.0;
+        let (sin, cos) = (rotation_deg * DEG2RAD).sin_cos();
+        Self::new(
+            projection,
+            crval,
+            crpix,
+            [[scale * cos, scale * sin], [-scale * sin, scale * cos]],
+        )
+    }
+
+    /// Determinant of the `CD` matrix, square degrees per pixel (negative
+    /// when east is to the left of north)
+    pub fn cd_determinant(&self) -> f64 {
+        self.cd[0][0] * self.cd[1][1] - self.cd[0][1] * self.cd[1][0]
+    }
+
+    /// Plate scale at the reference pixel, arcseconds per pixel
+    pub fn pixel_scale_arcsec(&self) -> f64 {
+        self.cd_determinant().abs().sqrt() * 3600.0
+    }
+
+    /// Sky position at pixel `(x, y)`, or `None` off the projection
+    pub fn pixel_to_sky(&self, x: f64, y: f64) -> Option<Equatorial> {
+        let (dx, dy) = (x - self.crpix.0, y - self.crpix.1);
+        let u = self.cd[0][0] * dx + self.cd[0][1] * dy;
+        let v = self.cd[1][0] * dx + self.cd[1][1] * dy;
+
+        let r = u.hypot(v);
+        let phi = if r == 0.0 { 0.0 } else { u.atan2(-v) };
+        let theta = self.projection.latitude(r)?;
+        Some(self.native_to_celestial(phi, theta))
+    }
+
+    /// Pixel coordinates of a sky position, or `None` where the projection
+    /// does not reach
+    pub fn sky_to_pixel(&self, position: &Equatorial) -> Option<(f64, f64)> {
+        let (phi, theta) = self.celestial_to_native(position);
+        let r = self.projection.radius(theta)?;
+        let u = r * phi.sin();
+        let v = -r * phi.cos();
+
+        let det = self.cd_determinant();
+        if det == 0.0 {
+            return None;
+        }
+        let dx = (self.cd[1][1] * u - self.cd[0][1] * v) / det;
+        let dy = (self.cd[0][0] * v - self.cd[1][0] * u) / det;
+        Some((dx + self.crpix.0, dy + self.crpix.1))
+    }
+
+    /// FITS header keywords describing this WCS
+    pub fn fits_keywords(&self) -> Vec<(&'static str, String)> {
+        let code = self.projection.code();
+        vec![
+            ("CTYPE1", format!("RA---{}", code)),
+            ("CTYPE2", format!("DEC--{}", code)),
+            ("CRPIX1", self.crpix.0.to_string()),
+            ("CRPIX2", self.crpix.1.to_string()),
+            ("CRVAL1", self.crval.ra_degrees().to_string()),
+            ("CRVAL2", self.crval.dec_degrees().to_string()),
+            ("CD1_1", self.cd[0][0].to_string()),
+            ("CD1_2", self.cd[0][1].to_string()),
+            ("CD2_1", self.cd[1][0].to_string()),
+            ("CD2_2", self.cd[1][1].to_string()),
+        ]
+    }
+
+    /// Celestial position of native coordinates (radians); the reference
+    /// point is the native pole, with the celestial pole at native
+    /// longitude 180°
+    fn native_to_celestial(&self, phi: f64, theta: f64) -> Equatorial {
+        let (sin_dp, cos_dp) = self.crval.dec.sin_cos();
+        let (sin_t, cos_t) = theta.sin_cos();
+        let (sin_dphi, cos_dphi) = (phi - std::f64::consts::PI).sin_cos();
+
+        let ra =
+            self.crval.ra + (-cos_t * sin_dphi).atan2(sin_t * cos_dp - cos_t * sin_dp * cos_dphi);
+        let dec = (sin_t * sin_dp + cos_t * cos_dp * cos_dphi)
+            .clamp(-1.0, 1.0)
+            .asin();
+        Equatorial::new(ra.rem_euclid(std::f64::consts::TAU), dec)
+    }
+
+    /// Native coordinates (radians) of a celestial position
+    fn celestial_to_native(&self, position: &Equatorial) -> (f64, f64) {
+        let (sin_dp, cos_dp) = self.crval.dec.sin_cos();
+        let (sin_d, cos_d) = position.dec.sin_cos();
+        let (sin_da, cos_da) = (position.ra - self.crval.ra).sin_cos();
+
+        let phi = std::f64::consts::PI
+            + (-cos_d * sin_da).atan2(sin_d * cos_dp - cos_d * sin_dp * cos_da);
+        let theta = (sin_d * sin_dp + cos_d * cos_dp * cos_da)
+            .clamp(-1.0, 1.0)
+            .asin();
+        (phi, theta)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::charting::GnomonicProjection;
+    use approx::assert_relative_eq;
+
+    const PROJECTIONS: [Projection; 3] = [Projection::Tan, Projection::Stg, Projection::Sin];
+
+    #[test]
+    fn test_round_trips() {
+        for projection in PROJECTIONS {
+            for (ra, dec) in [(83.8, -5.4), (0.5, 89.0), (359.0, -60.0)] {
+                let wcs = Wcs::new(
+                    projection,
+                    Equatorial::from_degrees(ra, dec),
+                    (512.5, 300.25),
+                    [[-2.0e-4, 5.0e-5], [4.0e-5, 1.9e-4]],
+                );
+                for (x, y) in [
+                    (512.5, 300.25),
+                    (1.0, 1.0),
+                    (1024.0, 600.0),
+                    (-3000.0, 80.0),
+                ] {
+                    let sky = wcs.pixel_to_sky(x, y).unwrap();
+                    let (x2, y2) = wcs.sky_to_pixel(&sky).unwrap();
+                    assert_relative_eq!(x2, x, epsilon = 1e-7);
+                    assert_relative_eq!(y2, y, epsilon = 1e-7);
+                }
+                let reference = wcs.pixel_to_sky(512.5, 300.25).unwrap();
+                assert!(reference.angular_distance(&wcs.crval) < 1e-12);
+            }
+        }
+    }
+
+    #[test]
+    fn test_projection_radii() {
+        let center = Equatorial::from_degrees(120.0, 30.0);
+        let unit = |projection| Wcs::from_scale(projection, center, (0.0, 0.0), 3600.0, 0.0);
+
+        // 90 degrees due north of the reference point
+        let pole = Equatorial::from_degrees(300.0, 60.0);
+        assert!(unit(Projection::Tan).sky_to_pixel(&pole).is_none());
+        let (x, y) = unit(Projection::Sin).sky_to_pixel(&pole).unwrap();
+        assert_relative_eq!(x, 0.0, epsilon = 1e-9);
+        assert_relative_eq!(y, RAD2DEG, epsilon = 1e-9);
+        let (_, y) = unit(Projection::Stg).sky_to_pixel(&pole).unwrap();
+        assert_relative_eq!(y, 2.0 * RAD2DEG, epsilon = 1e-9);
+        assert!(unit(Projection::Sin).pixel_to_sky(0.0, 60.0).is_none());
+
+        // TAN is the tangent-plane projection used for charts
+        let tan = unit(Projection::Tan);
+        let star = Equatorial::from_degrees(125.0, 27.0);
+        let (xi, eta) = GnomonicProjection::new(center).project(&star).unwrap();
+        let (x, y) = tan.sky_to_pixel(&star).unwrap();
+        assert_relative_eq!(x, xi * RAD2DEG, epsilon = 1e-9);
+        assert_relative_eq!(y, eta * RAD2DEG, epsilon = 1e-9);
+    }
+
+    #[test]
+    fn test_rotation_and_keywords() {
+        let center = Equatorial::from_degrees(10.0, 0.0);
+        let wcs = Wcs::from_scale(Projection::Tan, center, (100.0, 100.0), 2.0, 90.0);
+        assert_relative_eq!(wcs.pixel_scale_arcsec(), 2.0, epsilon = 1e-12);
+
+        // With +y pointing east, a star 20" east lands 10 pixels up
+        let (x, y) = wcs
+            .sky_to_pixel(&Equatorial::from_degrees(10.0 + 20.0 / 3600.0, 0.0))
+            .unwrap();
+        assert_relative_eq!(x, 100.0, epsilon = 1e-6);
+        assert_relative_eq!(y, 110.0, epsilon = 1e-6);
+
+        let keywords = wcs.fits_keywords();
+        assert_eq!(keywords[0], ("CTYPE1", "RA---TAN".to_string()));
+        assert_eq!(
+            Projection::from_ctype(&keywords[1].1),
+            Some(Projection::Tan)
+        );
+        assert_eq!(Projection::from_ctype("DEC--STG"), Some(Projection::Stg));
+        assert_eq!(Projection::from_ctype("GLON-CAR"), None);
+    }
+}
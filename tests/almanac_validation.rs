@@ -0,0 +1,69 @@
+//! Apparent positions against the bundled reference positions
+//!
+//! Run with `cargo test --features almanac-validation`. A JPL kernel in the
+//! data directory or download cache is used when present, and the analytic
+//! ephemeris otherwise; each comparison allows for the ephemeris' expected
+//! error on top of the reference's own tolerance.
+
+#![cfg(feature = "almanac-validation")]
+
+use starfield::constants::{AU_KM, DEG2RAD};
+use starfield::positions::icrf::IcrfVector;
+use starfield::validation::{compare, reference_positions, validate, ReferencePosition};
+use starfield::Loader;
+
+#[test]
+fn test_reference_positions() {
+    let loader = Loader::new();
+    let eph = loader.load_ephemeris().unwrap();
+    let ts = loader.timescale();
+
+    let report = validate(&eph, &ts, &reference_positions().unwrap()).unwrap();
+    println!("{}", report);
+    assert!(
+        report.passed(),
+        "{:?}",
+        report.failures().collect::<Vec<_>>()
+    );
+}
+
+#[test]
+fn test_topocentric_parallax() {
+    // The topocentric reference differs from the geocentric position by the
+    // parallax; computing it geocentrically must miss by about that much
+    let loader = Loader::new();
+    let eph = loader.load_ephemeris().unwrap();
+    let ts = loader.timescale();
+
+    for reference in reference_positions().unwrap() {
+        let Some(site) = reference.site else {
+            continue;
+        };
+        let body = reference.body().unwrap();
+        let topocentric = compare(&eph, &ts, &reference).unwrap();
+        let geocentric = compare(
+            &eph,
+            &ts,
+            &ReferencePosition {
+                site: None,
+                ..reference.clone()
+            },
+        )
+        .unwrap();
+
+        let distance_km = site
+            .at(&eph, &reference.time(&ts))
+            .unwrap()
+            .observe(body)
+            .unwrap()
+            .distance()
+            * AU_KM;
+        let parallax_arcsec = (6378.0 / distance_km).asin() / DEG2RAD * 3600.0;
+        let cos_dec = (topocentric.dec_deg * DEG2RAD).cos();
+        let shift = (topocentric.dec_deg - geocentric.dec_deg)
+            .hypot((topocentric.ra_deg - geocentric.ra_deg) * cos_dec)
+            * 3600.0;
+        assert!(shift > 0.1 * parallax_arcsec && shift <= parallax_arcsec);
+        assert!(topocentric.passed(), "{:?}", topocentric);
+    }
+}